@@ -0,0 +1,128 @@
+// measures the builders of this crate every request runs through before and after the connector,
+// the filter normalization, the ClickHouse conditions, the nested write plans and the response
+// bodies. the SQL dialects are built in `teo-sql-connector' and aren't measured here, run with
+// `cargo bench --features bench'
+use chrono::{TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value as JsonValue};
+use teo::bench;
+use teo::prelude::Model;
+use teo::test::TestServer;
+use teo_teon::teon;
+use teo_teon::value::Value;
+
+static SCHEMA: &str = include_str!("schema.teo");
+
+const SIZES: [usize; 3] = [1, 10, 100];
+
+fn server() -> TestServer {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(TestServer::new(SCHEMA)).unwrap()
+}
+
+fn model<'a>(server: &'a TestServer, name: &str) -> &'a Model {
+    server.app().main_namespace().model_at_path(&vec![name]).unwrap()
+}
+
+// `size' branches of field filters with the shorthands and a relation filter in each
+fn json_where(size: usize) -> JsonValue {
+    let branches: Vec<JsonValue> = (0..size).map(|i| json!({
+        "title": {"contains": format!("title {}", i), "mode": "insensitive"},
+        "views": {"gte": i, "lt": i + 100},
+        "archivedAt": {"isNull": true},
+        "replies": {"some": {"body": {"startsWith": "re"}, "key": null}},
+    })).collect();
+    json!({"where": {"OR": branches, "NOT": [{"slug": "hidden"}], "forumId": 1}})
+}
+
+// the same filters without the relation, which ClickHouse sources can't filter on
+fn teon_where(size: usize) -> Value {
+    let branches: Vec<Value> = (0..size).map(|i| teon!({
+        "title": {"contains": format!("title {}", i)},
+        "views": {"gte": i as i32, "lt": i as i32 + 100, "not": 50},
+        "archivedAt": {"equals": null},
+        "slug": {"in": ["a", "b", "c"]},
+    })).collect();
+    teon!({"OR": branches, "NOT": {"slug": "hidden"}, "forumId": 1})
+}
+
+// a forum with `size' threads of `size' replies each, the operations out of order
+fn nested_create(size: usize) -> JsonValue {
+    let threads: Vec<JsonValue> = (0..size).map(|i| json!({
+        "slug": format!("thread-{}", i),
+        "title": format!("Thread {}", i),
+        "views": 0,
+        "replies": {
+            "create": (0..size).map(|j| json!({"key": format!("{}-{}", i, j), "body": "reply"})).collect::<Vec<_>>(),
+            "connect": [{"key": "existing"}],
+            "disconnect": [{"key": "old"}],
+        },
+    })).collect();
+    json!({"create": {"name": "forum", "threads": {"create": threads, "delete": [{"slug": "stale"}], "set": []}}})
+}
+
+fn records(size: usize) -> Value {
+    let archived_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    Value::Array((0..size).map(|i| teon!({
+        "id": i as i32,
+        "slug": format!("thread-{}", i),
+        "title": format!("Thread {} with a longer title", i),
+        "views": (i * 7) as i64,
+        "archivedAt": (Value::DateTime(archived_at)),
+        "forumId": 1,
+    })).collect())
+}
+
+fn filter_normalization(c: &mut Criterion) {
+    let server = server();
+    let thread = model(&server, "Thread");
+    let mut group = c.benchmark_group("filter_normalization");
+    for size in SIZES {
+        let input = json_where(size);
+        group.bench_with_input(BenchmarkId::new("normalize", size), &input, |b, input| {
+            b.iter_batched(|| input.clone(), |mut input| bench::normalize_filters(thread, &mut input).unwrap(), BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+fn clickhouse_conditions(c: &mut Criterion) {
+    let server = server();
+    let thread = model(&server, "Thread");
+    let mut group = c.benchmark_group("clickhouse_conditions");
+    for size in SIZES {
+        let input = teon_where(size);
+        group.bench_with_input(BenchmarkId::new("where", size), &input, |b, input| {
+            b.iter(|| bench::clickhouse_conditions(thread, input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn nested_write_planning(c: &mut Criterion) {
+    let server = server();
+    let forum = model(&server, "Forum");
+    let mut group = c.benchmark_group("nested_writes");
+    for size in SIZES {
+        let input = nested_create(size);
+        group.throughput(Throughput::Elements((size * size) as u64));
+        group.bench_with_input(BenchmarkId::new("plan", size), &input, |b, input| {
+            b.iter_batched(|| input.clone(), |mut input| bench::plan_nested_writes(forum, "create", &mut input), BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+    for size in [10, 100, 1000] {
+        let value = teon!({"data": (records(size))});
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("json", size), &value, |b, value| b.iter(|| bench::json_body(value)));
+        group.bench_with_input(BenchmarkId::new("msgpack", size), &value, |b, value| b.iter(|| bench::msgpack_body(value).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, filter_normalization, clickhouse_conditions, nested_write_planning, serialization);
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+use teo::app::App;
+use teo::cli::entrance::Entrance;
+use teo_result::Result;
+
+/// Serves a schema with pipeline items defined in Rust: `$shout` upper cases a string and
+/// `$notSpam` rejects the string "spam".
+#[tokio::main]
+async fn main() -> Result<()> {
+    let app = App::new_with_entrance_and_runtime_version(Some(Entrance::CLI), None)?;
+    app.define_transform_pipeline_item("shout", |value: String| async move { value.to_uppercase() })?;
+    app.define_validator_pipeline_item("notSpam", |value: String| async move { value != "spam" })?;
+    app.run().await
+}
@@ -0,0 +1,147 @@
+use std::str::FromStr;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Field, Model};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::namespace::Namespace;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::object::batch::{BatchOptions, Batches};
+use crate::seeder::factory::{Factory, unique_keys};
+use crate::utils::sha256_hex;
+
+const DATA_KEY: &str = "anonymize";
+const PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    Fake,
+    Mask,
+    Hash,
+    Redact,
+    Null,
+}
+
+impl Strategy {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::Fake => "fake",
+            Strategy::Mask => "mask",
+            Strategy::Hash => "hash",
+            Strategy::Redact => "redact",
+            Strategy::Null => "null",
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "fake" => Strategy::Fake,
+            "mask" => Strategy::Mask,
+            "hash" => Strategy::Hash,
+            "redact" => Strategy::Redact,
+            "null" => Strategy::Null,
+            _ => Err(Error::new(format!("unknown anonymize strategy `{}'", s)))?,
+        })
+    }
+}
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("pii", |_args: Arguments, field: &mut Field| {
+        field.data.insert(DATA_KEY.to_owned(), Value::String(Strategy::Fake.as_str().to_owned()).into());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("anonymize", |args: Arguments, field: &mut Field| {
+        let strategy: Option<String> = args.get_optional("strategy")?;
+        let strategy = strategy.map(|s| Strategy::from_str(&s)).transpose()?.unwrap_or(Strategy::Fake);
+        field.data.insert(DATA_KEY.to_owned(), Value::String(strategy.as_str().to_owned()).into());
+        Ok(())
+    });
+}
+
+pub fn field_strategy(field: &Field) -> Option<Strategy> {
+    field.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_str()).and_then(|s| Strategy::from_str(s).ok())
+}
+
+pub async fn anonymize(ctx: transaction::Ctx, seed: u64) -> Result<usize> {
+    let factory = Factory::new(ctx.clone(), seed);
+    let mut updated = 0;
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    for model in models {
+        updated += anonymize_model(&ctx, &factory, model).await?;
+    }
+    Ok(updated)
+}
+
+fn collect_models(namespace: &'static Namespace, models: &mut Vec<&'static Model>) {
+    for model in namespace.models.values() {
+        if model.fields().iter().any(|f| field_strategy(f).is_some()) {
+            models.push(model);
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_models(child, models);
+    }
+}
+
+async fn anonymize_model(ctx: &transaction::Ctx, factory: &Factory, model: &'static Model) -> Result<usize> {
+    let fields: Vec<(&Field, Strategy)> = model.fields().into_iter().filter_map(|f| field_strategy(f).map(|s| (f, s))).collect();
+    let unique_keys = unique_keys(model);
+    let options = BatchOptions { size: PAGE_SIZE, ..Default::default() };
+    let mut batches = Batches::new(ctx, model, &teon!({}), options)?;
+    let mut updated = 0;
+    while let Some(objects) = batches.next().await? {
+        for object in &objects {
+            let sequence = updated;
+            for (field, strategy) in &fields {
+                let value = object.get_value(field.name.as_str())?;
+                if value.is_null() {
+                    continue
+                }
+                let unique = unique_keys.contains(field.name.as_str());
+                let replaced = anonymized_value(factory, field, *strategy, &value, unique, sequence).await?;
+                object.set_value(field.name.as_str(), replaced)?;
+            }
+            object.save().await?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+async fn anonymized_value(factory: &Factory, field: &Field, strategy: Strategy, value: &Value, unique: bool, sequence: usize) -> Result<Value> {
+    let string = match value {
+        Value::String(s) => Some(s.as_str()),
+        _ => None,
+    };
+    Ok(match (strategy, string) {
+        (Strategy::Null, _) if field.is_optional() => Value::Null,
+        (Strategy::Mask, Some(s)) if !unique => Value::String(mask(s)),
+        (Strategy::Hash, Some(s)) => Value::String(hash(s)),
+        (Strategy::Redact, Some(_)) if !unique => Value::String("[redacted]".to_owned()),
+        (Strategy::Redact, _) if field.is_optional() && !unique => Value::Null,
+        _ => factory.fake_value(field.name.as_str(), field.r#type.unwrap_optional(), unique, sequence).await?,
+    })
+}
+
+fn mask(value: &str) -> String {
+    let (local, domain) = match value.split_once("@") {
+        Some((local, domain)) => (local, Some(domain)),
+        None => (value, None),
+    };
+    let masked: String = local.chars().enumerate().map(|(i, c)| if i == 0 { c } else { '*' }).collect();
+    match domain {
+        Some(domain) => format!("{}@{}", masked, domain),
+        None => masked,
+    }
+}
+
+fn hash(value: &str) -> String {
+    sha256_hex(value.as_bytes(), 32)
+}
@@ -1,19 +1,78 @@
+use std::collections::BTreeMap;
 use std::process::exit;
 use std::env::current_dir;
+use std::sync::Arc;
+use std::time::Duration;
 use teo_result::{Error, Result};
 use teo_runtime::namespace::Namespace;
-use crate::app::ctx::Ctx;
+use crate::app::ctx::{Ctx, MainNamespaceMut};
 use teo_runtime::utils::find_main_schema_file;
 use crate::cli::parse::{parse as cli_parse};
-use teo_parser::{parse as schema_parse};
+use teo_parser::ast::schema::Schema;
+use teo_parser::traits::info_provider::InfoProvider;
+use teo_parser::diagnostics::diagnostics::DiagnosticsLog;
 use teo_parser::diagnostics::printer::print_diagnostics;
 use teo_runtime::stdlib::load::{load as load_std};
 use teo_runtime::schema::load::load_schema::load_schema;
 use crate::cli::run::run;
+use crate::cli::command::CLI;
 use dotenvy::dotenv;
 use teo_runtime::connection::transaction;
 use crate::app::callbacks::callback::AsyncCallbackArgument;
+use crate::app::database::provider::ConnectorProvider;
+use crate::app::plugin::Plugin;
+use crate::anonymize::load_decorators as load_anonymize_decorators;
+use crate::position::load_decorators as load_position_decorators;
+use crate::state::load_decorators as load_state_decorators;
+use crate::internal_only::load_decorators as load_internal_only_decorators;
+use crate::duplicate::load_decorators as load_duplicate_decorators;
+use crate::scope::load_decorators as load_scope_decorators;
+use crate::retention::load_decorators as load_retention_decorators;
+use crate::archive::load_decorators as load_archive_decorators;
+use crate::source::load_decorators as load_source_decorators;
+use crate::backfill::load_decorators as load_backfill_decorators;
+use crate::migrate::data_sets::load_decorators as load_data_set_decorators;
+use crate::migrate::expand::{load_decorators as load_expand_decorators, settle_renamed_fields};
+use crate::on_delete::{load_decorators as load_on_delete_decorators, settle_delete_rules};
+use crate::counter_cache::{load_decorators as load_counter_cache_decorators, check_counter_caches};
+use crate::enum_meta::{load_pipeline_items as load_enum_meta_pipeline_items, load_enum_meta};
+use crate::pipeline::fetch::load_pipeline_items as load_fetch_pipeline_items;
+use crate::pipeline::string::load_pipeline_items as load_string_pipeline_items;
+use crate::pipeline::conditional::load_pipeline_items as load_conditional_pipeline_items;
+use crate::pipeline::identity::load_pipeline_items as load_identity_pipeline_items;
+use crate::server::admin::AdminGuard;
+use crate::backfill::{Backfill, BackfillTransform};
+use crate::scope::ScopeFilter;
+use crate::server::report::ErrorReporter;
+use crate::event_sink::{EventSink, EventSinkOptions, RegisteredSink};
+use crate::telemetry::{Exporter, TelemetryOptions};
+use crate::server::tls::TlsOptions;
+use crate::isolation::TransactionOptions;
+use crate::server::limits::Limits;
+use crate::server::coerce::Coercion;
+use crate::server::embedded::{EmbeddedFiles, EmbeddedMount};
+use crate::server::idempotency::IdempotencyStore;
+use crate::server::stats::StatsRegistry;
+use crate::server::static_files::StaticFilesOptions;
 use crate::prelude::{Entrance, RuntimeVersion};
+use ring::hmac;
+use crate::schema::builder::SchemaBuilder;
+use crate::schema::constants::explain_invalid_expressions;
+use crate::schema::implicit::parse_schema;
+use teo_runtime::object::Object;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::relation::Relation;
+use teo_runtime::model::property::Property;
+use teo_runtime::r#enum::Enum;
+use teo_runtime::r#enum::member::Member;
+use teo_runtime::handler::Handler;
+use teo_runtime::pipeline;
+use teo_runtime::pipeline::item::callback::{CallbackArgument, CallbackResult};
+use teo_runtime::pipeline::item::compare::CompareArgument;
+use teo_runtime::pipeline::item::transform::{TransformArgument, TransformResult};
+use teo_runtime::pipeline::item::validator::{ValidateArgument, ValidateResult};
 
 #[derive(Debug)]
 pub struct App { }
@@ -37,54 +96,321 @@ impl App {
             Ctx::set_runtime_version(runtime_version);
         }
         let cli = cli_parse(Ctx::get().runtime_version.clone(), Ctx::get().entrance);
+        Self::new_with_cli(cli, true)
+    }
+
+    /// Creates the app from a schema built in Rust instead of a schema file. The schema is loaded
+    /// as if it was the file `schema.teo` in the current directory, so it may import schema files
+    /// relative to it.
+    pub fn new_with_schema(schema: &SchemaBuilder) -> Result<Self> {
+        let _ = dotenv();
+        if !Ctx::create() {
+            Err(Error::new("cannot create app while there is an existing instance"))?
+        }
+        let cli = cli_parse(Ctx::get().runtime_version.clone(), Ctx::get().entrance);
+        Self::new_with_cli_and_source(cli, Some(schema.source()), true)
+    }
+
+    pub(crate) fn new_with_cli(cli: CLI, exit_on_diagnostics_errors: bool) -> Result<Self> {
+        Self::new_with_cli_and_source(cli, None, exit_on_diagnostics_errors)
+    }
+
+    fn new_with_cli_and_source(cli: CLI, source: Option<String>, exit_on_diagnostics_errors: bool) -> Result<Self> {
         let current_dir = match current_dir() {
             Ok(current_dir) => current_dir,
             Err(e) => Err(Error::new(format!("{}", e)))?,
         };
-        let main_schema_file = find_main_schema_file(cli.schema.as_ref().map(AsRef::as_ref), &current_dir)?;
-        let (schema, diagnostics) = schema_parse(main_schema_file.as_path().to_str().unwrap(), None, None);
+        let (schema, diagnostics) = match source {
+            Some(source) => {
+                let main_schema_file = current_dir.join("schema.teo").to_str().unwrap().to_owned();
+                parse_schema(&main_schema_file, Some(BTreeMap::from([(main_schema_file.clone(), source)])))?
+            },
+            None => {
+                let main_schema_file = find_main_schema_file(cli.schema.as_ref().map(AsRef::as_ref), &current_dir)?;
+                parse_schema(main_schema_file.as_path().to_str().unwrap(), None)?
+            },
+        };
+        let diagnostics = explain_invalid_expressions(&schema, diagnostics);
         print_diagnostics(&diagnostics, true);
         if diagnostics.has_errors() {
-            exit(1);
+            if exit_on_diagnostics_errors {
+                exit(1);
+            }
+            let mut error = Error::new("schema has errors");
+            // kept for the callers which report them on their own, e.g. tests
+            error.insert_meta("diagnostics", diagnostics.errors().iter().map(|e| e.message().to_owned()).collect::<Vec<String>>());
+            Err(error)?
         }
-        load_std(Ctx::main_namespace_mut());
+        Ctx::with_main_namespace_mut(|namespace| {
+            load_std(namespace);
+            load_anonymize_decorators(namespace);
+            load_position_decorators(namespace);
+            load_state_decorators(namespace);
+            load_internal_only_decorators(namespace);
+            load_duplicate_decorators(namespace);
+            load_scope_decorators(namespace);
+            load_retention_decorators(namespace);
+            load_archive_decorators(namespace);
+            load_source_decorators(namespace);
+            load_backfill_decorators(namespace);
+            load_data_set_decorators(namespace);
+            load_expand_decorators(namespace);
+            load_on_delete_decorators(namespace);
+            load_counter_cache_decorators(namespace);
+            load_fetch_pipeline_items(namespace);
+            load_string_pipeline_items(namespace);
+            load_conditional_pipeline_items(namespace);
+            load_identity_pipeline_items(namespace);
+            load_enum_meta_pipeline_items(namespace);
+        })?;
         Ctx::set_schema(schema);
         Ctx::set_cli(cli);
         Ok(Self { })
     }
 
     pub fn setup<A, F>(&self, f: F) where F: AsyncCallbackArgument<A> + 'static {
-        let wrap_call = Box::leak(Box::new(f));
-        Ctx::set_setup(|ctx: transaction::Ctx| async {
-            wrap_call.call(ctx).await
-        });
+        Ctx::set_setup(move |ctx: transaction::Ctx| f.call(ctx));
     }
 
     pub fn program<A, F>(&self, name: &str, f: F) where F: AsyncCallbackArgument<A> + 'static {
-        let wrap_call = Box::leak(Box::new(f));
-        Ctx::insert_program(name, |ctx: transaction::Ctx| async {
-            wrap_call.call(ctx).await
-        });
+        Ctx::insert_program(name, move |ctx: transaction::Ctx| f.call(ctx));
+    }
+
+    pub fn backfill<T>(&self, name: &str, model: &str, transform: T) where T: BackfillTransform + 'static {
+        Ctx::update_settings(|settings| { settings.backfills.insert(name.to_owned(), Backfill { model: model.to_owned(), transform: Arc::new(transform) }); });
+    }
+
+    pub fn plugin<P>(&self, plugin: P) where P: Plugin + 'static {
+        Ctx::update_settings(|settings| settings.plugins.push(Arc::new(plugin)));
+    }
+
+    pub fn admin<G>(&self, guard: G) where G: AdminGuard + 'static {
+        Ctx::update_settings(|settings| settings.admin_guard = Some(Arc::new(guard)));
+    }
+
+    pub fn limits(&self, limits: Limits) {
+        Ctx::update_settings(|settings| settings.limits = limits);
+    }
+
+    pub fn action_limits(&self, action: &str, limits: Limits) {
+        Ctx::update_settings(|settings| { settings.action_limits.insert(action.to_owned(), limits); });
+    }
+
+    /// The isolation level and the retries of the transactions of a builtin action, e.g.
+    /// `"upsert"`. A batch item runs in the transaction of its batch instead.
+    pub fn action_transaction(&self, action: &str, options: TransactionOptions) {
+        Ctx::update_settings(|settings| { settings.action_transactions.insert(action.to_owned(), options); });
+    }
+
+    pub fn idempotency(&self, window: Duration) {
+        Ctx::update_settings(|settings| settings.idempotency = Some(Arc::new(IdempotencyStore::new(window))));
+    }
+
+    pub fn stats(&self) {
+        Ctx::update_settings(|settings| settings.stats = Some(Arc::new(StatsRegistry::new())));
+    }
+
+    /// Logs the reads which take `threshold` or longer with their statements and the plans of the
+    /// database for them. Plans are only available for SQL databases.
+    pub fn explain_slow_queries(&self, threshold: Duration) {
+        Ctx::update_settings(|settings| settings.slow_query_threshold = Some(threshold));
+    }
+
+    pub fn compression(&self, min_size: usize) {
+        Ctx::update_settings(|settings| settings.compression_min_size = Some(min_size));
+    }
+
+    pub fn coercion(&self, coercion: Coercion) {
+        Ctx::update_settings(|settings| settings.coercion = coercion);
+    }
+
+    pub fn embed_static_files(&self, mount: &str, files: EmbeddedFiles, options: StaticFilesOptions) {
+        Ctx::update_settings(|settings| settings.embedded_files.push(EmbeddedMount::new(mount, files, options)));
+    }
+
+    pub fn share_secret(&self, secret: &str) {
+        Ctx::update_settings(|settings| settings.share_key = Some(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())));
+    }
+
+    pub fn define_scope<F>(&self, model: &str, name: &str, filter: F) where F: ScopeFilter + 'static {
+        Ctx::update_settings(|settings| { settings.scopes.insert((model.to_owned(), name.to_owned()), Arc::new(filter)); });
+    }
+
+    pub fn on_error<R>(&self, reporter: R) where R: ErrorReporter + 'static {
+        Ctx::update_settings(|settings| settings.error_reporters.push(Arc::new(reporter)));
+    }
+
+    /// Publishes the creates, updates and deletes of records to `sink`, e.g. a `NatsSink`, once
+    /// their transactions commit. The events are kept in memory until they're published, the
+    /// ones not published yet are lost when the process stops.
+    pub fn event_sink<S>(&self, sink: S, options: EventSinkOptions) where S: EventSink + 'static {
+        Ctx::update_settings(|settings| settings.event_sinks.push(Arc::new(RegisteredSink::new(Arc::new(sink), options))));
+    }
+
+    /// Exports the spans of the requests to an OpenTelemetry collector. A request continues the
+    /// trace of its `traceparent` header, its handler and the statements of its connector calls
+    /// are spans of their own.
+    pub fn telemetry(&self, options: TelemetryOptions) {
+        Ctx::update_settings(|settings| settings.telemetry = Some(Arc::new(Exporter::new(options))));
+    }
+
+    /// Serves HTTPS on the bind of the server config, and HTTP/2 to the clients which negotiate
+    /// it. The `--tls-cert`, `--tls-key` and `--tls-self-signed` options of `serve` override it.
+    pub fn tls(&self, options: TlsOptions) {
+        Ctx::update_settings(|settings| settings.tls = Some(options));
+    }
+
+    /// The version of the schema, e.g. `"2.1"`. The old columns of the fields with `@renamedFrom`
+    /// are dropped once it's after the version of their `@droppedAfter`.
+    pub fn schema_version(&self, version: &str) {
+        Ctx::update_settings(|settings| settings.schema_version = Some(version.to_owned()));
+    }
+
+    pub fn register_connector_provider<P>(&self, name: &str, provider: P) where P: ConnectorProvider + 'static {
+        Ctx::update_settings(|settings| { settings.connector_providers.insert(name.to_owned(), Arc::new(provider)); });
+    }
+
+    /// Registers the database at `url` as the source `name`, models with `@source(name)` are
+    /// stored there. A registered connector provider connects to it when one matches the scheme of
+    /// `url`. SQLite in memory is shared by the connections of a process, a SQLite source needs a
+    /// file.
+    pub fn register_source(&self, name: &str, url: &str) {
+        Ctx::update_settings(|settings| { settings.sources.insert(name.to_owned(), url.to_owned()); });
+    }
+
+    pub fn define_model_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Model) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_model_decorator(name, call))
+    }
+
+    pub fn define_model_field_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Field) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_model_field_decorator(name, call))
+    }
+
+    pub fn define_model_relation_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Relation) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_model_relation_decorator(name, call))
+    }
+
+    pub fn define_model_property_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Property) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_model_property_decorator(name, call))
+    }
+
+    pub fn define_enum_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Enum) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_enum_decorator(name, call))
+    }
+
+    pub fn define_enum_member_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Member) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_enum_member_decorator(name, call))
+    }
+
+    pub fn define_handler_decorator<F>(&self, name: &str, call: F) -> Result<()> where F: Fn(Arguments, &mut Handler) -> Result<()> + 'static {
+        self.define(|namespace| namespace.define_handler_decorator(name, call))
     }
 
+    /// Defines the Rust implementation of a pipeline item. Its arguments, input and output are
+    /// declared in the schema with `declare pipeline item`, which the parser type checks call
+    /// sites against, and startup fails when the declaration is missing. Defining fails after the
+    /// namespace is loaded.
+    pub fn define_pipeline_item<T>(&self, name: &str, call: T) -> Result<()> where T: pipeline::item::Call + 'static {
+        self.define_pipeline(name, |namespace| namespace.define_pipeline_item(name, call))
+    }
+
+    pub fn define_transform_pipeline_item<A, O, F, R>(&self, name: &str, call: F) -> Result<()> where
+        A: Send + Sync + 'static,
+        O: Into<Object> + Send + Sync + 'static,
+        R: Into<TransformResult<O>> + Send + Sync + 'static,
+        F: TransformArgument<A, O, R> + 'static {
+        self.define_pipeline(name, |namespace| namespace.define_transform_pipeline_item(name, call))
+    }
+
+    pub fn define_validator_pipeline_item<T, F, O>(&self, name: &str, call: F) -> Result<()> where
+        T: Send + Sync + 'static,
+        F: ValidateArgument<T, O> + 'static,
+        O: Into<ValidateResult> + Send + Sync + 'static {
+        self.define_pipeline(name, |namespace| namespace.define_validator_pipeline_item(name, call))
+    }
+
+    pub fn define_callback_pipeline_item<T, F, O>(&self, name: &str, call: F) -> Result<()> where
+        T: Send + Sync + 'static,
+        F: CallbackArgument<T, O> + 'static,
+        O: Into<CallbackResult> + Send + Sync + 'static {
+        self.define_pipeline(name, |namespace| namespace.define_callback_pipeline_item(name, call))
+    }
+
+    pub fn define_compare_pipeline_item<T, O, F, E>(&self, name: &str, call: F) -> Result<()> where
+        T: Send + Sync + 'static,
+        O: Into<ValidateResult> + Send + Sync + 'static,
+        E: Into<Error> + std::error::Error,
+        F: CompareArgument<T, O, E> + 'static {
+        self.define_pipeline(name, |namespace| namespace.define_compare_pipeline_item(name, call))
+    }
+
+    /// Returns the loaded main namespace, panics while it's still loading.
     pub fn main_namespace(&self) -> &'static Namespace {
         Ctx::main_namespace()
     }
 
-    pub fn main_namespace_mut(&self) -> &'static mut Namespace {
+    /// Borrows the main namespace for loading, fails while it's borrowed and after it's loaded.
+    pub fn main_namespace_mut(&self) -> Result<MainNamespaceMut> {
         Ctx::main_namespace_mut()
     }
 
+    /// Calls `f` with the main namespace, fails while it's borrowed and after it's loaded.
+    pub fn with_main_namespace_mut<T>(&self, f: impl FnOnce(&mut Namespace) -> T) -> Result<T> {
+        Ctx::with_main_namespace_mut(f)
+    }
+
+    fn define(&self, f: impl FnOnce(&mut Namespace)) -> Result<()> {
+        Ctx::with_main_namespace_mut(f)
+    }
+
+    fn define_pipeline(&self, name: &str, f: impl FnOnce(&mut Namespace)) -> Result<()> {
+        self.define(f)?;
+        Ctx::update_settings(|settings| settings.rust_pipeline_items.push(name.to_owned()));
+        Ok(())
+    }
+
     pub async fn run(&self) -> Result<()> {
         self.prepare_for_run().await?;
         self.run_without_prepare().await
     }
 
     pub async fn prepare_for_run(&self) -> Result<()> {
-        load_schema(Ctx::main_namespace_mut(), Ctx::schema(), Ctx::cli().command.ignores_loading()).await
+        let schema = Ctx::schema();
+        check_pipeline_item_declarations(&schema)?;
+        for plugin in Ctx::settings().plugins.clone() {
+            Ctx::with_main_namespace_mut(|namespace| plugin.on_schema_load(namespace))??;
+        }
+        // the borrow owns the namespace until it's dropped, no lock is held across the await
+        let mut namespace = Ctx::main_namespace_mut()?;
+        load_schema(&mut namespace, &schema, Ctx::cli().command.ignores_loading()).await?;
+        settle_delete_rules(&mut namespace)?;
+        settle_renamed_fields(&mut namespace)?;
+        load_enum_meta(&mut namespace, &schema)?;
+        check_counter_caches(&namespace)
     }
 
     pub async fn run_without_prepare(&self) -> Result<()> {
-        run(Ctx::cli()).await
+        run(&Ctx::cli()).await
+    }
+}
+
+// commands which connect the databases publish the namespace after connecting, the others
+// before they read it
+pub(crate) async fn publish_main_namespace() -> Result<&'static Namespace> {
+    let namespace = Ctx::publish_main_namespace()?;
+    for plugin in Ctx::settings().plugins.clone() {
+        plugin.on_namespace_loaded(namespace).await?;
+    }
+    Ok(namespace)
+}
+
+// a pipeline item without a declaration can't be called from the schema
+fn check_pipeline_item_declarations(schema: &Schema) -> Result<()> {
+    let declarations = schema.pipeline_item_declarations();
+    for name in Ctx::settings().rust_pipeline_items.clone() {
+        if !declarations.iter().any(|d| d.namespace_str_path().is_empty() && d.identifier().name() == name) {
+            Err(Error::new(format!("pipeline item `{}' is defined in Rust but not declared, add `declare pipeline item {}' to the schema", name, name)))?
+        }
     }
-}
\ No newline at end of file
+    Ok(())
+}
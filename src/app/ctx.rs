@@ -1,146 +1,237 @@
 use educe::Educe;
 use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 use maplit::btreemap;
 use once_cell::sync::OnceCell;
 use teo_parser::ast::schema::Schema;
-use teo_result:: Result;
+use teo_result::{Error, Result};
 use teo_runtime::connection;
 use teo_runtime::namespace::Namespace;
 use crate::app::callbacks::callback::AsyncCallback;
+use crate::app::settings::Settings;
 use crate::cli::command::CLI;
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
 
-#[derive(Educe)]
+/// The settings of the app. Readers get a snapshot with `Ctx::get`, setters replace the snapshot,
+/// so a request sees the same settings from start to end.
+#[derive(Educe, Clone)]
 #[educe(Debug)]
 pub struct Ctx {
-    loaded: bool,
     pub(crate) runtime_version: RuntimeVersion,
     pub(crate) entrance: Entrance,
-    pub(crate) main_namespace: Namespace,
-    pub(crate) cli: Option<CLI>,
-    #[educe(Debug(ignore))]
-    pub(crate) schema: Option<Schema>,
     #[educe(Debug(ignore))]
     pub(crate) setup: Option<Arc<dyn AsyncCallback>>,
     #[educe(Debug(ignore))]
     pub(crate) programs: BTreeMap<String, Arc<dyn AsyncCallback>>,
-    #[educe(Debug(ignore))]
-    pub(crate) conn_ctx: Option<connection::Ctx>,
+    pub(crate) settings: Arc<Settings>,
+}
+
+// fields are dropped in order, the main namespace outlives the connections referring to it
+struct Current {
+    settings: RwLock<Arc<Ctx>>,
+    cli: OnceCell<Arc<CLI>>,
+    schema: OnceCell<Arc<Schema>>,
+    conn_ctx: OnceCell<connection::Ctx>,
+    main_namespace: MainNamespace,
+}
+
+// The runtime loads the main namespace through `&mut' and reads it as `&'static'. It's taken out
+// for loading, one borrow at a time, and put back when the borrow is dropped, until it's
+// published. The published namespace is owned by the app and dropped with it, the `&'static'
+// references the runtime keeps to it must not outlive the app.
+struct MainNamespace {
+    loading: Mutex<Option<Box<Namespace>>>,
+    published: OnceCell<Box<Namespace>>,
+}
+
+/// The main namespace borrowed for loading. Another borrow fails until this one is dropped.
+pub struct MainNamespaceMut {
+    current: Arc<Current>,
+    namespace: Option<Box<Namespace>>,
+}
+
+impl Deref for MainNamespaceMut {
+    type Target = Namespace;
+
+    fn deref(&self) -> &Namespace {
+        self.namespace.as_deref().unwrap()
+    }
+}
+
+impl DerefMut for MainNamespaceMut {
+
+    fn deref_mut(&mut self) -> &mut Namespace {
+        self.namespace.as_deref_mut().unwrap()
+    }
+}
+
+impl Drop for MainNamespaceMut {
+
+    fn drop(&mut self) {
+        *self.current.main_namespace.loading() = self.namespace.take();
+    }
+}
+
+impl MainNamespace {
+
+    fn loading(&self) -> MutexGuard<'_, Option<Box<Namespace>>> {
+        self.loading.lock().unwrap_or_else(|error| error.into_inner())
+    }
 }
 
 impl Ctx {
 
     fn new() -> Self {
         Self {
-            loaded: true,
             runtime_version: RuntimeVersion::Rust(env!("TEO_RUSTC_VERSION")),
             entrance: Entrance::APP,
-            main_namespace: Namespace::main(),
-            cli: None,
-            schema: None,
             setup: None,
             programs: btreemap!{},
-            conn_ctx: None,
+            settings: Arc::new(Settings::default()),
         }
     }
 
-    pub(in crate::app) fn create() -> bool {
-        if CURRENT.get().is_none() {
-            CURRENT.set(Arc::new(Mutex::new(Self::new()))).unwrap();
-            true
-        } else {
-            false
+    pub(crate) fn create() -> bool {
+        let mut current = CURRENT.write().unwrap();
+        if current.is_some() {
+            return false;
         }
+        *current = Some(Arc::new(Current {
+            settings: RwLock::new(Arc::new(Self::new())),
+            cli: OnceCell::new(),
+            schema: OnceCell::new(),
+            conn_ctx: OnceCell::new(),
+            main_namespace: MainNamespace { loading: Mutex::new(Some(Box::new(Namespace::main()))), published: OnceCell::new() },
+        }));
+        true
     }
 
-    pub(in crate::app) fn drop() -> Result<()> {
-        Ok(Self::get_mut().reset())
+    /// Retires the current app, so another one can be created in this process. Its main namespace
+    /// is dropped with it, the models and objects of the app must not be used afterwards.
+    pub(crate) fn retire() {
+        CURRENT.write().unwrap().take();
     }
 
-    pub fn get() -> &'static Ctx {
-        match CURRENT.get() {
-            Some(ctx) => {
-                let retval = ctx.lock().unwrap();
-                unsafe {
-                    &*(retval.deref() as * const Ctx)
-                }
-            },
+    fn current() -> Arc<Current> {
+        match &*CURRENT.read().unwrap() {
+            Some(current) => current.clone(),
             None => panic!("app ctx is accessed when it's not created"),
         }
     }
 
-    pub fn get_mut() -> &'static mut Ctx {
-        match CURRENT.get() {
-            Some(ctx) => {
-                let mut retval = ctx.lock().unwrap();
-                unsafe {
-                    &mut *(retval.deref_mut() as * mut Ctx)
-                }
-            },
-            None => panic!("app ctx is accessed mutably when it's not created"),
-        }
+    pub fn get() -> Arc<Ctx> {
+        Self::current().settings.read().unwrap().clone()
     }
 
-    fn reset(&mut self) {
-        self.loaded = false;
+    fn update(f: impl FnOnce(&mut Ctx)) {
+        let current = Self::current();
+        let mut settings = current.settings.write().unwrap();
+        f(Arc::make_mut(&mut settings));
     }
 
-    fn reload(&mut self) {
-        self.main_namespace = Namespace::main();
-        self.loaded = true;
+    /// Returns the loaded main namespace, panics while it's still loading.
+    pub fn main_namespace() -> &'static Namespace {
+        match Self::current().main_namespace.published.get() {
+            Some(namespace) => unsafe { &*(namespace.as_ref() as *const Namespace) },
+            None => panic!("main namespace is read before it's loaded"),
+        }
     }
 
-    pub fn main_namespace() -> &'static Namespace {
-        &Ctx::get().main_namespace
+    /// Borrows the main namespace for loading, fails while it's borrowed and after it's loaded.
+    pub fn main_namespace_mut() -> Result<MainNamespaceMut> {
+        let current = Self::current();
+        let namespace = current.main_namespace.loading().take();
+        match namespace {
+            Some(namespace) => Ok(MainNamespaceMut { current, namespace: Some(namespace) }),
+            None if current.main_namespace.published.get().is_some() => Err(Error::new("main namespace cannot be mutated after it's loaded")),
+            None => Err(Error::new("main namespace is already borrowed for loading")),
+        }
     }
 
-    pub fn main_namespace_mut() -> &'static mut Namespace {
-        &mut Ctx::get_mut().main_namespace
+    /// Calls `f` with the main namespace, fails while it's borrowed and after it's loaded.
+    pub fn with_main_namespace_mut<T>(f: impl FnOnce(&mut Namespace) -> T) -> Result<T> {
+        Ok(f(&mut *Self::main_namespace_mut()?))
+    }
+
+    /// Ends loading, the main namespace is only read from now on.
+    pub(crate) fn publish_main_namespace() -> Result<&'static Namespace> {
+        let current = Self::current();
+        let main_namespace = &current.main_namespace;
+        let namespace = main_namespace.loading().take();
+        match namespace {
+            Some(namespace) => if main_namespace.published.set(namespace).is_err() {
+                Err(Error::new("main namespace is published twice"))?
+            },
+            None if main_namespace.published.get().is_some() => Err(Error::new("main namespace is published twice"))?,
+            None => Err(Error::new("main namespace is published while it's borrowed for loading"))?,
+        }
+        Ok(Self::main_namespace())
     }
 
     pub fn set_cli(cli: CLI) {
-        Ctx::get_mut().cli = Some(cli)
+        if Self::current().cli.set(Arc::new(cli)).is_err() {
+            panic!("app cli is set twice");
+        }
     }
 
-    pub fn cli() -> &'static CLI {
-        Ctx::get().cli.as_ref().unwrap()
+    pub fn cli() -> Arc<CLI> {
+        Self::current().cli.get().unwrap().clone()
     }
 
     pub fn set_schema(schema: Schema) {
-        Ctx::get_mut().schema = Some(schema)
+        if Self::current().schema.set(Arc::new(schema)).is_err() {
+            panic!("app schema is set twice");
+        }
     }
 
-    pub fn schema() -> &'static Schema {
-        Ctx::get().schema.as_ref().unwrap()
+    pub fn schema() -> Arc<Schema> {
+        Self::current().schema.get().unwrap().clone()
     }
 
     pub fn set_entrance(entrance: Entrance) {
-        Ctx::get_mut().entrance = entrance;
+        Ctx::update(|ctx| ctx.entrance = entrance);
     }
 
     pub fn set_runtime_version(runtime_version: RuntimeVersion) {
-        Ctx::get_mut().runtime_version = runtime_version;
+        Ctx::update(|ctx| ctx.runtime_version = runtime_version);
     }
 
+    pub fn conn_ctx() -> connection::Ctx {
+        Self::current().conn_ctx.get().unwrap().clone()
+    }
 
-    pub fn conn_ctx() -> &'static connection::Ctx {
-        Ctx::get().conn_ctx.as_ref().unwrap()
+    pub(crate) fn set_conn_ctx(conn_ctx: connection::Ctx) -> Result<()> {
+        if Self::current().conn_ctx.set(conn_ctx).is_err() {
+            Err(Error::new("databases are connected twice"))?
+        }
+        Ok(())
     }
 
-    pub fn setup() -> Option<&'static Arc<dyn AsyncCallback>> {
-        Ctx::get().setup.as_ref()
+    pub fn setup() -> Option<Arc<dyn AsyncCallback>> {
+        Ctx::get().setup.clone()
     }
 
     pub fn set_setup<F>(f: F) where F: AsyncCallback + 'static {
-        Ctx::get_mut().setup = Some(Arc::new(f));
+        Ctx::update(|ctx| ctx.setup = Some(Arc::new(f)));
+    }
+
+    pub(crate) fn settings() -> Arc<Settings> {
+        Ctx::get().settings.clone()
+    }
+
+    pub(crate) fn update_settings(f: impl FnOnce(&mut Settings)) {
+        Ctx::update(|ctx| f(Arc::make_mut(&mut ctx.settings)));
+    }
+
+    pub fn program(name: &str) -> Option<Arc<dyn AsyncCallback>> {
+        Ctx::get().programs.get(name).cloned()
     }
 
     pub fn insert_program<F>(name: &str, f: F) where F: AsyncCallback + 'static {
-        Ctx::get_mut().programs.insert(name.to_owned(), Arc::new(f));
+        Ctx::update(|ctx| { ctx.programs.insert(name.to_owned(), Arc::new(f)); });
     }
 }
 
-static CURRENT: OnceCell<Arc<Mutex<Ctx>>> = OnceCell::new();
\ No newline at end of file
+static CURRENT: RwLock<Option<Arc<Current>>> = RwLock::new(None);
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use async_trait::async_trait;
+use key_path::KeyPath;
+use once_cell::sync::Lazy;
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_teon::value::Value;
+use tokio::sync::Mutex;
+
+// the transaction of each connection a test case used, by the address of the connection
+type Case = BTreeMap<usize, Arc<dyn Transaction>>;
+
+// the test case in progress
+static CASE: Lazy<Mutex<Option<Case>>> = Lazy::new(|| Mutex::new(None));
+
+static SAVEPOINT: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts a test case, whose transactions `end_case` rolls back.
+pub(crate) async fn begin_case() -> Result<()> {
+    let mut case = CASE.lock().await;
+    if case.is_some() {
+        Err(Error::new("a test case is in progress, test cases don't nest"))?
+    }
+    *case = Some(BTreeMap::new());
+    Ok(())
+}
+
+/// Rolls back the writes of the test case in progress.
+pub(crate) async fn end_case() -> Result<()> {
+    let Some(transactions) = CASE.lock().await.take() else {
+        return Ok(());
+    };
+    for transaction in transactions.into_values() {
+        transaction.query_raw(&Value::String("ROLLBACK".to_owned())).await?;
+    }
+    Ok(())
+}
+
+/// The transaction of `connection` in the test case in progress, `None` outside of a case.
+pub(crate) async fn case_transaction(connection: &Arc<dyn Connection>, provider: Option<Database>, transaction: bool) -> Result<Option<Arc<dyn Transaction>>> {
+    let mut case = CASE.lock().await;
+    let Some(transactions) = case.as_mut() else {
+        return Ok(None);
+    };
+    let key = Arc::as_ptr(connection) as *const () as usize;
+    let pinned = match transactions.get(&key) {
+        Some(pinned) => pinned.clone(),
+        None => {
+            let begin = match provider {
+                Some(Database::MySQL) => "START TRANSACTION",
+                Some(Database::PostgreSQL | Database::SQLite) => "BEGIN",
+                _ => Err(Error::new("test cases are rolled back on the SQL databases, a test on another database creates a test server of its own"))?,
+            };
+            let pinned = connection.no_transaction().await?;
+            pinned.query_raw(&Value::String(begin.to_owned())).await?;
+            transactions.insert(key, pinned.clone());
+            pinned
+        }
+    };
+    Ok(Some(Arc::new(CaseTransaction::new(pinned, transaction).await?)))
+}
+
+// a transaction of an action in a test case, on the connection of the case
+#[derive(Debug)]
+struct CaseTransaction {
+    inner: Arc<dyn Transaction>,
+    savepoint: Option<String>,
+}
+
+impl CaseTransaction {
+
+    async fn new(inner: Arc<dyn Transaction>, transaction: bool) -> Result<Self> {
+        let savepoint = if transaction {
+            let name = format!("teo_case_{}", SAVEPOINT.fetch_add(1, Ordering::SeqCst));
+            inner.query_raw(&Value::String(format!("SAVEPOINT {}", name))).await?;
+            Some(name)
+        } else {
+            None
+        };
+        Ok(Self { inner, savepoint })
+    }
+}
+
+#[async_trait]
+impl Transaction for CaseTransaction {
+
+    async fn migrate(&self, models: Vec<&Model>, dry_run: bool, reset_database: bool, silent: bool) -> Result<()> {
+        self.inner.migrate(models, dry_run, reset_database, silent).await
+    }
+
+    async fn purge(&self, models: Vec<&Model>) -> Result<()> {
+        self.inner.purge(models).await
+    }
+
+    async fn query_raw(&self, value: &Value) -> Result<Value> {
+        self.inner.query_raw(value).await
+    }
+
+    async fn save_object(&self, object: &Object, path: KeyPath) -> path::Result<()> {
+        self.inner.save_object(object, path).await
+    }
+
+    async fn delete_object(&self, object: &Object, path: KeyPath) -> path::Result<()> {
+        self.inner.delete_object(object, path).await
+    }
+
+    async fn find_unique(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> path::Result<Option<Object>> {
+        self.inner.find_unique(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path).await
+    }
+
+    async fn find_many(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> path::Result<Vec<Object>> {
+        self.inner.find_many(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path).await
+    }
+
+    async fn count(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<usize> {
+        self.inner.count(model, finder, transaction_ctx, path).await
+    }
+
+    async fn aggregate(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<Value> {
+        self.inner.aggregate(model, finder, transaction_ctx, path).await
+    }
+
+    async fn group_by(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<Vec<Value>> {
+        self.inner.group_by(model, finder, transaction_ctx, path).await
+    }
+
+    fn is_committed(&self) -> bool {
+        false
+    }
+
+    fn is_transaction(&self) -> bool {
+        self.savepoint.is_some()
+    }
+
+    async fn commit(&self) -> Result<()> {
+        if let Some(name) = &self.savepoint {
+            self.inner.query_raw(&Value::String(format!("RELEASE SAVEPOINT {}", name))).await?;
+        }
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        if let Some(name) = &self.savepoint {
+            self.inner.query_raw(&Value::String(format!("ROLLBACK TO SAVEPOINT {}", name))).await?;
+            self.inner.query_raw(&Value::String(format!("RELEASE SAVEPOINT {}", name))).await?;
+        }
+        Ok(())
+    }
+
+    async fn spawn(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(Self::new(self.inner.clone(), true).await?))
+    }
+}
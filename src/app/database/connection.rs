@@ -0,0 +1,391 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use async_trait::async_trait;
+use key_path::KeyPath;
+use serde_json::json;
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_teon::value::Value;
+use tokio::sync::Mutex;
+use crate::app::database::case::case_transaction;
+use crate::app::database::flavor::MySQLServer;
+use crate::app::database::unique::map_unique_violation;
+use crate::on_delete::migrate_foreign_keys;
+use crate::migrate::expand::{migrate_renamed_columns, write_old_columns};
+use crate::counter_cache::{refresh_counters, Write};
+use crate::event_sink::{change_event, publish_changes, ChangeEvent, ChangeKind};
+use crate::position::{assign_position, lock_requested_scopes};
+use crate::explain::{explained_read, traced_statements};
+use crate::app::database::nested::scope_nested_finder;
+use crate::app::database::relation_filters::resolve_relation_filters;
+use crate::server::dry_run::check_dry_run;
+use crate::server::statements::{count_read, count_write};
+use crate::source::source;
+use crate::state::{check_transition, run_transition_hooks};
+use crate::telemetry::{Span, SpanKind};
+use crate::savepoint::set_savepoints;
+use crate::isolation::set_isolation;
+use crate::object::relation::forget_relations;
+
+// the connections of a test context, by the address of the namespace connection they stand in for
+pub(crate) type ContextConnections = Arc<BTreeMap<usize, Arc<dyn Connection>>>;
+
+tokio::task_local! {
+    static CONTEXT: ContextConnections;
+}
+
+/// Runs `f` with the connections of a test context in place of those of the namespaces.
+pub(crate) async fn in_context<F: Future>(connections: ContextConnections, f: F) -> F::Output {
+    CONTEXT.scope(connections, f).await
+}
+
+/// The key of `connection` in the connections of a test context.
+pub(crate) fn context_key(connection: &Arc<dyn Connection>) -> usize {
+    Arc::as_ptr(connection) as *const () as usize
+}
+
+/// The connection of a namespace, models with `@source` use the connection of their source.
+#[derive(Debug)]
+pub(crate) struct NamespaceConnection {
+    inner: Arc<dyn Connection>,
+    // the database of `inner' if it's known, the statements of slow reads are explained for it
+    provider: Option<Database>,
+    // the detected server of a MySQL connection, its flavor decides how statements are explained
+    mysql: Option<MySQLServer>,
+    // the source this connection stores, its models aren't routed any further
+    source: Option<String>,
+    sources: Arc<BTreeMap<String, Arc<dyn Connection>>>,
+}
+
+impl NamespaceConnection {
+
+    pub(crate) fn new(inner: Arc<dyn Connection>, provider: Option<Database>) -> Self {
+        Self { inner, provider, mysql: None, source: None, sources: Arc::new(BTreeMap::new()) }
+    }
+
+    pub(crate) fn for_source(inner: Arc<dyn Connection>, provider: Option<Database>, name: &str) -> Self {
+        Self { source: Some(name.to_owned()), ..Self::new(inner, provider) }
+    }
+
+    pub(crate) fn with_mysql_server(self, mysql: Option<MySQLServer>) -> Self {
+        Self { mysql, ..self }
+    }
+
+    pub(crate) fn with_sources(self, sources: BTreeMap<String, Arc<dyn Connection>>) -> Self {
+        Self { sources: Arc::new(sources), ..self }
+    }
+
+    fn inner(&self) -> Arc<dyn Connection> {
+        let key = self as *const Self as *const () as usize;
+        CONTEXT.try_with(|connections| connections.get(&key).cloned()).ok().flatten().unwrap_or_else(|| self.inner.clone())
+    }
+
+    // the isolation level of a test case transaction isn't set, its actions run in savepoints
+    fn wrap(&self, inner: Arc<dyn Transaction>, in_case: bool) -> Arc<dyn Transaction> {
+        Arc::new(NamespaceTransaction { inner, provider: self.provider, mysql: self.mysql, source: self.source.clone(), sources: self.sources.clone(), routed: Mutex::new(BTreeMap::new()), changes: Arc::new(std::sync::Mutex::new(vec![])), isolated: AtomicBool::new(in_case) })
+    }
+}
+
+#[async_trait]
+impl Connection for NamespaceConnection {
+
+    async fn transaction(&self) -> Result<Arc<dyn Transaction>> {
+        let inner = self.inner();
+        if let Some(transaction) = case_transaction(&inner, self.provider, true).await? {
+            return Ok(self.wrap(transaction, true));
+        }
+        Ok(self.wrap(inner.transaction().await?, false))
+    }
+
+    async fn no_transaction(&self) -> Result<Arc<dyn Transaction>> {
+        let inner = self.inner();
+        if let Some(transaction) = case_transaction(&inner, self.provider, false).await? {
+            return Ok(self.wrap(transaction, true));
+        }
+        Ok(self.wrap(inner.no_transaction().await?, false))
+    }
+}
+
+// the transactions of the sources are opened on first use and committed after the transaction of
+// the namespace, a write across databases isn't atomic
+#[derive(Debug)]
+struct NamespaceTransaction {
+    inner: Arc<dyn Transaction>,
+    provider: Option<Database>,
+    mysql: Option<MySQLServer>,
+    source: Option<String>,
+    sources: Arc<BTreeMap<String, Arc<dyn Connection>>>,
+    routed: Mutex<BTreeMap<String, Arc<dyn Transaction>>>,
+    // the change events of the writes, published when the transaction commits
+    changes: Arc<std::sync::Mutex<Vec<ChangeEvent>>>,
+    // whether the isolation level in scope was set, before the first statement
+    isolated: AtomicBool,
+}
+
+impl NamespaceTransaction {
+
+    async fn routed(&self, model: &Model) -> Result<Option<Arc<dyn Transaction>>> {
+        let Some(name) = source(model).filter(|name| self.source.as_deref() != Some(name)) else {
+            return Ok(None);
+        };
+        let mut routed = self.routed.lock().await;
+        if let Some(transaction) = routed.get(name) {
+            return Ok(Some(transaction.clone()));
+        }
+        let Some(connection) = self.sources.get(name) else {
+            Err(Error::new(format!("source `{}' is not connected", name)))?
+        };
+        let transaction = if self.inner.is_transaction() {
+            connection.transaction().await?
+        } else {
+            connection.no_transaction().await?
+        };
+        routed.insert(name.to_owned(), transaction.clone());
+        Ok(Some(transaction))
+    }
+
+    fn record_change(&self, object: &Object, kind: ChangeKind) {
+        let Some(event) = change_event(object, kind) else { return };
+        if self.inner.is_transaction() {
+            self.changes.lock().unwrap().push(event);
+        } else {
+            publish_changes(vec![event]);
+        }
+    }
+
+    // the client span of the connector call `f', with the statements it issued
+    async fn traced<T>(&self, model: &Model, operation: &str, f: impl Future<Output = path::Result<T>>) -> path::Result<T> {
+        let Some(mut span) = Span::start(format!("{} {}", operation, model.path.join(".")), SpanKind::Client) else {
+            return f.await;
+        };
+        let statements = Arc::new(std::sync::Mutex::new(vec![]));
+        let output = span.scope(traced_statements(statements.clone(), f)).await;
+        if let Some(provider) = self.provider {
+            span.set("db.system", json!(db_system(provider)));
+        }
+        span.set("db.operation", json!(operation));
+        span.set("db.collection.name", json!(model.table_name));
+        let statements = statements.lock().unwrap().join(";\n");
+        if !statements.is_empty() {
+            span.set("db.statement", json!(statements));
+        }
+        if let Err(error) = &output {
+            span.fail(&error.message);
+        }
+        span.end();
+        output
+    }
+
+    // the savepoints of the nested transactions in scope, set before the first write in them
+    async fn savepoints(&self) -> Result<()> {
+        self.isolation().await?;
+        set_savepoints(&self.inner, self.provider, &self.changes).await
+    }
+
+    async fn isolation(&self) -> Result<()> {
+        set_isolation(&*self.inner, self.provider, &self.isolated).await
+    }
+
+    async fn routed_transactions(&self) -> Vec<Arc<dyn Transaction>> {
+        self.routed.lock().await.values().cloned().collect()
+    }
+
+    // splits `models' into those of the namespace and those of each source
+    async fn partition<'a>(&self, models: Vec<&'a Model>) -> Result<(Vec<&'a Model>, Vec<(Arc<dyn Transaction>, Vec<&'a Model>)>)> {
+        let mut own = vec![];
+        let mut routed: BTreeMap<&str, (Arc<dyn Transaction>, Vec<&'a Model>)> = BTreeMap::new();
+        for model in models {
+            match source(model).filter(|name| self.source.as_deref() != Some(name)) {
+                Some(name) => match routed.get_mut(name) {
+                    Some((_, models)) => models.push(model),
+                    None => {
+                        let transaction = self.routed(model).await?.unwrap();
+                        routed.insert(name, (transaction, vec![model]));
+                    }
+                },
+                None => own.push(model),
+            }
+        }
+        Ok((own, routed.into_values().collect()))
+    }
+}
+
+#[async_trait]
+impl Transaction for NamespaceTransaction {
+
+    async fn migrate(&self, models: Vec<&Model>, dry_run: bool, reset_database: bool, silent: bool) -> Result<()> {
+        let (own, routed) = self.partition(models).await?;
+        for (transaction, models) in routed {
+            transaction.migrate(models, dry_run, reset_database, silent).await?;
+        }
+        if let Some(database) = self.provider {
+            migrate_renamed_columns(&*self.inner, database, &own, dry_run, silent).await?;
+        }
+        self.inner.migrate(own.clone(), dry_run, reset_database, silent).await?;
+        match self.provider {
+            Some(database) if !dry_run => migrate_foreign_keys(&*self.inner, database, &own).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn purge(&self, models: Vec<&Model>) -> Result<()> {
+        let (own, routed) = self.partition(models).await?;
+        for (transaction, models) in routed {
+            transaction.purge(models).await?;
+        }
+        self.inner.purge(own).await
+    }
+
+    async fn query_raw(&self, value: &Value) -> Result<Value> {
+        count_write()?;
+        self.savepoints().await?;
+        self.inner.query_raw(value).await
+    }
+
+    // every connection is wrapped, so the record hooks below run for every write path, nested
+    // writes and batch items included
+    async fn save_object(&self, object: &Object, path: KeyPath) -> path::Result<()> {
+        if let Some(transaction) = self.routed(object.model()).await? {
+            return transaction.save_object(object, path).await;
+        }
+        check_dry_run(&*self.inner)?;
+        check_transition(object, &path)?;
+        run_transition_hooks(object, &path).await?;
+        let write = if object.is_new() { Write::Create } else { Write::Update };
+        if write == Write::Create {
+            self.isolation().await?;
+            assign_position(object, &path, &*self.inner, self.provider).await?;
+        }
+        write_old_columns(object)?;
+        count_write()?;
+        self.savepoints().await?;
+        // unique values aren't looked up before they're written, the violations are mapped instead
+        self.traced(object.model(), if write == Write::Create { "create" } else { "update" }, self.inner.save_object(object, path.clone())).await.map_err(|error| map_unique_violation(object.model(), error, &path))?;
+        refresh_counters(self, object, write, &path).await?;
+        forget_relations(object);
+        self.record_change(object, if write == Write::Create { ChangeKind::Create } else { ChangeKind::Update });
+        Ok(())
+    }
+
+    async fn delete_object(&self, object: &Object, path: KeyPath) -> path::Result<()> {
+        if let Some(transaction) = self.routed(object.model()).await? {
+            return transaction.delete_object(object, path).await;
+        }
+        check_dry_run(&*self.inner)?;
+        count_write()?;
+        self.savepoints().await?;
+        self.traced(object.model(), "delete", self.inner.delete_object(object, path.clone())).await?;
+        refresh_counters(self, object, Write::Delete, &path).await?;
+        forget_relations(object);
+        self.record_change(object, ChangeKind::Delete);
+        Ok(())
+    }
+
+    async fn find_unique(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> path::Result<Option<Object>> {
+        if let Some(transaction) = self.routed(model).await? {
+            return transaction.find_unique(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path).await;
+        }
+        self.isolation().await?;
+        let scoped = scope_nested_finder(model, action, finder);
+        let finder = scoped.as_ref().unwrap_or(finder);
+        let resolved = resolve_relation_filters(self, self.provider, model, finder, transaction_ctx.clone(), req_ctx.clone(), &path).await?;
+        let finder = resolved.as_ref().unwrap_or(finder);
+        count_read();
+        explained_read(&*self.inner, self.provider, self.mysql, model, "findUnique", self.traced(model, "findUnique", self.inner.find_unique(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path))).await
+    }
+
+    async fn find_many(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> path::Result<Vec<Object>> {
+        if let Some(transaction) = self.routed(model).await? {
+            return transaction.find_many(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path).await;
+        }
+        self.isolation().await?;
+        lock_requested_scopes(model, req_ctx.as_ref(), &*self.inner, self.provider).await?;
+        let scoped = scope_nested_finder(model, action, finder);
+        let finder = scoped.as_ref().unwrap_or(finder);
+        let resolved = resolve_relation_filters(self, self.provider, model, finder, transaction_ctx.clone(), req_ctx.clone(), &path).await?;
+        let finder = resolved.as_ref().unwrap_or(finder);
+        count_read();
+        explained_read(&*self.inner, self.provider, self.mysql, model, "findMany", self.traced(model, "findMany", self.inner.find_many(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path))).await
+    }
+
+    async fn count(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<usize> {
+        if let Some(transaction) = self.routed(model).await? {
+            return transaction.count(model, finder, transaction_ctx, path).await;
+        }
+        self.isolation().await?;
+        let resolved = resolve_relation_filters(self, self.provider, model, finder, transaction_ctx.clone(), None, &path).await?;
+        let finder = resolved.as_ref().unwrap_or(finder);
+        count_read();
+        explained_read(&*self.inner, self.provider, self.mysql, model, "count", self.traced(model, "count", self.inner.count(model, finder, transaction_ctx, path))).await
+    }
+
+    async fn aggregate(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<Value> {
+        if let Some(transaction) = self.routed(model).await? {
+            return transaction.aggregate(model, finder, transaction_ctx, path).await;
+        }
+        self.isolation().await?;
+        let resolved = resolve_relation_filters(self, self.provider, model, finder, transaction_ctx.clone(), None, &path).await?;
+        let finder = resolved.as_ref().unwrap_or(finder);
+        count_read();
+        explained_read(&*self.inner, self.provider, self.mysql, model, "aggregate", self.traced(model, "aggregate", self.inner.aggregate(model, finder, transaction_ctx, path))).await
+    }
+
+    async fn group_by(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<Vec<Value>> {
+        if let Some(transaction) = self.routed(model).await? {
+            return transaction.group_by(model, finder, transaction_ctx, path).await;
+        }
+        self.isolation().await?;
+        let resolved = resolve_relation_filters(self, self.provider, model, finder, transaction_ctx.clone(), None, &path).await?;
+        let finder = resolved.as_ref().unwrap_or(finder);
+        count_read();
+        explained_read(&*self.inner, self.provider, self.mysql, model, "groupBy", self.traced(model, "groupBy", self.inner.group_by(model, finder, transaction_ctx, path))).await
+    }
+
+    fn is_committed(&self) -> bool {
+        self.inner.is_committed()
+    }
+
+    fn is_transaction(&self) -> bool {
+        self.inner.is_transaction()
+    }
+
+    async fn commit(&self) -> Result<()> {
+        self.inner.commit().await?;
+        publish_changes(std::mem::take(&mut *self.changes.lock().unwrap()));
+        for transaction in self.routed_transactions().await {
+            transaction.commit().await?;
+        }
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        self.changes.lock().unwrap().clear();
+        self.inner.abort().await?;
+        for transaction in self.routed_transactions().await {
+            transaction.abort().await?;
+        }
+        Ok(())
+    }
+
+    async fn spawn(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(NamespaceTransaction { inner: self.inner.spawn().await?, provider: self.provider, mysql: self.mysql, source: self.source.clone(), sources: self.sources.clone(), routed: Mutex::new(BTreeMap::new()), changes: Arc::new(std::sync::Mutex::new(vec![])), isolated: AtomicBool::new(false) }))
+    }
+}
+
+// the names of the OpenTelemetry semantic conventions
+fn db_system(provider: Database) -> &'static str {
+    match provider {
+        Database::MongoDB => "mongodb",
+        Database::MySQL => "mysql",
+        Database::PostgreSQL => "postgresql",
+        Database::SQLite => "sqlite",
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+use teo_runtime::connection::connection::Connection;
+use teo_teon::value::Value;
+
+/// The server behind a MySQL connector. MariaDB and TiDB speak the MySQL protocol but differ in
+/// what they support, the server is detected from its version when the connector connects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MySQLFlavor {
+    MySQL,
+    MariaDB,
+    TiDB,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MySQLServer {
+    pub flavor: MySQLFlavor,
+    /// Major, minor and patch version of the server itself, not the MySQL version TiDB reports.
+    pub version: (u32, u32, u32),
+}
+
+impl MySQLServer {
+
+    /// Parses the output of `SELECT VERSION()`, e.g. `8.0.35`, `10.11.6-MariaDB-1:10.11.6` or
+    /// `5.7.25-TiDB-v7.1.0`.
+    pub fn parse(version: &str) -> Option<Self> {
+        if let Some((_, tidb)) = version.split_once("-TiDB-v") {
+            return Some(Self { flavor: MySQLFlavor::TiDB, version: numbers(tidb)? });
+        }
+        let flavor = if version.contains("MariaDB") { MySQLFlavor::MariaDB } else { MySQLFlavor::MySQL };
+        Some(Self { flavor, version: numbers(version)? })
+    }
+
+    /// `INSERT ... RETURNING`, MariaDB supports it from 10.5.
+    pub fn supports_returning(&self) -> bool {
+        self.flavor == MySQLFlavor::MariaDB && self.version >= (10, 5, 0)
+    }
+
+    /// JSON functions like `JSON_TABLE`, which MariaDB has from 10.6 and TiDB lacks.
+    pub fn supports_json_table(&self) -> bool {
+        match self.flavor {
+            MySQLFlavor::MySQL => self.version >= (8, 0, 4),
+            MySQLFlavor::MariaDB => self.version >= (10, 6, 0),
+            MySQLFlavor::TiDB => false,
+        }
+    }
+
+    /// TiDB clusters tables by their primary key by default, and the primary key of a clustered
+    /// table can't be dropped or changed.
+    pub fn clusters_primary_keys(&self) -> bool {
+        self.flavor == MySQLFlavor::TiDB && self.version >= (5, 0, 0)
+    }
+
+    /// The statement which explains `statement` and runs it for actual costs where the server can.
+    pub fn explain(&self, statement: &str) -> String {
+        match self.flavor {
+            MySQLFlavor::MySQL if self.version >= (8, 0, 18) => format!("EXPLAIN ANALYZE {}", statement),
+            MySQLFlavor::MySQL => format!("EXPLAIN FORMAT=JSON {}", statement),
+            MySQLFlavor::MariaDB if self.version >= (10, 1, 0) => format!("ANALYZE FORMAT=JSON {}", statement),
+            MySQLFlavor::MariaDB => format!("EXPLAIN {}", statement),
+            MySQLFlavor::TiDB => format!("EXPLAIN ANALYZE {}", statement),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        let flavor = match self.flavor {
+            MySQLFlavor::MySQL => "MySQL",
+            MySQLFlavor::MariaDB => "MariaDB",
+            MySQLFlavor::TiDB => "TiDB",
+        };
+        format!("{} {}.{}.{}", flavor, self.version.0, self.version.1, self.version.2)
+    }
+}
+
+/// Asks the server of a MySQL connection for its version, `None` when it can't be read.
+pub(crate) async fn detect(connection: &Arc<dyn Connection>) -> Option<MySQLServer> {
+    let transaction = connection.no_transaction().await.ok()?;
+    let rows = transaction.query_raw(&Value::String("SELECT VERSION() AS version".to_owned())).await.ok()?;
+    MySQLServer::parse(rows.as_array()?.first()?.get("version")?.as_str()?)
+}
+
+// the leading `major.minor.patch' of a version, missing parts are 0
+fn numbers(version: &str) -> Option<(u32, u32, u32)> {
+    let end = version.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(version.len());
+    let mut parts = version[..end].split('.').map(|p| p.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+    let patch = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+    Some((major, minor, patch))
+}
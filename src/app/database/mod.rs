@@ -1,52 +1,128 @@
+pub mod provider;
+pub mod flavor;
+pub mod clickhouse;
+pub mod unique;
+pub mod connection;
+pub mod case;
+pub mod nested;
+pub mod relation_filters;
+
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use array_tool::vec::Join;
-use teo_result::{Result};
-use teo_runtime::config::connector::Connector;
+use teo_result::{Error, Result};
 use teo_runtime::connection::connection::Connection;
 use teo_runtime::database::database::Database;
 use teo_runtime::namespace::Namespace;
 use teo_sql_connector::connector::SQLConnection;
 use teo_sql_connector::schema::dialect::SQLDialect;
 use teo_mongodb_connector::connector::MongoDBConnection;
+use crate::app::app::publish_main_namespace;
 use crate::app::ctx::Ctx;
+use crate::app::database::clickhouse::ClickHouseConnection;
+use crate::app::database::connection::NamespaceConnection;
+use crate::app::database::provider::ConnectorProvider;
+use crate::app::database::flavor::{detect, MySQLServer};
 use teo_runtime::connection::Ctx as ConnCtx;
 use crate::message::info_message;
+use crate::source::source;
 
 pub async fn connect_databases(namespace: &mut Namespace, silent: bool) -> Result<()> {
-    may_connect_database(namespace, silent).await?;
+    let mut sources = BTreeMap::new();
+    connect_database(namespace, &mut sources, silent).await?;
     for namespace in namespace.namespaces.values_mut() {
-        may_connect_database(namespace, silent).await?;
+        connect_database(namespace, &mut sources, silent).await?;
     }
-    let ctx = ConnCtx::from_namespace(Ctx::main_namespace());
-    Ctx::get_mut().conn_ctx = Some(ctx);
     Ok(())
 }
 
+// connecting is the last step of loading, the namespace is published afterwards
+pub(crate) async fn connect_main_namespace(silent: bool) -> Result<()> {
+    connect_databases(&mut *Ctx::main_namespace_mut()?, silent).await?;
+    let namespace = publish_main_namespace().await?;
+    Ctx::set_conn_ctx(ConnCtx::from_namespace(namespace))
+}
+
 pub async fn may_connect_database(namespace: &mut Namespace, silent: bool) -> Result<()> {
-    if namespace.connector.is_none() { return Ok(()) }
-    let connector = namespace.connector.as_ref().unwrap();
-    let connection = connection_for_connector(connector).await;
+    connect_database(namespace, &mut BTreeMap::new(), silent).await
+}
+
+// `sources' holds the secondary databases connected so far, namespaces share them
+async fn connect_database(namespace: &mut Namespace, sources: &mut BTreeMap<String, Arc<dyn Connection>>, silent: bool) -> Result<()> {
+    let Some(connector) = namespace.connector.as_ref() else { return Ok(()) };
+    let connection = connection_for_url(&connector.url, Some(connector.provider)).await?;
+    let mysql = mysql_server(&connector.url, connector.provider, &connection).await;
     if !silent {
-        info_message(format!("{} connector connected for `{}` at \"{}\"", connector.provider.lowercase_desc(), if namespace.path.is_empty() { "main".to_string() } else { namespace.path().join(".") }, connector.url));
+        info_message(format!("{} connector connected for `{}` at \"{}\"{}", connector.provider.lowercase_desc(), if namespace.path.is_empty() { "main".to_string() } else { namespace.path().join(".") }, connector.url, mysql.map(|m| format!(", server is {}", m.name())).unwrap_or_default()));
+    }
+    for model in namespace.models_under_connector() {
+        let Some(name) = source(model) else { continue };
+        if sources.contains_key(name) { continue }
+        let Some(url) = Ctx::settings().sources.get(name).cloned() else {
+            Err(Error::new(format!("model `{}' uses source `{}', which is not registered", model.path.join("."), name)))?
+        };
+        let source_connection = connection_for_url(&url, None).await?;
+        let source_mysql = match provider_for_url(&url) {
+            Ok(provider) => mysql_server(&url, provider, &source_connection).await,
+            Err(_) => None,
+        };
+        if !silent {
+            info_message(format!("source `{}` connected at \"{}\"{}", name, url, source_mysql.map(|m| format!(", server is {}", m.name())).unwrap_or_default()));
+        }
+        sources.insert(name.to_owned(), Arc::new(NamespaceConnection::for_source(source_connection, provider_for_url(&url).ok(), name).with_mysql_server(source_mysql)));
     }
-    namespace.connection = Some(connection);
+    namespace.connection = Some(Arc::new(NamespaceConnection::new(connection, Some(connector.provider)).with_mysql_server(mysql).with_sources(sources.clone())));
     Ok(())
 }
 
-async fn connection_for_connector(connector: &Connector) -> Arc<dyn Connection> {
-    if connector.provider.is_mongo() {
-        Arc::new(MongoDBConnection::new(connector.url.as_str()).await)
+// sources have no connector config, the scheme of their url names the database
+pub(crate) async fn connection_for_url(url: &str, provider: Option<Database>) -> Result<Arc<dyn Connection>> {
+    if let Some(provider) = registered_provider(url) {
+        return provider.connect(url.to_owned()).await;
+    }
+    if matches!(url.split_once(':').map(|(scheme, _)| scheme), Some("clickhouse" | "clickhouses")) {
+        return Ok(Arc::new(ClickHouseConnection::new(url)?));
+    }
+    let provider = match provider {
+        Some(provider) => provider,
+        None => provider_for_url(url)?,
+    };
+    Ok(if provider.is_mongo() {
+        Arc::new(MongoDBConnection::new(url).await)
     } else {
         Arc::new(SQLConnection::new(
-            match connector.provider {
+            match provider {
                 Database::MongoDB => unreachable!(),
                 Database::MySQL => SQLDialect::MySQL,
                 Database::PostgreSQL => SQLDialect::PostgreSQL,
                 Database::SQLite => SQLDialect::SQLite,
             },
-            connector.url.as_str(),
+            url,
             false,
         ).await)
+    })
+}
+
+// MySQL connections of registered providers and ClickHouse are left alone, they may not be MySQL
+// servers
+async fn mysql_server(url: &str, provider: Database, connection: &Arc<dyn Connection>) -> Option<MySQLServer> {
+    if !matches!(provider, Database::MySQL) || registered_provider(url).is_some() || url.starts_with("clickhouse") {
+        return None;
     }
+    detect(connection).await
+}
 
-}
\ No newline at end of file
+fn provider_for_url(url: &str) -> Result<Database> {
+    Ok(match url.split_once(':').map(|(scheme, _)| scheme) {
+        Some("sqlite") => Database::SQLite,
+        Some("mysql") => Database::MySQL,
+        Some("postgres" | "postgresql") => Database::PostgreSQL,
+        Some("mongodb" | "mongodb+srv") => Database::MongoDB,
+        _ => Err(Error::new(format!("source url \"{}\" has no known scheme", url)))?,
+    })
+}
+
+fn registered_provider(url: &str) -> Option<Arc<dyn ConnectorProvider>> {
+    let scheme = url.split_once(":").map(|(scheme, _)| scheme)?;
+    Ctx::settings().connector_providers.get(scheme).cloned()
+}
@@ -0,0 +1,19 @@
+use teo_runtime::action::Action;
+use teo_runtime::action::action::NESTED;
+use teo_runtime::model::Model;
+use teo_teon::value::Value;
+
+/// Merges the relation scope of a nested update or delete into its filter.
+pub(crate) fn scope_nested_finder(model: &Model, action: Action, finder: &Value) -> Option<Value> {
+    if action & NESTED != NESTED || model.field("where").is_some() {
+        return None;
+    }
+    let Some(Value::Dictionary(r#where)) = finder.get("where") else { return None };
+    let Some(Value::Dictionary(scope)) = r#where.get("where") else { return None };
+    let mut scoped = r#where.clone();
+    scoped.shift_remove("where");
+    scoped.extend(scope.clone());
+    let mut finder = finder.clone();
+    finder.as_dictionary_mut().unwrap().insert("where".to_owned(), Value::Dictionary(scoped));
+    Some(finder)
+}
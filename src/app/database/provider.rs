@@ -0,0 +1,17 @@
+use std::future::Future;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use teo_result::Result;
+use teo_runtime::connection::connection::Connection;
+
+pub trait ConnectorProvider: Send + Sync {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<Arc<dyn Connection>>>;
+}
+
+impl<F, Fut> ConnectorProvider for F where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Arc<dyn Connection>>> + Send + 'static {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<Arc<dyn Connection>>> {
+        Box::pin(self(url))
+    }
+}
@@ -0,0 +1,230 @@
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use indexmap::IndexMap;
+use key_path::KeyPath;
+use maplit::btreemap;
+use teo_runtime::action::action::{FIND, MANY, NESTED};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+
+const EVERY: &str = "every";
+const COMBINATORS: [&str; 3] = ["AND", "OR", "NOT"];
+
+/// Rewrites the relation filters the connector of `provider` can't run, `None` when there are none.
+pub(crate) async fn resolve_relation_filters(transaction: &dyn Transaction, provider: Option<Database>, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: &KeyPath) -> path::Result<Option<Value>> {
+    let Some(provider) = provider else {
+        return Ok(None);
+    };
+    if !has_relation_filters(model, finder, &transaction_ctx) {
+        return Ok(None);
+    }
+    let resolver = Resolver { transaction, mongo: matches!(provider, Database::MongoDB), transaction_ctx, req_ctx, path };
+    let mut finder = finder.clone();
+    resolver.finder(model, &mut finder).await?;
+    Ok(Some(finder))
+}
+
+struct Resolver<'a> {
+    transaction: &'a dyn Transaction,
+    mongo: bool,
+    transaction_ctx: transaction::Ctx,
+    req_ctx: Option<request::Ctx>,
+    path: &'a KeyPath,
+}
+
+impl<'a> Resolver<'a> {
+
+    // the `where' of the finder and of the relations it includes
+    fn finder<'b>(&'b self, model: &'static Model, finder: &'b mut Value) -> BoxFuture<'b, path::Result<()>> {
+        async move {
+            let Some(map) = finder.as_dictionary_mut() else { return Ok(()) };
+            if let Some(r#where) = map.get_mut("where") {
+                self.r#where(model, r#where, false, false).await?;
+            }
+            for key in ["include", "select"] {
+                let Some(relations) = map.get_mut(key).and_then(|r| r.as_dictionary_mut()) else { continue };
+                for (name, value) in relations.iter_mut() {
+                    if let Some(related) = self.related_model(model, name) {
+                        self.finder(related, value).await?;
+                    }
+                }
+            }
+            Ok(())
+        }.boxed()
+    }
+
+    // `nested' when `where' belongs to a relation filter, `combined' when it's inside `AND', `OR'
+    // or `NOT'
+    fn r#where<'b>(&'b self, model: &'static Model, r#where: &'b mut Value, nested: bool, combined: bool) -> BoxFuture<'b, path::Result<()>> {
+        async move {
+            let Some(map) = r#where.as_dictionary_mut() else { return Ok(()) };
+            let mut resolved = vec![];
+            for key in map.keys().cloned().collect::<Vec<_>>() {
+                if COMBINATORS.contains(&key.as_str()) {
+                    match map.get_mut(&key).unwrap() {
+                        Value::Array(items) => for item in items.iter_mut() {
+                            self.r#where(model, item, nested, true).await?;
+                        },
+                        value => self.r#where(model, value, nested, true).await?,
+                    }
+                    continue;
+                }
+                let Some(related) = self.related_model(model, &key) else { continue };
+                let filters = map.get_mut(&key).unwrap();
+                let Some(operators) = filters.as_dictionary_mut() else { continue };
+                if nested || (self.mongo && (combined || operators.len() > 1)) {
+                    for (operator, inner) in operators.iter() {
+                        resolved.push(self.keys(model, &key, operator, inner).await?);
+                    }
+                    map.shift_remove(&key);
+                    continue;
+                }
+                for (operator, inner) in operators.iter_mut() {
+                    self.r#where(related, inner, true, false).await?;
+                    if operator == EVERY && !self.mongo {
+                        *inner = truthy(related, inner);
+                    }
+                }
+                if self.mongo {
+                    if let Some(inner) = operators.shift_remove(EVERY) {
+                        operators.insert("all".to_owned(), inner);
+                    }
+                }
+            }
+            if !resolved.is_empty() {
+                match map.get_mut("AND") {
+                    Some(Value::Array(items)) => items.extend(resolved),
+                    Some(value) => *value = Value::Array(resolved.into_iter().chain([value.clone()]).collect()),
+                    None => { map.insert("AND".to_owned(), Value::Array(resolved)); },
+                }
+            }
+            Ok(())
+        }.boxed()
+    }
+
+    // the filter on the primary keys of the records of `model' which match the relation filter
+    async fn keys(&self, model: &'static Model, relation: &str, operator: &str, inner: &Value) -> path::Result<Value> {
+        let keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+        let select: IndexMap<String, Value> = keys.iter().map(|k| (k.clone(), Value::Bool(true))).collect();
+        let mut r#where = IndexMap::new();
+        r#where.insert(relation.to_owned(), teon!({operator: inner.clone()}));
+        let max = Ctx::settings().limits.max_relation_filter_keys;
+        let finder = teon!({"where": Value::Dictionary(r#where), "select": Value::Dictionary(select), "take": max as i64 + 1});
+        let objects = self.transaction.find_many(model, &finder, false, FIND | MANY | NESTED, self.transaction_ctx.clone(), self.req_ctx.clone(), self.path.clone()).await?;
+        if objects.len() > max {
+            return Err(too_many_keys(model, relation, operator, max));
+        }
+        if objects.is_empty() {
+            // no record has a null primary key
+            return Ok(teon!({keys[0].as_str(): {"equals": Value::Null}}));
+        }
+        if keys.len() == 1 {
+            let values = objects.iter().map(|o| o.get_value(&keys[0])).collect::<teo_result::Result<Vec<_>>>()?;
+            return Ok(teon!({keys[0].as_str(): {"in": Value::Array(values)}}));
+        }
+        let mut items = vec![];
+        for object in &objects {
+            let mut item = IndexMap::new();
+            for key in &keys {
+                item.insert(key.clone(), object.get_value(key)?);
+            }
+            items.push(Value::Dictionary(item));
+        }
+        Ok(teon!({"OR": Value::Array(items)}))
+    }
+
+    fn related_model(&self, model: &Model, name: &str) -> Option<&'static Model> {
+        let relation = model.relation(name)?;
+        self.transaction_ctx.namespace().model_at_path(&relation.model_path())
+    }
+}
+
+fn too_many_keys(model: &Model, relation: &str, operator: &str, max: usize) -> path::Error {
+    path::Error {
+        title: "QueryTooComplex",
+        message: format!("relation filter `{}.{}' of {} matches more than {} records, narrow it", relation, operator, model.path.join("."), max),
+        fields: None,
+        code: 400,
+        meta_map: btreemap! {},
+    }
+}
+
+fn has_relation_filters(model: &Model, finder: &Value, transaction_ctx: &transaction::Ctx) -> bool {
+    let Some(map) = finder.as_dictionary() else { return false };
+    if map.get("where").is_some_and(|w| where_has_relation_filters(model, w)) {
+        return true;
+    }
+    ["include", "select"].iter().filter_map(|key| map.get(*key).and_then(|r| r.as_dictionary())).flatten().any(|(name, value)| {
+        let related = model.relation(name).and_then(|r| transaction_ctx.namespace().model_at_path(&r.model_path()));
+        related.is_some_and(|related| has_relation_filters(related, value, transaction_ctx))
+    })
+}
+
+fn where_has_relation_filters(model: &Model, r#where: &Value) -> bool {
+    let Some(map) = r#where.as_dictionary() else { return false };
+    map.iter().any(|(key, value)| match value {
+        _ if model.relation(key).is_some() => true,
+        Value::Array(items) if COMBINATORS.contains(&key.as_str()) => items.iter().any(|item| where_has_relation_filters(model, item)),
+        _ if COMBINATORS.contains(&key.as_str()) => where_has_relation_filters(model, value),
+        _ => false,
+    })
+}
+
+// `where' as a filter which is either true or false on SQL, the comparisons of absent values are
+// false instead of null
+fn truthy(model: &Model, r#where: &Value) -> Value {
+    settle(model, r#where, true)
+}
+
+// `r#where' if `truth', its negation otherwise, with the absent values compared settled
+fn settle(model: &Model, r#where: &Value, truth: bool) -> Value {
+    let Some(map) = r#where.as_dictionary() else {
+        return r#where.clone();
+    };
+    let mut items = vec![];
+    for (key, value) in map {
+        let item = match key.as_str() {
+            "AND" | "OR" => {
+                let values = match value {
+                    Value::Array(values) => values.iter().map(|v| settle(model, v, truth)).collect(),
+                    value => vec![settle(model, value, truth)],
+                };
+                // the negation of a conjunction is the disjunction of the negations
+                let combinator = if (key == "AND") == truth { "AND" } else { "OR" };
+                teon!({combinator: Value::Array(values)})
+            }
+            "NOT" => settle(model, value, !truth),
+            _ => {
+                let entry = teon!({key.as_str(): value.clone()});
+                let entry = if truth { entry } else { teon!({"NOT": entry}) };
+                match model.field(key) {
+                    Some(field) if field.optionality.is_any_optional() && !compares_null(value) => {
+                        teon!({"AND": [entry, {key.as_str(): {"not": Value::Null}}]})
+                    }
+                    _ => entry,
+                }
+            }
+        };
+        items.push(item);
+    }
+    match items.len() {
+        1 => items.pop().unwrap(),
+        _ if truth => teon!({"AND": Value::Array(items)}),
+        _ => teon!({"OR": Value::Array(items)}),
+    }
+}
+
+// whether a field filter decides on absent values itself
+fn compares_null(filter: &Value) -> bool {
+    match filter {
+        Value::Null => true,
+        Value::Dictionary(map) => map.values().any(|v| v.is_null() || v.as_array().is_some_and(|a| a.iter().any(Value::is_null))),
+        _ => false,
+    }
+}
@@ -0,0 +1,110 @@
+use key_path::KeyPath;
+use teo_runtime::model::{Index, Model};
+use teo_runtime::path;
+
+const MESSAGE_PREFIX: &str = "unique value duplicated: ";
+
+// what a duplicate key error names, the dialects name either the columns or the index
+#[derive(Debug, PartialEq, Eq)]
+enum Violation {
+    Columns(Vec<String>),
+    Index(String),
+}
+
+/// Maps the duplicate key errors of the connectors to value errors on the unique fields.
+pub(crate) fn map_unique_violation(model: &Model, error: path::Error, path: &KeyPath) -> path::Error {
+    let text = error.fields.iter().flatten().map(|(_, message)| message.as_str()).chain([error.message.as_str()]).collect::<Vec<_>>().join("\n");
+    let Some(fields) = violated_fields(model, &text) else {
+        return error;
+    };
+    let message = format!("{}{}", MESSAGE_PREFIX, fields.join(", "));
+    let mut mapped = path::Error::value_error(path + fields[0].as_str(), message.clone());
+    if let Some(entries) = mapped.fields.as_mut() {
+        for field in &fields[1..] {
+            entries.insert((path + field.as_str()).to_string(), message.clone());
+        }
+    }
+    mapped
+}
+
+/// The fields of `model` a duplicate key error is about, `None` if `message` isn't one.
+pub fn violated_fields(model: &Model, message: &str) -> Option<Vec<String>> {
+    let violation = violation(message)?;
+    let index = model.indexes.values().filter(|i| i.r#type().is_unique() || i.r#type().is_primary()).find(|index| match &violation {
+        Violation::Index(name) => index_names(model, index).iter().any(|n| n == name),
+        Violation::Columns(columns) => columns.len() == index.keys().len() && columns.iter().all(|c| index.keys().iter().any(|k| column(model, k) == c.as_str())),
+    });
+    match (index, violation) {
+        (Some(index), _) => Some(index.keys().clone()),
+        (None, Violation::Columns(columns)) => Some(columns.iter().map(|c| field(model, c)).collect()),
+        (None, Violation::Index(name)) => Some(vec![name]),
+    }
+}
+
+fn violation(message: &str) -> Option<Violation> {
+    // SQLite 2067
+    if let Some(rest) = after(message, "UNIQUE constraint failed: ") {
+        return Some(Violation::Columns(until(rest, &['"', '\n']).split(", ").map(|c| c.rsplit('.').next().unwrap().to_owned()).collect()));
+    }
+    // PostgreSQL 23505
+    if let Some(rest) = after(message, "violates unique constraint ") {
+        return Some(Violation::Index(until(rest.trim_start_matches(['"', '\\']), &['"', '\\']).to_owned()));
+    }
+    // MySQL 1062, 8.0 prefixes the index with its table
+    if message.contains("Duplicate entry") {
+        let rest = after(message, "for key '")?;
+        return Some(Violation::Index(until(rest, &['\'']).rsplit('.').next().unwrap().to_owned()));
+    }
+    // MongoDB E11000
+    if message.contains("E11000") {
+        let rest = after(message, "index: ")?;
+        return Some(Violation::Index(until(rest, &[' ']).to_owned()));
+    }
+    // the connectors' own mapping names the columns, or the index it couldn't resolve
+    if let Some(rest) = after(message, MESSAGE_PREFIX) {
+        let names = until(rest, &['\n']);
+        return Some(if names.contains(", ") {
+            Violation::Columns(names.split(", ").map(ToOwned::to_owned).collect())
+        } else {
+            Violation::Index(names.to_owned())
+        });
+    }
+    if message.contains("UniqueConstraintViolation") {
+        if let Some(rest) = after(message, "Fields([") {
+            return Some(Violation::Columns(until(rest, &[']']).split(", ").map(|c| c.trim_matches(['"', '\\']).to_owned()).collect()));
+        }
+        let rest = after(message, "Index(\"")?;
+        return Some(Violation::Index(until(rest, &['"', '\\']).to_owned()));
+    }
+    None
+}
+
+// the names an index has on the databases, the connectors name unnamed ones after the table
+fn index_names(model: &Model, index: &Index) -> Vec<String> {
+    let table = &model.table_name;
+    let joined = index.keys().join("_");
+    let mut names = vec![index.name().to_owned(), format!("{}_{}", table, joined), format!("{}_{}_idx", table, joined)];
+    if index.r#type().is_primary() {
+        names.extend(["PRIMARY".to_owned(), "_id_".to_owned(), format!("{}_pkey", table), format!("sqlite_autoindex_{}_1", table)]);
+    }
+    if index.keys().len() == 1 {
+        names.extend([column(model, &index.keys()[0]).to_owned(), format!("{}_1", column(model, &index.keys()[0]))]);
+    }
+    names
+}
+
+fn column<'a>(model: &'a Model, field: &'a str) -> &'a str {
+    model.field(field).map_or(field, |f| f.column_name.as_str())
+}
+
+fn field(model: &Model, column: &str) -> String {
+    model.fields.values().find(|f| f.column_name == column).map_or(column, |f| f.name.as_str()).to_owned()
+}
+
+fn after<'a>(message: &'a str, pattern: &str) -> Option<&'a str> {
+    message.find(pattern).map(|i| &message[i + pattern.len()..])
+}
+
+fn until<'a>(message: &'a str, ends: &[char]) -> &'a str {
+    message.split(ends).next().unwrap_or(message)
+}
@@ -1,7 +1,11 @@
+#[allow(clippy::module_inception)]
 pub mod app;
 pub mod ctx;
 pub mod callbacks;
 pub mod database;
+pub mod plugin;
+pub(crate) mod settings;
 
 pub use app::App;
 pub use ctx::Ctx;
+pub use plugin::Plugin;
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use teo_result::Result;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request::Request;
+
+#[async_trait]
+pub trait Plugin: Send + Sync {
+
+    fn name(&self) -> &str;
+
+    fn on_schema_load(&self, _namespace: &mut Namespace) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_namespace_loaded(&self, _namespace: &'static Namespace) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_server_start(&self, _namespace: &'static Namespace) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_request(&self, _request: &Request) -> teo_runtime::path::Result<()> {
+        Ok(())
+    }
+
+    async fn on_shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+}
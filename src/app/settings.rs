@@ -0,0 +1,73 @@
+use educe::Educe;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use ring::hmac;
+use crate::app::database::provider::ConnectorProvider;
+use crate::app::plugin::Plugin;
+use crate::backfill::Backfill;
+use crate::event_sink::RegisteredSink;
+use crate::isolation::TransactionOptions;
+use crate::scope::ScopeFilter;
+use crate::server::admin::AdminGuard;
+use crate::server::coerce::Coercion;
+use crate::server::embedded::EmbeddedMount;
+use crate::server::idempotency::IdempotencyStore;
+use crate::server::limits::Limits;
+use crate::server::record::Recorder;
+use crate::server::report::ErrorReporter;
+use crate::server::stats::StatsRegistry;
+use crate::server::tls::TlsOptions;
+use crate::telemetry::Exporter;
+
+/// The features configured on the app, read with `Ctx::settings` and set with
+/// `Ctx::update_settings`.
+#[derive(Educe, Clone, Default)]
+#[educe(Debug)]
+pub(crate) struct Settings {
+    #[educe(Debug(ignore))]
+    pub(crate) plugins: Vec<Arc<dyn Plugin>>,
+    #[educe(Debug(ignore))]
+    pub(crate) connector_providers: BTreeMap<String, Arc<dyn ConnectorProvider>>,
+    pub(crate) sources: BTreeMap<String, String>,
+    #[educe(Debug(ignore))]
+    pub(crate) admin_guard: Option<Arc<dyn AdminGuard>>,
+    pub(crate) limits: Limits,
+    pub(crate) action_limits: BTreeMap<String, Limits>,
+    pub(crate) action_transactions: BTreeMap<String, TransactionOptions>,
+    #[educe(Debug(ignore))]
+    pub(crate) idempotency: Option<Arc<IdempotencyStore>>,
+    #[educe(Debug(ignore))]
+    pub(crate) stats: Option<Arc<StatsRegistry>>,
+    #[educe(Debug(ignore))]
+    pub(crate) share_key: Option<hmac::Key>,
+    pub(crate) mock_latency: Option<(Duration, Duration)>,
+    pub(crate) slow_query_threshold: Option<Duration>,
+    pub(crate) recorder: Option<Arc<Recorder>>,
+    pub(crate) compression_min_size: Option<usize>,
+    pub(crate) coercion: Coercion,
+    pub(crate) embedded_files: Vec<EmbeddedMount>,
+    #[educe(Debug(ignore))]
+    pub(crate) scopes: BTreeMap<(String, String), Arc<dyn ScopeFilter>>,
+    #[educe(Debug(ignore))]
+    pub(crate) error_reporters: Vec<Arc<dyn ErrorReporter>>,
+    #[educe(Debug(ignore))]
+    pub(crate) event_sinks: Vec<Arc<RegisteredSink>>,
+    pub(crate) telemetry: Option<Arc<Exporter>>,
+    pub(crate) tls: Option<TlsOptions>,
+    pub(crate) schema_version: Option<String>,
+    #[educe(Debug(ignore))]
+    pub(crate) backfills: BTreeMap<String, Backfill>,
+    pub(crate) rust_pipeline_items: Vec<String>,
+}
+
+impl Settings {
+
+    pub(crate) fn action_transaction(&self, action: &str) -> TransactionOptions {
+        self.action_transactions.get(action).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn scope(&self, model: &str, name: &str) -> Option<Arc<dyn ScopeFilter>> {
+        self.scopes.get(&(model.to_owned(), name.to_owned())).cloned()
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::Duration;
+use chrono::Utc;
+use key_path::path;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::action::action::{CODE_NAME, CODE_POSITION, CREATE, SINGLE};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Field, Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::namespace::Namespace;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::message::info_message;
+use crate::migrate::expand::retained_for;
+
+const DATA_KEY: &str = "archive";
+const PAGE_SIZE: usize = 500;
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub model: String,
+    pub days: i64,
+    pub field: String,
+    pub tombstone: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ArchiveReport {
+    pub model: String,
+    pub archived: usize,
+}
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("archive", |args: Arguments, model: &mut Model| {
+        let archive: String = args.get("model")?;
+        let days: i32 = args.get("days")?;
+        if days <= 0 {
+            Err(Error::new("archive days should be positive"))?
+        }
+        let field: String = args.get("field")?;
+        let tombstone: Option<String> = args.get_optional("tombstone")?;
+        model.data.insert(DATA_KEY.to_owned(), teon!({
+            "model": archive,
+            "days": days as i64,
+            "field": field,
+            "tombstone": tombstone.map_or(Value::Null, Value::String),
+        }).into());
+        Ok(())
+    });
+}
+
+pub fn archive(model: &Model) -> Option<Archive> {
+    let value = model.data.get(DATA_KEY).and_then(|o| o.as_teon())?;
+    Some(Archive {
+        model: value.get("model")?.as_str()?.to_owned(),
+        days: value.get("days")?.to_int64()?,
+        field: value.get("field")?.as_str()?.to_owned(),
+        tombstone: value.get("tombstone").and_then(|s| s.as_str()).map(ToOwned::to_owned),
+    })
+}
+
+/// The model the records of `model` are archived into, usually stored in a cheaper database with
+/// `@source`. It has the scalar fields of `model`, and the same primary key without
+/// `@autoIncrement`, so the archived records keep their identifiers.
+pub fn archive_model(namespace: &'static Namespace, model: &Model) -> Result<Option<&'static Model>> {
+    let Some(archive) = archive(model) else { return Ok(None) };
+    let Some(archive_model) = namespace.model_at_path(&archive.model.split('.').collect()) else {
+        Err(Error::new(format!("archive model `{}' of {} is not found", archive.model, model.path.join("."))))?
+    };
+    for field in archived_fields(model) {
+        if archive_model.field(&field.name).is_none_or(|f| f.r#virtual) {
+            Err(Error::new(format!("archive model {} has no field `{}' of {}", archive.model, field.name, model.path.join("."))))?
+        }
+    }
+    Ok(Some(archive_model))
+}
+
+/// Moves the records whose timestamp field is older than the archive window into the archive
+/// model. A moved record is deleted, or with `tombstone` kept as a tombstone: the optional
+/// datetime field is set to now and its other optional fields are cleared, except the keys and
+/// the timestamp field. A record found in the archive already is not copied again, so a run
+/// which stopped halfway is finished by the next one. Nothing is changed with `dry_run`, the
+/// records due are only counted.
+pub async fn enforce_archive(ctx: transaction::Ctx, dry_run: bool) -> Result<Vec<ArchiveReport>> {
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    let mut reports = vec![];
+    for (model, archive) in models {
+        let Some(archive_model) = archive_model(ctx.namespace(), model)? else { continue };
+        let r#where = due_filter(model, &archive)?;
+        let archived = if dry_run {
+            ctx.count(model, &teon!({"where": r#where}), path![]).await?
+        } else {
+            move_records(&ctx, model, archive_model, &archive, r#where).await?
+        };
+        reports.push(ArchiveReport { model: model.path.join("."), archived });
+    }
+    Ok(reports)
+}
+
+pub(crate) fn report_archive(reports: &Vec<ArchiveReport>, dry_run: bool) {
+    for report in reports {
+        let verb = if dry_run { "due" } else { "archived" };
+        info_message(format!("{}: {} record(s) {}", report.model, report.archived, verb));
+    }
+}
+
+/// Archives the due records in the background, once at start and then every hour.
+pub(crate) fn schedule_archive(ctx: transaction::Ctx, silent: bool) {
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    if models.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ARCHIVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match enforce_archive(ctx.clone(), false).await {
+                Ok(reports) => if !silent {
+                    report_archive(&reports.into_iter().filter(|r| r.archived > 0).collect(), false);
+                },
+                Err(error) => info_message(format!("archive failed: {}", error.message())),
+            }
+        }
+    });
+}
+
+fn collect_models(namespace: &'static Namespace, models: &mut Vec<(&'static Model, Archive)>) {
+    for model in namespace.models.values() {
+        if let Some(archive) = archive(model) {
+            models.push((model, archive));
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_models(child, models);
+    }
+}
+
+fn archived_fields(model: &Model) -> impl Iterator<Item = &Field> {
+    model.fields.values().filter(|f| !f.r#virtual && !f.dropped && retained_for(f).is_none())
+}
+
+fn due_filter(model: &Model, archive: &Archive) -> Result<Value> {
+    let Some(field) = model.field(&archive.field) else {
+        Err(Error::new(format!("archive field `{}' is not found on {}", archive.field, model.path.join("."))))?
+    };
+    let cutoff = Utc::now() - chrono::Duration::days(archive.days);
+    let cutoff = match field.r#type.unwrap_optional() {
+        Type::DateTime => Value::DateTime(cutoff),
+        Type::Date => Value::Date(cutoff.date_naive()),
+        _ => Err(Error::new(format!("archive field `{}' of {} should be a date or datetime", archive.field, model.path.join("."))))?,
+    };
+    let mut r#where = teon!({ archive.field.as_str(): {"lt": cutoff} });
+    if let Some(tombstone) = &archive.tombstone {
+        if !model.field(tombstone).is_some_and(|f| f.is_optional() && f.r#type.unwrap_optional().is_datetime()) {
+            Err(Error::new(format!("tombstone field `{}' of {} should be an optional datetime", tombstone, model.path.join("."))))?
+        }
+        r#where.as_dictionary_mut().unwrap().insert(tombstone.clone(), teon!({"equals": null}));
+    }
+    Ok(r#where)
+}
+
+// moved records leave the result set, so the first page is loaded again
+async fn move_records(ctx: &transaction::Ctx, model: &'static Model, archive_model: &'static Model, archive: &Archive, r#where: Value) -> Result<usize> {
+    let keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    let mut archived = 0;
+    loop {
+        let finder = teon!({"where": r#where.clone(), "take": PAGE_SIZE as i64});
+        let objects: Vec<Object> = ctx.find_many(model, &finder, None, path![]).await?;
+        for object in &objects {
+            let mut identifier = teon!({});
+            for key in &keys {
+                identifier.as_dictionary_mut().unwrap().insert(key.clone(), object.get_value(key)?);
+            }
+            // the connectors of the two models don't share a transaction
+            let copied: Option<Object> = ctx.find_unique(archive_model, &teon!({"where": identifier}), None, path![]).await?;
+            if copied.is_none() {
+                let copy = ctx.new_object(archive_model, CODE_NAME | CREATE | SINGLE | CODE_POSITION, None)?;
+                for field in archived_fields(model) {
+                    copy.set_value(&field.name, object.get_value(&field.name)?)?;
+                }
+                copy.save().await?;
+            }
+            match &archive.tombstone {
+                Some(tombstone) => {
+                    for field in archived_fields(model) {
+                        if field.is_optional() && !field.foreign_key && !keys.contains(&field.name) && field.name != archive.field && &field.name != tombstone {
+                            object.set_value(&field.name, Value::Null)?;
+                        }
+                    }
+                    object.set_value(tombstone, Value::DateTime(Utc::now()))?;
+                    object.save().await?;
+                }
+                None => object.delete().await?,
+            }
+        }
+        archived += objects.len();
+        if objects.len() < PAGE_SIZE {
+            break
+        }
+    }
+    Ok(archived)
+}
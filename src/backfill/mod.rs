@@ -0,0 +1,221 @@
+use std::future::Future;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use key_path::path;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::object::batch::{BatchOptions, Batches};
+use crate::message::info_message;
+
+const DATA_KEY: &str = "backfillProgress";
+
+/// Changes a record of a backfill before it's saved. A batch is transformed again when a run
+/// stops before its progress is stored, so a transform should be idempotent.
+pub trait BackfillTransform: Send + Sync {
+    fn call(&self, object: Object) -> BoxFuture<'static, Result<()>>;
+}
+
+impl<F, Fut> BackfillTransform for F where
+    F: Fn(Object) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send + 'static {
+    fn call(&self, object: Object) -> BoxFuture<'static, Result<()>> {
+        Box::pin(self(object))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Backfill {
+    pub(crate) model: String,
+    pub(crate) transform: Arc<dyn BackfillTransform>,
+}
+
+#[derive(Debug)]
+pub struct BackfillProgress {
+    pub name: String,
+    pub processed: i64,
+    pub finished: bool,
+}
+
+/// Runs the backfill from where it stopped, passing the records to the transform in primary key
+/// order and saving them. The progress is stored in the model marked with `@backfillProgress`
+/// after each batch, in the batch's transaction.
+///
+/// A batch is only repeated when the progress model is in another database than the backfilled
+/// model and a run stops between the two commits. Transforms should be idempotent.
+pub async fn run_backfill(ctx: transaction::Ctx, name: &str, batch: usize, silent: bool) -> Result<BackfillProgress> {
+    let Some(backfill) = Ctx::settings().backfills.get(name).cloned() else {
+        Err(Error::new(format!("backfill `{}' is not defined", name)))?
+    };
+    let model = backfill_model(&ctx, &backfill)?;
+    let key = match model.primary_index().map(|i| i.keys()) {
+        Some(keys) if keys.len() == 1 => keys[0].clone(),
+        _ => Err(Error::new(format!("backfill `{}' requires a single field primary key on {}", name, backfill.model)))?,
+    };
+    let progress_model = progress_model(&ctx)?;
+    let mut progress = load_progress(&ctx, progress_model, name).await?;
+    while !progress.finished {
+        let last_key = progress.last_key.as_ref().map(|k| parse_key(model, &key, k)).transpose()?;
+        progress = ctx.run_transaction(|ctx: transaction::Ctx| {
+            let backfill = &backfill;
+            let last_key = &last_key;
+            let progress = &progress;
+            async move {
+                let options = BatchOptions { size: batch, ..Default::default() };
+                let mut batches = Batches::new(&ctx, model, &teon!({}), options)?;
+                if let Some(last_key) = last_key {
+                    batches.resume_after(vec![last_key.clone()])?;
+                }
+                let objects = batches.next().await?.unwrap_or_default();
+                for object in &objects {
+                    backfill.transform.call(object.clone()).await?;
+                    object.save().await?;
+                }
+                let progress = StoredProgress {
+                    last_key: match batches.last() {
+                        Some(last) => Some(format_key(&last[0])?),
+                        None => progress.last_key.clone(),
+                    },
+                    processed: progress.processed + objects.len() as i64,
+                    finished: objects.len() < batch,
+                };
+                save_progress(&ctx, progress_model, name, &progress).await?;
+                Ok(progress)
+            }
+        }).await?;
+        if !silent {
+            info_message(format!("{}: {} record(s) processed", name, progress.processed));
+        }
+    }
+    Ok(BackfillProgress { name: name.to_owned(), processed: progress.processed, finished: progress.finished })
+}
+
+pub async fn backfill_progress(ctx: transaction::Ctx) -> Result<Vec<BackfillProgress>> {
+    let mut result = vec![];
+    let backfills = Ctx::settings().backfills.clone();
+    if backfills.is_empty() {
+        return Ok(result);
+    }
+    let progress_model = progress_model(&ctx)?;
+    for name in backfills.keys() {
+        let progress = load_progress(&ctx, progress_model, name).await?;
+        result.push(BackfillProgress { name: name.clone(), processed: progress.processed, finished: progress.finished });
+    }
+    Ok(result)
+}
+
+pub(crate) fn report_backfills(progresses: &Vec<BackfillProgress>) {
+    if progresses.is_empty() {
+        info_message("no backfills are defined");
+    }
+    for progress in progresses {
+        let state = match (progress.finished, progress.processed) {
+            (true, _) => "finished",
+            (false, 0) => "pending",
+            (false, _) => "in progress",
+        };
+        info_message(format!("{}: {}, {} record(s) processed", progress.name, state, progress.processed));
+    }
+}
+
+struct StoredProgress {
+    last_key: Option<String>,
+    processed: i64,
+    finished: bool,
+}
+
+fn backfill_model(ctx: &transaction::Ctx, backfill: &Backfill) -> Result<&'static Model> {
+    match ctx.namespace().model_at_path(&backfill.model.split(".").collect()) {
+        Some(model) => Ok(model),
+        None => Err(Error::new(format!("model `{}' is not found", backfill.model))),
+    }
+}
+
+/// Loads `@backfillProgress`, which marks the model storing the progress of the backfills.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("backfillProgress", |_args: Arguments, model: &mut Model| {
+        model.data.insert(DATA_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+fn progress_model(ctx: &transaction::Ctx) -> Result<&'static Model> {
+    let mut models = vec![];
+    collect_progress_models(ctx.namespace(), &mut models);
+    let model = match models.as_slice() {
+        [model] => *model,
+        [] => Err(Error::new("backfills store their progress in a model marked with @backfillProgress, declare one"))?,
+        _ => Err(Error::new("more than one model is marked with @backfillProgress"))?,
+    };
+    for (field, r#type) in [("name", Type::String), ("lastKey", Type::String), ("processed", Type::Int64), ("finished", Type::Bool)] {
+        if model.field(field).map(|f| f.r#type.unwrap_optional()) != Some(&r#type) {
+            Err(Error::new(format!("backfill progress model {} requires a field `{}' of {}", model.path.join("."), field, r#type)))?
+        }
+    }
+    Ok(model)
+}
+
+fn collect_progress_models(namespace: &'static Namespace, models: &mut Vec<&'static Model>) {
+    for model in namespace.models.values() {
+        if model.data.contains_key(DATA_KEY) {
+            models.push(model);
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_progress_models(child, models);
+    }
+}
+
+async fn load_progress(ctx: &transaction::Ctx, progress_model: &'static Model, name: &str) -> Result<StoredProgress> {
+    let Some(object) = ctx.find_unique::<Object>(progress_model, &teon!({"where": {"name": name}}), None, path![]).await? else {
+        return Ok(StoredProgress { last_key: None, processed: 0, finished: false });
+    };
+    Ok(StoredProgress {
+        last_key: object.get_value("lastKey")?.as_str().map(|k| k.to_owned()),
+        processed: object.get_value("processed")?.to_int64().unwrap_or(0),
+        finished: object.get_value("finished")?.as_bool().unwrap_or(false),
+    })
+}
+
+async fn save_progress(ctx: &transaction::Ctx, progress_model: &'static Model, name: &str, progress: &StoredProgress) -> Result<()> {
+    let values = teon!({
+        "lastKey": match &progress.last_key {
+            Some(last_key) => Value::String(last_key.clone()),
+            None => Value::Null,
+        },
+        "processed": Value::Int64(progress.processed),
+        "finished": progress.finished,
+    });
+    let object = match ctx.find_unique::<Object>(progress_model, &teon!({"where": {"name": name}}), None, path![]).await? {
+        Some(object) => object,
+        None => ctx.create_object(progress_model, &teon!({"name": name}), None).await?,
+    };
+    object.set_teon(&values).await?;
+    object.save().await
+}
+
+fn format_key(value: &Value) -> Result<String> {
+    match value {
+        Value::Int(int) => Ok(int.to_string()),
+        Value::Int64(int) => Ok(int.to_string()),
+        Value::String(string) => Ok(string.clone()),
+        _ => Err(Error::new("backfills require an integer or string primary key")),
+    }
+}
+
+fn parse_key(model: &Model, key: &str, stored: &str) -> Result<Value> {
+    let r#type = model.field(key).map(|f| f.r#type.unwrap_optional());
+    let value = match r#type {
+        Some(Type::Int) => stored.parse::<i32>().ok().map(Value::Int),
+        Some(Type::Int64) => stored.parse::<i64>().ok().map(Value::Int64),
+        Some(Type::String) => Some(Value::String(stored.to_owned())),
+        _ => None,
+    };
+    value.ok_or_else(|| Error::new(format!("invalid backfill progress `{}' of {}", stored, model.path.join("."))))
+}
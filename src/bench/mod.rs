@@ -0,0 +1,35 @@
+use serde_json::Value as JsonValue;
+use teo_runtime::model::Model;
+use teo_teon::value::Value;
+use crate::app::database::clickhouse;
+use crate::server::{filters, msgpack, plan};
+use crate::server::responder::JsonBody;
+use crate::server::error::BoxedResult;
+
+// the builders the benchmarks in `benches' measure, they aren't an API and change with the server
+
+/// Rewrites the filter shorthands of `json_body` as every request is before it reaches the
+/// connector.
+pub fn normalize_filters(model: &Model, json_body: &mut JsonValue) -> BoxedResult<()> {
+    filters::normalize_filters(model, json_body)
+}
+
+/// The condition of a ClickHouse statement for `r#where`.
+pub fn clickhouse_conditions(model: &Model, r#where: &Value) -> BoxedResult<String> {
+    clickhouse::conditions(model, r#where)
+}
+
+/// Orders the nested writes of `json_body` for `action`.
+pub fn plan_nested_writes(model: &Model, action: &str, json_body: &mut JsonValue) {
+    plan::plan_nested_writes(model, action, json_body)
+}
+
+/// The JSON body of a response with `value`.
+pub fn json_body(value: &Value) -> Vec<u8> {
+    serde_json::to_vec(&JsonBody(value)).unwrap()
+}
+
+/// The MessagePack body of a response with `value`.
+pub fn msgpack_body(value: &Value) -> Result<Vec<u8>, String> {
+    msgpack::to_msgpack(value)
+}
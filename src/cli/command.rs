@@ -1,8 +1,18 @@
+use std::time::Duration;
+use crate::server::tls::TlsOptions;
+
 #[derive(Debug)]
 pub(crate) struct ServeCommand {
     pub(crate) no_migration: bool,
+    #[allow(dead_code)]
     pub(crate) no_autoseed: bool,
+    #[allow(dead_code)]
     pub(crate) env: Option<String>,
+    pub(crate) record: Option<String>,
+    pub(crate) record_window: Option<Duration>,
+    pub(crate) record_credentials: bool,
+    pub(crate) strict_schema: bool,
+    pub(crate) tls: Option<TlsOptions>,
 }
 
 #[derive(Debug)]
@@ -15,12 +25,46 @@ pub(crate) enum GenerateCommand {
 pub(crate) struct GenerateClientCommand {
     pub(crate) all: bool,
     pub(crate) names: Option<Vec<String>>,
+    pub(crate) watch: bool,
+    pub(crate) diff: bool,
+    pub(crate) clean: bool,
 }
 
 #[derive(Debug)]
 pub(crate) struct GenerateEntityCommand {
     pub(crate) all: bool,
     pub(crate) names: Option<Vec<String>>,
+    pub(crate) watch: bool,
+    pub(crate) diff: bool,
+    pub(crate) clean: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct MockCommand {
+    pub(crate) records: usize,
+    pub(crate) latency: Option<(Duration, Duration)>,
+    pub(crate) seed: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct RetentionCommand {
+    pub(crate) dry: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct ArchiveCommand {
+    pub(crate) dry: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct BackfillCommand {
+    pub(crate) name: Option<String>,
+    pub(crate) batch: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct ReplayCommand {
+    pub(crate) file: String,
 }
 
 #[derive(Debug)]
@@ -33,6 +77,8 @@ pub(crate) struct SeedCommand {
     pub(crate) action: SeedCommandAction,
     pub(crate) all: bool,
     pub(crate) names: Option<Vec<String>>,
+    pub(crate) fake: Option<Vec<(String, usize)>>,
+    pub(crate) fake_seed: u64,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -45,6 +91,23 @@ pub(crate) enum SeedCommandAction {
 #[derive(Debug)]
 pub(crate) struct PurgeCommand { }
 
+#[derive(Debug)]
+pub(crate) enum DbCommand {
+    Doctor(DoctorCommand),
+}
+
+#[derive(Debug)]
+pub(crate) struct DoctorCommand {
+    pub(crate) fix: bool,
+    pub(crate) delete_orphans: bool,
+    pub(crate) yes: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct AnonymizeCommand {
+    pub(crate) seed: u64,
+}
+
 #[derive(Debug)]
 pub(crate) struct LintCommand { }
 
@@ -54,36 +117,34 @@ pub(crate) struct RunCommand {
 }
 
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct CLI {
     pub(crate) command: CLICommand,
     pub(crate) schema: Option<String>,
     pub(crate) silent: bool,
 }
 
-impl CLI {
-    pub(crate) fn main(&self) -> Option<&str> {
-        self.schema.as_ref().map(|s| s.as_str())
-    }
-}
-
 #[derive(Debug)]
 pub(crate) enum CLICommand {
     Serve(ServeCommand),
     Generate(GenerateCommand),
+    Mock(MockCommand),
     Migrate(MigrateCommand),
     Seed(SeedCommand),
     Purge(PurgeCommand),
+    Db(DbCommand),
+    Anonymize(AnonymizeCommand),
     Lint(LintCommand),
     Run(RunCommand),
+    Replay(ReplayCommand),
+    Retention(RetentionCommand),
+    Backfill(BackfillCommand),
+    Archive(ArchiveCommand),
 }
 
 impl CLICommand {
 
     pub(crate) fn ignores_loading(&self) -> bool {
-        match self {
-            CLICommand::Generate(_) => true,
-            CLICommand::Lint(_) => true,
-            _ => false,
-        }
+        matches!(self, CLICommand::Generate(_) | CLICommand::Lint(_))
     }
 }
\ No newline at end of file
@@ -6,7 +6,7 @@ pub enum Entrance {
 
 impl Entrance {
 
-    pub(crate) fn to_str(&self) -> &'static str {
+    pub(crate) fn to_str(self) -> &'static str {
         match self {
             Entrance::APP => "APP",
             Entrance::CLI => "CLI",
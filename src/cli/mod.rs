@@ -2,4 +2,5 @@ pub(crate) mod parse;
 pub mod entrance;
 pub mod runtime_version;
 pub(crate) mod command;
+pub(crate) mod watch;
 pub mod run;
\ No newline at end of file
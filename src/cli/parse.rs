@@ -1,22 +1,25 @@
 use std::env;
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
 use clap::{Arg, ArgAction, Command as ClapCommand};
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
-use crate::cli::command::{CLI, CLICommand, GenerateClientCommand, GenerateCommand, GenerateEntityCommand, LintCommand, MigrateCommand, PurgeCommand, RunCommand, SeedCommand, SeedCommandAction, ServeCommand};
+use crate::server::tls::TlsOptions;
+use crate::cli::command::{AnonymizeCommand, ArchiveCommand, BackfillCommand, CLI, CLICommand, DbCommand, DoctorCommand, GenerateClientCommand, GenerateCommand, GenerateEntityCommand, LintCommand, MigrateCommand, MockCommand, PurgeCommand, ReplayCommand, RetentionCommand, RunCommand, SeedCommand, SeedCommandAction, ServeCommand};
 
 pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI {
-    let version = Box::leak(Box::new(format!("Teo {} ({}) [{}]", env!("CARGO_PKG_VERSION"), runtime_version.to_string(), entrance.to_str())));
-    let about = Box::leak(Box::new(match entrance {
+    let version = format!("Teo {} ({}) [{}]", env!("CARGO_PKG_VERSION"), runtime_version, entrance.to_str());
+    let about = match entrance {
         Entrance::CLI => format!("{version}\n\nRun Teo application with CLI."),
         Entrance::APP => format!("{version}\n\nRun Teo application with user app loaded."),
-    }));
+    };
     let matches = ClapCommand::new("teo")
-        .version(version.as_str())
+        .version(version)
         .disable_version_flag(true)
         .disable_help_subcommand(true)
         .arg_required_else_help(true)
-        .about(about.as_str())
+        .about(about)
         .subcommand_required(true)
         .arg(Arg::new("SCHEMA_FILE")
             .short('s')
@@ -55,7 +58,44 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
                 .short('S')
                 .long("no-autoseed")
                 .help("Start old_server without auto seeding autoseed dataset")
-                .action(ArgAction::SetTrue)))
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("record")
+                .long("record")
+                .help("Append requests and responses to a file for `teo replay'")
+                .action(ArgAction::Set)
+                .num_args(1))
+            .arg(Arg::new("record-window")
+                .long("record-window")
+                .help("Stop recording after this many seconds")
+                .value_parser(clap::value_parser!(u64))
+                .requires("record"))
+            .arg(Arg::new("record-credentials")
+                .long("record-credentials")
+                .help("Record credential headers instead of redacting them")
+                .action(ArgAction::SetTrue)
+                .requires("record"))
+            .arg(Arg::new("strict-schema")
+                .long("strict-schema")
+                .help("Refuse to start when the database doesn't match the schema")
+                .action(ArgAction::SetTrue)
+                .requires("no-migration"))
+            .arg(Arg::new("tls-cert")
+                .long("tls-cert")
+                .help("Serve HTTPS with the PEM certificate chain in this file")
+                .action(ArgAction::Set)
+                .num_args(1)
+                .requires("tls-key"))
+            .arg(Arg::new("tls-key")
+                .long("tls-key")
+                .help("The PEM private key of the certificate")
+                .action(ArgAction::Set)
+                .num_args(1)
+                .requires("tls-cert"))
+            .arg(Arg::new("tls-self-signed")
+                .long("tls-self-signed")
+                .help("Serve HTTPS with a self-signed certificate of localhost, for development")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("tls-cert")))
         .subcommand(ClapCommand::new("generate")
             .about("Generate code")
             .arg_required_else_help(true)
@@ -71,7 +111,21 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
                     .action(ArgAction::Append)
                     .conflicts_with("all")
                     .help("Client names to generate")
-                    .num_args(1..)))
+                    .num_args(1..))
+                .arg(Arg::new("watch")
+                    .short('w')
+                    .long("watch")
+                    .help("Regenerate changed files when the schema changes")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("diff")
+                    .short('d')
+                    .long("diff")
+                    .help("Print the files which would change without writing them")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("clean")
+                    .long("clean")
+                    .help("Remove previously generated files which are no longer generated")
+                    .action(ArgAction::SetTrue)))
             .subcommand(ClapCommand::new("entity")
                 .about("Generate model entities")
                 .arg_required_else_help(false)
@@ -85,7 +139,36 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
                     .action(ArgAction::Append)
                     .conflicts_with("all")
                     .help("Entity names to generate")
-                    .num_args(1..))))
+                    .num_args(1..))
+                .arg(Arg::new("watch")
+                    .short('w')
+                    .long("watch")
+                    .help("Regenerate changed files when the schema changes")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("diff")
+                    .short('d')
+                    .long("diff")
+                    .help("Print the files which would change without writing them")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("clean")
+                    .long("clean")
+                    .help("Remove previously generated files which are no longer generated")
+                    .action(ArgAction::SetTrue))))
+        .subcommand(ClapCommand::new("mock")
+            .about("Start a server with fake records in in-memory databases")
+            .arg(Arg::new("records")
+                .short('n')
+                .long("records")
+                .help("Number of fake records per model")
+                .value_parser(clap::value_parser!(usize)))
+            .arg(Arg::new("latency")
+                .long("latency")
+                .help("Simulated response latency in milliseconds, e.g. 200 or 100-500")
+                .value_parser(parse_latency))
+            .arg(Arg::new("seed")
+                .long("seed")
+                .help("Random seed for generating fake records")
+                .value_parser(clap::value_parser!(u64))))
         .subcommand(ClapCommand::new("migrate")
             .about("Run migration")
             .arg(Arg::new("dry")
@@ -115,11 +198,76 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
                 .action(ArgAction::Append)
                 .conflicts_with("all")
                 .help("Data set names to process")
-                .num_args(1..)))
+                .num_args(1..))
+            .arg(Arg::new("fake")
+                .long("fake")
+                .help("Generate fake records, e.g. User:50")
+                .action(ArgAction::Append)
+                .value_parser(parse_fake)
+                .conflicts_with_all(["all", "NAME", "unseed", "reseed"]))
+            .arg(Arg::new("fake-seed")
+                .long("fake-seed")
+                .help("Random seed for generating fake records")
+                .value_parser(clap::value_parser!(u64))
+                .requires("fake")))
         .subcommand(ClapCommand::new("purge")
             .about("Purge and clear the database without dropping tables."))
+        .subcommand(ClapCommand::new("db")
+            .about("Database utilities")
+            .arg_required_else_help(true)
+            .subcommand(ClapCommand::new("doctor")
+                .about("Check referential integrity, unique constraints and stored values")
+                .arg(Arg::new("fix")
+                    .long("fix")
+                    .help("Nullify optional orphaned foreign keys")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("delete-orphans")
+                    .long("delete-orphans")
+                    .help("List records whose required foreign keys are orphaned, they are deleted with --yes")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("yes")
+                    .long("yes")
+                    .help("Confirm the deletion of the records listed by --delete-orphans")
+                    .action(ArgAction::SetTrue))))
+        .subcommand(ClapCommand::new("anonymize")
+            .about("Rewrite fields marked with @pii or @anonymize, run this against a database copy")
+            .arg(Arg::new("seed")
+                .long("seed")
+                .help("Random seed for generating fake values")
+                .value_parser(clap::value_parser!(u64))))
+        .subcommand(ClapCommand::new("retention")
+            .about("Delete records older than their @retention window")
+            .arg(Arg::new("dry")
+                .short('d')
+                .long("dry")
+                .help("Count expired records without deleting them")
+                .action(ArgAction::SetTrue)))
+        .subcommand(ClapCommand::new("archive")
+            .about("Move records older than their @archive window into the archive models")
+            .arg(Arg::new("dry")
+                .short('d')
+                .long("dry")
+                .help("Count due records without moving them")
+                .action(ArgAction::SetTrue)))
+        .subcommand(ClapCommand::new("backfill")
+            .about("Run or resume a backfill, or list the backfills without a name")
+            .arg(Arg::new("NAME")
+                .help("Backfill name to run")
+                .num_args(1))
+            .arg(Arg::new("batch")
+                .short('b')
+                .long("batch")
+                .help("Number of records processed between progress updates")
+                .value_parser(clap::value_parser!(usize))
+                .requires("NAME")))
         .subcommand(ClapCommand::new("lint")
             .about("Lint the schema files"))
+        .subcommand(ClapCommand::new("replay")
+            .about("Send recorded requests again and report changed responses")
+            .arg(Arg::new("FILE")
+                .required(true)
+                .help("Recording file written by `teo serve --record'")
+                .num_args(1)))
         .subcommand(ClapCommand::new("run")
             .about("Run a defined program")
             .arg(Arg::new("NAME")
@@ -140,21 +288,33 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
     let command = match matches.subcommand() {
         Some(("serve", submatches)) => {
             let env: Option<&String> = submatches.get_one("ENV");
-            CLICommand::Serve(ServeCommand { no_migration: submatches.get_flag("no-migration"), no_autoseed: submatches.get_flag("no-autoseed"), env: env.cloned() })
+            let record: Option<String> = submatches.get_one::<String>("record").cloned();
+            let record_window = submatches.get_one::<u64>("record-window").map(|s| Duration::from_secs(*s));
+            let tls = match (submatches.get_one::<String>("tls-cert"), submatches.get_one::<String>("tls-key")) {
+                (Some(cert), Some(key)) => Some(TlsOptions::Files { cert: PathBuf::from(cert), key: PathBuf::from(key) }),
+                _ => submatches.get_flag("tls-self-signed").then_some(TlsOptions::SelfSigned),
+            };
+            CLICommand::Serve(ServeCommand { no_migration: submatches.get_flag("no-migration"), no_autoseed: submatches.get_flag("no-autoseed"), env: env.cloned(), record, record_window, record_credentials: submatches.get_flag("record-credentials"), strict_schema: submatches.get_flag("strict-schema"), tls })
         }
         Some(("generate", submatches)) => {
             match submatches.subcommand() {
                 Some(("client", submatches)) => {
                     let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
-                    CLICommand::Generate(GenerateCommand::GenerateClientCommand(GenerateClientCommand { all: submatches.get_flag("all"), names }))
+                    CLICommand::Generate(GenerateCommand::GenerateClientCommand(GenerateClientCommand { all: submatches.get_flag("all"), names, watch: submatches.get_flag("watch"), diff: submatches.get_flag("diff"), clean: submatches.get_flag("clean") }))
                 }
                 Some(("entity", submatches)) => {
                     let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
-                    CLICommand::Generate(GenerateCommand::GenerateEntityCommand(GenerateEntityCommand { all: submatches.get_flag("all"), names }))
+                    CLICommand::Generate(GenerateCommand::GenerateEntityCommand(GenerateEntityCommand { all: submatches.get_flag("all"), names, watch: submatches.get_flag("watch"), diff: submatches.get_flag("diff"), clean: submatches.get_flag("clean") }))
                 }
                 _ => unreachable!()
             }
         }
+        Some(("mock", submatches)) => {
+            let records: Option<&usize> = submatches.get_one("records");
+            let latency: Option<&(Duration, Duration)> = submatches.get_one("latency");
+            let seed: Option<&u64> = submatches.get_one("seed");
+            CLICommand::Mock(MockCommand { records: records.cloned().unwrap_or(10), latency: latency.cloned(), seed: seed.cloned().unwrap_or(0) })
+        }
         Some(("migrate", submatches)) => {
             CLICommand::Migrate(MigrateCommand { dry: submatches.get_flag("dry") })
         }
@@ -167,23 +327,72 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance) -> CLI
                 SeedCommandAction::Seed
             };
             let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
+            let fake: Option<Vec<(String, usize)>> = submatches.get_many::<(String, usize)>("fake").map(|s| s.cloned().collect());
+            let fake_seed: Option<&u64> = submatches.get_one("fake-seed");
             CLICommand::Seed(SeedCommand {
                 action,
                 all: submatches.get_flag("all"),
                 names,
+                fake,
+                fake_seed: fake_seed.cloned().unwrap_or(0),
             })
         }
         Some(("purge", _submatches)) => {
             CLICommand::Purge(PurgeCommand { })
         }
+        Some(("db", submatches)) => {
+            match submatches.subcommand() {
+                Some(("doctor", submatches)) => {
+                    CLICommand::Db(DbCommand::Doctor(DoctorCommand { fix: submatches.get_flag("fix"), delete_orphans: submatches.get_flag("delete-orphans"), yes: submatches.get_flag("yes") }))
+                }
+                _ => unreachable!()
+            }
+        }
+        Some(("anonymize", submatches)) => {
+            let seed: Option<&u64> = submatches.get_one("seed");
+            CLICommand::Anonymize(AnonymizeCommand { seed: seed.cloned().unwrap_or(0) })
+        }
+        Some(("retention", submatches)) => {
+            CLICommand::Retention(RetentionCommand { dry: submatches.get_flag("dry") })
+        }
+        Some(("archive", submatches)) => {
+            CLICommand::Archive(ArchiveCommand { dry: submatches.get_flag("dry") })
+        }
+        Some(("backfill", submatches)) => {
+            let name: Option<String> = submatches.get_one::<String>("NAME").cloned();
+            let batch: Option<&usize> = submatches.get_one("batch");
+            CLICommand::Backfill(BackfillCommand { name, batch: batch.cloned().unwrap_or(100) })
+        }
         Some(("lint", _submatches)) => {
             CLICommand::Lint(LintCommand { })
         }
+        Some(("replay", submatches)) => {
+            let file: Option<String> = submatches.get_one::<String>("FILE").cloned();
+            CLICommand::Replay(ReplayCommand { file: file.unwrap() })
+        }
         Some(("run", submatches)) => {
-            let name: Option<String> = submatches.get_one::<String>("NAME").map(|s| s.clone());
+            let name: Option<String> = submatches.get_one::<String>("NAME").cloned();
             CLICommand::Run(RunCommand { name: name.unwrap() })
         }
         _ => unreachable!()
     };
     CLI { command, schema: schema.map(|s| s.to_string()), silent }
-}
\ No newline at end of file
+}
+
+fn parse_fake(value: &str) -> Result<(String, usize), String> {
+    match value.rsplit_once(":") {
+        Some((model, count)) => match count.parse::<usize>() {
+            Ok(count) => Ok((model.to_owned(), count)),
+            Err(_) => Err(format!("invalid count `{}'", count)),
+        },
+        None => Err("expect format MODEL:COUNT".to_owned()),
+    }
+}
+
+fn parse_latency(value: &str) -> Result<(Duration, Duration), String> {
+    let (min, max) = value.split_once("-").unwrap_or((value, value));
+    match (min.parse::<u64>(), max.parse::<u64>()) {
+        (Ok(min), Ok(max)) if min <= max => Ok((Duration::from_millis(min), Duration::from_millis(max))),
+        _ => Err("expect format MS or MIN-MAX".to_owned()),
+    }
+}
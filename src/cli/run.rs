@@ -1,51 +1,90 @@
+use std::sync::Arc;
 use teo_result::{Error, Result};
+use crate::app::app::publish_main_namespace;
 use crate::app::ctx::Ctx;
-use crate::app::database::connect_databases;
-use crate::cli::command::{CLI, CLICommand, GenerateCommand, SeedCommandAction};
+use crate::app::database::connect_main_namespace;
+use crate::cli::command::{CLI, CLICommand, DbCommand, GenerateCommand, SeedCommandAction};
 use crate::server::make::serve;
+use crate::server::record::{Recorder, replay};
 use teo_runtime::connection::transaction;
-use teo_runtime::schema::load::load_data_sets::load_data_sets;
 use crate::migrate::migrate;
+use crate::migrate::data_sets::{load_seed_data_sets, migrate_data_sets};
+use crate::migrate::check::check_schema;
+use crate::mock::mock;
 use crate::purge::purge;
+use crate::doctor::doctor;
+use crate::anonymize::anonymize;
+use crate::retention::{enforce_retention, report_retention, schedule_retention};
+use crate::archive::{enforce_archive, report_archive, schedule_archive};
+use crate::backfill::{backfill_progress, report_backfills, run_backfill};
+use crate::message::info_message;
 use crate::seeder::seed::seed;
+use crate::seeder::factory::Factory;
+use crate::cli::watch::{generate_incrementally, Target};
 
 pub async fn run(cli: &CLI) -> Result<()> {
     match &cli.command {
         CLICommand::Serve(serve_command) => {
-            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            if let Some(file) = serve_command.record.as_ref() {
+                let recorder = Recorder::new(file, serve_command.record_window, serve_command.record_credentials)?;
+                Ctx::update_settings(|settings| settings.recorder = Some(Arc::new(recorder)));
+            }
+            if let Some(tls) = serve_command.tls.clone() {
+                Ctx::update_settings(|settings| settings.tls = Some(tls));
+            }
+            connect_main_namespace(cli.silent).await?;
             let conn_ctx = Ctx::conn_ctx();
             // migrate
             if !serve_command.no_migration {
                 migrate(false, false, cli.silent).await?;
+                migrate_data_sets(transaction::Ctx::new(conn_ctx.clone()), false, cli.silent).await?;
+            } else {
+                check_schema(serve_command.strict_schema, cli.silent).await?;
             }
             // seed auto seed data sets
             if Ctx::main_namespace().database.is_some() {
-                let data_sets = load_data_sets(Ctx::main_namespace(), None, false, Ctx::schema())?;
-                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+                let data_sets = load_seed_data_sets(None, false)?;
+                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
                 seed(SeedCommandAction::Seed, data_sets, transaction_ctx, false).await?;
             }
             // setup
             if let Some(setup) = Ctx::setup() {
-                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
                 setup.call(transaction_ctx).await?;
             }
+            schedule_retention(transaction::Ctx::new(conn_ctx.clone()), cli.silent);
+            schedule_archive(transaction::Ctx::new(conn_ctx.clone()), cli.silent);
+            for plugin in Ctx::settings().plugins.clone() {
+                plugin.on_server_start(conn_ctx.namespace()).await?;
+            }
             // start server
-            serve(conn_ctx.namespace(), conn_ctx.namespace().server.as_ref().unwrap(), &Ctx::get().runtime_version, &Ctx::get().entrance, cli.silent).await
+            serve(conn_ctx.namespace(), &Ctx::get().runtime_version, &Ctx::get().entrance, cli.silent).await
         }
         CLICommand::Generate(generate_command) => {
+            publish_main_namespace().await?;
             match generate_command {
                 GenerateCommand::GenerateClientCommand(command) => {
                     let names = if let Some(names) = command.names.as_ref() {
                         names.clone()
                     } else if command.all {
-                        Ctx::main_namespace().clients.keys().map(|k| k.clone()).collect()
+                        Ctx::main_namespace().clients.keys().cloned().collect()
                     } else {
                         match Ctx::main_namespace().clients.len() {
                             0 => Err(Error::new("no clients found"))?,
-                            1 => return teo_generator::client::generate(Ctx::main_namespace(), Ctx::main_namespace().clients.first_key_value().unwrap().1).await,
+                            1 => vec![Ctx::main_namespace().clients.first_key_value().unwrap().0.clone()],
                             _ => Err(Error::new("requires client name"))?,
                         }
                     };
+                    if command.watch || command.diff || command.clean {
+                        let mut targets = vec![];
+                        for name in names {
+                            let Some(client) = Ctx::main_namespace().clients.get(&name) else {
+                                Err(Error::new("client not found"))?
+                            };
+                            targets.push((name, client.dest.clone()));
+                        }
+                        return generate_incrementally(Target::Client, targets, command.watch, command.diff).await;
+                    }
                     for name in names {
                         if let Some(client) = Ctx::main_namespace().clients.get(&name) {
                             teo_generator::client::generate(Ctx::main_namespace(), client).await?;
@@ -59,14 +98,24 @@ pub async fn run(cli: &CLI) -> Result<()> {
                     let names = if let Some(names) = command.names.as_ref() {
                         names.clone()
                     } else if command.all {
-                        Ctx::main_namespace().entities.keys().map(|k| k.clone()).collect()
+                        Ctx::main_namespace().entities.keys().cloned().collect()
                     } else {
                         match Ctx::main_namespace().entities.len() {
                             0 => Err(Error::new("no entities found"))?,
-                            1 => return teo_generator::entity::generate(Ctx::main_namespace(), Ctx::main_namespace().entities.first_key_value().unwrap().1).await,
+                            1 => vec![Ctx::main_namespace().entities.first_key_value().unwrap().0.clone()],
                             _ => Err(Error::new("requires entity name"))?,
                         }
                     };
+                    if command.watch || command.diff || command.clean {
+                        let mut targets = vec![];
+                        for name in names {
+                            let Some(entity) = Ctx::main_namespace().entities.get(&name) else {
+                                Err(Error::new("entity not found"))?
+                            };
+                            targets.push((name, entity.dest.clone()));
+                        }
+                        return generate_incrementally(Target::Entity, targets, command.watch, command.diff).await;
+                    }
                     for name in names {
                         if let Some(entity) = Ctx::main_namespace().entities.get(&name) {
                             teo_generator::entity::generate(Ctx::main_namespace(), entity).await?;
@@ -78,28 +127,90 @@ pub async fn run(cli: &CLI) -> Result<()> {
                 }
             }
         }
+        CLICommand::Mock(mock_command) => mock(mock_command, cli.silent).await,
         CLICommand::Migrate(migrate_command) => {
-            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            connect_main_namespace(cli.silent).await?;
             migrate(migrate_command.dry, false, cli.silent).await?;
+            migrate_data_sets(transaction::Ctx::new(Ctx::conn_ctx()), migrate_command.dry, cli.silent).await?;
             Ok(())
         }
         CLICommand::Seed(seed_command) => {
-            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
-            let data_sets = load_data_sets(Ctx::main_namespace(), seed_command.names.as_ref(), seed_command.all, Ctx::schema())?;
-            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+            connect_main_namespace(cli.silent).await?;
+            if let Some(fake) = seed_command.fake.as_ref() {
+                let factory = Factory::new(transaction::Ctx::new(Ctx::conn_ctx()), seed_command.fake_seed);
+                for (model_name, count) in fake {
+                    factory.create_many(model_name, *count).await?;
+                }
+                return Ok(());
+            }
+            let data_sets = load_seed_data_sets(seed_command.names.as_ref(), seed_command.all)?;
+            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
             seed(seed_command.action, data_sets, transaction_ctx, true).await?;
             Ok(())
         }
-        CLICommand::Purge(purge_command) => {
-            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+        CLICommand::Purge(_) => {
+            connect_main_namespace(cli.silent).await?;
             purge().await?;
             Ok(())
         }
-        CLICommand::Lint(lint_command) => Ok(()),
+        CLICommand::Db(db_command) => {
+            connect_main_namespace(cli.silent).await?;
+            match db_command {
+                DbCommand::Doctor(doctor_command) => doctor(doctor_command).await,
+            }
+        }
+        CLICommand::Anonymize(anonymize_command) => {
+            connect_main_namespace(cli.silent).await?;
+            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
+            let updated = anonymize(transaction_ctx, anonymize_command.seed).await?;
+            if !cli.silent {
+                info_message(format!("{} record(s) anonymized", updated));
+            }
+            Ok(())
+        }
+        CLICommand::Retention(retention_command) => {
+            connect_main_namespace(cli.silent).await?;
+            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
+            let reports = enforce_retention(transaction_ctx, retention_command.dry).await?;
+            if !cli.silent {
+                report_retention(&reports, retention_command.dry);
+            }
+            Ok(())
+        }
+        CLICommand::Archive(archive_command) => {
+            connect_main_namespace(cli.silent).await?;
+            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
+            let reports = enforce_archive(transaction_ctx, archive_command.dry).await?;
+            if !cli.silent {
+                report_archive(&reports, archive_command.dry);
+            }
+            Ok(())
+        }
+        CLICommand::Backfill(backfill_command) => {
+            connect_main_namespace(cli.silent).await?;
+            let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
+            match &backfill_command.name {
+                Some(name) => {
+                    run_backfill(transaction_ctx, name, backfill_command.batch, cli.silent).await?;
+                    if !cli.silent {
+                        info_message(format!("{} finished", name));
+                    }
+                }
+                None => report_backfills(&backfill_progress(transaction_ctx).await?),
+            }
+            Ok(())
+        }
+        CLICommand::Lint(_) => Ok(()),
+        CLICommand::Replay(replay_command) => {
+            connect_main_namespace(cli.silent).await?;
+            // the database is migrated like `serve' does, so recordings replay on a fresh one
+            migrate(false, false, cli.silent).await?;
+            replay(&replay_command.file).await
+        }
         CLICommand::Run(run_command) => {
-            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
-            if let Some(program) = Ctx::get_mut().programs.get(&run_command.name) {
-                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+            connect_main_namespace(cli.silent).await?;
+            if let Some(program) = Ctx::program(&run_command.name) {
+                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx());
                 program.call(transaction_ctx).await?;
                 std::process::exit(0);
             } else {
@@ -1,3 +1,5 @@
+use std::fmt::{Display, Formatter};
+
 #[derive(Clone, Debug)]
 pub enum RuntimeVersion {
     Rust(&'static str),
@@ -5,13 +7,13 @@ pub enum RuntimeVersion {
     Python(String),
 }
 
-impl ToString for RuntimeVersion {
+impl Display for RuntimeVersion {
 
-    fn to_string(&self) -> String {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            RuntimeVersion::Rust(v) => format!("Rust {v}"),
-            RuntimeVersion::NodeJS(v) => format!("Node.js {v}"),
-            RuntimeVersion::Python(v) => format!("Python {v}"),
+            RuntimeVersion::Rust(v) => write!(f, "Rust {v}"),
+            RuntimeVersion::NodeJS(v) => write!(f, "Node.js {v}"),
+            RuntimeVersion::Python(v) => write!(f, "Python {v}"),
         }
     }
 }
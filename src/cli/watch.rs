@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use colored::Colorize;
+use teo_parser::parse as schema_parse;
+use teo_result::{Error, Result};
+use tokio::process::Command;
+use uuid::Uuid;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MANIFEST: &str = ".teo-generated";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    Client,
+    Entity,
+}
+
+impl Target {
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Target::Client => "client",
+            Target::Entity => "entity",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Change {
+    Added(PathBuf),
+    Modified(PathBuf, usize, usize),
+    Removed(PathBuf),
+}
+
+/// Generates into a scratch directory and copies only the files which differ into the destinations.
+pub(crate) async fn generate_incrementally(target: Target, names: Vec<(String, String)>, watch: bool, diff: bool) -> Result<()> {
+    let main_schema = Ctx::schema().main_source().file_path.clone();
+    let mut stamps = source_stamps(&main_schema);
+    regenerate(target, &names, &main_schema, diff).await;
+    if !watch {
+        return Ok(());
+    }
+    info_message(format!("watching {} schema file(s) for changes", stamps.len()));
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let current = source_stamps(&main_schema);
+        if current != stamps {
+            stamps = current;
+            regenerate(target, &names, &main_schema, diff).await;
+        }
+    }
+}
+
+// failures are reported and the watcher keeps running, the next save usually fixes them
+async fn regenerate(target: Target, names: &[(String, String)], main_schema: &str, diff: bool) {
+    for (name, dest) in names {
+        match regenerate_one(target, name, dest, main_schema, diff).await {
+            Ok(changes) => report(target, name, dest, &changes, diff),
+            Err(error) => info_message(format!("{} {} {}: {}", "failed".red(), target.as_str(), name, error.message())),
+        }
+    }
+}
+
+async fn regenerate_one(target: Target, name: &str, dest: &str, main_schema: &str, diff: bool) -> Result<Vec<Change>> {
+    let dest = Path::new(dest);
+    // `..' would lead both the generated files and the destination out of their directories
+    if !dest.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        Err(Error::new("incremental generation requires a relative `dest' without `..'"))?
+    }
+    let scratch = env::temp_dir().join(format!("teo-generate-{}", Uuid::new_v4()));
+    fs::create_dir_all(&scratch)?;
+    let result = generate_into(target, name, main_schema, &scratch).await.and_then(|_| {
+        let current_dir = env::current_dir()?;
+        sync(&scratch.join(dest), &current_dir.join(dest), !diff)
+    });
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+// the generators exit the process when they finish, so each one runs in a child process
+async fn generate_into(target: Target, name: &str, main_schema: &str, dir: &Path) -> Result<()> {
+    let args: Vec<OsString> = env::args_os().collect();
+    let Some(index) = args.iter().position(|a| a == "generate") else {
+        Err(Error::new("cannot find the generate command in the arguments"))?
+    };
+    let mut command = Command::new(env::current_exe()?);
+    for arg in &args[1..index] {
+        command.arg(absolutized(arg));
+    }
+    command.arg("generate").arg(target.as_str()).arg(name);
+    if !args[1..index].iter().any(|a| a == "--schema" || a == "-s") {
+        command.arg("--schema").arg(absolutized(&OsString::from(main_schema)));
+    }
+    let output = command.current_dir(dir).output().await?;
+    if !output.status.success() {
+        Err(Error::new(String::from_utf8_lossy(&output.stderr).trim().to_owned()))?
+    }
+    Ok(())
+}
+
+// scripts and schema files are given relative to the working directory of this process
+fn absolutized(arg: &OsString) -> OsString {
+    let path = Path::new(arg);
+    if path.is_relative() && path.is_file() {
+        if let Ok(path) = fs::canonicalize(path) {
+            return path.into_os_string();
+        }
+    }
+    arg.clone()
+}
+
+fn sync(generated: &Path, dest: &Path, write: bool) -> Result<Vec<Change>> {
+    let mut changes = vec![];
+    let mut owned = vec![];
+    for file in files(generated)? {
+        let relative = file.strip_prefix(generated).unwrap().to_path_buf();
+        owned.push(relative.clone());
+        let target = dest.join(&relative);
+        let content = fs::read(&file)?;
+        let change = match fs::read(&target) {
+            Ok(existing) if existing == content => continue,
+            Ok(existing) => {
+                let (added, removed) = line_changes(&String::from_utf8_lossy(&existing), &String::from_utf8_lossy(&content));
+                Change::Modified(relative, added, removed)
+            }
+            Err(_) => Change::Added(relative),
+        };
+        if write {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, content)?;
+        }
+        changes.push(change);
+    }
+    for relative in read_manifest(dest) {
+        if owned.contains(&relative) || !dest.join(&relative).is_file() {
+            continue;
+        }
+        if write {
+            fs::remove_file(dest.join(&relative))?;
+            remove_empty_parents(dest, &relative);
+        }
+        changes.push(Change::Removed(relative));
+    }
+    // an empty generation is recorded too, so the files of the run before it aren't owned forever
+    if write {
+        fs::create_dir_all(dest)?;
+        write_manifest(dest, &owned)?;
+    }
+    Ok(changes)
+}
+
+fn read_manifest(dest: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(dest.join(MANIFEST)) else {
+        return vec![];
+    };
+    // never follow entries out of the destination
+    content.lines()
+        .map(PathBuf::from)
+        .filter(|p| p.is_relative() && p.components().all(|c| matches!(c, Component::Normal(_))))
+        .collect()
+}
+
+fn write_manifest(dest: &Path, owned: &[PathBuf]) -> Result<()> {
+    let content: String = owned.iter().map(|p| p.to_string_lossy().replace('\\', "/") + "\n").collect();
+    fs::write(dest.join(MANIFEST), content)?;
+    Ok(())
+}
+
+fn remove_empty_parents(dest: &Path, relative: &Path) {
+    let mut parent = relative.parent();
+    while let Some(dir) = parent {
+        if dir.as_os_str().is_empty() || fs::remove_dir(dest.join(dir)).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+}
+
+fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    if !dir.exists() {
+        return Ok(result);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|n| n == ".git" || n == MANIFEST) {
+            continue;
+        }
+        if path.is_dir() {
+            result.extend(files(&path)?);
+        } else {
+            result.push(path);
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+// counts lines by occurrence, which is close enough for a summary
+fn line_changes(before: &str, after: &str) -> (usize, usize) {
+    let mut counts: BTreeMap<&str, isize> = BTreeMap::new();
+    for line in after.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in before.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let added = counts.values().filter(|c| **c > 0).map(|c| *c as usize).sum();
+    let removed = counts.values().filter(|c| **c < 0).map(|c| (-*c) as usize).sum();
+    (added, removed)
+}
+
+fn report(target: Target, name: &str, dest: &str, changes: &[Change], diff: bool) {
+    if changes.is_empty() {
+        info_message(format!("{} {} is up to date", target.as_str(), name));
+        return;
+    }
+    let verb = if diff { "would change" } else { "updated" };
+    info_message(format!("{} {} {} {} file(s) in {}", target.as_str(), name, verb, changes.len(), dest));
+    for change in changes {
+        match change {
+            Change::Added(path) => info_message(format!("{} {}", "+".green(), path.display())),
+            Change::Modified(path, added, removed) => info_message(format!("{} {} ({}, {})", "~".yellow(), path.display(), format!("+{}", added).green(), format!("-{}", removed).red())),
+            Change::Removed(path) => info_message(format!("{} {}", "-".red(), path.display())),
+        }
+    }
+}
+
+// the schema is parsed again, so newly imported files are watched, too
+fn source_stamps(main_schema: &str) -> BTreeMap<String, SystemTime> {
+    let (schema, _) = schema_parse(main_schema, None, None);
+    schema.sources().into_iter()
+        .filter_map(|s| fs::metadata(&s.file_path).and_then(|m| m.modified()).ok().map(|t| (s.file_path.clone(), t)))
+        .collect()
+}
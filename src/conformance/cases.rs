@@ -0,0 +1,219 @@
+use futures::future::LocalBoxFuture;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::test::TestServer;
+
+pub type Case = for<'a> fn(&'a TestServer) -> LocalBoxFuture<'a, Result<()>>;
+
+pub fn cases() -> Vec<(&'static str, Case)> {
+    vec![
+        ("types.int32", int32),
+        ("types.int64", int64),
+        ("types.float32", float32),
+        ("types.float64", float64),
+        ("types.bool", bool),
+        ("types.string", string),
+        ("types.date", date),
+        ("types.dateTime", date_time),
+        ("types.decimal", decimal),
+        ("types.enum", r#enum),
+        ("queries.filter", filter),
+        ("queries.orderAndPaginate", order_and_paginate),
+        ("queries.count", count),
+        ("mutations.update", update),
+        ("mutations.delete", delete),
+        ("mutations.uniqueViolation", unique_violation),
+        ("relations.nestedCreate", nested_create),
+        ("relations.requiredRelation", required_relation),
+    ]
+}
+
+/// The cases a provider is known to fail, they are skipped and reported as such.
+pub fn known_skips(provider: &str) -> &'static [&'static str] {
+    match provider {
+        // the SQLite connector creates `Int64` columns as INT, see the roadmap
+        "sqlite" => &["types.int64"],
+        _ => &[],
+    }
+}
+
+fn int32(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "int32", json!(1), json!(1)))
+}
+
+fn int64(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "int64", json!(9007199254740i64), json!(9007199254740i64)))
+}
+
+fn float32(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "float32", json!(1.5), json!(1.5)))
+}
+
+fn float64(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "float64", json!(1.25), json!(1.25)))
+}
+
+fn bool(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "bool", json!(true), json!(true)))
+}
+
+fn string(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "string", json!("KOF XV"), json!("KOF XV")))
+}
+
+fn date(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "date", json!("2005-12-25"), json!("2005-12-25")))
+}
+
+fn date_time(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "dateTime", json!("2003-04-17T08:12:34.567Z"), json!("2003-04-17T08:12:34.567Z")))
+}
+
+fn decimal(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "decimal", json!("5.78"), json!("5.78")))
+}
+
+fn r#enum(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(roundtrip(server, "sex", json!("female"), json!("female")))
+}
+
+fn filter(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        create_authors(server).await?;
+        let found = data(server.request("Author", "findMany", json!({
+            "where": { "age": { "gt": 20 } },
+            "orderBy": { "name": "asc" },
+        })).await?)?;
+        expect_eq(names(&found), json!(["Dan", "Nova"]), "filtered names")
+    })
+}
+
+fn order_and_paginate(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        create_authors(server).await?;
+        let found = data(server.request("Author", "findMany", json!({
+            "orderBy": { "name": "desc" },
+            "skip": 1,
+            "take": 1,
+        })).await?)?;
+        expect_eq(names(&found), json!(["Dan"]), "paginated names")
+    })
+}
+
+fn count(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        create_authors(server).await?;
+        let count = data(server.request("Author", "count", json!({
+            "where": { "age": { "lte": 20 } },
+        })).await?)?;
+        expect_eq(count, json!(1), "count")
+    })
+}
+
+fn update(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let created = data(server.request("Author", "create", json!({ "create": { "name": "Dan", "age": 25 } })).await?)?;
+        data(server.request("Author", "update", json!({
+            "where": { "id": created["id"] },
+            "update": { "age": 26 },
+        })).await?)?;
+        let found = data(server.request("Author", "findUnique", json!({ "where": { "id": created["id"] } })).await?)?;
+        expect_eq(found["age"].clone(), json!(26), "updated age")
+    })
+}
+
+fn delete(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let created = data(server.request("Author", "create", json!({ "create": { "name": "Dan" } })).await?)?;
+        data(server.request("Author", "delete", json!({ "where": { "id": created["id"] } })).await?)?;
+        let count = data(server.request("Author", "count", json!({})).await?)?;
+        expect_eq(count, json!(0), "count after delete")
+    })
+}
+
+fn unique_violation(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        data(server.request("Author", "create", json!({ "create": { "name": "Dan" } })).await?)?;
+        let response = server.request("Author", "create", json!({ "create": { "name": "Dan" } })).await?;
+        expect_error(&response, "duplicated unique value")
+    })
+}
+
+fn nested_create(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        data(server.request("Author", "create", json!({
+            "create": {
+                "name": "Dan",
+                "posts": { "create": [{ "title": "First" }, { "title": "Second" }] },
+            },
+        })).await?)?;
+        let posts = data(server.request("Post", "findMany", json!({
+            "orderBy": { "title": "asc" },
+            "include": { "author": true },
+        })).await?)?;
+        let titles: Vec<JsonValue> = posts.as_array().into_iter().flatten().map(|p| p["title"].clone()).collect();
+        expect_eq(JsonValue::Array(titles), json!(["First", "Second"]), "post titles")?;
+        let authors: Vec<JsonValue> = posts.as_array().into_iter().flatten().map(|p| p["author"]["name"].clone()).collect();
+        expect_eq(JsonValue::Array(authors), json!(["Dan", "Dan"]), "included authors")
+    })
+}
+
+fn required_relation(server: &TestServer) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let response = server.request("Post", "create", json!({ "create": { "title": "Orphan" } })).await?;
+        expect_error(&response, "missing required relation")
+    })
+}
+
+async fn roundtrip(server: &TestServer, field: &str, input: JsonValue, expected: JsonValue) -> Result<()> {
+    let created = data(server.request("Support", "create", json!({ "create": { field: input } })).await?)?;
+    expect_eq(unwrap_special(&created[field]), expected.clone(), "created value")?;
+    let found = data(server.request("Support", "findUnique", json!({ "where": { "id": created["id"] } })).await?)?;
+    expect_eq(unwrap_special(&found[field]), expected, "found value")
+}
+
+async fn create_authors(server: &TestServer) -> Result<()> {
+    for (name, age) in [("Ann", 20), ("Dan", 25), ("Nova", 30)] {
+        data(server.request("Author", "create", json!({ "create": { "name": name, "age": age } })).await?)?;
+    }
+    Ok(())
+}
+
+fn names(value: &JsonValue) -> JsonValue {
+    JsonValue::Array(value.as_array().into_iter().flatten().map(|v| v["name"].clone()).collect())
+}
+
+fn data(response: JsonValue) -> Result<JsonValue> {
+    if let Some(error) = response.get("error") {
+        Err(Error::new(format!("unexpected error response: {}", error)))
+    } else {
+        Ok(response.get("data").cloned().unwrap_or(JsonValue::Null))
+    }
+}
+
+fn unwrap_special(value: &JsonValue) -> JsonValue {
+    if let Some(map) = value.as_object() {
+        if map.len() == 1 {
+            if let Some(inner) = map.get("$date").or(map.get("$datetime")).or(map.get("$decimal")) {
+                return inner.clone();
+            }
+        }
+    }
+    value.clone()
+}
+
+fn expect_eq(actual: JsonValue, expected: JsonValue, context: &str) -> Result<()> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::new(format!("{}: expect {}, found {}", context, expected, actual)))
+    }
+}
+
+fn expect_error(response: &JsonValue, context: &str) -> Result<()> {
+    if response.get("error").is_some() {
+        Ok(())
+    } else {
+        Err(Error::new(format!("{}: expect error, found {}", context, response)))
+    }
+}
@@ -0,0 +1,126 @@
+pub mod cases;
+
+use colored::Colorize;
+use teo_result::{Error, Result};
+use url::Url;
+use crate::message::info_message;
+use crate::test::TestServer;
+
+pub struct Report {
+    pub passed: Vec<String>,
+    pub failed: Vec<(String, Error)>,
+    pub skipped: Vec<String>,
+}
+
+impl Report {
+
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    pub fn print(&self) {
+        for name in &self.passed {
+            info_message(format!("{} {}", "passed".green(), name));
+        }
+        for name in &self.skipped {
+            info_message(format!("{} {}", "skipped".yellow(), name));
+        }
+        for (name, error) in &self.failed {
+            info_message(format!("{} {}: {}", "failed".red(), name, error.message()));
+        }
+    }
+}
+
+pub async fn run(url: &str) -> Result<Report> {
+    let provider = provider(url)?;
+    let server = TestServer::new(schema(provider, url)).await?;
+    let mut report = Report { passed: vec![], failed: vec![], skipped: vec![] };
+    for (name, case) in cases::cases() {
+        if cases::known_skips(provider).contains(&name) {
+            report.skipped.push(name.to_owned());
+            continue;
+        }
+        server.reset().await?;
+        match case(&server).await {
+            Ok(()) => report.passed.push(name.to_owned()),
+            Err(error) => report.failed.push((name.to_owned(), error)),
+        }
+    }
+    Ok(report)
+}
+
+pub async fn assert_conformance(url: &str) {
+    let report = run(url).await.unwrap();
+    report.print();
+    assert!(report.is_success(), "{} conformance case(s) failed", report.failed.len());
+}
+
+fn provider(url: &str) -> Result<&'static str> {
+    if url.starts_with("sqlite:") {
+        return Ok("sqlite");
+    }
+    let scheme = match Url::parse(url) {
+        Ok(parsed) => parsed.scheme().to_owned(),
+        Err(e) => Err(Error::new(format!("invalid connector url: {}", e)))?,
+    };
+    match scheme.as_str() {
+        "mysql" => Ok("mysql"),
+        "postgres" | "postgresql" => Ok("postgres"),
+        "mongodb" | "mongodb+srv" => Ok("mongo"),
+        _ => Err(Error::new(format!("unsupported connector url scheme: {}", scheme))),
+    }
+}
+
+fn schema(provider: &str, url: &str) -> String {
+    let id = if provider == "mongo" {
+        "@id @auto @map(\"_id\") @readonly\n  id: ObjectId"
+    } else {
+        "@id @autoIncrement @readonly\n  id: Int"
+    };
+    format!(r#"connector {{
+  provider: .{provider},
+  url: "{url}"
+}}
+
+server {{
+  bind: ("0.0.0.0", 4000)
+}}
+
+enum Sex {{
+  male
+  female
+}}
+
+model Support {{
+  {id}
+  int32: Int32?
+  int64: Int64?
+  float32: Float32?
+  float64: Float64?
+  bool: Bool?
+  string: String?
+  date: Date?
+  dateTime: DateTime?
+  decimal: Decimal?
+  sex: Sex?
+}}
+
+model Author {{
+  {id}
+  @unique
+  name: String
+  age: Int?
+  @relation(fields: .id, references: .authorId)
+  posts: Post[]
+}}
+
+model Post {{
+  {id}
+  title: String
+  @foreignKey
+  authorId: {id_type}
+  @relation(fields: .authorId, references: .id)
+  author: Author
+}}
+"#, id_type = if provider == "mongo" { "ObjectId" } else { "Int" })
+}
@@ -0,0 +1,127 @@
+use indexmap::IndexMap;
+use key_path::{KeyPath, path};
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{MANY, NESTED, UPDATE};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::model::{Field, Model, Object, Relation};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_teon::teon;
+use teo_teon::types::enum_variant::EnumVariant;
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+
+const DATA_KEY: &str = "counterCache";
+
+/// Loads `@counterCache`, which counts the records of a to many relation in a field.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("counterCache", |args: Arguments, field: &mut Field| {
+        let relation: &EnumVariant = args.get("relation")?;
+        field.data.insert(DATA_KEY.to_owned(), Value::String(relation.value.clone()).into());
+        Ok(())
+    });
+}
+
+/// The relation counted by `field`, if it's a counter cache.
+pub fn counted_relation(field: &Field) -> Option<&str> {
+    field.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_str())
+}
+
+/// Checks that every counter cache counts a to many relation with references on the other side.
+pub(crate) fn check_counter_caches(namespace: &Namespace) -> Result<()> {
+    for model in namespace.models.values() {
+        for field in model.fields.values() {
+            let Some(name) = counted_relation(field) else { continue };
+            let counter = format!("{}.{}", model.path.join("."), field.name);
+            match model.relation(name) {
+                None => Err(Error::new(format!("`{}' counts `{}', which isn't a relation", counter, name)))?,
+                Some(relation) if !relation.is_vec || relation.through.is_some() || relation.has_foreign_key => {
+                    Err(Error::new(format!("`{}' counts `{}', only to many relations without a join model can be counted", counter, name)))?
+                },
+                _ => (),
+            }
+        }
+    }
+    for namespace in namespace.namespaces.values() {
+        check_counter_caches(namespace)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Write {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Recounts the records `object` belongs to after it's written, in the transaction which wrote it.
+pub(crate) async fn refresh_counters(transaction: &dyn Transaction, object: &Object, write: Write, path: &KeyPath) -> path::Result<()> {
+    let model = object.model();
+    for (owner, relation) in object.namespace().model_opposite_relations(model) {
+        let counters: Vec<&Field> = owner.fields.values().filter(|f| counted_relation(f) == Some(relation.name.as_str())).collect();
+        if counters.is_empty() {
+            continue;
+        }
+        let mut keys = vec![];
+        match write {
+            Write::Update if !changed(object, relation) => (),
+            Write::Update => {
+                keys.push(references(relation, |key| object.get_value(key))?);
+                keys.push(references(relation, |key| object.get_previous_value(key))?);
+            },
+            _ => keys.push(references(relation, |key| object.get_value(key))?),
+        }
+        keys.dedup();
+        for key in keys.into_iter().flatten() {
+            recount(transaction, object, owner, relation, &counters, key, path).await?;
+        }
+    }
+    Ok(())
+}
+
+// whether an update reconnected the record
+fn changed(object: &Object, relation: &Relation) -> bool {
+    object.keys_for_save().into_iter().any(|key| relation.references.iter().any(|r| r == key))
+}
+
+// the owner's key values, nothing to count when the record isn't connected
+fn references(relation: &Relation, value: impl Fn(&str) -> teo_result::Result<Value>) -> BoxedResult<Option<Vec<(String, Value)>>> {
+    let mut key = vec![];
+    for (field, reference) in relation.iter() {
+        let value = value(reference)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        key.push((field.to_owned(), value));
+    }
+    Ok(Some(key))
+}
+
+async fn recount(transaction: &dyn Transaction, object: &Object, owner: &'static Model, relation: &Relation, counters: &[&Field], key: Vec<(String, Value)>, path: &KeyPath) -> path::Result<()> {
+    let transaction_ctx = object.transaction_ctx();
+    let counted = transaction_ctx.namespace().model_at_path(&relation.model_path()).unwrap();
+    let mut children = IndexMap::new();
+    let mut owners = IndexMap::new();
+    for ((field, value), reference) in key.iter().zip(&relation.references) {
+        children.insert(reference.clone(), value.clone());
+        owners.insert(field.clone(), value.clone());
+    }
+    let count = transaction_ctx.count(counted, &teon!({"where": Value::Dictionary(children)}), path.clone()).await?;
+    let finder = teon!({"where": Value::Dictionary(owners)});
+    let action: Action = UPDATE | MANY | NESTED;
+    for record in transaction_ctx.find_many_internal(owner, &finder, true, action, object.request_ctx(), path![]).await? {
+        for counter in counters {
+            if record.get_value(&counter.name)?.to_int64() != Some(count as i64) {
+                record.set_value(&counter.name, Value::Int64(count as i64))?;
+            }
+        }
+        if record.is_modified() {
+            transaction.save_object(&record, path.clone()).await?;
+            record.clear_state();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use colored::Colorize;
+use indexmap::IndexMap;
+use key_path::path;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::relation::Relation;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::cli::command::DoctorCommand;
+use crate::message::info_message;
+use crate::object::batch::{BatchOptions, Batches};
+
+const PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum IssueKind {
+    Orphan,
+    Duplicate,
+    Enum,
+    Type,
+    Unreadable,
+}
+
+impl IssueKind {
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            IssueKind::Orphan => "orphaned foreign key",
+            IssueKind::Duplicate => "duplicated unique value",
+            IssueKind::Enum => "unknown enum member",
+            IssueKind::Type => "type mismatch",
+            IssueKind::Unreadable => "unreadable record",
+        }
+    }
+}
+
+struct Orphan {
+    object: Object,
+    issue: usize,
+}
+
+struct Issue {
+    kind: IssueKind,
+    model: String,
+    record: String,
+    message: String,
+    fixed: bool,
+}
+
+pub(crate) async fn doctor(command: &DoctorCommand) -> Result<()> {
+    let ctx = Ctx::conn_ctx();
+    let transaction_ctx = transaction::Ctx::new(ctx.clone());
+    let mut issues = vec![];
+    let mut orphans = vec![];
+    for namespace_path in ctx.connections_iter().keys() {
+        let Some(namespace) = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else {
+            Err(Error::new(format!("namespace `{}' is not found", namespace_path.join("."))))?
+        };
+        for model in namespace.models_under_connector() {
+            // a model which cannot be checked is reported, the other models are still checked
+            if let Err(err) = check_model(&transaction_ctx, model, command, &mut issues, &mut orphans).await {
+                issues.push(Issue {
+                    kind: IssueKind::Unreadable,
+                    model: model.path.join("."),
+                    record: "records".to_owned(),
+                    message: format!("cannot be checked: {}", err.message),
+                    fixed: false,
+                });
+            }
+        }
+    }
+    if !orphans.is_empty() {
+        delete_orphans(&orphans, command.yes, &mut issues).await?;
+    }
+    for issue in &issues {
+        let status = if issue.fixed { "fixed".green() } else { "found".red() };
+        info_message(format!("{} {} {} {}: {}", status, issue.kind.as_str(), issue.model.bold(), issue.record, issue.message));
+    }
+    let unfixed = issues.iter().filter(|i| !i.fixed).count();
+    for kind in [IssueKind::Orphan, IssueKind::Duplicate, IssueKind::Enum, IssueKind::Type, IssueKind::Unreadable] {
+        let count = issues.iter().filter(|i| i.kind == kind).count();
+        info_message(format!("{}: {}", kind.as_str(), count));
+    }
+    if unfixed > 0 {
+        Err(Error::new(format!("{} integrity issue(s) found", unfixed)))
+    } else {
+        info_message("database integrity check passed");
+        Ok(())
+    }
+}
+
+async fn check_model(ctx: &transaction::Ctx, model: &'static Model, command: &DoctorCommand, issues: &mut Vec<Issue>, orphans: &mut Vec<Orphan>) -> Result<()> {
+    let model_name = model.path.join(".");
+    let unique_indexes: Vec<Vec<String>> = model.indexes.values().filter(|i| i.r#type().is_unique_or_primary()).map(|i| i.keys().clone()).collect();
+    let mut seen: Vec<HashMap<String, String>> = unique_indexes.iter().map(|_| HashMap::new()).collect();
+    let options = BatchOptions { size: PAGE_SIZE, ..Default::default() };
+    let mut batches = Batches::new(ctx, model, &teon!({}), options.clone())?;
+    loop {
+        let last = batches.last().map(|l| l.to_vec());
+        let objects: Vec<Object> = match batches.next().await {
+            Ok(Some(objects)) => objects,
+            Ok(None) => break,
+            Err(_) => match load_one_by_one(ctx, model, last, options.clone(), issues).await? {
+                Some((objects, last)) => {
+                    batches.resume_after(last)?;
+                    objects
+                },
+                None => break,
+            },
+        };
+        for object in objects {
+            let record = format!("{}", object.identifier());
+            for field in model.fields() {
+                if field.r#virtual {
+                    continue
+                }
+                let value = object.get_value(field.name.as_str())?;
+                if let Some(message) = check_value(ctx, &field.r#type, &value) {
+                    let kind = if field.r#type.unwrap_optional().is_enum_variant() { IssueKind::Enum } else { IssueKind::Type };
+                    issues.push(Issue { kind, model: model_name.clone(), record: record.clone(), message: format!("{}: {}", field.name, message), fixed: false });
+                }
+            }
+            for (index, keys) in unique_indexes.iter().enumerate() {
+                let values: Vec<Value> = keys.iter().map(|k| object.get_value(k.as_str())).collect::<Result<Vec<Value>>>()?;
+                if values.iter().any(|v| v.is_null()) {
+                    continue
+                }
+                let key = values.iter().map(|v| format!("{}", v)).collect::<Vec<String>>().join(", ");
+                if let Some(existing) = seen[index].get(&key) {
+                    issues.push(Issue {
+                        kind: IssueKind::Duplicate,
+                        model: model_name.clone(),
+                        record: record.clone(),
+                        message: format!("({}) = ({}) is also used by {}", keys.join(", "), key, existing),
+                        fixed: false,
+                    });
+                } else {
+                    seen[index].insert(key, record.clone());
+                }
+            }
+            for relation in model.relations() {
+                if relation.is_vec || !relation.has_foreign_key {
+                    continue
+                }
+                let Some(message) = check_relation(ctx, &object, relation).await? else {
+                    continue
+                };
+                if relation.is_optional() {
+                    if command.fix {
+                        nullify_orphan(&object, relation).await?;
+                    }
+                    issues.push(Issue { kind: IssueKind::Orphan, model: model_name.clone(), record: record.clone(), message, fixed: command.fix });
+                } else {
+                    if command.delete_orphans {
+                        orphans.push(Orphan { object: object.clone(), issue: issues.len() });
+                    }
+                    issues.push(Issue { kind: IssueKind::Orphan, model: model_name.clone(), record: record.clone(), message, fixed: false });
+                    // a record is deleted once, whichever of its relations is orphaned
+                    break
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// a batch which fails to load is loaded again record by record, the records which still fail are
+// reported, returns the loaded records and the key values of the last record of the batch
+async fn load_one_by_one(ctx: &transaction::Ctx, model: &'static Model, last: Option<Vec<Value>>, options: BatchOptions, issues: &mut Vec<Issue>) -> Result<Option<(Vec<Object>, Vec<Value>)>> {
+    let keys = match model.primary_index() {
+        Some(index) => index.keys().clone(),
+        None => Err(Error::new(format!("{} has no primary key", model.path.join("."))))?,
+    };
+    let select: IndexMap<String, Value> = keys.iter().map(|k| (k.clone(), Value::Bool(true))).collect();
+    let mut key_batches = Batches::new(ctx, model, &teon!({"select": Value::Dictionary(select)}), options)?;
+    if let Some(last) = last {
+        key_batches.resume_after(last)?;
+    }
+    let Some(key_objects) = key_batches.next().await? else {
+        return Ok(None);
+    };
+    let mut objects = vec![];
+    for key_object in key_objects {
+        let mut r#where = teon!({});
+        for key in &keys {
+            r#where.as_dictionary_mut().unwrap().insert(key.clone(), key_object.get_value(key)?);
+        }
+        match ctx.find_unique::<Object>(model, &teon!({"where": r#where.clone()}), None, path![]).await {
+            Ok(Some(object)) => objects.push(object),
+            Ok(None) => (),
+            Err(err) => issues.push(Issue {
+                kind: IssueKind::Unreadable,
+                model: model.path.join("."),
+                record: format!("{}", r#where),
+                message: format!("cannot be loaded: {}", err.message),
+                fixed: false,
+            }),
+        }
+    }
+    Ok(key_batches.last().map(|last| (objects, last.to_vec())))
+}
+
+async fn check_relation(ctx: &transaction::Ctx, object: &Object, relation: &'static Relation) -> Result<Option<String>> {
+    let mut r#where = teon!({});
+    for (field, reference) in relation.iter() {
+        let value = object.get_value(field)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        r#where.as_dictionary_mut().unwrap().insert(reference.to_owned(), value);
+    }
+    let related_model = match ctx.namespace().model_at_path(&relation.model_path()) {
+        Some(model) => model,
+        None => Err(Error::new(format!("model not found: {}", relation.model_path().join("."))))?,
+    };
+    if ctx.count(related_model, &teon!({"where": r#where.clone()}), path![]).await? > 0 {
+        Ok(None)
+    } else {
+        Ok(Some(format!("{} references missing {} {}", relation.name, relation.model.join("."), r#where)))
+    }
+}
+
+async fn nullify_orphan(object: &Object, relation: &'static Relation) -> Result<()> {
+    for field in relation.fields() {
+        object.set_value(field, Value::Null)?;
+    }
+    object.save().await
+}
+
+// the records are always listed, and only deleted with `--yes'
+async fn delete_orphans(orphans: &[Orphan], yes: bool, issues: &mut [Issue]) -> Result<()> {
+    info_message(format!("{} record(s) with orphaned required foreign keys:", orphans.len()));
+    for orphan in orphans {
+        let issue = &issues[orphan.issue];
+        info_message(format!("  {} {}", issue.model.bold(), issue.record));
+    }
+    if !yes {
+        info_message("no records deleted, pass --yes to delete them");
+        return Ok(());
+    }
+    for orphan in orphans {
+        orphan.object.delete().await?;
+        issues[orphan.issue].fixed = true;
+    }
+    Ok(())
+}
+
+fn check_value(ctx: &transaction::Ctx, r#type: &Type, value: &Value) -> Option<String> {
+    if value.is_null() {
+        return if r#type.is_optional() { None } else { Some("unexpected null value".to_owned()) };
+    }
+    let matches = match r#type.unwrap_optional() {
+        Type::Bool => value.is_bool(),
+        Type::Int | Type::Int64 => value.is_any_int(),
+        Type::Float32 | Type::Float => value.is_any_float() || value.is_any_int(),
+        Type::Decimal => value.is_decimal(),
+        Type::String => value.is_string(),
+        Type::ObjectId => value.is_object_id(),
+        Type::Date => value.is_date(),
+        Type::DateTime => value.is_datetime(),
+        Type::EnumVariant(reference) => {
+            let member = match value {
+                Value::String(s) => s.as_str(),
+                Value::EnumVariant(e) => e.value.as_str(),
+                _ => return Some(format!("expect enum member, found {}", value)),
+            };
+            let r#enum = ctx.namespace().enum_at_path(&reference.str_path());
+            return match r#enum {
+                Some(r#enum) if !r#enum.members.iter().any(|m| m.name == member) => Some(format!("`{}' is not a member of {}", member, reference.string_path().join("."))),
+                _ => None,
+            };
+        }
+        Type::Array(inner) => match value.as_array() {
+            Some(values) => return values.iter().find_map(|v| check_value(ctx, inner.as_ref(), v)),
+            None => false,
+        },
+        _ => true,
+    };
+    if matches {
+        None
+    } else {
+        Some(format!("expect {}, found {}", r#type.unwrap_optional(), value))
+    }
+}
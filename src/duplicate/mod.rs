@@ -0,0 +1,93 @@
+use std::future::Future;
+use std::pin::Pin;
+use key_path::{KeyPath, path};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{COPY, ENTRY, FIND, MANY, NESTED, SINGLE};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Field, Model, Object, Relation};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::pipeline;
+use teo_runtime::pipeline::pipeline::Pipeline;
+use teo_runtime::request;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+
+const DATA_KEY: &str = "onDuplicate";
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("onDuplicate", |args: Arguments, field: &mut Field| {
+        let pipeline: Pipeline = args.get("pipeline")?;
+        field.data.insert(DATA_KEY.to_owned(), pipeline.into());
+        Ok(())
+    });
+}
+
+pub fn on_duplicate(field: &Field) -> Option<&Pipeline> {
+    field.data.get(DATA_KEY).and_then(|o| o.as_pipeline())
+}
+
+// only to many relations whose foreign key is on the related records can be copied along
+pub fn duplicable_relation<'a>(model: &'a Model, name: &str) -> Option<(&'a Relation, &'static Model)> {
+    let relation = model.relation(name)?;
+    if !relation.is_vec || relation.through.is_some() {
+        return None;
+    }
+    let related = Ctx::main_namespace().model_at_path(&relation.model_path())?;
+    let primary_keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    relation.fields.iter().all(|f| primary_keys.contains(f)).then_some((relation, related))
+}
+
+/// Copies `object` and the records of `relations`, e.g. `{"posts": {"relations": {"comments": true}}}`.
+///
+/// Fields are copied like the `copy` action does, `@onDuplicate` pipelines receive the original
+/// value and `copy` overrides the result.
+pub fn duplicate<'a>(
+    ctx: &'a transaction::Ctx,
+    object: &'a Object,
+    copy: Option<&'a Value>,
+    relations: Option<&'a Value>,
+    req_ctx: Option<request::Ctx>,
+    path: KeyPath,
+) -> Pin<Box<dyn Future<Output = path::Result<Object>> + Send + 'a>> {
+    Box::pin(async move {
+        let model = object.model();
+        let action: Action = COPY | SINGLE | if path.is_empty() { ENTRY } else { NESTED };
+        let new = ctx.new_object_with_teon_and_path(model, &teon!({}), &path, action, req_ctx.clone()).await?;
+        new.update_teon(&object.copied_value()).await?;
+        for field in model.fields() {
+            let Some(pipeline) = on_duplicate(field) else { continue };
+            let pipeline_ctx = pipeline::Ctx::new(object.get_value(field.name.as_str())?.into(), new.clone(), &path + field.name.as_str(), action, ctx.clone(), req_ctx.clone());
+            let value: Value = pipeline_ctx.run_pipeline_into_path_value_error(pipeline).await?.try_into()?;
+            new.set_value(field.name.as_str(), value)?;
+        }
+        if let Some(copy) = copy {
+            new.set_teon_with_path(copy, &(&path + "copy")).await?;
+        }
+        new.save_with_session_and_path(&path).await?;
+        if let Some(Value::Dictionary(relations)) = relations {
+            for (name, nested) in relations {
+                let Some((relation, related)) = duplicable_relation(model, name) else {
+                    return Err(path::Error::value_error(&path + "relations" + name.as_str(), "relation cannot be duplicated"));
+                };
+                let mut r#where = teon!({});
+                for (field, reference) in relation.fields.iter().zip(relation.references.iter()) {
+                    r#where.as_dictionary_mut().unwrap().insert(reference.clone(), object.get_value(field)?);
+                }
+                let find: Action = FIND | MANY | NESTED;
+                let records = ctx.find_many_internal(related, &teon!({"where": r#where}), true, find, req_ctx.clone(), path![]).await?;
+                for (index, record) in records.iter().enumerate() {
+                    let mut parent = teon!({});
+                    for (field, reference) in relation.fields.iter().zip(relation.references.iter()) {
+                        parent.as_dictionary_mut().unwrap().insert(reference.clone(), new.get_value(field)?);
+                    }
+                    let path = &path + "relations" + name.as_str() + index;
+                    duplicate(ctx, record, Some(&parent), nested.get("relations"), req_ctx.clone(), path).await?;
+                }
+            }
+        }
+        Ok(new)
+    })
+}
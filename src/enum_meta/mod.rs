@@ -0,0 +1,114 @@
+use std::fs::read_to_string;
+use indexmap::IndexMap;
+use serde_json::Value as JsonValue;
+use teo_parser::ast::schema::Schema;
+use teo_parser::r#type::Type;
+use teo_parser::traits::identifiable::Identifiable;
+use teo_parser::traits::info_provider::InfoProvider;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_result::{Error, Result, ResultExt};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::object::Object;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::r#enum::Enum;
+use teo_runtime::r#enum::member::Member;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx as AppCtx;
+
+const DATA_KEY: &str = "meta";
+const COMMENT_TOKEN: &str = "@meta";
+
+/// Loads `enumMeta`, which outputs the data at `key` of an enum member.
+pub(crate) fn load_pipeline_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("enumMeta", |args: Arguments, ctx: Ctx| async move {
+        let key: String = args.get("key").err_prefix("enumMeta(key)")?;
+        let field: Option<String> = args.get_optional("field").err_prefix("enumMeta(field)")?;
+        let value = match &field {
+            Some(field) => ctx.object().get_value(field).err_prefix("enumMeta(field)")?,
+            None => ctx.value().as_teon().cloned().unwrap_or(Value::Null),
+        };
+        let name = match value {
+            Value::Null => return Ok(Object::from(Value::Null)),
+            Value::EnumVariant(variant) => variant.value,
+            Value::String(name) => name,
+            _ => Err(Error::new("enumMeta: input is not an enum member"))?,
+        };
+        let field = field.or_else(|| ctx.path().last().and_then(|key| key.as_key()).map(ToOwned::to_owned));
+        let r#enum = member_enum(&ctx, field.as_deref(), &name)?;
+        let member = r#enum.members.iter().find(|m| m.name == name).unwrap();
+        Ok(Object::from(enum_member_meta(member).and_then(|meta| meta.get(&key)).cloned().unwrap_or(Value::Null)))
+    });
+}
+
+/// Loads the data on the `@meta` lines of the enum members, once the schema is loaded.
+pub(crate) fn load_enum_meta(namespace: &mut Namespace, schema: &Schema) -> Result<()> {
+    for enum_declaration in schema.enums() {
+        let Some(source) = schema.source(enum_declaration.source_id()) else { continue };
+        // schemas built in Rust have no file, their members go without data
+        let Ok(content) = read_to_string(&source.file_path) else { continue };
+        let Some(r#enum) = namespace.namespace_mut_or_create_at_path(&enum_declaration.namespace_str_path()).enums.get_mut(enum_declaration.identifier().name()) else { continue };
+        for member_declaration in enum_declaration.members() {
+            let Some(comment) = member_declaration.comment() else { continue };
+            let Some(member) = r#enum.members.iter_mut().find(|m| m.name == member_declaration.identifier().name()) else { continue };
+            let mut meta = IndexMap::new();
+            let mut description = vec![];
+            // the parser keeps only the `@name' and `@description' lines of doc comments
+            for line in content[comment.span().start..comment.span().end].lines() {
+                let line = line.trim().trim_start_matches("///").trim();
+                let Some(json) = line.strip_prefix(COMMENT_TOKEN) else {
+                    description.push(line);
+                    continue;
+                };
+                match serde_json::from_str::<JsonValue>(json) {
+                    Ok(JsonValue::Object(fields)) => meta.extend(fields.iter().map(|(k, v)| (k.clone(), Value::from(v)))),
+                    _ => Err(Error::new(format!("data of enum member {}.{} is not a JSON object", r#enum.path.join("."), member.name)))?,
+                }
+            }
+            if meta.is_empty() {
+                continue;
+            }
+            member.data.insert(DATA_KEY.to_owned(), Value::Dictionary(meta).into());
+            // and the other lines make the description, which the data is left out of
+            if let Some(comment) = member.comment.as_mut() {
+                let description = description.into_iter().filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+                comment.desc = if description.is_empty() { None } else { Some(description) };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The data the `@meta` lines of its doc comment give `member`, if it has any.
+pub fn enum_member_meta(member: &Member) -> Option<&IndexMap<String, Value>> {
+    member.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_dictionary())
+}
+
+fn member_enum(ctx: &Ctx, field: Option<&str>, name: &str) -> Result<&'static Enum> {
+    let namespace = AppCtx::main_namespace();
+    let field_enum = field.and_then(|field| ctx.object().model().field(field)).and_then(|field| match field.r#type.unwrap_optional() {
+        Type::EnumVariant(reference) => namespace.enum_at_path(&reference.str_path()),
+        _ => None,
+    });
+    if let Some(r#enum) = field_enum.filter(|e| e.members.iter().any(|m| m.name == name)) {
+        return Ok(r#enum);
+    }
+    let mut enums = vec![];
+    collect_meta_enums(namespace, name, &mut enums);
+    match enums.as_slice() {
+        [r#enum] => Ok(*r#enum),
+        [] => Err(Error::new(format!("enumMeta: no enum with data has a member `{}'", name))),
+        _ => Err(Error::new(format!("enumMeta: member `{}' is found in {}, pass a field of the enum", name, enums.iter().map(|e| e.path.join(".")).collect::<Vec<_>>().join(", ")))),
+    }
+}
+
+fn collect_meta_enums(namespace: &'static Namespace, name: &str, enums: &mut Vec<&'static Enum>) {
+    for r#enum in namespace.enums.values() {
+        if r#enum.members.iter().any(|m| enum_member_meta(m).is_some()) && r#enum.members.iter().any(|m| m.name == name) {
+            enums.push(r#enum);
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_meta_enums(child, name, enums);
+    }
+}
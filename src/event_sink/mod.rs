@@ -0,0 +1,239 @@
+pub mod nats;
+
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use chrono::{SecondsFormat, Utc};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_result::Result;
+use teo_runtime::model::{Field, Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_teon::value::Value;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use uuid::Uuid;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+/// How the change events are written.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EventFormat {
+    /// The event as a JSON object.
+    #[default]
+    Json,
+    /// The event as the `payload` of an envelope with its `schema`, in the form of the JSON
+    /// converter of Kafka Connect, so sinks of Kafka Connect read it without a schema registry.
+    SchemaJson,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventSinkOptions {
+    /// The topic or subject of the events, `{model}` is replaced with the model path joined by
+    /// dots.
+    pub topic: String,
+    pub format: EventFormat,
+    /// The models whose changes are published, all models when it's empty.
+    pub models: Vec<String>,
+}
+
+impl Default for EventSinkOptions {
+
+    fn default() -> Self {
+        Self { topic: "teo.{model}".to_owned(), format: EventFormat::Json, models: vec![] }
+    }
+}
+
+/// An event written for a sink.
+#[derive(Debug, Clone)]
+pub struct EventMessage {
+    pub topic: String,
+    /// The primary key of the record as JSON, Kafka keeps the events of a record in one partition
+    /// by it.
+    pub key: String,
+    pub payload: Vec<u8>,
+}
+
+/// Publishes the change events, e.g. to Kafka with a client crate of its own. The events of a
+/// sink are published one after another in the order of the commits, a failed event is logged
+/// and not retried.
+pub trait EventSink: Send + Sync {
+    fn publish(&self, message: EventMessage) -> BoxFuture<'static, Result<()>>;
+}
+
+impl<F, Fut> EventSink for F where
+    F: Fn(EventMessage) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send + 'static {
+    fn publish(&self, message: EventMessage) -> BoxFuture<'static, Result<()>> {
+        Box::pin(self(message))
+    }
+}
+
+pub(crate) struct RegisteredSink {
+    sink: Arc<dyn EventSink>,
+    options: EventSinkOptions,
+    // started on the first event, the app may be set up outside of the runtime
+    queue: OnceLock<UnboundedSender<EventMessage>>,
+}
+
+impl RegisteredSink {
+
+    pub(crate) fn new(sink: Arc<dyn EventSink>, options: EventSinkOptions) -> Self {
+        Self { sink, options, queue: OnceLock::new() }
+    }
+
+    fn send(&self, message: EventMessage) {
+        let queue = self.queue.get_or_init(|| {
+            let (sender, mut receiver) = unbounded_channel::<EventMessage>();
+            let sink = self.sink.clone();
+            tokio::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    let topic = message.topic.clone();
+                    if let Err(error) = sink.publish(message).await {
+                        info_message(format!("event sink failed to publish to {}: {}", topic, error.message()));
+                    }
+                }
+            });
+            sender
+        });
+        let _ = queue.send(message);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Delete,
+}
+
+impl ChangeKind {
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Create => "create",
+            ChangeKind::Update => "update",
+            ChangeKind::Delete => "delete",
+        }
+    }
+}
+
+/// A written record, published when its transaction commits.
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeEvent {
+    model: &'static Model,
+    kind: ChangeKind,
+    key: JsonValue,
+    record: JsonValue,
+    at: String,
+}
+
+/// The change event of `object`, when an event sink is registered.
+pub(crate) fn change_event(object: &Object, kind: ChangeKind) -> Option<ChangeEvent> {
+    if Ctx::settings().event_sinks.is_empty() {
+        return None;
+    }
+    let model = object.model();
+    let mut key = Map::new();
+    for item in model.primary_index().map(|i| i.items().clone()).unwrap_or_default() {
+        key.insert(item.field.clone(), plain_json(&object.get_value(&item.field).ok()?));
+    }
+    let mut record = Map::new();
+    for field in event_fields(model) {
+        record.insert(field.name.clone(), plain_json(&object.get_value(&field.name).ok()?));
+    }
+    Some(ChangeEvent {
+        model,
+        kind,
+        key: JsonValue::Object(key),
+        record: JsonValue::Object(record),
+        at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+/// Hands the events of committed writes to the sinks.
+pub(crate) fn publish_changes(events: Vec<ChangeEvent>) {
+    for registered in Ctx::settings().event_sinks.clone() {
+        let options = &registered.options;
+        for event in &events {
+            let path = event.model.path.join(".");
+            if !options.models.is_empty() && !options.models.contains(&path) {
+                continue;
+            }
+            let body = json!({
+                "id": Uuid::new_v4().to_string(),
+                "model": path,
+                "kind": event.kind.as_str(),
+                "key": event.key,
+                "record": event.record,
+                "at": event.at,
+            });
+            let payload = match options.format {
+                EventFormat::Json => body,
+                EventFormat::SchemaJson => json!({ "schema": event_schema(event.model), "payload": body }),
+            };
+            registered.send(EventMessage {
+                topic: options.topic.replace("{model}", &path),
+                key: event.key.to_string(),
+                payload: payload.to_string().into_bytes(),
+            });
+        }
+    }
+}
+
+fn event_fields(model: &Model) -> impl Iterator<Item = &Field> {
+    model.fields.values().filter(|f| !f.r#virtual && !f.dropped && !f.read.is_no_read())
+}
+
+// dates and decimals are written as strings instead of the `$date' objects of teon
+fn plain_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Decimal(decimal) => json!(decimal.normalized().to_string()),
+        Value::Date(date) => json!(date.format("%Y-%m-%d").to_string()),
+        Value::DateTime(datetime) => json!(datetime.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        Value::EnumVariant(variant) => json!(variant.value),
+        Value::Array(values) => JsonValue::Array(values.iter().map(plain_json).collect()),
+        Value::Dictionary(values) => JsonValue::Object(values.iter().map(|(k, v)| (k.clone(), plain_json(v))).collect()),
+        _ => JsonValue::try_from(value).unwrap_or(JsonValue::Null),
+    }
+}
+
+fn event_schema(model: &Model) -> JsonValue {
+    let name = model.path.join(".");
+    let key = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    let key_fields: Vec<JsonValue> = key.iter().filter_map(|k| model.field(k)).map(|f| field_schema(f, false)).collect();
+    let record_fields: Vec<JsonValue> = event_fields(model).map(|f| field_schema(f, f.is_optional())).collect();
+    json!({
+        "type": "struct",
+        "name": format!("{}.Event", name),
+        "optional": false,
+        "fields": [
+            { "field": "id", "type": "string", "optional": false },
+            { "field": "model", "type": "string", "optional": false },
+            { "field": "kind", "type": "string", "optional": false },
+            { "field": "key", "type": "struct", "name": format!("{}.Key", name), "optional": false, "fields": key_fields },
+            { "field": "record", "type": "struct", "name": name, "optional": false, "fields": record_fields },
+            { "field": "at", "type": "string", "optional": false },
+        ],
+    })
+}
+
+fn field_schema(field: &Field, optional: bool) -> JsonValue {
+    let mut schema = type_schema(field.r#type.unwrap_optional());
+    let map = schema.as_object_mut().unwrap();
+    map.insert("field".to_owned(), json!(field.name));
+    map.insert("optional".to_owned(), json!(optional));
+    schema
+}
+
+fn type_schema(r#type: &Type) -> JsonValue {
+    match r#type {
+        Type::Bool => json!({ "type": "boolean" }),
+        Type::Int => json!({ "type": "int32" }),
+        Type::Int64 => json!({ "type": "int64" }),
+        Type::Float32 => json!({ "type": "float" }),
+        Type::Float => json!({ "type": "double" }),
+        Type::Array(inner) => json!({ "type": "array", "items": type_schema(inner.unwrap_optional()) }),
+        Type::Dictionary(inner) => json!({ "type": "map", "keys": { "type": "string" }, "values": type_schema(inner.unwrap_optional()) }),
+        _ => json!({ "type": "string" }),
+    }
+}
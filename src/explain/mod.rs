@@ -0,0 +1,166 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use key_path::path;
+use once_cell::sync::Lazy;
+use teo_result::{Error, Result};
+use teo_runtime::action::action::{ENTRY, FIND, MANY};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_teon::value::Value;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing::instrument::WithSubscriber;
+use tracing::span::{Attributes, Id, Record};
+use crate::app::ctx::Ctx;
+use crate::app::database::flavor::MySQLServer;
+use crate::message::info_message;
+
+tokio::task_local! {
+    static CAPTURED: Arc<Mutex<Vec<String>>>;
+    static EXPLAINED: Arc<Mutex<Vec<QueryPlan>>>;
+    static TRACED: Arc<Mutex<Vec<String>>>;
+}
+
+// the SQL connectors hand their statements to quaint, which traces each of them with a `query'
+// field. The dispatch is created once, so the interest of the quaint callsites includes it
+static CAPTURE: Lazy<Dispatch> = Lazy::new(|| Dispatch::new(Capture));
+
+/// A statement a read issued and the plan of the database for it.
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    pub statement: String,
+    pub plan: Value,
+}
+
+/// Finds the records of the model at the dot separated `model` path with `finder` and returns the
+/// plans of the statements the read issued. Plans are only available for SQL databases.
+pub async fn explain(model: &str, finder: &Value) -> Result<Vec<QueryPlan>> {
+    let path: Vec<&str> = model.split('.').collect();
+    let Some(model) = Ctx::conn_ctx().namespace().model_at_path(&path) else {
+        Err(Error::new(format!("model `{}' is not found", path.join("."))))?
+    };
+    let plans = Arc::new(Mutex::new(vec![]));
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx());
+    EXPLAINED.scope(plans.clone(), async {
+        ctx.find_many_internal(model, finder, false, FIND | MANY | ENTRY, None, path![]).await.map_err(|e| Error::new(e.message))
+    }).await?;
+    let plans = plans.lock().unwrap().clone();
+    Ok(plans)
+}
+
+fn explaining() -> bool {
+    EXPLAINED.try_with(|_| ()).is_ok()
+}
+
+/// Runs the read `f`, explaining its statements when it's explained or slow.
+pub(crate) async fn explained_read<F: Future>(transaction: &dyn Transaction, provider: Option<Database>, mysql: Option<MySQLServer>, model: &Model, action: &str, f: F) -> F::Output {
+    let threshold = Ctx::settings().slow_query_threshold;
+    if (threshold.is_none() && !explaining()) || CAPTURED.try_with(|_| ()).is_ok() {
+        return f.await;
+    }
+    let statements = Arc::new(Mutex::new(vec![]));
+    let started = std::time::Instant::now();
+    let output = CAPTURED.scope(statements.clone(), f.with_subscriber(CAPTURE.clone())).await;
+    let elapsed = started.elapsed();
+    let statements = statements.lock().unwrap().clone();
+    if explaining() {
+        let plans = plans(transaction, provider, mysql, statements).await;
+        let _ = EXPLAINED.try_with(|explained| explained.lock().unwrap().extend(plans));
+    } else if threshold.is_some_and(|threshold| elapsed >= threshold) && !Ctx::cli().silent {
+        log_slow_read(transaction, provider, mysql, model, action, elapsed, statements).await;
+    }
+    output
+}
+
+/// Runs `f` with the statements it issues kept in `statements`, for the span of a connector call.
+pub(crate) async fn traced_statements<F: Future>(statements: Arc<Mutex<Vec<String>>>, f: F) -> F::Output {
+    TRACED.scope(statements, f.with_subscriber(CAPTURE.clone())).await
+}
+
+async fn log_slow_read(transaction: &dyn Transaction, provider: Option<Database>, mysql: Option<MySQLServer>, model: &Model, action: &str, elapsed: Duration, statements: Vec<String>) {
+    let mut message = format!("{}.{} took {}ms", model.path.join("."), action, elapsed.as_millis());
+    if statements.is_empty() {
+        message.push_str(", plans are only available for SQL databases");
+    }
+    for QueryPlan { statement, plan } in plans(transaction, provider, mysql, statements).await {
+        message.push_str(&format!("\n  {}\n  {}", statement, rendered(&plan)));
+    }
+    info_message(message);
+}
+
+// `ANALYZE' runs the statement, only reads are explained. MySQL servers of an unknown flavor are
+// explained like MySQL 8
+async fn plans(transaction: &dyn Transaction, provider: Option<Database>, mysql: Option<MySQLServer>, statements: Vec<String>) -> Vec<QueryPlan> {
+    let mut plans = vec![];
+    for statement in statements {
+        if !statement.trim_start().get(..6).is_some_and(|s| s.eq_ignore_ascii_case("SELECT")) {
+            continue;
+        }
+        let explain = match provider {
+            Some(Database::SQLite) => format!("EXPLAIN QUERY PLAN {}", statement),
+            Some(Database::PostgreSQL) => format!("EXPLAIN (ANALYZE, FORMAT JSON) {}", statement),
+            Some(Database::MySQL) => match mysql {
+                Some(mysql) => mysql.explain(&statement),
+                None => format!("EXPLAIN ANALYZE {}", statement),
+            },
+            Some(Database::MongoDB) | None => continue,
+        };
+        let plan = match transaction.query_raw(&Value::String(explain)).await {
+            Ok(plan) => plan,
+            Err(e) => Value::String(format!("cannot explain: {}", e.message())),
+        };
+        plans.push(QueryPlan { statement, plan });
+    }
+    plans
+}
+
+fn rendered(plan: &Value) -> String {
+    match serde_json::Value::try_from(plan) {
+        Ok(json) => json.to_string(),
+        Err(_) => format!("{:?}", plan),
+    }
+}
+
+struct Capture;
+
+struct QueryVisitor(Option<String>);
+
+impl Visit for QueryVisitor {
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "query" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+impl Subscriber for Capture {
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("quaint")
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) { }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) { }
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = QueryVisitor(None);
+        event.record(&mut visitor);
+        if let Some(query) = visitor.0 {
+            let _ = TRACED.try_with(|traced| traced.lock().unwrap().push(query.clone()));
+            let _ = CAPTURED.try_with(|captured| captured.lock().unwrap().push(query));
+        }
+    }
+
+    fn enter(&self, _: &Id) { }
+
+    fn exit(&self, _: &Id) { }
+}
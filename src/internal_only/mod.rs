@@ -0,0 +1,101 @@
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::{Field, Model};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const DATA_KEY: &str = "internalOnly";
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("internalOnly", |_args: Arguments, field: &mut Field| {
+        field.data.insert(DATA_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+pub fn is_internal_only(field: &Field) -> bool {
+    field.data.contains_key(DATA_KEY)
+}
+
+// internal only fields are written by programs, seeders and handlers, never by request input
+pub(crate) fn reject_internal_only_input(model: &Model, action: &str, json_body: &JsonValue) -> BoxedResult<()> {
+    let keys: &[&str] = match action {
+        "create" | "createMany" => &["create"],
+        "update" | "updateMany" => &["update"],
+        "upsert" => &["create", "update"],
+        "copy" | "copyMany" => &["copy"],
+        _ => return Ok(()),
+    };
+    for key in keys {
+        if let Some(value) = json_body.get(key) {
+            check_many(model, value, &path![*key])?;
+        }
+    }
+    Ok(())
+}
+
+fn check_many(model: &Model, value: &JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    match value {
+        JsonValue::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                check_data(model, item, &(path + index))?;
+            }
+            Ok(())
+        }
+        _ => check_data(model, value, path),
+    }
+}
+
+fn check_data(model: &Model, data: &JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    let Some(map) = data.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in map {
+        if let Some(field) = model.field(key) {
+            if is_internal_only(field) {
+                return Err(path::Error::value_error(path + key.as_str(), "field is internal only").into());
+            }
+        } else if let Some(relation) = model.relation(key) {
+            let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else {
+                continue;
+            };
+            check_nested(related, value, &(path + key.as_str()))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_nested(model: &Model, value: &JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    let Some(map) = value.as_object() else {
+        return Ok(());
+    };
+    for (operation, value) in map {
+        let path = path + operation.as_str();
+        let items: Vec<(&JsonValue, KeyPath)> = match value {
+            JsonValue::Array(items) => items.iter().enumerate().map(|(i, item)| (item, &path + i)).collect(),
+            _ => vec![(value, path.clone())],
+        };
+        for (item, path) in items {
+            match operation.as_str() {
+                "create" | "createMany" => check_data(model, item, &path)?,
+                "update" | "updateMany" => match item.get("update") {
+                    Some(update) => check_data(model, update, &(&path + "update"))?,
+                    None => check_data(model, item, &path)?,
+                },
+                "upsert" | "connectOrCreate" => {
+                    for key in ["create", "update"] {
+                        if let Some(data) = item.get(key) {
+                            check_data(model, data, &(&path + key))?;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
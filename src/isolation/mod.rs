@@ -0,0 +1,88 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::path;
+use teo_teon::value::Value;
+
+/// How much the transactions running at the same time see of each other.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+
+    fn sql(&self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// The isolation level of a transaction, and how often it's run again when it fails to
+/// serialize with a concurrent one. The database's own level is used without `isolation`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TransactionOptions {
+    pub isolation: Option<IsolationLevel>,
+    pub retries: usize,
+}
+
+tokio::task_local! {
+    static ISOLATION: IsolationLevel;
+}
+
+/// Runs `f` with the isolation level of `options`, retrying it while it fails to serialize.
+pub(crate) async fn run_isolated<T, F, Fut>(options: TransactionOptions, f: F) -> path::Result<T> where
+    F: Fn() -> Fut,
+    Fut: Future<Output = path::Result<T>> {
+    let mut retries = options.retries;
+    loop {
+        let result = match options.isolation {
+            Some(level) => ISOLATION.scope(level, f()).await,
+            None => f().await,
+        };
+        match result {
+            Err(error) if retries > 0 && is_serialization_failure(&error) => retries -= 1,
+            result => return result,
+        }
+    }
+}
+
+/// Sets the isolation level in scope on `transaction` before its first statement.
+pub(crate) async fn set_isolation(transaction: &dyn Transaction, provider: Option<Database>, isolated: &AtomicBool) -> Result<()> {
+    let Ok(level) = ISOLATION.try_with(|level| *level) else {
+        return Ok(());
+    };
+    if !transaction.is_transaction() || isolated.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let set = format!("SET TRANSACTION ISOLATION LEVEL {}", level.sql());
+    match provider {
+        Some(Database::PostgreSQL) => {
+            transaction.query_raw(&Value::String(set)).await?;
+        }
+        // MySQL sets the level of the next transaction only, the empty one is started again
+        Some(Database::MySQL) => {
+            for statement in ["COMMIT".to_owned(), set, "START TRANSACTION".to_owned()] {
+                transaction.query_raw(&Value::String(statement)).await?;
+            }
+        }
+        // every transaction of SQLite is serializable
+        Some(Database::SQLite) => (),
+        _ => Err(Error::new("isolation levels are a feature of the SQL databases"))?,
+    }
+    Ok(())
+}
+
+// PostgreSQL 40001 and 40P01, MySQL 1213 and SQLite 5
+fn is_serialization_failure(error: &path::Error) -> bool {
+    error.fields.iter().flatten().map(|(_, message)| message.as_str()).chain([error.message.as_str()]).any(|message| {
+        message.contains("could not serialize access") || message.contains("deadlock detected") || message.contains("Deadlock found") || message.contains("database is locked")
+    })
+}
@@ -2,21 +2,54 @@ pub mod cli;
 pub mod app;
 pub mod server;
 pub mod migrate;
+pub(crate) mod mock;
 pub mod purge;
+pub mod doctor;
+pub mod anonymize;
+pub mod duplicate;
+pub mod internal_only;
+pub mod position;
+pub mod retention;
+pub mod backfill;
+pub mod archive;
+pub mod scope;
+pub mod state;
+pub mod tree;
+pub mod pipeline;
 pub mod seeder;
+pub mod object;
+pub mod test;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod schema;
+pub mod source;
+pub mod explain;
+pub mod event_sink;
+pub mod telemetry;
+pub mod savepoint;
+pub mod isolation;
+pub mod on_delete;
+pub mod counter_cache;
+pub mod enum_meta;
 mod message;
+mod utils;
 
 pub mod prelude {
     pub use crate::app::App;
+    pub use crate::app::Plugin;
     pub use crate::app;
     pub use crate::cli::entrance::Entrance;
     pub use crate::cli::runtime_version::RuntimeVersion;
-    pub use crate::server::static_files::serve_static_files;
+    pub use crate::server::static_files::{serve_static_files, serve_static_files_with_options, StaticFilesOptions};
     pub use teo_runtime::namespace::Namespace;
     pub extern crate teo_result;
     pub use teo_result::{Error, Result, ResultExt};
     pub extern crate tokio;
     pub use tokio::main;
+    pub extern crate async_trait;
+    pub use async_trait::async_trait;
     pub extern crate key_path;
     pub use key_path::path;
     pub use teo_runtime::request;
@@ -28,6 +61,10 @@ pub mod prelude {
     pub use teo_runtime::r#struct::Struct;
     pub use teo_runtime::interface_enum_variant::InterfaceEnumVariant;
     pub use teo_runtime::object;
+    pub use crate::object::relation::LazyRelation;
+    pub use crate::seeder::factory::Factory;
+    pub use crate::server::limits::Limits;
+    pub use crate::object::finder::Finder;
     pub use teo_runtime::interface;
     pub use teo_runtime::connection::transaction;
     pub use teo_teon::value::Value;
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use teo_result::{Error, Result};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+#[derive(Debug)]
+pub struct SchemaDrift {
+    pub model: String,
+    pub problem: String,
+}
+
+/// Compares the tables and columns of the SQL databases with the models.
+pub(crate) async fn check_schema(strict: bool, silent: bool) -> Result<()> {
+    let drifts = schema_drifts().await?;
+    if !silent || strict {
+        for drift in &drifts {
+            info_message(format!("schema drift in {}: {}", drift.model, drift.problem));
+        }
+    }
+    if strict && !drifts.is_empty() {
+        Err(Error::new(format!("database doesn't match the schema, {} problem(s) found", drifts.len())))?
+    }
+    Ok(())
+}
+
+pub async fn schema_drifts() -> Result<Vec<SchemaDrift>> {
+    let ctx = Ctx::conn_ctx();
+    let mut drifts = vec![];
+    for (namespace_path, connection) in ctx.connections_iter() {
+        let Some(namespace) = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else {
+            Err(Error::new(format!("namespace `{}' is not found", namespace_path.join("."))))?
+        };
+        let Some(database) = namespace.connector.as_ref().map(|c| c.provider) else { continue };
+        if database.is_mongo() {
+            continue;
+        }
+        let transaction = connection.no_transaction().await?;
+        for model in namespace.models_under_connector() {
+            let rows = transaction.query_raw(&Value::String(columns_query(database, &model.table_name))).await?;
+            let columns = live_columns(database, &rows);
+            drifts.extend(compare(model, &columns));
+        }
+    }
+    Ok(drifts)
+}
+
+pub(crate) fn columns_query(database: Database, table: &str) -> String {
+    let table = table.replace('\'', "''");
+    match database {
+        Database::SQLite => format!("SELECT name, \"notnull\" AS required FROM pragma_table_info('{}')", table),
+        Database::MySQL => format!("SELECT COLUMN_NAME AS name, IS_NULLABLE AS nullable FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = '{}'", table),
+        _ => format!("SELECT column_name AS name, is_nullable AS nullable FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = '{}'", table),
+    }
+}
+
+// column name to nullability, a missing table has no rows
+pub(crate) fn live_columns(database: Database, rows: &Value) -> BTreeMap<String, bool> {
+    let Some(rows) = rows.as_array() else {
+        return BTreeMap::new();
+    };
+    rows.iter().filter_map(|row| {
+        let nullable = match database {
+            Database::SQLite => row.get("required").and_then(|r| r.to_int64()).map(|r| r == 0),
+            _ => row.get("nullable").and_then(|n| n.as_str()).map(|n| n.eq_ignore_ascii_case("YES")),
+        };
+        Some((row.get("name")?.as_str()?.to_owned(), nullable.unwrap_or(true)))
+    }).collect()
+}
+
+fn compare(model: &Model, columns: &BTreeMap<String, bool>) -> Vec<SchemaDrift> {
+    let name = model.path.join(".");
+    if columns.is_empty() {
+        return vec![SchemaDrift { model: name, problem: format!("table `{}' is missing", model.table_name) }];
+    }
+    let primary_keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    let mut drifts = vec![];
+    for field in model.fields() {
+        if field.r#virtual {
+            continue;
+        }
+        match columns.get(&field.column_name) {
+            None => drifts.push(SchemaDrift { model: name.clone(), problem: format!("column `{}' is missing", field.column_name) }),
+            // SQLite reports integer primary keys as nullable
+            Some(nullable) if !primary_keys.contains(&field.name) && *nullable != field.is_optional() => drifts.push(SchemaDrift {
+                model: name.clone(),
+                problem: format!("column `{}' is {}, the field is {}", field.column_name, if *nullable { "nullable" } else { "not null" }, if field.is_optional() { "optional" } else { "required" }),
+            }),
+            _ => (),
+        }
+    }
+    drifts
+}
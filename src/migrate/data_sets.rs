@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use key_path::path;
+use teo_parser::ast::schema::Schema;
+use teo_parser::r#type::Type;
+use teo_parser::traits::identifiable::Identifiable;
+use teo_parser::traits::named_identifiable::NamedIdentifiable;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::data_set::DataSet;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::schema::load::load_data_sets::load_data_sets;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::seeder::seed::seed_dataset;
+
+const DATA_KEY: &str = "dataSetVersion";
+
+#[derive(Debug)]
+pub struct DataSetVersion {
+    pub name: String,
+    pub version: i32,
+    /// Whether the records of this version are inserted by this run, or would be in a dry run.
+    pub inserted: bool,
+}
+
+/// Inserts the records of the data sets which run once, when their declared version is above the
+/// one stored in the model marked with `@dataSetVersion`. A data set runs once when its doc
+/// comment has a `@runOnce` line, a `@version` line gives its version, which defaults to 1:
+///
+/// ```teo
+/// /// @runOnce
+/// /// @version 2
+/// dataset countries {
+///   group Country {
+///     record fr { code: "FR" }
+///   }
+/// }
+/// ```
+///
+/// A new version seeds the data set like `teo seed` does, so the records added since the last
+/// version are inserted and the removed ones are deleted, the others are left as they are. The
+/// version is saved after the records, a run which fails in between seeds the data set again the
+/// next time, which inserts the records it missed only. These data sets are left out of `teo seed`
+/// and autoseeding.
+pub async fn migrate_data_sets(ctx: transaction::Ctx, dry_run: bool, silent: bool) -> Result<Vec<DataSetVersion>> {
+    let declared = run_once_data_sets(&Ctx::schema())?;
+    let mut result = vec![];
+    if declared.is_empty() {
+        return Ok(result);
+    }
+    let version_model = version_model(&ctx)?;
+    let names = declared.keys().cloned().collect();
+    let data_sets = load_data_sets(Ctx::main_namespace(), Some(&names), false, &Ctx::schema())?;
+    for data_set in &data_sets {
+        let name = data_set.name.join(".");
+        let version = declared[&name];
+        let stored = load_version(&ctx, version_model, &name).await?;
+        let inserted = stored.is_none_or(|stored| stored < version);
+        if inserted && !dry_run {
+            seed_dataset(data_set, ctx.clone()).await;
+            save_version(&ctx, version_model, &name, version).await?;
+        }
+        if !silent && inserted {
+            info_message(format!("{}: version {} {}", name, version, if dry_run { "is pending" } else { "inserted" }));
+        }
+        result.push(DataSetVersion { name, version, inserted });
+    }
+    Ok(result)
+}
+
+/// The names of the data sets which run once, with their versions.
+pub(crate) fn run_once_data_sets(schema: &Schema) -> Result<BTreeMap<String, i32>> {
+    let mut result = BTreeMap::new();
+    for data_set in schema.data_sets() {
+        let Some(comment) = data_set.comment() else { continue };
+        let Some(source) = schema.source(data_set.source_id()) else { continue };
+        let content = match read_to_string(&source.file_path) {
+            Ok(content) => content,
+            Err(e) => Err(Error::new(format!("cannot read `{}': {}", source.file_path, e)))?,
+        };
+        let name = data_set.string_path().join(".");
+        let mut run_once = false;
+        let mut version = 1;
+        // the parser keeps only the `@name' and `@description' lines of doc comments
+        for line in content[comment.span().start..comment.span().end].lines() {
+            let line = line.trim().trim_start_matches("///").trim();
+            if line == "@runOnce" {
+                run_once = true;
+            } else if let Some(value) = line.strip_prefix("@version") {
+                version = match value.trim().parse::<i32>() {
+                    Ok(version) if version > 0 => version,
+                    _ => Err(Error::new(format!("version of data set `{}' is not a positive integer", name)))?,
+                };
+            }
+        }
+        if !run_once {
+            continue;
+        }
+        if data_set.auto_seed {
+            Err(Error::new(format!("data set `{}' runs once, it's inserted by `teo migrate' and can't be autoseeded", name)))?
+        }
+        let version = result.get(&name).map_or(version, |v: &i32| version.max(*v));
+        result.insert(name, version);
+    }
+    Ok(result)
+}
+
+/// Loads the data sets to seed, without the ones which run once.
+pub(crate) fn load_seed_data_sets(names: Option<&Vec<String>>, all: bool) -> Result<Vec<DataSet>> {
+    let run_once = run_once_data_sets(&Ctx::schema())?;
+    if let Some(name) = names.and_then(|names| names.iter().find(|n| run_once.contains_key(*n))) {
+        Err(Error::new(format!("data set `{}' runs once, it's inserted by `teo migrate'", name)))?
+    }
+    let data_sets = load_data_sets(Ctx::main_namespace(), names, all, &Ctx::schema())?;
+    Ok(data_sets.into_iter().filter(|d| !run_once.contains_key(&d.name.join("."))).collect())
+}
+
+/// Loads `@dataSetVersion`, which marks the model storing the versions of the data sets.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("dataSetVersion", |_args: Arguments, model: &mut Model| {
+        model.data.insert(DATA_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+fn version_model(ctx: &transaction::Ctx) -> Result<&'static Model> {
+    let mut models = vec![];
+    collect_version_models(ctx.namespace(), &mut models);
+    let model = match models.as_slice() {
+        [model] => *model,
+        [] => Err(Error::new("data sets which run once store their versions in a model marked with @dataSetVersion, declare one"))?,
+        _ => Err(Error::new("more than one model is marked with @dataSetVersion"))?,
+    };
+    for (field, r#type) in [("name", Type::String), ("version", Type::Int)] {
+        if model.field(field).map(|f| f.r#type.unwrap_optional()) != Some(&r#type) {
+            Err(Error::new(format!("data set version model {} requires a field `{}' of {}", model.path.join("."), field, r#type)))?
+        }
+    }
+    Ok(model)
+}
+
+fn collect_version_models(namespace: &'static Namespace, models: &mut Vec<&'static Model>) {
+    for model in namespace.models.values() {
+        if model.data.contains_key(DATA_KEY) {
+            models.push(model);
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_version_models(child, models);
+    }
+}
+
+async fn load_version(ctx: &transaction::Ctx, version_model: &'static Model, name: &str) -> Result<Option<i32>> {
+    match ctx.find_unique::<Object>(version_model, &teon!({"where": {"name": name}}), None, path![]).await? {
+        Some(object) => Ok(object.get_value("version")?.to_int()),
+        None => Ok(None),
+    }
+}
+
+async fn save_version(ctx: &transaction::Ctx, version_model: &'static Model, name: &str, version: i32) -> Result<()> {
+    let object = match ctx.find_unique::<Object>(version_model, &teon!({"where": {"name": name}}), None, path![]).await? {
+        Some(object) => object,
+        None => ctx.create_object(version_model, &teon!({"name": name}), None).await?,
+    };
+    object.set_teon(&teon!({"version": version})).await?;
+    object.save().await
+}
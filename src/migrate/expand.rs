@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::{Field, Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::readwrite::read::Read;
+use teo_runtime::readwrite::write::Write;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::migrate::check::{columns_query, live_columns};
+
+const RENAMED_FROM: &str = "renamedFrom";
+const DROPPED_AFTER: &str = "droppedAfter";
+const RETAINED_FOR: &str = "retainedFor";
+const BATCH: usize = 1000;
+
+/// Loads `@renamedFrom` and `@droppedAfter`, which rename the column of a field in two steps.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("renamedFrom", |args: Arguments, field: &mut Field| {
+        let name: String = args.get("name")?;
+        field.data.insert(RENAMED_FROM.to_owned(), Value::String(name).into());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("droppedAfter", |args: Arguments, field: &mut Field| {
+        let version: String = args.get("version")?;
+        parse_version(&version)?;
+        field.data.insert(DROPPED_AFTER.to_owned(), Value::String(version).into());
+        Ok(())
+    });
+}
+
+/// The column `field` was renamed from, if it's still in the database.
+pub fn renamed_from(field: &Field) -> Option<&str> {
+    field.data.get(RENAMED_FROM).and_then(|o| o.as_teon()).and_then(|v| v.as_str())
+}
+
+fn dropped_after(field: &Field) -> Option<&str> {
+    field.data.get(DROPPED_AFTER).and_then(|o| o.as_teon()).and_then(|v| v.as_str())
+}
+
+/// The field whose old column `field` keeps, if it's one.
+pub fn retained_for(field: &Field) -> Option<&str> {
+    field.data.get(RETAINED_FOR).and_then(|o| o.as_teon()).and_then(|v| v.as_str())
+}
+
+/// Adds a saved only field for each old column which is kept.
+pub(crate) fn settle_renamed_fields(namespace: &mut Namespace) -> Result<()> {
+    let version = Ctx::settings().schema_version.clone();
+    for model in namespace.models.values_mut() {
+        let mut retained = vec![];
+        for field in model.fields.values() {
+            let name = format!("{}.{}", model.path.join("."), field.name);
+            let Some(old) = renamed_from(field) else {
+                if dropped_after(field).is_some() {
+                    Err(Error::new(format!("`{}' has `@droppedAfter' without `@renamedFrom', only old columns are dropped later", name)))?
+                }
+                continue
+            };
+            if let (Some(version), Some(after)) = (&version, dropped_after(field)) {
+                if compare_versions(version, after)? == Ordering::Greater {
+                    continue
+                }
+            }
+            if model.fields.values().any(|f| f.name == old || f.column_name == old) {
+                Err(Error::new(format!("`{}' is renamed from `{}', which is still a field or a column of the model", name, old)))?
+            }
+            let mut keeper = Field::new();
+            keeper.name = old.to_owned();
+            keeper.column_name = old.to_owned();
+            keeper.r#type = field.r#type.clone();
+            keeper.database_type = field.database_type.clone();
+            if field.is_optional() { keeper.set_optional() } else { keeper.set_required() }
+            keeper.read = Read::NoRead;
+            keeper.write = Write::NoWrite;
+            // required values aren't checked, the value is copied when the record is saved
+            keeper.auto = true;
+            keeper.data.insert(RETAINED_FOR.to_owned(), Value::String(field.name.clone()).into());
+            retained.push(keeper);
+        }
+        for keeper in retained {
+            for keys in [&mut model.cache.all_keys, &mut model.cache.save_keys, &mut model.cache.save_keys_and_virtual_keys] {
+                keys.push(keeper.name.clone());
+            }
+            model.fields.insert(keeper.name.clone(), keeper);
+        }
+    }
+    for namespace in namespace.namespaces.values_mut() {
+        settle_renamed_fields(namespace)?;
+    }
+    Ok(())
+}
+
+/// Copies the values of the renamed fields of `object` to their old columns before it's saved.
+pub(crate) fn write_old_columns(object: &Object) -> Result<()> {
+    let model = object.model();
+    for field in model.fields.values() {
+        let Some(renamed) = retained_for(field) else { continue };
+        if object.is_new() || object.keys_for_save().contains(&renamed) {
+            object.set_value(&field.name, object.get_value(renamed)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// A step of the migration of a renamed column, besides the changes of the connector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameStep {
+    /// The new column is added and the old one is copied into it.
+    Expand { table: String, column: String, from: String },
+    /// The old column is copied into the new one again and dropped.
+    Contract { table: String, column: String, from: String },
+}
+
+impl Display for RenameStep {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenameStep::Expand { table, column, from } => write!(f, "{}: add column `{}' and copy `{}' into it", table, column, from),
+            RenameStep::Contract { table, column, from } => write!(f, "{}: copy `{}' into `{}' again and drop it", table, from, column),
+        }
+    }
+}
+
+/// Adds the new columns of the renamed fields and copies the old values into them in batches.
+pub(crate) async fn migrate_renamed_columns(transaction: &dyn Transaction, database: Database, models: &[&Model], dry_run: bool, silent: bool) -> Result<Vec<RenameStep>> {
+    let mut steps = vec![];
+    if !matches!(database, Database::MySQL | Database::PostgreSQL | Database::SQLite) {
+        return Ok(steps);
+    }
+    for model in models {
+        let renamed: Vec<&Field> = model.fields.values().filter(|f| renamed_from(f).is_some()).collect();
+        if renamed.is_empty() {
+            continue
+        }
+        let rows = transaction.query_raw(&Value::String(columns_query(database, &model.table_name))).await?;
+        let columns = live_columns(database, &rows);
+        for field in renamed {
+            let old = renamed_from(field).unwrap();
+            // a new table is created with the fields of the model
+            if !columns.contains_key(old) {
+                continue
+            }
+            let (table, column, from) = (model.table_name.clone(), field.column_name.clone(), old.to_owned());
+            if !columns.contains_key(&field.column_name) {
+                if !dry_run {
+                    add_column(transaction, database, model, field, old).await?;
+                    copy_column(transaction, database, model, field, old).await?;
+                }
+                steps.push(RenameStep::Expand { table: table.clone(), column: column.clone(), from: from.clone() });
+            }
+            if !model.fields.contains_key(old) {
+                if !dry_run {
+                    copy_column(transaction, database, model, field, old).await?;
+                    require_column(transaction, database, model, field, old).await?;
+                    let drop = format!("ALTER TABLE {} DROP COLUMN {}", identifier(database, &model.table_name), identifier(database, old));
+                    transaction.query_raw(&Value::String(drop)).await?;
+                }
+                steps.push(RenameStep::Contract { table, column, from });
+            }
+        }
+    }
+    if !silent {
+        for step in &steps {
+            info_message(format!("{}{}", if dry_run { "would " } else { "" }, step));
+        }
+    }
+    Ok(steps)
+}
+
+async fn add_column(transaction: &dyn Transaction, database: Database, model: &Model, field: &Field, old: &str) -> Result<()> {
+    let (table, column) = (identifier(database, &model.table_name), identifier(database, &field.column_name));
+    let r#type = column_type(transaction, database, &model.table_name, old).await?;
+    let add = match database {
+        Database::SQLite if field.is_required() => format!("ALTER TABLE {} ADD COLUMN {} {} NOT NULL DEFAULT {}", table, column, r#type, placeholder(&r#type)),
+        _ => format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, r#type),
+    };
+    transaction.query_raw(&Value::String(add)).await?;
+    Ok(())
+}
+
+// a statement per batch of records in primary key order, so no lock is held for long
+async fn copy_column(transaction: &dyn Transaction, database: Database, model: &Model, field: &Field, old: &str) -> Result<()> {
+    let table = identifier(database, &model.table_name);
+    let (column, from) = (identifier(database, &field.column_name), identifier(database, old));
+    let key = match model.primary_index().map(|i| i.keys()) {
+        Some(keys) if keys.len() == 1 => identifier(database, model.fields.get(&keys[0]).map_or(keys[0].as_str(), |f| f.column_name.as_str())),
+        _ => Err(Error::new(format!("the column of `{}.{}' is copied in batches, which requires a single field primary key", model.path.join("."), field.name)))?,
+    };
+    let mut last: Option<String> = None;
+    loop {
+        let after = last.as_ref().map(|l| format!(" WHERE {} > {}", key, l)).unwrap_or_default();
+        let rows = transaction.query_raw(&Value::String(format!("SELECT {} AS k FROM {}{} ORDER BY {} LIMIT {}", key, table, after, key, BATCH))).await?;
+        let keys = rows.as_array().map(|rows| rows.iter().filter_map(|r| r.get("k")).map(literal).collect::<Result<Vec<String>>>()).transpose()?.unwrap_or_default();
+        let Some(upto) = keys.last() else { break };
+        let range = match &last {
+            Some(last) => format!("{} > {} AND {} <= {}", key, last, key, upto),
+            None => format!("{} <= {}", key, upto),
+        };
+        transaction.query_raw(&Value::String(format!("UPDATE {} SET {} = {} WHERE {}", table, column, from, range))).await?;
+        if keys.len() < BATCH {
+            break
+        }
+        last = Some(upto.clone());
+    }
+    Ok(())
+}
+
+async fn require_column(transaction: &dyn Transaction, database: Database, model: &Model, field: &Field, old: &str) -> Result<()> {
+    if !field.is_required() {
+        return Ok(());
+    }
+    let (table, column) = (identifier(database, &model.table_name), identifier(database, &field.column_name));
+    match database {
+        Database::PostgreSQL => {
+            transaction.query_raw(&Value::String(format!("ALTER TABLE {} ALTER COLUMN {} SET NOT NULL", table, column))).await?;
+        }
+        Database::MySQL => {
+            let r#type = column_type(transaction, database, &model.table_name, old).await?;
+            transaction.query_raw(&Value::String(format!("ALTER TABLE {} MODIFY {} {} NOT NULL", table, column, r#type))).await?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+// the type of the old column as the database writes it, the new column takes it
+async fn column_type(transaction: &dyn Transaction, database: Database, table: &str, column: &str) -> Result<String> {
+    let (table, column) = (table.replace('\'', "''"), column.replace('\'', "''"));
+    let query = match database {
+        Database::SQLite => format!("SELECT type AS type FROM pragma_table_info('{}') WHERE name = '{}'", table, column),
+        Database::MySQL => format!("SELECT COLUMN_TYPE AS type FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = '{}' AND column_name = '{}'", table, column),
+        _ => format!("SELECT format_type(a.atttypid, a.atttypmod) AS type FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid WHERE c.relnamespace = current_schema()::regnamespace AND c.relname = '{}' AND a.attname = '{}' AND NOT a.attisdropped", table, column),
+    };
+    let rows = transaction.query_raw(&Value::String(query)).await?;
+    match rows.as_array().and_then(|rows| rows.first()).and_then(|row| row.get("type")).and_then(|t| t.as_str()) {
+        Some(r#type) => Ok(r#type.to_owned()),
+        None => Err(Error::new(format!("the type of column `{}' of `{}' is not found", column, table))),
+    }
+}
+
+// the default of a required column of SQLite until it's filled, by the affinity of its type
+fn placeholder(r#type: &str) -> &'static str {
+    let r#type = r#type.to_uppercase();
+    if ["INT", "REAL", "FLOA", "DOUB", "NUM", "DEC", "BOOL"].iter().any(|t| r#type.contains(t)) { "0" } else { "''" }
+}
+
+fn literal(value: &Value) -> Result<String> {
+    match value {
+        Value::Int(i) => Ok(i.to_string()),
+        Value::Int64(i) => Ok(i.to_string()),
+        Value::String(s) => Ok(format!("'{}'", s.replace('\'', "''"))),
+        _ => Err(Error::new(format!("primary key {} can't be copied in batches", value))),
+    }
+}
+
+fn identifier(database: Database, name: &str) -> String {
+    if matches!(database, Database::MySQL) {
+        format!("`{}`", name.replace('`', "``"))
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+fn parse_version(version: &str) -> Result<Vec<u64>> {
+    version.split('.').map(|part| part.parse::<u64>()).collect::<std::result::Result<Vec<u64>, _>>().map_err(|_| {
+        Error::new(format!("invalid version `{}', expect numbers separated by dots, e.g. `2.0'", version))
+    })
+}
+
+// `2.0' and `2.0.0' are the same version
+fn compare_versions(a: &str, b: &str) -> Result<Ordering> {
+    let (mut a, mut b) = (parse_version(a)?, parse_version(b)?);
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    Ok(a.cmp(&b))
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+use rand::Rng;
+use teo_result::{Error, Result};
+use teo_runtime::config::connector::Connector;
+use teo_runtime::connection::transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::namespace::Namespace;
+use crate::app::ctx::Ctx;
+use crate::cli::command::MockCommand;
+use crate::app::database::connect_main_namespace;
+use crate::message::info_message;
+use crate::migrate::migrate;
+use crate::seeder::factory::Factory;
+use crate::server::make::serve;
+
+const MOCK_URL: &str = "sqlite::memory:";
+
+/// Serves the schema from in memory databases filled with fake records.
+pub(crate) async fn mock(command: &MockCommand, silent: bool) -> Result<()> {
+    Ctx::with_main_namespace_mut(replace_connectors)??;
+    connect_main_namespace(true).await?;
+    migrate(false, false, true).await?;
+    let conn_ctx = Ctx::conn_ctx();
+    let factory = Factory::new(transaction::Ctx::new(conn_ctx.clone()), command.seed);
+    for namespace_path in conn_ctx.connections_iter().keys() {
+        let Some(namespace) = conn_ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else {
+            Err(Error::new(format!("namespace `{}' is not found", namespace_path.join("."))))?
+        };
+        for model in namespace.models_under_connector() {
+            let name = model.path.join(".");
+            // a model which cannot be faked is served empty instead of failing the whole mock
+            if let Err(error) = factory.create_many(&name, command.records).await {
+                info_message(format!("cannot mock {}: {}", name, error.message()));
+            }
+        }
+    }
+    if !silent {
+        info_message(format!("mocking with {} record(s) per model", command.records));
+    }
+    Ctx::update_settings(|settings| settings.mock_latency = command.latency);
+    for plugin in Ctx::settings().plugins.clone() {
+        plugin.on_server_start(conn_ctx.namespace()).await?;
+    }
+    serve(conn_ctx.namespace(), &Ctx::get().runtime_version, &Ctx::get().entrance, silent).await
+}
+
+// the models keep their column types, SQLite accepts any of the SQL ones
+fn replace_connectors(namespace: &mut Namespace) -> Result<()> {
+    if let Some(connector) = namespace.connector.as_mut() {
+        if connector.provider.is_mongo() {
+            Err(Error::new("mock requires SQL connectors"))?
+        }
+        *connector = Connector { provider: Database::SQLite, url: MOCK_URL.to_owned() };
+    }
+    for child in namespace.namespaces.values_mut() {
+        replace_connectors(child)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn simulated_latency() -> Option<Duration> {
+    let (min, max) = Ctx::settings().mock_latency?;
+    if max <= min {
+        return Some(min);
+    }
+    Some(rand::thread_rng().gen_range(min..=max))
+}
@@ -0,0 +1,174 @@
+use std::future::Future;
+use key_path::path;
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{CODE_AMOUNT, CODE_NAME, CODE_POSITION};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::request;
+use teo_teon::teon;
+use teo_teon::value::Value;
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Number of records loaded per query.
+    pub size: usize,
+    /// Field to iterate in ascending order, defaults to the stable key.
+    pub order_by: Option<String>,
+    /// Unique field which breaks ties and marks where the next batch starts, defaults to the
+    /// primary key.
+    pub stable_key: Option<String>,
+}
+
+impl Default for BatchOptions {
+
+    fn default() -> Self {
+        Self { size: 200, order_by: None, stable_key: None }
+    }
+}
+
+/// Loads the records matching a finder batch by batch. Each batch starts after the last record of
+/// the previous one instead of skipping a count of records, so records which are deleted or
+/// inserted while iterating don't shift the batches and every record is visited once. This also
+/// holds for updates unless `order_by` is a field which changes, an updated order value can move
+/// a record behind the last one and it is visited again, or before it and it is skipped.
+pub struct Batches {
+    ctx: transaction::Ctx,
+    model: &'static Model,
+    finder: Value,
+    size: usize,
+    keys: Vec<String>,
+    last: Option<Vec<Value>>,
+    finished: bool,
+    action: Action,
+    req_ctx: Option<request::Ctx>,
+}
+
+impl Batches {
+
+    pub fn new(ctx: &transaction::Ctx, model: &'static Model, finder: &Value, options: BatchOptions) -> Result<Self> {
+        let model_name = model.path.join(".");
+        if options.size == 0 {
+            Err(Error::new("batch size should be greater than 0"))?
+        }
+        let Some(dictionary) = finder.as_dictionary() else {
+            Err(Error::new("batch finder should be a dictionary"))?
+        };
+        for key in ["orderBy", "skip", "take", "cursor", "pageSize", "pageNumber"] {
+            if dictionary.contains_key(key) {
+                Err(Error::new(format!("batch finder cannot contain `{}', use batch options instead", key)))?
+            }
+        }
+        let stable_keys = match options.stable_key {
+            Some(key) => {
+                let unique = model.indexes.values().any(|i| i.r#type().is_unique_or_primary() && i.keys().len() == 1 && i.keys()[0] == key);
+                if !unique {
+                    Err(Error::new(format!("stable key `{}' of {} is not a unique field", key, model_name)))?
+                }
+                vec![key]
+            },
+            None => match model.primary_index() {
+                Some(index) => index.keys().clone(),
+                None => Err(Error::new(format!("batches of {} require a stable key", model_name)))?,
+            },
+        };
+        let mut keys: Vec<String> = options.order_by.into_iter().filter(|o| !stable_keys.contains(o)).collect();
+        keys.extend(stable_keys);
+        for key in &keys {
+            match model.field(key) {
+                Some(field) if field.is_optional() => Err(Error::new(format!("batches of {} cannot be ordered by optional field `{}'", model_name, key)))?,
+                Some(_) => (),
+                None => Err(Error::new(format!("field `{}' is not found on {}", key, model_name)))?,
+            }
+        }
+        Ok(Self { ctx: ctx.clone(), model, finder: finder.clone(), size: options.size, keys, last: None, finished: false, action: CODE_NAME | CODE_AMOUNT | CODE_POSITION, req_ctx: None })
+    }
+
+    /// Loads the records as `action` of a request, so the read rules of the request apply.
+    pub fn for_request(mut self, action: Action, req_ctx: request::Ctx) -> Self {
+        self.action = action;
+        self.req_ctx = Some(req_ctx);
+        self
+    }
+
+    /// Skips the records up to the one with these key values, to resume an earlier iteration.
+    pub fn resume_after(&mut self, last: Vec<Value>) -> Result<()> {
+        if last.len() != self.keys.len() {
+            Err(Error::new(format!("batches of {} resume after {} key value(s)", self.model.path.join("."), self.keys.len())))?
+        }
+        self.last = Some(last);
+        Ok(())
+    }
+
+    /// The key values of the last loaded record, ordering fields first and stable keys last.
+    pub fn last(&self) -> Option<&[Value]> {
+        self.last.as_deref()
+    }
+
+    /// Returns the next batch, or `None` when all records are loaded.
+    pub async fn next(&mut self) -> Result<Option<Vec<Object>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let finder = self.batch_finder();
+        let objects = self.ctx.find_many_internal(self.model, &finder, false, self.action, self.req_ctx.clone(), path![]).await?;
+        self.finished = objects.len() < self.size;
+        if let Some(object) = objects.last() {
+            self.last = Some(self.keys.iter().map(|k| object.get_value(k)).collect::<Result<Vec<Value>>>()?);
+        }
+        if objects.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(objects))
+        }
+    }
+
+    fn batch_finder(&self) -> Value {
+        let order_by: Vec<Value> = self.keys.iter().map(|k| teon!({k.as_str(): "asc"})).collect();
+        let mut finder = self.finder.clone();
+        let dictionary = finder.as_dictionary_mut().unwrap();
+        dictionary.insert("orderBy".to_owned(), Value::Array(order_by));
+        dictionary.insert("take".to_owned(), Value::Int64(self.size as i64));
+        if let Some(last) = &self.last {
+            let after = self.after(last);
+            let r#where = match dictionary.shift_remove("where") {
+                Some(r#where) => teon!({"AND": [r#where, after]}),
+                None => after,
+            };
+            dictionary.insert("where".to_owned(), r#where);
+        }
+        finder
+    }
+
+    // (k1, k2, k3) > (v1, v2, v3) is k1 > v1 or k1 = v1 and k2 > v2 or k1 = v1 and k2 = v2 and k3 > v3
+    fn after(&self, last: &[Value]) -> Value {
+        let branches: Vec<Value> = (0..self.keys.len()).map(|i| {
+            let mut branch = teon!({});
+            let dictionary = branch.as_dictionary_mut().unwrap();
+            for (key, value) in self.keys.iter().zip(last).take(i) {
+                dictionary.insert(key.clone(), value.clone());
+            }
+            dictionary.insert(self.keys[i].clone(), teon!({"gt": last[i].clone()}));
+            branch
+        }).collect();
+        if branches.len() == 1 {
+            branches.into_iter().next().unwrap()
+        } else {
+            teon!({"OR": branches})
+        }
+    }
+}
+
+/// Calls `f` with each record matching the finder, loading them with `Batches`.
+pub async fn batch<F, Fut>(ctx: &transaction::Ctx, model: &'static Model, finder: &Value, options: BatchOptions, f: F) -> Result<()> where
+    F: Fn(Object) -> Fut,
+    Fut: Future<Output = Result<()>> {
+    let mut batches = Batches::new(ctx, model, finder, options)?;
+    while let Some(objects) = batches.next().await? {
+        for object in objects {
+            f(object).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use key_path::path;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_runtime::coder::json_to_teon::json_to_teon_with_type;
+use teo_runtime::model::Object;
+use teo_runtime::path;
+use crate::app::ctx::Ctx;
+
+pub async fn diff(left: &Object, right: &Object) -> path::Result<JsonValue> {
+    if left.model().path != right.model().path {
+        return Err(path::Error::value_error_message_only("cannot compare objects of different models"));
+    }
+    Ok(json_diff(&object_json(left).await?, &object_json(right).await?))
+}
+
+/// Compares a record with a payload of its fields. The payload is parsed with the field types
+/// first, so values compare in the same form as the stored ones.
+pub async fn diff_with_json(object: &Object, json: &JsonValue) -> path::Result<JsonValue> {
+    let Some(payload) = json.as_object() else {
+        return Err(path::Error::value_error_message_only("expect object"));
+    };
+    let mut right = Map::new();
+    for (key, value) in payload {
+        let field = match object.model().field(key) {
+            Some(field) if !field.r#virtual => field,
+            _ => Err(path::Error::value_error(path![key.as_str()], "unexpected key"))?,
+        };
+        let value = json_to_teon_with_type(value, &path![key.as_str()], &field.r#type, Ctx::main_namespace())?;
+        match JsonValue::try_from(&value) {
+            Ok(value) => right.insert(key.clone(), value),
+            Err(e) => Err(path::Error::value_error(path![key.as_str()], format!("{}", e)))?,
+        };
+    }
+    let mut left = object_json(object).await?;
+    if let Some(map) = left.as_object_mut() {
+        map.retain(|k, _| right.contains_key(k));
+    }
+    Ok(json_diff(&left, &JsonValue::Object(right)))
+}
+
+pub fn json_diff(left: &JsonValue, right: &JsonValue) -> JsonValue {
+    let mut changes = vec![];
+    collect_changes(&mut vec![], left, right, &mut changes);
+    json!({
+        "equal": changes.is_empty(),
+        "changes": changes,
+    })
+}
+
+async fn object_json(object: &Object) -> path::Result<JsonValue> {
+    let value = object.to_teon().await?;
+    match JsonValue::try_from(&value) {
+        Ok(json) => Ok(json),
+        Err(e) => Err(path::Error::internal_server_error_message_only(format!("{}", e))),
+    }
+}
+
+fn collect_changes(path: &mut Vec<String>, left: &JsonValue, right: &JsonValue, changes: &mut Vec<JsonValue>) {
+    match (left, right) {
+        (JsonValue::Object(l), JsonValue::Object(r)) if !is_special(l) && !is_special(r) => {
+            for (key, left_value) in l {
+                path.push(key.clone());
+                match r.get(key) {
+                    Some(right_value) => collect_changes(path, left_value, right_value, changes),
+                    None => changes.push(change(path, "removed", left_value, &JsonValue::Null)),
+                }
+                path.pop();
+            }
+            for (key, right_value) in r {
+                if !l.contains_key(key) {
+                    path.push(key.clone());
+                    changes.push(change(path, "added", &JsonValue::Null, right_value));
+                    path.pop();
+                }
+            }
+        }
+        (JsonValue::Array(l), JsonValue::Array(r)) => {
+            for index in 0..l.len().max(r.len()) {
+                path.push(index.to_string());
+                match (l.get(index), r.get(index)) {
+                    (Some(left_value), Some(right_value)) => collect_changes(path, left_value, right_value, changes),
+                    (Some(left_value), None) => changes.push(change(path, "removed", left_value, &JsonValue::Null)),
+                    (None, Some(right_value)) => changes.push(change(path, "added", &JsonValue::Null, right_value)),
+                    (None, None) => (),
+                }
+                path.pop();
+            }
+        }
+        _ => if left != right {
+            changes.push(change(path, "changed", left, right));
+        }
+    }
+}
+
+// $date, $datetime and $decimal wrappers are compared as scalar values
+fn is_special(map: &Map<String, JsonValue>) -> bool {
+    map.len() == 1 && map.keys().next().is_some_and(|k| k.starts_with("$"))
+}
+
+fn change(path: &[String], kind: &str, left: &JsonValue, right: &JsonValue) -> JsonValue {
+    json!({
+        "path": path.join("."),
+        "kind": kind,
+        "left": left,
+        "right": right,
+    })
+}
@@ -0,0 +1,135 @@
+use std::marker::PhantomData;
+use indexmap::IndexMap;
+use teo_result::{Error, Result};
+use teo_runtime::model;
+use teo_runtime::sort::Sort;
+use teo_teon::value::Value;
+
+/// Builds the finder of a query on a model step by step. Generated entities wrap it with typed
+/// methods like `where_name_contains`, which forward to `where_field` with the field and operator
+/// names of the schema.
+pub struct Finder<T> {
+    ctx: model::Ctx,
+    r#where: IndexMap<String, Value>,
+    order_by: Vec<Value>,
+    include: IndexMap<String, Value>,
+    skip: Option<usize>,
+    take: Option<i64>,
+    // the error of the first step naming a field or relation which isn't on the model
+    unknown: Option<String>,
+    marker: PhantomData<T>,
+}
+
+impl<T> Finder<T> where T: From<model::Object> {
+
+    pub fn new(ctx: model::Ctx) -> Self {
+        Self {
+            ctx,
+            r#where: IndexMap::new(),
+            order_by: vec![],
+            include: IndexMap::new(),
+            skip: None,
+            take: None,
+            unknown: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Filters `field` with `operator`, conditions of the same field are combined.
+    pub fn where_field(mut self, field: &str, operator: &str, value: impl Into<Value>) -> Self {
+        if !self.has_field(field) {
+            return self;
+        }
+        let filter = self.r#where.entry(field.to_owned()).or_insert_with(|| Value::Dictionary(IndexMap::new()));
+        if let Some(filter) = filter.as_dictionary_mut() {
+            filter.insert(operator.to_owned(), value.into());
+        }
+        self
+    }
+
+    pub fn where_equals(self, field: &str, value: impl Into<Value>) -> Self {
+        self.where_field(field, "equals", value)
+    }
+
+    /// Orders by `field` after the fields ordered by before.
+    pub fn order_by(mut self, field: &str, sort: Sort) -> Self {
+        if !self.has_field(field) {
+            return self;
+        }
+        let sort = match sort {
+            Sort::Asc => "asc",
+            Sort::Desc => "desc",
+        };
+        self.order_by.push(Value::Dictionary(IndexMap::from([(field.to_owned(), Value::String(sort.to_owned()))])));
+        self
+    }
+
+    pub fn include(mut self, relation: &str) -> Self {
+        if self.ctx.model.relation(relation).is_none() {
+            self.unknown.get_or_insert_with(|| format!("relation `{}' is not found on model `{}'", relation, self.ctx.model.path.join(".")));
+            return self;
+        }
+        self.include.insert(relation.to_owned(), Value::Bool(true));
+        self
+    }
+
+    // fields and properties are filtered and ordered alike, the first unknown one is kept
+    fn has_field(&mut self, field: &str) -> bool {
+        if self.ctx.model.field(field).is_some() || self.ctx.model.property(field).is_some() {
+            return true;
+        }
+        self.unknown.get_or_insert_with(|| format!("field `{}' is not found on model `{}'", field, self.ctx.model.path.join(".")));
+        false
+    }
+
+    pub fn skip(mut self, skip: usize) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    pub fn take(mut self, take: i64) -> Self {
+        self.take = Some(take);
+        self
+    }
+
+    /// The finder built so far, fails if a step named a field or relation which isn't on the model.
+    pub fn finder(&self) -> Result<Value> {
+        if let Some(unknown) = &self.unknown {
+            Err(Error::new(unknown))?
+        }
+        let mut finder = IndexMap::new();
+        if !self.r#where.is_empty() {
+            finder.insert("where".to_owned(), Value::Dictionary(self.r#where.clone()));
+        }
+        if !self.order_by.is_empty() {
+            finder.insert("orderBy".to_owned(), Value::Array(self.order_by.clone()));
+        }
+        if !self.include.is_empty() {
+            finder.insert("include".to_owned(), Value::Dictionary(self.include.clone()));
+        }
+        if let Some(skip) = self.skip {
+            finder.insert("skip".to_owned(), Value::Int64(skip as i64));
+        }
+        if let Some(take) = self.take {
+            finder.insert("take".to_owned(), Value::Int64(take));
+        }
+        Ok(Value::Dictionary(finder))
+    }
+
+    pub async fn find_many(&self) -> Result<Vec<T>> {
+        Ok(self.ctx.find_many(&self.finder()?).await?)
+    }
+
+    pub async fn find_first(&self) -> Result<Option<T>> {
+        Ok(self.ctx.find_first(&self.finder()?).await?)
+    }
+
+    /// Counts the records matching the filters, ordering and pagination are ignored.
+    pub async fn count(&self) -> Result<usize> {
+        let mut finder = self.finder()?;
+        if let Some(finder) = finder.as_dictionary_mut() {
+            finder.retain(|key, _| key == "where");
+        }
+        Ok(self.ctx.count(&finder).await?)
+    }
+}
@@ -0,0 +1,4 @@
+pub mod relation;
+pub mod diff;
+pub mod batch;
+pub mod finder;
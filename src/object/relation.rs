@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use async_trait::async_trait;
+use teo_result::{Error, Result};
+use teo_runtime::model::Object;
+
+#[async_trait]
+pub trait LazyRelation {
+    async fn relation<T>(&self, name: &str) -> Result<T> where T: FromRelationObjects;
+}
+
+#[async_trait]
+impl LazyRelation for Object {
+
+    async fn relation<T>(&self, name: &str) -> Result<T> where T: FromRelationObjects {
+        let relation = match self.model().relation(name) {
+            Some(relation) => relation,
+            None => Err(Error::new(format!("relation '{}' is not defined on model '{}'", name, self.model().path.join("."))))?,
+        };
+        if self.has_query_relation_fetched(name) {
+            return T::from_relation_objects(self.get_relation_vec(name)?);
+        }
+        let memo_key = memo_key(self, name);
+        if let Some(request_ctx) = self.request_ctx() {
+            if let Some(objects) = request_ctx.data().get::<Memo>(MEMO_KEY).and_then(|memo| memo.get(&memo_key)) {
+                return T::from_relation_objects(objects.clone());
+            }
+        }
+        let objects = if relation.is_vec {
+            self.fetch_relation_objects(name, None).await?
+        } else {
+            match self.fetch_relation_object(name, None).await {
+                Ok(object) => object.into_iter().collect(),
+                Err(error) if error.code == 404 => vec![],
+                Err(error) => Err(error)?,
+            }
+        };
+        if let Some(request_ctx) = self.request_ctx() {
+            let mut data = request_ctx.data_mut();
+            match data.get_mut::<Memo>(MEMO_KEY) {
+                Some(memo) => { memo.insert(memo_key, objects.clone()); }
+                None => data.insert(MEMO_KEY, Memo::from([(memo_key, objects.clone())])),
+            }
+        }
+        T::from_relation_objects(objects)
+    }
+}
+
+// the relations loaded in a request, by owner and relation name
+type Memo = BTreeMap<String, Vec<Object>>;
+
+const MEMO_KEY: &str = "__teo_relations";
+
+fn memo_key(object: &Object, name: &str) -> String {
+    format!("{}:{}:{}", object.model().path.join("."), object.identifier(), name)
+}
+
+/// Forgets the relations loaded in the request of `object` after it's saved or deleted.
+pub(crate) fn forget_relations(object: &Object) {
+    if let Some(request_ctx) = object.request_ctx() {
+        request_ctx.data_mut().remove::<Memo>(MEMO_KEY);
+    }
+}
+
+pub trait FromRelationObjects: Sized + Send {
+    fn from_relation_objects(objects: Vec<Object>) -> Result<Self>;
+}
+
+impl FromRelationObjects for Vec<Object> {
+
+    fn from_relation_objects(objects: Vec<Object>) -> Result<Self> {
+        Ok(objects)
+    }
+}
+
+impl FromRelationObjects for Option<Object> {
+
+    fn from_relation_objects(objects: Vec<Object>) -> Result<Self> {
+        Ok(objects.into_iter().next())
+    }
+}
+
+impl FromRelationObjects for Object {
+
+    fn from_relation_objects(objects: Vec<Object>) -> Result<Self> {
+        match objects.into_iter().next() {
+            Some(object) => Ok(object),
+            None => Err(Error::new("related object is not found")),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::interface_enum_variant::InterfaceEnumVariant;
+use teo_runtime::model::{Model, Relation};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::relation::delete::Delete;
+use teo_runtime::namespace::Namespace;
+use teo_teon::value::Value;
+
+const DATA_KEY: &str = "onDelete";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnDelete {
+    Cascade,
+    SetNull,
+    Restrict,
+    NoAction,
+}
+
+impl OnDelete {
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cascade" => Some(OnDelete::Cascade),
+            "setNull" => Some(OnDelete::SetNull),
+            "restrict" => Some(OnDelete::Restrict),
+            "noAction" => Some(OnDelete::NoAction),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OnDelete::Cascade => "cascade",
+            OnDelete::SetNull => "setNull",
+            OnDelete::Restrict => "restrict",
+            OnDelete::NoAction => "noAction",
+        }
+    }
+
+    /// The referential action of the foreign key constraint.
+    pub fn sql(&self) -> &'static str {
+        match self {
+            OnDelete::Cascade => "CASCADE",
+            OnDelete::SetNull => "SET NULL",
+            OnDelete::Restrict => "RESTRICT",
+            OnDelete::NoAction => "NO ACTION",
+        }
+    }
+}
+
+/// Loads `@onDelete`, the rule for the records pointing to a deleted record.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_relation_decorator("onDelete", |args: Arguments, relation: &mut Relation| {
+        let variant: Result<InterfaceEnumVariant> = args.get("rule");
+        let name = match variant {
+            Ok(variant) => variant.value,
+            Err(_) => args.get("rule")?,
+        };
+        let Some(rule) = OnDelete::from_name(&name) else {
+            Err(Error::new(format!("unknown delete rule `{}', expect cascade, setNull, restrict or noAction", name)))?
+        };
+        relation.delete = match rule {
+            OnDelete::Cascade => Delete::Cascade,
+            OnDelete::SetNull => Delete::Nullify,
+            OnDelete::Restrict => Delete::Deny,
+            OnDelete::NoAction => Delete::NoAction,
+        };
+        relation.data.insert(DATA_KEY.to_owned(), Value::String(rule.name().to_owned()).into());
+        Ok(())
+    });
+}
+
+/// The rule `@onDelete` sets on `relation`, if it has one.
+pub fn on_delete(relation: &Relation) -> Option<OnDelete> {
+    relation.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_str()).and_then(OnDelete::from_name)
+}
+
+/// Resets the rules of the relations without a foreign key to no action.
+pub(crate) fn settle_delete_rules(namespace: &mut Namespace) -> Result<()> {
+    for model in namespace.models.values_mut() {
+        for relation in model.relations.values_mut() {
+            if relation.through.is_some() {
+                continue;
+            }
+            let name = format!("{}.{}", model.path.join("."), relation.name);
+            if !relation.has_foreign_key {
+                if on_delete(relation).is_some() {
+                    Err(Error::new(format!("`@onDelete' belongs on the side with the foreign key, `{}' has none", name)))?
+                }
+                if relation.delete == Delete::Nullify {
+                    relation.delete = Delete::NoAction;
+                }
+            } else if on_delete(relation) == Some(OnDelete::SetNull) {
+                if let Some(field) = relation.fields.iter().find(|f| model.fields.get(*f).is_some_and(|f| !f.is_optional())) {
+                    Err(Error::new(format!("`{}' can't be set to null when `{}' is deleted, `{}' is required", name, relation.model.join("."), field)))?
+                }
+            }
+        }
+    }
+    for namespace in namespace.namespaces.values_mut() {
+        settle_delete_rules(namespace)?;
+    }
+    Ok(())
+}
+
+/// Adds or replaces the foreign key constraints of `@onDelete` on MySQL and PostgreSQL.
+pub(crate) async fn migrate_foreign_keys(transaction: &dyn Transaction, database: Database, models: &[&Model]) -> Result<()> {
+    if !matches!(database, Database::MySQL | Database::PostgreSQL) {
+        return Ok(());
+    }
+    for model in models {
+        for relation in model.relations.values() {
+            let Some(rule) = on_delete(relation).filter(|_| relation.has_foreign_key) else { continue };
+            let Some(referenced) = models.iter().find(|m| m.path == relation.model) else { continue };
+            let name = constraint_name(&model.table_name, &relation.name);
+            let existing = transaction.query_raw(&Value::String(delete_rule_query(database, &name))).await?;
+            let existing = existing.as_array().and_then(|rows| rows.first()).and_then(|row| row.get("delete_rule")).and_then(|r| r.as_str());
+            if existing == Some(rule.sql()) {
+                continue;
+            }
+            if existing.is_some() {
+                transaction.query_raw(&Value::String(drop_statement(database, &model.table_name, &name))).await?;
+            }
+            let statement = foreign_key_statement(database, model, referenced, relation, rule);
+            transaction.query_raw(&Value::String(statement)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The statement which adds the foreign key constraint of `relation` on `model`.
+pub fn foreign_key_statement(database: Database, model: &Model, referenced: &Model, relation: &Relation, rule: OnDelete) -> String {
+    let columns = |model: &Model, fields: &[String]| fields.iter().map(|f| {
+        identifier(database, model.fields.get(f).map_or(f.as_str(), |f| f.column_name.as_str()))
+    }).collect::<Vec<_>>().join(", ");
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {}",
+        identifier(database, &model.table_name),
+        identifier(database, &constraint_name(&model.table_name, &relation.name)),
+        columns(model, &relation.fields),
+        identifier(database, &referenced.table_name),
+        columns(referenced, &relation.references),
+        rule.sql(),
+    )
+}
+
+// both dialects allow 63 characters at least
+fn constraint_name(table: &str, relation: &str) -> String {
+    format!("fk_{}_{}", table, relation).chars().take(63).collect()
+}
+
+fn delete_rule_query(database: Database, name: &str) -> String {
+    let schema = if matches!(database, Database::MySQL) { "DATABASE()" } else { "current_schema()" };
+    format!("SELECT delete_rule AS delete_rule FROM information_schema.referential_constraints WHERE constraint_schema = {} AND constraint_name = '{}'", schema, name.replace('\'', "''"))
+}
+
+fn drop_statement(database: Database, table: &str, name: &str) -> String {
+    let kind = if matches!(database, Database::MySQL) { "FOREIGN KEY" } else { "CONSTRAINT" };
+    format!("ALTER TABLE {} DROP {} {}", identifier(database, table), kind, identifier(database, name))
+}
+
+fn identifier(database: Database, name: &str) -> String {
+    if matches!(database, Database::MySQL) {
+        format!("`{}`", name.replace('`', "``"))
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
@@ -0,0 +1,36 @@
+use teo_result::{Error, ResultExt};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::pipeline::Pipeline;
+
+/// Loads `whenCreate`, `whenUpdate` and `validateWith`.
+pub(crate) fn load_pipeline_items(namespace: &mut Namespace) {
+
+    namespace.define_pipeline_item("whenCreate", |args: Arguments, ctx: Ctx| async move {
+        let pipeline: Pipeline = args.get("pipeline").err_prefix("whenCreate(pipeline)")?;
+        if ctx.object().is_new() {
+            ctx.run_pipeline(&pipeline).await
+        } else {
+            Ok(ctx.value().clone())
+        }
+    });
+
+    namespace.define_pipeline_item("whenUpdate", |args: Arguments, ctx: Ctx| async move {
+        let pipeline: Pipeline = args.get("pipeline").err_prefix("whenUpdate(pipeline)")?;
+        if ctx.object().is_new() {
+            Ok(ctx.value().clone())
+        } else {
+            ctx.run_pipeline(&pipeline).await
+        }
+    });
+
+    namespace.define_pipeline_item("validateWith", |args: Arguments, ctx: Ctx| async move {
+        let pipeline: Pipeline = args.get("pipeline").err_prefix("validateWith(pipeline)")?;
+        let message: String = args.get("message").err_prefix("validateWith(message)")?;
+        if ctx.run_pipeline(&pipeline).await.is_err() {
+            Err(Error::new(message))?
+        }
+        Ok(ctx.value().clone())
+    });
+}
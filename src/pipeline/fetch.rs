@@ -0,0 +1,108 @@
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use reqwest::Method;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result, ResultExt};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::object::Object;
+use teo_runtime::pipeline::Ctx;
+use teo_teon::value::Value;
+
+const DEFAULT_TIMEOUT: u64 = 10_000;
+
+// `{{value}}' is the pipeline value, `{{self.name}}' a field of the record
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*(value|self\.[A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap()
+});
+
+/// Loads `fetch`, which outputs the JSON response of an HTTP endpoint.
+pub(crate) fn load_pipeline_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("fetch", |args: Arguments, ctx: Ctx| async move {
+        let url: String = args.get("url").err_prefix("fetch(url)")?;
+        let method: Option<String> = args.get_optional("method").err_prefix("fetch(method)")?;
+        let headers: Option<Vec<String>> = args.get_optional("headers").err_prefix("fetch(headers)")?;
+        let body: Option<String> = args.get_optional("body").err_prefix("fetch(body)")?;
+        let extract: Option<String> = args.get_optional("extract").err_prefix("fetch(extract)")?;
+        let timeout: Option<i32> = args.get_optional("timeout").err_prefix("fetch(timeout)")?;
+        let method = match method {
+            Some(method) => Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| Error::new(format!("fetch(method): invalid method `{}'", method)))?,
+            None => Method::GET,
+        };
+        let url = render(&url, &ctx, |value| Ok(url::form_urlencoded::byte_serialize(text(value).as_bytes()).collect()))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(timeout.map(|t| t.max(1) as u64).unwrap_or(DEFAULT_TIMEOUT)))
+            .build()
+            .map_err(|e| Error::new(format!("fetch: {}", e)))?;
+        let mut request = client.request(method, &url);
+        for header in headers.unwrap_or_default() {
+            let Some((name, value)) = header.split_once(':') else {
+                Err(Error::new(format!("fetch(headers): expect `Name: value', found `{}'", header)))?
+            };
+            let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| Error::new(format!("fetch(headers): {}", e)))?;
+            let value = HeaderValue::from_str(value.trim()).map_err(|e| Error::new(format!("fetch(headers): {}", e)))?;
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            let body = render(&body, &ctx, |value| match JsonValue::try_from(value) {
+                Ok(json) => Ok(json.to_string()),
+                Err(e) => Err(Error::new(format!("fetch(body): {}", e))),
+            })?;
+            request = request.header(reqwest::header::CONTENT_TYPE, "application/json").body(body);
+        }
+        let response = request.send().await.map_err(|e| Error::new(format!("fetch failed: {}", if e.is_timeout() { "request timed out".to_owned() } else { e.to_string() })))?;
+        let status = response.status();
+        if !status.is_success() {
+            Err(Error::new(format!("fetch failed with status {}", status.as_u16())))?
+        }
+        let json: JsonValue = response.json().await.map_err(|e| Error::new(format!("fetch failed: invalid JSON response, {}", e)))?;
+        let json = match extract {
+            Some(path) => extracted(&json, &path).ok_or_else(|| Error::new(format!("fetch failed: response has no `{}'", path)))?,
+            None => json,
+        };
+        Ok(Object::from(Value::from(json)))
+    });
+}
+
+fn render(template: &str, ctx: &Ctx, encode: impl Fn(&Value) -> Result<String>) -> Result<String> {
+    let mut error = None;
+    let rendered = PLACEHOLDER.replace_all(template, |captures: &Captures| {
+        let value = match &captures[1] {
+            "value" => Ok(ctx.value().as_teon().cloned().unwrap_or(Value::Null)),
+            name => ctx.object().get_value(name.trim_start_matches("self.")),
+        };
+        match value.and_then(|value| encode(&value)) {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                error.get_or_insert(e);
+                String::new()
+            }
+        }
+    }).into_owned();
+    match error {
+        Some(error) => Err(error),
+        None => Ok(rendered),
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        value => format!("{}", value),
+    }
+}
+
+// `rates.EUR' and `results.0.location' select a key or an array index at each step
+fn extracted(json: &JsonValue, path: &str) -> Option<JsonValue> {
+    let mut current = json;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        current = match current {
+            JsonValue::Array(values) => values.get(segment.parse::<usize>().ok()?)?,
+            _ => current.get(segment)?,
+        };
+    }
+    Some(current.clone())
+}
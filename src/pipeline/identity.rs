@@ -0,0 +1,30 @@
+use teo_result::ResultExt;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Object as ModelObject;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::object::Object;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::request;
+use teo_teon::value::Value;
+
+/// The key a middleware stores the identity a request is made by at, in the request's data.
+pub const IDENTITY_KEY: &str = "identity";
+
+/// The identity `req_ctx` is made by, when a middleware stored it.
+pub fn identity(req_ctx: &request::Ctx) -> Option<ModelObject> {
+    req_ctx.data().get::<ModelObject>(IDENTITY_KEY).cloned()
+}
+
+/// Loads `identity`, which outputs the identity of the request writing the record.
+pub(crate) fn load_pipeline_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("identity", |args: Arguments, ctx: Ctx| async move {
+        let key: Option<String> = args.get_optional("key").err_prefix("identity(key)")?;
+        let Some(identity) = ctx.request_ctx().as_ref().and_then(identity) else {
+            return Ok(Object::from(Value::Null));
+        };
+        Ok(match key {
+            Some(key) => Object::from(identity.get_value(&key).err_prefix("identity(key)")?),
+            None => Object::from(identity),
+        })
+    });
+}
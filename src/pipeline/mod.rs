@@ -0,0 +1,4 @@
+pub mod fetch;
+pub mod string;
+pub mod conditional;
+pub mod identity;
@@ -0,0 +1,165 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_result::{Error, ResultExt};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::object::Object;
+use teo_runtime::pipeline::Ctx;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+static PHONE_SEPARATORS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[\s().-]").unwrap()
+});
+
+// country calling codes of the regions `isPhoneNumber' accepts
+const CALLING_CODES: &[(&str, &str)] = &[
+    ("AU", "61"), ("BR", "55"), ("CA", "1"), ("CH", "41"), ("CN", "86"), ("DE", "49"),
+    ("ES", "34"), ("FR", "33"), ("GB", "44"), ("HK", "852"), ("IN", "91"), ("IT", "39"),
+    ("JP", "81"), ("KR", "82"), ("MX", "52"), ("NL", "31"), ("RU", "7"), ("SE", "46"),
+    ("SG", "65"), ("TR", "90"), ("TW", "886"), ("US", "1"),
+];
+
+/// Loads the string items the runtime doesn't provide.
+pub(crate) fn load_pipeline_items(namespace: &mut Namespace) {
+
+    namespace.define_pipeline_item("slugify", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("slugify")?;
+        Ok(Object::from(slugify(input)))
+    });
+
+    namespace.define_pipeline_item("toLocaleLowerCase", |args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("toLocaleLowerCase")?;
+        let locale: String = args.get("locale").err_prefix("toLocaleLowerCase(locale)")?;
+        Ok(Object::from(if dotted_i(&locale) {
+            input.replace('I', "ı").replace('İ', "i").to_lowercase()
+        } else {
+            input.to_lowercase()
+        }))
+    });
+
+    namespace.define_pipeline_item("toLocaleUpperCase", |args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("toLocaleUpperCase")?;
+        let locale: String = args.get("locale").err_prefix("toLocaleUpperCase(locale)")?;
+        Ok(Object::from(if dotted_i(&locale) {
+            input.replace('i', "İ").replace('ı', "I").to_uppercase()
+        } else {
+            input.to_uppercase()
+        }))
+    });
+
+    namespace.define_pipeline_item("trimStart", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("trimStart")?;
+        Ok(Object::from(input.trim_start()))
+    });
+
+    namespace.define_pipeline_item("trimEnd", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("trimEnd")?;
+        Ok(Object::from(input.trim_end()))
+    });
+
+    namespace.define_pipeline_item("isURL", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("isURL")?;
+        match url::Url::parse(input) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => (),
+            _ => Err(Error::new("input is not url"))?,
+        }
+        Ok(ctx.value().clone())
+    });
+
+    namespace.define_pipeline_item("isUUID", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("isUUID")?;
+        // hyphenated only, `Uuid::parse_str' accepts the simple and braced forms as well
+        if input.len() != 36 || uuid::Uuid::parse_str(input).is_err() {
+            Err(Error::new("input is not uuid"))?
+        }
+        Ok(ctx.value().clone())
+    });
+
+    namespace.define_pipeline_item("isCreditCard", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("isCreditCard")?;
+        let digits: String = input.chars().filter(|c| *c != ' ' && *c != '-').collect();
+        if !(13..=19).contains(&digits.len()) || !luhn(&digits) {
+            Err(Error::new("input is not credit card"))?
+        }
+        Ok(ctx.value().clone())
+    });
+
+    namespace.define_pipeline_item("isPhoneNumber", |args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("isPhoneNumber")?;
+        let region: Option<String> = args.get_optional("region").err_prefix("isPhoneNumber(region)")?;
+        let calling_code = match &region {
+            Some(region) => Some(CALLING_CODES.iter().find(|(r, _)| r.eq_ignore_ascii_case(region)).map(|(_, code)| *code).ok_or_else(|| Error::new(format!("isPhoneNumber(region): unknown region `{}'", region)))?),
+            None => None,
+        };
+        if !phone_number(input, calling_code) {
+            Err(Error::new("input is not phone number"))?
+        }
+        Ok(ctx.value().clone())
+    });
+
+    namespace.define_pipeline_item("luhnCheck", |_args: Arguments, ctx: Ctx| async move {
+        let input: &str = ctx.value().try_into_err_prefix("luhnCheck")?;
+        if input.is_empty() || !luhn(input) {
+            Err(Error::new("input doesn't pass luhn check"))?
+        }
+        Ok(ctx.value().clone())
+    });
+}
+
+// accents are removed, runs of other characters become a single `-'
+fn slugify(input: &str) -> String {
+    let mut slug = String::new();
+    for c in input.nfkd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+// turkish and azerbaijani pair `I' with `ı' and `İ' with `i'
+fn dotted_i(locale: &str) -> bool {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    language.eq_ignore_ascii_case("tr") || language.eq_ignore_ascii_case("az")
+}
+
+fn luhn(digits: &str) -> bool {
+    let mut sum = 0;
+    for (index, c) in digits.chars().rev().enumerate() {
+        let Some(digit) = c.to_digit(10) else {
+            return false;
+        };
+        sum += if index % 2 == 1 {
+            if digit > 4 { digit * 2 - 9 } else { digit * 2 }
+        } else {
+            digit
+        };
+    }
+    sum % 10 == 0
+}
+
+// checks the E.164 length, 8 to 15 digits with the calling code, numbering plans aren't checked;
+// with a region, national numbers may leave out the calling code and start with a trunk `0'
+fn phone_number(input: &str, calling_code: Option<&str>) -> bool {
+    let number = PHONE_SEPARATORS.replace_all(input.trim(), "");
+    let (international, digits) = match number.strip_prefix('+') {
+        Some(digits) => (true, digits.to_owned()),
+        None => match number.strip_prefix("00") {
+            Some(digits) => (true, digits.to_owned()),
+            None => (false, number.to_string()),
+        },
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let full = match (international, calling_code) {
+        (true, Some(code)) if !digits.starts_with(code) => return false,
+        (true, _) => digits,
+        (false, Some(code)) => format!("{}{}", code, digits.strip_prefix('0').unwrap_or(&digits)),
+        (false, None) => return false,
+    };
+    (8..=15).contains(&full.len())
+}
@@ -0,0 +1,366 @@
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, MANY, SINGLE, UPDATE};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::{Field, Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const DATA_KEY: &str = "position";
+const UNPOSITIONED_KEY: &str = "teo.unpositioned";
+const LOCKS_KEY: &str = "teo.position.locks";
+const GAP: i64 = 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Placement {
+    Before,
+    After,
+}
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("position", |args: Arguments, field: &mut Field| {
+        let scope: Option<String> = args.get_optional("scope")?;
+        field.data.insert(DATA_KEY.to_owned(), Value::String(scope.unwrap_or_default()).into());
+        Ok(())
+    });
+}
+
+pub fn position_field(model: &Model) -> Option<&Field> {
+    model.fields().into_iter().find(|f| f.data.contains_key(DATA_KEY))
+}
+
+// a relation scope is resolved to its local foreign key fields
+pub fn scope_keys(model: &Model, field: &Field) -> Vec<String> {
+    let scope = field.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_str()).unwrap_or("");
+    if scope.is_empty() {
+        vec![]
+    } else if let Some(relation) = model.relation(scope) {
+        relation.fields().iter().map(|f| f.to_string()).collect()
+    } else {
+        vec![scope.to_owned()]
+    }
+}
+
+// `create' inputs without a position get a placeholder to pass the validation, the paths of these
+// inputs are returned. the position of a nested create is required, the paths of nested records
+// aren't known when they're saved
+pub(crate) fn mark_unpositioned(model: &Model, action: &str, json_body: &mut JsonValue) -> BoxedResult<Vec<KeyPath>> {
+    match (action, json_body.get("create")) {
+        ("create" | "upsert", Some(create)) => check_nested_creates(model, create, &path!["create"])?,
+        ("createMany", Some(JsonValue::Array(creates))) => for (index, create) in creates.iter().enumerate() {
+            check_nested_creates(model, create, &path!["create", index])?;
+        },
+        _ => (),
+    }
+    if let ("update" | "upsert", Some(update)) = (action, json_body.get("update")) {
+        check_nested_creates(model, update, &path!["update"])?;
+    }
+    let Some(field) = position_field(model) else {
+        return Ok(vec![]);
+    };
+    let creates: Vec<(KeyPath, &mut JsonValue)> = match (action, json_body.get_mut("create")) {
+        ("create" | "upsert", Some(create)) => vec![(path!["create"], create)],
+        ("createMany", Some(JsonValue::Array(creates))) => creates.iter_mut().enumerate().map(|(index, create)| (path!["create", index], create)).collect(),
+        _ => vec![],
+    };
+    let mut unpositioned = vec![];
+    for (path, create) in creates {
+        if let Some(create) = create.as_object_mut().filter(|c| !c.contains_key(&field.name)) {
+            create.insert(field.name.clone(), JsonValue::from(0));
+            unpositioned.push(path);
+        }
+    }
+    Ok(unpositioned)
+}
+
+fn check_nested_creates(model: &Model, data: &JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    let Some(map) = data.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in map {
+        let Some(relation) = model.relation(key) else { continue };
+        let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else { continue };
+        let Some(operations) = value.as_object() else { continue };
+        let path = path + key.as_str();
+        for (operation, value) in operations {
+            let path = &path + operation.as_str();
+            let items: Vec<(KeyPath, &JsonValue)> = match value {
+                JsonValue::Array(items) => items.iter().enumerate().map(|(index, item)| (&path + index, item)).collect(),
+                item => vec![(path.clone(), item)],
+            };
+            for (path, item) in items {
+                match operation.as_str() {
+                    "create" | "createMany" => check_nested_create(related, item, &path)?,
+                    "connectOrCreate" => if let Some(create) = item.get("create") {
+                        check_nested_create(related, create, &(&path + "create"))?;
+                    },
+                    "upsert" => {
+                        if let Some(create) = item.get("create") {
+                            check_nested_create(related, create, &(&path + "create"))?;
+                        }
+                        if let Some(update) = item.get("update") {
+                            check_nested_creates(related, update, &(&path + "update"))?;
+                        }
+                    }
+                    "update" if relation.is_vec => if let Some(update) = item.get("update") {
+                        check_nested_creates(related, update, &(&path + "update"))?;
+                    },
+                    "update" => check_nested_creates(related, item, &path)?,
+                    _ => (),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_nested_create(model: &Model, create: &JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    if let Some(field) = position_field(model) {
+        if create.get(&field.name).is_none() {
+            Err(path::Error::value_error(path + field.name.as_str(), "position of a nested create is required"))?
+        }
+    }
+    check_nested_creates(model, create, path)
+}
+
+pub(crate) fn set_unpositioned(ctx: &request::Ctx, unpositioned: Vec<KeyPath>) {
+    ctx.data_mut().insert(UNPOSITIONED_KEY, unpositioned);
+}
+
+// the scopes of a move are locked before its siblings are read, so the positions it places the
+// record between aren't taken by a concurrent move or create
+fn request_scope_locks(req_ctx: &request::Ctx, model: &Model, scopes: Vec<Value>) {
+    let mut scopes: Vec<(String, Value)> = scopes.into_iter().map(|scope| (format!("{}", scope), scope)).collect();
+    scopes.sort_by(|a, b| a.0.cmp(&b.0));
+    scopes.dedup_by(|a, b| a.0 == b.0);
+    req_ctx.data_mut().insert(LOCKS_KEY, (model.path.clone(), scopes.into_iter().map(|(_, scope)| scope).collect::<Vec<_>>()));
+}
+
+// runs before a read of `model' on `transaction', takes the locks a move requested for it, in the
+// same order in every transaction
+pub(crate) async fn lock_requested_scopes(model: &Model, req_ctx: Option<&request::Ctx>, transaction: &dyn Transaction, provider: Option<Database>) -> path::Result<()> {
+    let Some(req_ctx) = req_ctx else {
+        return Ok(());
+    };
+    let requested = req_ctx.data().get::<(Vec<String>, Vec<Value>)>(LOCKS_KEY).filter(|(path, _)| *path == model.path).map(|(_, scopes)| scopes.clone());
+    let Some(scopes) = requested else {
+        return Ok(());
+    };
+    req_ctx.data_mut().insert(LOCKS_KEY, (model.path.clone(), Vec::<Value>::new()));
+    for scope in &scopes {
+        lock_scope(transaction, provider, model, scope).await?;
+    }
+    Ok(())
+}
+
+
+// appends a record to the end of its scope when its `create' input has no position, runs when the
+// record is saved, the relations providing the scope are connected and the last position is read
+// in the transaction which saves the record, with the scope locked on `transaction'
+pub(crate) async fn assign_position(object: &Object, path: &KeyPath, transaction: &dyn Transaction, provider: Option<Database>) -> path::Result<()> {
+    let model = object.model();
+    let Some(field) = position_field(model) else {
+        return Ok(());
+    };
+    let Some(req_ctx) = object.request_ctx() else {
+        return Ok(());
+    };
+    let unpositioned = req_ctx.data().get::<Vec<KeyPath>>(UNPOSITIONED_KEY).is_some_and(|paths| paths.contains(path));
+    if !unpositioned {
+        return Ok(());
+    }
+    let mut r#where = teon!({});
+    for key in scope_keys(model, field) {
+        let value = object.get_value(&key)?;
+        if value.is_null() {
+            return Ok(());
+        }
+        r#where.as_dictionary_mut().unwrap().insert(key, value);
+    }
+    lock_scope(transaction, provider, model, &r#where).await?;
+    let last = last_position(model, field, &r#where, &object.transaction_ctx()).await?;
+    let position = last.map(|p| p + GAP).unwrap_or(GAP);
+    object.set_value(&field.name, position_value(field, position, path)?)?;
+    Ok(())
+}
+
+pub async fn move_object(req_ctx: &request::Ctx, model: &'static Model, r#where: &Value, target: &Value, placement: Placement) -> path::Result<Object> {
+    let Some(field) = position_field(model) else {
+        return Err(path::Error::value_error_message_only(format!("model `{}' has no position field", model.path.join("."))));
+    };
+    let scope_keys = scope_keys(model, field);
+    let object = req_ctx.transaction_ctx().run_transaction(|ctx: transaction::Ctx| {
+        let scope_keys = scope_keys.clone();
+        async move {
+            let object = find(req_ctx, &ctx, model, r#where, path!["where"]).await?;
+            let target = find(req_ctx, &ctx, model, target, path!["target"]).await?;
+            if object.identifier() == target.identifier() {
+                return Err(path::Error::value_error(path!["target"], "cannot move a record relative to itself"));
+            }
+            let mut scope = teon!({});
+            for key in &scope_keys {
+                scope.as_dictionary_mut().unwrap().insert(key.clone(), target.get_value(key)?);
+            }
+            let mut source = teon!({});
+            for key in &scope_keys {
+                source.as_dictionary_mut().unwrap().insert(key.clone(), object.get_value(key)?);
+            }
+            request_scope_locks(req_ctx, model, vec![source, scope.clone()]);
+            let mut siblings = siblings(req_ctx, &ctx, model, field, &scope, &object).await?;
+            let fits = position_value(field, new_position(&siblings, &target, field, placement)?, &path![]).is_ok();
+            if !fits || !has_gap(&siblings, &target, field, placement)? {
+                renumber(&siblings, field).await?;
+                siblings = self::siblings(req_ctx, &ctx, model, field, &scope, &object).await?;
+            }
+            let position = new_position(&siblings, &target, field, placement)?;
+            for key in &scope_keys {
+                object.set_value(key, target.get_value(key)?)?;
+            }
+            object.set_value(&field.name, position_value(field, position, &path![])?)?;
+            object.save_with_session_and_path(&path![]).await?;
+            Ok(object)
+        }
+    }).await?;
+    Ok(object)
+}
+
+// reading the last position doesn't lock anything under READ COMMITTED, so the scope is locked
+// until the transaction ends, and the creates and moves in one scope take turns. PostgreSQL takes an
+// advisory lock on the scope. MySQL reads the scope for update, its next key locks keep the others
+// from inserting into it. SQLite lets one transaction write at a time, a concurrent create fails
+// to write instead of reading a stale position. MongoDB isn't locked.
+async fn lock_scope(transaction: &dyn Transaction, provider: Option<Database>, model: &Model, scope: &Value) -> path::Result<()> {
+    if !transaction.is_transaction() {
+        return Ok(());
+    }
+    let scope = scope.as_dictionary().unwrap();
+    let statement = match provider {
+        Some(Database::PostgreSQL) => {
+            let key = format!("teo.position:{}:{}", model.table_name, scope.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(","));
+            format!("SELECT pg_advisory_xact_lock(hashtext('{}'))", key.replace('\'', "''"))
+        }
+        Some(Database::MySQL) => {
+            let mut conditions = vec![];
+            for (key, value) in scope {
+                let column = model.field(key).map(|f| f.column_name.as_str()).unwrap_or(key);
+                // a scope value without a literal is left out, more of the table is locked
+                if let Some(value) = literal(value) {
+                    conditions.push(format!("`{}` = {}", column.replace('`', "``"), value));
+                }
+            }
+            let r#where = if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) };
+            format!("SELECT 1 FROM `{}`{} FOR UPDATE", model.table_name.replace('`', "``"), r#where)
+        }
+        _ => return Ok(()),
+    };
+    transaction.query_raw(&Value::String(statement)).await?;
+    Ok(())
+}
+
+fn literal(value: &Value) -> Option<String> {
+    match value {
+        Value::Int(i) => Some(i.to_string()),
+        Value::Int64(i) => Some(i.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::String(s) => Some(format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''"))),
+        _ => None,
+    }
+}
+
+async fn last_position(model: &'static Model, field: &Field, r#where: &Value, transaction_ctx: &transaction::Ctx) -> path::Result<Option<i64>> {
+    let finder = teon!({"where": r#where.clone(), "orderBy": [{field.name.as_str(): "desc"}], "take": 1i64});
+    let action: Action = FIND | MANY | ENTRY;
+    let objects = transaction_ctx.find_many_internal(model, &finder, true, action, None, path![]).await?;
+    match objects.first() {
+        Some(object) => Ok(position_of(&object.get_value(&field.name)?)),
+        None => Ok(None),
+    }
+}
+
+async fn find(req_ctx: &request::Ctx, ctx: &transaction::Ctx, model: &'static Model, r#where: &Value, path: KeyPath) -> path::Result<Object> {
+    let action: Action = UPDATE | SINGLE | ENTRY;
+    match ctx.find_unique_internal(model, &teon!({"where": r#where.clone()}), true, action, Some(req_ctx.clone()), path.clone()).await? {
+        Some(object) => Ok(object),
+        None => Err(path::Error::not_found(path)),
+    }
+}
+
+// the moved record itself is excluded, it is placed relative to the others
+async fn siblings(req_ctx: &request::Ctx, ctx: &transaction::Ctx, model: &'static Model, field: &Field, scope: &Value, object: &Object) -> path::Result<Vec<Object>> {
+    let mut order_by = vec![teon!({field.name.as_str(): "asc"})];
+    for key in model.primary_index().unwrap().keys() {
+        order_by.push(teon!({key.as_str(): "asc"}));
+    }
+    let finder = teon!({"where": scope.clone(), "orderBy": Value::Array(order_by)});
+    let action: Action = UPDATE | MANY | ENTRY;
+    let objects = ctx.find_many_internal(model, &finder, true, action, Some(req_ctx.clone()), path![]).await?;
+    Ok(objects.into_iter().filter(|o| o.identifier() != object.identifier()).collect())
+}
+
+fn position(object: &Object, field: &Field) -> BoxedResult<i64> {
+    Ok(position_of(&object.get_value(&field.name)?).unwrap_or(0))
+}
+
+fn position_of(value: &Value) -> Option<i64> {
+    value.to_int64().or_else(|| value.to_float().map(|f| f as i64))
+}
+
+fn neighbours(siblings: &[Object], target: &Object, field: &Field, placement: Placement) -> BoxedResult<(i64, Option<i64>)> {
+    // the target may be hidden from the siblings by the read rules
+    let Some(index) = siblings.iter().position(|o| o.identifier() == target.identifier()) else {
+        return Err(path::Error::not_found(path!["target"]).into());
+    };
+    let target_position = position(&siblings[index], field)?;
+    let neighbour = match placement {
+        Placement::Before => if index == 0 { None } else { Some(position(&siblings[index - 1], field)?) },
+        Placement::After => match siblings.get(index + 1) {
+            Some(next) => Some(position(next, field)?),
+            None => None,
+        },
+    };
+    Ok((target_position, neighbour))
+}
+
+fn has_gap(siblings: &[Object], target: &Object, field: &Field, placement: Placement) -> BoxedResult<bool> {
+    Ok(match neighbours(siblings, target, field, placement)? {
+        (target, Some(neighbour)) => (target - neighbour).abs() >= 2,
+        (_, None) => true,
+    })
+}
+
+fn new_position(siblings: &[Object], target: &Object, field: &Field, placement: Placement) -> BoxedResult<i64> {
+    Ok(match (neighbours(siblings, target, field, placement)?, placement) {
+        ((target, Some(neighbour)), _) => neighbour + (target - neighbour) / 2,
+        ((target, None), Placement::Before) => target - GAP,
+        ((target, None), Placement::After) => target + GAP,
+    })
+}
+
+async fn renumber(siblings: &[Object], field: &Field) -> path::Result<()> {
+    for (index, sibling) in siblings.iter().enumerate() {
+        sibling.set_value(&field.name, position_value(field, (index as i64 + 1) * GAP, &path![])?)?;
+        sibling.save_with_session_and_path(&path![]).await?;
+    }
+    Ok(())
+}
+
+fn position_value(field: &Field, position: i64, path: &KeyPath) -> BoxedResult<Value> {
+    if field.r#type.is_int() {
+        match i32::try_from(position) {
+            Ok(position) => Ok(Value::Int(position)),
+            Err(_) => Err(path::Error::value_error(path + field.name.as_str(), "position is out of the range of `Int'").into()),
+        }
+    } else if field.r#type.is_int64() {
+        Ok(Value::Int64(position))
+    } else {
+        Ok(Value::Float(position as f64))
+    }
+}
@@ -1,10 +1,12 @@
-use teo_result::Result;
+use teo_result::{Error, Result};
 use crate::app::ctx::Ctx;
 
 pub(crate) async fn purge() -> Result<()> {
     let ctx = Ctx::conn_ctx();
     for (namespace_path, connection) in ctx.connections_iter() {
-        let namespace = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()).unwrap();
+        let Some(namespace) = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else {
+            Err(Error::new(format!("namespace `{}' is not found", namespace_path.join("."))))?
+        };
         let transaction = connection.no_transaction().await?;
         transaction.purge(namespace.models_under_connector()).await?;
     }
@@ -0,0 +1,161 @@
+use std::time::Duration;
+use chrono::Utc;
+use key_path::path;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::namespace::Namespace;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::message::info_message;
+
+const DATA_KEY: &str = "retention";
+const PAGE_SIZE: usize = 500;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone)]
+pub struct Retention {
+    pub days: i64,
+    pub field: String,
+    pub soft_delete: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RetentionReport {
+    pub model: String,
+    pub expired: usize,
+    pub soft_deleted: bool,
+}
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("retention", |args: Arguments, model: &mut Model| {
+        let days: i32 = args.get("days")?;
+        if days <= 0 {
+            Err(Error::new("retention days should be positive"))?
+        }
+        let field: String = args.get("field")?;
+        let soft_delete: Option<String> = args.get_optional("softDelete")?;
+        model.data.insert(DATA_KEY.to_owned(), teon!({
+            "days": days as i64,
+            "field": field,
+            "softDelete": soft_delete.map_or(Value::Null, Value::String),
+        }).into());
+        Ok(())
+    });
+}
+
+pub fn retention(model: &Model) -> Option<Retention> {
+    let value = model.data.get(DATA_KEY).and_then(|o| o.as_teon())?;
+    Some(Retention {
+        days: value.get("days")?.to_int64()?,
+        field: value.get("field")?.as_str()?.to_owned(),
+        soft_delete: value.get("softDelete").and_then(|s| s.as_str()).map(ToOwned::to_owned),
+    })
+}
+
+/// Deletes, or marks with the `softDelete` field, the records whose timestamp field is older than
+/// the retention window. Nothing is changed with `dry_run`, the expired records are only counted.
+pub async fn enforce_retention(ctx: transaction::Ctx, dry_run: bool) -> Result<Vec<RetentionReport>> {
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    let mut reports = vec![];
+    for (model, retention) in models {
+        let r#where = expired_filter(model, &retention)?;
+        let expired = if dry_run {
+            ctx.count(model, &teon!({"where": r#where}), path![]).await?
+        } else {
+            expire(&ctx, model, &retention, r#where).await?
+        };
+        reports.push(RetentionReport { model: model.path.join("."), expired, soft_deleted: retention.soft_delete.is_some() });
+    }
+    Ok(reports)
+}
+
+pub(crate) fn report_retention(reports: &Vec<RetentionReport>, dry_run: bool) {
+    for report in reports {
+        let verb = match (dry_run, report.soft_deleted) {
+            (true, _) => "expired",
+            (false, true) => "soft deleted",
+            (false, false) => "deleted",
+        };
+        info_message(format!("{}: {} record(s) {}", report.model, report.expired, verb));
+    }
+}
+
+/// Enforces the retention policies in the background, once at start and then every hour.
+pub(crate) fn schedule_retention(ctx: transaction::Ctx, silent: bool) {
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    if models.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            match enforce_retention(ctx.clone(), false).await {
+                Ok(reports) => if !silent {
+                    report_retention(&reports.into_iter().filter(|r| r.expired > 0).collect(), false);
+                },
+                Err(error) => info_message(format!("retention failed: {}", error.message())),
+            }
+        }
+    });
+}
+
+fn collect_models(namespace: &'static Namespace, models: &mut Vec<(&'static Model, Retention)>) {
+    for model in namespace.models.values() {
+        if let Some(retention) = retention(model) {
+            models.push((model, retention));
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_models(child, models);
+    }
+}
+
+fn expired_filter(model: &Model, retention: &Retention) -> Result<Value> {
+    let Some(field) = model.field(&retention.field) else {
+        Err(Error::new(format!("retention field `{}' is not found on {}", retention.field, model.path.join("."))))?
+    };
+    let cutoff = Utc::now() - chrono::Duration::days(retention.days);
+    let cutoff = match field.r#type.unwrap_optional() {
+        Type::DateTime => Value::DateTime(cutoff),
+        Type::Date => Value::Date(cutoff.date_naive()),
+        _ => Err(Error::new(format!("retention field `{}' of {} should be a date or datetime", retention.field, model.path.join("."))))?,
+    };
+    let mut r#where = teon!({ retention.field.as_str(): {"lt": cutoff} });
+    if let Some(soft_delete) = &retention.soft_delete {
+        if !model.field(soft_delete).is_some_and(|f| f.is_optional() && f.r#type.unwrap_optional().is_datetime()) {
+            Err(Error::new(format!("soft delete field `{}' of {} should be an optional datetime", soft_delete, model.path.join("."))))?
+        }
+        r#where.as_dictionary_mut().unwrap().insert(soft_delete.clone(), teon!({"equals": null}));
+    }
+    Ok(r#where)
+}
+
+// expired records leave the result set once handled, so the first page is loaded again
+async fn expire(ctx: &transaction::Ctx, model: &'static Model, retention: &Retention, r#where: Value) -> Result<usize> {
+    let mut expired = 0;
+    loop {
+        let finder = teon!({"where": r#where.clone(), "take": PAGE_SIZE as i64});
+        let objects: Vec<Object> = ctx.find_many(model, &finder, None, path![]).await?;
+        for object in &objects {
+            match &retention.soft_delete {
+                Some(soft_delete) => {
+                    object.set_value(soft_delete, Value::DateTime(Utc::now()))?;
+                    object.save().await?;
+                }
+                None => object.delete().await?,
+            }
+        }
+        expired += objects.len();
+        if objects.len() < PAGE_SIZE {
+            break
+        }
+    }
+    Ok(expired)
+}
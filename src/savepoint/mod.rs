@@ -0,0 +1,92 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::path;
+use teo_teon::value::Value;
+use crate::event_sink::ChangeEvent;
+use crate::isolation::{run_isolated, TransactionOptions};
+
+tokio::task_local! {
+    static FRAME: Arc<Frame>;
+}
+
+// a call of `transaction', the outermost one owns the transaction and the others a savepoint
+struct Frame {
+    savepoint: Option<String>,
+    parent: Option<Arc<Frame>>,
+    // the transactions the savepoint was set on, with the number of their pending change events
+    established: Mutex<Vec<Established>>,
+}
+
+struct Established {
+    transaction: Arc<dyn Transaction>,
+    changes: Arc<Mutex<Vec<ChangeEvent>>>,
+    mark: usize,
+}
+
+/// Runs `f` in a transaction, committed when it succeeds and rolled back when it fails. Called
+/// again in `f`, it runs in a savepoint of that transaction instead: an error of the nested `f`
+/// rolls back its own writes only, and the outer `f` carries on, or retries it. The outer
+/// transaction commits or rolls back as a whole.
+///
+/// Savepoints are a feature of the SQL databases, a nested write to MongoDB fails. A
+/// `run_transaction` of the context isn't a transaction of this function, a call in it starts
+/// a transaction of its own.
+pub async fn transaction<F, Fut, R>(ctx: &transaction::Ctx, f: F) -> path::Result<R> where
+    F: Fn(transaction::Ctx) -> Fut,
+    Fut: Future<Output = path::Result<R>> {
+    transaction_with(ctx, TransactionOptions::default(), f).await
+}
+
+/// Like `transaction`, with the isolation level of `options`, and run again from the start
+/// when it fails to serialize. A nested call belongs to the transaction of the outer one, its
+/// options are ignored.
+pub async fn transaction_with<F, Fut, R>(ctx: &transaction::Ctx, options: TransactionOptions, f: F) -> path::Result<R> where
+    F: Fn(transaction::Ctx) -> Fut,
+    Fut: Future<Output = path::Result<R>> {
+    let Ok(parent) = FRAME.try_with(Clone::clone) else {
+        return run_isolated(options, || ctx.run_transaction(|ctx: transaction::Ctx| {
+            let frame = Arc::new(Frame { savepoint: None, parent: None, established: Mutex::new(vec![]) });
+            FRAME.scope(frame, f(ctx))
+        })).await;
+    };
+    let depth = std::iter::successors(Some(&parent), |frame| frame.parent.as_ref()).count();
+    let frame = Arc::new(Frame { savepoint: Some(format!("teo_savepoint_{}", depth)), parent: Some(parent), established: Mutex::new(vec![]) });
+    let result = FRAME.scope(frame.clone(), f(ctx.clone())).await;
+    let name = frame.savepoint.as_ref().unwrap();
+    let established = std::mem::take(&mut *frame.established.lock().unwrap());
+    for established in established {
+        if result.is_err() {
+            established.transaction.query_raw(&Value::String(format!("ROLLBACK TO SAVEPOINT {}", name))).await?;
+            established.changes.lock().unwrap().truncate(established.mark);
+        }
+        established.transaction.query_raw(&Value::String(format!("RELEASE SAVEPOINT {}", name))).await?;
+    }
+    result
+}
+
+/// Sets the savepoints of the calls in scope on `transaction` before its first write in them.
+pub(crate) async fn set_savepoints(transaction: &Arc<dyn Transaction>, provider: Option<Database>, changes: &Arc<Mutex<Vec<ChangeEvent>>>) -> Result<()> {
+    let Ok(frame) = FRAME.try_with(Clone::clone) else {
+        return Ok(());
+    };
+    if !transaction.is_transaction() {
+        return Ok(());
+    }
+    let frames: Vec<Arc<Frame>> = std::iter::successors(Some(frame), |frame| frame.parent.clone()).filter(|frame| frame.savepoint.is_some()).collect();
+    for frame in frames.into_iter().rev() {
+        if frame.established.lock().unwrap().iter().any(|e| Arc::ptr_eq(&e.transaction, transaction)) {
+            continue;
+        }
+        match provider {
+            Some(Database::MySQL) | Some(Database::PostgreSQL) | Some(Database::SQLite) => (),
+            _ => Err(Error::new("nested transactions need savepoints, which this database doesn't have"))?,
+        }
+        transaction.query_raw(&Value::String(format!("SAVEPOINT {}", frame.savepoint.as_ref().unwrap()))).await?;
+        let mark = changes.lock().unwrap().len();
+        frame.established.lock().unwrap().push(Established { transaction: transaction.clone(), changes: changes.clone(), mark });
+    }
+    Ok(())
+}
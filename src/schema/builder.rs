@@ -0,0 +1,549 @@
+use teo_runtime::sort::Sort;
+
+/// Builds the source of a schema from Rust, for apps which can't ship schema files. The source is
+/// parsed and checked like a schema file, so everything the schema language supports is
+/// available. Pipelines, default values and other expressions are given in schema syntax, e.g.
+/// `$presents.isEmail` or `$now`.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaBuilder {
+    blocks: Vec<String>,
+}
+
+impl SchemaBuilder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `provider` is a database name like `sqlite` or `postgres`.
+    pub fn connector(mut self, provider: &str, url: &str) -> Self {
+        self.blocks.push(format!("connector {{\n  provider: .{},\n  url: {}\n}}", provider, quote(url)));
+        self
+    }
+
+    pub fn server(mut self, host: &str, port: u16) -> Self {
+        self.blocks.push(format!("server {{\n  bind: ({}, {})\n}}", quote(host), port));
+        self
+    }
+
+    pub fn r#enum(mut self, r#enum: EnumBuilder) -> Self {
+        self.blocks.push(r#enum.source());
+        self
+    }
+
+    pub fn model(mut self, model: ModelBuilder) -> Self {
+        self.blocks.push(model.source());
+        self
+    }
+
+    /// Appends a block written in the schema language, e.g. a config, an interface or a
+    /// `declare` statement.
+    pub fn raw(mut self, source: &str) -> Self {
+        self.blocks.push(source.trim().to_owned());
+        self
+    }
+
+    pub fn source(&self) -> String {
+        self.blocks.join("\n\n") + "\n"
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumBuilder {
+    name: String,
+    doc: Option<String>,
+    members: Vec<(String, Option<String>)>,
+}
+
+impl EnumBuilder {
+
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_owned(), doc: None, members: vec![] }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+
+    pub fn member(mut self, name: &str) -> Self {
+        self.members.push((name.to_owned(), None));
+        self
+    }
+
+    pub fn member_with_doc(mut self, name: &str, doc: &str) -> Self {
+        self.members.push((name.to_owned(), Some(doc.to_owned())));
+        self
+    }
+
+    fn source(&self) -> String {
+        let mut source = doc_lines(self.doc.as_deref(), "");
+        source += &format!("enum {} {{\n", self.name);
+        for (name, doc) in &self.members {
+            source += &doc_lines(doc.as_deref(), "  ");
+            source += &format!("  {}\n", name);
+        }
+        source + "}"
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelMigration {
+    pub renamed: Vec<String>,
+    pub version: Option<String>,
+    pub drop: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelBuilder {
+    name: String,
+    doc: Option<String>,
+    decorators: Vec<String>,
+    members: Vec<String>,
+}
+
+impl ModelBuilder {
+
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_owned(), doc: None, decorators: vec![], members: vec![] }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_owned());
+        self
+    }
+
+    /// Adds a model decorator written in the schema language, e.g. `@generateClient(false)`.
+    pub fn decorator(mut self, decorator: &str) -> Self {
+        self.decorators.push(decorator.to_owned());
+        self
+    }
+
+    pub fn map(self, table_name: &str) -> Self {
+        let decorator = format!("@map({})", quote(table_name));
+        self.decorator(&decorator)
+    }
+
+    pub fn index(self, index: IndexBuilder) -> Self {
+        let decorator = index.source();
+        self.decorator(&decorator)
+    }
+
+    pub fn migration(self, migration: ModelMigration) -> Self {
+        let mut arguments = vec![];
+        if !migration.renamed.is_empty() {
+            arguments.push(format!("renamed: {}", string_list(&migration.renamed)));
+        }
+        if let Some(version) = &migration.version {
+            arguments.push(format!("version: {}", quote(version)));
+        }
+        if migration.drop {
+            arguments.push("drop: true".to_owned());
+        }
+        let decorator = format!("@migration({})", arguments.join(", "));
+        self.decorator(&decorator)
+    }
+
+    pub fn can_read(self, pipeline: &str) -> Self {
+        let decorator = format!("@canRead({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn can_mutate(self, pipeline: &str) -> Self {
+        let decorator = format!("@canMutate({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn before_save(self, pipeline: &str) -> Self {
+        let decorator = format!("@beforeSave({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn after_save(self, pipeline: &str) -> Self {
+        let decorator = format!("@afterSave({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn before_delete(self, pipeline: &str) -> Self {
+        let decorator = format!("@beforeDelete({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn after_delete(self, pipeline: &str) -> Self {
+        let decorator = format!("@afterDelete({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.members.push(field.member.source());
+        self
+    }
+
+    pub fn relation(mut self, relation: RelationBuilder) -> Self {
+        self.members.push(relation.member.source());
+        self
+    }
+
+    pub fn property(mut self, property: PropertyBuilder) -> Self {
+        self.members.push(property.member.source());
+        self
+    }
+
+    fn source(&self) -> String {
+        let mut source = doc_lines(self.doc.as_deref(), "");
+        for decorator in &self.decorators {
+            source += &format!("{}\n", decorator);
+        }
+        source += &format!("model {} {{\n", self.name);
+        source += &self.members.join("");
+        source + "}"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    Primary,
+    Unique,
+    Index,
+}
+
+/// An index of several fields. An index of one field is declared on the field with
+/// `FieldBuilder::index` and its siblings.
+#[derive(Debug, Clone)]
+pub struct IndexBuilder {
+    kind: IndexKind,
+    keys: Vec<(String, Option<Sort>, Option<i64>)>,
+    map: Option<String>,
+}
+
+impl IndexBuilder {
+
+    pub fn new(kind: IndexKind) -> Self {
+        Self { kind, keys: vec![], map: None }
+    }
+
+    pub fn key(mut self, field: &str) -> Self {
+        self.keys.push((field.to_owned(), None, None));
+        self
+    }
+
+    /// A key with an order, and the length of the indexed prefix for databases which support it.
+    pub fn key_with_options(mut self, field: &str, sort: Option<Sort>, length: Option<i64>) -> Self {
+        self.keys.push((field.to_owned(), sort, length));
+        self
+    }
+
+    pub fn map(mut self, name: &str) -> Self {
+        self.map = Some(name.to_owned());
+        self
+    }
+
+    fn source(&self) -> String {
+        let name = match self.kind {
+            IndexKind::Primary => "id",
+            IndexKind::Unique => "unique",
+            IndexKind::Index => "index",
+        };
+        let keys: Vec<String> = self.keys.iter().map(|(field, sort, length)| {
+            let options = index_options(*sort, *length, None);
+            if options.is_empty() {
+                format!(".{}", field)
+            } else {
+                format!(".{}({})", field, options.join(", "))
+            }
+        }).collect();
+        match &self.map {
+            Some(map) => format!("@{}([{}], map: {})", name, keys.join(", "), quote(map)),
+            None => format!("@{}([{}])", name, keys.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FieldMigration {
+    pub renamed: Vec<String>,
+    pub version: Option<String>,
+    /// The value existing records get when the column is added, in schema syntax.
+    pub default: Option<String>,
+    pub priority: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+struct Member {
+    name: String,
+    r#type: String,
+    doc: Option<String>,
+    decorators: Vec<String>,
+}
+
+impl Member {
+
+    fn new(name: &str, r#type: &str) -> Self {
+        Self { name: name.to_owned(), r#type: r#type.to_owned(), doc: None, decorators: vec![] }
+    }
+
+    fn source(&self) -> String {
+        let mut source = doc_lines(self.doc.as_deref(), "  ");
+        if !self.decorators.is_empty() {
+            source += &format!("  {}\n", self.decorators.join(" "));
+        }
+        source + &format!("  {}: {}\n", self.name, self.r#type)
+    }
+}
+
+/// A field, its type is written in the schema language, e.g. `String?` or `Int[]`.
+#[derive(Debug, Clone)]
+pub struct FieldBuilder {
+    member: Member,
+}
+
+impl FieldBuilder {
+
+    pub fn new(name: &str, r#type: &str) -> Self {
+        Self { member: Member::new(name, r#type) }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.member.doc = Some(doc.to_owned());
+        self
+    }
+
+    /// Adds a field decorator written in the schema language, e.g. `@queryable`.
+    pub fn decorator(mut self, decorator: &str) -> Self {
+        self.member.decorators.push(decorator.to_owned());
+        self
+    }
+
+    pub fn id(self) -> Self {
+        self.decorator("@id")
+    }
+
+    pub fn auto_increment(self) -> Self {
+        self.decorator("@autoIncrement")
+    }
+
+    pub fn unique(self) -> Self {
+        self.decorator("@unique")
+    }
+
+    pub fn index(self) -> Self {
+        self.decorator("@index")
+    }
+
+    /// An index of this field with an order, a prefix length and a name.
+    pub fn index_with_options(self, kind: IndexKind, sort: Option<Sort>, length: Option<i64>, map: Option<&str>) -> Self {
+        let name = match kind {
+            IndexKind::Primary => "id",
+            IndexKind::Unique => "unique",
+            IndexKind::Index => "index",
+        };
+        let options = index_options(sort, length, map);
+        let decorator = if options.is_empty() { format!("@{}", name) } else { format!("@{}({})", name, options.join(", ")) };
+        self.decorator(&decorator)
+    }
+
+    pub fn map(self, column_name: &str) -> Self {
+        let decorator = format!("@map({})", quote(column_name));
+        self.decorator(&decorator)
+    }
+
+    /// `value` is a value or a pipeline in schema syntax.
+    pub fn default(self, value: &str) -> Self {
+        let decorator = format!("@default({})", value);
+        self.decorator(&decorator)
+    }
+
+    pub fn readonly(self) -> Self {
+        self.decorator("@readonly")
+    }
+
+    pub fn writeonly(self) -> Self {
+        self.decorator("@writeonly")
+    }
+
+    pub fn internal(self) -> Self {
+        self.decorator("@internal")
+    }
+
+    pub fn foreign_key(self) -> Self {
+        self.decorator("@foreignKey")
+    }
+
+    pub fn on_set(self, pipeline: &str) -> Self {
+        let decorator = format!("@onSet({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn on_save(self, pipeline: &str) -> Self {
+        let decorator = format!("@onSave({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn on_output(self, pipeline: &str) -> Self {
+        let decorator = format!("@onOutput({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn can_read(self, pipeline: &str) -> Self {
+        let decorator = format!("@canRead({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn can_mutate(self, pipeline: &str) -> Self {
+        let decorator = format!("@canMutate({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn read_if(self, pipeline: &str) -> Self {
+        let decorator = format!("@readIf({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn write_if(self, pipeline: &str) -> Self {
+        let decorator = format!("@writeIf({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn migration(self, migration: FieldMigration) -> Self {
+        let mut arguments = vec![];
+        if !migration.renamed.is_empty() {
+            arguments.push(format!("renamed: {}", string_list(&migration.renamed)));
+        }
+        if let Some(version) = &migration.version {
+            arguments.push(format!("version: {}", quote(version)));
+        }
+        if let Some(default) = &migration.default {
+            arguments.push(format!("default: {}", default));
+        }
+        if let Some(priority) = migration.priority {
+            arguments.push(format!("priority: {}", priority));
+        }
+        let decorator = format!("@migration({})", arguments.join(", "));
+        self.decorator(&decorator)
+    }
+
+    pub fn dropped(self) -> Self {
+        self.decorator("@dropped")
+    }
+}
+
+/// A relation, its type is the related model, e.g. `User?` or `Post[]`.
+#[derive(Debug, Clone)]
+pub struct RelationBuilder {
+    member: Member,
+}
+
+impl RelationBuilder {
+
+    pub fn new(name: &str, r#type: &str, fields: &[&str], references: &[&str]) -> Self {
+        let mut member = Member::new(name, r#type);
+        member.decorators.push(format!("@relation(fields: {}, references: {})", field_list(fields), field_list(references)));
+        Self { member }
+    }
+
+    /// A relation through the join model `through`, `local` and `foreign` are its relations to
+    /// this model and to the related one.
+    pub fn through(name: &str, r#type: &str, through: &str, local: &str, foreign: &str) -> Self {
+        let mut member = Member::new(name, r#type);
+        member.decorators.push(format!("@relation(through: {}, local: .{}, foreign: .{})", through, local, foreign));
+        Self { member }
+    }
+
+    /// A many to many relation joined by a generated model, the related model declares the
+    /// relation back to this one the same way.
+    pub fn implicit(name: &str, r#type: &str) -> Self {
+        let mut member = Member::new(name, r#type);
+        member.decorators.push("@relation".to_owned());
+        Self { member }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.member.doc = Some(doc.to_owned());
+        self
+    }
+
+    pub fn decorator(mut self, decorator: &str) -> Self {
+        self.member.decorators.push(decorator.to_owned());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PropertyBuilder {
+    member: Member,
+}
+
+impl PropertyBuilder {
+
+    pub fn new(name: &str, r#type: &str) -> Self {
+        Self { member: Member::new(name, r#type) }
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.member.doc = Some(doc.to_owned());
+        self
+    }
+
+    pub fn decorator(mut self, decorator: &str) -> Self {
+        self.member.decorators.push(decorator.to_owned());
+        self
+    }
+
+    pub fn getter(self, pipeline: &str) -> Self {
+        let decorator = format!("@getter({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    pub fn setter(self, pipeline: &str) -> Self {
+        let decorator = format!("@setter({})", pipeline);
+        self.decorator(&decorator)
+    }
+
+    /// Stores the value in a column, it is recalculated when one of `deps` changes.
+    pub fn cached(self, deps: &[&str]) -> Self {
+        let decorator = format!("@cached @deps({})", field_list(deps));
+        self.decorator(&decorator)
+    }
+
+    pub fn index(self) -> Self {
+        self.decorator("@index")
+    }
+
+    pub fn unique(self) -> Self {
+        self.decorator("@unique")
+    }
+}
+
+fn index_options(sort: Option<Sort>, length: Option<i64>, map: Option<&str>) -> Vec<String> {
+    let mut options = vec![];
+    if let Some(sort) = sort {
+        options.push(format!("sort: .{}", if sort == Sort::Asc { "asc" } else { "desc" }));
+    }
+    if let Some(length) = length {
+        options.push(format!("length: {}", length));
+    }
+    if let Some(map) = map {
+        options.push(format!("map: {}", quote(map)));
+    }
+    options
+}
+
+fn doc_lines(doc: Option<&str>, indent: &str) -> String {
+    doc.map(|doc| doc.lines().map(|line| format!("{}/// {}\n", indent, line)).collect()).unwrap_or_default()
+}
+
+fn field_list(fields: &[&str]) -> String {
+    if fields.len() == 1 {
+        format!(".{}", fields[0])
+    } else {
+        format!("[{}]", fields.iter().map(|f| format!(".{}", f)).collect::<Vec<String>>().join(", "))
+    }
+}
+
+fn string_list(strings: &[String]) -> String {
+    format!("[{}]", strings.iter().map(|s| quote(s)).collect::<Vec<String>>().join(", "))
+}
+
+fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
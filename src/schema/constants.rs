@@ -0,0 +1,94 @@
+use teo_parser::ast::arith_expr::{ArithExpr, ArithExprOperator, BinaryOperation};
+use teo_parser::ast::expression::ExpressionKind;
+use teo_parser::ast::node::Node;
+use teo_parser::ast::schema::Schema;
+use teo_parser::diagnostics::diagnostics::{Diagnostics, DiagnosticsError, DiagnosticsLog};
+use teo_parser::r#type::Type;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_parser::traits::resolved::Resolve;
+
+/// Names the operands of the arithmetic the parser rejects as an `invalid expression`.
+pub(crate) fn explain_invalid_expressions(schema: &Schema, diagnostics: Diagnostics) -> Diagnostics {
+    if !diagnostics.errors().iter().any(|e| e.message().ends_with("invalid expression")) {
+        return diagnostics;
+    }
+    let mut operations = vec![];
+    for source in schema.sources() {
+        if source.builtin {
+            continue;
+        }
+        for node in source.children.values() {
+            collect_operations(node, &source.file_path, &mut operations);
+        }
+    }
+    let mut result = Diagnostics::new();
+    for error in diagnostics.errors() {
+        let operation = operations.iter().find(|(path, o)| *path == error.source_path() && &o.span() == error.span());
+        let message = operation.and_then(|(_, o)| explain(o)).unwrap_or_else(|| error.message().to_owned());
+        result.insert(DiagnosticsError::new(*error.span(), message, error.source_path()));
+    }
+    for warning in diagnostics.warnings() {
+        result.insert(warning.clone());
+    }
+    result
+}
+
+fn collect_operations<'a>(node: &'a Node, file_path: &'a str, operations: &mut Vec<(&'a str, &'a BinaryOperation)>) {
+    // an operation is kept as the kind of its expression, which lists the operands as children
+    let operation = match node {
+        Node::ArithExpr(ArithExpr::BinaryOperation(operation)) => Some(operation),
+        Node::Expression(expression) => match &expression.kind {
+            ExpressionKind::ArithExpr(ArithExpr::BinaryOperation(operation)) => Some(operation),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(operation) = operation {
+        operations.push((file_path, operation));
+    }
+    for child in node.children().into_iter().flat_map(|c| c.values()) {
+        collect_operations(child, file_path, operations);
+    }
+}
+
+fn explain(operation: &BinaryOperation) -> Option<String> {
+    let lhs = operand_type(operation.lhs())?;
+    let rhs = operand_type(operation.rhs())?;
+    Some(format!("cannot apply `{}' to {} and {}", symbol(operation.op)?, lhs, rhs))
+}
+
+// the parser keeps the types of the operands only, operations take the type of their left side
+fn operand_type(arith_expr: &ArithExpr) -> Option<Type> {
+    let r#type = match arith_expr {
+        ArithExpr::Expression(expression) if expression.is_resolved() => expression.resolved().r#type().clone(),
+        ArithExpr::Expression(_) => None?,
+        ArithExpr::UnaryOperation(operation) => match operation.op {
+            ArithExprOperator::Not => Type::Bool,
+            _ => operand_type(operation.rhs())?,
+        },
+        ArithExpr::UnaryPostfixOperation(operation) => operand_type(operation.lhs())?.unwrap_optional().clone(),
+        ArithExpr::BinaryOperation(operation) => match operation.op {
+            ArithExprOperator::Gt | ArithExprOperator::Gte | ArithExprOperator::Lt | ArithExprOperator::Lte | ArithExprOperator::Eq | ArithExprOperator::Neq => Type::Bool,
+            _ => operand_type(operation.lhs())?,
+        },
+    };
+    if r#type.is_undetermined() { None } else { Some(r#type) }
+}
+
+fn symbol(op: ArithExprOperator) -> Option<&'static str> {
+    Some(match op {
+        ArithExprOperator::Add => "+",
+        ArithExprOperator::Sub => "-",
+        ArithExprOperator::Mul => "*",
+        ArithExprOperator::Div => "/",
+        ArithExprOperator::Mod => "%",
+        ArithExprOperator::BitAnd => "&",
+        ArithExprOperator::BitXor => "^",
+        ArithExprOperator::BitOr => "|",
+        ArithExprOperator::BitLS => "<<",
+        ArithExprOperator::BitRS => ">>",
+        ArithExprOperator::RangeOpen => "..",
+        ArithExprOperator::RangeClose => "...",
+        _ => None?,
+    })
+}
@@ -0,0 +1,128 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use teo_parser::{parse as schema_parse};
+use teo_parser::ast::field::Field;
+use teo_parser::ast::model::Model;
+use teo_parser::ast::schema::Schema;
+use teo_parser::diagnostics::diagnostics::Diagnostics;
+use teo_parser::traits::identifiable::Identifiable;
+use teo_parser::traits::info_provider::InfoProvider;
+use teo_parser::traits::named_identifiable::NamedIdentifiable;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_parser::traits::resolved::Resolve;
+use teo_result::{Error, Result};
+use crate::schema::packages::parse_with_packages;
+
+/// Parses the schema at `main`, with the join models of its implicit many to many relations.
+pub(crate) fn parse_schema(main: &str, unsaved_files: Option<BTreeMap<String, String>>) -> Result<(Schema, Diagnostics)> {
+    let (schema, diagnostics, mut files) = parse_with_packages(main, unsaved_files)?;
+    let relations = implicit_relations(&schema);
+    if relations.is_empty() {
+        return Ok((schema, diagnostics));
+    }
+    let mut edits: BTreeMap<String, Vec<(usize, usize, String)>> = BTreeMap::new();
+    for (model, field, related) in &relations {
+        let opposite: Vec<&(&Model, &Field, &Model)> = relations.iter().filter(|(m, _, r)| m.path() == related.path() && r.path() == model.path()).collect();
+        let name = format!("{}.{}", model.string_path().join("."), field.name());
+        if model.path() == related.path() {
+            Err(Error::new(format!("`{}' joins a model to itself, implicit relations need two models, declare a join model with `@relation(through:)'", name)))?
+        }
+        if opposite.len() != 1 || relations.iter().filter(|(m, _, r)| m.path() == model.path() && r.path() == related.path()).count() != 1 {
+            Err(Error::new(format!("`{}' has no single implicit relation back from `{}', declare both sides with a bare `@relation' or a join model with `@relation(through:)'", name, related.name())))?
+        }
+        if model.namespace_str_path() != related.namespace_str_path() {
+            Err(Error::new(format!("`{}' relates models of different namespaces, declare a join model with `@relation(through:)'", name)))?
+        }
+        let join = join_model_name(model, related);
+        if schema.models().iter().any(|m| m.namespace_str_path() == model.namespace_str_path() && m.name() == join) {
+            Err(Error::new(format!("`{}' is joined by `{}', which is already declared", name, join)))?
+        }
+        let decorator = field.decorators().find(|d| is_relation(d.identifier_path().names())).unwrap();
+        let file_path = source_file_path(&schema, *field);
+        edits.entry(file_path).or_default().push((decorator.span().start, decorator.span().end, format!("@relation(through: {}, local: .{}, foreign: .{})", join, relation_name(model), relation_name(related))));
+        // the join model follows the first of its models
+        if model.name() < related.name() {
+            let source = join_model_source(&schema, &files, &join, model, related)?;
+            edits.entry(source_file_path(&schema, *model)).or_default().push((model.span().end, model.span().end, source));
+        }
+    }
+    for (file_path, mut edits) in edits {
+        let mut content = source_content(&files, &file_path)?;
+        edits.sort_by_key(|e| Reverse(e.0));
+        for (start, end, text) in edits {
+            content.replace_range(start..end, &text);
+        }
+        files.insert(file_path, content);
+    }
+    Ok(schema_parse(main, None, Some(files)))
+}
+
+// the relations of the user's models declared with a bare `@relation', with their models and the
+// models they relate to
+fn implicit_relations(schema: &Schema) -> Vec<(&Model, &Field, &Model)> {
+    let mut relations = vec![];
+    for model in schema.models() {
+        if schema.source(model.source_id()).is_some_and(|s| s.builtin) {
+            continue;
+        }
+        for field in model.fields() {
+            if !field.decorators().any(|d| is_relation(d.identifier_path().names()) && d.argument_list().is_none()) {
+                continue;
+            }
+            if !field.type_expr().is_resolved() || !field.type_expr().resolved().unwrap_optional().is_array() {
+                continue;
+            }
+            let Some(reference) = field.type_expr().resolved().unwrap_optional().unwrap_array().unwrap_optional().as_model_object() else { continue };
+            let Some(related) = schema.find_top_by_path(reference.path()).and_then(|t| t.as_model()) else { continue };
+            relations.push((model, field, related));
+        }
+    }
+    relations
+}
+
+fn is_relation(names: Vec<&str>) -> bool {
+    names == ["relation"] || names == ["std", "relation"]
+}
+
+fn join_model_name(model: &Model, related: &Model) -> String {
+    let (first, second) = if model.name() <= related.name() { (model, related) } else { (related, model) };
+    format!("_{}To{}", first.name(), second.name())
+}
+
+// the relation of the join model to `model'
+fn relation_name(model: &Model) -> String {
+    let mut chars = model.name().chars();
+    chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn join_model_source(schema: &Schema, files: &BTreeMap<String, String>, join: &str, model: &Model, related: &Model) -> Result<String> {
+    let mut members = vec![];
+    let mut keys = vec![];
+    for model in [model, related] {
+        let relation = relation_name(model);
+        let ids: Vec<&Field> = model.fields().filter(|f| f.decorators().any(|d| d.identifier_path().names().last() == Some(&"id"))).collect();
+        let [id] = ids.as_slice() else {
+            Err(Error::new(format!("`{}' has no single primary key field, which an implicit relation can refer to", model.string_path().join("."))))?
+        };
+        let content = source_content(files, &source_file_path(schema, *id))?;
+        let r#type = content[id.type_expr().span().start..id.type_expr().span().end].trim_end_matches('?');
+        members.push(format!("  @foreignKey\n  {}Id: {}\n  @relation(fields: .{}Id, references: .{})\n  {}: {}", relation, r#type, relation, id.name(), relation, model.name()));
+        keys.push(format!(".{}Id", relation));
+    }
+    Ok(format!("\n\n@id([{}]) @generateClient(false) @generateEntity(false)\nmodel {} {{\n{}\n}}", keys.join(", "), join, members.join("\n")))
+}
+
+fn source_file_path<N>(schema: &Schema, node: &N) -> String where N: Identifiable {
+    schema.source(node.source_id()).unwrap().file_path.clone()
+}
+
+pub(crate) fn source_content(files: &BTreeMap<String, String>, file_path: &str) -> Result<String> {
+    if let Some(content) = files.get(file_path) {
+        return Ok(content.clone());
+    }
+    match read_to_string(file_path) {
+        Ok(content) => Ok(content),
+        Err(e) => Err(Error::new(format!("cannot read `{}': {}", file_path, e))),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod builder;
+pub(crate) mod constants;
+pub(crate) mod implicit;
+pub(crate) mod packages;
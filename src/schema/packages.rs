@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, remove_dir_all, rename};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::env;
+use teo_parser::{parse as schema_parse};
+use teo_parser::ast::schema::Schema;
+use teo_parser::diagnostics::diagnostics::Diagnostics;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_result::{Error, Result};
+use uuid::Uuid;
+use crate::schema::implicit::source_content;
+
+const PACKAGE_PREFIX: &str = "git:";
+
+/// Parses the schema at `main`, with the packages it imports from git repositories.
+pub(crate) fn parse_with_packages(main: &str, unsaved_files: Option<BTreeMap<String, String>>) -> Result<(Schema, Diagnostics, BTreeMap<String, String>)> {
+    let mut files = unsaved_files.unwrap_or_default();
+    let cache = package_cache().ok();
+    loop {
+        let (schema, diagnostics) = schema_parse(main, None, Some(files.clone()));
+        let mut edits: BTreeMap<String, Vec<(usize, usize, String)>> = BTreeMap::new();
+        let in_package = |file_path: &str| cache.as_ref().is_some_and(|cache| Path::new(file_path).starts_with(cache));
+        let connector = schema.sources().into_iter().find(|s| !s.builtin && !in_package(&s.file_path) && s.get_connector().is_some()).map(|s| s.file_path.clone());
+        for source in schema.sources() {
+            if source.builtin {
+                continue;
+            }
+            let content = source_content(&files, &source.file_path)?;
+            for import in source.imports() {
+                let span = import.source.span();
+                let Some(package) = content[span.start..span.end].trim_matches('"').strip_prefix(PACKAGE_PREFIX) else { continue };
+                let directory = fetch_package(package, &source.file_path)?;
+                edits.entry(source.file_path.clone()).or_default().push((span.start, span.end, format!("\"{}\"", directory.display())));
+            }
+            // the parser looks for the connector of a file in the file and the files it imports,
+            // the import goes last to keep the line numbers of the diagnostics
+            if let Some(connector) = connector.as_ref().filter(|_| in_package(&source.file_path) && source.get_connector().is_none()) {
+                if !source.imports().iter().any(|i| &i.file_path == connector) {
+                    edits.entry(source.file_path.clone()).or_default().push((content.len(), content.len(), format!("\nimport \"{}\"\n", connector)));
+                }
+            }
+        }
+        // packages are replaced with their checkouts, so this ends when no new package is found
+        if edits.is_empty() {
+            return Ok((schema, diagnostics, files));
+        }
+        for (file_path, mut edits) in edits {
+            let mut content = source_content(&files, &file_path)?;
+            edits.sort_by_key(|e| std::cmp::Reverse(e.0));
+            for (start, end, text) in edits {
+                content.replace_range(start..end, &text);
+            }
+            files.insert(file_path, content);
+        }
+    }
+}
+
+fn fetch_package(package: &str, importer: &str) -> Result<PathBuf> {
+    let (repository, reference) = match package.rsplit_once('@') {
+        Some((repository, reference)) if !reference.is_empty() && !reference.contains('/') && !repository.is_empty() => (repository, reference),
+        _ => Err(Error::new(format!("package `{}' has no version, pin it with `@' and a tag, branch or commit", package)))?,
+    };
+    if reference.starts_with('-') || reference.contains(char::is_whitespace) {
+        Err(Error::new(format!("package `{}' has an invalid version `{}'", package, reference)))?
+    }
+    let url = if repository.contains("://") || repository.starts_with("git@") {
+        repository.to_owned()
+    } else if repository.starts_with('/') || repository.starts_with('.') {
+        let parent = Path::new(importer).parent().unwrap_or(Path::new("."));
+        match parent.join(repository).canonicalize() {
+            Ok(path) => path.to_str().unwrap().to_owned(),
+            Err(e) => Err(Error::new(format!("package `{}' is not found: {}", package, e)))?,
+        }
+    } else {
+        format!("https://{}", repository)
+    };
+    let directory = package_cache()?.join(directory_name(&url)).join(directory_name(reference));
+    if directory.is_dir() {
+        return Ok(directory);
+    }
+    // fetched next to the checkout and moved into place, so an interrupted fetch leaves nothing
+    let temporary = directory.with_file_name(format!(".{}-{}", directory_name(reference), Uuid::new_v4()));
+    if let Err(e) = create_dir_all(&temporary) {
+        Err(Error::new(format!("cannot create `{}': {}", temporary.display(), e)))?
+    }
+    let fetched = git(&temporary, &["init", "-q"])
+        .and_then(|_| git(&temporary, &["fetch", "-q", "--depth", "1", &url, reference]))
+        .and_then(|_| git(&temporary, &["checkout", "-q", "FETCH_HEAD"]));
+    if let Err(e) = fetched {
+        let _ = remove_dir_all(&temporary);
+        Err(Error::new(format!("cannot fetch package `{}': {}", package, e.message())))?
+    }
+    // another process may have fetched it in the meantime
+    if rename(&temporary, &directory).is_err() {
+        let _ = remove_dir_all(&temporary);
+        if !directory.is_dir() {
+            Err(Error::new(format!("cannot move package `{}' into `{}'", package, directory.display())))?
+        }
+    }
+    Ok(directory)
+}
+
+fn git(directory: &Path, args: &[&str]) -> Result<()> {
+    let output = match Command::new("git").arg("-C").arg(directory).args(args).output() {
+        Ok(output) => output,
+        Err(e) => Err(Error::new(format!("cannot run git: {}", e)))?,
+    };
+    if !output.status.success() {
+        Err(Error::new(String::from_utf8_lossy(&output.stderr).trim().to_owned()))?
+    }
+    Ok(())
+}
+
+fn package_cache() -> Result<PathBuf> {
+    if let Ok(directory) = env::var("TEO_PACKAGE_CACHE") {
+        return Ok(PathBuf::from(directory));
+    }
+    match env::var("HOME").or_else(|_| env::var("USERPROFILE")) {
+        Ok(home) => Ok(PathBuf::from(home).join(".teo").join("packages")),
+        Err(_) => Err(Error::new("cannot find the package cache, set TEO_PACKAGE_CACHE")),
+    }
+}
+
+// a leading dot is replaced too, so a version of `..' stays inside the cache
+fn directory_name(string: &str) -> String {
+    string.chars().enumerate().map(|(i, c)| if c.is_ascii_alphanumeric() || c == '-' || (c == '.' && i > 0) { c } else { '_' }).collect()
+}
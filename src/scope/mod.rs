@@ -0,0 +1,77 @@
+use serde_json::{json, Value as JsonValue};
+use key_path::path;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request::Request;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const DATA_KEY: &str = "scopes";
+const SCOPED_ACTIONS: [&str; 7] = ["findMany", "findFirst", "count", "aggregate", "groupBy", "updateMany", "deleteMany"];
+
+pub trait ScopeFilter: Send + Sync {
+    fn call(&self, request: &Request) -> Result<JsonValue>;
+}
+
+impl<F> ScopeFilter for F where F: Fn(&Request) -> Result<JsonValue> + Send + Sync {
+    fn call(&self, request: &Request) -> Result<JsonValue> {
+        self(request)
+    }
+}
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("scope", |args: Arguments, model: &mut Model| {
+        let name: String = args.get("name")?;
+        let r#where: Value = args.get("where")?;
+        // nested literals lose their values in the parser, operators have to be defined in Rust
+        if r#where.as_dictionary().map_or(true, |d| d.values().any(|v| v.is_dictionary() || v.is_array())) {
+            Err(Error::new("@scope only supports field equality, define other filters with `define_scope'"))?
+        }
+        let mut scopes = model.data.get(DATA_KEY).and_then(|o| o.as_teon()).cloned().unwrap_or(Value::Dictionary(Default::default()));
+        scopes.as_dictionary_mut().unwrap().insert(name, r#where);
+        model.data.insert(DATA_KEY.to_owned(), scopes.into());
+        Ok(())
+    });
+}
+
+/// Replaces `scope` with the named filters, which are combined with the client's `where`.
+pub(crate) fn apply_scope(model: &Model, action: &str, request: &Request, json: &mut JsonValue) -> BoxedResult<()> {
+    let Some(scope) = json.as_object_mut().and_then(|map| map.remove("scope")) else {
+        return Ok(());
+    };
+    if !SCOPED_ACTIONS.contains(&action) {
+        return Err(path::Error::value_error(path!["scope"], format!("scope is not supported by {}", action)).into());
+    }
+    let names: Vec<String> = match scope {
+        JsonValue::String(name) => vec![name],
+        JsonValue::Array(names) if names.iter().all(|n| n.is_string()) => names.into_iter().map(|n| n.as_str().unwrap().to_owned()).collect(),
+        _ => return Err(path::Error::value_error(path!["scope"], "expect string or array of strings").into()),
+    };
+    let mut filters = vec![];
+    for name in &names {
+        filters.push(scope_filter(model, name, request)?);
+    }
+    if let Some(r#where) = json.get("where") {
+        filters.push(r#where.clone());
+    }
+    let r#where = if filters.len() == 1 { filters.pop().unwrap() } else { json!({ "AND": filters }) };
+    json.as_object_mut().unwrap().insert("where".to_owned(), r#where);
+    Ok(())
+}
+
+fn scope_filter(model: &Model, name: &str, request: &Request) -> BoxedResult<JsonValue> {
+    if let Some(r#where) = model.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|s| s.get(name)) {
+        return match JsonValue::try_from(r#where.clone()) {
+            Ok(r#where) => Ok(r#where),
+            Err(e) => Err(path::Error::internal_server_error_message_only(format!("{}", e)).into()),
+        };
+    }
+    match Ctx::settings().scope(&model.path.join("."), name) {
+        Some(filter) => filter.call(request).map_err(|e| e.into()),
+        None => Err(path::Error::value_error(path!["scope"], format!("unknown scope `{}'", name)).into()),
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use async_recursion::async_recursion;
+use bigdecimal::BigDecimal;
+use bson::oid::ObjectId;
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use key_path::path;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use tokio::sync::Mutex;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::index;
+use teo_runtime::model::{Field, Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_teon::teon;
+use teo_teon::value::Value;
+
+const WORDS: [&str; 16] = [
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    "india", "juliet", "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+
+pub struct Factory {
+    ctx: transaction::Ctx,
+    rng: Mutex<StdRng>,
+    sequences: Mutex<HashMap<String, usize>>,
+}
+
+impl Factory {
+
+    pub fn new(ctx: transaction::Ctx, seed: u64) -> Self {
+        Self {
+            ctx,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            sequences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn create(&self, model_name: &str) -> Result<Object> {
+        let model = self.model(model_name)?;
+        self.create_for_model(model).await
+    }
+
+    pub async fn create_many(&self, model_name: &str, count: usize) -> Result<Vec<Object>> {
+        let model = self.model(model_name)?;
+        let mut result = vec![];
+        for _ in 0..count {
+            result.push(self.create_for_model(model).await?);
+        }
+        Ok(result)
+    }
+
+    fn model(&self, model_name: &str) -> Result<&'static Model> {
+        let path: Vec<&str> = model_name.split(".").collect();
+        match self.ctx.namespace().model_at_path(&path) {
+            Some(model) => Ok(model),
+            None => Err(Error::new(format!("model not found: {}", model_name))),
+        }
+    }
+
+    #[async_recursion]
+    async fn create_for_model(&self, model: &'static Model) -> Result<Object> {
+        let sequence = self.next_sequence(model).await?;
+        let unique_keys = unique_keys(model);
+        let mut input = teon!({});
+        for relation in model.relations() {
+            if relation.is_vec || !relation.has_foreign_key || relation.is_optional() {
+                continue
+            }
+            let related_model = match self.ctx.namespace().model_at_path(&relation.model_path()) {
+                Some(model) => model,
+                None => Err(Error::new(format!("model not found: {}", relation.model_path().join("."))))?,
+            };
+            let related = self.create_for_model(related_model).await?;
+            for (field, reference) in relation.iter() {
+                input.as_dictionary_mut().unwrap().insert(field.to_owned(), related.get_value(reference)?);
+            }
+        }
+        for field in model.fields() {
+            if field.auto || field.auto_increment || field.r#virtual || field.foreign_key || field.default.is_some() {
+                continue
+            }
+            if input.as_dictionary().unwrap().contains_key(field.name.as_str()) {
+                continue
+            }
+            let unique = unique_keys.contains(field.name.as_str());
+            let value = self.fake_field_value(field, unique, sequence).await?;
+            input.as_dictionary_mut().unwrap().insert(field.name.clone(), value);
+        }
+        let object = self.ctx.create_object(model, &input, None).await?;
+        object.save().await?;
+        Ok(object)
+    }
+
+    async fn next_sequence(&self, model: &'static Model) -> Result<usize> {
+        let key = model.path.join(".");
+        let mut sequences = self.sequences.lock().await;
+        let sequence = match sequences.get(&key) {
+            Some(sequence) => *sequence,
+            None => self.ctx.count(model, &teon!({}), path![]).await?,
+        };
+        sequences.insert(key, sequence + 1);
+        Ok(sequence)
+    }
+
+    async fn fake_field_value(&self, field: &Field, unique: bool, sequence: usize) -> Result<Value> {
+        if field.is_optional() && !unique && self.rng.lock().await.gen_ratio(1, 4) {
+            return Ok(Value::Null);
+        }
+        self.fake_value(field.name.as_str(), field.r#type.unwrap_optional(), unique, sequence).await
+    }
+
+    #[async_recursion]
+    pub(crate) async fn fake_value(&self, name: &str, r#type: &Type, unique: bool, sequence: usize) -> Result<Value> {
+        Ok(match r#type.unwrap_optional() {
+            Type::Bool => Value::Bool(self.rng.lock().await.gen()),
+            Type::Int => if unique {
+                Value::Int(sequence as i32 + 1)
+            } else {
+                Value::Int(self.rng.lock().await.gen_range(0..1000))
+            },
+            Type::Int64 => if unique {
+                Value::Int64(sequence as i64 + 1)
+            } else {
+                Value::Int64(self.rng.lock().await.gen_range(0..100000))
+            },
+            Type::Float32 => Value::Float32(self.rng.lock().await.gen_range(0.0..1000.0)),
+            Type::Float => Value::Float(self.rng.lock().await.gen_range(0.0..1000.0)),
+            Type::Decimal => {
+                let cents: i64 = self.rng.lock().await.gen_range(0..100000);
+                Value::Decimal(BigDecimal::from_str(&format!("{}.{:02}", cents / 100, cents % 100)).unwrap())
+            }
+            Type::String => Value::String(self.fake_string(name, unique, sequence).await),
+            Type::ObjectId => {
+                let bytes: [u8; 12] = self.rng.lock().await.gen();
+                Value::ObjectId(ObjectId::from_bytes(bytes))
+            }
+            Type::Date => {
+                let days = self.rng.lock().await.gen_range(0..3650);
+                Value::Date(NaiveDate::from_ymd_opt(2015, 1, 1).unwrap() + Duration::days(days))
+            }
+            Type::DateTime => {
+                let seconds = self.rng.lock().await.gen_range(0..315_360_000);
+                Value::DateTime(Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(seconds))
+            }
+            Type::EnumVariant(reference) => {
+                let r#enum = match self.ctx.namespace().enum_at_path(&reference.str_path()) {
+                    Some(r#enum) => r#enum,
+                    None => Err(Error::new(format!("enum not found: {}", reference.string_path().join("."))))?,
+                };
+                if r#enum.members.is_empty() {
+                    Err(Error::new(format!("enum has no members: {}", reference.string_path().join("."))))?
+                }
+                let index = self.rng.lock().await.gen_range(0..r#enum.members.len());
+                Value::String(r#enum.members.get(index).unwrap().name.clone())
+            }
+            Type::Array(inner) => {
+                let len = self.rng.lock().await.gen_range(0..4);
+                let mut values = vec![];
+                for _ in 0..len {
+                    values.push(self.fake_value(name, inner.as_ref(), false, sequence).await?);
+                }
+                Value::Array(values)
+            }
+            _ => Err(Error::new(format!("cannot generate fake value for field `{}' of type {}", name, r#type)))?,
+        })
+    }
+
+    async fn fake_string(&self, name: &str, unique: bool, sequence: usize) -> String {
+        let lowercased = name.to_lowercase();
+        let word = {
+            let mut rng = self.rng.lock().await;
+            WORDS[rng.gen_range(0..WORDS.len())]
+        };
+        if lowercased.contains("email") {
+            if unique {
+                format!("{}{}@example.com", word, sequence + 1)
+            } else {
+                format!("{}@example.com", word)
+            }
+        } else if lowercased.contains("url") {
+            format!("https://example.com/{}/{}", word, sequence + 1)
+        } else if unique {
+            format!("{}-{}", word, sequence + 1)
+        } else {
+            let suffix = self.rng.lock().await.gen_range(0..1000);
+            format!("{} {}", word, suffix)
+        }
+    }
+}
+
+pub(crate) fn unique_keys(model: &'static Model) -> BTreeSet<&'static str> {
+    let mut result = BTreeSet::new();
+    for index in model.indexes() {
+        if index.r#type() == index::Type::Primary || index.r#type() == index::Type::Unique {
+            for key in index.keys() {
+                result.insert(key.as_str());
+            }
+        }
+    }
+    result
+}
@@ -1,2 +1,3 @@
 pub(crate) mod seed;
-pub(crate) mod models;
\ No newline at end of file
+pub(crate) mod models;
+pub mod factory;
@@ -4,6 +4,7 @@ use key_path::path;
 use teo_runtime::connection::transaction;
 use teo_runtime::model;
 use crate::prelude::{Value, Result};
+use crate::seeder::models::std_model;
 
 /// Group record
 pub struct DataSetRecord {
@@ -20,25 +21,25 @@ impl DataSetRecord {
 
     /// Find many group records.
     pub async fn find_many(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Vec<DataSetRecord>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRecord"]).unwrap();
+        let model = std_model(&ctx, "DataSetRecord")?;
         Ok(ctx.find_many(model, query.borrow(), None, path![]).await?)
     }
 
     /// Find a unique group record.
     pub async fn find_unique(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Option<DataSetRecord>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRecord"]).unwrap();
+        let model = std_model(&ctx, "DataSetRecord")?;
         Ok(ctx.find_unique(model, query.borrow(), None, path![]).await?)
     }
 
     /// Find a non unique group record.
     pub async fn find_first(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Option<DataSetRecord>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRecord"]).unwrap();
+        let model = std_model(&ctx, "DataSetRecord")?;
         Ok(ctx.find_first(model, query.borrow(), None, path![]).await?)
     }
 
     /// Create a new group record.
     pub async fn new(values: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Self> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRecord"]).unwrap();
+        let model = std_model(&ctx, "DataSetRecord")?;
         Ok(ctx.create_object(model, values.borrow(), None).await?.into())
     }
 
@@ -122,9 +123,9 @@ impl DataSetRecord {
     }
 }
 
-impl Into<model::Object> for DataSetRecord {
-    fn into(self) -> model::Object {
-        self.inner.clone()
+impl From<DataSetRecord> for model::Object {
+    fn from(value: DataSetRecord) -> Self {
+        value.inner
     }
 }
 
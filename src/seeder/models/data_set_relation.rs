@@ -4,6 +4,7 @@ use key_path::path;
 use teo_runtime::connection::transaction;
 use teo_runtime::model;
 use crate::prelude::{Value, Result};
+use crate::seeder::models::std_model;
 
 /// Group relation
 #[derive(Clone, PartialEq)]
@@ -15,25 +16,25 @@ impl DataSetRelation {
 
     /// Find many group records.
     pub async fn find_many(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Vec<DataSetRelation>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRelation"]).unwrap();
+        let model = std_model(&ctx, "DataSetRelation")?;
         Ok(ctx.find_many(model, query.borrow(), None, path![]).await?)
     }
 
     /// Find a unique group record.
     pub async fn find_unique(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Option<DataSetRelation>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRelation"]).unwrap();
+        let model = std_model(&ctx, "DataSetRelation")?;
         Ok(ctx.find_unique(model, query.borrow(), None, path![]).await?)
     }
 
     /// Find a non unique group record.
     pub async fn find_first(query: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Option<DataSetRelation>> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRelation"]).unwrap();
+        let model = std_model(&ctx, "DataSetRelation")?;
         Ok(ctx.find_first(model, query.borrow(), None, path![]).await?)
     }
 
     /// Create a new group relation.
     pub async fn new(values: impl Borrow<Value>, ctx: transaction::Ctx) -> Result<Self> {
-        let model = ctx.namespace().model_at_path(&vec!["std", "DataSetRelation"]).unwrap();
+        let model = std_model(&ctx, "DataSetRelation")?;
         Ok(ctx.create_object(model, values.borrow(), None).await?.into())
     }
 
@@ -142,9 +143,9 @@ impl DataSetRelation {
     }
 }
 
-impl Into<model::Object> for DataSetRelation {
-    fn into(self) -> model::Object {
-        self.inner.clone()
+impl From<DataSetRelation> for model::Object {
+    fn from(value: DataSetRelation) -> Self {
+        value.inner
     }
 }
 
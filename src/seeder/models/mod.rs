@@ -1,2 +1,13 @@
 pub mod data_set_record;
-pub mod data_set_relation;
\ No newline at end of file
+pub mod data_set_relation;
+
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::Model;
+
+fn std_model(ctx: &transaction::Ctx, name: &str) -> Result<&'static Model> {
+    match ctx.namespace().model_at_path(&vec!["std", name]) {
+        Some(model) => Ok(model),
+        None => Err(Error::new(format!("model `std.{}' is not found", name))),
+    }
+}
@@ -8,7 +8,9 @@ use crate::prelude::{Value};
 use crate::seeder::models::data_set_record::DataSetRecord;
 use crate::seeder::models::data_set_relation::DataSetRelation;
 use teo_teon::teon;
+use crate::app::ctx::Ctx;
 use crate::cli::command::SeedCommandAction;
+use crate::migrate::data_sets::run_once_data_sets;
 use teo_result::Result;
 use teo_runtime::connection::transaction;
 use teo_runtime::data_set::{DataSet, Group, Record};
@@ -26,7 +28,10 @@ pub(crate) async fn seed(action: SeedCommandAction, datasets: Vec<DataSet>, ctx:
             SeedCommandAction::Unseed => unseed_dataset(dataset, ctx.clone()).await,
         }
     }
-    remove_user_deleted_dataset_records_and_relations(&datasets, ctx).await;
+    // the records of the data sets which run once stay tracked, `teo migrate' inserts them
+    let mut names: Vec<String> = datasets.iter().map(|d| d.name.join(".")).collect();
+    names.extend(run_once_data_sets(&Ctx::schema())?.into_keys());
+    remove_user_deleted_dataset_records_and_relations(&names, ctx).await;
     if exit {
         std::process::exit(0);
     } else {
@@ -49,7 +54,7 @@ pub(crate) async fn seed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
             }
         }), ctx.clone()).await.unwrap();
         for record in group.records.iter() {
-            let existing = seed_records.iter().find(|r| &r.name() == &record.name).is_some();
+            let existing = seed_records.iter().find(|r| r.name() == record.name).is_some();
             if !existing {
                 perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await;
                 added_names.push(record.name.clone());
@@ -58,7 +63,7 @@ pub(crate) async fn seed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
         added_records.insert(group.name.join("."), added_names);
         // delete records which are not recorded in user dataset
         for seed_record in seed_records.iter() {
-            let existing = group.records.iter().find(|r| &r.name == &seed_record.name()).is_some();
+            let existing = group.records.iter().find(|r| r.name == seed_record.name()).is_some();
             if !existing {
                 perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await;
             }
@@ -81,8 +86,8 @@ async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_group
     }), ctx.clone()).await.unwrap();
     for record in user_removed_seed_records_for_group {
         let model = ctx.namespace().model_at_path(&record.group().iter().map(AsRef::as_ref).collect());
-        if model.is_some() {
-            perform_remove_from_database(dataset, &record, model.unwrap(), ctx.clone()).await;
+        if let Some(model) = model {
+            perform_remove_from_database(dataset, &record, model, ctx.clone()).await;
         } else {
             // this table is already dropped
             record.delete().await.unwrap();
@@ -130,7 +135,7 @@ pub(crate) async fn reseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
             }
         }), ctx.clone()).await.unwrap();
         for record in group.records.iter() {
-            if let Some(seed_record) = seed_records.iter().find(|r| &r.name() == &record.name) {
+            if let Some(seed_record) = seed_records.iter().find(|r| r.name() == record.name) {
                 // recreate or update
                 perform_recreate_or_update_an_record(dataset, group, record, group_model, seed_record, ctx.clone()).await;
             } else {
@@ -140,7 +145,7 @@ pub(crate) async fn reseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
         }
         // delete records which are not recorded in user dataset
         for seed_record in seed_records.iter() {
-            let existing = group.records.iter().find(|r| &r.name == &seed_record.name()).is_some();
+            let existing = group.records.iter().find(|r| r.name == seed_record.name()).is_some();
             if !existing {
                 perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await;
             }
@@ -182,7 +187,7 @@ async fn sync_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: tr
             }
         }), ctx.clone()).await.unwrap();
         for record in group.records.iter() {
-            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).unwrap();
+            let seed_record = seed_records.iter().find(|o| o.name().as_str() == record.name).unwrap();
             let object: Object = ctx.find_unique(group_model, &teon!({
                 "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)
             }), None, path![]).await.unwrap().unwrap();
@@ -241,7 +246,7 @@ async fn setup_new_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, li
         }), ctx.clone()).await.unwrap();
         for record in group.records.iter() {
             if !(limit.is_none() || limit.unwrap().get(&group.name.join(".")).unwrap().contains(&record.name)) { continue }
-            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).unwrap();
+            let seed_record = seed_records.iter().find(|o| o.name().as_str() == record.name).unwrap();
             let object: Object = ctx.find_unique(group_model, &teon!({
                 "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)
             }), None, path![]).await.unwrap().unwrap();
@@ -260,11 +265,12 @@ async fn setup_new_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, li
     }
 }
 
-async fn sync_relation_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, relation_records: &'a Vec<DataSetRelation>, relation_record_refs: &mut Vec<&'a DataSetRelation>, ctx: transaction::Ctx) {
+#[allow(clippy::too_many_arguments)]
+async fn sync_relation_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, relation_records: &'a [DataSetRelation], relation_record_refs: &mut Vec<&'a DataSetRelation>, ctx: transaction::Ctx) {
     let that_name = reference.as_enum_variant().unwrap().value.clone();
     if let Some(existing_relation_record) = relation_records.iter().find(|r| {
-        (&r.name_a() == record.name.as_str() && r.name_b() == that_name) ||
-            (&r.name_b() == record.name.as_str() && r.name_a() == that_name)
+        (r.name_a() == record.name.as_str() && r.name_b() == that_name) ||
+            (r.name_b() == record.name.as_str() && r.name_a() == that_name)
     }) {
         let index = relation_record_refs.iter().position(|r| *r == existing_relation_record).unwrap();
         relation_record_refs.remove(index);
@@ -346,7 +352,7 @@ async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, rel
             "relationA": relation.name(),
             "nameA": record.name.as_str(),
             "groupB": that_object.model().path().join("."),
-            "relationB": if that_relation.is_some() { Value::String(that_relation.unwrap().name().to_owned()) } else { Value::Null },
+            "relationB": if let Some(that_relation) = that_relation { Value::String(that_relation.name().to_owned()) } else { Value::Null },
             "nameB": that_name.clone(),
         }), ctx.clone()).await.unwrap();
         new_relation_record.save().await.unwrap();
@@ -354,7 +360,7 @@ async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, rel
 }
 
 /// This perform, deletes an object from the database.
-async fn perform_remove_from_database<'a>(dataset: &DataSet, record: &'a DataSetRecord, group_model: &'static Model, ctx: transaction::Ctx) {
+async fn perform_remove_from_database(dataset: &DataSet, record: &DataSetRecord, group_model: &'static Model, ctx: transaction::Ctx) {
     let json_identifier = record.record();
     let exist: Option<Object> = ctx.find_unique(group_model, &teon!({
         "where": record_json_string_to_where_unique(json_identifier, group_model)
@@ -458,7 +464,7 @@ async fn cut_relation<'a>(relation: &'a DataSetRelation, record: &'a DataSetReco
     relation.delete().await.unwrap();
 }
 
-async fn perform_recreate_or_update_an_record<'a>(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, seed_record: &'a DataSetRecord, ctx: transaction::Ctx) {
+async fn perform_recreate_or_update_an_record(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, seed_record: &DataSetRecord, ctx: transaction::Ctx) {
     let object: Option<Object> = ctx.find_unique(group_model, &teon!({
         "where": record_json_string_to_where_unique(seed_record.record(), group_model)
     }), None, path![]).await.unwrap();
@@ -534,7 +540,7 @@ async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Recor
                     "relationA": relation.name(),
                     "nameA": record.name.as_str(),
                     "groupB": that_record.model().path().join("."),
-                    "relationB": if opposite_relation.is_some() { Value::String(opposite_relation.unwrap().name().to_owned()) } else { Value::Null },
+                    "relationB": if let Some(opposite_relation) = opposite_relation { Value::String(opposite_relation.name().to_owned()) } else { Value::Null },
                     "nameB": v.as_enum_variant().unwrap().value.clone()
                 }), ctx.clone()).await.unwrap();
                     relation_record.save().await.unwrap();
@@ -621,7 +627,7 @@ fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Vec<&Group> {
         }
         for group in &result {
             let group_name = group.name.join(".");
-            deps.remove(&group_name);
+            deps.swap_remove(&group_name);
         }
         for (_model_name, model_deps) in deps.iter_mut() {
             for group in &result {
@@ -635,9 +641,9 @@ fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Vec<&Group> {
     result
 }
 
-async fn remove_user_deleted_dataset_records_and_relations(datasets: &Vec<DataSet>, ctx: transaction::Ctx) {
+async fn remove_user_deleted_dataset_records_and_relations(names: &[String], ctx: transaction::Ctx) {
     // remove seed data set records if user removed some seed data set
-    let names = Value::Array(datasets.iter().map(|d| Value::String(d.name.join(".").clone())).collect::<Vec<Value>>());
+    let names = Value::Array(names.iter().map(|n| Value::String(n.clone())).collect::<Vec<Value>>());
     let records_to_remove = DataSetRecord::find_many(teon!({
         "where": {
             "dataSet": {
@@ -0,0 +1,124 @@
+use key_path::path;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{AGGREGATE, CODE_AMOUNT, CODE_NAME, CODE_POSITION, CONNECT, CONNECT_OR_CREATE, COPY, COUNT, CREATE, DELETE, DISCONNECT, ENTRY, FIND, FIND_FIRST, FIRST, GROUP_BY, JOIN_CREATE, JOIN_DELETE, MANY, NESTED, SET, SINGLE, UPDATE, UPSERT};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::default::{find_first, find_many, find_unique, count, aggregate};
+use teo_runtime::model::Model;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use crate::server::group_by::group_by;
+use crate::server::mutation::{self, joined_transaction};
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+
+const NEGATED_BIT: u32 = 1 << 31;
+
+pub fn builtin_action_enabled(model: &Model, action: Action) -> bool {
+    let enabled: Vec<Action> = model.actions.iter().filter(|a| a.0 & NEGATED_BIT == 0).cloned().collect();
+    let disabled: Vec<Action> = model.actions.iter().filter(|a| a.0 & NEGATED_BIT != 0).map(|a| !*a).collect();
+    if !enabled.is_empty() && !enabled.iter().any(|matcher| matches(action, *matcher)) {
+        return false;
+    }
+    !disabled.iter().any(|matcher| matches(action, *matcher))
+}
+
+pub(super) async fn builtin_handler(ctx: request::Ctx) -> path::Result<Response> {
+    if joined_transaction(&ctx) {
+        if let Some(response) = batched_mutation(&ctx).await {
+            return response;
+        }
+    }
+    match ctx.handler_match().handler_name() {
+        "findMany" => find_many(&ctx).await,
+        "findFirst" => find_first(&ctx).await,
+        "findUnique" => find_unique(&ctx).await,
+        "create" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::create(transaction_ctx, &ctx)).await,
+        "delete" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::delete(transaction_ctx, &ctx)).await,
+        "update" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::update(transaction_ctx, &ctx)).await,
+        "upsert" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::upsert(transaction_ctx, &ctx)).await,
+        "copy" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::copy(transaction_ctx, &ctx)).await,
+        "createMany" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::create_many(transaction_ctx, &ctx)).await,
+        "updateMany" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::update_many(transaction_ctx, &ctx)).await,
+        "copyMany" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::copy_many(transaction_ctx, &ctx)).await,
+        "deleteMany" => ctx.transaction_ctx().run_transaction(|transaction_ctx: transaction::Ctx| mutation::delete_many(transaction_ctx, &ctx)).await,
+        "count" => match ctx.body().get("select") {
+            Some(select) => count_fields(&ctx, select).await,
+            None => count(&ctx).await,
+        },
+        "aggregate" => aggregate(&ctx).await,
+        "groupBy" => group_by(&ctx).await,
+        _ => Err(path::Error::not_found_message_only()),
+    }
+}
+
+// the mutations of a batch item run in the transaction of the batch, reads don't open one
+async fn batched_mutation(ctx: &request::Ctx) -> Option<path::Result<Response>> {
+    Some(match ctx.handler_match().handler_name() {
+        "create" => mutation::create(ctx.transaction_ctx(), ctx).await,
+        "delete" => mutation::delete(ctx.transaction_ctx(), ctx).await,
+        "update" => mutation::update(ctx.transaction_ctx(), ctx).await,
+        "upsert" => mutation::upsert(ctx.transaction_ctx(), ctx).await,
+        "copy" => mutation::copy(ctx.transaction_ctx(), ctx).await,
+        "createMany" => mutation::create_many(ctx.transaction_ctx(), ctx).await,
+        "updateMany" => mutation::update_many(ctx.transaction_ctx(), ctx).await,
+        "copyMany" => mutation::copy_many(ctx.transaction_ctx(), ctx).await,
+        "deleteMany" => mutation::delete_many(ctx.transaction_ctx(), ctx).await,
+        _ => return None,
+    })
+}
+
+// counts with `select' report the non null values of each selected field and `_all'
+async fn count_fields(ctx: &request::Ctx, select: &Value) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    let mut finder = ctx.body().as_dictionary().unwrap().clone();
+    finder.shift_remove("select");
+    finder.insert("_count".to_owned(), select.clone());
+    let result = ctx.transaction_ctx().aggregate(model, &Value::Dictionary(finder), path![]).await?;
+    Ok(Response::data(result.get("_count").cloned().unwrap_or(Value::Null)))
+}
+
+pub(crate) fn handler_model(ctx: &request::Ctx) -> BoxedResult<&'static Model> {
+    match ctx.namespace().model_at_path(&ctx.handler_match().path()) {
+        Some(model) => Ok(model),
+        None => Err(path::Error::internal_server_error_message_only(format!("model `{}' is not found", ctx.handler_match().path().join("."))).into()),
+    }
+}
+
+fn matches(action: Action, matcher: Action) -> bool {
+    let action = finalized(action);
+    let matcher = finalized(matcher);
+    if (matcher & FIRST).0 != 0 && (action & FIRST).0 == 0 {
+        return false;
+    }
+    let action_names = action & all_names() & !FIRST;
+    let matcher_names = matcher & all_names() & !FIRST;
+    let names_match = if matcher_names == UPSERT {
+        action_names == UPSERT
+    } else {
+        (action_names & !matcher_names).0 == 0
+    };
+    names_match && (action & matcher & all_positions()).0 != 0 && (action & matcher & all_amounts()).0 != 0
+}
+
+fn finalized(action: Action) -> Action {
+    let mut result = action;
+    for all in [all_names(), all_positions(), all_amounts()] {
+        if (action & all).0 == 0 {
+            result = result | all;
+        }
+    }
+    result
+}
+
+fn all_names() -> Action {
+    CREATE | UPDATE | UPSERT | DELETE | COPY | FIND | FIND_FIRST | CONNECT | CONNECT_OR_CREATE | DISCONNECT | SET | JOIN_CREATE | JOIN_DELETE | COUNT | AGGREGATE | GROUP_BY | CODE_NAME
+}
+
+fn all_positions() -> Action {
+    ENTRY | NESTED | CODE_POSITION
+}
+
+fn all_amounts() -> Action {
+    SINGLE | MANY | CODE_AMOUNT
+}
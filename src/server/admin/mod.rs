@@ -0,0 +1,44 @@
+use std::future::Future;
+use actix_web::{HttpRequest, HttpResponse};
+use futures_util::future::BoxFuture;
+use teo_runtime::path;
+use teo_runtime::request::Request;
+use crate::app::ctx::Ctx;
+use crate::server::request::teo_request;
+
+static INDEX_HTML: &str = include_str!("index.html");
+
+pub trait AdminGuard: Send + Sync {
+    fn allow(&self, request: Request) -> BoxFuture<'static, bool>;
+}
+
+impl<F, Fut> AdminGuard for F where
+    F: Fn(Request) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send + 'static {
+    fn allow(&self, request: Request) -> BoxFuture<'static, bool> {
+        Box::pin(self(request))
+    }
+}
+
+pub(crate) fn is_admin_path(path: &str) -> bool {
+    path == "/admin" || path.starts_with("/admin/")
+}
+
+// admin endpoints don't exist without a guard, and answer 401 when the guard denies the request
+pub(crate) async fn guard_admin_endpoint(http_request: &HttpRequest) -> path::Result<()> {
+    let Some(guard) = Ctx::settings().admin_guard.clone() else {
+        return Err(path::Error::not_found_message_only());
+    };
+    if guard.allow(teo_request(http_request)).await {
+        Ok(())
+    } else {
+        Err(path::Error::unauthorized_error_message_only("admin access denied"))
+    }
+}
+
+pub(crate) fn admin_page(path_prefix: Option<&str>) -> HttpResponse {
+    let prefix = path_prefix.map(|p| p.trim_end_matches("/")).unwrap_or("");
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(INDEX_HTML.replace("__TEO_PATH_PREFIX__", prefix))
+}
@@ -0,0 +1,46 @@
+use key_path::path;
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, MANY};
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::archive::archive_model;
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+pub(super) const FIND_ARCHIVED: &str = "findArchived";
+
+pub(super) fn find_archived_action() -> Action {
+    FIND | MANY | ENTRY
+}
+
+// the arguments of `findMany' on the archive model
+pub(super) fn find_archived_input(model: &Model, json_body: &JsonValue, main_namespace: &'static Namespace) -> BoxedResult<Value> {
+    let archive_model = resolved_archive_model(model, main_namespace)?;
+    let args = if json_body.is_null() { JsonValue::Object(Default::default()) } else { json_body.clone() };
+    Ok(validate_and_transform_json_input_for_builtin_action(archive_model, find_archived_action(), &args, main_namespace)?)
+}
+
+/// Finds the records an `@archive` policy moved into the archive model.
+pub(super) async fn find_archived(ctx: &request::Ctx) -> path::Result<Response> {
+    let archive_model = resolved_archive_model(handler_model(ctx)?, ctx.namespace())?;
+    let objects: Vec<Object> = ctx.transaction_ctx().find_many_internal(archive_model, ctx.body(), false, find_archived_action(), Some(ctx.clone()), path![]).await?;
+    let mut data = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        data.push(object.to_teon_internal(&path!["data", index]).await?);
+    }
+    Ok(Response::data(Value::Array(data)))
+}
+
+fn resolved_archive_model(model: &Model, main_namespace: &'static Namespace) -> BoxedResult<&'static Model> {
+    match archive_model(main_namespace, model) {
+        Ok(Some(archive_model)) => Ok(archive_model),
+        Ok(None) => Err(path::Error::not_found_message_only().into()),
+        Err(error) => Err(path::Error::internal_server_error_message_only(error.message()).into()),
+    }
+}
@@ -0,0 +1,176 @@
+use std::cell::Cell;
+use actix_web::HttpRequest;
+use indexmap::IndexMap;
+use key_path::{KeyPath, path};
+use serde_json::{json, Map, Value as JsonValue};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::r#match::HandlerMatch;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::connection;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::action::builtin_action_enabled;
+use crate::server::builtin::{BuiltinCall, call_builtin};
+use crate::server::error::{BoxedResult, REQUEST_ID_HEADER, WrapError};
+
+pub(super) const BATCH_PATH: &str = "/batch/action";
+
+pub(super) async fn batch(http_request: HttpRequest, json_body: &JsonValue, main_namespace: &'static Namespace) -> path::Result<Response> {
+    let Some(items) = json_body.get("actions").and_then(|a| a.as_array()) else {
+        return Err(path::Error::value_error(path!["actions"], "expect array"));
+    };
+    // items are executed in order in one transaction, the first failure rolls back every item
+    let transaction_ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let current = Cell::new(0);
+    let result = transaction_ctx.run_transaction(|ctx: transaction::Ctx| {
+        let http_request = &http_request;
+        let current = &current;
+        async move {
+            let mut results = vec![];
+            let mut values = vec![];
+            for (index, item) in items.iter().enumerate() {
+                current.set(index);
+                let args = resolve_references(item.get("args").unwrap_or(&JsonValue::Null), &results, &path!["actions", index, "args"])?;
+                let value = perform(http_request, item, args, main_namespace, ctx.clone(), path!["actions", index]).await?;
+                match JsonValue::try_from(&value) {
+                    Ok(json) => results.push(json),
+                    Err(e) => Err(path::Error::internal_server_error(path!["actions", index], format!("{}", e)))?,
+                }
+                values.push(value);
+            }
+            current.set(items.len());
+            Ok(values)
+        }
+    }).await;
+    let error = match result {
+        Ok(values) => return Ok(Response::data(Value::Array(values))),
+        Err(error) => error,
+    };
+    let failed = current.get();
+    let items: Vec<JsonValue> = (0..items.len()).map(|index| {
+        if index < failed {
+            json!({ "status": "rolledBack" })
+        } else if index == failed {
+            json!({ "status": "failed" })
+        } else {
+            json!({ "status": "skipped" })
+        }
+    }).collect();
+    let code = error.code as u16;
+    let wrap_error = WrapError::from(error);
+    let mut error = wrap_error.json_value();
+    error.as_object_mut().unwrap().insert("index".to_owned(), JsonValue::from(failed));
+    if let Some(request_id) = http_request.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()) {
+        error.as_object_mut().unwrap().insert("requestId".to_owned(), JsonValue::String(request_id.to_owned()));
+    }
+    let response = Response::teon(Value::from(json!({
+        "error": error,
+        "data": items,
+    })));
+    response.set_code(code);
+    Ok(response)
+}
+
+async fn perform(http_request: &HttpRequest, item: &JsonValue, args: JsonValue, main_namespace: &'static Namespace, transaction_ctx: transaction::Ctx, path: KeyPath) -> path::Result<Value> {
+    let Some(model_name) = item.get("model").and_then(|m| m.as_str()) else {
+        return Err(path::Error::value_error(path + "model", "expect string"));
+    };
+    let Some(action_name) = item.get("action").and_then(|m| m.as_str()) else {
+        return Err(path::Error::value_error(path + "action", "expect string"));
+    };
+    let model_path: Vec<&str> = model_name.split(".").collect();
+    let Some(model) = main_namespace.model_at_path(&model_path) else {
+        return Err(path::Error::value_error(path + "model", "model not found"));
+    };
+    let Some(action) = builtin_action_handler_from_name(action_name) else {
+        return Err(path::Error::value_error(path + "action", "action not found"));
+    };
+    if !builtin_action_enabled(model, action) {
+        return Err(path::Error::value_error(path + "action", format!("action `{}' is disabled", action_name)));
+    }
+    let Some(dest_namespace) = main_namespace.namespace_at_path(&model_path[..model_path.len() - 1].to_vec()) else {
+        return Err(path::Error::value_error(path + "model", "model not found"));
+    };
+    let if_match = match item.get("ifMatch") {
+        None => None,
+        Some(JsonValue::String(if_match)) => Some(if_match.as_str()),
+        Some(_) => return Err(path::Error::value_error(path + "ifMatch", "expect string")),
+    };
+    let args = if args.is_null() { json!({}) } else { args };
+    let call = BuiltinCall {
+        http_request,
+        main_namespace,
+        dest_namespace,
+        model,
+        action,
+        handler_match: HandlerMatch {
+            path: model_path.iter().map(|p| p.to_string()).collect(),
+            name: action_name.to_owned(),
+            captures: IndexMap::new(),
+        },
+        transaction_ctx,
+        if_match,
+        batched: true,
+    };
+    let response = call_builtin(call, args).await.map_err(|error| prefixed(error, &(path + "args")))?;
+    let value = response.body().as_teon().cloned().unwrap_or(Value::Null);
+    Ok(value)
+}
+
+// `{ "$ref": "0.id" }` is replaced with the `id` of the first item's data
+fn resolve_references(value: &JsonValue, results: &[JsonValue], path: &KeyPath) -> BoxedResult<JsonValue> {
+    match value {
+        JsonValue::Object(map) => {
+            if map.len() == 1 {
+                if let Some(reference) = map.get("$ref") {
+                    return resolve_reference(reference, results, path);
+                }
+            }
+            let mut result = Map::new();
+            for (key, value) in map {
+                result.insert(key.clone(), resolve_references(value, results, &(path + key.as_str()))?);
+            }
+            Ok(JsonValue::Object(result))
+        }
+        JsonValue::Array(values) => {
+            let mut result = vec![];
+            for (index, value) in values.iter().enumerate() {
+                result.push(resolve_references(value, results, &(path + index))?);
+            }
+            Ok(JsonValue::Array(result))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+fn resolve_reference(reference: &JsonValue, results: &[JsonValue], path: &KeyPath) -> BoxedResult<JsonValue> {
+    let Some(reference) = reference.as_str() else {
+        return Err(path::Error::value_error(path.clone(), "expect string reference").into());
+    };
+    let mut segments = reference.split(".");
+    let index: Option<usize> = segments.next().and_then(|s| s.parse().ok());
+    let Some(mut current) = index.and_then(|i| results.get(i)).and_then(|r| r.get("data")) else {
+        return Err(path::Error::value_error(path.clone(), format!("reference `{}' points to no previous result", reference)).into());
+    };
+    for segment in segments {
+        let next = match current {
+            JsonValue::Array(values) => segment.parse::<usize>().ok().and_then(|i| values.get(i)),
+            _ => current.get(segment),
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Err(path::Error::value_error(path.clone(), format!("reference `{}' cannot be resolved", reference)).into()),
+        }
+    }
+    Ok(current.clone())
+}
+
+// the fields of an item's error are keyed like its arguments, `actions.1.args.create.code'
+fn prefixed(mut error: path::Error, path: &KeyPath) -> path::Error {
+    if let Some(fields) = error.fields.take() {
+        error.fields = Some(fields.into_iter().map(|(k, v)| (format!("{}.{}", path, k), v)).collect());
+    }
+    error
+}
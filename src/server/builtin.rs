@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+use actix_web::HttpRequest;
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::handler::r#match::HandlerMatch;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use crate::telemetry::traced;
+use crate::app::ctx::Ctx;
+use crate::internal_only::reject_internal_only_input;
+use crate::isolation::{run_isolated, TransactionOptions};
+use crate::position::{mark_unpositioned, set_unpositioned};
+use crate::scope::apply_scope;
+use crate::server::action::builtin_handler;
+use crate::server::coerce::apply_coercion;
+use crate::server::cost::{check_latency, check_query_cost};
+use crate::server::dry_run::{dry_run, take_dry_run};
+use crate::server::etag::{set_etag, set_if_match};
+use crate::server::filters::normalize_filters;
+use crate::server::last_modified::apply_last_modified;
+use crate::server::limits::limits_for_action;
+use crate::server::mutation::join_transaction;
+use crate::server::pagination::apply_page_meta;
+use crate::server::report::keep_ctx;
+use crate::server::plan::plan_nested_writes;
+use crate::server::request::teo_request;
+use crate::server::shaping::{apply_output_shape, take_output_shape};
+use crate::server::statements::{check_statements, StatementBudget};
+use crate::server::to_one::check_to_one_writes;
+use crate::server::through::{apply_through_includes, set_through_writes, take_through_includes, take_through_writes};
+
+/// A builtin model action requested by a route or by an item of a batch.
+pub(super) struct BuiltinCall<'a> {
+    pub(super) http_request: &'a HttpRequest,
+    pub(super) main_namespace: &'static Namespace,
+    pub(super) dest_namespace: &'static Namespace,
+    pub(super) model: &'static Model,
+    pub(super) action: Action,
+    pub(super) handler_match: HandlerMatch,
+    pub(super) transaction_ctx: transaction::Ctx,
+    pub(super) if_match: Option<&'a str>,
+    /// Batch items run in the transaction of the batch, and the response headers are dropped.
+    pub(super) batched: bool,
+}
+
+/// Runs a builtin action with every input check and output transform the server applies to it.
+pub(super) async fn call_builtin(call: BuiltinCall<'_>, mut json_body: JsonValue) -> path::Result<Response> {
+    if take_dry_run(call.handler_match.handler_name(), call.batched, &mut json_body)? {
+        return dry_run(call, json_body).await;
+    }
+    call_action(call, json_body).await
+}
+
+pub(super) async fn call_action(call: BuiltinCall<'_>, mut json_body: JsonValue) -> path::Result<Response> {
+    let model = call.model;
+    let name = call.handler_match.handler_name().to_owned();
+    let limits = limits_for_action(&name);
+    let request = teo_request(call.http_request);
+    apply_scope(model, &name, &request, &mut json_body)?;
+    reject_internal_only_input(model, &name, &json_body)?;
+    apply_coercion(model, &name, &mut json_body);
+    normalize_filters(model, &mut json_body)?;
+    check_to_one_writes(model, &name, &json_body)?;
+    check_query_cost(model, &name, &json_body, &limits)?;
+    plan_nested_writes(model, &name, &mut json_body);
+    let through_writes = take_through_writes(model, &name, &mut json_body, call.main_namespace)?;
+    let through_includes = take_through_includes(model, &mut json_body)?;
+    let unpositioned = mark_unpositioned(model, &name, &mut json_body)?;
+    let shape = take_output_shape(model, &mut json_body)?;
+    let body = validate_and_transform_json_input_for_builtin_action(model, call.action, &json_body, call.main_namespace)?;
+    let ctx = request::Ctx::new(
+        request,
+        Arc::new(body),
+        call.transaction_ctx.clone(),
+        call.handler_match,
+    );
+    keep_ctx(call.http_request, &ctx);
+    if call.batched {
+        join_transaction(&ctx);
+    }
+    set_unpositioned(&ctx, unpositioned);
+    set_through_writes(&ctx, through_writes);
+    if let Some(if_match) = call.if_match {
+        set_if_match(&ctx, if_match);
+    }
+    let body = ctx.body().clone();
+    let start = SystemTime::now();
+    let budget = Arc::new(StatementBudget::new(&limits));
+    let span_name = || format!("handler {}.{}", model.path.join("."), name);
+    let transaction = if call.batched { TransactionOptions::default() } else { Ctx::settings().action_transaction(&name) };
+    let mut response = traced(span_name, budget.scope(run_isolated(transaction, || call.dest_namespace.middleware_stack.call(ctx.clone(), &builtin_handler)))).await;
+    let elapsed = SystemTime::now().duration_since(start).unwrap_or_default();
+    if let Some(registry) = Ctx::settings().stats.clone() {
+        registry.record(&model.path.join("."), &name, elapsed, &response);
+    }
+    check_statements(model, &name, &budget)?;
+    check_latency(model, &name, elapsed, &limits)?;
+    response = response.map(|response| apply_page_meta(&name, &body, response));
+    if let (Ok(_), false) = (&response, through_includes.is_empty()) {
+        response = apply_through_includes(&through_includes, &call.transaction_ctx, response?).await;
+    }
+    if let (Ok(response), false) = (&response, call.batched) {
+        set_etag(model, &name, &body, response);
+    }
+    if let Some(shape) = &shape {
+        response = response.map(|response| apply_output_shape(shape, response));
+    }
+    if call.batched {
+        return response;
+    }
+    Ok(apply_last_modified(call.http_request, model, &name, response?))
+}
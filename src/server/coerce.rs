@@ -0,0 +1,124 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat};
+use serde_json::Value as JsonValue;
+use teo_parser::r#type::Type;
+use teo_runtime::model::Model;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+const RECORD_KEYS: [&str; 3] = ["create", "update", "where"];
+
+/// How input values which don't match their field's type are handled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Coercion {
+    /// Mismatched values are rejected with an error for each field.
+    #[default]
+    Strict,
+    /// Numbers, booleans and dates written in another representation are converted with a warning.
+    Lenient,
+}
+
+pub(crate) fn apply_coercion(model: &Model, action: &str, json: &mut JsonValue) {
+    if Ctx::settings().coercion != Coercion::Lenient {
+        return;
+    }
+    let coerced = coerce_input(model, json);
+    if !coerced.is_empty() && !Ctx::cli().silent {
+        info_message(format!("{}.{} coerced {}", model.path.join("."), action, coerced.join(", ")));
+    }
+}
+
+/// Converts the values under `create`, `update` and `where`, returns the paths which changed.
+pub(crate) fn coerce_input(model: &Model, json: &mut JsonValue) -> Vec<String> {
+    let mut coerced = vec![];
+    for key in RECORD_KEYS {
+        if let Some(record) = json.get_mut(key) {
+            coerce_record(model, record, key.to_owned(), &mut coerced);
+        }
+    }
+    coerced
+}
+
+fn coerce_record(model: &Model, record: &mut JsonValue, path: String, coerced: &mut Vec<String>) {
+    match record {
+        JsonValue::Array(records) => for (index, record) in records.iter_mut().enumerate() {
+            coerce_record(model, record, format!("{}.{}", path, index), coerced);
+        },
+        JsonValue::Object(map) => for (key, value) in map.iter_mut() {
+            let path = format!("{}.{}", path, key);
+            if let Some(field) = model.field(key) {
+                coerce_value(&field.r#type, value, path, coerced);
+            } else if let Some(relation) = model.relation(key) {
+                let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else { continue };
+                // nested mutations like `create' or `connect' contain records of the related model
+                if let JsonValue::Object(nested) = value {
+                    for (key, nested) in nested.iter_mut() {
+                        coerce_nested(related, nested, format!("{}.{}", path, key), coerced);
+                    }
+                }
+            } else if key == "AND" || key == "OR" || key == "NOT" {
+                coerce_record(model, value, path, coerced);
+            }
+        },
+        _ => (),
+    }
+}
+
+fn coerce_nested(model: &Model, nested: &mut JsonValue, path: String, coerced: &mut Vec<String>) {
+    match nested {
+        JsonValue::Array(records) => for (index, record) in records.iter_mut().enumerate() {
+            coerce_nested(model, record, format!("{}.{}", path, index), coerced);
+        },
+        JsonValue::Object(map) if map.keys().any(|k| RECORD_KEYS.contains(&k.as_str()) || k == "data") => for (key, value) in map.iter_mut() {
+            coerce_record(model, value, format!("{}.{}", path, key), coerced);
+        },
+        _ => coerce_record(model, nested, path, coerced),
+    }
+}
+
+fn coerce_value(r#type: &Type, value: &mut JsonValue, path: String, coerced: &mut Vec<String>) {
+    match value {
+        // filter operators and array items have the field's type
+        JsonValue::Object(map) => for (key, value) in map.iter_mut() {
+            coerce_value(r#type, value, format!("{}.{}", path, key), coerced);
+        },
+        JsonValue::Array(values) => for (index, value) in values.iter_mut().enumerate() {
+            coerce_value(r#type.as_array().unwrap_or(r#type), value, format!("{}.{}", path, index), coerced);
+        },
+        _ => if let Some(converted) = coerce_scalar(r#type, value) {
+            *value = converted;
+            coerced.push(path);
+        },
+    }
+}
+
+fn coerce_scalar(r#type: &Type, value: &JsonValue) -> Option<JsonValue> {
+    if value.as_str() == Some("") && r#type.is_optional() {
+        return Some(JsonValue::Null);
+    }
+    match (r#type.unwrap_optional(), value) {
+        (Type::Int | Type::Int64, JsonValue::String(string)) => string.trim().parse::<i64>().ok().map(JsonValue::from),
+        (Type::Int | Type::Int64, JsonValue::Number(number)) if number.is_f64() => {
+            let float = number.as_f64()?;
+            (float.fract() == 0.0).then(|| JsonValue::from(float as i64))
+        }
+        (Type::Float | Type::Float32, JsonValue::String(string)) => string.trim().parse::<f64>().ok().map(JsonValue::from),
+        (Type::Float | Type::Float32, JsonValue::Number(number)) if !number.is_f64() => number.as_f64().map(JsonValue::from),
+        (Type::Bool, JsonValue::String(string)) => match string.trim() {
+            "true" | "on" | "1" => Some(JsonValue::Bool(true)),
+            "false" | "off" | "0" => Some(JsonValue::Bool(false)),
+            _ => None,
+        },
+        (Type::String, JsonValue::Number(number)) => Some(JsonValue::String(number.to_string())),
+        (Type::String, JsonValue::Bool(bool)) => Some(JsonValue::String(bool.to_string())),
+        // a date or a datetime without offset is taken as UTC
+        (Type::DateTime, JsonValue::String(string)) if DateTime::parse_from_rfc3339(string).is_err() => {
+            let datetime = NaiveDateTime::parse_from_str(string, "%Y-%m-%dT%H:%M:%S%.f").ok()
+                .or_else(|| NaiveDate::parse_from_str(string, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))?;
+            Some(JsonValue::String(datetime.and_utc().to_rfc3339_opts(SecondsFormat::Millis, true)))
+        }
+        (Type::Date, JsonValue::String(string)) if NaiveDate::parse_from_str(string, "%Y-%m-%d").is_err() => {
+            DateTime::parse_from_rfc3339(string).ok().map(|d| JsonValue::String(d.date_naive().format("%Y-%m-%d").to_string()))
+        }
+        _ => None,
+    }
+}
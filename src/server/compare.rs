@@ -0,0 +1,82 @@
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, SINGLE};
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::object::diff::{diff, diff_with_json};
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+pub(super) fn find_unique_action() -> Action {
+    FIND | SINGLE | ENTRY
+}
+
+pub(super) fn compare_input(model: &Model, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let mut result = teon!({
+        "where": where_input(model, json_body.get("where"), "where", main_namespace)?,
+    });
+    match (json_body.get("with"), json_body.get("data")) {
+        (Some(with), None) => {
+            let r#where = where_input(model, with.get("where"), "with.where", main_namespace)?;
+            result.as_dictionary_mut().unwrap().insert("with".to_owned(), teon!({"where": r#where}));
+        }
+        (None, Some(data)) if data.is_object() => {
+            result.as_dictionary_mut().unwrap().insert("data".to_owned(), Value::from(data));
+        }
+        (None, Some(_)) => Err(path::Error::value_error(path!["data"], "expect object"))?,
+        _ => Err(path::Error::value_error_message_only("expect exactly one of `with' and `data'"))?,
+    }
+    Ok(result)
+}
+
+pub(super) async fn compare(ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    let left = find(ctx, model, ctx.body().get("where").unwrap(), path!["where"]).await?;
+    let result = if let Some(with) = ctx.body().get("with") {
+        let right = find(ctx, model, with.get("where").unwrap(), path!["with", "where"]).await?;
+        diff(&left, &right).await?
+    } else {
+        let data = match JsonValue::try_from(ctx.body().get("data").unwrap()) {
+            Ok(data) => data,
+            Err(e) => Err(path::Error::value_error(path!["data"], format!("{}", e)))?,
+        };
+        diff_with_json(&left, &data).await.map_err(|mut error| {
+            if let Some(fields) = error.fields.take() {
+                error.fields = Some(fields.into_iter().map(|(k, v)| (format!("data.{}", k), v)).collect());
+            }
+            error
+        })?
+    };
+    Ok(Response::data(Value::from(result)))
+}
+
+pub(super) fn where_input(model: &Model, json: Option<&JsonValue>, key: &str, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let Some(json) = json else {
+        return Err(path::Error::value_error_message_only(format!("`{}' is required", key)).into());
+    };
+    let input = serde_json::json!({ "where": json });
+    match validate_and_transform_json_input_for_builtin_action(model, find_unique_action(), &input, main_namespace) {
+        Ok(value) => Ok(value.get("where").cloned().unwrap()),
+        Err(mut error) => {
+            if let Some(fields) = error.fields.take() {
+                error.fields = Some(fields.into_iter().map(|(k, v)| (k.replacen("where", key, 1), v)).collect());
+            }
+            Err(error.into())
+        }
+    }
+}
+
+async fn find(ctx: &request::Ctx, model: &'static Model, r#where: &Value, path: KeyPath) -> path::Result<Object> {
+    let finder = teon!({"where": r#where.clone()});
+    match ctx.transaction_ctx().find_unique_internal(model, &finder, false, find_unique_action(), Some(ctx.clone()), path.clone()).await? {
+        Some(object) => Ok(object),
+        None => Err(path::Error::not_found(path)),
+    }
+}
@@ -0,0 +1,50 @@
+use actix_http::body::{BodySize, BoxBody, MessageBody};
+use actix_http::encoding::Encoder;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{AcceptEncoding, CONTENT_TYPE, ContentEncoding, Encoding, Header, VARY, HeaderValue};
+use mime_guess::mime;
+use crate::app::ctx::Ctx;
+
+const SUPPORTED_ENCODINGS: [Encoding; 4] = [
+    Encoding::Known(ContentEncoding::Brotli),
+    Encoding::Known(ContentEncoding::Zstd),
+    Encoding::Known(ContentEncoding::Gzip),
+    Encoding::Known(ContentEncoding::Deflate),
+];
+
+/// The encoding negotiated from `Accept-Encoding`, `None` when there's none.
+pub(crate) fn negotiate_encoding(req: &ServiceRequest) -> Option<ContentEncoding> {
+    Ctx::settings().compression_min_size?;
+    let accept_encoding = AcceptEncoding::parse(req).ok()?;
+    match accept_encoding.negotiate(SUPPORTED_ENCODINGS.iter())? {
+        Encoding::Known(ContentEncoding::Identity) => None,
+        Encoding::Known(encoding) => Some(encoding),
+        Encoding::Unknown(_) => None,
+    }
+}
+
+/// Compresses the body with the negotiated encoding, small bodies and media are sent as is.
+pub(crate) fn compress<B: MessageBody + 'static>(res: ServiceResponse<B>, encoding: Option<ContentEncoding>) -> ServiceResponse<BoxBody> {
+    let (Some(encoding), Some(min_size)) = (encoding, Ctx::settings().compression_min_size) else {
+        return res.map_into_boxed_body();
+    };
+    let small = matches!(res.response().body().size(), BodySize::Sized(size) if size < min_size as u64);
+    if small || !compressible(res.headers().get(CONTENT_TYPE)) {
+        let mut res = res.map_into_boxed_body();
+        res.headers_mut().append(VARY, HeaderValue::from_static("accept-encoding"));
+        return res;
+    }
+    res.map_body(|head, body| Encoder::response(encoding, head, body).boxed())
+}
+
+// already compressed media is not worth compressing again
+fn compressible(content_type: Option<&HeaderValue>) -> bool {
+    let Some(mime) = content_type.and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<mime::Mime>().ok()) else {
+        return true;
+    };
+    match mime.type_() {
+        mime::IMAGE => mime.subtype() == mime::SVG,
+        mime::VIDEO => false,
+        _ => true,
+    }
+}
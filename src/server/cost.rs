@@ -0,0 +1,102 @@
+use std::time::Duration;
+use maplit::btreemap;
+use serde_json::Value as JsonValue;
+use teo_runtime::model::Model;
+use teo_runtime::path::Error;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::server::limits::Limits;
+use crate::server::error::BoxedResult;
+
+// the row count assumed for queries without `take' or `pageSize'
+const UNBOUNDED_ROWS: u64 = 1000;
+const SINGLE_ROW_ACTIONS: [&str; 8] = ["findUnique", "findFirst", "create", "update", "upsert", "delete", "copy", "compare"];
+const AGGREGATE_KEYS: [&str; 5] = ["_count", "_sum", "_avg", "_min", "_max"];
+pub(crate) const READ_ACTIONS: [&str; 6] = ["findUnique", "findFirst", "findMany", "count", "aggregate", "groupBy"];
+
+/// Estimates the work of a query as predicted rows.
+pub(crate) fn estimate_cost(model: &Model, action: &str, args: &JsonValue) -> u64 {
+    let rows = if SINGLE_ROW_ACTIONS.contains(&action) { 1 } else { predicted_rows(args) };
+    let mut aggregates = 0;
+    for key in AGGREGATE_KEYS {
+        if let Some(fields) = args.get(key).and_then(|f| f.as_object()) {
+            aggregates += fields.len() as u64;
+        }
+    }
+    if let Some(by) = args.get("by").and_then(|b| b.as_array()) {
+        aggregates += by.len() as u64;
+    }
+    rows.saturating_mul(include_cost(model, args)).saturating_mul(1 + aggregates)
+}
+
+/// Rejects queries above `max_query_cost` and logs queries above `warn_query_cost`.
+pub(crate) fn check_query_cost(model: &Model, action: &str, args: &JsonValue, limits: &Limits) -> BoxedResult<()> {
+    if limits.max_query_cost.is_none() && limits.warn_query_cost.is_none() {
+        return Ok(());
+    }
+    let cost = estimate_cost(model, action, args);
+    if let Some(max) = limits.max_query_cost {
+        if cost > max {
+            return Err(Error {
+                title: "QueryTooComplex",
+                message: format!("estimated query cost {} exceeds {}", cost, max),
+                fields: None,
+                code: 400,
+                meta_map: btreemap! {},
+            }.into());
+        }
+    }
+    if let Some(warn) = limits.warn_query_cost {
+        if cost > warn && !Ctx::cli().silent {
+            info_message(format!("{}.{} has estimated query cost {}", model.path.join("."), action, cost));
+        }
+    }
+    Ok(())
+}
+
+/// Logs actions slower than `slow_request`, and fails reads with `strict_slow_request`.
+pub(crate) fn check_latency(model: &Model, action: &str, elapsed: Duration, limits: &Limits) -> BoxedResult<()> {
+    let Some(threshold) = limits.slow_request else {
+        return Ok(());
+    };
+    if elapsed <= threshold {
+        return Ok(());
+    }
+    if !Ctx::cli().silent {
+        info_message(format!("{}.{} took {}ms, over the {}ms threshold", model.path.join("."), action, elapsed.as_millis(), threshold.as_millis()));
+    }
+    if limits.strict_slow_request && READ_ACTIONS.contains(&action) {
+        return Err(Error {
+            title: "SlowRequest",
+            message: format!("request took {}ms, over the {}ms threshold", elapsed.as_millis(), threshold.as_millis()),
+            fields: None,
+            code: 500,
+            meta_map: btreemap! {},
+        }.into());
+    }
+    Ok(())
+}
+
+fn predicted_rows(args: &JsonValue) -> u64 {
+    match args.get("take").and_then(|t| t.as_i64()).or_else(|| args.get("pageSize").and_then(|p| p.as_i64())) {
+        Some(take) => take.unsigned_abs(),
+        None => UNBOUNDED_ROWS,
+    }
+}
+
+fn include_cost(model: &Model, args: &JsonValue) -> u64 {
+    let mut cost: u64 = 1;
+    let relations = ["include", "select"].into_iter().filter_map(|key| args.get(key).and_then(|r| r.as_object())).flatten();
+    for (name, nested) in relations {
+        let Some(relation) = model.relation(name) else { continue };
+        let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else { continue };
+        let nested_cost = match nested {
+            JsonValue::Bool(false) => continue,
+            JsonValue::Object(_) => include_cost(related, nested),
+            _ => 1,
+        };
+        let rows = if relation.is_vec { predicted_rows(nested) } else { 1 };
+        cost = cost.saturating_add(rows.saturating_mul(nested_cost));
+    }
+    cost
+}
@@ -0,0 +1,77 @@
+use std::sync::Mutex;
+use key_path::path;
+use serde_json::Value as JsonValue;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::path::{Error, Result};
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::builtin::{BuiltinCall, call_action};
+use crate::server::error::BoxedResult;
+
+const DRY_RUN_KEY: &str = "dryRun";
+const DRY_RUN_ACTIONS: [&str; 3] = ["create", "update", "delete"];
+
+tokio::task_local! {
+    static DRY_RUN: ();
+}
+
+pub(crate) fn take_dry_run(action: &str, batched: bool, json: &mut JsonValue) -> BoxedResult<bool> {
+    let Some(dry_run) = json.as_object_mut().and_then(|map| map.remove(DRY_RUN_KEY)) else {
+        return Ok(false);
+    };
+    let Some(dry_run) = dry_run.as_bool() else {
+        return Err(Error::value_error(path![DRY_RUN_KEY], "expect bool").into());
+    };
+    if dry_run && !DRY_RUN_ACTIONS.contains(&action) {
+        return Err(Error::value_error(path![DRY_RUN_KEY], format!("dry run is not supported by {}", action)).into());
+    }
+    // a dry run rolls back a transaction of its own, a batch item runs in the transaction of the batch
+    if dry_run && batched {
+        return Err(Error::value_error(path![DRY_RUN_KEY], "dry run is not supported in a batch").into());
+    }
+    Ok(dry_run)
+}
+
+/// Runs the action of `call` in a transaction which is rolled back.
+pub(super) async fn dry_run(call: BuiltinCall<'_>, json_body: JsonValue) -> Result<Response> {
+    let response = Mutex::new(None);
+    let result = DRY_RUN.scope((), call.transaction_ctx.run_transaction(|ctx: transaction::Ctx| {
+        let call = BuiltinCall {
+            http_request: call.http_request,
+            main_namespace: call.main_namespace,
+            dest_namespace: call.dest_namespace,
+            model: call.model,
+            action: call.action,
+            handler_match: call.handler_match.clone(),
+            transaction_ctx: ctx,
+            if_match: call.if_match,
+            batched: true,
+        };
+        let json_body = json_body.clone();
+        let response = &response;
+        async move {
+            *response.lock().unwrap() = Some(call_action(call, json_body).await?);
+            // an error is the only way to have the transaction rolled back
+            Err::<(), _>(Error::internal_server_error_message_only("dry run is rolled back"))
+        }
+    })).await;
+    let Some(response) = response.into_inner().unwrap() else {
+        return Err(result.err().unwrap());
+    };
+    let mut body = response.body().as_teon().cloned().unwrap_or(Value::Null);
+    if let Some(body) = body.as_dictionary_mut() {
+        let meta = body.entry("meta".to_owned()).or_insert_with(|| Value::Dictionary(Default::default()));
+        if let Some(meta) = meta.as_dictionary_mut() {
+            meta.insert(DRY_RUN_KEY.to_owned(), Value::Bool(true));
+        }
+    }
+    Ok(Response::teon(body))
+}
+
+/// Fails the first write of a dry run on a connection without transactions.
+pub(crate) fn check_dry_run(transaction: &dyn Transaction) -> BoxedResult<()> {
+    if DRY_RUN.try_with(|_| ()).is_ok() && !transaction.is_transaction() {
+        return Err(Error::value_error(path![DRY_RUN_KEY], "dry run needs a database with transactions").into());
+    }
+    Ok(())
+}
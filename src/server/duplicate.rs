@@ -0,0 +1,75 @@
+use key_path::{KeyPath, path};
+use serde_json::{json, Value as JsonValue};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{COPY, ENTRY, SINGLE};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::duplicate::{duplicable_relation, duplicate};
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+pub(super) fn duplicate_action() -> Action {
+    COPY | SINGLE | ENTRY
+}
+
+// the copy arguments plus `relations' naming the related records to copy along
+pub(super) fn duplicate_input(model: &Model, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let mut args = if json_body.is_null() { json!({}) } else { json_body.clone() };
+    let Some(map) = args.as_object_mut() else {
+        return Err(path::Error::value_error_message_only("expect object").into());
+    };
+    let relations = map.shift_remove("relations");
+    if !map.contains_key("copy") {
+        map.insert("copy".to_owned(), json!({}));
+    }
+    let mut result = validate_and_transform_json_input_for_builtin_action(model, duplicate_action(), &args, main_namespace)?;
+    if let Some(relations) = relations {
+        result.as_dictionary_mut().unwrap().insert("relations".to_owned(), relations_input(model, &relations, &path!["relations"])?);
+    }
+    Ok(result)
+}
+
+fn relations_input(model: &Model, json: &JsonValue, path: &KeyPath) -> BoxedResult<Value> {
+    let Some(map) = json.as_object() else {
+        return Err(path::Error::value_error(path.clone(), "expect object").into());
+    };
+    let mut result = teon!({});
+    for (name, nested) in map {
+        let path = path + name.as_str();
+        let Some((_, related)) = duplicable_relation(model, name) else {
+            return Err(path::Error::value_error(path, "relation cannot be duplicated").into());
+        };
+        let value = match nested {
+            JsonValue::Bool(false) => continue,
+            JsonValue::Bool(true) => teon!({}),
+            JsonValue::Object(inner) => match inner.get("relations") {
+                None => teon!({}),
+                Some(relations) => teon!({"relations": relations_input(related, relations, &(&path + "relations"))?}),
+            },
+            _ => return Err(path::Error::value_error(path, "expect bool or object").into()),
+        };
+        result.as_dictionary_mut().unwrap().insert(name.clone(), value);
+    }
+    Ok(result)
+}
+
+pub(super) async fn duplicate_record(req_ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(req_ctx)?;
+    let value: Value = req_ctx.transaction_ctx().run_transaction(|ctx: transaction::Ctx| async move {
+        let finder = teon!({"where": req_ctx.body().get("where").unwrap()});
+        let Some(object) = ctx.find_unique_internal(model, &finder, true, duplicate_action(), Some(req_ctx.clone()), path!["where"]).await? else {
+            return Err(path::Error::not_found(path!["where"]));
+        };
+        let new = duplicate(&ctx, &object, req_ctx.body().get("copy"), req_ctx.body().get("relations"), Some(req_ctx.clone()), path![]).await?;
+        let refreshed = new.refreshed(req_ctx.body().get("include"), req_ctx.body().get("select")).await?;
+        refreshed.to_teon_internal(&path!["data"]).await
+    }).await?;
+    Ok(Response::data(value))
+}
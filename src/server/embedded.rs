@@ -0,0 +1,109 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use actix_web::{HttpRequest, HttpResponse};
+use once_cell::sync::OnceCell;
+use crate::app::ctx::Ctx;
+use crate::server::static_files::StaticFilesOptions;
+use crate::utils::sha256_hex;
+
+const INDEX_FILE: &str = "index.html";
+
+pub type EmbeddedFiles = &'static [(&'static str, &'static [u8])];
+
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddedMount {
+    pub(crate) mount: String,
+    pub(crate) files: EmbeddedFiles,
+    pub(crate) options: StaticFilesOptions,
+    // hashed when a file is first served
+    etags: Arc<Vec<OnceCell<String>>>,
+}
+
+impl EmbeddedMount {
+
+    pub(crate) fn new(mount: &str, files: EmbeddedFiles, options: StaticFilesOptions) -> Self {
+        Self { mount: mount.to_owned(), files, options, etags: Arc::new(files.iter().map(|_| OnceCell::new()).collect()) }
+    }
+
+    fn etag(&self, index: usize) -> &str {
+        self.etags[index].get_or_init(|| {
+            let hash = sha256_hex(self.files[index].1, 16);
+            format!("\"{}\"", hash)
+        })
+    }
+}
+
+/// Writes the files under `dir` as an `include_bytes!` list, to be called from a build script and
+/// included with `include!(concat!(env!("OUT_DIR"), "/assets.rs"))`.
+pub fn write_embedded_files(dir: impl AsRef<Path>, out: impl AsRef<Path>) -> std::io::Result<()> {
+    let dir = dir.as_ref().canonicalize()?;
+    let mut files = vec![];
+    collect_files(&dir, &dir, &mut files)?;
+    files.sort();
+    let mut output = fs::File::create(out)?;
+    writeln!(output, "&[")?;
+    for (name, path) in files {
+        writeln!(output, "    ({:?}, include_bytes!({:?})),", name, path)?;
+    }
+    writeln!(output, "]")?;
+    println!("cargo:rerun-if-changed={}", dir.display());
+    Ok(())
+}
+
+fn collect_files(base: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(base, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            files.push((name, path.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn serve_embedded(http_request: &HttpRequest, path: &str) -> Option<HttpResponse> {
+    let settings = Ctx::settings();
+    for mount in &settings.embedded_files {
+        let prefix = mount.mount.trim_end_matches('/');
+        let Some(rest) = path.strip_prefix(prefix) else { continue };
+        if !rest.is_empty() && !rest.starts_with('/') {
+            continue;
+        }
+        let name = rest.trim_start_matches('/');
+        let index = match find(mount.files, name) {
+            Some(index) => Some(index),
+            None if mount.options.spa_fallback && Path::new(name).extension().is_none() => find(mount.files, INDEX_FILE),
+            None => None,
+        };
+        if let Some(index) = index {
+            return Some(file_response(http_request, mount, index));
+        }
+    }
+    None
+}
+
+fn find(files: EmbeddedFiles, name: &str) -> Option<usize> {
+    files.iter().position(|(n, _)| *n == name)
+}
+
+fn file_response(http_request: &HttpRequest, mount: &EmbeddedMount, index: usize) -> HttpResponse {
+    let (name, contents) = mount.files[index];
+    let options = &mount.options;
+    let etag = mount.etag(index);
+    let not_modified = http_request.headers().get("If-None-Match")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(",").map(|t| t.trim().trim_start_matches("W/")).any(|t| t == etag || t == "*"));
+    let mut builder = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    builder.insert_header(("ETag", etag));
+    if let Some(cache_control) = &options.cache_control {
+        builder.insert_header(("Cache-Control", cache_control.as_str()));
+    }
+    if not_modified {
+        return builder.finish();
+    }
+    builder.content_type(mime_guess::from_path(name).first_or_octet_stream().as_ref()).body(contents)
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use std::ops::Deref;
 use actix_http::body::BoxBody;
 use actix_http::StatusCode;
 use actix_web::{HttpResponse, ResponseError};
@@ -6,7 +7,7 @@ use serde_json::json;
 use teo_teon::Value;
 
 #[derive(Debug)]
-pub(super) enum WrapError {
+pub(crate) enum WrapError {
     PathError(teo_runtime::path::Error),
     ResultError(teo_result::Error),
 }
@@ -24,37 +25,267 @@ impl Display for WrapError {
 impl From<teo_runtime::path::Error> for WrapError {
 
     fn from(value: teo_runtime::path::Error) -> Self {
-        Self::PathError(value)
+        Self::PathError(restore_error_type(value))
     }
 }
 
 impl From<teo_result::Error> for WrapError {
 
     fn from(value: teo_result::Error) -> Self {
-        Self::ResultError(value)
+        if value.meta_map.contains_key(TYPE_META_KEY) {
+            Self::PathError(restore_error_type(value.into()))
+        } else {
+            Self::ResultError(value)
+        }
+    }
+}
+
+impl From<BoxedError> for WrapError {
+
+    fn from(value: BoxedError) -> Self {
+        Self::from(value.into_inner())
+    }
+}
+
+/// A `path::Error` behind a pointer. `path::Error` carries its fields and meta inline, so the
+/// checks which run on every request return this to keep their results small. `?` turns it back
+/// into a `path::Error` or a `teo_result::Error`.
+#[derive(Debug)]
+pub struct BoxedError(Box<teo_runtime::path::Error>);
+
+pub type BoxedResult<T> = Result<T, BoxedError>;
+
+impl BoxedError {
+
+    pub fn into_inner(self) -> teo_runtime::path::Error {
+        *self.0
+    }
+}
+
+impl Deref for BoxedError {
+    type Target = teo_runtime::path::Error;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for BoxedError {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl From<teo_runtime::path::Error> for BoxedError {
+
+    fn from(value: teo_runtime::path::Error) -> Self {
+        Self(Box::new(value))
+    }
+}
+
+impl From<teo_result::Error> for BoxedError {
+
+    fn from(value: teo_result::Error) -> Self {
+        Self(Box::new(value.into()))
+    }
+}
+
+impl From<BoxedError> for teo_runtime::path::Error {
+
+    fn from(value: BoxedError) -> Self {
+        value.into_inner()
+    }
+}
+
+impl From<BoxedError> for teo_result::Error {
+
+    fn from(value: BoxedError) -> Self {
+        value.into_inner().into()
     }
 }
 
+const TYPE_META_KEY: &str = "teo.type";
+const STATUS_META_KEY: &str = "teo.status";
+
+/// Keeps the type and status of `error` when the runtime passes it on as a `teo_result::Error`.
+pub(crate) fn keep_error_type(mut error: teo_runtime::path::Error) -> teo_runtime::path::Error {
+    error.insert_meta(TYPE_META_KEY, error.title);
+    error.insert_meta(STATUS_META_KEY, error.code);
+    error
+}
+
+fn restore_error_type(mut error: teo_runtime::path::Error) -> teo_runtime::path::Error {
+    if let Some(title) = error.get_meta::<&'static str>(TYPE_META_KEY).copied() {
+        error.title = title;
+    }
+    if let Some(code) = error.get_meta::<i32>(STATUS_META_KEY).copied() {
+        error.code = code;
+    }
+    error
+}
+
 impl ResponseError for WrapError {
 
     fn status_code(&self) -> StatusCode {
         match self {
             Self::PathError(e) => StatusCode::from_u16(e.code as u16).unwrap(),
-            Self::ResultError(e) => StatusCode::from_u16(500).unwrap(),
+            Self::ResultError(_) => StatusCode::from_u16(500).unwrap(),
         }
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let value: Value = match self {
-            WrapError::PathError(e) => e.into(),
-            WrapError::ResultError(e) => {
-                let path_error = teo_runtime::path::Error::from(e);
-                path_error.into()
-            },
-        };
-        let json_value: serde_json::Value = value.try_into().unwrap();
         HttpResponse::Ok().status(self.status_code()).json(json!({
+            "error": self.json_value()
+        }))
+    }
+}
+
+impl WrapError {
+
+    pub(super) fn error_response_with_request_id(&self, request_id: &str) -> HttpResponse<BoxBody> {
+        let mut json_value = self.json_value();
+        json_value.as_object_mut().unwrap().insert("requestId".to_owned(), serde_json::Value::String(request_id.to_owned()));
+        HttpResponse::Ok().status(self.status_code()).insert_header((REQUEST_ID_HEADER, request_id)).json(json!({
             "error": json_value
         }))
     }
+
+    pub(super) fn json_value(&self) -> serde_json::Value {
+        let path_error = match self {
+            WrapError::PathError(e) => e,
+            WrapError::ResultError(e) => &teo_runtime::path::Error::from(e),
+        };
+        let value: Value = path_error.into();
+        let mut json_value: serde_json::Value = value.try_into().unwrap();
+        json_value.as_object_mut().unwrap().insert("code".to_owned(), serde_json::Value::String(error_code(path_error)));
+        if let Some(key) = path_error.fields.as_ref().and_then(|f| f.keys().next()) {
+            json_value.as_object_mut().unwrap().insert("path".to_owned(), serde_json::Value::Array(path_segments(key)));
+        }
+        json_value
+    }
+}
+
+// the field keys are left as they are, array indices are numbers in the structured path
+fn path_segments(key: &str) -> Vec<serde_json::Value> {
+    key.split('.').filter(|s| !s.is_empty()).map(|segment| match segment.parse::<u64>() {
+        Ok(index) => serde_json::Value::from(index),
+        Err(_) => serde_json::Value::String(segment.to_owned()),
+    }).collect()
+}
+
+pub(super) const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// The code of an error response, handlers can override it by inserting a String under the
+/// `code` meta key of their `path::Error`.
+pub fn error_code(error: &teo_runtime::path::Error) -> String {
+    if let Some(code) = error.get_meta::<String>("code") {
+        return code.clone();
+    }
+    ErrorCode::for_error(error).as_str().to_owned()
+}
+
+/// The stable codes of error responses, chosen by the error type.
+///
+/// | Code  | Type                  | Status |
+/// |-------|-----------------------|--------|
+/// | T4000 | any other client error | 4xx |
+/// | T4001 | `ValueError`          | 400    |
+/// | T4002 | `UniqueError`         | 400    |
+/// | T4003 | `QueryTooComplex`     | 400    |
+/// | T4010 | `Unauthorized`        | 401    |
+/// | T4030 | `PermissionError`     | 403    |
+/// | T4040 | `NotFound`            | 404    |
+/// | T4050 | `MethodNotAllowed`    | 405    |
+/// | T4100 | `Gone`                | 410    |
+/// | T4120 | `PreconditionFailed`  | 412    |
+/// | T4130 | `PayloadTooLarge`     | 413    |
+/// | T5000 | `InternalServerError` and any other error | 500 |
+/// | T5001 | `TooManyStatements`   | 500    |
+/// | T5002 | `SlowRequest`         | 500    |
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    BadRequest,
+    ValueError,
+    UniqueError,
+    QueryTooComplex,
+    Unauthorized,
+    PermissionError,
+    NotFound,
+    MethodNotAllowed,
+    Gone,
+    PreconditionFailed,
+    PayloadTooLarge,
+    InternalServerError,
+    TooManyStatements,
+    SlowRequest,
+}
+
+impl ErrorCode {
+
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::BadRequest,
+        ErrorCode::ValueError,
+        ErrorCode::UniqueError,
+        ErrorCode::QueryTooComplex,
+        ErrorCode::Unauthorized,
+        ErrorCode::PermissionError,
+        ErrorCode::NotFound,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::Gone,
+        ErrorCode::PreconditionFailed,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::InternalServerError,
+        ErrorCode::TooManyStatements,
+        ErrorCode::SlowRequest,
+    ];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "T4000",
+            ErrorCode::ValueError => "T4001",
+            ErrorCode::UniqueError => "T4002",
+            ErrorCode::QueryTooComplex => "T4003",
+            ErrorCode::Unauthorized => "T4010",
+            ErrorCode::PermissionError => "T4030",
+            ErrorCode::NotFound => "T4040",
+            ErrorCode::MethodNotAllowed => "T4050",
+            ErrorCode::Gone => "T4100",
+            ErrorCode::PreconditionFailed => "T4120",
+            ErrorCode::PayloadTooLarge => "T4130",
+            ErrorCode::InternalServerError => "T5000",
+            ErrorCode::TooManyStatements => "T5001",
+            ErrorCode::SlowRequest => "T5002",
+        }
+    }
+
+    /// The error type the code is chosen for, `None` for the fallback codes.
+    pub const fn error_type(&self) -> Option<&'static str> {
+        match self {
+            ErrorCode::BadRequest => None,
+            ErrorCode::ValueError => Some("ValueError"),
+            ErrorCode::UniqueError => Some("UniqueError"),
+            ErrorCode::QueryTooComplex => Some("QueryTooComplex"),
+            ErrorCode::Unauthorized => Some("Unauthorized"),
+            ErrorCode::PermissionError => Some("PermissionError"),
+            ErrorCode::NotFound => Some("NotFound"),
+            ErrorCode::MethodNotAllowed => Some("MethodNotAllowed"),
+            ErrorCode::Gone => Some("Gone"),
+            ErrorCode::PreconditionFailed => Some("PreconditionFailed"),
+            ErrorCode::PayloadTooLarge => Some("PayloadTooLarge"),
+            ErrorCode::InternalServerError => Some("InternalServerError"),
+            ErrorCode::TooManyStatements => Some("TooManyStatements"),
+            ErrorCode::SlowRequest => Some("SlowRequest"),
+        }
+    }
+
+    /// Errors of other types fall back to `BadRequest` for client errors and to
+    /// `InternalServerError` otherwise.
+    pub fn for_error(error: &teo_runtime::path::Error) -> Self {
+        match Self::ALL.iter().find(|code| code.error_type() == Some(error.title)) {
+            Some(code) => *code,
+            None if (400..500).contains(&error.code) => ErrorCode::BadRequest,
+            None => ErrorCode::InternalServerError,
+        }
+    }
 }
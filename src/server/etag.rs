@@ -0,0 +1,92 @@
+use actix_web::HttpRequest;
+use key_path::path;
+use maplit::btreemap;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::path::{Error, Result};
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::error::keep_error_type;
+use crate::server::last_modified::updated_at_field;
+use crate::utils::sha256_hex;
+
+pub(crate) const ETAG_HEADER: &str = "ETag";
+const IF_MATCH_HEADER: &str = "If-Match";
+const IF_MATCH_KEY: &str = "teo.ifMatch";
+
+/// The ETag of a record, computed from its output like the ETags of the responses.
+pub async fn object_etag(object: &Object) -> Result<String> {
+    let data = object.to_teon_internal(&path![]).await?;
+    Ok(data_etag(object.model(), &data).unwrap_or_default())
+}
+
+// the primary keys and the update date, or every stored field when there's no update date
+fn data_etag(model: &Model, data: &Value) -> Option<String> {
+    let data = data.as_dictionary()?;
+    let mut content = String::new();
+    for key in model.primary_index()?.keys() {
+        content += &format!("{}\n", data.get(key)?);
+    }
+    match updated_at_field(model) {
+        Some(field) => content += &format!("{}", data.get(field.name.as_str())?),
+        None => for field in model.fields() {
+            if let Some(value) = data.get(field.name.as_str()).filter(|_| !field.r#virtual) {
+                content += &format!("\n{}", value);
+            }
+        },
+    }
+    let hash = sha256_hex(content.as_bytes(), 16);
+    Some(format!("\"{}\"", hash))
+}
+
+pub(crate) fn if_match(http_request: &HttpRequest) -> Option<&str> {
+    http_request.headers().get(IF_MATCH_HEADER).and_then(|v| v.to_str().ok())
+}
+
+/// Hands the `If-Match` header to the write handler, which checks it with `check_if_match`.
+pub(crate) fn set_if_match(ctx: &request::Ctx, if_match: &str) {
+    ctx.data_mut().insert(IF_MATCH_KEY, if_match.to_owned());
+}
+
+// compared by the entry update and delete handlers on the record they load in the transaction of
+// the write, right before it's changed, the header is taken so it's compared once
+pub(crate) async fn check_if_match(req_ctx: &request::Ctx, object: &Object) -> Result<()> {
+    let Some(if_match) = req_ctx.data().get::<String>(IF_MATCH_KEY).cloned() else {
+        return Ok(());
+    };
+    req_ctx.data_mut().remove::<String>(IF_MATCH_KEY);
+    if if_match.trim() == "*" {
+        return Ok(());
+    }
+    let etag = object_etag(object).await?;
+    if if_match.split(",").map(|t| t.trim().trim_start_matches("W/")).any(|t| t == etag) {
+        Ok(())
+    } else {
+        Err(precondition_failed())
+    }
+}
+
+// computed from the returned record, the write is committed already and this can't fail
+pub(crate) fn set_etag(model: &'static Model, action: &str, body: &Value, response: &Response) {
+    if action != "findUnique" && action != "update" {
+        return;
+    }
+    // the ETag covers the whole record
+    if body.get("select").is_some() {
+        return;
+    }
+    let data = response.body().as_teon().and_then(|v| v.get("data")).and_then(|data| data_etag(model, data));
+    if let Some(etag) = data {
+        response.headers().set(ETAG_HEADER, etag);
+    }
+}
+
+fn precondition_failed() -> Error {
+    keep_error_type(Error {
+        title: "PreconditionFailed",
+        message: "record has been modified since it was fetched".to_owned(),
+        fields: None,
+        code: 412,
+        meta_map: btreemap! {},
+    })
+}
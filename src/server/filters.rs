@@ -0,0 +1,96 @@
+use key_path::KeyPath;
+use serde_json::{json, Value as JsonValue};
+use teo_runtime::model::Model;
+use teo_runtime::path;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const IS_NULL: &str = "isNull";
+
+// rewrites the filter shorthands into the forms the connectors understand
+pub(crate) fn normalize_filters(model: &Model, json_body: &mut JsonValue) -> BoxedResult<()> {
+    normalize_args(model, json_body, &KeyPath::default())
+}
+
+fn normalize_args(model: &Model, args: &mut JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    let Some(map) = args.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(r#where) = map.get_mut("where") {
+        normalize_where(model, r#where, &(path + "where"))?;
+    }
+    for key in ["include", "select"] {
+        let Some(JsonValue::Object(relations)) = map.get_mut(key) else { continue };
+        for (name, value) in relations.iter_mut() {
+            if let Some(related) = related_model(model, name) {
+                normalize_args(related, value, &(path + key + name.as_str()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn normalize_where(model: &Model, r#where: &mut JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    let Some(map) = r#where.as_object_mut() else {
+        return Ok(());
+    };
+    for (key, value) in map.iter_mut() {
+        let path = path + key.as_str();
+        match key.as_str() {
+            "AND" | "OR" | "NOT" => {
+                normalize_combinator(key, value);
+                match value {
+                    JsonValue::Array(items) => for (index, item) in items.iter_mut().enumerate() {
+                        normalize_where(model, item, &(&path + index))?;
+                    },
+                    _ => normalize_where(model, value, &path)?,
+                }
+            }
+            _ => if model.field(key).is_some() {
+                normalize_scalar(value, &path)?;
+            } else if let Some(related) = related_model(model, key) {
+                let Some(filters) = value.as_object_mut() else { continue };
+                for (operator, inner) in filters.iter_mut() {
+                    normalize_where(related, inner, &(&path + operator.as_str()))?;
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+// `AND' and `OR' take a single filter too, `NOT' takes a list which excludes every filter in it
+fn normalize_combinator(key: &str, value: &mut JsonValue) {
+    match (key, &value) {
+        ("AND" | "OR", JsonValue::Object(_)) => *value = JsonValue::Array(vec![value.take()]),
+        ("NOT", JsonValue::Array(_)) => *value = json!({"OR": value.take()}),
+        _ => (),
+    }
+}
+
+fn normalize_scalar(filter: &mut JsonValue, path: &KeyPath) -> BoxedResult<()> {
+    if filter.is_null() {
+        *filter = json!({"equals": null});
+        return Ok(());
+    }
+    let Some(map) = filter.as_object_mut() else {
+        return Ok(());
+    };
+    let Some(is_null) = map.shift_remove(IS_NULL) else {
+        return Ok(());
+    };
+    let Some(is_null) = is_null.as_bool() else {
+        return Err(path::Error::value_error(path + IS_NULL, "expect bool").into());
+    };
+    let key = if is_null { "equals" } else { "not" };
+    if map.contains_key(key) {
+        return Err(path::Error::value_error(path + IS_NULL, format!("cannot be combined with `{}'", key)).into());
+    }
+    map.insert(key.to_owned(), JsonValue::Null);
+    Ok(())
+}
+
+fn related_model<'a>(model: &Model, name: &str) -> Option<&'a Model> {
+    let relation = model.relation(name)?;
+    Ctx::main_namespace().model_at_path(&relation.model_path())
+}
@@ -0,0 +1,69 @@
+use key_path::path;
+use serde_json::{Map, Value as JsonValue};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{CREATE, ENTRY, SINGLE};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+pub(super) const FIND_FIRST_OR_CREATE: &str = "findFirstOrCreate";
+
+// both of the builtin actions have to be enabled
+pub(super) fn find_first_or_create_actions() -> [Action; 2] {
+    [builtin_action_handler_from_name("findFirst").unwrap(), builtin_action_handler_from_name("create").unwrap()]
+}
+
+pub(super) fn find_first_or_create_input(model: &Model, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let Some(map) = json_body.as_object() else {
+        return Err(path::Error::value_error_message_only("expect object").into());
+    };
+    if !map.contains_key("where") {
+        Err(path::Error::value_error_message_only("`where' is required"))?
+    }
+    let mut find = Map::new();
+    let mut create = Map::new();
+    for (key, value) in map {
+        match key.as_str() {
+            "create" => { create.insert(key.clone(), value.clone()); }
+            "include" | "select" => {
+                find.insert(key.clone(), value.clone());
+                create.insert(key.clone(), value.clone());
+            }
+            _ => { find.insert(key.clone(), value.clone()); }
+        }
+    }
+    let [find_first, create_action] = find_first_or_create_actions();
+    let mut result = validate_and_transform_json_input_for_builtin_action(model, find_first, &JsonValue::Object(find), main_namespace)?;
+    let create = validate_and_transform_json_input_for_builtin_action(model, create_action, &JsonValue::Object(create), main_namespace)?;
+    result.as_dictionary_mut().unwrap().insert("create".to_owned(), create.get("create").cloned().unwrap_or(teon!({})));
+    Ok(result)
+}
+
+// the lookup and the creation run in one transaction
+pub(super) async fn find_first_or_create(req_ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(req_ctx)?;
+    let action = CREATE | SINGLE | ENTRY;
+    let value: Value = req_ctx.transaction_ctx().run_transaction(|ctx: transaction::Ctx| async move {
+        let [find_first, _] = find_first_or_create_actions();
+        if let Some(object) = ctx.find_first_internal(model, req_ctx.body(), false, find_first, Some(req_ctx.clone()), path![]).await? {
+            return object.to_teon_internal(&path!["data"]).await;
+        }
+        let include = req_ctx.body().get("include");
+        let select = req_ctx.body().get("select");
+        let new = ctx.new_object_with_teon_and_path(model, &teon!({}), &path![], action, Some(req_ctx.clone())).await?;
+        new.set_teon_with_path(req_ctx.body().get("create").unwrap(), &path!["create"]).await?;
+        new.save_with_session_and_path(&path!["create"]).await?;
+        let refreshed = new.refreshed(include, select).await?;
+        refreshed.to_teon_internal(&path!["data"]).await
+    }).await?;
+    Ok(Response::data(value))
+}
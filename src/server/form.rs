@@ -0,0 +1,120 @@
+use actix_multipart::Multipart;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, web};
+use futures_util::{StreamExt, TryStreamExt};
+use serde_json::{Map, Value as JsonValue};
+use teo_runtime::model::Model;
+use teo_runtime::path::{Error, Result};
+use crate::server::coerce::coerce_input;
+use crate::server::limits::payload_too_large;
+use crate::server::error::BoxedResult;
+
+const FORM_ACTIONS: [&str; 2] = ["create", "update"];
+const FORM_FLAG_KEYS: [&str; 2] = ["include", "select"];
+
+pub(crate) fn is_form_request(http_request: &HttpRequest, action: &str) -> bool {
+    FORM_ACTIONS.contains(&action) && matches!(http_request.content_type(), "application/x-www-form-urlencoded" | "multipart/form-data")
+}
+
+/// Reads an HTML form with bracketed keys into the action's JSON input.
+pub(crate) async fn parse_builtin_form_body(http_request: HttpRequest, payload: web::Payload, model: &Model, max_body_size: usize) -> Result<JsonValue> {
+    let pairs = if http_request.content_type() == "multipart/form-data" {
+        multipart_pairs(http_request, payload, max_body_size).await?
+    } else {
+        urlencoded_pairs(payload, max_body_size).await?
+    };
+    let mut json = JsonValue::Object(Map::new());
+    for (key, value) in pairs {
+        insert_bracketed(&mut json, &key, value)?;
+    }
+    coerce_input(model, &mut json);
+    for key in FORM_FLAG_KEYS {
+        if let Some(flags) = json.get_mut(key) {
+            coerce_flags(flags);
+        }
+    }
+    Ok(json)
+}
+
+async fn urlencoded_pairs(mut payload: web::Payload, max_body_size: usize) -> Result<Vec<(String, String)>> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_| Error::value_error_message_only("incorrect form format"))?;
+        if body.len() + chunk.len() > max_body_size {
+            return Err(payload_too_large(max_body_size));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(url::form_urlencoded::parse(&body).into_owned().collect())
+}
+
+async fn multipart_pairs(http_request: HttpRequest, payload: web::Payload, max_body_size: usize) -> Result<Vec<(String, String)>> {
+    let mut inner_payload = payload.into_inner();
+    let Ok(mut multipart) = Multipart::from_request(&http_request, &mut inner_payload).await else {
+        return Err(Error::value_error_message_only("incorrect form format"));
+    };
+    let mut pairs = vec![];
+    let mut size = 0;
+    while let Some(mut field) = multipart.try_next().await.map_err(|_| Error::value_error_message_only("incorrect form format"))? {
+        if field.content_disposition().get_filename().is_some() {
+            return Err(Error::value_error_message_only(format!("file uploads are not supported by builtin actions, found `{}'", field.name())));
+        }
+        let mut body = web::BytesMut::new();
+        while let Some(chunk) = field.try_next().await.map_err(|_| Error::value_error_message_only("incorrect form format"))? {
+            size += chunk.len();
+            if size > max_body_size {
+                return Err(payload_too_large(max_body_size));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        pairs.push((field.name().to_owned(), String::from_utf8_lossy(&body).into_owned()));
+    }
+    Ok(pairs)
+}
+
+// numeric segments index arrays, an empty segment appends
+fn insert_bracketed(json: &mut JsonValue, key: &str, value: String) -> BoxedResult<()> {
+    let (head, rest) = key.split_once('[').unwrap_or((key, ""));
+    let mut segments = vec![head];
+    if !rest.is_empty() {
+        let Some(rest) = rest.strip_suffix(']') else {
+            return Err(Error::value_error_message_only(format!("invalid form key `{}'", key)).into());
+        };
+        segments.extend(rest.split("]["));
+    }
+    let mut current = json;
+    for (index, segment) in segments.iter().enumerate() {
+        let last = index == segments.len() - 1;
+        let next = if last { JsonValue::String(value.clone()) } else if segments[index + 1].parse::<usize>().is_ok() || segments[index + 1].is_empty() { JsonValue::Array(vec![]) } else { JsonValue::Object(Map::new()) };
+        current = match current {
+            JsonValue::Object(map) => {
+                let entry = map.entry(segment.to_string()).or_insert(next);
+                if last { *entry = JsonValue::String(value.clone()); }
+                entry
+            }
+            JsonValue::Array(array) => {
+                let position = if segment.is_empty() { array.len() } else { segment.parse::<usize>().map_err(|_| Error::value_error_message_only(format!("invalid form key `{}'", key)))? };
+                if position > array.len() {
+                    return Err(Error::value_error_message_only(format!("form key `{}' skips array indices", key)).into());
+                }
+                if position == array.len() {
+                    array.push(next);
+                } else if last {
+                    array[position] = JsonValue::String(value.clone());
+                }
+                &mut array[position]
+            }
+            _ => return Err(Error::value_error_message_only(format!("form key `{}' conflicts with another key", key)).into()),
+        };
+    }
+    Ok(())
+}
+
+fn coerce_flags(value: &mut JsonValue) {
+    match value {
+        JsonValue::String(string) if string == "true" || string == "false" => *value = JsonValue::Bool(string == "true"),
+        JsonValue::Object(map) => for value in map.values_mut() {
+            coerce_flags(value);
+        },
+        _ => (),
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+use key_path::path;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::action::handler_model;
+
+// the connectors build group by queries on top of the row query, which applies `orderBy', `skip'
+// and `take' to the rows before they're grouped, so these are applied to the groups here
+pub(super) async fn group_by(ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    let Some(finder) = ctx.body().as_dictionary() else {
+        return Err(path::Error::value_error_message_only("expect object"));
+    };
+    let mut finder = finder.clone();
+    // connectors expect `by' as field names, input validation hands them over as enum variants
+    if let Some(Value::Array(by)) = finder.get_mut("by") {
+        for field in by.iter_mut() {
+            if let Some(name) = name(field) {
+                *field = Value::String(name);
+            }
+        }
+    }
+    let order_by = finder.shift_remove("orderBy");
+    let skip = finder.shift_remove("skip").and_then(|v| v.to_int64()).unwrap_or(0).max(0) as usize;
+    let take = finder.shift_remove("take").and_then(|v| v.to_int64()).map(|t| t.max(0) as usize);
+    let mut groups = ctx.transaction_ctx().group_by(model, &Value::Dictionary(finder), path![]).await?;
+    if let Some(order_by) = order_by {
+        let keys = order_keys(&order_by);
+        let ordering = |a: &Value, b: &Value| {
+            for (key, descending) in &keys {
+                let ordering = compare(lookup(a, key), lookup(b, key));
+                if ordering != Ordering::Equal {
+                    return if *descending { ordering.reverse() } else { ordering };
+                }
+            }
+            Ordering::Equal
+        };
+        // only the requested page is sorted
+        if let Some(end) = take.map(|t| skip.saturating_add(t)).filter(|end| *end < groups.len()) {
+            groups.select_nth_unstable_by(end, ordering);
+            groups.truncate(end);
+        }
+        groups.sort_by(ordering);
+    }
+    let groups = groups.into_iter().skip(skip);
+    let groups: Vec<Value> = match take {
+        Some(take) => groups.take(take).collect(),
+        None => groups.collect(),
+    };
+    Ok(Response::data(Value::Array(groups)))
+}
+
+// `{"name": "desc"}' orders by a grouped field, `{"_sum": {"age": "desc"}}' by an aggregate
+fn order_keys(order_by: &Value) -> Vec<(Vec<String>, bool)> {
+    let items = match order_by {
+        Value::Array(items) => items.iter().collect(),
+        _ => vec![order_by],
+    };
+    let mut keys = vec![];
+    for item in items {
+        let Some(map) = item.as_dictionary() else { continue };
+        for (key, value) in map {
+            match value.as_dictionary() {
+                Some(inner) => for (field, direction) in inner {
+                    keys.push((vec![key.clone(), field.clone()], descending(direction)));
+                },
+                None => keys.push((vec![key.clone()], descending(value))),
+            }
+        }
+    }
+    keys
+}
+
+fn descending(direction: &Value) -> bool {
+    name(direction).as_deref() == Some("desc")
+}
+
+fn name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::EnumVariant(e) => Some(e.value.clone()),
+        _ => None,
+    }
+}
+
+fn lookup<'a>(group: &'a Value, key: &[String]) -> Option<&'a Value> {
+    key.iter().try_fold(group, |value, k| value.get(k.as_str()))
+}
+
+// nulls come first in ascending order
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a.filter(|v| !v.is_null()), b.filter(|v| !v.is_null())) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use indexmap::IndexMap;
+use key_path::path;
+use maplit::btreemap;
+use serde_json::{Map, Value as JsonValue};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, MANY};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::object::batch::{BatchOptions, Batches};
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+const BATCH_SIZE: usize = 500;
+// the records are bucketed in memory, larger inputs are rejected instead of scanned
+const MAX_ROWS: usize = 100_000;
+const TIMEZONE_EXPECTED: &str = "expect `UTC', an offset like `+08:00' or a zone like `Europe/Berlin'";
+const AGGREGATES: [&str; 5] = ["_count", "_sum", "_avg", "_min", "_max"];
+
+#[derive(Copy, Clone)]
+enum Interval {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl FromStr for Interval {
+
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(Interval::Hour),
+            "day" => Ok(Interval::Day),
+            "week" => Ok(Interval::Week),
+            "month" => Ok(Interval::Month),
+            _ => Err(()),
+        }
+    }
+}
+
+// a fixed offset, or a zone of the tz database whose offset changes with daylight saving time
+#[derive(Copy, Clone)]
+enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl Zone {
+
+    fn truncate(&self, time: DateTime<Utc>, interval: Interval) -> DateTime<Utc> {
+        match self {
+            Zone::Fixed(offset) => truncate(time, interval, offset),
+            Zone::Named(tz) => truncate(time, interval, tz),
+        }
+    }
+}
+
+// a date is a calendar day in no time zone, its bucket is a date too
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Start {
+    Time(DateTime<Utc>),
+    Date(NaiveDate),
+}
+
+#[derive(Default)]
+struct Bucket {
+    count: i64,
+    counts: IndexMap<String, i64>,
+    sums: IndexMap<String, Value>,
+    averages: IndexMap<String, (Value, i64)>,
+    mins: IndexMap<String, Value>,
+    maxes: IndexMap<String, Value>,
+}
+
+pub(super) fn group_by_time_action() -> Action {
+    builtin_action_handler_from_name("aggregate").unwrap()
+}
+
+pub(super) fn group_by_time_input(model: &Model, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let Some(object) = json_body.as_object() else {
+        return Err(path::Error::value_error_message_only("expect object").into());
+    };
+    let Some(field) = object.get("field").and_then(|f| f.as_str()) else {
+        return Err(path::Error::value_error(path!["field"], "expect string").into());
+    };
+    match model.field(field) {
+        Some(f) if f.r#type.unwrap_optional().is_datetime() || f.r#type.unwrap_optional().is_date() => (),
+        _ => Err(path::Error::value_error(path!["field"], "expect a Date or DateTime field"))?,
+    }
+    let interval = object.get("interval").and_then(|i| i.as_str()).unwrap_or("");
+    if Interval::from_str(interval).is_err() {
+        Err(path::Error::value_error(path!["interval"], "expect one of hour, day, week and month"))?
+    }
+    let timezone = object.get("timezone").and_then(|t| t.as_str()).unwrap_or("UTC");
+    if parse_timezone(timezone).is_none() {
+        Err(path::Error::value_error(path!["timezone"], TIMEZONE_EXPECTED))?
+    }
+    // the rest of the input is an ordinary aggregate input
+    let mut aggregate = Map::new();
+    for (key, value) in object {
+        if key == "where" || AGGREGATES.contains(&key.as_str()) {
+            aggregate.insert(key.clone(), value.clone());
+        } else if key != "field" && key != "interval" && key != "timezone" {
+            Err(path::Error::value_error(path![key.as_str()], "unexpected key"))?
+        }
+    }
+    let mut result = validate_and_transform_json_input_for_builtin_action(model, group_by_time_action(), &JsonValue::Object(aggregate), main_namespace)?;
+    let result_map = result.as_dictionary_mut().unwrap();
+    result_map.insert("field".to_owned(), Value::String(field.to_owned()));
+    result_map.insert("interval".to_owned(), Value::String(interval.to_owned()));
+    result_map.insert("timezone".to_owned(), Value::String(timezone.to_owned()));
+    Ok(result)
+}
+
+/// `POST /<Model>/groupByTime` aggregates the records in buckets of a Date or DateTime field.
+pub(super) async fn group_by_time(ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    let body = ctx.body();
+    let Some(field) = body.get("field").and_then(|f| f.as_str()) else {
+        return Err(path::Error::value_error(path!["field"], "expect string"));
+    };
+    let Some(interval) = body.get("interval").and_then(|i| i.as_str()).and_then(|i| Interval::from_str(i).ok()) else {
+        return Err(path::Error::value_error(path!["interval"], "expect one of hour, day, week and month"));
+    };
+    let Some(timezone) = body.get("timezone").and_then(|t| t.as_str()).and_then(parse_timezone) else {
+        return Err(path::Error::value_error(path!["timezone"], TIMEZONE_EXPECTED));
+    };
+    let mut finder = teon!({});
+    if let Some(r#where) = body.get("where") {
+        finder.as_dictionary_mut().unwrap().insert("where".to_owned(), r#where.clone());
+    }
+    // records are loaded by primary key after the last one, the buckets don't depend on the order
+    let mut batches = match Batches::new(&ctx.transaction_ctx(), model, &finder, BatchOptions { size: BATCH_SIZE, ..Default::default() }) {
+        Ok(batches) => batches.for_request(FIND | MANY | ENTRY, ctx.clone()),
+        Err(e) => return Err(path::Error::internal_server_error_message_only(e.message())),
+    };
+    let mut buckets: BTreeMap<Start, Bucket> = BTreeMap::new();
+    let mut rows = 0;
+    while let Some(objects) = batches.next().await? {
+        rows += objects.len();
+        if rows > MAX_ROWS {
+            return Err(too_many_rows());
+        }
+        for object in &objects {
+            let start = match object.get_value(field)? {
+                Value::DateTime(time) => Start::Time(timezone.truncate(time, interval)),
+                Value::Date(date) => Start::Date(start_date(date, interval)),
+                _ => continue,
+            };
+            let bucket = buckets.entry(start).or_default();
+            accumulate(bucket, object, body)?;
+        }
+    }
+    let data: Vec<Value> = buckets.into_iter().map(|(start, bucket)| bucket_value(start, bucket, body)).collect();
+    Ok(Response::data(Value::Array(data)))
+}
+
+fn too_many_rows() -> path::Error {
+    path::Error {
+        title: "QueryTooComplex",
+        message: format!("groupByTime matches more than {} records, narrow `where'", MAX_ROWS),
+        fields: None,
+        code: 400,
+        meta_map: btreemap! {},
+    }
+}
+
+fn parse_timezone(timezone: &str) -> Option<Zone> {
+    match timezone {
+        "UTC" | "Z" => FixedOffset::east_opt(0).map(Zone::Fixed),
+        _ if timezone.starts_with(['+', '-']) => FixedOffset::from_str(timezone).ok().map(Zone::Fixed),
+        _ => Tz::from_str(timezone).ok().map(Zone::Named),
+    }
+}
+
+// an hour is truncated in the offset of the record, so the two hours of a clock set back are two
+// buckets, the other intervals start at midnight in the offset of that day
+fn truncate<Z: TimeZone>(time: DateTime<Utc>, interval: Interval, timezone: &Z) -> DateTime<Utc> {
+    let local = time.with_timezone(timezone).naive_local();
+    if let Interval::Hour = interval {
+        return time - Duration::seconds(local.minute() as i64 * 60 + local.second() as i64) - Duration::nanoseconds(local.nanosecond() as i64);
+    }
+    local_to_utc(start_date(local.date(), interval).and_time(NaiveTime::MIN), timezone)
+}
+
+fn start_date(date: NaiveDate, interval: Interval) -> NaiveDate {
+    match interval {
+        Interval::Hour | Interval::Day => date,
+        Interval::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        Interval::Month => date.with_day(1).unwrap(),
+    }
+}
+
+// a midnight skipped by a daylight saving change starts its day at the change
+fn local_to_utc<Z: TimeZone>(local: NaiveDateTime, timezone: &Z) -> DateTime<Utc> {
+    let mut time = local;
+    loop {
+        if let Some(start) = timezone.from_local_datetime(&time).earliest() {
+            return start.with_timezone(&Utc);
+        }
+        time += Duration::minutes(30);
+    }
+}
+
+fn requested_fields<'a>(body: &'a Value, aggregate: &str) -> Vec<&'a str> {
+    match body.get(aggregate).and_then(|a| a.as_dictionary()) {
+        Some(map) => map.iter().filter(|(k, v)| k.as_str() != "_all" && v.as_bool() == Some(true)).map(|(k, _)| k.as_str()).collect(),
+        None => vec![],
+    }
+}
+
+fn accumulate(bucket: &mut Bucket, object: &Object, body: &Value) -> BoxedResult<()> {
+    bucket.count += 1;
+    for field in requested_fields(body, "_count") {
+        let count = bucket.counts.entry(field.to_owned()).or_insert(0);
+        if !object.get_value(field)?.is_null() {
+            *count += 1;
+        }
+    }
+    for field in requested_fields(body, "_sum") {
+        let value = object.get_value(field)?;
+        let sum = bucket.sums.entry(field.to_owned()).or_insert(Value::Null);
+        *sum = add(sum, &value);
+    }
+    for field in requested_fields(body, "_avg") {
+        let value = object.get_value(field)?;
+        let (sum, count) = bucket.averages.entry(field.to_owned()).or_insert((Value::Null, 0));
+        if !value.is_null() {
+            *sum = add(sum, &value);
+            *count += 1;
+        }
+    }
+    for field in requested_fields(body, "_min") {
+        let value = object.get_value(field)?;
+        let min = bucket.mins.entry(field.to_owned()).or_insert(Value::Null);
+        if !value.is_null() && (min.is_null() || value < *min) {
+            *min = value;
+        }
+    }
+    for field in requested_fields(body, "_max") {
+        let value = object.get_value(field)?;
+        let max = bucket.maxes.entry(field.to_owned()).or_insert(Value::Null);
+        if !value.is_null() && (max.is_null() || value > *max) {
+            *max = value;
+        }
+    }
+    Ok(())
+}
+
+fn add(lhs: &Value, rhs: &Value) -> Value {
+    match (lhs, rhs) {
+        (Value::Null, value) | (value, Value::Null) => match value {
+            Value::Int(i) => Value::Int64(*i as i64),
+            Value::Float32(f) => Value::Float(*f as f64),
+            _ => value.clone(),
+        },
+        (Value::Decimal(l), Value::Decimal(r)) => Value::Decimal(l + r),
+        _ => match (lhs.to_int64(), rhs.to_int64()) {
+            (Some(l), Some(r)) => Value::Int64(l.saturating_add(r)),
+            _ => Value::Float(lhs.to_float().unwrap_or(0.0) + rhs.to_float().unwrap_or(0.0)),
+        },
+    }
+}
+
+fn average(sum: Value, count: i64) -> Value {
+    if count == 0 {
+        return Value::Null;
+    }
+    match sum {
+        Value::Decimal(d) => Value::Decimal(d / BigDecimal::from_i64(count).unwrap()),
+        value => value.to_float().map(|f| Value::Float(f / count as f64)).unwrap_or(Value::Null),
+    }
+}
+
+fn bucket_value(start: Start, bucket: Bucket, body: &Value) -> Value {
+    let start = match start {
+        Start::Time(time) => Value::DateTime(time),
+        Start::Date(date) => Value::Date(date),
+    };
+    let mut result = teon!({"bucket": start});
+    let map = result.as_dictionary_mut().unwrap();
+    if let Some(count) = body.get("_count").and_then(|c| c.as_dictionary()) {
+        let mut counts: IndexMap<String, Value> = bucket.counts.into_iter().map(|(k, v)| (k, Value::Int64(v))).collect();
+        if count.get("_all").and_then(|a| a.as_bool()) == Some(true) {
+            counts.insert("_all".to_owned(), Value::Int64(bucket.count));
+        }
+        map.insert("_count".to_owned(), Value::Dictionary(counts));
+    }
+    if body.get("_sum").is_some() {
+        map.insert("_sum".to_owned(), Value::Dictionary(bucket.sums));
+    }
+    if body.get("_avg").is_some() {
+        map.insert("_avg".to_owned(), Value::Dictionary(bucket.averages.into_iter().map(|(k, (sum, count))| (k, average(sum, count))).collect()));
+    }
+    if body.get("_min").is_some() {
+        map.insert("_min".to_owned(), Value::Dictionary(bucket.mins));
+    }
+    if body.get("_max").is_some() {
+        map.insert("_max".to_owned(), Value::Dictionary(bucket.maxes));
+    }
+    result
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use actix_web::HttpRequest;
+use maplit::btreemap;
+use serde_json::Value as JsonValue;
+use teo_runtime::path::{Error, Result};
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+use crate::utils::sha256_hex;
+
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const IDEMPOTENT_ACTIONS: [&str; 6] = ["create", "update", "upsert", "createMany", "updateMany", "batch"];
+const CREDENTIAL_HEADERS: [&str; 2] = ["Authorization", "Cookie"];
+
+// a pending key is held by its request until it finishes or is dropped, e.g. because its client
+// disconnected, the token tells its guard from the one of a later request with the same key
+enum Entry {
+    Pending {
+        fingerprint: String,
+        token: u64,
+    },
+    Done {
+        fingerprint: String,
+        expires: Instant,
+        code: u16,
+        headers: Vec<(String, String)>,
+        body: Value,
+    },
+}
+
+impl Entry {
+
+    fn expired(&self, now: Instant) -> bool {
+        match self {
+            Entry::Pending { .. } => false,
+            Entry::Done { expires, .. } => *expires <= now,
+        }
+    }
+
+    fn is_pending(&self, token: u64) -> bool {
+        matches!(self, Entry::Pending { token: t, .. } if *t == token)
+    }
+
+    fn fingerprint(&self) -> &str {
+        match self {
+            Entry::Pending { fingerprint, .. } => fingerprint,
+            Entry::Done { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+pub(crate) struct IdempotencyStore {
+    window: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+    tokens: AtomicU64,
+}
+
+impl IdempotencyStore {
+
+    pub(crate) fn new(window: Duration) -> Self {
+        Self { window, entries: Mutex::new(HashMap::new()), tokens: AtomicU64::new(0) }
+    }
+
+    /// Returns the recorded response of this key, or marks it pending until the guard is dropped.
+    pub(crate) fn begin(self: &Arc<Self>, key: &str, fingerprint: String) -> BoxedResult<Begun> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| !entry.expired(now));
+        if let Some(entry) = entries.get(key) {
+            if entry.fingerprint() != fingerprint {
+                return Err(error("UnprocessableEntity", "idempotency key is already used for a different request", 422).into());
+            }
+            return match entry {
+                Entry::Pending { .. } => Err(error("Conflict", "a request with this idempotency key is in progress", 409).into()),
+                Entry::Done { code, headers, body, .. } => {
+                    // the body is encoded for the replaying request, which may accept another format
+                    let recorded = Response::teon(body.clone());
+                    recorded.set_code(*code);
+                    for (name, value) in headers {
+                        recorded.headers().set(name.as_str(), value.as_str());
+                    }
+                    recorded.headers().set(REPLAYED_HEADER, "true");
+                    Ok(Begun::Recorded(recorded))
+                }
+            };
+        }
+        let token = self.tokens.fetch_add(1, Ordering::SeqCst);
+        entries.insert(key.to_owned(), Entry::Pending { fingerprint, token });
+        Ok(Begun::Pending(PendingKey { store: self.clone(), key: key.to_owned(), token, finished: false }))
+    }
+
+    // successful responses are recorded, failed requests release the key so they can be retried
+    fn finish(&self, key: &str, token: u64, response: &Result<Response>) {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.get(key).is_some_and(|entry| entry.is_pending(token)) {
+            return;
+        }
+        let Some(Entry::Pending { fingerprint, .. }) = entries.remove(key) else {
+            return;
+        };
+        let expires = Instant::now() + self.window;
+        if let Some(response) = response.as_ref().ok().filter(|r| r.code() < 400) {
+            if let Some(body) = response.body().as_teon().cloned() {
+                let headers = response.headers().keys().into_iter().filter_map(|name| {
+                    let value = response.headers().get(&name)?;
+                    Some((name, value))
+                }).collect();
+                entries.insert(key.to_owned(), Entry::Done { fingerprint, expires, code: response.code(), headers, body });
+            }
+        }
+    }
+}
+
+pub(crate) enum Begun {
+    Recorded(Response),
+    Pending(PendingKey),
+}
+
+/// A key whose request is in progress, released if the request doesn't finish.
+pub(crate) struct PendingKey {
+    store: Arc<IdempotencyStore>,
+    key: String,
+    token: u64,
+    finished: bool,
+}
+
+impl PendingKey {
+
+    pub(crate) fn finish(mut self, response: &Result<Response>) {
+        self.store.finish(&self.key, self.token, response);
+        self.finished = true;
+    }
+}
+
+impl Drop for PendingKey {
+
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // the lock may be poisoned when dropped in a panic, the entries are consistent anyway
+        let mut entries = self.store.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.get(&self.key).is_some_and(|entry| entry.is_pending(self.token)) {
+            entries.remove(&self.key);
+        }
+    }
+}
+
+// keys are scoped to the credentials of the request, so a client can't replay another one's response
+pub(crate) fn idempotency_key(http_request: &HttpRequest, action: &str) -> Option<String> {
+    if !IDEMPOTENT_ACTIONS.contains(&action) {
+        return None;
+    }
+    let key = http_request.headers().get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 255)?;
+    let credentials: Vec<&[u8]> = CREDENTIAL_HEADERS.iter()
+        .map(|name| http_request.headers().get(*name).map_or(&[][..], |v| v.as_bytes()))
+        .collect();
+    let scope = sha256_hex(&credentials.join(&b'\n'), 32);
+    Some(format!("{}:{}", scope, key))
+}
+
+pub(crate) fn fingerprint(path: &str, json_body: &JsonValue) -> String {
+    let content = format!("{}\n{}", path, json_body);
+    sha256_hex(content.as_bytes(), 32)
+}
+
+fn error(title: &'static str, message: &str, code: i32) -> Error {
+    Error {
+        title,
+        message: message.to_owned(),
+        fields: None,
+        code,
+        meta_map: btreemap! {},
+    }
+}
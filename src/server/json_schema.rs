@@ -0,0 +1,207 @@
+use serde_json::{json, Map, Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::{Field, Model};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::readwrite::write::Write;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::server::action::builtin_action_enabled;
+use crate::server::meta::{description, localized_name};
+
+pub(super) const JSON_SCHEMA_PATH: &str = "/_jsonSchema";
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema documents of the `create` and `update` inputs of the models, keyed by model path,
+/// which frontends validate forms with before sending them. The rules come from the field types
+/// and the validation items at the start of `@onSet`, e.g. `$isEmail`, `$regexMatch(/.../)`,
+/// `$hasLength(2...20)`, `$gte(rhs: 0)` and `$oneOf(["a", "b"])`. Other items like `$trim`
+/// change the value, the rules after them describe the changed value and are left out.
+pub fn namespace_json_schema(namespace: &Namespace) -> JsonValue {
+    let mut models = Map::new();
+    collect(namespace, &mut models);
+    JsonValue::Object(models)
+}
+
+fn collect(namespace: &Namespace, models: &mut Map<String, JsonValue>) {
+    for model in namespace.models.values() {
+        if !model.generate_client {
+            continue
+        }
+        let mut inputs = Map::new();
+        for action in ["create", "update"] {
+            if model.builtin_handlers.iter().any(|a| a.as_handler_str() == action && builtin_action_enabled(model, *a)) {
+                inputs.insert(action.to_owned(), input_schema(model, action));
+            }
+        }
+        models.insert(model.path.join("."), JsonValue::Object(inputs));
+    }
+    for child in namespace.namespaces.values() {
+        if child.path == vec!["std".to_owned()] {
+            continue
+        }
+        collect(child, models);
+    }
+}
+
+fn input_schema(model: &Model, action: &str) -> JsonValue {
+    let name = model.path.last().cloned().unwrap_or_default();
+    let mut properties = Map::new();
+    let mut required = vec![];
+    for field in model.fields.values() {
+        let writable = match field.write {
+            Write::NoWrite => false,
+            Write::WriteOnCreate => action == "create",
+            _ => true,
+        };
+        if !writable {
+            continue
+        }
+        properties.insert(field.name.clone(), field_schema(field));
+        if action == "create" && required_on_create(field) {
+            required.push(field.name.clone());
+        }
+    }
+    json!({
+        "$schema": DIALECT,
+        "title": format!("{}{}Input", name, if action == "create" { "Create" } else { "Update" }),
+        "description": description(model.comment.as_ref()),
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+// foreign keys are usually set by connecting the relation
+fn required_on_create(field: &Field) -> bool {
+    field.is_required() && field.default.is_none() && field.on_save.is_empty() && !field.input_omissible && !field.auto && !field.auto_increment && !field.foreign_key
+}
+
+fn field_schema(field: &Field) -> JsonValue {
+    let mut schema = type_schema(field.r#type.unwrap_optional());
+    schema.insert("title".to_owned(), json!(localized_name(field.comment.as_ref(), &field.name)));
+    if let Some(description) = description(field.comment.as_ref()) {
+        schema.insert("description".to_owned(), json!(description));
+    }
+    let mut patterns = vec![];
+    for item in &field.on_set.items {
+        if !apply_rule(item, &mut schema, &mut patterns) {
+            break
+        }
+    }
+    match patterns.len() {
+        0 => (),
+        1 => { schema.insert("pattern".to_owned(), patterns.remove(0)); }
+        _ => { schema.insert("allOf".to_owned(), patterns.into_iter().map(|p| json!({ "pattern": p })).collect()); }
+    }
+    if let Some(default) = field.default.as_ref().and_then(|d| d.as_teon()).and_then(default_value) {
+        schema.insert("default".to_owned(), default);
+    }
+    // `$presents' rejects null, which the field accepts otherwise
+    let presents = field.on_set.items.first().is_some_and(|item| item.path == ["std", "presents"]);
+    if field.is_optional() && !presents {
+        if let Some(JsonValue::String(r#type)) = schema.remove("type") {
+            schema.insert("type".to_owned(), json!([r#type, "null"]));
+        }
+        if let Some(JsonValue::Array(members)) = schema.get_mut("enum") {
+            members.push(JsonValue::Null);
+        }
+    }
+    JsonValue::Object(schema)
+}
+
+fn type_schema(r#type: &Type) -> Map<String, JsonValue> {
+    let schema = match r#type {
+        Type::Bool => json!({ "type": "boolean" }),
+        Type::Int | Type::Int64 => json!({ "type": "integer" }),
+        Type::Float32 | Type::Float => json!({ "type": "number" }),
+        Type::Decimal => json!({ "type": ["string", "number"] }),
+        Type::String => json!({ "type": "string" }),
+        Type::ObjectId => json!({ "type": "string", "pattern": "^[0-9a-fA-F]{24}$" }),
+        Type::Date => json!({ "type": "string", "format": "date" }),
+        Type::DateTime => json!({ "type": "string", "format": "date-time" }),
+        Type::EnumVariant(reference) => match Ctx::main_namespace().enum_at_path(&reference.str_path()) {
+            Some(r#enum) => json!({ "type": "string", "enum": r#enum.members.iter().map(|m| m.name.clone()).collect::<Vec<String>>() }),
+            None => json!({ "type": "string" }),
+        },
+        Type::Array(inner) => json!({ "type": "array", "items": type_schema(inner.unwrap_optional()) }),
+        Type::Dictionary(inner) => json!({ "type": "object", "additionalProperties": type_schema(inner.unwrap_optional()) }),
+        _ => json!({}),
+    };
+    match schema {
+        JsonValue::Object(schema) => schema,
+        _ => unreachable!(),
+    }
+}
+
+/// Adds the rule of a validation item, returns false for an item which changes the value.
+fn apply_rule(item: &BoundedItem, schema: &mut Map<String, JsonValue>, patterns: &mut Vec<JsonValue>) -> bool {
+    // the items of this crate are loaded into the main namespace
+    let name = match item.path.as_slice() {
+        [namespace, name] if namespace == "std" => name.as_str(),
+        [name] if name == "isURL" || name == "isUUID" => name.as_str(),
+        _ => return false,
+    };
+    // arguments given as pipelines are only known when the value is set
+    let argument = |key: &str| item.arguments.get_object_ref(key).ok().and_then(|o| o.as_teon());
+    match name {
+        "presents" => (),
+        "isEmail" => { schema.insert("format".to_owned(), json!("email")); }
+        "isURL" => { schema.insert("format".to_owned(), json!("uri")); }
+        "isUUID" => { schema.insert("format".to_owned(), json!("uuid")); }
+        "isHexColor" => patterns.push(json!("^[A-Fa-f0-9]{6}$")),
+        "isNumeric" => patterns.push(json!("^\\p{N}*$")),
+        "isAlphabetic" => patterns.push(json!("^\\p{Alphabetic}*$")),
+        "isAlphanumeric" => patterns.push(json!("^[\\p{Alphabetic}\\p{N}]*$")),
+        "regexMatch" => if let Some(regex) = argument("regex").and_then(|r| r.as_regexp()) {
+            patterns.push(json!(regex.as_str()));
+        },
+        "hasLength" => {
+            let (min_key, max_key) = if schema.get("type") == Some(&json!("array")) { ("minItems", "maxItems") } else { ("minLength", "maxLength") };
+            if let Some(len) = argument("len").and_then(|l| l.to_int64()) {
+                schema.insert(min_key.to_owned(), json!(len));
+                schema.insert(max_key.to_owned(), json!(len));
+            } else if let Some(range) = argument("range").and_then(|r| r.as_range()) {
+                if let Some(start) = range.start.to_int64() {
+                    schema.insert(min_key.to_owned(), json!(start));
+                }
+                if let Some(end) = range.end.to_int64() {
+                    schema.insert(max_key.to_owned(), json!(if range.closed { end } else { end - 1 }));
+                }
+            }
+        }
+        "gt" | "gte" | "lt" | "lte" => if let Some(rhs) = argument("rhs").and_then(number) {
+            let key = match name {
+                "gt" => "exclusiveMinimum",
+                "gte" => "minimum",
+                "lt" => "exclusiveMaximum",
+                _ => "maximum",
+            };
+            schema.insert(key.to_owned(), rhs);
+        },
+        "oneOf" => if let Some(candidates) = argument("candidates").and_then(|c| c.as_array()) {
+            let candidates: Vec<JsonValue> = candidates.iter().filter_map(|c| JsonValue::try_from(c).ok()).collect();
+            schema.insert("enum".to_owned(), json!(candidates));
+        },
+        _ => return false,
+    }
+    true
+}
+
+fn default_value(value: &Value) -> Option<JsonValue> {
+    match value {
+        Value::EnumVariant(variant) => Some(json!(variant.value)),
+        // dates and decimals are wrapped in objects which aren't input values
+        _ => JsonValue::try_from(value).ok().filter(|v| !v.is_object()),
+    }
+}
+
+fn number(value: &Value) -> Option<JsonValue> {
+    match value {
+        Value::Int(_) | Value::Int64(_) => value.to_int64().map(|n| json!(n)),
+        Value::Float32(_) | Value::Float(_) => value.to_float().map(|n| json!(n)),
+        _ => None,
+    }
+}
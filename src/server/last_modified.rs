@@ -0,0 +1,59 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::HttpRequest;
+use actix_web::http::header::HttpDate;
+use chrono::{DateTime, Utc};
+use teo_runtime::model::{Field, Model};
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+
+const LAST_MODIFIED_HEADER: &str = "Last-Modified";
+const IF_MODIFIED_SINCE_HEADER: &str = "If-Modified-Since";
+const READ_ACTIONS: [&str; 3] = ["findMany", "findFirst", "findUnique"];
+
+/// The date time field which is set to `$now` on every save, declared with `@onSave($now)`.
+pub(crate) fn updated_at_field(model: &Model) -> Option<&Field> {
+    model.fields().into_iter().find(|field| {
+        !field.r#virtual && field.r#type.unwrap_optional().is_datetime() && field.on_save.items.last().is_some_and(|item| item.path == ["std", "now"])
+    })
+}
+
+/// Sets `Last-Modified` and answers with 304 when the records didn't change since `If-Modified-Since`.
+pub(crate) fn apply_last_modified(http_request: &HttpRequest, model: &Model, action: &str, response: Response) -> Response {
+    if !READ_ACTIONS.contains(&action) {
+        return response;
+    }
+    let Some(field) = updated_at_field(model) else {
+        return response;
+    };
+    let Some(last_modified) = latest_update(&response, &field.name) else {
+        return response;
+    };
+    // HTTP dates have no fractional seconds
+    let last_modified = UNIX_EPOCH + Duration::from_secs(last_modified.timestamp().max(0) as u64);
+    let not_modified = http_request.headers().get(IF_MODIFIED_SINCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<HttpDate>().ok())
+        .is_some_and(|since| last_modified <= SystemTime::from(since));
+    let response = if not_modified {
+        let empty = Response::empty();
+        empty.set_code(304);
+        for key in response.headers().keys() {
+            empty.headers().set(key.as_str(), response.headers().get(&key).unwrap());
+        }
+        empty
+    } else {
+        response
+    };
+    response.headers().set(LAST_MODIFIED_HEADER, HttpDate::from(last_modified).to_string());
+    response
+}
+
+fn latest_update(response: &Response, field: &str) -> Option<DateTime<Utc>> {
+    let body = response.body();
+    let records = match body.as_teon()?.get("data")? {
+        Value::Array(records) => records.iter().collect(),
+        record @ Value::Dictionary(_) => vec![record],
+        _ => return None,
+    };
+    records.iter().filter_map(|r| r.get(field)?.as_datetime().cloned()).max()
+}
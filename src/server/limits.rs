@@ -0,0 +1,87 @@
+use std::time::Duration;
+use serde_json::Value as JsonValue;
+use maplit::btreemap;
+use teo_runtime::path::Error;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const NESTED_MUTATION_KEYS: [&str; 11] = ["create", "createMany", "update", "updateMany", "upsert", "connectOrCreate", "connect", "set", "disconnect", "delete", "deleteMany"];
+
+#[derive(Debug, Copy, Clone)]
+pub struct Limits {
+    pub max_body_size: usize,
+    pub max_mutation_depth: usize,
+    pub max_array_length: usize,
+    pub max_query_cost: Option<u64>,
+    pub warn_query_cost: Option<u64>,
+    pub slow_request: Option<Duration>,
+    pub strict_slow_request: bool,
+    pub max_statements: Option<usize>,
+    pub strict_max_statements: bool,
+    /// The records a relation filter inside a relation filter may match, it's run as a list of
+    /// their primary keys.
+    pub max_relation_filter_keys: usize,
+}
+
+impl Default for Limits {
+
+    fn default() -> Self {
+        Self {
+            max_body_size: 262_144,
+            max_mutation_depth: 8,
+            max_array_length: 1000,
+            max_query_cost: None,
+            warn_query_cost: None,
+            slow_request: None,
+            strict_slow_request: false,
+            max_statements: None,
+            strict_max_statements: false,
+            max_relation_filter_keys: 10_000,
+        }
+    }
+}
+
+pub(crate) fn limits_for_action(action: &str) -> Limits {
+    let settings = Ctx::settings();
+    settings.action_limits.get(action).cloned().unwrap_or(settings.limits)
+}
+
+pub(crate) fn validate_limits(value: &JsonValue, limits: &Limits) -> BoxedResult<()> {
+    validate_value(value, limits, &mut vec![], 0)
+}
+
+pub(crate) fn payload_too_large(limit: usize) -> Error {
+    Error {
+        title: "PayloadTooLarge",
+        message: format!("request body exceeds {} bytes", limit),
+        fields: None,
+        code: 413,
+        meta_map: btreemap! {},
+    }
+}
+
+fn validate_value(value: &JsonValue, limits: &Limits, path: &mut Vec<String>, depth: usize) -> BoxedResult<()> {
+    match value {
+        JsonValue::Object(map) => for (key, value) in map {
+            let depth = if NESTED_MUTATION_KEYS.contains(&key.as_str()) { depth + 1 } else { depth };
+            path.push(key.clone());
+            if depth > limits.max_mutation_depth {
+                return Err(Error::value_error_message_only(format!("nested mutation depth exceeds {} at `{}'", limits.max_mutation_depth, path.join("."))).into());
+            }
+            validate_value(value, limits, path, depth)?;
+            path.pop();
+        },
+        JsonValue::Array(values) => {
+            if values.len() > limits.max_array_length {
+                return Err(Error::value_error_message_only(format!("array length exceeds {} at `{}'", limits.max_array_length, path.join("."))).into());
+            }
+            for (index, value) in values.iter().enumerate() {
+                path.push(index.to_string());
+                validate_value(value, limits, path, depth)?;
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
@@ -1,19 +1,20 @@
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::time::SystemTime;
 use actix_web::dev::Service;
 use futures_util::FutureExt;
 use colored::Colorize;
 use futures_util::future;
-use serde_json::{Value as JsonValue};
+use serde_json::{json, Value as JsonValue};
 use teo_result::{Error, Result};
 use teo_runtime::config::server::Server;
 use teo_runtime::namespace::Namespace;
 use actix_http::body::MessageBody;
 use actix_http::{HttpMessage, Method as HttpMethod};
-use actix_web::{App, FromRequest, HttpRequest, HttpResponse, HttpServer, web};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, web};
 use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
 use actix_web::middleware::DefaultHeaders;
-use key_path::path;
+use actix_web::http::header::{HeaderName, HeaderValue};
 use teo_parser::ast::handler::HandlerInputFormat;
 use teo_runtime::action::Action;
 use teo_runtime::handler::action::builtin_action_handler_from_name;
@@ -21,21 +22,57 @@ use teo_runtime::handler::Handler;
 use teo_runtime::handler::handler::Method;
 use teo_runtime::{connection, request};
 use teo_runtime::connection::transaction;
-use teo_runtime::handler::default::{create, find_first, find_many, find_unique, update, upsert, copy, create_many, update_many, copy_many, delete_many, count, aggregate, group_by, delete};
 use teo_runtime::model::Model;
 use teo_runtime::response::Response;
 use teo_teon::Value;
+use uuid::Uuid;
+use maplit::btreemap;
+use crate::telemetry::{parse_traceparent, traced, Span, SpanKind, TRACEPARENT_HEADER};
+use crate::app::ctx::Ctx;
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
+use crate::server::action::builtin_action_enabled;
+use crate::server::admin::{admin_page, guard_admin_endpoint, is_admin_path};
+use crate::server::batch::{BATCH_PATH, batch};
+use crate::server::builtin::{BuiltinCall, call_builtin};
+use crate::server::embedded::serve_embedded;
+use crate::server::form::{is_form_request, parse_builtin_form_body};
+use crate::server::etag::if_match;
+use crate::server::idempotency::{Begun, fingerprint, idempotency_key};
+use crate::internal_only::reject_internal_only_input;
+use crate::position::position_field;
+use crate::server::reorder::{placement, reorder, reorder_input};
+use crate::server::tree::{TREE_ACTIONS, tree, tree_builtin_action, tree_input};
+use crate::tree::parent_relation;
+use crate::server::share::{SHARE_PATH, prepare_share_args, share_action, share_input, share_preview, share_response, shared};
+use crate::server::stats::STATS_PATH;
+use crate::server::compare::{compare, compare_input, find_unique_action};
+use crate::server::filters::normalize_filters;
+use crate::server::compression::{compress, negotiate_encoding};
+use crate::server::coerce::apply_coercion;
+use crate::server::cost::check_query_cost;
+use crate::mock::simulated_latency;
+use crate::server::record::{record, tee_payload};
+use crate::server::report::{keep_ctx, panic_error, report_error};
+use crate::server::duplicate::{duplicate_action, duplicate_input, duplicate_record};
+use crate::server::find_or_create::{FIND_FIRST_OR_CREATE, find_first_or_create, find_first_or_create_actions, find_first_or_create_input};
+use crate::server::group_by_time::{group_by_time, group_by_time_action, group_by_time_input};
+use crate::archive::archive;
+use crate::server::archive::{FIND_ARCHIVED, find_archived, find_archived_action, find_archived_input};
+use crate::server::limits::{limits_for_action, validate_limits};
+use crate::server::meta::{META_PATH, namespace_meta};
+use crate::server::json_schema::{JSON_SCHEMA_PATH, namespace_json_schema};
 use crate::server::parse::{parse_form_body, parse_json_body};
 use teo_runtime::handler::input::{validate_and_transform_json_input_for_handler, validate_and_transform_json_input_for_builtin_action};
 use teo_runtime::handler::r#match::HandlerMatch;
+use teo_runtime::middleware::next::Next;
 use crate::message::{info_message, request_message, unhandled_request_message};
-use crate::server::error::WrapError;
-use crate::server::request::RequestImpl;
+use crate::server::error::{REQUEST_ID_HEADER, WrapError};
+use crate::server::request::teo_request;
 use crate::server::responder::IntoHttpResponse;
+use crate::server::tls;
 
-fn make_server_app(
+pub(crate) fn make_server_app(
     main_namespace: &'static Namespace,
     conf: &'static Server,
 ) -> App<impl ServiceFactory<
@@ -50,7 +87,60 @@ fn make_server_app(
             .add(("Access-Control-Allow-Origin", "*"))
             .add(("Access-Control-Allow-Methods", "OPTIONS, POST, GET"))
             .add(("Access-Control-Allow-Headers", "*"))
-            .add(("Access-Control-Max-Age", "86400")))
+            .add(("Access-Control-Max-Age", "86400"))
+            .add(("Access-Control-Expose-Headers", "X-Request-Id, ETag")))
+        .wrap_fn(|mut req, srv| {
+            let request_id = match req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()) {
+                Some(request_id) if !request_id.is_empty() && request_id.len() <= 200 => request_id.to_owned(),
+                _ => Uuid::new_v4().to_string(),
+            };
+            req.headers_mut().insert(HeaderName::from_static("x-request-id"), HeaderValue::from_str(&request_id).unwrap());
+            let http_request = req.request().clone();
+            // a panicking handler is answered and reported like an internal server error
+            let fut = AssertUnwindSafe(srv.call(req)).catch_unwind();
+            async move {
+                let res = match fut.await {
+                    Ok(res) => res?.map_into_boxed_body(),
+                    Err(panic) => ServiceResponse::new(http_request, HttpResponse::from_error(panic_error(panic))),
+                };
+                let mut res = if let Some(wrap_error) = res.response().error().and_then(|e| e.as_error::<WrapError>()) {
+                    report_error(wrap_error, &request_id, &res);
+                    let response = wrap_error.error_response_with_request_id(&request_id);
+                    res.into_response(response)
+                } else {
+                    res
+                };
+                res.headers_mut().insert(HeaderName::from_static("x-request-id"), HeaderValue::from_str(&request_id).unwrap());
+                Ok(res)
+            }
+        })
+        .wrap_fn(|req, srv| {
+            let parent = req.headers().get(TRACEPARENT_HEADER).and_then(|v| v.to_str().ok()).and_then(parse_traceparent);
+            let span = Span::start_with_parent(req.method().as_str(), SpanKind::Server, parent).map(|mut span| {
+                span.set("http.request.method", json!(req.method().as_str()));
+                span.set("url.path", json!(req.path()));
+                span
+            });
+            let fut = srv.call(req);
+            async move {
+                let Some(mut span) = span else {
+                    return fut.await;
+                };
+                let res = span.scope(fut).await;
+                if let Ok(res) = &res {
+                    if let Some(handler_match) = res.request().extensions().get::<HandlerMatch>() {
+                        span.rename(format!("{} {}", res.request().method(), handler_match.path.iter().chain(std::iter::once(&handler_match.name)).cloned().collect::<Vec<_>>().join(".")));
+                    }
+                    let status = res.response().status();
+                    span.set("http.response.status_code", json!(status.as_u16()));
+                    if status.is_server_error() {
+                        span.fail(status.to_string());
+                    }
+                }
+                span.end();
+                res
+            }
+        })
         .wrap_fn(|req, srv| {
             let start = SystemTime::now();
             let fut = srv.call(req);
@@ -58,7 +148,7 @@ fn make_server_app(
                 let res = fut.await?;
                 {
                     let binding = res.request().extensions();
-                    let handler_found_info = binding.get::<HandlerMatch>().clone();
+                    let handler_found_info = binding.get::<HandlerMatch>();
                     let time_elapsed = SystemTime::now().duration_since(start).unwrap();
                     let path = res.request().path();
                     let method = res.request().method().as_str();
@@ -71,21 +161,115 @@ fn make_server_app(
                 Ok(res)
             }
         })
+        .wrap_fn(|mut req, srv| {
+            let recording = Ctx::settings().recorder.clone().filter(|r| r.is_recording()).map(|recorder| {
+                let body = tee_payload(&mut req);
+                let headers = recorder.recorded_headers(&req);
+                (recorder, req.method().to_string(), req.uri().to_string(), headers, body)
+            });
+            let fut = srv.call(req);
+            async move {
+                let res = fut.await?.map_into_boxed_body();
+                Ok(match recording {
+                    Some((recorder, method, uri, headers, body)) => record(&recorder, method, uri, headers, body, res).await,
+                    None => res,
+                })
+            }
+        })
+        .wrap_fn(|req, srv| {
+            let encoding = negotiate_encoding(&req);
+            let fut = srv.call(req);
+            async move {
+                Ok(compress(fut.await?, encoding))
+            }
+        })
         .default_service(web::route().to(move |http_request: HttpRequest, payload: web::Payload| async move {
             // validate path
-            let path = main_namespace.handler_map.remove_path_prefix(http_request.path(), conf.path_prefix.as_ref().map(|s| s.as_str()));
+            let path = main_namespace.handler_map.remove_path_prefix(http_request.path(), conf.path_prefix.as_deref());
             let method = method_from(http_request.method())?;
+            if let Some(latency) = simulated_latency() {
+                tokio::time::sleep(latency).await;
+            }
+            if !Ctx::settings().plugins.is_empty() {
+                let request = teo_request(&http_request);
+                for plugin in Ctx::settings().plugins.clone() {
+                    plugin.on_request(&request).await?;
+                }
+            }
+            if let Some(guard) = Ctx::settings().admin_guard.clone() {
+                if method == Method::Get && is_admin_path(path) {
+                    let request = teo_request(&http_request);
+                    if !guard.allow(request).await {
+                        Err(teo_runtime::path::Error::unauthorized_error_message_only("admin access denied"))?
+                    }
+                    return Ok::<HttpResponse, WrapError>(admin_page(conf.path_prefix.as_deref()));
+                }
+            }
+            if path == BATCH_PATH && method == Method::Post {
+                let limits = limits_for_action("batch");
+                let json_body = parse_json_body(payload, limits.max_body_size).await?;
+                validate_limits(&json_body, &limits)?;
+                let pending = match Ctx::settings().idempotency.clone().zip(idempotency_key(&http_request, "batch")) {
+                    Some((store, key)) => match store.begin(&key, fingerprint(path, &json_body))? {
+                        Begun::Recorded(recorded) => return Ok::<HttpResponse, WrapError>(recorded.into_http_response(http_request.clone())),
+                        Begun::Pending(pending) => Some(pending),
+                    },
+                    None => None,
+                };
+                let response = batch(http_request.clone(), &json_body, main_namespace).await;
+                if let Some(pending) = pending {
+                    pending.finish(&response);
+                }
+                return Ok::<HttpResponse, WrapError>(response?.into_http_response(http_request.clone()));
+            }
+            if (path == SHARE_PATH || path.starts_with("/_share/")) && (method == Method::Get || method == Method::Post) {
+                let json_body = if method == Method::Post { parse_json_body(payload, Ctx::settings().limits.max_body_size).await? } else { JsonValue::Null };
+                return Ok::<HttpResponse, WrapError>(shared(http_request.clone(), &json_body, main_namespace).await?.into_http_response(http_request.clone()));
+            }
+            if path == STATS_PATH && (method == Method::Get || method == Method::Post) {
+                if let Some(registry) = Ctx::settings().stats.clone() {
+                    guard_admin_endpoint(&http_request).await?;
+                    let json_body = if method == Method::Post { parse_json_body(payload, Ctx::settings().limits.max_body_size).await? } else { JsonValue::Null };
+                    let data = registry.snapshot();
+                    if json_body.get("reset").and_then(|r| r.as_bool()) == Some(true) {
+                        registry.reset();
+                    }
+                    return Ok::<HttpResponse, WrapError>(HttpResponse::Ok().json(json!({
+                        "data": data
+                    })));
+                }
+            }
+            if path == META_PATH && (method == Method::Get || method == Method::Post) {
+                guard_admin_endpoint(&http_request).await?;
+                // the read permissions are checked for the identity the middlewares find
+                let handler_match = HandlerMatch { path: vec![], name: META_PATH.to_owned(), captures: Default::default() };
+                return Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), Value::Dictionary(Default::default()), main_namespace, main_namespace, handler_match, &|ctx: request::Ctx| async move {
+                    Ok(Response::data(Value::from(namespace_meta(&ctx).await?)))
+                }).await?.into_http_response(http_request.clone()));
+            }
+            if path == JSON_SCHEMA_PATH && (method == Method::Get || method == Method::Post) {
+                guard_admin_endpoint(&http_request).await?;
+                return Ok::<HttpResponse, WrapError>(HttpResponse::Ok().json(json!({
+                    "data": namespace_json_schema(main_namespace)
+                })));
+            }
             let match_result = if let Some(m_result) = main_namespace.handler_map.r#match(method, path) {
                 m_result
             } else if let Some(m_result) = main_namespace.handler_map.default_match(method, path) {
                 m_result
             } else {
+                // embedded files never shadow the routes
+                if method == Method::Get {
+                    if let Some(response) = serve_embedded(&http_request, path) {
+                        return Ok::<HttpResponse, WrapError>(response);
+                    }
+                }
                 Err(teo_runtime::path::Error::not_found_message_only())?
             };
             let mut group = false;
             let dest_namespace = if let Some(d) = main_namespace.namespace_at_path(&match_result.path()) {
                 d
-            } else if match_result.path().len() > 0 {
+            } else if !match_result.path().is_empty() {
                 if let Some(d) = main_namespace.namespace_at_path(&match_result.path_without_last()) {
                     group = true;
                     d
@@ -101,15 +285,15 @@ fn make_server_app(
                         if let Some(handler) = group.handlers.get(match_result.handler_name()) {
                             (dest_namespace, HandlerResolved::Custom(handler))
                         } else {
-                            if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
-                                (dest_namespace, HandlerResolved::Builtin(model, action))
+                            if let Some(resolved) = builtin_handler_resolved(model, match_result.handler_name()) {
+                                (dest_namespace, resolved)
                             } else {
                                 Err(teo_runtime::path::Error::not_found_message_only())?
                             }
                         }
                     } else {
-                        if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
-                            (dest_namespace, HandlerResolved::Builtin(model, action))
+                        if let Some(resolved) = builtin_handler_resolved(model, match_result.handler_name()) {
+                            (dest_namespace, resolved)
                         } else {
                             Err(teo_runtime::path::Error::not_found_message_only())?
                         }
@@ -132,108 +316,151 @@ fn make_server_app(
             };
             let dest_namespace = handler_resolved.0;
             let handler_resolved = handler_resolved.1;
+            match handler_resolved {
+                HandlerResolved::Builtin(model, action) => if !builtin_action_enabled(model, action) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Compare(model) => if !builtin_action_enabled(model, find_unique_action()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Duplicate(model) => if !builtin_action_enabled(model, duplicate_action()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::FindFirstOrCreate(model) => if !find_first_or_create_actions().into_iter().all(|action| builtin_action_enabled(model, action)) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::GroupByTime(model) => if !builtin_action_enabled(model, group_by_time_action()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::FindArchived(model) => if !builtin_action_enabled(model, find_archived_action()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Share(model) => if !builtin_action_enabled(model, share_action()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Tree(model) => if !builtin_action_enabled(model, tree_builtin_action(match_result.handler_name())) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Reorder(model) => if !builtin_action_enabled(model, builtin_action_handler_from_name("update").unwrap()) {
+                    Err(method_not_allowed(match_result.handler_name()))?
+                },
+                HandlerResolved::Custom(_) => (),
+            }
             if method == Method::Options {
                 // special handle for options
-                let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                let transaction_ctx = transaction::Ctx::new(conn_ctx);
-                let ctx = request::Ctx::new(
-                    request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                    Arc::new(Value::Null),
-                    transaction_ctx,
-                    match_result
-                );
-                return Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async {
+                return Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), Value::Null, main_namespace, dest_namespace, match_result, &|_: request::Ctx| async {
                     Ok(Response::empty())
                 }).await?.into_http_response(http_request.clone()));
             }
             http_request.extensions_mut().insert(match_result.clone());
             // parse body
             let mut format = HandlerInputFormat::Json;
-            match handler_resolved {
-                HandlerResolved::Custom(handler) => {
-                    format = handler.format;
-                }
-                _ => (),
+            if let HandlerResolved::Custom(handler) = handler_resolved {
+                format = handler.format;
             }
-            let json_body = match format {
-                HandlerInputFormat::Json => if method == Method::Get || method == Method::Delete {
+            let limits = limits_for_action(match_result.handler_name());
+            let form_model = match &handler_resolved {
+                HandlerResolved::Builtin(model, _) if is_form_request(&http_request, match_result.handler_name()) => Some(*model),
+                _ => None,
+            };
+            let mut json_body = match format {
+                HandlerInputFormat::Json => if let Some(model) = form_model {
+                    parse_builtin_form_body(http_request.clone(), payload, model, limits.max_body_size).await?
+                } else if method == Method::Get || method == Method::Delete {
                     JsonValue::Null
                 } else {
-                    parse_json_body(payload).await?
+                    parse_json_body(payload, limits.max_body_size).await?
                 },
-                HandlerInputFormat::Form => parse_form_body(http_request.clone(), payload).await?,
+                HandlerInputFormat::Form => parse_form_body(http_request.clone(), payload, limits.max_body_size).await?,
             };
-            return match handler_resolved {
+            validate_limits(&json_body, &limits)?;
+            match handler_resolved {
                 HandlerResolved::Builtin(model, action) => {
-                    let body = validate_and_transform_json_input_for_builtin_action(model, action, &json_body, main_namespace)?;
-                    let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                    let transaction_ctx = transaction::Ctx::new(conn_ctx);
-                    let ctx = request::Ctx::new(
-                        request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                        Arc::new(body),
-                        transaction_ctx,
-                        match_result.clone(),
-                    );
-                    match match_result.handler_name() {
-                        "findMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "findFirst" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_first(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "findUnique" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_unique(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "create" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            create(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "delete" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            delete(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "update" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            update(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "upsert" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            upsert(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "copy" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            copy(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "createMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            create_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "updateMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            update_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "copyMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            copy_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "deleteMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            delete_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "count" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            count(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "aggregate" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            aggregate(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "groupBy" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            group_by(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        _ => Err(teo_runtime::path::Error::not_found_message_only())?,
+                    let idempotency = Ctx::settings().idempotency.clone().zip(idempotency_key(&http_request, match_result.handler_name())).filter(|_| json_body.get("dryRun") != Some(&JsonValue::Bool(true)));
+                    let pending = match idempotency {
+                        Some((store, key)) => match store.begin(&key, fingerprint(path, &json_body))? {
+                            Begun::Recorded(recorded) => return Ok::<HttpResponse, WrapError>(recorded.into_http_response(http_request.clone())),
+                            Begun::Pending(pending) => Some(pending),
+                        },
+                        None => None,
+                    };
+                    let call = BuiltinCall {
+                        http_request: &http_request,
+                        main_namespace,
+                        dest_namespace,
+                        model,
+                        action,
+                        handler_match: match_result.clone(),
+                        transaction_ctx: transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
+                        if_match: if_match(&http_request),
+                        batched: false,
+                    };
+                    let response = call_builtin(call, json_body).await;
+                    if let Some(pending) = pending {
+                        pending.finish(&response);
                     }
+                    Ok::<HttpResponse, WrapError>(response?.into_http_response(http_request.clone()))
                 },
+                HandlerResolved::Compare(model) => {
+                    check_query_cost(model, match_result.handler_name(), &json_body, &limits)?;
+                    let body = compare_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        compare(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::Duplicate(model) => {
+                    reject_internal_only_input(model, "copy", &json_body)?;
+                    let body = duplicate_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        duplicate_record(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::FindFirstOrCreate(model) => {
+                    reject_internal_only_input(model, "create", &json_body)?;
+                    apply_coercion(model, "findFirstOrCreate", &mut json_body);
+                    normalize_filters(model, &mut json_body)?;
+                    let body = find_first_or_create_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        find_first_or_create(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::GroupByTime(model) => {
+                    check_query_cost(model, match_result.handler_name(), &json_body, &limits)?;
+                    let body = group_by_time_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        group_by_time(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::FindArchived(model) => {
+                    let body = find_archived_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        find_archived(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::Reorder(model) => {
+                    let body = reorder_input(model, &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        reorder(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
+                HandlerResolved::Share(model) => {
+                    let (mut args, expires_in) = share_input(&json_body)?;
+                    let request = teo_request(&http_request);
+                    prepare_share_args(model, &request, &mut args)?;
+                    let body = validate_and_transform_json_input_for_builtin_action(model, share_action(), &args, main_namespace)?;
+                    let grant = call_through_middlewares(&http_request, request, body, main_namespace, dest_namespace, match_result, &share_preview).await?;
+                    Ok::<HttpResponse, WrapError>(share_response(model, &args, grant.body().as_teon(), expires_in)?)
+                }
+                HandlerResolved::Tree(model) => {
+                    check_query_cost(model, match_result.handler_name(), &json_body, &limits)?;
+                    let body = tree_input(model, match_result.handler_name(), &json_body, main_namespace)?;
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, &|ctx: request::Ctx| async move {
+                        tree(&ctx).await
+                    }).await?.into_http_response(http_request.clone()))
+                }
                 HandlerResolved::Custom(handler) => {
                     let body = validate_and_transform_json_input_for_handler(handler, &json_body, main_namespace)?;
-                    let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                    let transaction_ctx = transaction::Ctx::new(conn_ctx);
-                    let ctx = request::Ctx::new(
-                        request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                        Arc::new(body),
-                        transaction_ctx,
-                        match_result
-                    );
-                    Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, handler.call).await?.into_http_response(http_request.clone()))
+                    Ok::<HttpResponse, WrapError>(call_through_middlewares(&http_request, teo_request(&http_request), body, main_namespace, dest_namespace, match_result, handler.call).await?.into_http_response(http_request.clone()))
                 }
             }
         }));
@@ -242,29 +469,39 @@ fn make_server_app(
 
 pub(crate) async fn serve(
     namespace: &'static Namespace,
-    conf: &'static Server,
-    runtime_version: &'static RuntimeVersion,
-    entrance: &'static Entrance,
+    runtime_version: &RuntimeVersion,
+    entrance: &Entrance,
     silent: bool,
 ) -> Result<()> {
+    let Some(conf) = namespace.server.as_ref() else {
+        Err(Error::new("server config is missing"))?
+    };
     let bind = conf.bind.clone();
     let port = bind.1;
-    let server = HttpServer::new(move || {
+    let http_server = HttpServer::new(move || {
         make_server_app(namespace, conf)
-    })
-        .bind((bind.0, bind.1 as u16))
-        .unwrap()
-        .run();
+    });
+    let bound = match Ctx::settings().tls.clone() {
+        Some(options) => http_server.bind_rustls_0_23((bind.0.as_str(), bind.1 as u16), tls::server_config(&options)?),
+        None => http_server.bind((bind.0.as_str(), bind.1 as u16)),
+    };
+    let server = match bound {
+        Ok(server) => server.run(),
+        Err(e) => Err(Error::new(format!("cannot bind {}:{}: {}", bind.0, bind.1, e)))?,
+    };
     let result = future::join(server, server_start_message(port as u16, runtime_version, entrance, silent)).await;
+    for plugin in Ctx::settings().plugins.clone() {
+        plugin.on_shutdown().await?;
+    }
     result.1
 }
 
-async fn server_start_message(port: u16, runtime_version: &'static RuntimeVersion, entrance: &'static Entrance, silent: bool) -> Result<()> {
+async fn server_start_message(port: u16, runtime_version: &RuntimeVersion, entrance: &Entrance, silent: bool) -> Result<()> {
     if silent { return Ok(()) }
     // Introducing
     let teo_version = env!("CARGO_PKG_VERSION");
     let teo = format!("Teo {}", teo_version);
-    info_message(format!("{} ({}, {})", teo, runtime_version.to_string(), entrance.to_str()));
+    info_message(format!("{} ({}, {})", teo, runtime_version, entrance.to_str()));
     // Listening
     let port_str = format!("{port}").bold();
     info_message(format!("listening on port {}", port_str));
@@ -283,7 +520,63 @@ fn method_from(m: &HttpMethod) -> Result<Method> {
     })
 }
 
+fn method_not_allowed(action: &str) -> teo_runtime::path::Error {
+    teo_runtime::path::Error {
+        title: "MethodNotAllowed",
+        message: format!("action `{}' is disabled", action),
+        fields: None,
+        code: 405,
+        meta_map: btreemap! {},
+    }
+}
+
+async fn call_through_middlewares(
+    http_request: &HttpRequest,
+    request: request::Request,
+    body: Value,
+    main_namespace: &'static Namespace,
+    dest_namespace: &'static Namespace,
+    match_result: HandlerMatch,
+    next: &'static dyn Next,
+) -> teo_runtime::path::Result<Response> {
+    let transaction_ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let ctx = request::Ctx::new(request, Arc::new(body), transaction_ctx, match_result);
+    keep_ctx(http_request, &ctx);
+    traced(|| format!("handler {}", ctx.handler_match().path.iter().chain(std::iter::once(&ctx.handler_match().name)).cloned().collect::<Vec<_>>().join(".")), dest_namespace.middleware_stack.call(ctx.clone(), next)).await
+}
+
+fn builtin_handler_resolved<'a>(model: &'a Model, name: &str) -> Option<HandlerResolved<'a>> {
+    if name == "compare" {
+        Some(HandlerResolved::Compare(model))
+    } else if name == "share" && Ctx::settings().share_key.is_some() {
+        Some(HandlerResolved::Share(model))
+    } else if name == "duplicate" {
+        Some(HandlerResolved::Duplicate(model))
+    } else if name == FIND_FIRST_OR_CREATE {
+        Some(HandlerResolved::FindFirstOrCreate(model))
+    } else if name == "groupByTime" {
+        Some(HandlerResolved::GroupByTime(model))
+    } else if name == FIND_ARCHIVED {
+        archive(model).map(|_| HandlerResolved::FindArchived(model))
+    } else if TREE_ACTIONS.contains(&name) {
+        parent_relation(model).map(|_| HandlerResolved::Tree(model))
+    } else if placement(name).is_some() {
+        position_field(model).map(|_| HandlerResolved::Reorder(model))
+    } else {
+        builtin_action_handler_from_name(name).map(|action| HandlerResolved::Builtin(model, action))
+    }
+}
+
+#[derive(Copy, Clone)]
 enum HandlerResolved<'a> {
     Custom(&'a Handler),
     Builtin(&'a Model, Action),
+    Compare(&'a Model),
+    FindFirstOrCreate(&'a Model),
+    Duplicate(&'a Model),
+    GroupByTime(&'a Model),
+    FindArchived(&'a Model),
+    Reorder(&'a Model),
+    Tree(&'a Model),
+    Share(&'a Model),
 }
\ No newline at end of file
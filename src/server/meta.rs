@@ -0,0 +1,222 @@
+use key_path::path;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_parser::ast::doc_comment::DocComment;
+use teo_parser::ast::handler::HandlerDeclaration;
+use teo_parser::traits::identifiable::Identifiable;
+use teo_parser::traits::named_identifiable::NamedIdentifiable;
+use teo_runtime::action::action::{ENTRY, FIND, SINGLE};
+use teo_runtime::comment::Comment;
+use teo_runtime::interface::Interface;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::pipeline;
+use teo_runtime::pipeline::pipeline::Pipeline;
+use teo_runtime::request;
+use teo_runtime::r#enum::Enum;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::enum_meta::enum_member_meta;
+use crate::server::action::builtin_action_enabled;
+
+pub(super) const META_PATH: &str = "/_meta";
+
+/// The models, fields and relations `ctx` can read, with the enums, interfaces, handlers and
+/// data sets of the schema.
+pub async fn namespace_meta(ctx: &request::Ctx) -> path::Result<JsonValue> {
+    let mut all_models = vec![];
+    let mut enums = Map::new();
+    let mut interfaces = Map::new();
+    collect(ctx.namespace(), &mut all_models, &mut enums, &mut interfaces);
+    let mut readable = vec![];
+    for model in all_models {
+        if let Some(fields) = readable_fields(ctx, model).await? {
+            readable.push((model, fields));
+        }
+    }
+    let readable_models: Vec<String> = readable.iter().map(|(model, _)| model.path.join(".")).collect();
+    let mut models = Map::new();
+    for (model, fields) in &readable {
+        models.insert(model.path.join("."), model_meta(model, fields, &readable_models));
+    }
+    // the runtime doesn't keep the doc comments of handlers and data sets, they're read from the
+    // schema
+    let schema = Ctx::schema();
+    let user_defined = |source_id: usize| schema.source(source_id).is_some_and(|s| !s.builtin);
+    let mut handler_groups = Map::new();
+    for group in schema.handler_group_declarations().into_iter().filter(|g| user_defined(g.source_id())) {
+        handler_groups.insert(group.string_path().join("."), json!({
+            "name": group.identifier().name(),
+            "localizedName": localized_name(doc_comment(group.comment()).as_ref(), group.identifier().name()),
+            "description": description(doc_comment(group.comment()).as_ref()),
+            "handlers": group.handler_declarations().map(handler_meta).collect::<Vec<JsonValue>>(),
+        }));
+    }
+    let mut handlers = Map::new();
+    for handler in schema.handler_declarations().into_iter().filter(|h| user_defined(h.source_id())) {
+        handlers.insert(handler.string_path().join("."), handler_meta(handler));
+    }
+    let mut data_sets = Map::new();
+    for data_set in schema.data_sets().into_iter().filter(|d| user_defined(d.source_id())) {
+        let comment = doc_comment(data_set.comment());
+        data_sets.insert(data_set.string_path().join("."), json!({
+            "name": data_set.identifier().name(),
+            "localizedName": localized_name(comment.as_ref(), data_set.identifier().name()),
+            "description": description(comment.as_ref()),
+        }));
+    }
+    Ok(json!({
+        "models": models,
+        "enums": enums,
+        "interfaces": interfaces,
+        "handlerGroups": handler_groups,
+        "handlers": handlers,
+        "dataSets": data_sets,
+    }))
+}
+
+fn collect(namespace: &'static Namespace, models: &mut Vec<&'static Model>, enums: &mut Map<String, JsonValue>, interfaces: &mut Map<String, JsonValue>) {
+    for model in namespace.models.values() {
+        if !model.generate_client {
+            continue
+        }
+        models.push(model);
+    }
+    for r#enum in namespace.enums.values() {
+        enums.insert(r#enum.path.join("."), enum_meta(r#enum));
+    }
+    for interface in namespace.interfaces.values() {
+        interfaces.insert(interface.path.join("."), interface_meta(interface));
+    }
+    for child in namespace.namespaces.values() {
+        if child.path == vec!["std".to_owned()] {
+            continue
+        }
+        collect(child, models, enums, interfaces);
+    }
+}
+
+// the permissions are run on a blank record, those depending on the values of a record fail
+// and hide what they guard. `None' when the model can't be read.
+async fn readable_fields(ctx: &request::Ctx, model: &'static Model) -> path::Result<Option<Vec<String>>> {
+    let object = ctx.transaction_ctx().new_object(model, FIND | SINGLE | ENTRY, Some(ctx.clone()))?;
+    if !allowed(&object, &model.can_read).await {
+        return Ok(None);
+    }
+    let mut fields = vec![];
+    for field in model.fields.values() {
+        if !field.read.is_no_read() && allowed(&object, &field.can_read).await {
+            fields.push(field.name.clone());
+        }
+    }
+    Ok(Some(fields))
+}
+
+async fn allowed(object: &Object, permission: &Pipeline) -> bool {
+    let ctx = pipeline::Ctx::new(Value::Null.into(), object.clone(), path![], object.action(), object.transaction_ctx(), object.request_ctx());
+    ctx.run_pipeline_into_path_value_error(permission).await.is_ok()
+}
+
+fn model_meta(model: &Model, readable_fields: &[String], readable_models: &[String]) -> JsonValue {
+    let mut fields = vec![];
+    for field in model.fields.values() {
+        if !readable_fields.contains(&field.name) {
+            continue
+        }
+        fields.push(json!({
+            "name": field.name,
+            "localizedName": localized_name(field.comment.as_ref(), &field.name),
+            "description": description(field.comment.as_ref()),
+            "type": format!("{}", field.r#type.unwrap_optional()),
+            "optional": field.is_optional(),
+            "readonly": field.write.is_no_write(),
+        }));
+    }
+    let mut relations = vec![];
+    for relation in model.relations.values() {
+        if !readable_models.contains(&relation.model.join(".")) {
+            continue
+        }
+        relations.push(json!({
+            "name": relation.name,
+            "localizedName": localized_name(relation.comment.as_ref(), &relation.name),
+            "description": description(relation.comment.as_ref()),
+            "model": relation.model.join("."),
+            "fields": relation.fields,
+            "references": relation.references,
+            "isVec": relation.is_vec,
+            "optional": relation.is_optional(),
+        }));
+    }
+    let handlers = match Ctx::schema().find_top_by_path(&model.parser_path).and_then(|node| node.as_model()) {
+        Some(declaration) => declaration.handlers().map(handler_meta).collect(),
+        None => vec![],
+    };
+    let name = model.path.last().cloned().unwrap_or_default();
+    json!({
+        "name": name,
+        "localizedName": localized_name(model.comment.as_ref(), &name),
+        "description": description(model.comment.as_ref()),
+        "fields": fields,
+        "relations": relations,
+        "handlers": handlers,
+        "actions": model.builtin_handlers.iter().filter(|a| builtin_action_enabled(model, **a)).map(|a| a.as_handler_str()).collect::<Vec<&str>>(),
+    })
+}
+
+fn enum_meta(r#enum: &Enum) -> JsonValue {
+    let name = r#enum.path.last().cloned().unwrap_or_default();
+    json!({
+        "name": name,
+        "localizedName": localized_name(r#enum.comment.as_ref(), &name),
+        "description": description(r#enum.comment.as_ref()),
+        "members": r#enum.members.iter().map(|member| json!({
+            "name": member.name,
+            "localizedName": localized_name(member.comment.as_ref(), &member.name),
+            "description": description(member.comment.as_ref()),
+            "meta": enum_member_meta(member).and_then(|meta| JsonValue::try_from(&Value::Dictionary(meta.clone())).ok()),
+        })).collect::<Vec<JsonValue>>(),
+    })
+}
+
+fn interface_meta(interface: &Interface) -> JsonValue {
+    let name = interface.path.last().cloned().unwrap_or_default();
+    json!({
+        "name": name,
+        "localizedName": localized_name(interface.comment.as_ref(), &name),
+        "description": description(interface.comment.as_ref()),
+        "fields": interface.fields.values().map(|field| json!({
+            "name": field.name,
+            "localizedName": localized_name(field.comment.as_ref(), &field.name),
+            "description": description(field.comment.as_ref()),
+            "type": format!("{}", field.r#type.unwrap_optional()),
+            "optional": field.is_optional(),
+        })).collect::<Vec<JsonValue>>(),
+    })
+}
+
+fn handler_meta(handler: &HandlerDeclaration) -> JsonValue {
+    let comment = doc_comment(handler.comment());
+    json!({
+        "name": handler.identifier().name(),
+        "localizedName": localized_name(comment.as_ref(), handler.identifier().name()),
+        "description": description(comment.as_ref()),
+    })
+}
+
+fn doc_comment(comment: Option<&DocComment>) -> Option<Comment> {
+    comment.map(|comment| Comment {
+        name: comment.name().map(ToOwned::to_owned),
+        desc: comment.desc().map(ToOwned::to_owned),
+    })
+}
+
+// the parser keeps the space after `@name'
+pub(super) fn localized_name(comment: Option<&Comment>, name: &str) -> String {
+    comment.and_then(|c| c.name.as_deref()).map(str::trim).unwrap_or(name).to_owned()
+}
+
+pub(super) fn description(comment: Option<&Comment>) -> Option<String> {
+    comment.and_then(|c| c.desc.clone())
+}
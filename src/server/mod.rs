@@ -3,4 +3,42 @@ pub mod parse;
 pub mod request;
 pub mod responder;
 pub mod error;
+pub mod limits;
+pub mod action;
+pub mod admin;
+pub mod meta;
+pub mod json_schema;
+pub mod compare;
+pub mod cost;
+pub mod group_by;
+pub mod find_or_create;
+pub mod filters;
+pub mod to_one;
+pub mod duplicate;
+pub mod group_by_time;
+pub mod batch;
+pub mod idempotency;
+pub mod etag;
+pub mod shaping;
+pub mod reorder;
+pub mod tree;
+pub mod stats;
+pub mod share;
+pub mod pagination;
+pub mod record;
+pub mod report;
 pub mod static_files;
+pub mod compression;
+pub mod msgpack;
+pub mod last_modified;
+pub mod embedded;
+pub mod form;
+pub mod coerce;
+pub mod dry_run;
+pub mod builtin;
+pub mod mutation;
+pub mod plan;
+pub mod through;
+pub mod archive;
+pub mod tls;
+pub mod statements;
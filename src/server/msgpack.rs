@@ -0,0 +1,115 @@
+use actix_web::HttpRequest;
+use actix_web::http::header::ACCEPT;
+use chrono::SecondsFormat;
+use teo_teon::value::Value;
+
+pub(crate) const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+const ACCEPTED_CONTENT_TYPES: [&str; 2] = [MSGPACK_CONTENT_TYPE, "application/x-msgpack"];
+
+/// Whether the client asked for MessagePack bodies with `Accept`.
+pub(crate) fn accepts_msgpack(http_request: &HttpRequest) -> bool {
+    let Some(accept) = http_request.headers().get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    accept.split(',').map(|t| t.split(';').next().unwrap_or("").trim()).any(|t| ACCEPTED_CONTENT_TYPES.contains(&t))
+}
+
+/// Encodes a value as MessagePack, in the same shape as its JSON body.
+pub(crate) fn to_msgpack(value: &Value) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    write_value(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Null => bytes.push(0xc0),
+        Value::ObjectId(o) => write_str(bytes, &o.to_hex()),
+        Value::Bool(b) => bytes.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Int(i) => write_int(bytes, *i as i64),
+        Value::Int64(i) => write_int(bytes, *i),
+        Value::Float32(f) => write_float(bytes, *f as f64),
+        Value::Float(f) => write_float(bytes, *f),
+        Value::Decimal(d) => write_tagged(bytes, "$decimal", &d.normalized().to_string()),
+        Value::String(s) => write_str(bytes, s),
+        Value::Date(d) => write_tagged(bytes, "$date", &d.format("%Y-%m-%d").to_string()),
+        Value::DateTime(d) => write_tagged(bytes, "$datetime", &d.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        Value::Array(a) => {
+            write_header(bytes, a.len(), 0x90, 0xdc)?;
+            for item in a {
+                write_value(bytes, item)?;
+            }
+        },
+        Value::Dictionary(d) => {
+            write_header(bytes, d.len(), 0x80, 0xde)?;
+            for (key, item) in d {
+                write_str(bytes, key);
+                write_value(bytes, item)?;
+            }
+        },
+        _ => Err(format!("Cannot convert {} into msgpack", value.type_hint()))?,
+    }
+    Ok(())
+}
+
+fn write_int(bytes: &mut Vec<u8>, i: i64) {
+    if (-32..128).contains(&i) {
+        bytes.push(i as i8 as u8);
+    } else if let Ok(i) = i8::try_from(i) {
+        bytes.push(0xd0);
+        bytes.extend(i.to_be_bytes());
+    } else if let Ok(i) = i16::try_from(i) {
+        bytes.push(0xd1);
+        bytes.extend(i.to_be_bytes());
+    } else if let Ok(i) = i32::try_from(i) {
+        bytes.push(0xd2);
+        bytes.extend(i.to_be_bytes());
+    } else {
+        bytes.push(0xd3);
+        bytes.extend(i.to_be_bytes());
+    }
+}
+
+fn write_float(bytes: &mut Vec<u8>, f: f64) {
+    bytes.push(0xcb);
+    bytes.extend(f.to_be_bytes());
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    let len = s.len();
+    if len < 32 {
+        bytes.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        bytes.push(0xd9);
+        bytes.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        bytes.push(0xda);
+        bytes.extend((len as u16).to_be_bytes());
+    } else {
+        bytes.push(0xdb);
+        bytes.extend((len as u32).to_be_bytes());
+    }
+    bytes.extend(s.as_bytes());
+}
+
+// arrays and maps share their layout, a fix marker for up to 15 items then 16 and 32 bit lengths
+fn write_header(bytes: &mut Vec<u8>, len: usize, fix: u8, long: u8) -> Result<(), String> {
+    if len < 16 {
+        bytes.push(fix | len as u8);
+    } else if len <= u16::MAX as usize {
+        bytes.push(long);
+        bytes.extend((len as u16).to_be_bytes());
+    } else if len <= u32::MAX as usize {
+        bytes.push(long + 1);
+        bytes.extend((len as u32).to_be_bytes());
+    } else {
+        Err(format!("Cannot encode {} items into msgpack", len))?
+    }
+    Ok(())
+}
+
+fn write_tagged(bytes: &mut Vec<u8>, tag: &str, value: &str) {
+    bytes.push(0x81);
+    write_str(bytes, tag);
+    write_str(bytes, value);
+}
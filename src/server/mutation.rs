@@ -0,0 +1,151 @@
+use key_path::{KeyPath, path};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{COPY, CREATE, DELETE, ENTRY, MANY, SINGLE, UPDATE, UPSERT};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::Object;
+use teo_runtime::model::object::object::ErrorIfNotFound;
+use teo_runtime::object::error_ext;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::server::action::handler_model;
+use crate::server::etag::check_if_match;
+use crate::server::through::{write_through_on_create, write_through_on_update};
+
+// the default handlers commit the transactions they open, the mutations below run in the
+// transaction they're given, the transaction of a batch for its items, and the batch commits or
+// rolls back every item together. The entry writes check `If-Match' on the record they load and run
+// the writes through join models, in the same transaction.
+
+const JOINED_TRANSACTION_KEY: &str = "teo.joinedTransaction";
+
+/// Makes the mutations of `ctx` run in its transaction ctx instead of their own transactions.
+pub(super) fn join_transaction(ctx: &request::Ctx) {
+    ctx.data_mut().insert(JOINED_TRANSACTION_KEY, true);
+}
+
+pub(super) fn joined_transaction(ctx: &request::Ctx) -> bool {
+    ctx.data().contains::<bool>(JOINED_TRANSACTION_KEY)
+}
+
+pub(super) async fn create(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let value = create_object(&ctx, req_ctx, req_ctx.body().get("create"), CREATE | SINGLE | ENTRY, &path!["create"]).await?;
+    Ok(Response::data(value))
+}
+
+pub(super) async fn create_many(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let mut values = vec![];
+    for (index, create) in req_ctx.body().get("create").and_then(|c| c.as_array()).into_iter().flatten().enumerate() {
+        values.push(create_object(&ctx, req_ctx, Some(create), CREATE | MANY | ENTRY, &path!["create", index]).await?);
+    }
+    let count = values.len() as i64;
+    Ok(Response::data_meta(Value::Array(values), teon!({"count": count})))
+}
+
+pub(super) async fn update(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(req_ctx)?;
+    let action = UPDATE | SINGLE | ENTRY;
+    let object = ctx.find_unique_internal(model, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await.into_not_found_error(path![])?;
+    check_if_match(req_ctx, &object).await?;
+    write_through_on_update(req_ctx, model, std::slice::from_ref(&object)).await?;
+    let value = update_object(req_ctx, &object, &path!["update"]).await?;
+    Ok(Response::data(value))
+}
+
+pub(super) async fn update_many(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(req_ctx)?;
+    let action = UPDATE | MANY | ENTRY;
+    let objects = ctx.find_many_internal(model, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await?;
+    write_through_on_update(req_ctx, model, &objects).await?;
+    let mut values = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        values.push(update_object(req_ctx, object, &path!["update", index]).await?);
+    }
+    let count = values.len() as i64;
+    Ok(Response::data_meta(Value::Array(values), teon!({"count": count})))
+}
+
+pub(super) async fn upsert(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let action = UPSERT | SINGLE | ENTRY;
+    let value = match ctx.find_unique_internal(handler_model(req_ctx)?, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await? {
+        Some(object) => update_object(req_ctx, &object, &path!["update"]).await?,
+        None => create_object(&ctx, req_ctx, req_ctx.body().get("create"), action, &path!["create"]).await?,
+    };
+    Ok(Response::data(value))
+}
+
+pub(super) async fn delete(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let action = DELETE | SINGLE | ENTRY;
+    let object = ctx.find_unique_internal(handler_model(req_ctx)?, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await.into_not_found_error(path![])?;
+    check_if_match(req_ctx, &object).await?;
+    object.delete_internal(path!["delete"]).await?;
+    Ok(Response::data(object.to_teon_internal(&path!["data"]).await?))
+}
+
+pub(super) async fn delete_many(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let action = DELETE | MANY | ENTRY;
+    let objects = ctx.find_many_internal(handler_model(req_ctx)?, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await?;
+    let mut values = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        object.delete_internal(path!["data", index]).await?;
+        values.push(object.to_teon_internal(&path!["data", index]).await?);
+    }
+    let count = values.len() as i64;
+    Ok(Response::data_meta(Value::Array(values), teon!({"count": count})))
+}
+
+pub(super) async fn copy(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let action = COPY | SINGLE | ENTRY;
+    let object = ctx.find_unique_internal(handler_model(req_ctx)?, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await.into_not_found_error(path![])?;
+    let value = copy_object(&ctx, req_ctx, &object, action, &path!["data"]).await?;
+    Ok(Response::data(value))
+}
+
+pub(super) async fn copy_many(ctx: transaction::Ctx, req_ctx: &request::Ctx) -> path::Result<Response> {
+    let action = COPY | MANY | ENTRY;
+    let objects = ctx.find_many_internal(handler_model(req_ctx)?, req_ctx.body(), true, action, Some(req_ctx.clone()), path![]).await?;
+    let mut values = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        values.push(copy_object(&ctx, req_ctx, object, action, &path!["data", index]).await?);
+    }
+    let count = values.len() as i64;
+    Ok(Response::data_meta(Value::Array(values), teon!({"count": count})))
+}
+
+async fn create_object(ctx: &transaction::Ctx, req_ctx: &request::Ctx, create: Option<&Value>, action: Action, path: &KeyPath) -> path::Result<Value> {
+    let object = ctx.new_object(handler_model(req_ctx)?, action, Some(req_ctx.clone()))?;
+    let empty = teon!({});
+    let create = match create {
+        Some(create) if !create.is_dictionary() => Err(error_ext::unexpected_input_value_with_reason(path.clone(), "expect object"))?,
+        Some(create) => create,
+        None => &empty,
+    };
+    object.set_teon_with_path(create, path).await?;
+    object.save_with_session_and_path(path).await?;
+    write_through_on_create(&object, path).await?;
+    output(req_ctx, &object, &path!["data"]).await
+}
+
+async fn update_object(req_ctx: &request::Ctx, object: &Object, path: &KeyPath) -> path::Result<Value> {
+    let update = req_ctx.body().get("update").cloned().unwrap_or(teon!({}));
+    object.set_teon_with_path(&update, path).await?;
+    object.save_with_session_and_path(path).await?;
+    output(req_ctx, object, &path!["data"]).await
+}
+
+async fn copy_object(ctx: &transaction::Ctx, req_ctx: &request::Ctx, object: &Object, action: Action, path: &KeyPath) -> path::Result<Value> {
+    let new = ctx.new_object_with_teon_and_path(object.model(), &teon!({}), &path![], action, Some(req_ctx.clone())).await?;
+    new.update_teon(&object.copied_value()).await?;
+    if let Some(copy) = req_ctx.body().get("copy") {
+        new.set_teon_with_path(copy, &path!["copy"]).await?;
+    }
+    new.save_with_session_and_path(&path!["copy"]).await?;
+    output(req_ctx, &new, path).await
+}
+
+async fn output(req_ctx: &request::Ctx, object: &Object, path: &KeyPath) -> path::Result<Value> {
+    let refreshed = object.refreshed(req_ctx.body().get("include"), req_ctx.body().get("select")).await?;
+    refreshed.to_teon_internal(path).await
+}
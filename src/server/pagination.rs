@@ -0,0 +1,30 @@
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+
+// completes the page meta of a paginated findMany, so clients don't need another count request
+pub(crate) fn apply_page_meta(action: &str, body: &Value, response: Response) -> Response {
+    if action != "findMany" {
+        return response;
+    }
+    let Some(page_size) = body.get("pageSize").and_then(|v| v.to_int64()) else {
+        return response;
+    };
+    let page_number = body.get("pageNumber").and_then(|v| v.to_int64()).unwrap_or(1);
+    let Some(Value::Dictionary(mut result)) = response.body().as_teon().cloned() else {
+        return response;
+    };
+    let Some(Value::Dictionary(meta)) = result.get_mut("meta") else {
+        return response;
+    };
+    let number_of_pages = meta.get("numberOfPages").and_then(|v| v.to_int64()).unwrap_or(0);
+    meta.insert("pageSize".to_owned(), Value::Int64(page_size));
+    meta.insert("pageNumber".to_owned(), Value::Int64(page_number));
+    meta.insert("hasNext".to_owned(), Value::Bool(page_number < number_of_pages));
+    meta.insert("hasPrevious".to_owned(), Value::Bool(page_number > 1));
+    let paged = Response::teon(Value::Dictionary(result));
+    paged.set_code(response.code());
+    for key in response.headers().keys() {
+        paged.headers().set(key.as_str(), response.headers().get(&key).unwrap());
+    }
+    paged
+}
@@ -5,14 +5,15 @@ use futures_util::{StreamExt, TryStreamExt};
 use regex::Regex;
 use serde_json::{json, Value as JsonValue};
 use teo_runtime::path::{Result, Error};
+use crate::server::limits::payload_too_large;
 
-pub(super) async fn parse_json_body(mut payload: web::Payload) -> teo_runtime::path::Result<JsonValue> {
+pub(super) async fn parse_json_body(mut payload: web::Payload, max_body_size: usize) -> teo_runtime::path::Result<JsonValue> {
     let mut body = web::BytesMut::new();
     while let Some(chunk) = payload.next().await {
-        let chunk = chunk.unwrap();
+        let chunk = chunk.map_err(|_| Error::value_error_message_only("incorrect request body"))?;
         // limit max size of in-memory payload
-        if (body.len() + chunk.len()) > 262_144usize {
-            return Err(Error::internal_server_error_message_only("memory overflow"));
+        if (body.len() + chunk.len()) > max_body_size {
+            return Err(payload_too_large(max_body_size));
         }
         body.extend_from_slice(&chunk);
     }
@@ -29,25 +30,35 @@ pub(super) async fn parse_json_body(mut payload: web::Payload) -> teo_runtime::p
     Ok(parsed_json_body)
 }
 
-pub(super) async fn parse_form_body(http_request: HttpRequest, mut payload: web::Payload) -> Result<JsonValue> {
+// files count against `max_body_size' too, a file which exceeds it is removed again
+pub(super) async fn parse_form_body(http_request: HttpRequest, payload: web::Payload, max_body_size: usize) -> Result<JsonValue> {
     let mut inner_payload = payload.into_inner();
     let multipart_result = Multipart::from_request(&http_request, &mut inner_payload).await;
     let mut multipart = match multipart_result {
         Ok(multipart) => multipart,
-        Err(err) => return Err(Error::value_error_message_only("incorrect form format")),
+        Err(_) => return Err(Error::value_error_message_only("incorrect form format")),
     };
     let mut result_value = json!({});
-    while let Some(mut field) = multipart.try_next().await.unwrap() {
+    let mut size = 0;
+    let regex = Regex::new("(.*)\\[(.*)\\]").unwrap();
+    while let Some(mut field) = multipart.try_next().await.map_err(incorrect_form_format)? {
         // A multipart/form-data stream has to contain `content_disposition`
         if let Some(filename) = field.content_disposition().get_filename().map(|f| f.to_owned()) {
             let filepath = std::env::temp_dir().join(filename.clone()).to_str().unwrap().to_owned();
             let filepath2 = filepath.clone();
             // File::create is blocking operation, use threadpool
-            let mut f = web::block(move || std::fs::File::create(&filepath)).await.unwrap().unwrap();
+            let mut f = web::block(move || std::fs::File::create(&filepath)).await.map_err(upload_failed)?.map_err(upload_failed)?;
             // Field in turn is stream of *Bytes* object
-            while let Some(chunk) = field.try_next().await.unwrap() {
+            while let Some(chunk) = field.try_next().await.map_err(incorrect_form_format)? {
+                size += chunk.len();
+                if size > max_body_size {
+                    drop(f);
+                    let filepath = filepath2.clone();
+                    let _ = web::block(move || std::fs::remove_file(filepath)).await;
+                    return Err(payload_too_large(max_body_size));
+                }
                 // filesystem operations are blocking, we have to use threadpool
-                f = web::block(move || f.write_all(&chunk).map(|_| f)).await.unwrap().unwrap();
+                f = web::block(move || f.write_all(&chunk).map(|_| f)).await.map_err(upload_failed)?.map_err(upload_failed)?;
             }
             let owned_field_name = field.name().to_owned();
             if owned_field_name.ends_with("[]") {
@@ -62,7 +73,6 @@ pub(super) async fn parse_form_body(http_request: HttpRequest, mut payload: web:
                     "filenameExt": field.content_disposition().get_filename_ext().map(|e| e.to_string()),
                 }));
             } else if owned_field_name.ends_with("]") {
-                let regex = Regex::new("(.*)\\[(.*)\\]").unwrap();
                 let found = regex.captures(&owned_field_name).unwrap();
                 let field_name = found.get(1).unwrap().as_str().to_owned();
                 let dict_name = found.get(2).unwrap().as_str().to_owned();
@@ -85,11 +95,23 @@ pub(super) async fn parse_form_body(http_request: HttpRequest, mut payload: web:
             }
         } else {
             let mut body = web::BytesMut::new();
-            while let Some(chunk) = field.try_next().await.unwrap() {
+            while let Some(chunk) = field.try_next().await.map_err(incorrect_form_format)? {
+                size += chunk.len();
+                if size > max_body_size {
+                    return Err(payload_too_large(max_body_size));
+                }
                 body.extend_from_slice(&chunk);
             }
-            result_value.as_object_mut().unwrap().insert(field.name().to_owned(), serde_json::Value::String(String::from_utf8(body.as_ref().to_vec()).unwrap()));
+            result_value.as_object_mut().unwrap().insert(field.name().to_owned(), serde_json::Value::String(String::from_utf8(body.as_ref().to_vec()).map_err(incorrect_form_format)?));
         }
     }
     Ok(result_value)
 }
+
+fn incorrect_form_format<E>(_: E) -> Error {
+    Error::value_error_message_only("incorrect form format")
+}
+
+fn upload_failed<E: std::fmt::Display>(e: E) -> Error {
+    Error::internal_server_error_message_only(format!("cannot save uploaded file: {}", e))
+}
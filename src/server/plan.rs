@@ -0,0 +1,66 @@
+use serde_json::{Map, Value as JsonValue};
+use teo_runtime::model::Model;
+use crate::app::ctx::Ctx;
+
+// the runtime writes the records a relation points to before the record and the records pointing
+// to it afterwards, but runs the operations on one relation in the order of the input keys, so
+// these are ordered to detach and delete before anything is attached or created
+const TO_MANY_ORDER: [&str; 11] = ["disconnect", "set", "delete", "deleteMany", "update", "updateMany", "upsert", "connect", "connectOrCreate", "create", "createMany"];
+const TO_ONE_ORDER: [&str; 8] = ["disconnect", "delete", "update", "upsert", "set", "connect", "connectOrCreate", "create"];
+
+/// Orders the nested writes of a create, update or upsert at every level.
+pub(crate) fn plan_nested_writes(model: &Model, action: &str, json_body: &mut JsonValue) {
+    let Some(body) = json_body.as_object_mut() else { return };
+    for key in ["create", "update", "copy"] {
+        let Some(data) = body.get_mut(key) else { continue };
+        match (action, data) {
+            ("createMany", JsonValue::Array(items)) => items.iter_mut().for_each(|item| plan_data(model, item)),
+            (_, data) => plan_data(model, data),
+        }
+    }
+}
+
+fn plan_data(model: &Model, data: &mut JsonValue) {
+    let Some(map) = data.as_object_mut() else { return };
+    for (key, value) in map.iter_mut() {
+        let Some(relation) = model.relation(key) else { continue };
+        let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else { continue };
+        let Some(operations) = value.as_object_mut() else { continue };
+        *operations = ordered(std::mem::take(operations), if relation.is_vec { &TO_MANY_ORDER } else { &TO_ONE_ORDER });
+        for (operation, argument) in operations.iter_mut() {
+            let items = match argument {
+                JsonValue::Array(items) => items.iter_mut().collect(),
+                argument => vec![argument],
+            };
+            for item in items {
+                match (operation.as_str(), relation.is_vec) {
+                    ("create" | "createMany", _) | ("update", false) => plan_data(related, item),
+                    ("connectOrCreate", _) => plan_nested(related, item, &["create"]),
+                    ("update" | "updateMany", true) => plan_nested(related, item, &["update"]),
+                    ("upsert", _) => plan_nested(related, item, &["create", "update"]),
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn plan_nested(model: &Model, item: &mut JsonValue, keys: &[&str]) {
+    for key in keys {
+        if let Some(data) = item.get_mut(*key) {
+            plan_data(model, data);
+        }
+    }
+}
+
+// unknown operations keep their place after the known ones, input validation reports them
+fn ordered(mut operations: Map<String, JsonValue>, order: &[&str]) -> Map<String, JsonValue> {
+    let mut result = Map::new();
+    for key in order {
+        if let Some(value) = operations.remove(*key) {
+            result.insert((*key).to_owned(), value);
+        }
+    }
+    result.extend(operations);
+    result
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use actix_http::HttpMessage;
+use actix_web::body::{BoxBody, to_bytes};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::HttpServer;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::server::error::REQUEST_ID_HEADER;
+use crate::server::make::make_server_app;
+
+const CREDENTIAL_HEADERS: [&str; 5] = ["authorization", "proxy-authorization", "cookie", "x-api-key", "x-auth-token"];
+const REDACTED: &str = "[redacted]";
+// the replaying client sets these itself
+const SET_BY_CLIENT: [&str; 2] = ["host", "content-length"];
+
+// bodies are base64 encoded, so binary uploads and downloads are replayed byte for byte
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    time: DateTime<Utc>,
+    method: String,
+    uri: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+    status: u16,
+    response: String,
+}
+
+/// Appends every request and its response to a JSON lines file until the window passes.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    file: Mutex<File>,
+    until: Option<SystemTime>,
+    credentials: bool,
+}
+
+impl Recorder {
+
+    pub(crate) fn new(path: &str, window: Option<Duration>, credentials: bool) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            until: window.map(|w| SystemTime::now() + w),
+            credentials,
+        })
+    }
+
+    pub(crate) fn recorded_headers(&self, req: &ServiceRequest) -> BTreeMap<String, String> {
+        req.headers().iter()
+            .filter(|(name, _)| !name.as_str().eq_ignore_ascii_case(REQUEST_ID_HEADER))
+            .filter_map(|(name, value)| value.to_str().ok().map(|v| {
+                let redacted = !self.credentials && CREDENTIAL_HEADERS.contains(&name.as_str());
+                (name.as_str().to_owned(), if redacted { REDACTED.to_owned() } else { v.to_owned() })
+            }))
+            .collect()
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.until.is_none_or(|until| SystemTime::now() < until)
+    }
+
+    fn write(&self, recording: &Recording) {
+        let Ok(line) = serde_json::to_string(recording) else { return };
+        let _ = writeln!(self.file.lock().unwrap(), "{}", line);
+    }
+}
+
+// the body is copied while the handler reads it, so the handler sees the original stream
+pub(crate) fn tee_payload(req: &mut ServiceRequest) -> Arc<Mutex<Vec<u8>>> {
+    let captured = Arc::new(Mutex::new(vec![]));
+    let sink = captured.clone();
+    let payload = req.take_payload().inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            sink.lock().unwrap().extend_from_slice(chunk);
+        }
+    });
+    req.set_payload(Payload::from(payload.boxed_local()));
+    captured
+}
+
+pub(crate) async fn record(
+    recorder: &Recorder,
+    method: String,
+    uri: String,
+    headers: BTreeMap<String, String>,
+    body: Arc<Mutex<Vec<u8>>>,
+    res: ServiceResponse<BoxBody>,
+) -> ServiceResponse<BoxBody> {
+    let (http_request, res) = res.into_parts();
+    let status = res.status().as_u16();
+    let (res, response_body) = res.into_parts();
+    let bytes = to_bytes(response_body).await.unwrap_or_default();
+    recorder.write(&Recording {
+        time: Utc::now(),
+        method,
+        uri,
+        headers,
+        body: STANDARD.encode(&*body.lock().unwrap()),
+        status,
+        response: STANDARD.encode(&bytes),
+    });
+    ServiceResponse::new(http_request, res.set_body(BoxBody::new(bytes)))
+}
+
+/// Replays the recorded requests against the current app and reports differing responses.
+pub(crate) async fn replay(path: &str) -> Result<()> {
+    let namespace = Ctx::conn_ctx().namespace();
+    let Some(conf) = namespace.server.as_ref() else {
+        Err(Error::new("replay requires a server config"))?
+    };
+    let server = HttpServer::new(move || make_server_app(namespace, conf)).workers(1).bind(("127.0.0.1", 0))
+        .map_err(|e| Error::new(format!("cannot bind a port for replay: {}", e)))?;
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    tokio::spawn(server);
+    let result = replay_to(path, &format!("http://{}", addr)).await;
+    handle.stop(true).await;
+    result
+}
+
+async fn replay_to(path: &str, origin: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let mut total = 0;
+    let mut changed = 0;
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recording: Recording = match serde_json::from_str(&line) {
+            Ok(recording) => recording,
+            Err(e) => Err(Error::new(format!("invalid recording at line {}: {}", index + 1, e)))?,
+        };
+        let (Ok(body), Ok(recorded_response)) = (STANDARD.decode(&recording.body), STANDARD.decode(&recording.response)) else {
+            Err(Error::new(format!("invalid body encoding at line {}", index + 1)))?
+        };
+        let method = reqwest::Method::from_bytes(recording.method.as_bytes()).map_err(|_| Error::new(format!("invalid method at line {}", index + 1)))?;
+        let mut request = client.request(method, format!("{}{}", origin, recording.uri)).body(body);
+        // redacted credentials are left out, the request is replayed unauthenticated
+        for (name, value) in recording.headers.iter().filter(|(n, v)| *v != REDACTED && !SET_BY_CLIENT.contains(&n.as_str())) {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request.send().await.map_err(|e| Error::new(format!("cannot replay line {}: {}", index + 1, e)))?;
+        let status = response.status().as_u16();
+        let bytes = response.bytes().await.map_err(|e| Error::new(format!("cannot replay line {}: {}", index + 1, e)))?;
+        total += 1;
+        if status == recording.status && same_response(&recorded_response, &bytes) {
+            continue;
+        }
+        changed += 1;
+        info_message(format!("{} {} {}: {} => {}", "changed".red(), recording.method, recording.uri, recording.status, status));
+        info_message(format!("  {} {}", "-".red(), String::from_utf8_lossy(&recorded_response)));
+        info_message(format!("  {} {}", "+".green(), String::from_utf8_lossy(&bytes)));
+    }
+    info_message(format!("{} request(s) replayed, {} changed", total, changed));
+    if changed > 0 {
+        Err(Error::new(format!("{} response(s) changed", changed)))
+    } else {
+        Ok(())
+    }
+}
+
+// request ids differ between runs
+fn same_response(recorded: &[u8], replayed: &[u8]) -> bool {
+    match (serde_json::from_slice::<JsonValue>(recorded), serde_json::from_slice::<JsonValue>(replayed)) {
+        (Ok(mut recorded), Ok(mut replayed)) => {
+            for value in [&mut recorded, &mut replayed] {
+                if let Some(error) = value.get_mut("error").and_then(|e| e.as_object_mut()) {
+                    error.remove("requestId");
+                }
+            }
+            recorded == replayed
+        }
+        _ => recorded == replayed,
+    }
+}
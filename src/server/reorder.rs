@@ -0,0 +1,35 @@
+use key_path::path;
+use serde_json::Value as JsonValue;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::position::{move_object, Placement};
+use crate::server::compare::where_input;
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+pub(super) fn placement(name: &str) -> Option<Placement> {
+    match name {
+        "moveBefore" => Some(Placement::Before),
+        "moveAfter" => Some(Placement::After),
+        _ => None,
+    }
+}
+
+pub(super) fn reorder_input(model: &Model, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    Ok(teon!({
+        "where": where_input(model, json_body.get("where"), "where", main_namespace)?,
+        "target": where_input(model, json_body.get("target"), "target", main_namespace)?,
+    }))
+}
+
+pub(super) async fn reorder(ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    let placement = placement(ctx.handler_match().handler_name()).unwrap();
+    let object = move_object(ctx, model, ctx.body().get("where").unwrap(), ctx.body().get("target").unwrap(), placement).await?;
+    Ok(Response::data(object.to_teon_internal(&path!["data"]).await?))
+}
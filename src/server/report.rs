@@ -0,0 +1,85 @@
+use std::any::Any;
+use std::future::Future;
+use actix_http::HttpMessage;
+use actix_web::dev::ServiceResponse;
+use actix_web::{HttpRequest, ResponseError};
+use futures_util::future::BoxFuture;
+use teo_runtime::handler::r#match::HandlerMatch;
+use teo_runtime::model::Object;
+use teo_runtime::request;
+use crate::app::ctx::Ctx;
+use crate::pipeline::identity::identity;
+use crate::server::error::WrapError;
+
+/// What an error reporter receives for a request which failed with a server error. The request
+/// body is never included.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub request_id: String,
+    pub method: String,
+    pub path: String,
+    pub handler: Option<String>,
+    pub status: u16,
+    pub title: &'static str,
+    pub message: String,
+    /// The identity the request was made by, when a middleware stored it as `identity`.
+    pub identity: Option<Object>,
+}
+
+/// Receives the report and the ctx of the failed request, the ctx is `None` when the request
+/// failed before a handler was matched. Reporters run in the background after the response is
+/// sent.
+pub trait ErrorReporter: Send + Sync {
+    fn report(&self, report: ErrorReport, ctx: Option<request::Ctx>) -> BoxFuture<'static, ()>;
+}
+
+impl<F, Fut> ErrorReporter for F where
+    F: Fn(ErrorReport, Option<request::Ctx>) -> Fut + Send + Sync,
+    Fut: Future<Output = ()> + Send + 'static {
+    fn report(&self, report: ErrorReport, ctx: Option<request::Ctx>) -> BoxFuture<'static, ()> {
+        Box::pin(self(report, ctx))
+    }
+}
+
+/// Keeps the ctx of the request, so the reporters of its errors receive it.
+pub(crate) fn keep_ctx(http_request: &HttpRequest, ctx: &request::Ctx) {
+    http_request.extensions_mut().insert(ctx.clone());
+}
+
+pub(crate) fn report_error<B>(error: &WrapError, request_id: &str, res: &ServiceResponse<B>) {
+    let status = error.status_code();
+    if !status.is_server_error() || Ctx::settings().error_reporters.is_empty() {
+        return;
+    }
+    let (title, message) = match error {
+        WrapError::PathError(e) => (e.title, e.message.clone()),
+        WrapError::ResultError(e) => ("InternalServerError", e.message().to_owned()),
+    };
+    let request = res.request();
+    let ctx = request.extensions().get::<request::Ctx>().cloned();
+    let report = ErrorReport {
+        request_id: request_id.to_owned(),
+        method: request.method().to_string(),
+        path: request.path().to_owned(),
+        handler: request.extensions().get::<HandlerMatch>().map(|m| format!("{}.{}", m.path.join("."), m.name)),
+        status: status.as_u16(),
+        title,
+        message,
+        identity: ctx.as_ref().and_then(identity),
+    };
+    for reporter in Ctx::settings().error_reporters.clone() {
+        tokio::spawn(reporter.report(report.clone(), ctx.clone()));
+    }
+}
+
+/// The error a handler panic is answered with.
+pub(crate) fn panic_error(panic: Box<dyn Any + Send>) -> WrapError {
+    let message = match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "handler panicked".to_owned(),
+        },
+    };
+    WrapError::ResultError(teo_result::Error::new(message))
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 use actix_http::header::HeaderMap as HTTPHeaderMap;
 use actix_http::HttpMessage;
 use actix_web::HttpRequest;
+use teo_runtime::request;
 use teo_runtime::request::header::readonly::HeaderMap;
 use teo_runtime::request::request::r#trait;
 
@@ -47,6 +48,13 @@ impl RequestImpl {
     }
 }
 
+/// The teo request of an actix request.
+// an `HttpRequest' is neither `Send' nor `Sync', it stays on the worker which received it
+#[allow(clippy::arc_with_non_send_sync)]
+pub(crate) fn teo_request(http_request: &HttpRequest) -> request::Request {
+    request::Request::new(Arc::new(RequestImpl::new(http_request.clone())))
+}
+
 impl r#trait::Request for RequestImpl {
 
     fn method(&self) -> &str {
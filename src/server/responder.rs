@@ -1,8 +1,16 @@
 use actix_web::{HttpRequest, HttpResponse};
 use actix_web::http::StatusCode;
+use actix_web::http::header::{HeaderName, HeaderValue, VARY};
+use chrono::SecondsFormat;
+use serde::{Serialize, Serializer};
+use serde::ser::{Error, SerializeMap};
 use teo_runtime::response::body::BodyInner;
+use teo_runtime::path;
 use teo_runtime::response::Response;
+use teo_teon::value::Value;
 use actix_files::NamedFile;
+use crate::server::error::WrapError;
+use crate::server::msgpack::{accepts_msgpack, MSGPACK_CONTENT_TYPE, to_msgpack};
 
 pub trait IntoHttpResponse {
     fn into_http_response(self, http_request: HttpRequest) -> HttpResponse;
@@ -19,14 +27,74 @@ impl IntoHttpResponse for Response {
         match self.body().inner.as_ref() {
             BodyInner::Empty => (),
             BodyInner::String(content) => return builder.body(content.to_string()),
-            BodyInner::File(file) => return NamedFile::open(file).unwrap().into_response(&http_request),
+            BodyInner::File(file) => {
+                // the file response brings its own headers, the ones set by the handler, e.g.
+                // `Cache-Control', are added to them
+                let Ok(file) = NamedFile::open(file) else { return HttpResponse::NotFound().finish() };
+                let mut response = file.into_response(&http_request);
+                for key in self.headers().keys() {
+                    if let (Ok(name), Ok(value)) = (HeaderName::try_from(key.as_str()), HeaderValue::from_str(self.headers().get(&key).unwrap().as_str())) {
+                        response.headers_mut().insert(name, value);
+                    }
+                }
+                return response;
+            }
+            BodyInner::Teon(value) if accepts_msgpack(&http_request) => {
+                let bytes = match to_msgpack(value) {
+                    Ok(bytes) => bytes,
+                    Err(message) => return unserializable(message),
+                };
+                builder.content_type(MSGPACK_CONTENT_TYPE);
+                builder.append_header((VARY, "accept"));
+                return builder.body(bytes);
+            }
             BodyInner::Teon(value) => {
+                let bytes = match serde_json::to_vec(&JsonBody(value)) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return unserializable(e.to_string()),
+                };
                 builder.content_type("application/json");
-                let json_value = serde_json::Value::try_from(value).unwrap();
-                let string_value = serde_json::to_string(&json_value).unwrap();
-                return builder.body(string_value);
+                builder.append_header((VARY, "accept"));
+                return builder.body(bytes);
             }
         }
         builder.finish()
     }
 }
+
+// a body which can't be written is answered like an error of the handler, the error stays on
+// the response so it's reported with the request id
+fn unserializable(message: String) -> HttpResponse {
+    HttpResponse::from_error(WrapError::from(path::Error::internal_server_error_message_only(message)))
+}
+
+// writes the same JSON as converting into serde_json::Value first, without building the tree
+pub(crate) struct JsonBody<'a>(pub(crate) &'a Value);
+
+impl<'a> Serialize for JsonBody<'a> {
+
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match self.0 {
+            Value::Null => serializer.serialize_none(),
+            Value::ObjectId(o) => serializer.serialize_str(&o.to_hex()),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => serializer.serialize_i32(*i),
+            Value::Int64(i) => serializer.serialize_i64(*i),
+            Value::Float32(f) => serializer.serialize_f64(*f as f64),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::Decimal(d) => tagged(serializer, "$decimal", &d.normalized().to_string()),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Date(d) => tagged(serializer, "$date", &d.format("%Y-%m-%d").to_string()),
+            Value::DateTime(d) => tagged(serializer, "$datetime", &d.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            Value::Array(a) => serializer.collect_seq(a.iter().map(JsonBody)),
+            Value::Dictionary(d) => serializer.collect_map(d.iter().map(|(k, v)| (k, JsonBody(v)))),
+            _ => Err(S::Error::custom(format!("Cannot convert {} into json", self.0.type_hint()))),
+        }
+    }
+}
+
+fn tagged<S>(serializer: S, tag: &str, value: &str) -> Result<S::Ok, S::Error> where S: Serializer {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(tag, value)?;
+    map.end()
+}
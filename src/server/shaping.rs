@@ -0,0 +1,201 @@
+use std::collections::BTreeSet;
+use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::model::Model;
+use teo_runtime::path::Error;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+
+enum Expr {
+    Field(String),
+    Literal(Value),
+    Concat(Vec<Expr>),
+    Arithmetic(Operator, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Copy, Clone)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+pub(crate) struct OutputShape {
+    outputs: Vec<(String, Expr)>,
+    hidden: BTreeSet<String>,
+}
+
+// replaces aliases and computed expressions in `select' with the fields they read
+pub(crate) fn take_output_shape(model: &Model, json_body: &mut JsonValue) -> BoxedResult<Option<OutputShape>> {
+    let Some(select) = json_body.get_mut("select").and_then(|s| s.as_object_mut()) else {
+        return Ok(None);
+    };
+    let keys: Vec<String> = select.iter().filter(|(_, v)| v.is_object()).map(|(k, _)| k.clone()).collect();
+    if keys.is_empty() {
+        return Ok(None);
+    }
+    let mut outputs = vec![];
+    let mut fields = BTreeSet::new();
+    for key in keys {
+        let value = select.remove(&key).unwrap();
+        let expr = parse(model, &value, &path!["select", key.as_str()])?;
+        collect_fields(&expr, &mut fields);
+        outputs.push((key, expr));
+    }
+    let inclusive = select.is_empty() || select.values().any(|v| v.as_bool() == Some(true));
+    let mut hidden = BTreeSet::new();
+    for field in fields {
+        match select.get(&field).and_then(|v| v.as_bool()) {
+            Some(true) => (),
+            Some(false) => Err(Error::value_error(path!["select", field.as_str()], "field is excluded but used in a computed output"))?,
+            None => if inclusive {
+                select.insert(field.clone(), JsonValue::Bool(true));
+                hidden.insert(field);
+            },
+        }
+    }
+    Ok(Some(OutputShape { outputs, hidden }))
+}
+
+pub(crate) fn apply_output_shape(shape: &OutputShape, response: Response) -> Response {
+    let Some(Value::Dictionary(body)) = response.body().as_teon().cloned() else {
+        return response;
+    };
+    let mut body = body;
+    match body.get_mut("data") {
+        Some(Value::Dictionary(_)) => shape_object(shape, body.get_mut("data").unwrap()),
+        Some(Value::Array(objects)) => objects.iter_mut().for_each(|object| shape_object(shape, object)),
+        _ => return response,
+    }
+    let result = Response::teon(Value::Dictionary(body));
+    result.set_code(response.code());
+    for key in response.headers().keys() {
+        result.headers().set(key.as_str(), response.headers().get(&key).unwrap());
+    }
+    result
+}
+
+fn shape_object(shape: &OutputShape, object: &mut Value) {
+    let computed: Vec<(String, Value)> = shape.outputs.iter().map(|(key, expr)| (key.clone(), evaluate(expr, object))).collect();
+    let Value::Dictionary(map) = object else {
+        return;
+    };
+    for field in &shape.hidden {
+        map.shift_remove(field);
+    }
+    for (key, value) in computed {
+        map.insert(key, value);
+    }
+}
+
+fn parse(model: &Model, json: &JsonValue, path: &KeyPath) -> BoxedResult<Expr> {
+    match json {
+        JsonValue::String(s) if s.starts_with("$") => {
+            let name = s.trim_start_matches("$");
+            if model.field(name).is_none() {
+                Err(Error::value_error(path.clone(), format!("field `{}' is not defined", name)))?
+            }
+            Ok(Expr::Field(name.to_owned()))
+        }
+        JsonValue::Object(map) if map.len() == 1 => {
+            let (name, args) = map.iter().next().unwrap();
+            let path = path + name.as_str();
+            match name.as_str() {
+                "alias" => match args.as_str() {
+                    Some(field) => parse(model, &JsonValue::String(format!("${}", field)), &path),
+                    None => Err(Error::value_error(path, "expect field name").into()),
+                },
+                "concat" => match args.as_array() {
+                    Some(args) => Ok(Expr::Concat(args.iter().enumerate().map(|(i, a)| parse(model, a, &(&path + i))).collect::<BoxedResult<Vec<Expr>>>()?)),
+                    None => Err(Error::value_error(path, "expect array").into()),
+                },
+                "add" | "subtract" | "multiply" | "divide" => {
+                    let operator = match name.as_str() {
+                        "add" => Operator::Add,
+                        "subtract" => Operator::Subtract,
+                        "multiply" => Operator::Multiply,
+                        _ => Operator::Divide,
+                    };
+                    match args.as_array().map(|a| a.as_slice()) {
+                        Some([lhs, rhs]) => Ok(Expr::Arithmetic(operator, Box::new(parse(model, lhs, &(&path + 0))?), Box::new(parse(model, rhs, &(&path + 1))?))),
+                        _ => Err(Error::value_error(path, "expect two operands").into()),
+                    }
+                }
+                _ => Err(Error::value_error(path, "unknown expression").into()),
+            }
+        }
+        JsonValue::Object(_) | JsonValue::Array(_) => Err(Error::value_error(path.clone(), "unknown expression").into()),
+        _ => Ok(Expr::Literal(Value::from(json))),
+    }
+}
+
+fn collect_fields(expr: &Expr, fields: &mut BTreeSet<String>) {
+    match expr {
+        Expr::Field(name) => { fields.insert(name.clone()); }
+        Expr::Literal(_) => (),
+        Expr::Concat(exprs) => exprs.iter().for_each(|e| collect_fields(e, fields)),
+        Expr::Arithmetic(_, lhs, rhs) => {
+            collect_fields(lhs, fields);
+            collect_fields(rhs, fields);
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, object: &Value) -> Value {
+    match expr {
+        Expr::Field(name) => object.get(name.as_str()).cloned().unwrap_or(Value::Null),
+        Expr::Literal(value) => value.clone(),
+        Expr::Concat(exprs) => {
+            let mut result = String::new();
+            for expr in exprs {
+                match evaluate(expr, object) {
+                    Value::Null => return Value::Null,
+                    Value::String(s) => result += &s,
+                    value => result += &format!("{}", value),
+                }
+            }
+            Value::String(result)
+        }
+        Expr::Arithmetic(operator, lhs, rhs) => arithmetic(*operator, &evaluate(lhs, object), &evaluate(rhs, object)),
+    }
+}
+
+fn arithmetic(operator: Operator, lhs: &Value, rhs: &Value) -> Value {
+    if lhs.is_decimal() || rhs.is_decimal() {
+        let (Some(l), Some(r)) = (to_decimal(lhs), to_decimal(rhs)) else { return Value::Null };
+        return match operator {
+            Operator::Add => Value::Decimal(l + r),
+            Operator::Subtract => Value::Decimal(l - r),
+            Operator::Multiply => Value::Decimal(l * r),
+            Operator::Divide => if r.is_zero() { Value::Null } else { Value::Decimal(l / r) },
+        };
+    }
+    if let (Some(l), Some(r), false) = (lhs.to_int64(), rhs.to_int64(), matches!(operator, Operator::Divide)) {
+        return match operator {
+            Operator::Add => l.checked_add(r),
+            Operator::Subtract => l.checked_sub(r),
+            _ => l.checked_mul(r),
+        }.map(Value::Int64).unwrap_or(Value::Null);
+    }
+    let (Some(l), Some(r)) = (lhs.to_float(), rhs.to_float()) else { return Value::Null };
+    match operator {
+        Operator::Add => Value::Float(l + r),
+        Operator::Subtract => Value::Float(l - r),
+        Operator::Multiply => Value::Float(l * r),
+        Operator::Divide => if r == 0.0 { Value::Null } else { Value::Float(l / r) },
+    }
+}
+
+fn to_decimal(value: &Value) -> Option<BigDecimal> {
+    match value {
+        Value::Decimal(d) => Some(d.clone()),
+        _ => if let Some(i) = value.to_int64() {
+            Some(BigDecimal::from(i))
+        } else {
+            value.to_float().and_then(BigDecimal::from_f64)
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{HttpRequest, HttpResponse};
+use indexmap::IndexMap;
+use key_path::path;
+use maplit::btreemap;
+use ring::hmac;
+use serde_json::{json, Value as JsonValue};
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, SINGLE};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::default::find_many;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::handler::r#match::HandlerMatch;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::connection;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::pipeline::identity::{identity, IDENTITY_KEY};
+use crate::scope::apply_scope;
+use crate::server::cost::check_query_cost;
+use crate::server::filters::normalize_filters;
+use crate::server::limits::limits_for_action;
+use crate::server::pagination::apply_page_meta;
+use crate::server::report::keep_ctx;
+use crate::server::request::teo_request;
+use crate::server::error::BoxedResult;
+use crate::utils::hex;
+
+pub(super) const SHARE_PATH: &str = "/_share";
+const DEFAULT_EXPIRES_IN: u64 = 3600;
+const GRANTED_IDENTITY_KEY: &str = "teo.share.identity";
+
+pub(super) fn share_action() -> Action {
+    builtin_action_handler_from_name("findMany").unwrap()
+}
+
+// splits `expiresIn' from the findMany arguments
+pub(super) fn share_input(json_body: &JsonValue) -> BoxedResult<(JsonValue, u64)> {
+    let mut args = if json_body.is_null() { json!({}) } else { json_body.clone() };
+    let Some(map) = args.as_object_mut() else {
+        return Err(path::Error::value_error_message_only("expect object").into());
+    };
+    let expires_in = match map.remove("expiresIn") {
+        None => DEFAULT_EXPIRES_IN,
+        Some(value) => match value.as_u64() {
+            Some(seconds) if seconds > 0 => seconds,
+            _ => Err(path::Error::value_error(path!["expiresIn"], "expect positive integer"))?,
+        },
+    };
+    Ok((args, expires_in))
+}
+
+// scopes, filter shorthands and the query cost are checked when a share is created and again when
+// it's read, the limits may have changed in between
+pub(super) fn prepare_share_args(model: &Model, request: &request::Request, args: &mut JsonValue) -> BoxedResult<()> {
+    apply_scope(model, "findMany", request, args)?;
+    normalize_filters(model, args)?;
+    check_query_cost(model, "findMany", args, &limits_for_action("share"))
+}
+
+// the creator has to be able to run the query, the middlewares and read permissions are checked
+// here. The identity of the creator is output, it's granted to the readers of the share.
+pub(super) async fn share_preview(ctx: request::Ctx) -> path::Result<Response> {
+    find_many(&ctx).await?;
+    Ok(Response::teon(match identity(&ctx) {
+        Some(identity) => teon!({"model": identity.model().path.join("."), "identifier": identity.identifier()}),
+        None => Value::Null,
+    }))
+}
+
+pub(super) fn share_response(model: &Model, args: &JsonValue, grant: Option<&Value>, expires_in: u64) -> BoxedResult<HttpResponse> {
+    let Some(key) = Ctx::settings().share_key.clone() else {
+        return Err(path::Error::not_found_message_only().into());
+    };
+    let grant = grant.and_then(|grant| JsonValue::try_from(grant).ok()).unwrap_or(JsonValue::Null);
+    let expires_at = now() + expires_in;
+    let payload = json!({
+        "model": model.path.join("."),
+        "args": args,
+        "identity": grant,
+        "exp": expires_at,
+    });
+    let payload = hex(payload.to_string().as_bytes());
+    let signature = hex(hmac::sign(&key, payload.as_bytes()).as_ref());
+    Ok(HttpResponse::Ok().json(json!({
+        "data": {
+            "token": format!("{}.{}", payload, signature),
+            "expiresAt": expires_at,
+        }
+    })))
+}
+
+// requests presenting a valid token get the shared result set, through the middlewares of the
+// namespace of the model as a findMany does. The read runs as the identity of the creator, so it
+// passes the read permissions the creator passed, whoever presents the token.
+pub(super) async fn shared(http_request: HttpRequest, json_body: &JsonValue, main_namespace: &'static Namespace) -> path::Result<Response> {
+    let Some(key) = Ctx::settings().share_key.clone() else {
+        return Err(path::Error::not_found_message_only());
+    };
+    let token = match http_request.path().rsplit_once(&format!("{}/", SHARE_PATH)) {
+        Some((_, token)) if !token.is_empty() => token.to_owned(),
+        _ => match json_body.get("token").and_then(|t| t.as_str()) {
+            Some(token) => token.to_owned(),
+            None => Err(path::Error::value_error(path!["token"], "expect string"))?,
+        },
+    };
+    let payload = verify(&key, &token)?;
+    let Some(model_path) = payload.get("model").and_then(|m| m.as_str()) else {
+        return Err(invalid_token());
+    };
+    let model_path: Vec<&str> = model_path.split(".").collect();
+    let Some(model) = main_namespace.model_at_path(&model_path) else {
+        return Err(invalid_token());
+    };
+    let mut args = payload.get("args").cloned().unwrap_or(json!({}));
+    // only the page of a paginated share can be chosen by the client
+    if let Some(page_number) = json_body.get("pageNumber") {
+        if args.get("pageSize").is_none() {
+            Err(path::Error::value_error(path!["pageNumber"], "share is not paginated"))?
+        }
+        args.as_object_mut().unwrap().insert("pageNumber".to_owned(), page_number.clone());
+    } else if args.get("pageSize").is_some() && args.get("pageNumber").is_none() {
+        args.as_object_mut().unwrap().insert("pageNumber".to_owned(), json!(1));
+    }
+    let request = teo_request(&http_request);
+    prepare_share_args(model, &request, &mut args)?;
+    let body = validate_and_transform_json_input_for_builtin_action(model, share_action(), &args, main_namespace)?;
+    let transaction_ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let granted = granted_identity(payload.get("identity"), main_namespace, &transaction_ctx).await?;
+    let ctx = request::Ctx::new(
+        request,
+        Arc::new(body),
+        transaction_ctx,
+        HandlerMatch {
+            path: model_path.iter().map(|p| p.to_string()).collect(),
+            name: "findMany".to_owned(),
+            captures: IndexMap::new(),
+        },
+    );
+    keep_ctx(&http_request, &ctx);
+    if let Some(granted) = granted {
+        ctx.data_mut().insert(GRANTED_IDENTITY_KEY, granted);
+    }
+    let Some(dest_namespace) = main_namespace.namespace_at_path(&model.namespace_path()) else {
+        return Err(invalid_token());
+    };
+    let body = ctx.body().clone();
+    let response = dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
+        // the middlewares may have set the identity of the reader
+        let granted = ctx.data().get::<Object>(GRANTED_IDENTITY_KEY).cloned();
+        match granted {
+            Some(granted) => ctx.data_mut().insert(IDENTITY_KEY, granted),
+            None => {
+                ctx.data_mut().remove::<Object>(IDENTITY_KEY);
+            }
+        }
+        find_many(&ctx).await
+    }).await?;
+    Ok(apply_page_meta("findMany", &body, response))
+}
+
+// the identity of the creator is read again, a removed identity invalidates its shares
+async fn granted_identity(grant: Option<&JsonValue>, main_namespace: &'static Namespace, transaction_ctx: &transaction::Ctx) -> path::Result<Option<Object>> {
+    let Some(grant) = grant.filter(|grant| !grant.is_null()) else {
+        return Ok(None);
+    };
+    let model_path: Vec<&str> = grant.get("model").and_then(|m| m.as_str()).unwrap_or_default().split(".").collect();
+    let Some(model) = main_namespace.model_at_path(&model_path) else {
+        return Err(invalid_token());
+    };
+    let action = builtin_action_handler_from_name("findUnique").unwrap();
+    let finder = validate_and_transform_json_input_for_builtin_action(model, action, &json!({"where": grant.get("identifier")}), main_namespace).map_err(|_| invalid_token())?;
+    match transaction_ctx.find_unique_internal(model, &finder, true, FIND | SINGLE | ENTRY, None, path![]).await? {
+        Some(identity) => Ok(Some(identity)),
+        None => Err(invalid_token()),
+    }
+}
+
+fn verify(key: &hmac::Key, token: &str) -> BoxedResult<JsonValue> {
+    let Some((payload, signature)) = token.split_once(".") else {
+        return Err(invalid_token().into());
+    };
+    let Some(signature) = unhex(signature) else {
+        return Err(invalid_token().into());
+    };
+    if hmac::verify(key, payload.as_bytes(), &signature).is_err() {
+        return Err(invalid_token().into());
+    }
+    let Some(payload) = unhex(payload).and_then(|bytes| serde_json::from_slice::<JsonValue>(&bytes).ok()) else {
+        return Err(invalid_token().into());
+    };
+    match payload.get("exp").and_then(|e| e.as_u64()) {
+        Some(exp) if exp > now() => Ok(payload),
+        _ => Err(path::Error {
+            title: "Gone",
+            message: "share token has expired".to_owned(),
+            fields: None,
+            code: 410,
+            meta_map: btreemap! {},
+        }.into()),
+    }
+}
+
+fn invalid_token() -> path::Error {
+    path::Error::unauthorized_error_message_only("invalid share token")
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok())).collect()
+}
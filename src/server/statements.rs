@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use maplit::btreemap;
+use teo_runtime::model::Model;
+use teo_runtime::path;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::server::error::{BoxedResult, keep_error_type};
+use crate::server::cost::READ_ACTIONS;
+use crate::server::limits::Limits;
+
+tokio::task_local! {
+    static BUDGET: Arc<StatementBudget>;
+}
+
+/// Counts the connector statements of a request against `max_statements`.
+#[derive(Debug)]
+pub(crate) struct StatementBudget {
+    count: AtomicUsize,
+    max: Option<usize>,
+    strict: bool,
+}
+
+impl StatementBudget {
+
+    pub(crate) fn new(limits: &Limits) -> Self {
+        Self { count: AtomicUsize::new(0), max: limits.max_statements, strict: limits.strict_max_statements }
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Runs `f` with the statements it issues counted against this budget.
+    pub(crate) async fn scope<F: Future>(self: &Arc<Self>, f: F) -> F::Output {
+        BUDGET.scope(self.clone(), f).await
+    }
+}
+
+/// Logs actions over `max_statements`, and fails reads with `strict_max_statements`.
+pub(crate) fn check_statements(model: &Model, action: &str, budget: &StatementBudget) -> BoxedResult<()> {
+    let Some(max) = budget.max else {
+        return Ok(());
+    };
+    if budget.count() <= max {
+        return Ok(());
+    }
+    if !Ctx::cli().silent {
+        info_message(format!("{}.{} issued {} statements, over the budget of {}", model.path.join("."), action, budget.count(), max));
+    }
+    if budget.strict && READ_ACTIONS.contains(&action) {
+        return Err(too_many_statements(max).into());
+    }
+    Ok(())
+}
+
+// reads over a strict budget fail when the action is done, the default handlers unwrap some of
+// their reads
+pub(crate) fn count_read() {
+    let _ = BUDGET.try_with(|budget| budget.count.fetch_add(1, Ordering::Relaxed));
+}
+
+// a strict budget fails the write over it, so it fails before its transaction commits
+pub(crate) fn count_write() -> BoxedResult<()> {
+    BUDGET.try_with(|budget| {
+        let count = budget.count.fetch_add(1, Ordering::Relaxed) + 1;
+        match budget.max {
+            Some(max) if budget.strict && count > max => Err(too_many_statements(max).into()),
+            _ => Ok(()),
+        }
+    }).unwrap_or(Ok(()))
+}
+
+fn too_many_statements(max: usize) -> path::Error {
+    keep_error_type(path::Error {
+        title: "TooManyStatements",
+        message: format!("request issued more than {} statements", max),
+        fields: None,
+        code: 500,
+        meta_map: btreemap! {},
+    })
+}
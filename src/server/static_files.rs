@@ -1,14 +1,46 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use teo_runtime::path;
 use teo_runtime::response::Response;
+use crate::server::error::BoxedResult;
 
-pub fn serve_static_files(base: impl AsRef<str>, path: impl AsRef<str>) -> path::Result<Response> {
-    let base_str = base.as_ref();
-    let path_str = path.as_ref();
-    let combined_path = PathBuf::from(base_str).join(path_str);
-    if combined_path.is_file() {
-        Ok(Response::file(combined_path))
-    } else {
-        Err(path::Error::not_found_message_only())
+const INDEX_FILE: &str = "index.html";
+
+#[derive(Debug, Clone, Default)]
+pub struct StaticFilesOptions {
+    /// Serve `index.html` for unknown paths without a file extension, for client side routing.
+    pub spa_fallback: bool,
+    pub cache_control: Option<String>,
+}
+
+pub fn serve_static_files(base: impl AsRef<str>, path: impl AsRef<str>) -> BoxedResult<Response> {
+    serve_static_files_with_options(base, path, &StaticFilesOptions::default())
+}
+
+/// Files are sent with `ETag` and `Last-Modified`, conditional requests are answered with 304.
+pub fn serve_static_files_with_options(base: impl AsRef<str>, path: impl AsRef<str>, options: &StaticFilesOptions) -> BoxedResult<Response> {
+    let base = PathBuf::from(base.as_ref());
+    let path = Path::new(path.as_ref().trim_start_matches('/'));
+    let file = match resolve(&base, path) {
+        Some(file) => file,
+        None if options.spa_fallback && path.extension().is_none() => match resolve(&base, Path::new(INDEX_FILE)) {
+            Some(index) => index,
+            None => Err(path::Error::not_found_message_only())?,
+        },
+        None => Err(path::Error::not_found_message_only())?,
+    };
+    let response = Response::file(file);
+    if let Some(cache_control) = &options.cache_control {
+        response.headers().set("Cache-Control", cache_control.as_str());
+    }
+    Ok(response)
+}
+
+// `..' is rejected up front and symbolic links must not lead out of the base directory
+fn resolve(base: &Path, path: &Path) -> Option<PathBuf> {
+    if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return None;
     }
+    let base = base.canonicalize().ok()?;
+    let file = base.join(path).canonicalize().ok()?;
+    (file.starts_with(&base) && file.is_file()).then_some(file)
 }
@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::Serialize;
+use teo_runtime::path;
+use teo_runtime::response::Response;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+
+pub(super) const STATS_PATH: &str = "/_stats";
+const SAMPLES: usize = 1000;
+
+#[derive(Default)]
+struct Entry {
+    count: u64,
+    errors: u64,
+    total: Duration,
+    rows: u64,
+    samples: VecDeque<Duration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionStats {
+    pub model: String,
+    pub action: String,
+    pub count: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub rows: u64,
+    pub avg_rows: f64,
+}
+
+pub(crate) struct StatsRegistry {
+    entries: Mutex<BTreeMap<(String, String), Entry>>,
+}
+
+impl StatsRegistry {
+
+    pub(crate) fn new() -> Self {
+        Self { entries: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub(crate) fn record(&self, model: &str, action: &str, elapsed: Duration, response: &path::Result<Response>) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry((model.to_owned(), action.to_owned())).or_default();
+        entry.count += 1;
+        entry.total += elapsed;
+        if entry.samples.len() == SAMPLES {
+            entry.samples.pop_front();
+        }
+        entry.samples.push_back(elapsed);
+        match response {
+            Ok(response) => entry.rows += rows(response),
+            Err(_) => entry.errors += 1,
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<ActionStats> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|((model, action), entry)| {
+            let mut samples: Vec<Duration> = entry.samples.iter().cloned().collect();
+            samples.sort();
+            ActionStats {
+                model: model.clone(),
+                action: action.clone(),
+                count: entry.count,
+                errors: entry.errors,
+                avg_ms: millis(entry.total) / entry.count as f64,
+                p50_ms: percentile(&samples, 0.5),
+                p95_ms: percentile(&samples, 0.95),
+                p99_ms: percentile(&samples, 0.99),
+                rows: entry.rows,
+                avg_rows: entry.rows as f64 / entry.count as f64,
+            }
+        }).collect()
+    }
+
+    pub(crate) fn reset(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// Statistics of the builtin actions handled so far, empty unless `App::stats` is called.
+pub fn stats() -> Vec<ActionStats> {
+    Ctx::settings().stats.as_ref().map(|s| s.snapshot()).unwrap_or_default()
+}
+
+pub fn reset_stats() {
+    if let Some(stats) = Ctx::settings().stats.clone() {
+        stats.reset();
+    }
+}
+
+fn rows(response: &Response) -> u64 {
+    match response.body().as_teon().and_then(|v| v.get("data")) {
+        Some(Value::Array(values)) => values.len() as u64,
+        Some(Value::Null) | None => 0,
+        Some(_) => 1,
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// nearest rank over the most recent samples
+fn percentile(samples: &[Duration], p: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p * samples.len() as f64).ceil() as usize).clamp(1, samples.len());
+    millis(samples[rank - 1])
+}
@@ -0,0 +1,371 @@
+use std::ptr;
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{CONNECT, CREATE, ENTRY, FIND, JOIN_CREATE, MANY, NESTED, SINGLE, UPDATE, UPSERT};
+use teo_runtime::coder::json_to_teon::json_to_teon;
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object, Relation};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::object::object::ErrorIfNotFound;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+const THROUGH_KEY: &str = "_through";
+const THROUGH_WRITES_KEY: &str = "teo.throughWrites";
+
+// the record on the far side of a relation through a join model
+enum Far {
+    Create(Value),
+    Connect(Value),
+    ConnectOrCreate(Value, Value),
+    // a connected record found by the filter is updated, otherwise one is created and connected
+    Upsert(Value, Value, Value),
+}
+
+/// A nested write on a relation through a join model, with the values of its `_through` input.
+pub(crate) struct ThroughWrite {
+    // the entry model and the entry input the write was taken from
+    model: &'static Model,
+    entry: KeyPath,
+    relation: &'static Relation,
+    far: Far,
+    values: Value,
+    path: KeyPath,
+}
+
+// the runtime creates join records without input, so the creates and connects with join record
+// values are taken out of the entry input and run by the server once the entry record is saved
+pub(crate) fn take_through_writes(model: &'static Model, action: &str, json_body: &mut JsonValue, main_namespace: &Namespace) -> BoxedResult<Vec<ThroughWrite>> {
+    let entries: Vec<(KeyPath, &mut JsonValue)> = match (action, json_body.get_mut(if action.starts_with("update") { "update" } else { "create" })) {
+        ("create", Some(create)) => vec![(path!["create"], create)],
+        ("createMany", Some(JsonValue::Array(creates))) => creates.iter_mut().enumerate().map(|(index, create)| (path!["create", index], create)).collect(),
+        ("update" | "updateMany", Some(update)) => vec![(path!["update"], update)],
+        _ => vec![],
+    };
+    let mut writes = vec![];
+    for (entry, data) in entries {
+        take_data(model, &entry, data, main_namespace, &mut writes)?;
+    }
+    Ok(writes)
+}
+
+fn take_data(model: &'static Model, entry: &KeyPath, data: &mut JsonValue, main_namespace: &Namespace, writes: &mut Vec<ThroughWrite>) -> BoxedResult<()> {
+    let Some(map) = data.as_object_mut() else { return Ok(()) };
+    let mut emptied = vec![];
+    for (key, value) in map.iter_mut() {
+        let Some(relation) = model.relation(key) else { continue };
+        let Some(operations) = value.as_object_mut() else { continue };
+        for operation in ["create", "connect", "connectOrCreate", "upsert"] {
+            let Some(argument) = operations.get_mut(operation) else { continue };
+            let path = entry + key.as_str() + operation;
+            let taken = match argument {
+                JsonValue::Array(items) => {
+                    let mut kept = vec![];
+                    for (index, item) in std::mem::take(items).into_iter().enumerate() {
+                        match through_write(model, entry, relation, operation, &item, &(&path + index), main_namespace)? {
+                            Some(write) => writes.push(write),
+                            None => kept.push(item),
+                        }
+                    }
+                    *items = kept;
+                    items.is_empty()
+                }
+                item => match through_write(model, entry, relation, operation, item, &path, main_namespace)? {
+                    Some(write) => {
+                        writes.push(write);
+                        true
+                    }
+                    None => false,
+                },
+            };
+            if taken {
+                operations.remove(operation);
+            }
+        }
+        if operations.is_empty() {
+            emptied.push(key.clone());
+        }
+    }
+    for key in emptied {
+        map.remove(&key);
+    }
+    Ok(())
+}
+
+fn through_write(model: &'static Model, entry: &KeyPath, relation: &'static Relation, operation: &str, item: &JsonValue, path: &KeyPath, main_namespace: &Namespace) -> BoxedResult<Option<ThroughWrite>> {
+    // the runtime looks the record of an upsert up among every record instead of the connected ones,
+    // so the upserts on these relations are always taken
+    let through = item.get(THROUGH_KEY);
+    if through.is_none() && !(operation == "upsert" && relation.has_join_table()) {
+        // the join record the runtime creates would fail without them
+        if relation.has_join_table() {
+            check_required_join_fields(relation, path, main_namespace)?;
+        }
+        return Ok(None);
+    }
+    if !relation.has_join_table() {
+        Err(path::Error::value_error(path + THROUGH_KEY, "relation is not through a join model"))?
+    }
+    let mut item = item.clone();
+    item.as_object_mut().unwrap().remove(THROUGH_KEY);
+    let related = main_namespace.model_at_path(&relation.model_path()).unwrap();
+    let far = match operation {
+        "create" => Far::Create(input(related, "create", "create", &item, path, main_namespace)?),
+        "connect" => Far::Connect(input(related, "findUnique", "where", &item, path, main_namespace)?),
+        "connectOrCreate" => Far::ConnectOrCreate(
+            input(related, "findUnique", "where", item.get("where").unwrap_or(&JsonValue::Null), &(path + "where"), main_namespace)?,
+            input(related, "create", "create", item.get("create").unwrap_or(&JsonValue::Null), &(path + "create"), main_namespace)?,
+        ),
+        _ => Far::Upsert(
+            input(related, "findUnique", "where", item.get("where").unwrap_or(&JsonValue::Null), &(path + "where"), main_namespace)?,
+            input(related, "create", "create", item.get("create").unwrap_or(&JsonValue::Null), &(path + "create"), main_namespace)?,
+            input(related, "updateMany", "update", item.get("update").unwrap_or(&JsonValue::Null), &(path + "update"), main_namespace)?,
+        ),
+    };
+    let values = match through {
+        Some(through) => join_values(relation, through, &(path + THROUGH_KEY), main_namespace)?,
+        None => {
+            check_required_join_fields(relation, path, main_namespace)?;
+            teon!({})
+        }
+    };
+    Ok(Some(ThroughWrite { model, entry: entry.clone(), relation, far, values, path: path.clone() }))
+}
+
+// validates a nested input as the `key' of the input of `action', with errors at its own path
+fn input(model: &Model, action: &str, key: &str, json: &JsonValue, path: &KeyPath, main_namespace: &Namespace) -> BoxedResult<Value> {
+    // an update input is validated as the update of every record
+    let mut input = if action == "updateMany" { serde_json::json!({ "where": {} }) } else { serde_json::json!({}) };
+    input[key] = json.clone();
+    match validate_and_transform_json_input_for_builtin_action(model, builtin_action_handler_from_name(action).unwrap(), &input, main_namespace) {
+        Ok(value) => Ok(value.get(key).cloned().unwrap()),
+        Err(mut error) => {
+            if let Some(fields) = error.fields.take() {
+                error.fields = Some(fields.into_iter().map(|(k, v)| (k.replacen(key, &path.to_string(), 1), v)).collect());
+            }
+            Err(error.into())
+        }
+    }
+}
+
+// the fields linking the join record are set by the write, the other fields take the input
+fn join_values(relation: &Relation, json: &JsonValue, path: &KeyPath, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let Some(map) = json.as_object() else {
+        return Err(path::Error::value_error(path.clone(), "expect object").into());
+    };
+    let (join_model, local) = main_namespace.through_relation(relation);
+    let (_, foreign) = main_namespace.through_opposite_relation(relation);
+    let mut values = teon!({});
+    for (key, value) in map {
+        let field = join_model.field(key).filter(|_| !local.fields().contains(&key.as_str()) && !foreign.fields().contains(&key.as_str()));
+        let Some(field) = field else {
+            Err(path::Error::value_error(path + key.as_str(), format!("field `{}' is not an input of `{}'", key, join_model.path.join("."))))?
+        };
+        values.as_dictionary_mut().unwrap().insert(key.clone(), json_to_teon(value, &(path + key.as_str()), &field.r#type, main_namespace)?);
+    }
+    Ok(values)
+}
+
+fn check_required_join_fields(relation: &Relation, path: &KeyPath, main_namespace: &Namespace) -> BoxedResult<()> {
+    let (join_model, local) = main_namespace.through_relation(relation);
+    let (_, foreign) = main_namespace.through_opposite_relation(relation);
+    let required = join_model.fields().into_iter()
+        .filter(|f| !f.is_optional() && f.default.is_none() && !f.auto && !f.auto_increment)
+        .find(|f| !local.fields().contains(&f.name.as_str()) && !foreign.fields().contains(&f.name.as_str()));
+    match required {
+        Some(field) => Err(path::Error::value_error(path + THROUGH_KEY, format!("`{}' of the join record is required", field.name)).into()),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn set_through_writes(ctx: &request::Ctx, writes: Vec<ThroughWrite>) {
+    if !writes.is_empty() {
+        ctx.data_mut().insert(THROUGH_WRITES_KEY, writes);
+    }
+}
+
+// the writes are taken when they run, so refreshing the record doesn't run them again
+fn take_writes(req_ctx: &request::Ctx, model: &Model, entry: &KeyPath) -> Vec<ThroughWrite> {
+    let mut data = req_ctx.data_mut();
+    let Some(writes) = data.get_mut::<Vec<ThroughWrite>>(THROUGH_WRITES_KEY) else {
+        return vec![];
+    };
+    let (taken, kept) = std::mem::take(writes).into_iter().partition(|w| &w.entry == entry && ptr::eq(w.model, model));
+    *writes = kept;
+    taken
+}
+
+/// Runs the writes taken from the `create` input at `path` once its record is saved.
+pub(crate) async fn write_through_on_create(object: &Object, path: &KeyPath) -> path::Result<()> {
+    let Some(req_ctx) = object.request_ctx() else {
+        return Ok(());
+    };
+    for write in take_writes(&req_ctx, object.model(), path) {
+        write_through(object, &write).await?;
+    }
+    Ok(())
+}
+
+/// Runs the writes taken from the `update` input for each record an entry update found.
+pub(crate) async fn write_through_on_update(req_ctx: &request::Ctx, model: &Model, objects: &[Object]) -> path::Result<()> {
+    let writes = take_writes(req_ctx, model, &path!["update"]);
+    for object in objects {
+        for write in &writes {
+            write_through(object, write).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn write_through(object: &Object, write: &ThroughWrite) -> path::Result<()> {
+    let namespace = Ctx::main_namespace();
+    let related = namespace.model_at_path(&write.relation.model_path()).unwrap();
+    let ctx = object.transaction_ctx();
+    let req_ctx = object.request_ctx();
+    let path = &write.path;
+    let far = match &write.far {
+        Far::Create(create) => create_far(&ctx, req_ctx.clone(), related, create, path).await?,
+        Far::Connect(r#where) => {
+            let finder = teon!({"where": r#where.clone()});
+            ctx.find_unique_internal(related, &finder, true, NESTED | CONNECT | SINGLE, req_ctx.clone(), path.clone()).await.into_not_found_error(path.clone())?
+        }
+        Far::ConnectOrCreate(r#where, create) => {
+            let finder = teon!({"where": r#where.clone()});
+            match ctx.find_unique_internal(related, &finder, true, NESTED | CONNECT | SINGLE, req_ctx.clone(), path.clone()).await? {
+                Some(far) => far,
+                None => create_far(&ctx, req_ctx.clone(), related, create, &(path + "create")).await?,
+            }
+        }
+        Far::Upsert(r#where, create, update) => {
+            let finder = teon!({"where": r#where.clone()});
+            let far = ctx.find_unique_internal(related, &finder, true, NESTED | UPSERT | UPDATE | SINGLE, req_ctx.clone(), path.clone()).await?;
+            match far {
+                Some(far) if joined(object, &far, write.relation, &ctx).await? => {
+                    far.set_teon_with_path(update, &(path + "update")).await?;
+                    return far.save_with_session_and_path(&(path + "update")).await;
+                }
+                _ => create_far(&ctx, req_ctx.clone(), related, create, &(path + "create")).await?,
+            }
+        }
+    };
+    let (join_model, local) = namespace.through_relation(write.relation);
+    let (_, foreign) = namespace.through_opposite_relation(write.relation);
+    let join = ctx.new_object(join_model, JOIN_CREATE | CREATE | SINGLE, req_ctx)?;
+    join.set_teon_with_path(&write.values, &(path + THROUGH_KEY)).await?;
+    for (field, reference) in local.iter() {
+        join.set_value(field, object.get_value(reference)?)?;
+    }
+    for (field, reference) in foreign.iter() {
+        join.set_value(field, far.get_value(reference)?)?;
+    }
+    join.save_with_session_and_path(path).await
+}
+
+async fn joined(object: &Object, far: &Object, relation: &Relation, ctx: &transaction::Ctx) -> path::Result<bool> {
+    let namespace = Ctx::main_namespace();
+    let (join_model, local) = namespace.through_relation(relation);
+    let (_, foreign) = namespace.through_opposite_relation(relation);
+    let mut r#where = teon!({});
+    for (field, reference) in local.iter() {
+        r#where.as_dictionary_mut().unwrap().insert(field.to_owned(), object.get_value(reference)?);
+    }
+    for (field, reference) in foreign.iter() {
+        r#where.as_dictionary_mut().unwrap().insert(field.to_owned(), far.get_value(reference)?);
+    }
+    Ok(ctx.count(join_model, &teon!({"where": r#where}), path![]).await? > 0)
+}
+
+async fn create_far(ctx: &transaction::Ctx, req_ctx: Option<request::Ctx>, model: &'static Model, create: &Value, path: &KeyPath) -> path::Result<Object> {
+    let action: Action = NESTED | CREATE | SINGLE;
+    let object = ctx.new_object_with_teon_and_path(model, create, path, action, req_ctx).await?;
+    object.save_with_session_and_path(path).await?;
+    Ok(object)
+}
+
+/// Takes `_through` out of the includes and returns the relations it was on.
+pub(crate) fn take_through_includes(model: &'static Model, json_body: &mut JsonValue) -> BoxedResult<Vec<&'static Relation>> {
+    let Some(include) = json_body.get_mut("include").and_then(|i| i.as_object_mut()) else {
+        return Ok(vec![]);
+    };
+    let mut relations = vec![];
+    for (key, value) in include.iter_mut() {
+        let Some(through) = value.as_object_mut().and_then(|v| v.remove(THROUGH_KEY)) else { continue };
+        let path = path!["include", key.as_str(), THROUGH_KEY];
+        let Some(through) = through.as_bool() else {
+            Err(path::Error::value_error(path, "expect bool"))?
+        };
+        match model.relation(key) {
+            Some(relation) if relation.has_join_table() => if through {
+                relations.push(relation);
+            },
+            _ => Err(path::Error::value_error(path, "relation is not through a join model"))?,
+        }
+        if value.as_object().is_some_and(|v| v.is_empty()) {
+            *value = JsonValue::Bool(true);
+        }
+    }
+    Ok(relations)
+}
+
+/// Attaches the join record of each included record of `relations` as its `_through`.
+pub(crate) async fn apply_through_includes(relations: &[&'static Relation], transaction_ctx: &transaction::Ctx, response: Response) -> path::Result<Response> {
+    let Some(Value::Dictionary(mut body)) = response.body().as_teon().cloned() else {
+        return Ok(response);
+    };
+    match body.get_mut("data") {
+        Some(Value::Dictionary(_)) => for relation in relations {
+            attach_join_records(relation, transaction_ctx, body.get_mut("data").unwrap()).await?;
+        },
+        Some(Value::Array(records)) => for record in records {
+            for relation in relations {
+                attach_join_records(relation, transaction_ctx, record).await?;
+            }
+        },
+        _ => return Ok(response),
+    }
+    let result = Response::teon(Value::Dictionary(body));
+    result.set_code(response.code());
+    for key in response.headers().keys() {
+        result.headers().set(key.as_str(), response.headers().get(&key).unwrap());
+    }
+    Ok(result)
+}
+
+// the join records of a record are read at once and matched to its included records by their keys
+async fn attach_join_records(relation: &'static Relation, transaction_ctx: &transaction::Ctx, record: &mut Value) -> path::Result<()> {
+    let namespace = Ctx::main_namespace();
+    let (join_model, local) = namespace.through_relation(relation);
+    let (_, foreign) = namespace.through_opposite_relation(relation);
+    let mut r#where = teon!({});
+    for (field, reference) in local.iter() {
+        let Some(value) = record.get(reference) else { return Ok(()) };
+        r#where.as_dictionary_mut().unwrap().insert(field.to_owned(), value.clone());
+    }
+    let Some(Value::Array(fars)) = record.as_dictionary_mut().and_then(|r| r.get_mut(relation.name.as_str())) else {
+        return Ok(());
+    };
+    let action: Action = FIND | MANY | ENTRY;
+    let joins = transaction_ctx.find_many_internal(join_model, &teon!({"where": r#where}), true, action, None, path![]).await?;
+    for far in fars {
+        let mut matched = None;
+        for join in &joins {
+            if foreign.iter().all(|(field, reference)| far.get(reference).is_some_and(|v| join.get_value(field).is_ok_and(|j| &j == v))) {
+                matched = Some(join);
+                break;
+            }
+        }
+        if let (Some(join), Some(far)) = (matched, far.as_dictionary_mut()) {
+            far.insert(THROUGH_KEY.to_owned(), join.to_teon_internal(&path![]).await?);
+        }
+    }
+    Ok(())
+}
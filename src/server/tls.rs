@@ -0,0 +1,136 @@
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{Duration, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use rustls::ServerConfig;
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::CertificateDer;
+use teo_result::{Error, Result};
+
+/// The certificate the server terminates HTTPS with. HTTP/2 is negotiated with clients which
+/// offer it, others are served HTTP/1.1.
+#[derive(Debug, Clone)]
+pub enum TlsOptions {
+    /// PEM files of the certificate chain and of its private key, PKCS#8, RSA or SEC1.
+    Files { cert: PathBuf, key: PathBuf },
+    /// A certificate of localhost generated at start, which browsers warn about. For development
+    /// only.
+    SelfSigned,
+}
+
+/// The config the server binds with, actix-web adds `h2` and `http/1.1` to its ALPN protocols.
+pub(crate) fn server_config(options: &TlsOptions) -> Result<ServerConfig> {
+    let (cert, key) = match options {
+        TlsOptions::Files { cert, key } => (read_pem(cert)?, read_pem(key)?),
+        TlsOptions::SelfSigned => {
+            let (cert, key) = self_signed_certificate(&["localhost", "127.0.0.1", "::1"])?;
+            (cert.into_bytes(), key.into_bytes())
+        }
+    };
+    let certs = rustls_pemfile::certs(&mut cert.as_slice())
+        .collect::<io::Result<Vec<CertificateDer>>>()
+        .map_err(|e| Error::new(format!("invalid TLS certificate: {}", e)))?;
+    let Some(key) = rustls_pemfile::private_key(&mut key.as_slice()).map_err(|e| Error::new(format!("invalid TLS private key: {}", e)))? else {
+        Err(Error::new("TLS private key is missing"))?
+    };
+    if certs.is_empty() {
+        Err(Error::new("TLS certificate is missing"))?
+    }
+    ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::new(format!("invalid TLS config: {}", e)))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| Error::new(format!("invalid TLS certificate: {}", e)))
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::new(format!("cannot read {}: {}", path.display(), e)))
+}
+
+/// A certificate valid for a year for `hosts`, names or IP addresses, and its P-256 key, as
+/// PEM. It's signed by its own key, so clients have to trust it explicitly.
+pub fn self_signed_certificate(hosts: &[&str]) -> Result<(String, String)> {
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).map_err(|_| Error::new("cannot generate TLS key"))?;
+    let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).map_err(|_| Error::new("cannot generate TLS key"))?;
+    let name = der(SEQUENCE, &der(SET, &der(SEQUENCE, &[der(OID, OID_COMMON_NAME), der(UTF8_STRING, b"Teo self-signed")].concat())));
+    let now = Utc::now();
+    let validity = [
+        der(UTC_TIME, (now - Duration::days(1)).format("%y%m%d%H%M%SZ").to_string().as_bytes()),
+        der(UTC_TIME, (now + Duration::days(365)).format("%y%m%d%H%M%SZ").to_string().as_bytes()),
+    ].concat();
+    let public_key = [
+        der(SEQUENCE, &[der(OID, OID_EC_PUBLIC_KEY), der(OID, OID_P256)].concat()),
+        der(BIT_STRING, &[&[0], key_pair.public_key().as_ref()].concat()),
+    ].concat();
+    let alt_names: Vec<u8> = hosts.iter().flat_map(|host| match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => der(SAN_IP_ADDRESS, &ip.octets()),
+        Ok(IpAddr::V6(ip)) => der(SAN_IP_ADDRESS, &ip.octets()),
+        Err(_) => der(SAN_DNS_NAME, host.as_bytes()),
+    }).collect();
+    let extensions = der(SEQUENCE, &der(SEQUENCE, &[der(OID, OID_SUBJECT_ALT_NAME), der(OCTET_STRING, &der(SEQUENCE, &alt_names))].concat()));
+    // positive and minimally encoded, so the first byte is neither zero nor above 0x7f
+    let mut serial: [u8; 16] = rand::random();
+    serial[0] = serial[0] & 0x7f | 0x40;
+    let tbs = der(SEQUENCE, &[
+        der(0xa0, &der(INTEGER, &[2])),
+        der(INTEGER, &serial),
+        signature_algorithm(),
+        name.clone(),
+        der(SEQUENCE, &validity),
+        name,
+        der(SEQUENCE, &public_key),
+        der(0xa3, &extensions),
+    ].concat());
+    let signature = key_pair.sign(&rng, &tbs).map_err(|_| Error::new("cannot sign TLS certificate"))?;
+    let cert = der(SEQUENCE, &[tbs, signature_algorithm(), der(BIT_STRING, &[&[0], signature.as_ref()].concat())].concat());
+    Ok((pem("CERTIFICATE", &cert), pem("PRIVATE KEY", pkcs8.as_ref())))
+}
+
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const UTC_TIME: u8 = 0x17;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const SAN_DNS_NAME: u8 = 0x82;
+const SAN_IP_ADDRESS: u8 = 0x87;
+
+// 1.2.840.10045.4.3.2, 1.2.840.10045.2.1, 1.2.840.10045.3.1.7, 2.5.4.3 and 2.5.29.17
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+fn signature_algorithm() -> Vec<u8> {
+    der(SEQUENCE, &der(OID, OID_ECDSA_WITH_SHA256))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let length: Vec<u8> = content.len().to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | length.len() as u8);
+        out.extend(length);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let lines: Vec<&str> = encoded.as_bytes().chunks(64).map(|c| std::str::from_utf8(c).unwrap()).collect();
+    format!("-----BEGIN {}-----\n{}\n-----END {}-----\n", label, lines.join("\n"), label)
+}
@@ -0,0 +1,61 @@
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::{Model, Relation};
+use teo_runtime::path;
+use crate::app::ctx::Ctx;
+use crate::server::error::BoxedResult;
+
+// disconnecting or deleting the other side of a required to one relation would leave a dangling foreign key
+pub(crate) fn check_to_one_writes(model: &Model, action: &str, json_body: &JsonValue) -> BoxedResult<()> {
+    match action {
+        "update" | "updateMany" | "upsert" => check_update(model, json_body.get("update"), &path!["update"]),
+        _ => Ok(()),
+    }
+}
+
+fn check_update(model: &Model, data: Option<&JsonValue>, path: &KeyPath) -> BoxedResult<()> {
+    let Some(JsonValue::Object(map)) = data else {
+        return Ok(());
+    };
+    for (key, value) in map {
+        let Some(relation) = model.relation(key) else { continue };
+        let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) else { continue };
+        let Some(operations) = value.as_object() else { continue };
+        let path = path + key.as_str();
+        if !relation.is_vec && relation.through.is_none() {
+            if operations.get("disconnect") == Some(&JsonValue::Bool(true)) && foreign_key_required(model, related, relation) {
+                Err(path::Error::value_error(&path + "disconnect", "cannot disconnect required relation"))?
+            }
+            if operations.get("delete") == Some(&JsonValue::Bool(true)) && owns_foreign_key(model, relation) && foreign_key_required(model, related, relation) {
+                Err(path::Error::value_error(&path + "delete", "cannot delete required relation"))?
+            }
+            if let Some(update) = operations.get("update") {
+                check_update(related, Some(update), &(&path + "update"))?;
+            }
+            if let Some(upsert) = operations.get("upsert") {
+                check_update(related, upsert.get("update"), &(&path + "upsert" + "update"))?;
+            }
+        } else if let Some(JsonValue::Array(updates)) = operations.get("update") {
+            for (index, update) in updates.iter().enumerate() {
+                check_update(related, update.get("update"), &(&path + "update" + index + "update"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// the foreign key is on the side whose relation fields are not its primary key
+fn owns_foreign_key(model: &Model, relation: &Relation) -> bool {
+    let primary_keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    !relation.fields.iter().all(|f| primary_keys.contains(f))
+}
+
+fn foreign_key_required(model: &Model, related: &Model, relation: &Relation) -> bool {
+    let (owner, keys) = if owns_foreign_key(model, relation) {
+        (model, &relation.fields)
+    } else {
+        (related, &relation.references)
+    };
+    keys.iter().filter_map(|k| owner.field(k)).any(|f| !f.is_optional())
+}
@@ -0,0 +1,110 @@
+use key_path::{KeyPath, path};
+use serde_json::Value as JsonValue;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, SINGLE, UPDATE};
+use teo_runtime::connection::transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::isolation::{IsolationLevel, run_isolated, TransactionOptions};
+use crate::server::compare::where_input;
+use crate::tree::{ancestors, descendants, move_subtree};
+use crate::server::action::handler_model;
+use crate::server::error::BoxedResult;
+
+const MOVE_RETRIES: usize = 5;
+
+pub(super) const TREE_ACTIONS: [&str; 3] = ["findDescendants", "findAncestors", "moveSubtree"];
+
+// the builtin action which has to be enabled for a tree action
+pub(super) fn tree_builtin_action(name: &str) -> Action {
+    builtin_action_handler_from_name(if name == "moveSubtree" { "update" } else { "findMany" }).unwrap()
+}
+
+pub(super) fn tree_input(model: &Model, name: &str, json_body: &JsonValue, main_namespace: &Namespace) -> BoxedResult<Value> {
+    let mut result = teon!({
+        "where": where_input(model, json_body.get("where"), "where", main_namespace)?,
+    });
+    let map = result.as_dictionary_mut().unwrap();
+    match name {
+        "findDescendants" => match json_body.get("depth") {
+            None | Some(JsonValue::Null) => (),
+            Some(depth) => match depth.as_u64() {
+                Some(depth) if depth > 0 => { map.insert("depth".to_owned(), Value::Int64(depth as i64)); }
+                _ => Err(path::Error::value_error(path!["depth"], "expect positive integer"))?,
+            },
+        },
+        "moveSubtree" => match json_body.get("parent") {
+            None => Err(path::Error::value_error_message_only("`parent' is required"))?,
+            Some(JsonValue::Null) => { map.insert("parent".to_owned(), Value::Null); }
+            parent => { map.insert("parent".to_owned(), where_input(model, parent, "parent", main_namespace)?); }
+        },
+        _ => (),
+    }
+    Ok(result)
+}
+
+pub(super) async fn tree(ctx: &request::Ctx) -> path::Result<Response> {
+    let model = handler_model(ctx)?;
+    match ctx.handler_match().handler_name() {
+        "findDescendants" => {
+            let object = find(ctx, ctx.transaction_ctx(), model, ctx.body().get("where").unwrap(), path!["where"]).await?;
+            let depth = ctx.body().get("depth").and_then(|d| d.to_int64()).map(|d| d as usize);
+            data(descendants(&ctx.transaction_ctx(), &object, depth, Some(ctx.clone())).await?).await
+        }
+        "findAncestors" => {
+            let object = find(ctx, ctx.transaction_ctx(), model, ctx.body().get("where").unwrap(), path!["where"]).await?;
+            data(ancestors(&ctx.transaction_ctx(), &object, Some(ctx.clone())).await?).await
+        }
+        _ => {
+            let object = run_isolated(move_transaction(ctx, model), || async { ctx.transaction_ctx().run_transaction(|tctx: transaction::Ctx| async move {
+                let object = find(ctx, tctx.clone(), model, ctx.body().get("where").unwrap(), path!["where"]).await?;
+                let parent = match ctx.body().get("parent").unwrap() {
+                    Value::Null => None,
+                    r#where => Some(find(ctx, tctx.clone(), model, r#where, path!["parent"]).await?),
+                };
+                move_subtree(&tctx, &object, parent.as_ref(), path!["parent"]).await?;
+                Ok(object)
+            }).await }).await?;
+            Ok(Response::data(object.to_teon_internal(&path!["data"]).await?))
+        }
+    }
+}
+
+// two moves at the same time, e.g. `a' under `b' and `b' under `a', would both pass the cycle
+// check under READ COMMITTED, serializable transactions fail one of them, which runs again and
+// sees the other's move. MongoDB has no isolation levels, its moves aren't isolated
+fn move_transaction(ctx: &request::Ctx, model: &Model) -> TransactionOptions {
+    let namespace = ctx.transaction_ctx().namespace();
+    let provider = namespace.namespace_at_path(&model.namespace_path())
+        .and_then(|n| n.connector_reference())
+        .and_then(|path| namespace.namespace_at_path(&path))
+        .and_then(|n| n.connector.as_ref())
+        .map(|c| c.provider);
+    match provider {
+        Some(Database::MongoDB) | None => TransactionOptions::default(),
+        Some(_) => TransactionOptions { isolation: Some(IsolationLevel::Serializable), retries: MOVE_RETRIES },
+    }
+}
+
+async fn find(ctx: &request::Ctx, tctx: transaction::Ctx, model: &'static Model, r#where: &Value, path: KeyPath) -> path::Result<Object> {
+    let action: Action = if ctx.handler_match().handler_name() == "moveSubtree" { UPDATE | SINGLE | ENTRY } else { FIND | SINGLE | ENTRY };
+    match tctx.find_unique_internal(model, &teon!({"where": r#where.clone()}), true, action, Some(ctx.clone()), path.clone()).await? {
+        Some(object) => Ok(object),
+        None => Err(path::Error::not_found(path)),
+    }
+}
+
+async fn data(objects: Vec<Object>) -> path::Result<Response> {
+    let mut values = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        values.push(object.to_teon_internal(&path!["data", index]).await?);
+    }
+    Ok(Response::data(Value::Array(values)))
+}
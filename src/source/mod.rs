@@ -0,0 +1,20 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_teon::value::Value;
+
+const DATA_KEY: &str = "source";
+
+/// Loads `@source`, which stores the records of a model in a registered secondary database.
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("source", |args: Arguments, model: &mut Model| {
+        let name: String = args.get("name")?;
+        model.data.insert(DATA_KEY.to_owned(), Value::String(name).into());
+        Ok(())
+    });
+}
+
+/// The name of the secondary database of `model`, if it has one.
+pub fn source(model: &Model) -> Option<&str> {
+    model.data.get(DATA_KEY).and_then(|o| o.as_teon()).and_then(|v| v.as_str())
+}
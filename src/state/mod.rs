@@ -0,0 +1,132 @@
+use key_path::KeyPath;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::{Field, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::object::Object as RuntimeObject;
+use teo_runtime::path;
+use teo_runtime::pipeline;
+use teo_runtime::pipeline::pipeline::Pipeline;
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+
+const DATA_KEY: &str = "state";
+const HOOKS_DATA_KEY: &str = "onTransition";
+const ANY: &str = "*";
+
+pub(crate) fn load_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("state", |args: Arguments, field: &mut Field| {
+        let transitions: Vec<String> = args.get("transitions")?;
+        for transition in &transitions {
+            parse_transition(transition)?;
+        }
+        field.data.insert(DATA_KEY.to_owned(), Value::Array(transitions.into_iter().map(Value::String).collect()).into());
+        Ok(())
+    });
+    // a field may have a hook for each of its transitions, they're kept in the order of the schema
+    namespace.define_model_field_decorator("onTransition", |args: Arguments, field: &mut Field| {
+        let from: Value = args.get("from")?;
+        let to: Value = args.get("to")?;
+        let pipeline: Pipeline = args.get("pipeline")?;
+        let (Some(from), Some(to)) = (state_name(&from), state_name(&to)) else {
+            Err(Error::new("onTransition(from, to) expect states"))?
+        };
+        let hook: RuntimeObject = vec![RuntimeObject::from(Value::String(from)), RuntimeObject::from(Value::String(to)), pipeline.into()].into();
+        let mut hooks = field.data.get(HOOKS_DATA_KEY).and_then(|h| h.as_array()).cloned().unwrap_or_default();
+        hooks.push(hook);
+        field.data.insert(HOOKS_DATA_KEY.to_owned(), hooks.into());
+        Ok(())
+    });
+}
+
+// transitions are written as `pending -> approved', `*' matches every state
+fn parse_transition(transition: &str) -> Result<(String, String)> {
+    match transition.split_once("->") {
+        Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => Ok((from.trim().to_owned(), to.trim().to_owned())),
+        _ => Err(Error::new(format!("invalid state transition `{}', expect `from -> to'", transition))),
+    }
+}
+
+pub fn transitions(field: &Field) -> Option<Vec<(String, String)>> {
+    let value = field.data.get(DATA_KEY).and_then(|o| o.as_teon())?;
+    Some(value.as_array()?.iter().filter_map(|t| t.as_str()).filter_map(|t| parse_transition(t).ok()).collect())
+}
+
+pub fn transition_allowed(field: &Field, from: &str, to: &str) -> bool {
+    if from == to {
+        return true;
+    }
+    match transitions(field) {
+        Some(transitions) => transitions.iter().any(|(f, t)| (f == from || f == ANY) && t == to),
+        None => true,
+    }
+}
+
+// runs before every record is saved, so nested writes and many updates are validated too, the
+// record loaded in the transaction keeps the state it had before the update
+pub(crate) fn check_transition(object: &Object, path: &KeyPath) -> BoxedResult<()> {
+    if object.is_new() {
+        return Ok(());
+    }
+    for field in object.model().fields() {
+        if transitions(field).is_none() {
+            continue;
+        }
+        let Some(from) = state_name(&object.get_previous_value(field.name.as_str())?) else {
+            continue;
+        };
+        let Some(to) = state_name(&object.get_value(field.name.as_str())?) else {
+            continue;
+        };
+        if !transition_allowed(field, &from, &to) {
+            return Err(path::Error::value_error(path + field.name.as_str(), format!("transition from `{}' to `{}' is not allowed", from, to)).into());
+        }
+    }
+    Ok(())
+}
+
+/// The `@onTransition` pipelines of `field` with the states they're run between.
+pub fn transition_hooks(field: &Field) -> Vec<(String, String, &Pipeline)> {
+    field.data.get(HOOKS_DATA_KEY).and_then(|h| h.as_array()).into_iter().flatten().filter_map(|hook| {
+        let hook = hook.as_array()?;
+        let from = hook.first()?.as_teon()?.as_str()?.to_owned();
+        let to = hook.get(1)?.as_teon()?.as_str()?.to_owned();
+        Some((from, to, hook.get(2)?.as_pipeline()?))
+    }).collect()
+}
+
+// runs after the transitions are checked and before the record is saved, in the transaction of
+// the save, so a failing hook rolls back the write it belongs to
+pub(crate) async fn run_transition_hooks(object: &Object, path: &KeyPath) -> path::Result<()> {
+    if object.is_new() {
+        return Ok(());
+    }
+    for field in object.model().fields() {
+        let hooks = transition_hooks(field);
+        if hooks.is_empty() {
+            continue;
+        }
+        let Some(from) = state_name(&object.get_previous_value(field.name.as_str())?) else {
+            continue;
+        };
+        let Some(to) = state_name(&object.get_value(field.name.as_str())?) else {
+            continue;
+        };
+        if from == to {
+            continue;
+        }
+        for (_, _, pipeline) in hooks.iter().filter(|(f, t, _)| (*f == from || f == ANY) && *t == to) {
+            let ctx = pipeline::Ctx::new(object.clone().into(), object.clone(), path + field.name.as_str(), object.action(), object.transaction_ctx(), object.request_ctx());
+            ctx.run_pipeline_into_path_value_error(pipeline).await?;
+        }
+    }
+    Ok(())
+}
+
+fn state_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::EnumVariant(e) => Some(e.value.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,238 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::{json, Value as JsonValue};
+use teo_runtime::path;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::{timeout_at, Instant};
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+pub(crate) const TRACEPARENT_HEADER: &str = "traceparent";
+
+const BATCH_SIZE: usize = 512;
+const BATCH_DELAY: Duration = Duration::from_millis(500);
+
+/// Where the spans are exported to, over OTLP with HTTP and JSON. The defaults come from the
+/// `OTEL_EXPORTER_OTLP_ENDPOINT`, `OTEL_EXPORTER_OTLP_HEADERS` and `OTEL_SERVICE_NAME`
+/// environment variables, or else a collector on localhost.
+#[derive(Debug, Clone)]
+pub struct TelemetryOptions {
+    /// The base URL of the collector, spans are posted to `/v1/traces` under it.
+    pub endpoint: String,
+    pub service_name: String,
+    /// Sent with each export, e.g. the API key of a hosted collector.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for TelemetryOptions {
+
+    fn default() -> Self {
+        Self {
+            endpoint: std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").unwrap_or("http://localhost:4318".to_owned()),
+            service_name: std::env::var("OTEL_SERVICE_NAME").unwrap_or("teo".to_owned()),
+            headers: std::env::var("OTEL_EXPORTER_OTLP_HEADERS").map(|headers| headers.split(',').filter_map(|h| {
+                let (key, value) = h.split_once('=')?;
+                Some((key.trim().to_owned(), value.trim().to_owned()))
+            }).collect()).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Exporter {
+    options: TelemetryOptions,
+    // started on the first span, the app may be set up outside of the runtime
+    queue: OnceLock<UnboundedSender<JsonValue>>,
+}
+
+impl Exporter {
+
+    pub(crate) fn new(options: TelemetryOptions) -> Self {
+        Self { options, queue: OnceLock::new() }
+    }
+
+    fn send(&self, span: JsonValue) {
+        let queue = self.queue.get_or_init(|| {
+            let (sender, receiver) = unbounded_channel();
+            tokio::spawn(export(self.options.clone(), receiver));
+            sender
+        });
+        let _ = queue.send(span);
+    }
+}
+
+// the spans which end within the delay of the first one are posted together
+async fn export(options: TelemetryOptions, mut receiver: UnboundedReceiver<JsonValue>) {
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/traces", options.endpoint.trim_end_matches('/'));
+    while let Some(span) = receiver.recv().await {
+        let mut spans = vec![span];
+        let deadline = Instant::now() + BATCH_DELAY;
+        while spans.len() < BATCH_SIZE {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(span)) => spans.push(span),
+                _ => break,
+            }
+        }
+        let body = json!({
+            "resourceSpans": [{
+                "resource": { "attributes": [attribute("service.name", json!(options.service_name))] },
+                "scopeSpans": [{
+                    "scope": { "name": "teo", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        });
+        let mut request = client.post(&url).json(&body);
+        for (key, value) in &options.headers {
+            request = request.header(key, value);
+        }
+        match request.send().await {
+            Ok(response) if !response.status().is_success() => info_message(format!("OTLP collector at {} answered {}", url, response.status())),
+            Err(error) => info_message(format!("cannot export spans to {}: {}", url, error)),
+            Ok(_) => (),
+        }
+    }
+}
+
+/// The trace a span belongs to, and the span the spans started in its scope are children of.
+#[derive(Debug, Clone)]
+pub(crate) struct SpanContext {
+    trace_id: String,
+    span_id: String,
+    // an unsampled trace is propagated without exporting its spans
+    sampled: bool,
+}
+
+tokio::task_local! {
+    static CURRENT: SpanContext;
+}
+
+/// The span context of a W3C `traceparent` header.
+pub(crate) fn parse_traceparent(header: &str) -> Option<SpanContext> {
+    let mut parts = header.trim().split('-');
+    let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if !hex(version, 2) || version == "ff" || !hex(trace_id, 32) || !hex(span_id, 16) || !hex(flags, 2) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') || span_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    Some(SpanContext {
+        trace_id: trace_id.to_owned(),
+        span_id: span_id.to_owned(),
+        sampled: u8::from_str_radix(flags, 16).ok()? & 1 == 1,
+    })
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum SpanKind {
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+}
+
+/// A span which is exported when it ends, the spans started in its scope are its children.
+pub(crate) struct Span {
+    exporter: Arc<Exporter>,
+    context: SpanContext,
+    parent: Option<String>,
+    name: String,
+    kind: SpanKind,
+    start: SystemTime,
+    attributes: Vec<JsonValue>,
+    error: Option<String>,
+}
+
+impl Span {
+
+    /// A child of the span in scope, or the root of a new trace. `None` without telemetry.
+    pub(crate) fn start(name: impl Into<String>, kind: SpanKind) -> Option<Span> {
+        Self::start_with_parent(name, kind, CURRENT.try_with(Clone::clone).ok())
+    }
+
+    pub(crate) fn start_with_parent(name: impl Into<String>, kind: SpanKind, parent: Option<SpanContext>) -> Option<Span> {
+        let exporter = Ctx::settings().telemetry.clone()?;
+        let context = SpanContext {
+            trace_id: parent.as_ref().map_or_else(|| hex_id(16), |p| p.trace_id.clone()),
+            span_id: hex_id(8),
+            sampled: parent.as_ref().is_none_or(|p| p.sampled),
+        };
+        Some(Span { exporter, context, parent: parent.map(|p| p.span_id), name: name.into(), kind, start: SystemTime::now(), attributes: vec![], error: None })
+    }
+
+    pub(crate) fn rename(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    pub(crate) fn set(&mut self, key: &str, value: JsonValue) {
+        self.attributes.push(attribute(key, value));
+    }
+
+    pub(crate) fn fail(&mut self, message: impl Into<String>) {
+        self.error = Some(message.into());
+    }
+
+    /// Runs `f` with this span as the parent of the spans it starts.
+    pub(crate) async fn scope<F: Future>(&self, f: F) -> F::Output {
+        CURRENT.scope(self.context.clone(), f).await
+    }
+
+    pub(crate) fn end(self) {
+        if !self.context.sampled {
+            return;
+        }
+        let mut span = json!({
+            "traceId": self.context.trace_id,
+            "spanId": self.context.span_id,
+            "name": self.name,
+            "kind": self.kind as u8,
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": self.attributes,
+            "status": match &self.error {
+                Some(message) => json!({ "code": 2, "message": message }),
+                None => json!({ "code": 0 }),
+            },
+        });
+        if let Some(parent) = self.parent {
+            span["parentSpanId"] = json!(parent);
+        }
+        self.exporter.send(span);
+    }
+}
+
+/// Runs the handler `f` in a span of the phase which ends with its result.
+pub(crate) async fn traced<T, F>(name: impl FnOnce() -> String, f: F) -> path::Result<T> where F: Future<Output = path::Result<T>> {
+    let Some(mut span) = Span::start(name(), SpanKind::Internal) else {
+        return f.await;
+    };
+    let output = span.scope(f).await;
+    if let Err(error) = &output {
+        span.fail(&error.message);
+    }
+    span.end();
+    output
+}
+
+// integers are written as strings in OTLP JSON, they're 64 bits
+fn attribute(key: &str, value: JsonValue) -> JsonValue {
+    let value = match value {
+        JsonValue::Bool(b) => json!({ "boolValue": b }),
+        JsonValue::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        JsonValue::Number(n) => json!({ "doubleValue": n }),
+        JsonValue::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn hex_id(bytes: usize) -> String {
+    (0..bytes).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
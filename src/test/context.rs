@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use actix_http::Request;
+use actix_web::body::BoxBody;
+use actix_web::dev::ServiceResponse;
+use serde_json::Value as JsonValue;
+use teo_result::Result;
+use teo_runtime::database::database::Database;
+use url::Url;
+use uuid::Uuid;
+use crate::app::ctx::Ctx;
+use crate::app::database::connection_for_url;
+use crate::app::database::connection::{context_key, in_context, ContextConnections};
+use crate::migrate::migrate;
+use crate::purge::purge;
+use crate::test::server::{drop_database, TestServer};
+
+/// A test context of a `TestServer` with databases of its own. Its requests run on them in place
+/// of the databases of the server, so the contexts of a server don't see each other's records and
+/// their requests run concurrently, e.g. in `join!`. Each SQLite file gets a file of its own next
+/// to it, and each MySQL, PostgreSQL and MongoDB database a database with the suffix of the
+/// context. The databases are migrated when the context is created and dropped with it. SQLite
+/// in memory, ClickHouse, sources and the databases of registered connector providers are shared
+/// with the server.
+pub struct TestContext<'a> {
+    server: &'a TestServer,
+    connections: ContextConnections,
+    databases: Vec<(Database, String)>,
+    files: Vec<PathBuf>,
+}
+
+impl<'a> TestContext<'a> {
+
+    pub(super) async fn new(server: &'a TestServer) -> Result<Self> {
+        let suffix = Uuid::new_v4().simple().to_string()[0..8].to_owned();
+        let conn_ctx = Ctx::conn_ctx();
+        let mut context = Self { server, connections: Arc::new(BTreeMap::new()), databases: vec![], files: vec![] };
+        let mut connections = BTreeMap::new();
+        for (path, connection) in conn_ctx.connections_iter() {
+            let Some(connector) = conn_ctx.namespace().namespace_at_path(&path.iter().map(AsRef::as_ref).collect()).and_then(|n| n.connector.as_ref()) else {
+                continue;
+            };
+            let Some(url) = context_url(connector.provider, &connector.url, &suffix) else {
+                continue;
+            };
+            match connector.provider {
+                Database::SQLite => context.files.push(PathBuf::from(url.trim_start_matches("sqlite:"))),
+                provider => context.databases.push((provider, url.clone())),
+            }
+            connections.insert(context_key(connection), connection_for_url(&url, Some(connector.provider)).await?);
+        }
+        context.connections = Arc::new(connections);
+        in_context(context.connections.clone(), migrate(false, false, true)).await?;
+        Ok(context)
+    }
+
+    pub async fn request(&self, model: &str, action: &str, body: JsonValue) -> Result<JsonValue> {
+        in_context(self.connections.clone(), self.server.request(model, action, body)).await
+    }
+
+    pub async fn request_at_path(&self, path: &str, body: JsonValue) -> Result<JsonValue> {
+        in_context(self.connections.clone(), self.server.request_at_path(path, body)).await
+    }
+
+    pub async fn request_at_path_with_headers(&self, path: &str, body: JsonValue, headers: &[(&str, &str)]) -> Result<JsonValue> {
+        in_context(self.connections.clone(), self.server.request_at_path_with_headers(path, body, headers)).await
+    }
+
+    pub async fn call(&self, request: Request) -> ServiceResponse<BoxBody> {
+        in_context(self.connections.clone(), self.server.call(request)).await
+    }
+
+    pub async fn reset(&self) -> Result<()> {
+        in_context(self.connections.clone(), purge()).await
+    }
+
+    /// Seeds the named data sets into the databases of the context.
+    pub async fn seed(&self, names: &[&str]) -> Result<()> {
+        in_context(self.connections.clone(), self.server.seed(names)).await
+    }
+}
+
+impl Drop for TestContext<'_> {
+
+    fn drop(&mut self) {
+        // the connections are closed before their databases are dropped
+        self.connections = Arc::new(BTreeMap::new());
+        let databases = std::mem::take(&mut self.databases);
+        if !databases.is_empty() {
+            let _ = thread::spawn(move || -> Result<()> {
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+                for database in databases {
+                    runtime.block_on(drop_database(database))?;
+                }
+                Ok(())
+            }).join();
+        }
+        for file in &self.files {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+fn context_url(provider: Database, url: &str, suffix: &str) -> Option<String> {
+    if url.starts_with("clickhouse") {
+        return None;
+    }
+    match provider {
+        Database::SQLite if !url.starts_with("sqlite:") || url.contains(":memory:") => None,
+        Database::SQLite => {
+            let path = PathBuf::from(url.trim_start_matches("sqlite:"));
+            let name = format!("{}_{}.sqlite", path.file_stem()?.to_str()?, suffix);
+            Some(format!("sqlite:{}", path.with_file_name(name).display()))
+        }
+        _ => {
+            let mut url = Url::parse(url).ok()?;
+            let database_name = url.path().trim_start_matches('/').to_owned();
+            url.set_path(&format!("{}_{}", database_name, suffix));
+            Some(url.to_string())
+        }
+    }
+}
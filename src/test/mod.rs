@@ -0,0 +1,7 @@
+pub mod server;
+pub mod context;
+pub mod snapshot;
+
+pub use server::TestServer;
+pub use context::TestContext;
+pub use snapshot::{Snapshot, assert_snapshot};
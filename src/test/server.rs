@@ -0,0 +1,270 @@
+use std::fs;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use actix_http::Request;
+use actix_web::body::BoxBody;
+use actix_web::dev::ServiceResponse;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use futures_util::future::LocalBoxFuture;
+use futures_util::FutureExt;
+use once_cell::sync::Lazy;
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction;
+use teo_runtime::database::database::Database;
+use teo_sql_connector::connector::SQLConnection;
+use teo_sql_connector::schema::dialect::SQLDialect;
+use teo_teon::value::Value;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio::time::timeout;
+use url::Url;
+use uuid::Uuid;
+use crate::app::App;
+use crate::app::ctx::Ctx;
+use crate::app::database::connect_main_namespace;
+use crate::app::database::case::{begin_case, end_case};
+use crate::cli::command::{CLI, CLICommand, SeedCommandAction, ServeCommand};
+use crate::migrate::migrate;
+use crate::migrate::data_sets::load_seed_data_sets;
+use crate::purge::purge;
+use crate::seeder::seed::seed;
+use crate::server::make::make_server_app;
+use crate::test::context::TestContext;
+
+// the app is global to the process, so the instances in a process take turns
+static INSTANCE: Lazy<Arc<Mutex<()>>> = Lazy::new(|| Arc::new(Mutex::new(())));
+
+// an instance which is never dropped fails the tests waiting behind it instead of hanging the run
+const INSTANCE_TIMEOUT: Duration = Duration::from_secs(600);
+
+type Service = Rc<dyn Fn(Request) -> LocalBoxFuture<'static, ServiceResponse<BoxBody>>>;
+
+/// A server for integration tests. Each instance runs against its own database, a SQLite file in
+/// the instance directory when the schema names a file or memory, or a randomly suffixed
+/// database name for the other connectors, which is dropped with the instance. Instances in a
+/// process are created one after another, `new` waits until the previous instance is dropped, and
+/// fails after ten minutes. The requests of an instance run in parallel in test contexts, each
+/// with databases of its own, see `context`.
+pub struct TestServer {
+    app: App,
+    directory: PathBuf,
+    service: Service,
+    database: Option<(Database, String)>,
+    // fields are dropped in order, the app is retired before the next instance may create its own
+    _app: Retire,
+    _instance: OwnedMutexGuard<()>,
+}
+
+struct Retire;
+
+impl Drop for Retire {
+
+    fn drop(&mut self) {
+        Ctx::retire()
+    }
+}
+
+impl TestServer {
+
+    pub async fn new(schema: impl AsRef<str>) -> Result<Self> {
+        Self::new_with(schema, |_| Ok(())).await
+    }
+
+    /// Like `new`, `setup` configures the app before the schema is loaded, e.g. defines pipeline
+    /// items or registers sources and connector providers.
+    pub async fn new_with<F>(schema: impl AsRef<str>, setup: F) -> Result<Self> where F: FnOnce(&App) -> Result<()> {
+        let Ok(instance) = timeout(INSTANCE_TIMEOUT, INSTANCE.clone().lock_owned()).await else {
+            Err(Error::new("timed out waiting for the previous test server to be dropped"))?
+        };
+        let directory = std::env::temp_dir().join(format!("teo-test-{}", Uuid::new_v4()));
+        if let Err(e) = fs::create_dir_all(&directory) {
+            Err(Error::new(format!("{}", e)))?
+        }
+        let schema_file = directory.join("schema.teo");
+        if let Err(e) = fs::write(&schema_file, schema.as_ref()) {
+            Err(Error::new(format!("{}", e)))?
+        }
+        if !Ctx::create() {
+            Err(Error::new("cannot create test server while there is an existing instance"))?
+        }
+        // retires the app when creating the instance fails
+        let retire = Retire;
+        let app = App::new_with_cli(CLI {
+            command: CLICommand::Serve(ServeCommand { no_migration: false, no_autoseed: true, env: None, record: None, record_window: None, record_credentials: false, strict_schema: false, tls: None }),
+            schema: Some(schema_file.to_str().unwrap().to_owned()),
+            silent: true,
+        }, false)?;
+        setup(&app)?;
+        app.prepare_for_run().await?;
+        let database = isolate_connector_url(&directory)?;
+        connect_main_namespace(true).await?;
+        migrate(false, false, true).await?;
+        purge().await?;
+        let namespace = Ctx::conn_ctx().namespace();
+        let conf = match namespace.server.as_ref() {
+            Some(conf) => conf,
+            None => Err(Error::new("test schema requires a server config"))?,
+        };
+        let service = Rc::new(init_service(make_server_app(namespace, conf)).await);
+        let service: Service = Rc::new(move |request| {
+            let service = service.clone();
+            Box::pin(async move { call_service(&*service, request).await.map_into_boxed_body() })
+        });
+        Ok(Self { app, directory, service, database, _app: retire, _instance: instance })
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    /// A test context with databases of its own, see `TestContext`.
+    pub async fn context(&self) -> Result<TestContext<'_>> {
+        TestContext::new(self).await
+    }
+
+    pub async fn request(&self, model: &str, action: &str, body: JsonValue) -> Result<JsonValue> {
+        self.request_at_path(&format!("/{model}/{action}"), body).await
+    }
+
+    pub async fn request_at_path(&self, path: &str, body: JsonValue) -> Result<JsonValue> {
+        self.request_at_path_with_headers(path, body, &[]).await
+    }
+
+    pub async fn request_at_path_with_headers(&self, path: &str, body: JsonValue, headers: &[(&str, &str)]) -> Result<JsonValue> {
+        let mut request = TestRequest::post().uri(path).set_json(body);
+        for header in headers {
+            request = request.insert_header(*header);
+        }
+        let request = request.to_request();
+        let response = (self.service)(request).await;
+        let bytes = read_body(response).await;
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => Ok(value),
+            Err(e) => Err(Error::new(format!("{}", e))),
+        }
+    }
+
+    /// Sends `request` as it is, for the requests and responses which aren't JSON, e.g. files.
+    pub async fn call(&self, request: Request) -> ServiceResponse<BoxBody> {
+        (self.service)(request).await
+    }
+
+    pub async fn reset(&self) -> Result<()> {
+        purge().await
+    }
+
+    /// Clears the database and seeds the named data sets. Records are deleted and created again,
+    /// the writes of the tests before stay until the next reset, `isolated` rolls them back.
+    pub async fn reset_to(&self, names: &[&str]) -> Result<()> {
+        purge().await?;
+        self.seed(names).await
+    }
+
+    /// Runs the test case `f` on the named data sets, seeded on top of the current records, and
+    /// rolls back its writes when it's done, so the next case starts from the same records. The
+    /// case runs in a transaction, the transaction of each action in it is a savepoint, a failing
+    /// action rolls back its own writes only. Cases are rolled back on the SQL databases, a test
+    /// on MongoDB creates an instance, with a database of its own, instead.
+    pub async fn isolated<F, Fut, R>(&self, names: &[&str], f: F) -> Result<R> where F: FnOnce() -> Fut, Fut: Future<Output = R> {
+        begin_case().await?;
+        let output = AssertUnwindSafe(async {
+            self.seed(names).await?;
+            Ok(f().await)
+        }).catch_unwind().await;
+        end_case().await?;
+        match output {
+            Ok(output) => output,
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+
+    /// Seeds the named data sets on top of the current records.
+    pub async fn seed(&self, names: &[&str]) -> Result<()> {
+        let names = names.iter().map(|n| n.to_string()).collect();
+        let data_sets = load_seed_data_sets(Some(&names), false)?;
+        let missing: Vec<&str> = names.iter().filter(|n| !data_sets.iter().any(|d| &d.name.join(".") == *n)).map(AsRef::as_ref).collect();
+        if !missing.is_empty() {
+            Err(Error::new(format!("data sets are not found: {}", missing.join(", "))))?
+        }
+        seed(SeedCommandAction::Seed, data_sets, transaction::Ctx::new(Ctx::conn_ctx()), false).await
+    }
+}
+
+impl Drop for TestServer {
+
+    fn drop(&mut self) {
+        if let Some(database) = self.database.take() {
+            // drop can't await, the database is dropped from a runtime of its own
+            let _ = thread::spawn(move || -> Result<()> {
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+                runtime.block_on(drop_database(database))
+            }).join();
+        }
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+// returns the provider and url of the test database if it has to be dropped
+fn isolate_connector_url(directory: &Path) -> Result<Option<(Database, String)>> {
+    Ctx::with_main_namespace_mut(|namespace| {
+        let connector = namespace.connector.as_mut()?;
+        // ClickHouse databases are managed by ClickHouse, the tests read the one they are given
+        if connector.url.starts_with("clickhouse") {
+            return None;
+        }
+        match connector.provider {
+            // the URLs of the registered providers are theirs
+            Database::SQLite if !connector.url.starts_with("sqlite:") => None,
+            // the connector keeps SQLite in memory in one connection for the process, the instances
+            // would share it, so it's a file as well, which is removed with the directory
+            Database::SQLite => {
+                let file_name = match connector.url.contains(":memory:") {
+                    true => "memory.sqlite".into(),
+                    false => Path::new(connector.url.trim_start_matches("sqlite:")).file_name()?.to_owned(),
+                };
+                connector.url = format!("sqlite:{}", directory.join(file_name).display());
+                None
+            }
+            _ => {
+                let mut url = Url::parse(&connector.url).ok()?;
+                let database_name = url.path().trim_start_matches('/').to_owned();
+                let suffix = Uuid::new_v4().simple().to_string();
+                url.set_path(&format!("{}_test_{}", database_name, &suffix[0..8]));
+                connector.url = url.to_string();
+                Some((connector.provider, connector.url.clone()))
+            }
+        }
+    })
+}
+
+pub(super) async fn drop_database((provider, url): (Database, String)) -> Result<()> {
+    let mut url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(e) => Err(Error::new(format!("{}", e)))?,
+    };
+    let database_name = url.path().trim_start_matches('/').to_owned();
+    let (dialect, statement) = match provider {
+        Database::MongoDB => {
+            let client = mongodb::Client::with_uri_str(url.as_str()).await.map_err(|e| Error::new(format!("{}", e)))?;
+            return client.database(&database_name).drop(None).await.map_err(|e| Error::new(format!("{}", e)));
+        }
+        Database::MySQL => {
+            url.set_path("/mysql");
+            (SQLDialect::MySQL, format!("DROP DATABASE IF EXISTS `{}`", database_name))
+        }
+        Database::PostgreSQL => {
+            url.set_path("/postgres");
+            (SQLDialect::PostgreSQL, format!("DROP DATABASE IF EXISTS \"{}\" WITH (FORCE)", database_name))
+        }
+        Database::SQLite => return Ok(()),
+    };
+    let connection = SQLConnection::new(dialect, url.as_str(), false).await;
+    connection.no_transaction().await?.query_raw(&Value::String(statement)).await?;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+
+static OBJECT_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^[0-9a-f]{24}$").unwrap());
+static DATE_TIME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^\\d{4}-\\d{2}-\\d{2}(T\\d{2}:\\d{2}:\\d{2}(\\.\\d+)?(Z|[+-]\\d{2}:\\d{2})?)?$").unwrap());
+
+pub struct Snapshot {
+    directory: PathBuf,
+    volatile_keys: Vec<String>,
+}
+
+impl Snapshot {
+
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_owned(),
+            volatile_keys: vec!["id".to_owned(), "createdAt".to_owned(), "updatedAt".to_owned()],
+        }
+    }
+
+    pub fn volatile_key(mut self, key: impl Into<String>) -> Self {
+        self.volatile_keys.push(key.into());
+        self
+    }
+
+    /// Masks the primary keys of `model` whose values are generated by the database or a default.
+    pub fn generated_keys(mut self, model: &Model) -> Self {
+        let Some(index) = model.primary_index() else {
+            return self;
+        };
+        for key in index.keys() {
+            if model.field(key).is_some_and(|f| f.auto || f.auto_increment || f.default.is_some()) && !self.volatile_keys.contains(key) {
+                self.volatile_keys.push(key.clone());
+            }
+        }
+        self
+    }
+
+    pub fn assert(&self, name: &str, value: &JsonValue) -> Result<()> {
+        let actual = match serde_json::to_string_pretty(&self.normalize(None, value)) {
+            Ok(actual) => actual + "\n",
+            Err(e) => Err(Error::new(format!("{}", e)))?,
+        };
+        let file = self.directory.join(format!("{}.json", name));
+        if !file.exists() || env::var("TEO_UPDATE_SNAPSHOTS").is_ok() {
+            if let Err(e) = fs::create_dir_all(&self.directory) {
+                Err(Error::new(format!("{}", e)))?
+            }
+            if let Err(e) = fs::write(&file, actual) {
+                Err(Error::new(format!("{}", e)))?
+            }
+            return Ok(());
+        }
+        let expected = match fs::read_to_string(&file) {
+            Ok(expected) => expected,
+            Err(e) => Err(Error::new(format!("{}", e)))?,
+        };
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(Error::new(format!("snapshot `{}' does not match, set TEO_UPDATE_SNAPSHOTS=1 to update\n{}", name, diff(&expected, &actual))))
+        }
+    }
+
+    fn normalize(&self, key: Option<&str>, value: &JsonValue) -> JsonValue {
+        match value {
+            JsonValue::Object(map) => {
+                if map.len() == 1 && key.is_some_and(|k| self.is_volatile_key(k)) {
+                    if map.contains_key("$datetime") {
+                        return JsonValue::String("[dateTime]".to_owned());
+                    }
+                    if map.contains_key("$date") || map.contains_key("$decimal") {
+                        return JsonValue::String("[volatile]".to_owned());
+                    }
+                }
+                let mut result = Map::new();
+                for (k, v) in map {
+                    result.insert(k.clone(), self.normalize(Some(k.as_str()), v));
+                }
+                JsonValue::Object(result)
+            }
+            JsonValue::Array(values) => JsonValue::Array(values.iter().map(|v| self.normalize(key, v)).collect()),
+            JsonValue::String(s) if OBJECT_ID_REGEX.is_match(s) => JsonValue::String("[objectId]".to_owned()),
+            JsonValue::String(s) if DATE_TIME_REGEX.is_match(s) && key.is_some_and(|k| self.is_volatile_key(k)) => JsonValue::String("[dateTime]".to_owned()),
+            JsonValue::Null => JsonValue::Null,
+            _ => if key.is_some_and(|k| self.is_volatile_key(k)) {
+                JsonValue::String("[volatile]".to_owned())
+            } else {
+                value.clone()
+            }
+        }
+    }
+
+    fn is_volatile_key(&self, key: &str) -> bool {
+        self.volatile_keys.iter().any(|k| k == key)
+    }
+}
+
+pub fn assert_snapshot(name: &str, value: &JsonValue) -> Result<()> {
+    let base = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("."));
+    Snapshot::new(base.join("tests").join("snapshots")).assert(name, value)
+}
+
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut lengths = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            result.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            result.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            result.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+    result.join("\n")
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use key_path::{KeyPath, path};
+use maplit::btreemap;
+use teo_runtime::action::Action;
+use teo_runtime::action::action::{ENTRY, FIND, MANY};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object, Relation};
+use teo_runtime::path;
+use teo_runtime::request;
+use teo_teon::teon;
+use teo_teon::value::Value;
+use crate::server::error::BoxedResult;
+
+const CHUNK_SIZE: usize = 500;
+// the records are read level by level into memory, larger trees are rejected instead of read
+pub const MAX_RECORDS: usize = 10_000;
+
+// the self referencing relation which holds the foreign key, e.g. `parent'
+pub fn parent_relation(model: &Model) -> Option<&Relation> {
+    let primary_keys = model.primary_index().map(|i| i.keys().clone()).unwrap_or_default();
+    model.relations().into_iter().find(|r| {
+        !r.is_vec && r.through.is_none() && r.model == model.path && !r.fields.iter().all(|f| primary_keys.contains(f))
+    })
+}
+
+// breadth first, nearest descendants come first, fails with more than `MAX_RECORDS'
+pub async fn descendants(ctx: &transaction::Ctx, object: &Object, depth: Option<usize>, req_ctx: Option<request::Ctx>) -> path::Result<Vec<Object>> {
+    let model = object.model();
+    let relation = relation(model)?;
+    let mut visited = HashSet::from([format!("{}", object.identifier())]);
+    let mut result = vec![];
+    let mut level = vec![object.clone()];
+    let mut current_depth = 0;
+    while !level.is_empty() && depth.is_none_or(|d| current_depth < d) {
+        let mut next = vec![];
+        for chunk in level.chunks(CHUNK_SIZE) {
+            let mut or = vec![];
+            for parent in chunk {
+                let mut r#where = teon!({});
+                for (field, reference) in relation.fields.iter().zip(relation.references.iter()) {
+                    r#where.as_dictionary_mut().unwrap().insert(field.clone(), parent.get_value(reference)?);
+                }
+                or.push(r#where);
+            }
+            let action: Action = FIND | MANY | ENTRY;
+            let finder = teon!({"where": {"OR": Value::Array(or)}});
+            for child in ctx.find_many_internal(model, &finder, true, action, req_ctx.clone(), path![]).await? {
+                if visited.insert(format!("{}", child.identifier())) {
+                    next.push(child);
+                }
+            }
+            if result.len() + next.len() > MAX_RECORDS {
+                return Err(too_many_records("findDescendants"));
+            }
+        }
+        result.extend(next.iter().cloned());
+        level = next;
+        current_depth += 1;
+    }
+    Ok(result)
+}
+
+// the parent comes first, the root last, fails with more than `MAX_RECORDS'
+pub async fn ancestors(ctx: &transaction::Ctx, object: &Object, req_ctx: Option<request::Ctx>) -> path::Result<Vec<Object>> {
+    let model = object.model();
+    let relation = relation(model)?;
+    let mut visited = HashSet::from([format!("{}", object.identifier())]);
+    let mut result = vec![];
+    let mut current = object.clone();
+    while let Some(parent) = parent(ctx, &current, relation, req_ctx.clone()).await? {
+        if !visited.insert(format!("{}", parent.identifier())) {
+            break
+        }
+        if result.len() == MAX_RECORDS {
+            return Err(too_many_records("findAncestors"));
+        }
+        result.push(parent.clone());
+        current = parent;
+    }
+    Ok(result)
+}
+
+// moves a record with its subtree under `parent', or to the top level when `parent' is none. The
+// cycle check reads the ancestors of `parent' without locking them, `ctx' is serializable, so a
+// concurrent move can't close a cycle with this one
+pub async fn move_subtree(ctx: &transaction::Ctx, object: &Object, parent: Option<&Object>, path: KeyPath) -> path::Result<()> {
+    let model = object.model();
+    let relation = relation(model)?;
+    if let Some(parent) = parent {
+        if parent.identifier() == object.identifier() {
+            return Err(path::Error::value_error(path, "cannot move a record under itself"));
+        }
+        // ancestors hidden from the request would still close a cycle
+        for ancestor in ancestors(ctx, parent, None).await? {
+            if ancestor.identifier() == object.identifier() {
+                return Err(path::Error::value_error(path, "cannot move a record under its descendant"));
+            }
+        }
+    }
+    for (field, reference) in relation.fields.iter().zip(relation.references.iter()) {
+        let value = match parent {
+            Some(parent) => parent.get_value(reference)?,
+            None => Value::Null,
+        };
+        object.set_value(field, value)?;
+    }
+    object.save_with_session_and_path(&path![]).await?;
+    Ok(())
+}
+
+fn too_many_records(action: &str) -> path::Error {
+    path::Error {
+        title: "QueryTooComplex",
+        message: format!("{} finds more than {} records", action, MAX_RECORDS),
+        fields: None,
+        code: 400,
+        meta_map: btreemap! {},
+    }
+}
+
+fn relation(model: &Model) -> BoxedResult<&Relation> {
+    match parent_relation(model) {
+        Some(relation) => Ok(relation),
+        None => Err(path::Error::value_error_message_only(format!("model `{}' has no parent relation", model.path.join("."))).into()),
+    }
+}
+
+async fn parent(ctx: &transaction::Ctx, object: &Object, relation: &Relation, req_ctx: Option<request::Ctx>) -> path::Result<Option<Object>> {
+    let mut r#where = teon!({});
+    for (field, reference) in relation.fields.iter().zip(relation.references.iter()) {
+        let value = object.get_value(field)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        r#where.as_dictionary_mut().unwrap().insert(reference.clone(), value);
+    }
+    let action: Action = FIND | MANY | ENTRY;
+    let finder = teon!({"where": r#where, "take": 1i64});
+    Ok(ctx.find_many_internal(object.model(), &finder, true, action, req_ctx, path![]).await?.into_iter().next())
+}
@@ -0,0 +1,10 @@
+use ring::digest::{digest, SHA256};
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The first `len` bytes of the SHA-256 digest of `bytes` in hex.
+pub(crate) fn sha256_hex(bytes: &[u8], len: usize) -> String {
+    hex(&digest(&SHA256, bytes).as_ref()[..len])
+}
@@ -0,0 +1,67 @@
+use serde_json::Value;
+
+/// Declares the `test` module of a server test directory, serving its `schema.teo` on `PORT`.
+#[macro_export]
+macro_rules! server_tests {
+    ($port:expr, { $($body:tt)* }) => {
+        $crate::server_tests!($port, "serve", { $($body)* });
+    };
+    ($port:expr, example $example:literal, { $($body:tt)* }) => {
+        #[test_helpers::before_all]
+        #[test_helpers::after_all]
+        mod test {
+            static HANDLE: once_cell::sync::Lazy<std::sync::Mutex<$crate::lib::ExecutionHandle>> = once_cell::sync::Lazy::new(|| {
+                std::sync::Mutex::new($crate::lib::ExecutionHandle::new())
+            });
+            static PORT: i32 = $port;
+
+            fn before_all() {
+                HANDLE.lock().unwrap().execute_example($example, file!(), "serve");
+            }
+
+            fn after_all() {
+                HANDLE.lock().unwrap().exit();
+            }
+
+            $($body)*
+        }
+    };
+    ($port:expr, $args:expr, { $($body:tt)* }) => {
+        #[test_helpers::before_all]
+        #[test_helpers::after_all]
+        mod test {
+            static HANDLE: once_cell::sync::Lazy<std::sync::Mutex<$crate::lib::ExecutionHandle>> = once_cell::sync::Lazy::new(|| {
+                std::sync::Mutex::new($crate::lib::ExecutionHandle::new())
+            });
+            static PORT: i32 = $port;
+
+            fn before_all() {
+                HANDLE.lock().unwrap().execute(file!(), $args);
+            }
+
+            fn after_all() {
+                HANDLE.lock().unwrap().exit();
+            }
+
+            $($body)*
+        }
+    };
+}
+
+/// Asserts that `res` is an error response of `error_type` with `message`.
+pub fn assert_error(res: &Value, error_type: &str, message: &str) {
+    let error = &res["error"];
+    assert_eq!(error["type"], error_type, "unexpected response {}", res);
+    assert_eq!(error["message"], message, "unexpected response {}", res);
+}
+
+/// Asserts that `res` is a value error reporting `message` for the input at the dotted `path`.
+pub fn assert_field_error(res: &Value, path: &str, message: &str) {
+    assert_error(res, "ValueError", "value is invalid");
+    assert_eq!(res["error"]["fields"][path], message, "unexpected response {}", res);
+    let segments: Vec<String> = res["error"]["path"].as_array().into_iter().flatten().map(|s| match s {
+        Value::String(s) => s.clone(),
+        s => s.to_string(),
+    }).collect();
+    assert_eq!(segments.join("."), path, "unexpected response {}", res);
+}
@@ -234,7 +234,7 @@ impl From<f32> for Matcher {
 
 impl From<f64> for Matcher {
     fn from(value: f64) -> Self {
-        Matcher::Number(Number::from_f64(value as f64).unwrap())
+        Matcher::Number(Number::from_f64(value).unwrap())
     }
 }
 
@@ -277,17 +277,11 @@ impl From<Arc<dyn Fn(&Value) -> bool + 'static>> for Matcher {
 impl Matcher {
 
     pub fn is_null(&self) -> bool {
-        match self {
-            Matcher::Null => true,
-            _ => false,
-        }
+        matches!(self, Matcher::Null)
     }
 
     pub fn is_ignore(&self) -> bool {
-        match self {
-            Matcher::Ignore => true,
-            _ => false,
-        }
+        matches!(self, Matcher::Ignore)
     }
 
     pub fn as_str(&self) -> Option<&str> {
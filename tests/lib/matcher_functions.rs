@@ -1,16 +1,11 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use maplit::hashmap;
-use once_cell::sync::Lazy;
 use regex::Regex;
 use serde_json::Value;
-use uuid::Uuid;
 use crate::lib::json_match;
 use crate::lib::matcher::Matcher;
 
 pub fn date_time_value(val: impl AsRef<str>) -> impl Fn(&Value) -> bool {
-    return move |v: &Value| {
+    move |v: &Value| {
         if !v.is_object() { return false }
         let obj = v.as_object().unwrap();
         if obj.len() != 1 { return false }
@@ -22,7 +17,7 @@ pub fn date_time_value(val: impl AsRef<str>) -> impl Fn(&Value) -> bool {
 }
 
 pub fn decimal_value(val: impl AsRef<str>) -> impl Fn(&Value) -> bool {
-    return move |v: &Value| {
+    move |v: &Value| {
         if !v.is_object() { return false }
         let obj = v.as_object().unwrap();
         if obj.len() != 1 { return false }
@@ -40,7 +35,7 @@ pub fn object_id_value(v: &Value) -> bool {
 }
 
 pub fn one_match(matcher: impl Borrow<Matcher>) -> impl Fn(&Value) -> bool {
-    return move |v: &Value| {
+    move |v: &Value| {
         if !v.is_array() { return false }
         let array = v.as_array().unwrap();
         for value in array {
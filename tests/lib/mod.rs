@@ -1,7 +1,11 @@
+pub mod fixture;
 pub mod matcher;
 pub mod matcher_functions;
 
-use std::process::{Child, Command, Stdio};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
 use std::{env, thread};
 use std::borrow::Borrow;
 use std::collections::HashSet;
@@ -9,7 +13,6 @@ use std::path::{Path, PathBuf};
 use key_path::{KeyPath, path};
 use serde_json::{Map, Number, Value};
 use crate::lib::matcher::Matcher;
-use whoami::Platform;
 
 fn schema_from_file(file: &str) -> PathBuf {
     let file_path = Path::new(file);
@@ -19,8 +22,8 @@ fn schema_from_file(file: &str) -> PathBuf {
 
 fn teo_exe_path_buf() -> PathBuf {
     let mut current_dir = env::current_dir().unwrap();
-    while current_dir != PathBuf::from("/") {
-        let exe_path = current_dir.join(if whoami::platform() == Platform::Windows {
+    while current_dir != Path::new("/") {
+        let exe_path = current_dir.join(if cfg!(windows) {
             "target/debug/cargo-teo.exe"
         } else {
             "target/debug/cargo-teo"
@@ -37,42 +40,114 @@ fn teo_exe_path() -> String {
     teo_exe_path_buf().to_str().unwrap().to_string()
 }
 
+// the examples aren't built for a single test target, so the example is built first
+fn example_exe_path(example: &str) -> PathBuf {
+    let status = Command::new(env::var("CARGO").unwrap_or("cargo".to_owned())).args(["build", "--quiet", "--example", example]).status().unwrap();
+    assert!(status.success(), "example `{}' did not build", example);
+    teo_exe_path_buf().parent().unwrap().join("examples").join(example)
+}
+
 pub struct ExecutionHandle {
     child: Option<Child>
 }
 
+impl Default for ExecutionHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ExecutionHandle {
     pub fn new() -> Self {
         Self { child: None }
     }
 
     pub fn execute(&mut self, file: &str, args: &str) {
+        self.execute_program(&teo_exe_path(), file, args);
+    }
+
+    /// Like `execute`, runs the example `example`, which sets up the app in Rust, in place of
+    /// the Teo executable.
+    pub fn execute_example(&mut self, example: &str, file: &str, args: &str) {
+        self.execute_program(example_exe_path(example).to_str().unwrap(), file, args);
+    }
+
+    fn execute_program(&mut self, program: &str, file: &str, args: &str) {
         env::set_var("TEO_ENV", "test");
-        self.child = Some(Command::new(teo_exe_path()).arg("-s").arg(schema_from_file(file)).arg(args).stdout(Stdio::null()).spawn().unwrap());
-        thread::sleep(std::time::Duration::from_secs(3))
+        let mut child = Command::new(program).arg("-s").arg(schema_from_file(file)).args(args.split_whitespace()).stdout(Stdio::piped()).spawn().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        // the output is read until the server exits, so its request logs never block it
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line.contains("listening on port") {
+                    let _ = sender.send(());
+                }
+            }
+        });
+        self.child = Some(child);
+        receiver.recv_timeout(Duration::from_secs(60)).expect("server did not start listening");
     }
 
     pub fn exit(&mut self) {
-        if let Some(child) = &mut self.child {
-            child.kill().unwrap();
+        // the server is reaped as well, so it does not outlive the test run as a zombie
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
         }
     }
 }
 
 unsafe impl Sync for ExecutionHandle { }
 
+/// Runs a command of the Teo executable against the schema next to `file` until it exits.
+pub fn run_command(file: &str, args: &[&str]) -> Output {
+    Command::new(teo_exe_path()).env("TEO_ENV", "test").arg("-s").arg(schema_from_file(file)).args(args).output().unwrap()
+}
+
+/// Runs the example `example` against `schema` until it exits.
+pub fn run_example(example: &str, schema: &Path, args: &[&str]) -> Output {
+    Command::new(example_exe_path(example)).env("TEO_ENV", "test").arg("-s").arg(schema).args(args).output().unwrap()
+}
+
 pub fn req<J: Borrow<Value>>(port: i32, action: &str, model: &str, data: J) -> Value {
-    let url = format!("http://127.0.0.1:{}/{}/action/{}", port, model, action);
+    let url = format!("http://127.0.0.1:{}/{}/{}", port, model, action);
     let client = reqwest::blocking::Client::new();
     let res = client.post(url).json(data.borrow()).send().unwrap();
     res.json().unwrap()
 }
 
+pub fn req_form(port: i32, action: &str, model: &str, fields: &[(&str, &str)]) -> Value {
+    let url = format!("http://127.0.0.1:{}/{}/{}", port, model, action);
+    let client = reqwest::blocking::Client::new();
+    let res = client.post(url).form(fields).send().unwrap();
+    res.json().unwrap()
+}
+
+/// Sends `multipart/form-data`, parts with a file name are sent as files.
+pub fn req_multipart(port: i32, action: &str, model: &str, parts: &[(&str, Option<&str>, &str)]) -> Value {
+    let url = format!("http://127.0.0.1:{}/{}/{}", port, model, action);
+    let boundary = "teo-test-boundary";
+    let mut body = String::new();
+    for (name, filename, content) in parts {
+        body += &format!("--{}\r\n", boundary);
+        match filename {
+            Some(filename) => body += &format!("Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n", name, filename),
+            None => body += &format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name),
+        }
+        body += &format!("{}\r\n", content);
+    }
+    body += &format!("--{}--\r\n", boundary);
+    let client = reqwest::blocking::Client::new();
+    let res = client.post(url).header("content-type", format!("multipart/form-data; boundary={}", boundary)).body(body).send().unwrap();
+    res.json().unwrap()
+}
+
 pub fn json_match<J: Borrow<Value>, M: Borrow<Matcher>>(value: J, matcher: M) -> Result<(), String> {
     json_match_internal(value.borrow(), matcher.borrow(), &path![])
 }
 
-fn json_match_internal(value: &Value, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_internal(value: &Value, matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     if matcher.is_ignore() {
         return Ok(());
     }
@@ -87,14 +162,14 @@ fn json_match_internal(value: &Value, matcher: &Matcher, path: &KeyPath<'_>) ->
     Ok(())
 }
 
-fn json_match_null(matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_null(matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     if matcher.is_null() {
         return Ok(());
     }
     json_match_error(&Value::Null, path)
 }
 
-fn json_match_string(value: &Value, string: &String, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_string(value: &Value, string: &String, matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     match matcher {
         Matcher::String(s) => json_match_error_if_not(s == string, value, path),
         Matcher::ValueMatcher(m) => json_match_error_if_not(m(value), value, path),
@@ -102,7 +177,7 @@ fn json_match_string(value: &Value, string: &String, matcher: &Matcher, path: &K
     }
 }
 
-fn json_match_bool(value: &Value, bool: &bool, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_bool(value: &Value, bool: &bool, matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     match matcher {
         Matcher::Bool(b) => json_match_error_if_not(b == bool, value, path),
         Matcher::ValueMatcher(m) => json_match_error_if_not(m(value), value, path),
@@ -110,7 +185,7 @@ fn json_match_bool(value: &Value, bool: &bool, matcher: &Matcher, path: &KeyPath
     }
 }
 
-fn json_match_number(value: &Value, number: &Number, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_number(value: &Value, number: &Number, matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     match matcher {
         Matcher::Number(n) => json_match_error_if_not(n == number, value, path),
         Matcher::ValueMatcher(m) => json_match_error_if_not(m(value), value, path),
@@ -118,7 +193,7 @@ fn json_match_number(value: &Value, number: &Number, matcher: &Matcher, path: &K
     }
 }
 
-fn json_match_array(value: &Value, array: &Vec<Value>, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_array(value: &Value, array: &[Value], matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     match matcher {
         Matcher::Array(a) => {
             json_match_error_if_not(a.len() == array.len(), value, path)?;
@@ -133,13 +208,13 @@ fn json_match_array(value: &Value, array: &Vec<Value>, matcher: &Matcher, path:
     }
 }
 
-fn json_match_object(value: &Value, map: &Map<String, Value>, matcher: &Matcher, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_object(value: &Value, map: &Map<String, Value>, matcher: &Matcher, path: &KeyPath) -> Result<(), String> {
     match matcher {
         Matcher::Object(m) => {
             // compare keys
             json_match_error_if_not(m.len() == map.len(), value, path)?;
-            let m_keys: HashSet<&str> = m.keys().into_iter().map(|k| k.as_str()).collect();
-            let map_keys: HashSet<&str> = map.keys().into_iter().map(|k| k.as_str()).collect();
+            let m_keys: HashSet<&str> = m.keys().map(|k| k.as_str()).collect();
+            let map_keys: HashSet<&str> = map.keys().map(|k| k.as_str()).collect();
             json_match_error_if_not(m_keys == map_keys, value, path)?;
             for (key, matcher) in m.iter() {
                 let map_value = map.get(key).unwrap();
@@ -152,15 +227,15 @@ fn json_match_object(value: &Value, map: &Map<String, Value>, matcher: &Matcher,
     }
 }
 
-fn json_match_error(value: &Value, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_error(value: &Value, path: &KeyPath) -> Result<(), String> {
     if path.is_empty() {
-        Err(format!("value `{}` does not match json matcher.", value.to_string()))
+        Err(format!("value `{}` does not match json matcher.", value))
     } else {
-        Err(format!("value `{}` at `{}` does not match json matcher.", value.to_string(), path.to_string()))
+        Err(format!("value `{}` at `{}` does not match json matcher.", value, path))
     }
 }
 
-fn json_match_error_if_not(result: bool, value: &Value, path: &KeyPath<'_>) -> Result<(), String> {
+fn json_match_error_if_not(result: bool, value: &Value, path: &KeyPath) -> Result<(), String> {
     if !result {
         json_match_error(value, path)
     } else {
@@ -1,3 +1,5 @@
+#![allow(special_module_name)]
+
 pub mod lib;
 pub mod connectors;
 pub mod core;
@@ -0,0 +1,71 @@
+// `/_meta' is served with an admin guard set up on the app, so these tests run the server in process
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn error_type(res: &JsonValue) -> Option<&str> {
+        res["error"]["type"].as_str()
+    }
+
+    #[tokio::test]
+    async fn enable_list_allows_only_its_actions() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Entry", "create", json!({"create": {"text": "a"}})).await.unwrap();
+        assert_eq!(res["data"]["text"], json!("a"), "unexpected response {}", res);
+        for action in ["findMany", "findFirst", "findUnique"] {
+            let res = server.request("Entry", action, json!({"where": {"id": 1}})).await.unwrap();
+            assert_eq!(error_type(&res), None, "{} is enabled, unexpected response {}", action, res);
+        }
+        let res = server.request("Entry", "update", json!({"where": {"id": 1}, "update": {"text": "b"}})).await.unwrap();
+        assert_eq!(error_type(&res), Some("MethodNotAllowed"));
+        assert_eq!(res["error"]["message"], json!("action `update' is disabled"));
+    }
+
+    #[tokio::test]
+    async fn disable_list_matches_name_and_amount() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Log", "createMany", json!({"create": [{"text": "a"}, {"text": "b"}]})).await.unwrap();
+        let res = server.request("Log", "deleteMany", json!({})).await.unwrap();
+        assert_eq!(error_type(&res), Some("MethodNotAllowed"), "unexpected response {}", res);
+        let res = server.request("Log", "delete", json!({"where": {"id": 1}})).await.unwrap();
+        assert_eq!(res["data"]["id"], json!(1), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn upsert_is_disabled_on_its_own() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Tag", "upsert", json!({"where": {"name": "a"}, "create": {"name": "a"}, "update": {}})).await.unwrap();
+        assert_eq!(error_type(&res), Some("MethodNotAllowed"), "unexpected response {}", res);
+        let res = server.request("Tag", "create", json!({"create": {"name": "a"}})).await.unwrap();
+        assert_eq!(res["data"]["name"], json!("a"), "unexpected response {}", res);
+        let res = server.request("Tag", "update", json!({"where": {"name": "a"}, "update": {"name": "b"}})).await.unwrap();
+        assert_eq!(res["data"]["name"], json!("b"), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn unknown_actions_are_not_found() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Entry", "archive", json!({})).await.unwrap();
+        assert_eq!(error_type(&res), Some("NotFound"), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn meta_lists_the_enabled_actions() {
+        // `/_meta' is an admin endpoint, served when there is a guard
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.admin(|_| async { true });
+            Ok(())
+        }).await.unwrap();
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        let actions = |model: &str| res["data"]["models"][model]["actions"].as_array().unwrap().iter().map(|a| a.as_str().unwrap().to_owned()).collect::<Vec<String>>();
+        let entry = actions("Entry");
+        assert!(entry.contains(&"create".to_owned()) && entry.contains(&"findMany".to_owned()), "{:?}", entry);
+        assert!(!entry.contains(&"update".to_owned()) && !entry.contains(&"delete".to_owned()), "{:?}", entry);
+        let log = actions("Log");
+        assert!(log.contains(&"delete".to_owned()) && !log.contains(&"deleteMany".to_owned()), "{:?}", log);
+        let tag = actions("Tag");
+        assert!(tag.contains(&"update".to_owned()) && !tag.contains(&"upsert".to_owned()), "{:?}", tag);
+    }
+}
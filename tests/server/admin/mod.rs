@@ -0,0 +1,48 @@
+// the admin guard is set up on the app, so these tests run the server in process
+mod test {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{read_body, TestRequest};
+    use serde_json::json;
+    use teo::test::TestServer;
+    use teo_runtime::request::Request;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // allows the requests with an `admin' header
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.admin(|request: Request| {
+                let allowed = request.headers().get("admin").is_some();
+                async move { allowed }
+            });
+            Ok(())
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn refused_request_gets_no_admin_page() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/admin").to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        assert_eq!(res["error"]["message"], json!("admin access denied"));
+    }
+
+    #[tokio::test]
+    async fn allowed_request_gets_the_admin_page() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/admin").insert_header(("admin", "1")).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+        assert!(body.contains("<html"));
+        let res = server.request_at_path_with_headers("/_meta", json!({}), &[("admin", "1")]).await.unwrap();
+        assert_eq!(res["data"]["models"]["Memo"]["name"], json!("Memo"));
+    }
+
+    #[tokio::test]
+    async fn admin_endpoints_are_missing_without_a_guard() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.call(TestRequest::get().uri("/admin").insert_header(("admin", "1")).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,77 @@
+// archives are moved through the Rust API, so these tests run the server in process
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use chrono::{Duration, SecondsFormat, Utc};
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::archive::enforce_archive;
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn ctx() -> transaction::Ctx {
+        transaction::Ctx::new(Ctx::conn_ctx())
+    }
+
+    fn days_ago(days: i64) -> String {
+        (Utc::now() - Duration::days(days)).to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    // the archive models are stored in a SQLite file of their own
+    async fn server() -> (TestServer, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("teo-archive-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        let url = format!("sqlite:{}", directory.join("cold.sqlite").display());
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.register_source("cold", &url);
+            Ok(())
+        }).await.unwrap();
+        for (memo, days) in [("rent", 90), ("fees", 45), ("coffee", 1)] {
+            server.request("Ledger", "create", json!({"create": {"memo": memo, "recordedAt": days_ago(days)}})).await.unwrap();
+        }
+        for (title, days) in [("broken link", 60), ("typo", 2)] {
+            server.request("Ticket", "create", json!({"create": {"title": title, "body": "details", "closedAt": days_ago(days)}})).await.unwrap();
+        }
+        (server, directory)
+    }
+
+    #[tokio::test]
+    async fn due_records_move_into_the_archive_model() {
+        let (server, directory) = server().await;
+        let reports = enforce_archive(ctx(), true).await.unwrap();
+        assert_eq!(reports.iter().map(|r| (r.model.as_str(), r.archived)).collect::<Vec<_>>(), vec![("Ledger", 2), ("Ticket", 1)]);
+        let res = server.request("Ledger", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"].as_array().unwrap().len(), 3);
+        enforce_archive(ctx(), false).await.unwrap();
+        let res = server.request("Ledger", "findMany", json!({"select": {"memo": true}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"memo": "coffee"}]));
+        let res = server.request("Ledger", "findArchived", json!({"orderBy": {"id": "asc"}, "select": {"id": true, "memo": true}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"id": 1, "memo": "rent"}, {"id": 2, "memo": "fees"}]));
+        let reports = enforce_archive(ctx(), false).await.unwrap();
+        assert!(reports.iter().all(|r| r.archived == 0));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tombstones_keep_the_keys_of_the_records() {
+        let (server, directory) = server().await;
+        enforce_archive(ctx(), false).await.unwrap();
+        let res = server.request("Ticket", "findMany", json!({"orderBy": {"id": "asc"}, "select": {"title": true, "body": true}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"title": "broken link"}, {"title": "typo", "body": "details"}]));
+        let res = server.request("Ticket", "findMany", json!({"where": {"archivedAt": {"not": null}}})).await.unwrap();
+        assert_eq!(res["data"].as_array().unwrap().len(), 1);
+        let res = server.request("Ticket", "findArchived", json!({"where": {"title": "broken link"}, "select": {"body": true}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"body": "details"}]));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn models_without_an_archive_have_no_find_archived() {
+        let (server, directory) = server().await;
+        let res = server.request("cold.LedgerArchive", "findArchived", json!({})).await.unwrap();
+        assert_eq!(res["error"]["type"], json!("NotFound"), "{}", res);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+// backfills are defined on the app and run through the Rust API, so these tests run the server in
+// process
+mod test {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::backfill::{backfill_progress, run_backfill};
+    use teo::test::TestServer;
+    use teo_result::Error;
+    use teo_runtime::connection::transaction;
+    use teo_runtime::model::Object;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static FAILED: AtomicBool = AtomicBool::new(false);
+
+    fn ctx() -> transaction::Ctx {
+        transaction::Ctx::new(Ctx::conn_ctx())
+    }
+
+    async fn server() -> TestServer {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.backfill("slugs", "Customer", |object: Object| async move {
+                let name: String = object.get("name")?;
+                object.set("slug", name.to_lowercase())?;
+                Ok(())
+            });
+            // fails on `Eve' once
+            app.backfill("flaky", "Customer", |object: Object| async move {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+                let name: String = object.get("name")?;
+                if name == "Eve" && !FAILED.swap(true, Ordering::SeqCst) {
+                    return Err(Error::new("flaky failure"));
+                }
+                Ok(())
+            });
+            Ok(())
+        }).await.unwrap();
+        for name in ["Ann", "Bob", "Cy", "Dan", "Eve"] {
+            server.request("Customer", "create", json!({"create": {"name": name}})).await.unwrap();
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn records_are_transformed_in_batches() {
+        let server = server().await;
+        let progress = run_backfill(ctx(), "slugs", 2, true).await.unwrap();
+        assert_eq!(progress.processed, 5);
+        assert!(progress.finished);
+        let res = server.request("Customer", "findMany", json!({"orderBy": {"id": "asc"}, "select": {"slug": true}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"slug": "ann"}, {"slug": "bob"}, {"slug": "cy"}, {"slug": "dan"}, {"slug": "eve"}]));
+        let progress = run_backfill(ctx(), "slugs", 2, true).await.unwrap();
+        assert_eq!(progress.processed, 5);
+    }
+
+    #[tokio::test]
+    async fn failed_runs_resume_after_the_last_stored_batch() {
+        let _server = server().await;
+        let error = run_backfill(ctx(), "flaky", 2, true).await.unwrap_err();
+        assert_eq!(error.message(), "flaky failure");
+        assert_eq!(CALLS.load(Ordering::SeqCst), 5);
+        let progress = backfill_progress(ctx()).await.unwrap();
+        let flaky = progress.iter().find(|p| p.name == "flaky").unwrap();
+        assert_eq!((flaky.processed, flaky.finished), (4, false));
+        let progress = run_backfill(ctx(), "flaky", 2, true).await.unwrap();
+        assert_eq!((progress.processed, progress.finished), (5, true));
+        // only the failed batch is transformed again
+        assert_eq!(CALLS.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn a_progress_model_is_required() {
+        let schema = SCHEMA.replace("@backfillProgress\n", "");
+        let _server = TestServer::new_with(schema, |app| {
+            app.backfill("slugs", "Customer", |_object: Object| async move { Ok(()) });
+            Ok(())
+        }).await.unwrap();
+        let error = run_backfill(ctx(), "slugs", 2, true).await.unwrap_err();
+        assert_eq!(error.message(), "backfills store their progress in a model marked with @backfillProgress, declare one");
+    }
+}
@@ -0,0 +1,90 @@
+// batches run every item in one transaction, these tests read the database between requests, so
+// they run the server in process
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn batch(server: &TestServer, actions: JsonValue) -> JsonValue {
+        server.request_at_path("/batch/action", json!({"actions": actions})).await.unwrap()
+    }
+
+    async fn codes(server: &TestServer) -> Vec<String> {
+        let res = server.request("Item", "findMany", json!({"orderBy": {"id": "asc"}})).await.unwrap();
+        res["data"].as_array().unwrap().iter().map(|i| i["code"].as_str().unwrap().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn items_are_committed_together() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = batch(&server, json!([
+            {"model": "Item", "action": "create", "args": {"create": {"code": "a"}}},
+            {"model": "Item", "action": "update", "args": {"where": {"id": {"$ref": "0.id"}}, "update": {"code": "b"}}},
+            {"model": "Item", "action": "createMany", "args": {"create": [{"code": "c"}, {"code": "d"}]}},
+        ])).await;
+        assert_eq!(res["data"][1]["data"]["code"], json!("b"));
+        assert_eq!(res["data"][2]["meta"]["count"], json!(2));
+        assert_eq!(codes(&server).await, vec!["b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn failing_item_rolls_back_the_batch() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Item", "create", json!({"create": {"code": "a"}})).await.unwrap();
+        let res = batch(&server, json!([
+            {"model": "Item", "action": "create", "args": {"create": {"code": "b"}}},
+            {"model": "Item", "action": "delete", "args": {"where": {"code": "a"}}},
+            {"model": "Item", "action": "create", "args": {"create": {"code": "b"}}},
+            {"model": "Item", "action": "create", "args": {"create": {"code": "c"}}},
+        ])).await;
+        assert_eq!(res["error"]["index"], json!(2));
+        assert_eq!(res["error"]["fields"], json!({"actions.2.args.create.code": "unique value duplicated: code"}));
+        assert_eq!(res["error"]["path"], json!(["actions", 2, "args", "create", "code"]));
+        assert_eq!(res["data"], json!([
+            {"status": "rolledBack"},
+            {"status": "rolledBack"},
+            {"status": "failed"},
+            {"status": "skipped"},
+        ]));
+        assert_eq!(codes(&server).await, vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn unknown_reference_fails_the_item() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = batch(&server, json!([
+            {"model": "Item", "action": "create", "args": {"create": {"code": "a"}}},
+            {"model": "Item", "action": "update", "args": {"where": {"id": {"$ref": "3.id"}}, "update": {"code": "b"}}},
+        ])).await;
+        assert_eq!(res["error"]["index"], json!(1));
+        assert_eq!(res["error"]["fields"]["actions.1.args.where.id"], json!("reference `3.id' points to no previous result"));
+        assert_eq!(codes(&server).await, Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn stale_if_match_fails_the_item() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Item", "create", json!({"create": {"code": "a"}})).await.unwrap();
+        let res = batch(&server, json!([
+            {"model": "Item", "action": "create", "args": {"create": {"code": "b"}}},
+            {"model": "Item", "action": "update", "args": {"where": {"code": "a"}, "update": {"code": "c"}}, "ifMatch": "\"stale\""},
+        ])).await;
+        assert_eq!(res["error"]["type"], json!("PreconditionFailed"));
+        assert_eq!(res["data"], json!([{"status": "rolledBack"}, {"status": "failed"}]));
+        assert_eq!(codes(&server).await, vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn created_items_are_positioned() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Item", "create", json!({"create": {"code": "a"}})).await.unwrap();
+        let res = batch(&server, json!([
+            {"model": "Item", "action": "create", "args": {"create": {"code": "b"}}},
+            {"model": "Item", "action": "createMany", "args": {"create": [{"code": "c"}, {"code": "d", "position": 1}]}},
+        ])).await;
+        assert_eq!(res["data"][0]["data"]["position"], json!(2048));
+        assert_eq!(res["data"][1]["data"][0]["position"], json!(3072));
+        assert_eq!(res["data"][1]["data"][1]["position"], json!(1));
+    }
+}
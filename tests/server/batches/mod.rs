@@ -0,0 +1,105 @@
+// batches are iterated through the Rust API, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::object::batch::{BatchOptions, Batches};
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction;
+    use teo_runtime::model::Model;
+    use teo_teon::teon;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn item() -> &'static Model {
+        Ctx::main_namespace().model_at_path(&vec!["Item"]).unwrap()
+    }
+
+    fn batches(finder: Value, options: BatchOptions) -> Batches {
+        Batches::new(&transaction::Ctx::new(Ctx::conn_ctx()), item(), &finder, options).unwrap()
+    }
+
+    fn error(finder: Value, options: BatchOptions) -> String {
+        match Batches::new(&transaction::Ctx::new(Ctx::conn_ctx()), item(), &finder, options) {
+            Ok(_) => panic!("batches are created"),
+            Err(e) => e.message().to_owned(),
+        }
+    }
+
+    async fn create_items(server: &TestServer, items: &[(&str, i32)]) {
+        for (code, rank) in items {
+            server.request("Item", "create", json!({"create": {"code": code, "rank": rank}})).await.unwrap();
+        }
+    }
+
+    // the codes of a loaded batch
+    async fn next_codes(batches: &mut Batches) -> Option<Vec<String>> {
+        let objects = batches.next().await.unwrap()?;
+        Some(objects.iter().map(|o| o.get_value("code").unwrap().as_str().unwrap().to_owned()).collect())
+    }
+
+    async fn all_codes(batches: &mut Batches) -> Vec<Vec<String>> {
+        let mut result = vec![];
+        while let Some(codes) = next_codes(batches).await {
+            result.push(codes);
+        }
+        result
+    }
+
+    #[tokio::test]
+    async fn iterates_by_primary_key() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_items(&server, &[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1)]).await;
+        let mut batches = batches(teon!({}), BatchOptions { size: 2, ..Default::default() });
+        assert_eq!(all_codes(&mut batches).await, vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+    }
+
+    #[tokio::test]
+    async fn order_by_ties_are_broken_by_stable_key() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_items(&server, &[("a", 3), ("b", 1), ("c", 2), ("d", 1)]).await;
+        let mut batches = batches(teon!({}), BatchOptions { size: 3, order_by: Some("rank".to_owned()), stable_key: Some("code".to_owned()) });
+        assert_eq!(all_codes(&mut batches).await, vec![vec!["b", "d", "c"], vec!["a"]]);
+    }
+
+    #[tokio::test]
+    async fn finder_filters_records() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_items(&server, &[("a", 1), ("b", 2), ("c", 1), ("d", 2)]).await;
+        let mut batches = batches(teon!({"where": {"rank": 2}}), BatchOptions { size: 1, ..Default::default() });
+        assert_eq!(all_codes(&mut batches).await, vec![vec!["b"], vec!["d"]]);
+    }
+
+    #[tokio::test]
+    async fn deletes_and_inserts_while_iterating_do_not_shift_batches() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_items(&server, &[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1)]).await;
+        let mut batches = batches(teon!({}), BatchOptions { size: 2, ..Default::default() });
+        assert_eq!(next_codes(&mut batches).await, Some(vec!["a".to_owned(), "b".to_owned()]));
+        server.request("Item", "deleteMany", json!({"where": {"code": {"in": ["a", "b"]}}})).await.unwrap();
+        create_items(&server, &[("f", 1)]).await;
+        assert_eq!(all_codes(&mut batches).await, vec![vec!["c", "d"], vec!["e", "f"]]);
+    }
+
+    #[tokio::test]
+    async fn resumes_after_key_values() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_items(&server, &[("a", 1), ("b", 1), ("c", 1)]).await;
+        let mut first = batches(teon!({}), BatchOptions { size: 1, ..Default::default() });
+        next_codes(&mut first).await;
+        let last = first.last().unwrap().to_vec();
+        let mut resumed = batches(teon!({}), BatchOptions { size: 1, ..Default::default() });
+        resumed.resume_after(last).unwrap();
+        assert_eq!(all_codes(&mut resumed).await, vec![vec!["b"], vec!["c"]]);
+        assert!(resumed.resume_after(vec![]).is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_options_are_rejected() {
+        let _server = TestServer::new(SCHEMA).await.unwrap();
+        assert_eq!(error(teon!({}), BatchOptions { size: 0, ..Default::default() }), "batch size should be greater than 0");
+        assert_eq!(error(teon!({"take": 1}), BatchOptions::default()), "batch finder cannot contain `take', use batch options instead");
+        assert_eq!(error(teon!({}), BatchOptions { stable_key: Some("rank".to_owned()), ..Default::default() }), "stable key `rank' of Item is not a unique field");
+        assert_eq!(error(teon!({}), BatchOptions { order_by: Some("note".to_owned()), ..Default::default() }), "batches of Item cannot be ordered by optional field `note'");
+    }
+}
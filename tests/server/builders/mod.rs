@@ -0,0 +1,63 @@
+// the schema is built through the Rust API and served in process
+mod test {
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::schema::builder::{EnumBuilder, FieldBuilder, FieldMigration, IndexBuilder, IndexKind, ModelBuilder, PropertyBuilder, RelationBuilder, SchemaBuilder};
+    use teo::test::TestServer;
+    use teo_runtime::sort::Sort;
+    use teo_runtime::traits::documentable::Documentable;
+
+    fn schema() -> SchemaBuilder {
+        SchemaBuilder::new()
+            .connector("sqlite", "sqlite::memory:")
+            .server("0.0.0.0", 4047)
+            .r#enum(EnumBuilder::new("Role").member("admin").member_with_doc("member", "A regular member"))
+            .model(ModelBuilder::new("User")
+                .doc("A user of the app")
+                .map("users")
+                .index(IndexBuilder::new(IndexKind::Unique).key("firstName").key_with_options("lastName", Some(Sort::Desc), None).map("users_full_name"))
+                .field(FieldBuilder::new("id", "Int").id().auto_increment().readonly())
+                .field(FieldBuilder::new("email", "String").unique().on_set("$if($presents, then: $toLowerCase)").on_output("$if($presents, then: $toUpperCase)"))
+                .field(FieldBuilder::new("firstName", "String"))
+                .field(FieldBuilder::new("lastName", "String").migration(FieldMigration { renamed: vec!["surname".to_owned()], ..Default::default() }))
+                .field(FieldBuilder::new("role", "Role?"))
+                .field(FieldBuilder::new("nickname", "String").default("\"none\""))
+                .field(FieldBuilder::new("secret", "String?").writeonly())
+                .relation(RelationBuilder::new("posts", "Post[]", &["id"], &["authorId"])))
+            .model(ModelBuilder::new("Post")
+                .field(FieldBuilder::new("id", "Int").id().auto_increment().readonly())
+                .field(FieldBuilder::new("title", "String"))
+                .field(FieldBuilder::new("authorId", "Int").foreign_key())
+                .relation(RelationBuilder::new("author", "User", &["authorId"], &["id"])))
+    }
+
+    #[test]
+    fn renders_the_schema_language() {
+        let source = schema().source();
+        assert!(source.contains("/// A user of the app\n@map(\"users\")\n@unique([.firstName, .lastName(sort: .desc)], map: \"users_full_name\")\nmodel User {\n"));
+        assert!(source.contains("  @id @autoIncrement @readonly\n  id: Int\n"));
+        assert!(source.contains("  @migration(renamed: [\"surname\"])\n  lastName: String\n"));
+        assert!(source.contains("  @relation(fields: .id, references: .authorId)\n  posts: Post[]\n"));
+        assert!(source.contains("enum Role {\n  admin\n  /// A regular member\n  member\n}"));
+        let source = SchemaBuilder::new().model(ModelBuilder::new("Profile")
+            .field(FieldBuilder::new("name", "String").index_with_options(IndexKind::Index, None, Some(10), Some("profiles_name")))
+            .property(PropertyBuilder::new("display", "String?").getter("$self.get(.name)").cached(&["name"]))).source();
+        assert_eq!(source, "model Profile {\n  @index(length: 10, map: \"profiles_name\")\n  name: String\n  @getter($self.get(.name)) @cached @deps(.name)\n  display: String?\n}\n");
+    }
+
+    #[tokio::test]
+    async fn serves_the_built_schema() {
+        let server = TestServer::new(schema().source()).await.unwrap();
+        let model = Ctx::main_namespace().model_at_path(&vec!["User"]).unwrap();
+        assert_eq!(model.table_name, "users");
+        assert_eq!(model.desc(), "A user of the app");
+        let res = server.request("User", "create", json!({"create": {"email": "Ann@Example.com", "firstName": "Ann", "lastName": "Lee", "role": "member", "secret": "s"}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("ANN@EXAMPLE.COM"));
+        assert_eq!(res["data"]["nickname"], json!("none"));
+        assert_eq!(res["data"]["secret"], json!(null));
+        let res = server.request("Post", "create", json!({"create": {"title": "Hello", "authorId": res["data"]["id"]}, "include": {"author": true}})).await.unwrap();
+        assert_eq!(res["data"]["author"]["firstName"], json!("Ann"));
+        let res = server.request("User", "create", json!({"create": {"email": "bob@example.com", "firstName": "Ann", "lastName": "Lee"}})).await.unwrap();
+        assert_eq!(res["error"]["type"], json!("ValueError"));
+    }
+}
@@ -0,0 +1,114 @@
+// the ClickHouse server is a stub of its HTTP interface which listens on a port picked at runtime,
+// so these tests run the server in process
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    type Statements = Arc<Mutex<Vec<String>>>;
+
+    // records the statements it's sent and answers them with canned rows, requests without the
+    // expected credentials and database are rejected
+    fn stub(statements: Statements) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let statements = statements.clone();
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut head = vec![];
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                            break;
+                        }
+                        head.push(line.trim_end().to_owned());
+                    }
+                    let header = |name: &str| head.iter().find_map(|h| h.split_once(':').filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.trim().to_owned()));
+                    let length = header("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let statement = String::from_utf8(body).unwrap();
+                    let authorized = header("x-clickhouse-user").as_deref() == Some("reader") && header("x-clickhouse-key").as_deref() == Some("secret") && head[0].contains("database=warehouse");
+                    let rows = if statement.starts_with("SELECT count() AS count") {
+                        json!([{"count": 2}])
+                    } else if statement.contains("GROUP BY") {
+                        json!([{"_sum.visits": 10, "page_path": "/a"}, {"_sum.visits": 5, "page_path": "/b"}])
+                    } else if statement.starts_with("SELECT count() AS `_count._all`") {
+                        json!([{"_count._all": 3, "_avg.duration": 1.5, "_max.visits": 7}])
+                    } else {
+                        json!([
+                            {"id": 1, "page_path": "/a", "visits": 7, "duration": 2, "day": "2024-03-01"},
+                            {"id": 2, "page_path": "/b", "visits": 3, "duration": 1.5, "day": null},
+                        ])
+                    };
+                    statements.lock().unwrap().push(statement);
+                    let (status, response) = if authorized {
+                        (200, json!({"meta": [], "data": rows}).to_string())
+                    } else {
+                        (403, "Code: 516. DB::Exception: Authentication failed".to_owned())
+                    };
+                    let _ = write!(stream, "HTTP/1.1 {} OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, response.len(), response);
+                });
+            }
+        });
+        port
+    }
+
+    async fn server(statements: &Statements, credentials: &str) -> TestServer {
+        let url = format!("clickhouse://{}@127.0.0.1:{}/warehouse", credentials, stub(statements.clone()));
+        TestServer::new(SCHEMA.replace("CLICKHOUSE_URL", &url)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_are_found() {
+        let statements = Statements::default();
+        let server = server(&statements, "reader:secret").await;
+        let res = server.request("PageView", "findMany", json!({"where": {"path": {"startsWith": "/"}, "visits": {"gt": 1}}, "orderBy": {"visits": "desc"}, "take": 2})).await.unwrap();
+        assert_eq!(res["data"], json!([
+            {"id": 1, "path": "/a", "visits": 7, "duration": 2.0, "day": {"$date": "2024-03-01"}},
+            {"id": 2, "path": "/b", "visits": 3, "duration": 1.5},
+        ]));
+        let statements = statements.lock().unwrap();
+        assert_eq!(statements[statements.len() - 2], "SELECT `id`, `page_path`, `visits`, `duration`, `day` FROM `page_views` WHERE (`page_path` LIKE '/%') AND (`visits` > 1) ORDER BY `visits` DESC LIMIT 2 OFFSET 0");
+    }
+
+    #[tokio::test]
+    async fn records_are_counted_aggregated_and_grouped() {
+        let statements = Statements::default();
+        let server = server(&statements, "reader:secret").await;
+        let res = server.request("PageView", "count", json!({"where": {"visits": {"in": [3, 7]}}})).await.unwrap();
+        assert_eq!(res["data"], json!(2));
+        let res = server.request("PageView", "aggregate", json!({"_count": {"_all": true}, "_avg": {"duration": true}, "_max": {"visits": true}})).await.unwrap();
+        assert_eq!(res["data"], json!({"_count": {"_all": 3}, "_avg": {"duration": 1.5}, "_max": {"visits": 7}}));
+        let res = server.request("PageView", "groupBy", json!({"by": ["path"], "_sum": {"visits": true}, "having": {"visits": {"_sum": {"gt": 4}}}})).await.unwrap();
+        assert_eq!(res["data"], json!([{"_sum": {"visits": 10.0}, "path": "/a"}, {"_sum": {"visits": 5.0}, "path": "/b"}]));
+        let statements = statements.lock().unwrap();
+        assert!(statements[statements.len() - 3].ends_with("WHERE (`visits` IN (3, 7)))"), "{}", statements[statements.len() - 3]);
+        assert_eq!(statements[statements.len() - 1], "SELECT toFloat64(sum(`visits`)) AS `_sum.visits`, `page_path` FROM (SELECT `id`, `page_path`, `visits`, `duration`, `day` FROM `page_views`) GROUP BY `page_path` HAVING ((toFloat64(sum(`visits`)) > 4))");
+    }
+
+    #[tokio::test]
+    async fn records_are_read_only() {
+        let statements = Statements::default();
+        let server = server(&statements, "reader:secret").await;
+        let res = server.request("PageView", "create", json!({"create": {"id": 3, "path": "/c", "visits": 1, "duration": 0.5}})).await.unwrap();
+        assert_eq!(res["error"]["message"], json!("value is invalid"));
+        assert_eq!(res["error"]["fields"]["create"], json!("PageView is stored in ClickHouse, which is read-only"));
+    }
+
+    #[tokio::test]
+    async fn failed_queries_are_errors() {
+        let statements = Statements::default();
+        let server = server(&statements, "reader:wrong").await;
+        let res = server.request("PageView", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["error"]["type"], json!("InternalServerError"));
+    }
+}
@@ -0,0 +1,62 @@
+// the coercion mode is set on the app, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::server::coerce::Coercion;
+    use teo::test::TestServer;
+    use crate::lib::fixture::assert_field_error;
+    use crate::{assert_json, matcher};
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    #[tokio::test]
+    async fn strict_rejects_other_representations() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Support", "create", json!({
+            "create": {
+                "int": "12",
+            },
+        })).await.unwrap();
+        assert_field_error(&res, "create.int", "expect int");
+    }
+
+    #[tokio::test]
+    async fn lenient_converts_other_representations() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.app().coercion(Coercion::Lenient);
+        let res = server.request("Support", "create", json!({
+            "create": {
+                "int": "12",
+                "float": "1.5",
+                "bool": "true",
+                "date": "2024-01-02",
+                "dateTime": "2024-01-02T03:04:05Z",
+            },
+        })).await.unwrap();
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "int": 12,
+                "float": 1.5,
+                "bool": true,
+                "date": {"$date": "2024-01-02"},
+                "dateTime": {"$datetime": "2024-01-02T03:04:05.000Z"},
+            }
+        }));
+        let res = server.request("Support", "count", json!({
+            "where": {"int": "12"},
+        })).await.unwrap();
+        assert_json!(res, matcher!({"data": 1}));
+    }
+
+    #[tokio::test]
+    async fn lenient_rejects_unconvertible_values() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.app().coercion(Coercion::Lenient);
+        let res = server.request("Support", "create", json!({
+            "create": {
+                "int": "twelve",
+            },
+        })).await.unwrap();
+        assert_field_error(&res, "create.int", "expect int");
+    }
+}
@@ -0,0 +1,32 @@
+// the items are loaded by the app rather than declared in a schema of the runtime, so these tests
+// run the server in process with the declarations in the schema
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn create(server: &TestServer, email: &str) -> JsonValue {
+        server.request("Account", "create", json!({"create": {"code": "ab", "label": "Main", "email": email, "website": "example.com"}})).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn branches_on_create_and_update() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = create(&server, "ann@example.com").await;
+        assert_eq!(res["data"]["code"], json!("AB"));
+        assert_eq!(res["data"]["label"], json!("Main"));
+        assert_eq!(res["data"]["website"], json!("https://example.com"));
+        let res = server.request("Account", "update", json!({"where": {"id": res["data"]["id"]}, "update": {"code": "cd", "label": "Side", "website": "https://example.org"}})).await.unwrap();
+        assert_eq!(res["data"]["code"], json!("cd"));
+        assert_eq!(res["data"]["label"], json!("side"));
+        assert_eq!(res["data"]["website"], json!("https://example.org"));
+    }
+
+    #[tokio::test]
+    async fn failed_validation_reports_the_message() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = create(&server, "not an email").await;
+        assert_eq!(res["error"]["fields"]["create.email"], json!("enter a valid email address"));
+    }
+}
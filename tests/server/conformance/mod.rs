@@ -0,0 +1,21 @@
+// the suite runs its own test server on the connector url, so these tests call it in process
+#![cfg(feature = "conformance")]
+
+mod test {
+    use teo::conformance::{cases::{cases, known_skips}, run};
+
+    #[tokio::test]
+    async fn sqlite_passes_every_case_but_its_known_skips() {
+        let report = run("sqlite::memory:").await.unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.skipped, vec!["types.int64"]);
+        let expected: Vec<&str> = cases().into_iter().map(|(name, _)| name).filter(|name| !known_skips("sqlite").contains(name)).collect();
+        assert_eq!(report.passed, expected);
+    }
+
+    #[tokio::test]
+    async fn unsupported_urls_are_reported() {
+        let Err(error) = run("redis://localhost").await else { panic!("expect an error") };
+        assert_eq!(error.message(), "unsupported connector url scheme: redis");
+    }
+}
@@ -0,0 +1,43 @@
+// providers are registered on the app before it connects, so these tests run the server in process
+mod test {
+    use std::sync::{Arc, Mutex};
+    use serde_json::json;
+    use teo::test::TestServer;
+    use teo_result::Error;
+    use teo_runtime::connection::connection::Connection;
+    use teo_sql_connector::connector::SQLConnection;
+    use teo_sql_connector::schema::dialect::SQLDialect;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    #[tokio::test]
+    async fn connector_url_scheme_selects_the_provider() {
+        let urls: Arc<Mutex<Vec<String>>> = Arc::default();
+        let server = TestServer::new_with(SCHEMA, |app| {
+            let urls = urls.clone();
+            app.register_connector_provider("recorded", move |url: String| {
+                urls.lock().unwrap().push(url);
+                async move {
+                    let connection: Arc<dyn Connection> = Arc::new(SQLConnection::new(SQLDialect::SQLite, "sqlite::memory:", false).await);
+                    Ok(connection)
+                }
+            });
+            Ok(())
+        }).await.unwrap();
+        assert_eq!(*urls.lock().unwrap(), vec!["recorded::memory:".to_owned()]);
+        server.request("Note", "create", json!({"create": {"text": "kept"}})).await.unwrap();
+        let res = server.request("Note", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"][0]["text"], json!("kept"));
+    }
+
+    #[tokio::test]
+    async fn provider_errors_fail_the_startup() {
+        let error = TestServer::new_with(SCHEMA, |app| {
+            app.register_connector_provider("recorded", |_url: String| async move {
+                Err::<Arc<dyn Connection>, _>(Error::new("recorded database is offline"))
+            });
+            Ok(())
+        }).await.err().unwrap();
+        assert_eq!(error.message(), "recorded database is offline");
+    }
+}
@@ -0,0 +1,36 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn diagnostics(schema: String) -> Vec<String> {
+        let error = TestServer::new(schema).await.err().unwrap();
+        error.get_meta::<Vec<String>>("diagnostics").unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn constants_resolve_in_configs_and_decorator_arguments() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        assert_eq!(server.app().main_namespace().server.as_ref().unwrap().bind.1, 4060);
+        let res = server.request("Item", "create", json!({"create": {}})).await.unwrap();
+        assert_eq!(res["data"], json!({"id": res["data"]["id"], "size": 20, "text": "hello, world", "large": 15}));
+        let res = server.request_at_path("/inner/Thing/create", json!({"create": {}})).await.unwrap();
+        assert_eq!(res["data"]["size"], json!(42));
+    }
+
+    #[tokio::test]
+    async fn invalid_operations_name_their_operand_types() {
+        let schema = SCHEMA.replace("\"hello\" + \", \" + \"world\"", "\"hello\" + base").replace("base * 2", "base * 2.5");
+        assert_eq!(diagnostics(schema).await, vec![
+            "cannot apply `*' to Int and Float".to_owned(),
+            "cannot apply `+' to String and Int".to_owned(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn cycles_are_reported() {
+        let schema = SCHEMA.replace("let base = 10", "let base = inner.twice - 1");
+        assert!(diagnostics(schema).await.contains(&"circular reference detected".to_owned()));
+    }
+}
@@ -0,0 +1,39 @@
+mod test {
+    use serde_json::{json, Value};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn count(server: &TestServer, id: &Value) -> Value {
+        let res = server.request("Shelf", "findUnique", json!({"where": {"id": id}})).await.unwrap();
+        res["data"]["volumeCount"].clone()
+    }
+
+    #[tokio::test]
+    async fn nested_creates_are_counted() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Shelf", "create", json!({"create": {"name": "A", "volumes": {"create": [{"name": "1"}, {"name": "2"}]}}})).await.unwrap();
+        assert_eq!(res["data"]["volumeCount"], json!(2), "{}", res);
+        let id = res["data"]["id"].clone();
+        server.request("Volume", "create", json!({"create": {"name": "3", "shelf": {"connect": {"id": id}}}})).await.unwrap();
+        assert_eq!(count(&server, &id).await, json!(3));
+    }
+
+    #[tokio::test]
+    async fn deletes_and_reconnects_are_counted() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Shelf", "create", json!({"create": {"name": "A", "volumes": {"create": [{"name": "1"}, {"name": "2"}]}}, "include": {"volumes": true}})).await.unwrap();
+        let a = res["data"]["id"].clone();
+        let volumes = res["data"]["volumes"].clone();
+        let res = server.request("Shelf", "create", json!({"create": {"name": "B"}})).await.unwrap();
+        let b = res["data"]["id"].clone();
+        let res = server.request("Volume", "update", json!({"where": {"id": volumes[0]["id"]}, "update": {"shelf": {"connect": {"id": b}}}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        assert_eq!(count(&server, &a).await, json!(1));
+        assert_eq!(count(&server, &b).await, json!(1));
+        server.request("Volume", "delete", json!({"where": {"id": volumes[1]["id"]}})).await.unwrap();
+        assert_eq!(count(&server, &a).await, json!(0));
+        let res = server.request("Shelf", "update", json!({"where": {"id": b}, "update": {"volumes": {"disconnect": [{"id": volumes[0]["id"]}]}}})).await.unwrap();
+        assert_eq!(res["data"]["volumeCount"], json!(0), "{}", res);
+    }
+}
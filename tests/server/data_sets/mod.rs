@@ -0,0 +1,67 @@
+// data sets which run once are inserted through the Rust API `teo migrate' calls, so these tests
+// run the server in process
+mod test {
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::migrate::data_sets::migrate_data_sets;
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn ctx() -> transaction::Ctx {
+        transaction::Ctx::new(Ctx::conn_ctx())
+    }
+
+    async fn countries(server: &TestServer) -> serde_json::Value {
+        let res = server.request("Country", "findMany", json!({"orderBy": {"code": "asc"}, "select": {"code": true, "name": true}})).await.unwrap();
+        res["data"].clone()
+    }
+
+    #[tokio::test]
+    async fn records_are_inserted_once_per_version() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let versions = migrate_data_sets(ctx(), true, true).await.unwrap();
+        assert_eq!(versions.iter().map(|v| (v.name.as_str(), v.version, v.inserted)).collect::<Vec<_>>(), vec![("countries", 2, true)]);
+        assert_eq!(countries(&server).await, json!([]));
+        migrate_data_sets(ctx(), false, true).await.unwrap();
+        assert_eq!(countries(&server).await, json!([{"code": "DE", "name": "Germany"}, {"code": "FR", "name": "France"}]));
+        server.request("Country", "update", json!({"where": {"code": "FR"}, "update": {"name": "République française"}})).await.unwrap();
+        let versions = migrate_data_sets(ctx(), false, true).await.unwrap();
+        assert!(!versions[0].inserted);
+        // a lower stored version inserts the data set again, the existing records are kept
+        server.request("DataSetVersion", "update", json!({"where": {"name": "countries"}, "update": {"version": 1}})).await.unwrap();
+        let versions = migrate_data_sets(ctx(), false, true).await.unwrap();
+        assert!(versions[0].inserted);
+        assert_eq!(countries(&server).await, json!([{"code": "DE", "name": "Germany"}, {"code": "FR", "name": "République française"}]));
+    }
+
+    #[tokio::test]
+    async fn seeding_leaves_them_alone() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        migrate_data_sets(ctx(), false, true).await.unwrap();
+        server.seed(&["fixtures"]).await.unwrap();
+        let error = server.seed(&["countries"]).await.unwrap_err();
+        assert_eq!(error.message(), "data set `countries' runs once, it's inserted by `teo migrate'");
+        // the seeder still knows the records, the next version doesn't insert them twice
+        server.request("DataSetVersion", "update", json!({"where": {"name": "countries"}, "update": {"version": 1}})).await.unwrap();
+        migrate_data_sets(ctx(), false, true).await.unwrap();
+        assert_eq!(countries(&server).await.as_array().unwrap().len(), 2);
+        let res = server.request("Fixture", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn a_version_model_is_required() {
+        let _server = TestServer::new(SCHEMA.replace("@dataSetVersion\n", "")).await.unwrap();
+        let error = migrate_data_sets(ctx(), false, true).await.unwrap_err();
+        assert_eq!(error.message(), "data sets which run once store their versions in a model marked with @dataSetVersion, declare one");
+    }
+
+    #[tokio::test]
+    async fn they_cant_be_autoseeded() {
+        let _server = TestServer::new(SCHEMA.replace("dataset countries", "autoseed dataset countries")).await.unwrap();
+        let error = migrate_data_sets(ctx(), false, true).await.unwrap_err();
+        assert_eq!(error.message(), "data set `countries' runs once, it's inserted by `teo migrate' and can't be autoseeded");
+    }
+}
@@ -0,0 +1,42 @@
+// the decorators are defined in Rust, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+    use teo_runtime::action::action::DELETE;
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::model::{Field, Model};
+    use teo_runtime::object::Object;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.define_model_decorator("undeletable", |_args: Arguments, model: &mut Model| {
+                model.actions.push(!DELETE);
+                Ok(())
+            })?;
+            app.define_model_field_decorator("fallback", |args: Arguments, field: &mut Field| {
+                let value: String = args.get("value")?;
+                field.default = Some(Object::from(Value::String(value)));
+                field.input_omissible = true;
+                Ok(())
+            })
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn decorators_change_models_and_fields() {
+        let server = server().await;
+        let res = server.request("Note", "create", json!({"create": {}})).await.unwrap();
+        assert_eq!(res["data"]["title"], json!("untitled"), "unexpected response {}", res);
+        let res = server.request("Note", "delete", json!({"where": {"id": 1}})).await.unwrap();
+        assert_eq!(res["error"]["type"], json!("MethodNotAllowed"), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn decorators_are_defined_before_loading() {
+        let server = server().await;
+        assert!(server.app().define_model_decorator("late", |_args: Arguments, _model: &mut Model| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+use crate::server_tests;
+
+// SQLite in memory runs without transactions, a dry run there couldn't be rolled back
+server_tests!(4083, {
+    use serde_json::json;
+    use crate::lib::fixture::assert_field_error;
+    use crate::lib::req;
+
+    #[test]
+    fn dry_runs_are_rejected_without_transactions() {
+        let res = req(PORT, "create", "Author", json!({"create": {"name": "Ann"}, "dryRun": true}));
+        assert_field_error(&res, "dryRun", "dry run needs a database with transactions");
+        let res = req(PORT, "count", "Author", json!({}));
+        assert_eq!(res["data"], json!(0), "unexpected response {}", res);
+    }
+});
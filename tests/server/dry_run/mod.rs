@@ -0,0 +1,70 @@
+pub mod memory;
+
+// dry runs are rolled back, which SQLite in memory can't do, so these tests run the server in
+// process with a SQLite file
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+    use crate::lib::fixture::assert_field_error;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn count(server: &TestServer, model: &str) -> JsonValue {
+        server.request(model, "count", json!({})).await.unwrap()["data"].clone()
+    }
+
+    #[tokio::test]
+    async fn create_returns_the_record_and_writes_nothing() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Author", "create", json!({
+            "create": {"name": "Ann", "books": {"create": [{"title": "First"}, {"title": "Second"}]}},
+            "include": {"books": true},
+            "dryRun": true,
+        })).await.unwrap();
+        assert_eq!(res["meta"]["dryRun"], json!(true), "{}", res);
+        assert_eq!(res["data"]["name"], json!("Ann"));
+        assert_eq!(res["data"]["books"].as_array().unwrap().len(), 2, "{}", res);
+        assert_eq!(count(&server, "Author").await, json!(0));
+        assert_eq!(count(&server, "Book").await, json!(0));
+    }
+
+    #[tokio::test]
+    async fn update_and_delete_are_rolled_back() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Author", "create", json!({"create": {"name": "Ann"}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        let res = server.request("Author", "update", json!({"where": {"id": id}, "update": {"name": "Bob"}, "dryRun": true})).await.unwrap();
+        assert_eq!(res["data"]["name"], json!("Bob"), "{}", res);
+        let res = server.request("Author", "delete", json!({"where": {"id": id}, "dryRun": true})).await.unwrap();
+        assert_eq!(res["meta"]["dryRun"], json!(true), "{}", res);
+        let res = server.request("Author", "findUnique", json!({"where": {"id": id}})).await.unwrap();
+        assert_eq!(res["data"]["name"], json!("Ann"));
+    }
+
+    #[tokio::test]
+    async fn unique_violation_is_rejected() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Author", "create", json!({"create": {"name": "Ann"}})).await.unwrap();
+        let res = server.request("Author", "create", json!({"create": {"name": "Ann"}, "dryRun": true})).await.unwrap();
+        assert_field_error(&res, "create.name", "unique value duplicated: name");
+        assert_eq!(count(&server, "Author").await, json!(1));
+    }
+
+    #[tokio::test]
+    async fn permission_violation_is_rejected() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Review", "create", json!({"create": {"body": "anonymous"}, "dryRun": true})).await.unwrap();
+        assert!(res["error"].is_object(), "{}", res);
+        assert!(res.get("data").is_none(), "{}", res);
+    }
+
+    #[tokio::test]
+    async fn batch_items_reject_dry_runs() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request_at_path("/batch/action", json!({"actions": [
+            {"model": "Author", "action": "create", "args": {"create": {"name": "Ann"}, "dryRun": true}},
+        ]})).await.unwrap();
+        assert_eq!(res["error"]["type"], json!("ValueError"), "{}", res);
+        assert_eq!(count(&server, "Author").await, json!(0));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::server_tests;
+
+server_tests!(4042, {
+    use serde_json::{json, Value};
+    use crate::lib::fixture::assert_field_error;
+    use crate::lib::req;
+
+    fn create_page(slug: &str, sections: &[&str]) -> Value {
+        let sections: Vec<Value> = sections.iter().map(|body| json!({"body": body})).collect();
+        req(PORT, "create", "Page", json!({"create": {"slug": slug, "title": "Title", "sections": {"create": sections}}}))["data"].clone()
+    }
+
+    fn section_bodies(page: &Value) -> Vec<String> {
+        let res = req(PORT, "findMany", "Section", json!({"where": {"pageId": page["id"]}, "orderBy": {"id": "asc"}}));
+        res["data"].as_array().unwrap().iter().map(|s| s["body"].as_str().unwrap().to_owned()).collect()
+    }
+
+    #[test]
+    fn duplicates_run_the_on_duplicate_pipelines() {
+        let page = create_page("home", &[]);
+        let res = req(PORT, "duplicate", "Page", json!({"where": {"id": page["id"]}}));
+        assert_eq!(res["data"]["slug"], "home-copy", "unexpected response {}", res);
+        assert_eq!(res["data"]["title"], "Title");
+        assert_ne!(res["data"]["id"], page["id"]);
+    }
+
+    #[test]
+    fn copy_overrides_the_duplicated_values() {
+        let page = create_page("about", &[]);
+        let res = req(PORT, "duplicate", "Page", json!({"where": {"id": page["id"]}, "copy": {"slug": "about-us"}}));
+        assert_eq!(res["data"]["slug"], "about-us", "unexpected response {}", res);
+    }
+
+    #[test]
+    fn relations_are_duplicated_when_named() {
+        let page = create_page("blog", &["intro", "outro"]);
+        let res = req(PORT, "duplicate", "Page", json!({"where": {"id": page["id"]}, "relations": {"sections": true}}));
+        assert_eq!(res["data"]["slug"], "blog-copy", "unexpected response {}", res);
+        assert_eq!(section_bodies(&res["data"]), vec!["intro", "outro"]);
+        assert_eq!(section_bodies(&page), vec!["intro", "outro"]);
+        let res = req(PORT, "duplicate", "Page", json!({"where": {"id": page["id"]}, "copy": {"slug": "blog-shallow"}}));
+        assert_eq!(section_bodies(&res["data"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn only_to_many_relations_are_duplicated() {
+        let page = create_page("contact", &["form"]);
+        let sections = req(PORT, "findMany", "Section", json!({"where": {"pageId": page["id"]}}));
+        let res = req(PORT, "duplicate", "Section", json!({"where": {"id": sections["data"][0]["id"]}, "relations": {"page": true}}));
+        assert_field_error(&res, "relations.page", "relation cannot be duplicated");
+    }
+});
@@ -0,0 +1,73 @@
+// the files are embedded in the app, so these tests run the server in process
+mod test {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{read_body, TestRequest};
+    use serde_json::json;
+    use teo::prelude::StaticFilesOptions;
+    use teo::server::embedded::EmbeddedFiles;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+    static FILES: EmbeddedFiles = &[
+        ("index.html", b"<html>app</html>"),
+        ("js/main.js", b"console.log(\"app\")"),
+    ];
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.embed_static_files("/app", FILES, StaticFilesOptions { spa_fallback: true, cache_control: Some("no-cache".to_owned()) });
+            Ok(())
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_files_under_the_mount() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/app/js/main.js").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("Cache-Control").unwrap(), "no-cache");
+        assert_eq!(res.headers().get("Content-Type").unwrap(), "text/javascript");
+        assert!(res.headers().get("ETag").is_some());
+        assert_eq!(read_body(res).await, "console.log(\"app\")");
+        let res = server.call(TestRequest::get().uri("/app").to_request()).await;
+        assert_eq!(read_body(res).await, "<html>app</html>");
+        // only whole segments match the mount
+        let res = server.call(TestRequest::get().uri("/application").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn answers_conditional_requests_with_not_modified() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/app/index.html").to_request()).await;
+        let etag = res.headers().get("ETag").unwrap().to_str().unwrap().to_owned();
+        let res = server.call(TestRequest::get().uri("/app/index.html").insert_header(("If-None-Match", etag.as_str())).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get("ETag").unwrap(), etag.as_str());
+        assert!(read_body(res).await.is_empty());
+        let res = server.call(TestRequest::get().uri("/app/index.html").insert_header(("If-None-Match", "\"stale\"")).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_index_for_client_routes() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/app/settings/profile").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "<html>app</html>");
+        // a missing file is not a client route
+        let res = server.call(TestRequest::get().uri("/app/js/missing.js").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn never_shadows_the_routes() {
+        let server = server().await;
+        server.request("Note", "create", json!({"create": {"title": "first"}})).await.unwrap();
+        let res = server.request("Note", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"][0]["title"], json!("first"), "unexpected response {}", res);
+        // files are only served for GET
+        let res = server.call(TestRequest::post().uri("/app/index.html").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,42 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.admin(|_| async { true });
+            Ok(())
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn pipelines_read_member_data() {
+        let server = server().await;
+        let res = server.request("Staff", "create", json!({"create": {"role": "ADMIN"}})).await.unwrap();
+        assert_eq!(res["data"], json!({"id": 1, "role": "ADMIN", "rank": 10, "label": "Administrator"}));
+        let res = server.request("Staff", "create", json!({"create": {"role": "MEMBER"}})).await.unwrap();
+        assert_eq!(res["data"], json!({"id": 2, "role": "MEMBER", "rank": 1, "label": "Member"}));
+        let res = server.request("Staff", "create", json!({"create": {"role": "GUEST"}})).await.unwrap();
+        assert_eq!(res["data"], json!({"id": 3, "role": "GUEST"}));
+    }
+
+    #[tokio::test]
+    async fn metadata_lists_member_data() {
+        let server = server().await;
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        assert_eq!(res["data"]["enums"]["Role"]["members"], json!([
+            {"name": "ADMIN", "localizedName": "ADMIN", "description": "Runs the site.", "meta": {"label": "Administrator", "rank": 10}},
+            {"name": "MEMBER", "localizedName": "MEMBER", "description": null, "meta": {"label": "Member", "rank": 1}},
+            {"name": "GUEST", "localizedName": "GUEST", "description": null, "meta": null},
+        ]));
+    }
+
+    #[tokio::test]
+    async fn member_data_is_a_json_object() {
+        let schema = SCHEMA.replace("{\"rank\": 1}", "rank: 1");
+        let error = TestServer::new(schema).await.err().unwrap();
+        assert_eq!(error.message(), "data of enum member Role.MEMBER is not a JSON object");
+    }
+}
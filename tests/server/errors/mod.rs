@@ -0,0 +1,43 @@
+use crate::server_tests;
+
+server_tests!(4036, {
+    use serde_json::{json, Value};
+    use crate::lib::req;
+
+    fn req_with_request_id(request_id: Option<&str>) -> (String, Value) {
+        let url = format!("http://127.0.0.1:{}/Tag/create", PORT);
+        let mut builder = reqwest::blocking::Client::new().post(url).json(&json!({"create": {}}));
+        if let Some(request_id) = request_id {
+            builder = builder.header("X-Request-Id", request_id);
+        }
+        let res = builder.send().unwrap();
+        let header = res.headers().get("X-Request-Id").unwrap().to_str().unwrap().to_owned();
+        (header, res.json().unwrap())
+    }
+
+    #[test]
+    fn request_ids_are_propagated() {
+        let (header, res) = req_with_request_id(Some("trace-1"));
+        assert_eq!(header, "trace-1");
+        assert_eq!(res["error"]["requestId"], "trace-1");
+    }
+
+    #[test]
+    fn request_ids_are_generated() {
+        let (header, res) = req_with_request_id(None);
+        assert_eq!(header.len(), 36);
+        assert_eq!(res["error"]["requestId"], header.as_str());
+    }
+
+    #[test]
+    fn codes_follow_the_published_table() {
+        for (res, error_type, code) in [
+            (req(PORT, "create", "Tag", json!({"create": {}})), "ValueError", "T4001"),
+            (req(PORT, "findMany", "Missing", json!({})), "NotFound", "T4040"),
+            (req(PORT, "delete", "Note", json!({"where": {"id": 1}})), "MethodNotAllowed", "T4050"),
+        ] {
+            assert_eq!(res["error"]["type"], error_type, "unexpected response {}", res);
+            assert_eq!(res["error"]["code"], code, "unexpected response {}", res);
+        }
+    }
+});
@@ -0,0 +1,53 @@
+use crate::server_tests;
+
+server_tests!(4039, {
+    use serde_json::{json, Value};
+    use crate::lib::fixture::assert_error;
+    use crate::lib::req;
+
+    fn req_with_if_match(action: &str, data: Value, if_match: Option<&str>) -> (Option<String>, Value) {
+        let url = format!("http://127.0.0.1:{}/Note/{}", PORT, action);
+        let mut builder = reqwest::blocking::Client::new().post(url).json(&data);
+        if let Some(if_match) = if_match {
+            builder = builder.header("If-Match", if_match);
+        }
+        let res = builder.send().unwrap();
+        let etag = res.headers().get("ETag").map(|v| v.to_str().unwrap().to_owned());
+        (etag, res.json().unwrap())
+    }
+
+    fn create_note(title: &str) -> i64 {
+        req(PORT, "create", "Note", json!({"create": {"title": title}}))["data"]["id"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn updates_with_the_current_etag_succeed() {
+        let id = create_note("first");
+        let (etag, _) = req_with_if_match("findUnique", json!({"where": {"id": id}}), None);
+        let etag = etag.unwrap();
+        let (updated, res) = req_with_if_match("update", json!({"where": {"id": id}, "update": {"title": "second"}}), Some(&etag));
+        assert_eq!(res["data"]["title"], "second", "unexpected response {}", res);
+        assert_ne!(updated.unwrap(), etag);
+    }
+
+    #[test]
+    fn updates_with_a_stale_etag_fail() {
+        let id = create_note("first");
+        let (etag, _) = req_with_if_match("findUnique", json!({"where": {"id": id}}), None);
+        req(PORT, "update", "Note", json!({"where": {"id": id}, "update": {"title": "second"}}));
+        let (_, res) = req_with_if_match("update", json!({"where": {"id": id}, "update": {"title": "third"}}), Some(&etag.unwrap()));
+        assert_error(&res, "PreconditionFailed", "record has been modified since it was fetched");
+        assert_eq!(res["error"]["code"], "T4120");
+        let res = req(PORT, "findUnique", "Note", json!({"where": {"id": id}}));
+        assert_eq!(res["data"]["title"], "second");
+    }
+
+    #[test]
+    fn deletes_honour_if_match() {
+        let id = create_note("first");
+        let (_, res) = req_with_if_match("delete", json!({"where": {"id": id}}), Some("\"stale\""));
+        assert_error(&res, "PreconditionFailed", "record has been modified since it was fetched");
+        let (_, res) = req_with_if_match("delete", json!({"where": {"id": id}}), Some("*"));
+        assert_eq!(res["data"]["id"], id, "unexpected response {}", res);
+    }
+});
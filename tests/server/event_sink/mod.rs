@@ -0,0 +1,131 @@
+// sinks are registered on the app, so these tests run the server in process
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use serde_json::{json, Value as JsonValue};
+    use teo::event_sink::{EventFormat, EventMessage, EventSinkOptions};
+    use teo::event_sink::nats::NatsSink;
+    use teo::test::TestServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::time::sleep;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    type Published = Arc<Mutex<Vec<EventMessage>>>;
+
+    async fn server(options: EventSinkOptions) -> (TestServer, Published) {
+        let published: Published = Arc::new(Mutex::new(vec![]));
+        let sink = published.clone();
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.event_sink(move |message: EventMessage| {
+                let sink = sink.clone();
+                async move {
+                    sink.lock().unwrap().push(message);
+                    Ok(())
+                }
+            }, options);
+            Ok(())
+        }).await.unwrap();
+        (server, published)
+    }
+
+    // the events are published in the background
+    async fn wait_for<T>(items: &Arc<Mutex<Vec<T>>>, count: usize) {
+        for _ in 0..100 {
+            if items.lock().unwrap().len() >= count {
+                return;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    fn payloads(published: &Published) -> Vec<JsonValue> {
+        published.lock().unwrap().iter().map(|m| serde_json::from_slice(&m.payload).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn changes_are_published_after_commit() {
+        let (server, published) = server(EventSinkOptions::default()).await;
+        let res = server.request("Article", "create", json!({"create": {"title": "First", "publishedOn": "2026-01-02", "secret": "draft"}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        server.request("Article", "update", json!({"where": {"id": id}, "update": {"title": "Second"}})).await.unwrap();
+        server.request("Article", "delete", json!({"where": {"id": id}})).await.unwrap();
+        wait_for(&published, 3).await;
+        let topics: Vec<String> = published.lock().unwrap().iter().map(|m| m.topic.clone()).collect();
+        assert_eq!(topics, vec!["teo.Article"; 3]);
+        assert_eq!(published.lock().unwrap()[0].key, json!({"id": id}).to_string());
+        let events = payloads(&published);
+        assert_eq!(events.iter().map(|e| e["kind"].clone()).collect::<Vec<_>>(), vec![json!("create"), json!("update"), json!("delete")]);
+        assert_eq!(events[0]["model"], json!("Article"));
+        assert_eq!(events[0]["record"], json!({"id": id, "title": "First", "publishedOn": "2026-01-02"}));
+        assert_eq!(events[1]["record"]["title"], json!("Second"));
+    }
+
+    #[tokio::test]
+    async fn rolled_back_writes_publish_nothing() {
+        let (server, published) = server(EventSinkOptions::default()).await;
+        server.request("Remark", "create", json!({"create": {"code": "taken"}})).await.unwrap();
+        wait_for(&published, 1).await;
+        published.lock().unwrap().clear();
+        let res = server.request("Article", "create", json!({"create": {"title": "Doomed", "remarks": {"create": {"code": "taken"}}}})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        let res = server.request("Article", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!([]));
+        sleep(Duration::from_millis(200)).await;
+        assert!(published.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn schema_json_wraps_the_events_of_the_chosen_models() {
+        let options = EventSinkOptions { topic: "cdc.{model}".to_owned(), format: EventFormat::SchemaJson, models: vec!["Remark".to_owned()] };
+        let (server, published) = server(options).await;
+        server.request("Article", "create", json!({"create": {"title": "First", "remarks": {"create": {"code": "a"}}}})).await.unwrap();
+        wait_for(&published, 1).await;
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(published.lock().unwrap().len(), 1);
+        assert_eq!(published.lock().unwrap()[0].topic, "cdc.Remark");
+        let event = payloads(&published).remove(0);
+        assert_eq!(event["payload"]["record"]["code"], json!("a"));
+        let record = event["schema"]["fields"].as_array().unwrap().iter().find(|f| f["field"] == json!("record")).unwrap().clone();
+        assert_eq!(record["name"], json!("Remark"));
+        assert_eq!(record["fields"], json!([
+            {"type": "int32", "field": "id", "optional": false},
+            {"type": "string", "field": "code", "optional": false},
+            {"type": "int32", "field": "articleId", "optional": true},
+        ]));
+    }
+
+    #[tokio::test]
+    async fn nats_sink_publishes_to_the_subject_of_the_topic() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let lines = Arc::new(Mutex::new(vec![]));
+        let received = lines.clone();
+        // a server which greets the client and keeps what it receives
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            write.write_all(b"INFO {\"server_id\":\"test\"}\r\n").await.unwrap();
+            let mut reader = BufReader::new(read);
+            let mut line = String::new();
+            while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+                received.lock().unwrap().push(line.trim_end().to_owned());
+                line.clear();
+            }
+        });
+        let sink = NatsSink::new(&format!("nats://127.0.0.1:{}", port)).unwrap();
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.event_sink(sink, EventSinkOptions::default());
+            Ok(())
+        }).await.unwrap();
+        server.request("Article", "create", json!({"create": {"title": "First"}})).await.unwrap();
+        wait_for(&lines, 3).await;
+        let lines = lines.lock().unwrap().clone();
+        assert!(lines[0].starts_with("CONNECT {"), "{:?}", lines);
+        assert_eq!(lines[1], format!("PUB teo.Article {}", lines[2].len()));
+        let event: JsonValue = serde_json::from_str(&lines[2]).unwrap();
+        assert_eq!(event["kind"], json!("create"));
+        assert_eq!(event["record"]["title"], json!("First"));
+    }
+}
@@ -0,0 +1,46 @@
+// plans are read from the database of the app, so these tests run the server in process
+mod test {
+    use std::time::Duration;
+    use serde_json::json;
+    use teo::explain::explain;
+    use teo::test::TestServer;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn seeded(server: &TestServer) {
+        let res = server.request("User", "create", json!({"create": {"email": "ada@example.com", "posts": {"create": [{"title": "Notes"}]}}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("ada@example.com"));
+    }
+
+    #[tokio::test]
+    async fn reads_are_explained() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        seeded(&server).await;
+        let plans = explain("User", &teon!({"where": {"email": "ada@example.com"}, "include": {"posts": true}})).await.unwrap();
+        assert_eq!(plans.len(), 2, "{:?}", plans);
+        assert!(plans[0].statement.to_uppercase().contains("FROM `USER`"), "{}", plans[0].statement);
+        assert!(format!("{:?}", plans[0].plan).contains("SEARCH TABLE User USING"), "{:?}", plans[0].plan);
+        assert!(plans[1].statement.to_uppercase().contains("FROM `POST`"), "{}", plans[1].statement);
+    }
+
+    #[tokio::test]
+    async fn unknown_model_is_an_error() {
+        let _server = TestServer::new(SCHEMA).await.unwrap();
+        let error = explain("Comment", &teon!({})).await.err().unwrap();
+        assert_eq!(error.message(), "model `Comment' is not found");
+    }
+
+    #[tokio::test]
+    async fn slow_reads_are_answered() {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.explain_slow_queries(Duration::ZERO);
+            Ok(())
+        }).await.unwrap();
+        seeded(&server).await;
+        let res = server.request("User", "findMany", json!({"include": {"posts": true}})).await.unwrap();
+        assert_eq!(res["data"][0]["posts"][0]["title"], json!("Notes"));
+        let res = server.request("Post", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(1));
+    }
+}
@@ -0,0 +1,62 @@
+// the factory creates its records through the Rust API, so these tests run the server in process
+mod test {
+    use std::collections::BTreeSet;
+    use serde_json::{json, Value};
+    use teo::app::ctx::Ctx;
+    use teo::prelude::Factory;
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction;
+    use crate::lib::run_command;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn factory(seed: u64) -> Factory {
+        Factory::new(transaction::Ctx::new(Ctx::conn_ctx()), seed)
+    }
+
+    async fn users(server: &TestServer) -> Value {
+        let res = server.request("User", "findMany", json!({"orderBy": {"id": "asc"}, "select": {"email": true, "name": true, "role": true}, "include": {"posts": {"select": {"title": true}}}})).await.unwrap();
+        res["data"].clone()
+    }
+
+    #[tokio::test]
+    async fn records_get_their_required_relations() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        factory(1).create_many("Post", 3).await.unwrap();
+        let users = users(&server).await;
+        let users = users.as_array().unwrap();
+        assert_eq!(users.len(), 3);
+        for user in users {
+            assert_eq!(user["posts"].as_array().unwrap().len(), 1, "unexpected user {}", user);
+            assert!(["admin", "member"].contains(&user["role"].as_str().unwrap()), "unexpected user {}", user);
+        }
+        let emails: BTreeSet<&str> = users.iter().map(|u| u["email"].as_str().unwrap()).collect();
+        assert_eq!(emails.len(), 3);
+        assert!(emails.iter().all(|e| e.ends_with("@example.com")));
+    }
+
+    #[tokio::test]
+    async fn the_same_seed_creates_the_same_records() {
+        let mut runs = vec![];
+        for _ in 0..2 {
+            let server = TestServer::new(SCHEMA).await.unwrap();
+            factory(7).create_many("User", 4).await.unwrap();
+            runs.push(users(&server).await);
+        }
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn unknown_models_are_reported() {
+        let _server = TestServer::new(SCHEMA).await.unwrap();
+        let error = factory(1).create("Missing").await.unwrap_err();
+        assert_eq!(error.message(), "model not found: Missing");
+    }
+
+    #[test]
+    fn fake_counts_are_parsed() {
+        let output = run_command(file!(), &["seed", "--fake", "User"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("expect format MODEL:COUNT"));
+    }
+}
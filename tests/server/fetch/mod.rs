@@ -0,0 +1,78 @@
+// the items call a stub HTTP server which listens on a port picked at runtime, so these tests
+// run the server in process with that port written into the schema
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // answers `/rates?currency=EUR' with a rate, `SLOW' after a second and `GONE' with a 404,
+    // `/echo' returns the `X-Token' header when the body is the expected JSON
+    fn stub() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut head = vec![];
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                            break;
+                        }
+                        head.push(line.trim_end().to_owned());
+                    }
+                    let header = |name: &str| head.iter().find_map(|h| h.split_once(':').filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.trim().to_owned()));
+                    let length = header("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let (status, response) = match head[0].split(' ').nth(1).unwrap() {
+                        "/rates?currency=EUR" => (200, json!({"rates": [{"value": 0.5}]}).to_string()),
+                        "/rates?currency=SLOW" => {
+                            thread::sleep(Duration::from_secs(1));
+                            (200, json!({"rates": [{"value": 1.0}]}).to_string())
+                        }
+                        "/echo" if serde_json::from_slice::<JsonValue>(&body).ok() == Some(json!({"currency": "EUR"})) => (200, json!({"token": header("x-token")}).to_string()),
+                        _ => (404, "{}".to_owned()),
+                    };
+                    let _ = write!(stream, "HTTP/1.1 {} OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, response.len(), response);
+                });
+            }
+        });
+        port
+    }
+
+    async fn server() -> TestServer {
+        TestServer::new(SCHEMA.replace("{port}", &stub().to_string())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn response_is_extracted() {
+        let server = server().await;
+        let res = server.request("Price", "create", json!({"create": {"currency": "EUR"}})).await.unwrap();
+        assert_eq!(res["data"]["rate"], json!(0.5));
+        assert_eq!(res["data"]["token"], json!("secret"));
+    }
+
+    #[tokio::test]
+    async fn failed_status_is_a_value_error() {
+        let server = server().await;
+        let res = server.request("Price", "create", json!({"create": {"currency": "GONE"}})).await.unwrap();
+        assert_eq!(res["error"]["fields"]["create.rate"], json!("fetch failed with status 404"));
+        let res = server.request("Price", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(0));
+    }
+
+    #[tokio::test]
+    async fn slow_response_times_out() {
+        let server = server().await;
+        let res = server.request("Price", "create", json!({"create": {"currency": "SLOW"}})).await.unwrap();
+        assert_eq!(res["error"]["fields"]["create.rate"], json!("fetch failed: request timed out"));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::server_tests;
+
+server_tests!(4024, {
+    use serde_json::{json, Value};
+    use crate::lib::req;
+    use crate::lib::fixture::assert_field_error;
+    use crate::{assert_json, matcher};
+
+    // each test filters its own group of Ann (20), an account without nickname (30) and Cy
+    fn create_group(group: &str) {
+        for create in [
+            json!({"group": group, "nickname": "Ann", "age": 20}),
+            json!({"group": group, "age": 30}),
+            json!({"group": group, "nickname": "Cy"}),
+        ] {
+            req(PORT, "create", "Account", json!({"create": create}));
+        }
+    }
+
+    fn find_ages(group: &str, filter: Value) -> Value {
+        req(PORT, "findMany", "Account", json!({
+            "where": {"AND": [{"group": group}, filter]},
+            "orderBy": {"id": "asc"},
+            "select": {"age": true},
+        }))
+    }
+
+    #[test]
+    fn is_null() {
+        create_group("isNull");
+        assert_json!(find_ages("isNull", json!({"nickname": {"isNull": true}})), matcher!({
+            "data": [{"age": 30}],
+            "meta": {"count": 1},
+        }));
+        assert_json!(find_ages("isNull", json!({"nickname": {"isNull": false}})), matcher!({
+            "data": [{"age": 20}, {}],
+            "meta": {"count": 2},
+        }));
+    }
+
+    #[test]
+    fn bare_null() {
+        create_group("bareNull");
+        assert_json!(find_ages("bareNull", json!({"nickname": null})), matcher!({
+            "data": [{"age": 30}],
+            "meta": {"count": 1},
+        }));
+    }
+
+    #[test]
+    fn is_null_conflicts_and_types() {
+        let res = find_ages("conflicts", json!({"nickname": {"isNull": true, "equals": "Ann"}}));
+        assert_field_error(&res, "where.AND.1.nickname.isNull", "cannot be combined with `equals'");
+        let res = find_ages("conflicts", json!({"nickname": {"isNull": "yes"}}));
+        assert_field_error(&res, "where.AND.1.nickname.isNull", "expect bool");
+    }
+
+    #[test]
+    fn single_filters_in_and_and_or() {
+        create_group("single");
+        assert_json!(find_ages("single", json!({"AND": {"age": {"gte": 25}}})), matcher!({
+            "data": [{"age": 30}],
+            "meta": {"count": 1},
+        }));
+        assert_json!(find_ages("single", json!({"OR": {"nickname": "Ann"}})), matcher!({
+            "data": [{"age": 20}],
+            "meta": {"count": 1},
+        }));
+    }
+
+    #[test]
+    fn not_excludes_every_filter_in_a_list() {
+        create_group("notList");
+        assert_json!(find_ages("notList", json!({"NOT": [{"age": 20}, {"age": null}]})), matcher!({
+            "data": [{"age": 30}],
+            "meta": {"count": 1},
+        }));
+    }
+
+    #[test]
+    fn nested_combinators() {
+        create_group("nested");
+        let filter = json!({"OR": [
+            {"AND": [{"age": {"gt": 10}}, {"nickname": {"isNull": false}}]},
+            {"NOT": {"age": {"isNull": false}}},
+        ]});
+        assert_json!(find_ages("nested", filter), matcher!({
+            "data": [{"age": 20}, {}],
+            "meta": {"count": 2},
+        }));
+    }
+});
@@ -0,0 +1,64 @@
+// finders are built through the Rust API, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::object::finder::Finder;
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction;
+    use teo_runtime::model;
+    use teo_runtime::sort::Sort;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn authors() -> Finder<model::Object> {
+        let model = Ctx::main_namespace().model_at_path(&vec!["Author"]).unwrap();
+        Finder::new(model::Ctx { transaction_ctx: transaction::Ctx::new(Ctx::conn_ctx()), model })
+    }
+
+    async fn create_authors(server: &TestServer) {
+        for (name, age) in [("Ann", 31), ("Bob", 45), ("Anna", 27)] {
+            server.request("Author", "create", json!({"create": {"name": name, "age": age}})).await.unwrap();
+        }
+    }
+
+    fn names(objects: &[model::Object]) -> Vec<String> {
+        objects.iter().map(|o| o.get_value("name").unwrap().as_str().unwrap().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn builds_the_finder() {
+        let _server = TestServer::new(SCHEMA).await.unwrap();
+        let finder = authors().where_field("name", "contains", "An").where_field("age", "gte", 30).order_by("age", Sort::Desc).include("posts").skip(1).take(2).finder().unwrap();
+        assert_eq!(finder, teon!({
+            "where": {"name": {"contains": "An"}, "age": {"gte": 30}},
+            "orderBy": [{"age": "desc"}],
+            "include": {"posts": true},
+            "skip": 1_i64,
+            "take": 2_i64,
+        }));
+    }
+
+    #[tokio::test]
+    async fn finds_the_matching_records() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_authors(&server).await;
+        let objects = authors().where_field("name", "startsWith", "An").order_by("age", Sort::Asc).find_many().await.unwrap();
+        assert_eq!(names(&objects), vec!["Anna", "Ann"]);
+        let object = authors().where_equals("name", "Bob").find_first().await.unwrap().unwrap();
+        assert_eq!(object.get_value("age").unwrap(), teon!(45));
+        assert_eq!(authors().where_field("age", "lt", 40).take(1).count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_fields_fail_the_query() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        create_authors(&server).await;
+        let error = authors().where_field("email", "contains", "a").find_many().await.unwrap_err();
+        assert_eq!(error.message(), "field `email' is not found on model `Author'");
+        let error = authors().order_by("email", Sort::Asc).finder().unwrap_err();
+        assert_eq!(error.message(), "field `email' is not found on model `Author'");
+        let error = authors().include("comments").finder().unwrap_err();
+        assert_eq!(error.message(), "relation `comments' is not found on model `Author'");
+    }
+}
@@ -0,0 +1,34 @@
+// the flavor is detected from the version a MySQL server reports, these tests need no server
+mod test {
+    use teo::app::database::flavor::{MySQLFlavor, MySQLServer};
+
+    #[test]
+    fn detects_the_flavor_from_the_version() {
+        assert_eq!(MySQLServer::parse("8.0.35"), Some(MySQLServer { flavor: MySQLFlavor::MySQL, version: (8, 0, 35) }));
+        assert_eq!(MySQLServer::parse("8.0.35-0ubuntu0.22.04.1"), Some(MySQLServer { flavor: MySQLFlavor::MySQL, version: (8, 0, 35) }));
+        assert_eq!(MySQLServer::parse("10.11.6-MariaDB-1:10.11.6+maria~ubu2204"), Some(MySQLServer { flavor: MySQLFlavor::MariaDB, version: (10, 11, 6) }));
+        assert_eq!(MySQLServer::parse("5.7.25-TiDB-v7.1.0"), Some(MySQLServer { flavor: MySQLFlavor::TiDB, version: (7, 1, 0) }));
+        assert_eq!(MySQLServer::parse("unknown"), None);
+    }
+
+    #[test]
+    fn capabilities_follow_the_flavor() {
+        let mariadb = MySQLServer::parse("10.5.2-MariaDB").unwrap();
+        assert!(mariadb.supports_returning());
+        assert!(!mariadb.supports_json_table());
+        assert!(!MySQLServer::parse("10.4.32-MariaDB").unwrap().supports_returning());
+        let tidb = MySQLServer::parse("5.7.25-TiDB-v7.1.0").unwrap();
+        assert!(tidb.clusters_primary_keys());
+        assert!(!tidb.supports_json_table());
+        assert!(!MySQLServer::parse("8.0.35").unwrap().supports_returning());
+    }
+
+    #[test]
+    fn reads_are_explained_per_flavor() {
+        let statement = "SELECT * FROM `users`";
+        assert_eq!(MySQLServer::parse("8.0.35").unwrap().explain(statement), "EXPLAIN ANALYZE SELECT * FROM `users`");
+        assert_eq!(MySQLServer::parse("5.7.44").unwrap().explain(statement), "EXPLAIN FORMAT=JSON SELECT * FROM `users`");
+        assert_eq!(MySQLServer::parse("10.11.6-MariaDB").unwrap().explain(statement), "ANALYZE FORMAT=JSON SELECT * FROM `users`");
+        assert_eq!(MySQLServer::parse("5.7.25-TiDB-v7.1.0").unwrap().explain(statement), "EXPLAIN ANALYZE SELECT * FROM `users`");
+    }
+}
@@ -0,0 +1,124 @@
+use crate::server_tests;
+
+server_tests!(4021, {
+    use serde_json::json;
+    use crate::lib::{req, req_form, req_multipart};
+    use crate::lib::fixture::assert_error;
+    use crate::{assert_json, matcher};
+
+    #[test]
+    fn urlencoded_values_are_converted_to_field_types() {
+        let res = req_form(PORT, "create", "Post", &[
+            ("create[title]", "Converted"),
+            ("create[views]", "12"),
+            ("create[rating]", "2.5"),
+            ("create[published]", "true"),
+            ("create[publishedAt]", "2024-01-02T03:04:05.000Z"),
+        ]);
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "title": "Converted",
+                "views": 12,
+                "rating": 2.5,
+                "published": true,
+                "publishedAt": {"$datetime": "2024-01-02T03:04:05.000Z"},
+            }
+        }));
+    }
+
+    #[test]
+    fn bracket_keys_nest_objects_and_arrays() {
+        let res = req_form(PORT, "create", "Author", &[
+            ("create[name]", "Nested"),
+            ("create[posts][create][0][title]", "Nested 0"),
+            ("create[posts][create][1][title]", "Nested 1"),
+        ]);
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "name": "Nested",
+            }
+        }));
+        let res = req(PORT, "count", "Post", json!({
+            "where": {"title": {"startsWith": "Nested "}},
+        }));
+        assert_json!(res, matcher!({"data": 2}));
+    }
+
+    #[test]
+    fn flags_are_converted_to_bools() {
+        let res = req_form(PORT, "create", "Post", &[
+            ("create[title]", "Flags"),
+            ("select[title]", "true"),
+        ]);
+        assert_json!(res, matcher!({
+            "data": {
+                "title": "Flags",
+            }
+        }));
+    }
+
+    #[test]
+    fn index_gaps_are_rejected() {
+        let res = req_form(PORT, "create", "Author", &[
+            ("create[name]", "Gap"),
+            ("create[posts][create][0][title]", "Gap 0"),
+            ("create[posts][create][2][title]", "Gap 2"),
+        ]);
+        assert_error(&res, "ValueError", "form key `create[posts][create][2][title]' skips array indices");
+    }
+
+    #[test]
+    fn conflicting_keys_are_rejected() {
+        let res = req_form(PORT, "create", "Post", &[
+            ("create[title]", "Conflict"),
+            ("create[title][nested]", "Conflict"),
+        ]);
+        assert_error(&res, "ValueError", "form key `create[title][nested]' conflicts with another key");
+    }
+
+    #[test]
+    fn multipart_fields_are_read() {
+        let res = req_multipart(PORT, "create", "Post", &[
+            ("create[title]", None, "Multipart"),
+            ("create[views]", None, "3"),
+        ]);
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "title": "Multipart",
+                "views": 3,
+            }
+        }));
+    }
+
+    #[test]
+    fn multipart_files_are_rejected() {
+        let res = req_multipart(PORT, "create", "Post", &[
+            ("create[title]", None, "File"),
+            ("create[views]", Some("views.txt"), "3"),
+        ]);
+        assert_error(&res, "ValueError", "file uploads are not supported by builtin actions, found `create[views]'");
+    }
+
+    #[test]
+    fn bodies_over_the_size_limit_are_rejected() {
+        let title = "a".repeat(300_000);
+        let res = req_form(PORT, "create", "Post", &[("create[title]", title.as_str())]);
+        assert_error(&res, "PayloadTooLarge", "request body exceeds 262144 bytes");
+        let res = req_multipart(PORT, "create", "Post", &[("create[title]", None, title.as_str())]);
+        assert_error(&res, "PayloadTooLarge", "request body exceeds 262144 bytes");
+    }
+
+    #[test]
+    fn malformed_multipart_bodies_are_rejected() {
+        let url = format!("http://127.0.0.1:{}/Post/create", PORT);
+        let body = "--teo-test-boundary\r\nContent-Disposition: form-data; name=\"create[title]\"\r\n\r\nCut";
+        let res: serde_json::Value = reqwest::blocking::Client::new().post(url)
+            .header("content-type", "multipart/form-data; boundary=teo-test-boundary")
+            .body(body)
+            .send().unwrap().json().unwrap();
+        assert_error(&res, "ValueError", "incorrect form format");
+    }
+});
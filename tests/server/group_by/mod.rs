@@ -0,0 +1,46 @@
+// each test needs its own records, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let sales: Vec<_> = [("a", 1), ("a", 2), ("b", 10), ("c", 4), ("c", 4), ("d", 7)].iter().map(|(region, amount)| json!({"region": region, "amount": amount})).collect();
+        server.request("Sale", "createMany", json!({"create": sales})).await.unwrap();
+        server
+    }
+
+    #[tokio::test]
+    async fn groups_are_ordered_and_paged() {
+        let server = server().await;
+        let res = server.request("Sale", "groupBy", json!({
+            "by": ["region"],
+            "_sum": {"amount": true},
+            "orderBy": {"region": "desc"},
+            "skip": 1,
+            "take": 2,
+        })).await.unwrap();
+        assert_eq!(res["data"], json!([
+            {"region": "c", "_sum": {"amount": 8.0}},
+            {"region": "b", "_sum": {"amount": 10.0}},
+        ]));
+    }
+
+    #[tokio::test]
+    async fn take_beyond_the_groups_returns_the_rest() {
+        let server = server().await;
+        let res = server.request("Sale", "groupBy", json!({
+            "by": ["region"],
+            "_count": {"_all": true},
+            "orderBy": {"region": "asc"},
+            "skip": 2,
+            "take": 10,
+        })).await.unwrap();
+        assert_eq!(res["data"], json!([
+            {"region": "c", "_count": {"_all": 2}},
+            {"region": "d", "_count": {"_all": 1}},
+        ]));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::server_tests;
+
+server_tests!(4025, {
+    use serde_json::{json, Value};
+    use crate::lib::req;
+    use crate::lib::fixture::assert_field_error;
+    use crate::{assert_json, matcher};
+
+    // each test aggregates its own region
+    fn create_sales(region: &str) {
+        for (amount, sold_at) in [(10, "2024-03-01T10:00:00Z"), (6, "2024-03-01T23:30:00Z"), (8, "2024-03-03T01:00:00Z")] {
+            req(PORT, "create", "Sale", json!({
+                "create": {"region": region, "amount": amount, "soldAt": sold_at},
+            }));
+        }
+    }
+
+    #[test]
+    fn buckets_by_day() {
+        create_sales("day");
+        let res = req(PORT, "groupByTime", "Sale", json!({
+            "field": "soldAt",
+            "interval": "day",
+            "where": {"region": "day"},
+            "_count": {"_all": true},
+            "_sum": {"amount": true},
+            "_max": {"amount": true},
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {
+                    "bucket": {"$datetime": "2024-03-01T00:00:00.000Z"},
+                    "_count": {"_all": 2},
+                    "_sum": {"amount": 16},
+                    "_max": {"amount": 10},
+                },
+                {
+                    "bucket": {"$datetime": "2024-03-03T00:00:00.000Z"},
+                    "_count": {"_all": 1},
+                    "_sum": {"amount": 8},
+                    "_max": {"amount": 8},
+                },
+            ]
+        }));
+    }
+
+    #[test]
+    fn buckets_start_in_the_timezone() {
+        create_sales("timezone");
+        let res = req(PORT, "groupByTime", "Sale", json!({
+            "field": "soldAt",
+            "interval": "day",
+            "timezone": "+02:00",
+            "where": {"region": "timezone"},
+            "_count": {"_all": true},
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {"bucket": {"$datetime": "2024-02-29T22:00:00.000Z"}, "_count": {"_all": 1}},
+                {"bucket": {"$datetime": "2024-03-01T22:00:00.000Z"}, "_count": {"_all": 1}},
+                {"bucket": {"$datetime": "2024-03-02T22:00:00.000Z"}, "_count": {"_all": 1}},
+            ]
+        }));
+    }
+
+    #[test]
+    fn buckets_follow_daylight_saving_time() {
+        for sold_at in ["2024-03-30T22:30:00Z", "2024-03-31T00:30:00Z", "2024-03-31T22:30:00Z"] {
+            req(PORT, "create", "Sale", json!({
+                "create": {"region": "dst", "amount": 1, "soldAt": sold_at},
+            }));
+        }
+        let res = req(PORT, "groupByTime", "Sale", json!({
+            "field": "soldAt",
+            "interval": "day",
+            "timezone": "Europe/Berlin",
+            "where": {"region": "dst"},
+            "_count": {"_all": true},
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {"bucket": {"$datetime": "2024-03-29T23:00:00.000Z"}, "_count": {"_all": 1}},
+                {"bucket": {"$datetime": "2024-03-30T23:00:00.000Z"}, "_count": {"_all": 1}},
+                {"bucket": {"$datetime": "2024-03-31T22:00:00.000Z"}, "_count": {"_all": 1}},
+            ]
+        }));
+    }
+
+    #[test]
+    fn dates_stay_on_their_day_in_any_timezone() {
+        for sold_on in ["2024-03-31", "2024-04-01", "2024-04-01"] {
+            req(PORT, "create", "Sale", json!({
+                "create": {"region": "dates", "amount": 1, "soldAt": "2024-04-01T12:00:00Z", "soldOn": sold_on},
+            }));
+        }
+        for (interval, timezone) in [("day", "-05:00"), ("month", "America/New_York")] {
+            let res = req(PORT, "groupByTime", "Sale", json!({
+                "field": "soldOn",
+                "interval": interval,
+                "timezone": timezone,
+                "where": {"region": "dates"},
+                "_count": {"_all": true},
+            }));
+            let first = if interval == "day" { "2024-03-31" } else { "2024-03-01" };
+            assert_json!(res, matcher!({
+                "data": [
+                    {"bucket": {"$date": first}, "_count": {"_all": 1}},
+                    {"bucket": {"$date": "2024-04-01"}, "_count": {"_all": 2}},
+                ]
+            }));
+        }
+    }
+
+    #[test]
+    fn buckets_by_month() {
+        create_sales("month");
+        let res = req(PORT, "groupByTime", "Sale", json!({
+            "field": "soldAt",
+            "interval": "month",
+            "where": {"region": "month"},
+            "_avg": {"amount": true},
+            "_min": {"amount": true},
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {"bucket": {"$datetime": "2024-03-01T00:00:00.000Z"}, "_avg": {"amount": 8.0}, "_min": {"amount": 6}},
+            ]
+        }));
+    }
+
+    #[test]
+    fn buckets_span_several_batches() {
+        let sales: Vec<Value> = (0..1200).map(|i| json!({"region": "batches", "amount": 1, "soldAt": if i % 2 == 0 { "2024-03-01T10:00:00Z" } else { "2024-03-02T10:00:00Z" }})).collect();
+        for chunk in sales.chunks(600) {
+            req(PORT, "createMany", "Sale", json!({"create": chunk}));
+        }
+        let res = req(PORT, "groupByTime", "Sale", json!({
+            "field": "soldAt",
+            "interval": "day",
+            "where": {"region": "batches"},
+            "_count": {"_all": true},
+            "_sum": {"amount": true},
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {"bucket": {"$datetime": "2024-03-01T00:00:00.000Z"}, "_count": {"_all": 600}, "_sum": {"amount": 600}},
+                {"bucket": {"$datetime": "2024-03-02T00:00:00.000Z"}, "_count": {"_all": 600}, "_sum": {"amount": 600}},
+            ]
+        }));
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        for (input, path, message) in [
+            (json!({"field": "soldAt", "interval": "year"}), "interval", "expect one of hour, day, week and month"),
+            (json!({"field": "amount", "interval": "day"}), "field", "expect a Date or DateTime field"),
+            (json!({"field": "soldAt", "interval": "day", "timezone": "Mars"}), "timezone", "expect `UTC', an offset like `+08:00' or a zone like `Europe/Berlin'"),
+            (json!({"field": "soldAt", "interval": "day", "orderBy": {"id": "asc"}}), "orderBy", "unexpected key"),
+        ] {
+            let res = req(PORT, "groupByTime", "Sale", input);
+            assert_field_error(&res, path, message);
+        }
+    }
+});
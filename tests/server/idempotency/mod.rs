@@ -0,0 +1,114 @@
+// the window and pipeline items which panic and sleep are set up on the app, so these tests run the
+// server in process
+mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use actix_web::test::{read_body, TestRequest};
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::pipeline::Ctx;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+    static EXPLODED: AtomicBool = AtomicBool::new(false);
+
+    async fn server(window: Duration) -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.idempotency(window);
+            // panics on the first `explode', later values pass
+            app.define_pipeline_item("explodeOnce", |_args: Arguments, ctx: Ctx| async move {
+                if ctx.value().as_teon().and_then(|v| v.as_str()) == Some("explode") && !EXPLODED.swap(true, Ordering::SeqCst) {
+                    panic!("explode");
+                }
+                Ok(ctx.value().clone())
+            })?;
+            // keeps the request of a `slow' value in progress for a while
+            app.define_pipeline_item("sleepOnSlow", |_args: Arguments, ctx: Ctx| async move {
+                if ctx.value().as_teon().and_then(|v| v.as_str()) == Some("slow") {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                }
+                Ok(ctx.value().clone())
+            })
+        }).await.unwrap()
+    }
+
+    async fn create(server: &TestServer, key: &str, text: &str) -> JsonValue {
+        server.request_at_path_with_headers("/Note/create", json!({"create": {"text": text}}), &[("Idempotency-Key", key)]).await.unwrap()
+    }
+
+    async fn count(server: &TestServer) -> JsonValue {
+        server.request("Note", "count", json!({})).await.unwrap()["data"].clone()
+    }
+
+    #[tokio::test]
+    async fn same_request_is_replayed() {
+        let server = server(Duration::from_secs(60)).await;
+        let first = create(&server, "a", "hello").await;
+        let second = create(&server, "a", "hello").await;
+        assert_eq!(first["data"]["id"], second["data"]["id"]);
+        assert_eq!(count(&server).await, json!(1));
+    }
+
+    #[tokio::test]
+    async fn different_request_conflicts() {
+        let server = server(Duration::from_secs(60)).await;
+        create(&server, "a", "hello").await;
+        let res = create(&server, "a", "bye").await;
+        assert_eq!(res["error"]["message"], json!("idempotency key is already used for a different request"));
+        assert_eq!(count(&server).await, json!(1));
+    }
+
+    #[tokio::test]
+    async fn expired_key_is_used_again() {
+        let server = server(Duration::from_millis(500)).await;
+        let first = create(&server, "a", "hello").await;
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let second = create(&server, "a", "hello").await;
+        assert_ne!(first["data"]["id"], second["data"]["id"]);
+        assert_eq!(count(&server).await, json!(2));
+    }
+
+    #[tokio::test]
+    async fn panicked_request_releases_its_key() {
+        let server = server(Duration::from_secs(60)).await;
+        // the server answers a panic with an internal server error
+        let res = create(&server, "a", "explode").await;
+        assert_eq!(res["error"]["type"], json!("InternalServerError"), "{}", res);
+        let res = create(&server, "a", "explode").await;
+        assert_eq!(res["data"]["text"], json!("explode"));
+    }
+
+    #[tokio::test]
+    async fn replay_has_the_headers_of_the_response() {
+        let server = server(Duration::from_secs(60)).await;
+        let id = create(&server, "a", "hello").await["data"]["id"].clone();
+        let request = |accept: &'static str| TestRequest::post().uri("/Note/update")
+            .insert_header(("Idempotency-Key", "b"))
+            .insert_header(("Accept", accept))
+            .set_json(json!({"where": {"id": id}, "update": {"text": "bye"}}))
+            .to_request();
+        let first = server.call(request("application/json")).await;
+        let etag = first.headers().get("ETag").cloned();
+        assert!(etag.is_some());
+        // the format is negotiated with the replaying request
+        let second = server.call(request("application/msgpack")).await;
+        assert_eq!(second.headers().get("ETag").cloned(), etag);
+        assert_eq!(second.headers().get("Idempotent-Replayed").unwrap(), "true");
+        assert_eq!(second.headers().get("Content-Type").unwrap(), "application/msgpack");
+        let third = server.call(request("application/json")).await;
+        let body: JsonValue = serde_json::from_slice(&read_body(third).await).unwrap();
+        assert_eq!(body["data"]["text"], json!("bye"));
+    }
+
+    #[tokio::test]
+    async fn request_in_progress_holds_its_key_past_the_window() {
+        let server = server(Duration::from_millis(50)).await;
+        let (first, second) = tokio::join!(create(&server, "a", "slow"), async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            create(&server, "a", "slow").await
+        });
+        assert_eq!(first["data"]["text"], json!("slow"), "{}", first);
+        assert_eq!(second["error"]["message"], json!("a request with this idempotency key is in progress"), "{}", second);
+        assert_eq!(count(&server).await, json!(1));
+    }
+}
@@ -0,0 +1,69 @@
+// the identity item and the middleware which sets the identity are defined on the app, so these
+// tests run the server in process
+mod test {
+    use key_path::path;
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::action::action::{ENTRY, FIND, SINGLE};
+    use teo_runtime::middleware::middleware::Middleware;
+    use teo_runtime::middleware::next::Next;
+    use teo_runtime::request;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // the agent named by the `agent' header makes the request
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.with_main_namespace_mut(|namespace| namespace.define_middleware("actAs", |_args: Arguments| async {
+                let middleware: &'static dyn Middleware = Box::leak(Box::new(|ctx: request::Ctx, next: &'static dyn Next| async move {
+                    if let Some(name) = ctx.request().headers().get("agent").map(ToOwned::to_owned) {
+                        let model = ctx.namespace().model_at_path(&vec!["Agent"]).unwrap();
+                        let finder = teon!({"where": {"name": name}});
+                        if let Some(agent) = ctx.transaction_ctx().find_first_internal(model, &finder, true, FIND | SINGLE | ENTRY, None, path![]).await? {
+                            ctx.data_mut().insert("identity", agent);
+                        }
+                    }
+                    next.call(ctx).await
+                }));
+                Ok(middleware)
+            }))
+        }).await.unwrap()
+    }
+
+    async fn request(server: &TestServer, agent: Option<&str>, model: &str, action: &str, body: JsonValue) -> JsonValue {
+        let headers: Vec<(&str, &str)> = agent.map(|a| ("agent", a)).into_iter().collect();
+        server.request_at_path_with_headers(&format!("/{model}/{action}"), body, &headers).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn nested_writes_see_the_identity_of_the_request() {
+        let server = server().await;
+        request(&server, None, "Agent", "create", json!({"create": {"name": "ann"}})).await;
+        let res = request(&server, Some("ann"), "Ticket", "create", json!({
+            "create": {"title": "Broken", "remarks": {"create": [{"body": "first"}]}},
+            "include": {"remarks": true},
+        })).await;
+        assert_eq!(res["data"]["remarks"][0]["writtenBy"], json!("ann"), "{}", res);
+        let id = res["data"]["id"].clone();
+        let remark = request(&server, Some("ann"), "Remark", "create", json!({"create": {"body": "loose"}})).await;
+        assert_eq!(remark["data"]["writtenBy"], json!("ann"));
+        let res = request(&server, Some("ann"), "Ticket", "update", json!({
+            "where": {"id": id},
+            "update": {"remarks": {"connect": [{"id": remark["data"]["id"]}], "create": [{"body": "second"}]}},
+            "include": {"remarks": {"orderBy": {"id": "asc"}}},
+        })).await;
+        let written: Vec<&JsonValue> = res["data"]["remarks"].as_array().unwrap().iter().map(|r| &r["writtenBy"]).collect();
+        assert_eq!(written, vec![&json!("ann"), &json!("ann"), &json!("ann")]);
+    }
+
+    #[tokio::test]
+    async fn nested_writes_are_checked_like_direct_ones() {
+        let server = server().await;
+        let res = request(&server, None, "Remark", "create", json!({"create": {"body": "anonymous"}})).await;
+        assert!(res["error"].is_object(), "{}", res);
+        let res = request(&server, None, "Ticket", "create", json!({"create": {"title": "Anonymous", "remarks": {"create": [{"body": "anonymous"}]}}})).await;
+        assert!(res["error"].is_object(), "{}", res);
+    }
+}
@@ -0,0 +1,30 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    #[tokio::test]
+    async fn records_are_joined_through_a_generated_model() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let namespace = server.app().main_namespace();
+        let join = namespace.model_at_path(&vec!["_PostToTag"]).unwrap();
+        assert_eq!(join.table_name(), "_PostToTag");
+        assert_eq!(namespace.model_at_path(&vec!["Post"]).unwrap().relation("tags").unwrap().through_path(), Some(vec!["_PostToTag"]));
+        server.request("Post", "create", json!({"create": {"title": "first", "tags": {"create": [{"name": "rust"}, {"name": "web"}]}}})).await.unwrap();
+        server.request("Tag", "update", json!({"where": {"id": 2}, "update": {"posts": {"create": {"title": "second"}}}})).await.unwrap();
+        let res = server.request("Post", "findMany", json!({"include": {"tags": {"orderBy": {"id": "asc"}}}, "orderBy": {"id": "asc"}})).await.unwrap();
+        let tags: Vec<Vec<String>> = res["data"].as_array().unwrap().iter().map(|p| p["tags"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap().to_owned()).collect()).collect();
+        assert_eq!(tags, vec![vec!["rust".to_owned(), "web".to_owned()], vec!["web".to_owned()]]);
+        server.request("Post", "update", json!({"where": {"id": 1}, "update": {"tags": {"disconnect": {"id": 1}}}})).await.unwrap();
+        let res = server.request("Tag", "findMany", json!({"where": {"posts": {"none": {}}}})).await.unwrap();
+        assert_eq!(res["data"][0]["name"], json!("rust"));
+    }
+
+    #[tokio::test]
+    async fn one_sided_implicit_relations_are_rejected() {
+        let schema = SCHEMA.replace("  @relation\n  posts: Post[]\n", "");
+        let error = TestServer::new(schema).await.err().unwrap();
+        assert!(error.message().contains("`Post.tags' has no single implicit relation back from `Tag'"));
+    }
+}
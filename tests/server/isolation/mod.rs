@@ -0,0 +1,82 @@
+// the handler is defined on the app, so these tests run the server in process
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use serde_json::{json, Value as JsonValue};
+    use teo::isolation::{IsolationLevel, TransactionOptions};
+    use teo::prelude::{path, request, Response};
+    use teo::savepoint::transaction_with;
+    use teo::test::TestServer;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    // writes an entry and fails to serialize until the chosen attempt
+    async fn flaky(ctx: request::Ctx) -> path::Result<Response> {
+        let retries = ctx.body().get("retries").and_then(|r| r.to_int()).unwrap_or_default() as usize;
+        let succeed_at = ctx.body().get("succeedAt").and_then(|r| r.to_int()).unwrap_or_default() as usize;
+        let message = ctx.body().get("message").and_then(|m| m.as_str()).unwrap_or("could not serialize access due to concurrent update").to_owned();
+        let options = TransactionOptions { isolation: Some(IsolationLevel::Serializable), retries };
+        transaction_with(&ctx.transaction_ctx(), options, |ctx| {
+            let message = message.clone();
+            async move {
+                let attempt = ATTEMPTS.fetch_add(1, Ordering::SeqCst) + 1;
+                let model = ctx.namespace().model_at_path(&vec!["Entry"]).unwrap();
+                let object = ctx.create_object(model, teon!({"code": format!("attempt {}", attempt)}), None).await?;
+                object.save().await?;
+                if attempt != succeed_at {
+                    Err(path::Error::internal_server_error(path![], message))?;
+                }
+                Ok(())
+            }
+        }).await?;
+        Ok(Response::data(teon!(true)))
+    }
+
+    async fn server() -> TestServer {
+        ATTEMPTS.store(0, Ordering::SeqCst);
+        TestServer::new_with(SCHEMA, |app| {
+            app.action_transaction("create", TransactionOptions { isolation: Some(IsolationLevel::Serializable), retries: 1 });
+            app.with_main_namespace_mut(|namespace| namespace.define_handler("flaky", flaky))
+        }).await.unwrap()
+    }
+
+    async fn codes(server: &TestServer) -> JsonValue {
+        let res = server.request("Entry", "findMany", json!({"orderBy": {"id": "asc"}})).await.unwrap();
+        JsonValue::Array(res["data"].as_array().unwrap().iter().map(|e| e["code"].clone()).collect())
+    }
+
+    #[tokio::test]
+    async fn serialization_failures_are_retried() {
+        let server = server().await;
+        let res = server.request_at_path("/flaky", json!({"retries": 2, "succeedAt": 3})).await.unwrap();
+        assert_eq!(res["data"], json!(true), "{}", res);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+        assert_eq!(codes(&server).await, json!(["attempt 3"]));
+    }
+
+    #[tokio::test]
+    async fn retries_are_limited() {
+        let server = server().await;
+        let res = server.request_at_path("/flaky", json!({"retries": 1, "succeedAt": 3})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+        assert_eq!(codes(&server).await, json!([]));
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        let server = server().await;
+        let res = server.request_at_path("/flaky", json!({"retries": 3, "succeedAt": 2, "message": "gave up"})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn builtin_actions_run_with_their_defaults() {
+        let server = server().await;
+        let res = server.request("Entry", "create", json!({"create": {"code": "serializable"}})).await.unwrap();
+        assert_eq!(res["data"]["code"], json!("serializable"), "{}", res);
+    }
+}
@@ -0,0 +1,75 @@
+// the values are returned by a handler defined in Rust, so these tests run the server in process
+mod test {
+    use std::str::FromStr;
+    use actix_web::test::{read_body, TestRequest};
+    use bigdecimal::BigDecimal;
+    use bson::oid::ObjectId;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use indexmap::indexmap;
+    use serde_json::json;
+    use teo::test::TestServer;
+    use teo_runtime::request;
+    use teo_runtime::response::Response;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn kinds() -> Vec<(&'static str, Value)> {
+        vec![
+            ("null", Value::Null),
+            ("objectId", Value::ObjectId(ObjectId::from_str("65a1b2c3d4e5f60718293a4b").unwrap())),
+            ("bool", Value::Bool(true)),
+            ("int", Value::Int(-7)),
+            ("int64", Value::Int64(9_007_199_254_740_993)),
+            ("float32", Value::Float32(0.1)),
+            ("float", Value::Float(2.5e-8)),
+            ("decimal", Value::Decimal(BigDecimal::from_str("12.3400").unwrap())),
+            ("string", Value::String("quote \" backslash \\ newline \n emoji 🦀".to_owned())),
+            ("date", Value::Date(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())),
+            ("dateTime", Value::DateTime(Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 58).unwrap())),
+            ("array", Value::Array(vec![Value::Int(1), Value::Null, Value::String("a".to_owned())])),
+            ("dictionary", Value::Dictionary(indexmap! {
+                "b".to_owned() => Value::Int(1),
+                "a".to_owned() => Value::Array(vec![Value::Dictionary(indexmap! { "c".to_owned() => Value::Bool(false) })]),
+            })),
+        ]
+    }
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.with_main_namespace_mut(|namespace| {
+                namespace.define_handler("kind", |ctx: request::Ctx| async move {
+                    let kind = ctx.body().get("kind").and_then(|k| k.as_str()).unwrap_or_default().to_owned();
+                    match kinds().into_iter().find(|(name, _)| *name == kind) {
+                        Some((_, value)) => Ok(Response::teon(value)),
+                        // a tuple has no JSON form
+                        None => Ok(Response::teon(Value::Tuple(vec![Value::Int(1)]))),
+                    }
+                });
+            })
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn each_value_kind_is_written_like_the_json_conversion() {
+        let server = server().await;
+        for (kind, value) in kinds() {
+            let response = server.call(TestRequest::post().uri("/kind").set_json(json!({"kind": kind})).to_request()).await;
+            assert_eq!(response.status(), 200, "{kind}");
+            let body = read_body(response).await;
+            let converted = serde_json::to_vec(&serde_json::Value::try_from(&value).unwrap()).unwrap();
+            assert_eq!(String::from_utf8(body.to_vec()).unwrap(), String::from_utf8(converted).unwrap(), "{kind}");
+        }
+    }
+
+    #[tokio::test]
+    async fn a_value_without_json_form_is_an_internal_server_error() {
+        let server = server().await;
+        let response = server.call(TestRequest::post().uri("/kind").set_json(json!({"kind": "tuple"})).to_request()).await;
+        assert_eq!(response.status(), 500);
+        assert!(response.headers().contains_key("x-request-id"));
+        let body: serde_json::Value = serde_json::from_slice(&read_body(response).await).unwrap();
+        assert_eq!(body["error"]["message"], json!("Cannot convert Tuple into json"));
+        assert_eq!(body["error"]["code"], json!("T5000"));
+    }
+}
@@ -0,0 +1,50 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.admin(|_| async { true });
+            Ok(())
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_input_has_the_rules_of_the_fields() {
+        let server = server().await;
+        let res = server.request_at_path("/_jsonSchema", json!({})).await.unwrap();
+        let create = &res["data"]["Applicant"]["create"];
+        assert_eq!(create["title"], json!("ApplicantCreateInput"));
+        assert_eq!(create["description"], json!("A person signing up."));
+        assert_eq!(create["required"], json!(["email", "handle", "age"]));
+        assert_eq!(create["properties"]["email"], json!({"type": "string", "title": "E-mail", "format": "email"}));
+        assert_eq!(create["properties"]["handle"], json!({"type": "string", "title": "handle", "minLength": 2, "maxLength": 20, "pattern": "^[a-z]+$"}));
+        assert_eq!(create["properties"]["age"], json!({"type": "integer", "title": "age", "minimum": 18, "exclusiveMaximum": 130}));
+        assert_eq!(create["properties"]["plan"], json!({"type": "string", "title": "plan", "enum": ["FREE", "PRO"], "default": "FREE"}));
+        assert_eq!(create["properties"]["color"], json!({"type": "string", "title": "color", "enum": ["red", "blue"]}));
+        assert_eq!(create["properties"]["website"], json!({"type": "string", "title": "website", "format": "uri"}));
+        assert_eq!(create["properties"]["referrer"], json!({"type": ["string", "null"], "title": "referrer"}));
+        assert!(create["properties"].get("id").is_none());
+        assert!(create["properties"].get("createdAt").is_none());
+    }
+
+    #[tokio::test]
+    async fn rules_after_a_transformation_are_left_out() {
+        let server = server().await;
+        let res = server.request_at_path("/_jsonSchema", json!({})).await.unwrap();
+        assert_eq!(res["data"]["Applicant"]["create"]["properties"]["displayName"], json!({"type": "string", "title": "displayName"}));
+    }
+
+    #[tokio::test]
+    async fn update_input_has_no_required_fields() {
+        let server = server().await;
+        let res = server.request_at_path("/_jsonSchema", json!({})).await.unwrap();
+        let update = &res["data"]["Applicant"]["update"];
+        assert_eq!(update["title"], json!("ApplicantUpdateInput"));
+        assert_eq!(update["required"], json!([]));
+        assert!(update["properties"].get("referrer").is_none());
+        assert_eq!(res["data"]["Entry"], json!({}));
+    }
+}
@@ -0,0 +1,49 @@
+// the handler is defined on the app, so these tests run the server in process
+mod test {
+    use serde_json::json;
+    use teo::prelude::{path, request, LazyRelation, Response};
+    use teo::test::TestServer;
+    use teo_runtime::model::Object;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn post_count(user: &Object) -> path::Result<i64> {
+        Ok(user.relation::<Vec<Object>>("posts").await?.len() as i64)
+    }
+
+    // the posts of the first user, loaded again after each write of the request
+    async fn post_counts(ctx: request::Ctx) -> path::Result<Response> {
+        let transaction_ctx = ctx.transaction_ctx();
+        let user_model = ctx.namespace().model_at_path(&vec!["User"]).unwrap();
+        let post_model = ctx.namespace().model_at_path(&vec!["Post"]).unwrap();
+        let user: Object = transaction_ctx.find_unique(user_model, &teon!({"where": {"id": 1}}), Some(ctx.clone()), path![]).await?.unwrap();
+        let mut counts = vec![post_count(&user).await?];
+        let post = transaction_ctx.create_object(post_model, teon!({"title": "b", "authorId": 1}), Some(ctx.clone())).await?;
+        post.save().await?;
+        counts.push(post_count(&user).await?);
+        post.set_teon(&teon!({"authorId": 2})).await?;
+        post.save().await?;
+        counts.push(post_count(&user).await?);
+        let first: Object = transaction_ctx.find_unique(post_model, &teon!({"where": {"id": 1}}), Some(ctx.clone()), path![]).await?.unwrap();
+        first.delete().await?;
+        counts.push(post_count(&user).await?);
+        Ok(Response::data(teon!(counts)))
+    }
+
+    async fn server() -> TestServer {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.with_main_namespace_mut(|namespace| namespace.define_handler("postCounts", post_counts))
+        }).await.unwrap();
+        server.request("User", "create", json!({"create": {"name": "Ada", "posts": {"create": {"title": "a"}}}})).await.unwrap();
+        server.request("User", "create", json!({"create": {"name": "Grace"}})).await.unwrap();
+        server
+    }
+
+    #[tokio::test]
+    async fn writes_reload_the_relations_of_the_request() {
+        let server = server().await;
+        let res = server.request_at_path("/postCounts", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!([1, 2, 1, 0]), "{}", res);
+    }
+}
@@ -0,0 +1,90 @@
+// the admin guard, the handlers and a permission item are set up on the app, so these tests run
+// the server in process
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+    use teo_result::Error;
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::pipeline::Ctx;
+    use teo_runtime::request;
+    use teo_runtime::response::Response;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.admin(|_| async { true });
+            // passes the requests with a `staff' header
+            app.define_pipeline_item("staffOnly", |_args: Arguments, ctx: Ctx| async move {
+                if ctx.request_ctx().is_none_or(|r| r.request().headers().get("staff").is_none()) {
+                    Err(Error::new("staff only"))?
+                }
+                Ok(ctx.value().clone())
+            })?;
+            app.with_main_namespace_mut(|namespace| {
+                namespace.define_handler("ping", |_ctx: request::Ctx| async move { Ok(Response::data(teon!("pong"))) });
+                namespace.define_handler_group("mail", |group| {
+                    group.define_handler("send", |_ctx: request::Ctx| async move { Ok(Response::data(teon!(true))) });
+                    group.define_handler("count", |_ctx: request::Ctx| async move { Ok(Response::data(teon!(0))) });
+                });
+                namespace.define_model_handler_group("Memo", |group| {
+                    group.define_handler("archive", |_ctx: request::Ctx| async move { Ok(Response::data(teon!(true))) });
+                });
+            })
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn handlers_and_data_sets_keep_their_doc_comments() {
+        let server = server().await;
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        assert_eq!(res["data"]["handlerGroups"], json!({
+            "mail": {
+                "name": "mail",
+                "localizedName": "Mail",
+                "description": "Sends messages.",
+                "handlers": [
+                    {"name": "send", "localizedName": "send", "description": "Sends one message."},
+                    {"name": "count", "localizedName": "count", "description": null},
+                ],
+            },
+        }));
+        assert_eq!(res["data"]["handlers"], json!({
+            "ping": {"name": "ping", "localizedName": "ping", "description": "Answers while the server is up."},
+        }));
+        assert_eq!(res["data"]["models"]["Memo"]["handlers"], json!([
+            {"name": "archive", "localizedName": "archive", "description": "Hides the memo from the list."},
+        ]));
+        assert_eq!(res["data"]["dataSets"], json!({
+            "starters": {"name": "starters", "localizedName": "starters", "description": "Memos every deployment starts with."},
+        }));
+    }
+
+    #[tokio::test]
+    async fn interfaces_keep_their_doc_comments() {
+        let server = server().await;
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        assert_eq!(res["data"]["interfaces"]["MessageInput"], json!({
+            "name": "MessageInput",
+            "localizedName": "MessageInput",
+            "description": "The message to send.",
+            "fields": [
+                {"name": "subject", "localizedName": "subject", "description": null, "type": "String", "optional": true},
+                {"name": "to", "localizedName": "to", "description": "Who the message goes to.", "type": "String", "optional": false},
+            ],
+        }));
+    }
+
+    #[tokio::test]
+    async fn models_and_fields_are_filtered_by_read_permission() {
+        let server = server().await;
+        let field_names = |res: &serde_json::Value| res["data"]["models"]["Memo"]["fields"].as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap().to_owned()).collect::<Vec<String>>();
+        let res = server.request_at_path("/_meta", json!({})).await.unwrap();
+        assert!(res["data"]["models"].get("Ledger").is_none());
+        assert_eq!(field_names(&res), vec!["id", "title"]);
+        let res = server.request_at_path_with_headers("/_meta", json!({}), &[("staff", "1")]).await.unwrap();
+        assert_eq!(res["data"]["models"]["Ledger"]["name"], json!("Ledger"));
+        assert_eq!(field_names(&res), vec!["id", "title", "note"]);
+    }
+}
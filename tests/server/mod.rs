@@ -1 +1,65 @@
 pub mod actions;
+pub mod errors;
+pub mod forms;
+pub mod coercion;
+pub mod shaping;
+pub mod filters;
+pub mod group_by_time;
+pub mod batches;
+pub mod state;
+pub mod batch_actions;
+pub mod group_by;
+pub mod idempotency;
+pub mod share;
+pub mod backfill;
+pub mod record;
+pub mod etag;
+pub mod position;
+pub mod tree;
+pub mod duplicate;
+pub mod fetch;
+pub mod strings;
+pub mod sources;
+pub mod explain;
+pub mod conditionals;
+pub mod flavors;
+pub mod clickhouse;
+pub mod nested;
+pub mod on_delete;
+pub mod counter_cache;
+pub mod unique;
+pub mod identity;
+pub mod relation_filters;
+pub mod implicit;
+pub mod through;
+pub mod data_sets;
+pub mod packages;
+pub mod constants;
+pub mod enum_meta;
+pub mod meta;
+pub mod json_schema;
+pub mod archive;
+pub mod event_sink;
+pub mod telemetry;
+pub mod tls;
+pub mod savepoint;
+pub mod isolation;
+pub mod renamed;
+pub mod finders;
+pub mod builders;
+pub mod statements;
+pub mod static_files;
+pub mod embedded;
+pub mod lazy_relation;
+pub mod dry_run;
+pub mod admin;
+pub mod test_cases;
+pub mod json_body;
+pub mod pipeline_items;
+pub mod decorators;
+pub mod plugins;
+pub mod fake;
+pub mod snapshots;
+pub mod conformance;
+pub mod connector_providers;
+pub mod action_lists;
@@ -0,0 +1,48 @@
+// the nested operations below would collide on unique values or foreign keys if they ran in the
+// order they're written
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    #[tokio::test]
+    async fn deletes_run_before_creates() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Forum", "create", json!({"create": {"name": "Ada", "threads": {"create": [{"slug": "intro", "title": "Old"}]}}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        assert!(!id.is_null(), "{}", res);
+        let res = server.request("Forum", "update", json!({"where": {"id": id}, "update": {"threads": {"create": [{"slug": "intro", "title": "New"}], "delete": [{"slug": "intro"}]}}, "include": {"threads": true}})).await.unwrap();
+        assert_eq!(res["data"]["threads"].as_array().unwrap().len(), 1, "{}", res);
+        assert_eq!(res["data"]["threads"][0]["title"], json!("New"));
+    }
+
+    #[tokio::test]
+    async fn deeper_levels_are_ordered() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Forum", "create", json!({"create": {"name": "Ada", "threads": {"create": [{"slug": "intro", "title": "Intro", "replies": {"create": [{"key": "first"}]}}]}}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        assert!(!id.is_null(), "{}", res);
+        let res = server.request("Forum", "update", json!({"where": {"id": id}, "update": {"threads": {"update": [{"where": {"slug": "intro"}, "update": {"replies": {"create": [{"key": "first"}], "delete": [{"key": "first"}]}}}]}}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        let res = server.request("Reply", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn nested_updates_stay_within_their_parent() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("Forum", "create", json!({"create": {"name": "Ada", "threads": {"create": [{"slug": "own", "title": "Own"}]}}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        server.request("Forum", "create", json!({"create": {"name": "Bob", "threads": {"create": [{"slug": "other", "title": "Other"}]}}})).await.unwrap();
+        let res = server.request("Forum", "update", json!({"where": {"id": id}, "update": {"threads": {"update": {"where": {"slug": "other"}, "update": {"title": "X"}}}}})).await.unwrap();
+        assert!(!res["error"].is_null(), "{}", res);
+        let res = server.request("Forum", "update", json!({"where": {"id": id}, "update": {"threads": {"updateMany": {"where": {"title": {"endsWith": "r"}}, "update": {"title": "Y"}}}}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        let res = server.request("Forum", "update", json!({"where": {"id": id}, "update": {"threads": {"upsert": {"where": {"slug": "other"}, "create": {"slug": "upserted", "title": "Z"}, "update": {"title": "Z"}}}}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        let res = server.request("Thread", "findMany", json!({"where": {"slug": {"in": ["own", "other", "upserted"]}}, "orderBy": {"id": "asc"}})).await.unwrap();
+        let threads: Vec<(serde_json::Value, serde_json::Value, bool)> = res["data"].as_array().unwrap().iter().map(|t| (t["slug"].clone(), t["title"].clone(), t["forumId"] == id)).collect();
+        assert_eq!(threads, vec![(json!("own"), json!("Own"), true), (json!("other"), json!("Other"), false), (json!("upserted"), json!("Z"), true)]);
+    }
+}
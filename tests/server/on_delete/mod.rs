@@ -0,0 +1,50 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn title(server: &TestServer) -> serde_json::Value {
+        let res = server.request("Publisher", "create", json!({"create": {"name": "Ada", "titles": {"create": [{
+            "name": "Notes",
+            "chapters": {"create": [{}, {}]},
+            "critiques": {"create": [{}]},
+            "jacket": {"create": {}},
+        }]}}, "include": {"titles": true}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        res["data"]["titles"][0].clone()
+    }
+
+    #[tokio::test]
+    async fn dependents_are_deleted_or_detached() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let title = title(&server).await;
+        let res = server.request("Title", "delete", json!({"where": {"id": title["id"]}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        let res = server.request("Chapter", "count", json!({"where": {"titleId": title["id"]}})).await.unwrap();
+        assert_eq!(res["data"], json!(0));
+        let res = server.request("Critique", "findMany", json!({"orderBy": {"id": "desc"}, "take": 1})).await.unwrap();
+        assert_eq!(res["data"][0]["titleId"], json!(null), "{}", res);
+    }
+
+    #[tokio::test]
+    async fn restricted_deletes_are_denied() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let title = title(&server).await;
+        let res = server.request("Publisher", "delete", json!({"where": {"id": title["publisherId"]}})).await.unwrap();
+        assert!(!res["error"].is_null(), "{}", res);
+        let res = server.request("Title", "count", json!({"where": {"id": title["id"]}})).await.unwrap();
+        assert_eq!(res["data"], json!(1));
+    }
+
+    // the title has no foreign key for its jacket, so there is nothing to set to null on it
+    #[tokio::test]
+    async fn referenced_side_is_left_alone() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let title = title(&server).await;
+        let res = server.request("Jacket", "deleteMany", json!({"where": {"titleId": title["id"]}})).await.unwrap();
+        assert!(res["error"].is_null(), "{}", res);
+        let res = server.request("Title", "findUnique", json!({"where": {"id": title["id"]}})).await.unwrap();
+        assert_eq!(res["data"]["name"], json!("Notes"), "{}", res);
+    }
+}
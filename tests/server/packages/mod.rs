@@ -0,0 +1,51 @@
+// packages are fetched with the git command line, from a repository these tests create on disk
+mod test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use once_cell::sync::Lazy;
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // a repository with `v1' tagged before `Account' requires a name, and a cache of its own
+    static REPOSITORY: Lazy<PathBuf> = Lazy::new(|| {
+        let directory = std::env::temp_dir().join(format!("teo-package-{}", std::process::id()));
+        let repository = directory.join("teo-auth");
+        fs::create_dir_all(&repository).unwrap();
+        std::env::set_var("TEO_PACKAGE_CACHE", directory.join("cache"));
+        git(&repository, &["init", "-q"]);
+        fs::write(repository.join("index.teo"), "import \"./profile\"\n\nmodel Account {\n  @id @autoIncrement @readonly\n  id: Int\n  email: String\n}\n").unwrap();
+        fs::write(repository.join("profile.teo"), "model Profile {\n  @id @autoIncrement @readonly\n  id: Int\n  bio: String?\n}\n").unwrap();
+        git(&repository, &["add", "."]);
+        git(&repository, &["commit", "-q", "-m", "v1"]);
+        git(&repository, &["tag", "v1"]);
+        fs::write(repository.join("index.teo"), "model Account {\n  @id @autoIncrement @readonly\n  id: Int\n  email: String\n  name: String\n}\n").unwrap();
+        git(&repository, &["commit", "-q", "-am", "v2"]);
+        repository
+    });
+
+    fn git(directory: &Path, args: &[&str]) {
+        let status = Command::new("git").arg("-C").arg(directory).args(["-c", "user.name=teo", "-c", "user.email=teo@example.com"]).args(args).status().unwrap();
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn models_are_imported_from_the_pinned_version() {
+        let schema = SCHEMA.replace("REPOSITORY", REPOSITORY.to_str().unwrap());
+        let server = TestServer::new(schema).await.unwrap();
+        let res = server.request("Account", "create", json!({"create": {"email": "ann@example.com"}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("ann@example.com"));
+        let res = server.request("Profile", "create", json!({"create": {"bio": "hi"}})).await.unwrap();
+        assert_eq!(res["data"]["bio"], json!("hi"));
+        assert!(REPOSITORY.parent().unwrap().join("cache").read_dir().unwrap().next().is_some());
+    }
+
+    #[tokio::test]
+    async fn packages_need_a_version() {
+        let schema = SCHEMA.replace("REPOSITORY@v1", REPOSITORY.to_str().unwrap());
+        let error = TestServer::new(schema).await.err().unwrap();
+        assert!(error.message().contains("has no version, pin it with `@' and a tag, branch or commit"));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::server_tests;
+
+server_tests!(4076, example "pipeline_items", {
+    use std::fs;
+    use std::path::Path;
+    use serde_json::json;
+    use crate::lib::{req, run_example};
+    use crate::lib::fixture::assert_field_error;
+
+    #[test]
+    fn transforms_run_in_the_pipeline() {
+        let res = req(PORT, "create", "Note", json!({"create": {"title": "hello", "body": "text"}}));
+        assert_eq!(res["data"]["title"], json!("HELLO"), "unexpected response {}", res);
+    }
+
+    #[test]
+    fn validators_reject_values() {
+        let res = req(PORT, "create", "Note", json!({"create": {"title": "hello", "body": "spam"}}));
+        assert_field_error(&res, "create.body", "value is invalid");
+    }
+
+    #[test]
+    fn items_defined_in_rust_are_declared() {
+        let schema = fs::read_to_string(Path::new(file!()).parent().unwrap().join("schema.teo")).unwrap();
+        let undeclared = std::env::temp_dir().join("teo-pipeline-items-undeclared.teo");
+        fs::write(&undeclared, schema.replace("declare pipeline item notSpam: String -> String\n", "").replace("  @onSet($presents.notSpam)\n", "")).unwrap();
+        let output = run_example("pipeline_items", &undeclared, &["serve"]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("pipeline item `notSpam' is defined in Rust but not declared, add `declare pipeline item notSpam' to the schema"), "unexpected output {}", stderr);
+    }
+});
@@ -0,0 +1,64 @@
+// plugins are registered on the app, so these tests run the server in process
+mod test {
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use teo::prelude::Plugin;
+    use teo::test::TestServer;
+    use teo_result::Result;
+    use teo_runtime::namespace::Namespace;
+    use teo_runtime::path;
+    use teo_runtime::request::{self, Request};
+    use teo_runtime::response::Response;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // records the hooks it's called with, defines the `ping' handler and refuses blocked requests
+    struct Recorder {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Plugin for Recorder {
+
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn on_schema_load(&self, namespace: &mut Namespace) -> Result<()> {
+            self.calls.lock().unwrap().push("schemaLoad".to_owned());
+            namespace.define_handler("ping", |_ctx: request::Ctx| async move { Ok(Response::data(teon!("pong"))) });
+            Ok(())
+        }
+
+        async fn on_namespace_loaded(&self, namespace: &'static Namespace) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("namespaceLoaded {}", namespace.handlers.contains_key("ping")));
+            Ok(())
+        }
+
+        async fn on_request(&self, request: &Request) -> path::Result<()> {
+            self.calls.lock().unwrap().push(format!("request {}", request.path()));
+            if request.headers().get("blocked").is_some() {
+                Err(path::Error::unauthorized_error_message_only("blocked by plugin"))?
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_run_through_the_lifecycle() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let plugin = Recorder { calls: calls.clone() };
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.plugin(plugin);
+            Ok(())
+        }).await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["schemaLoad", "namespaceLoaded true"]);
+        let res = server.request_at_path("/ping", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!("pong"));
+        let res = server.request_at_path_with_headers("/ping", json!({}), &[("blocked", "1")]).await.unwrap();
+        assert_eq!(res["error"]["message"], json!("blocked by plugin"), "unexpected response {}", res);
+        assert_eq!(calls.lock().unwrap()[2..], ["request /ping", "request /ping"]);
+    }
+}
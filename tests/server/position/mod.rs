@@ -0,0 +1,68 @@
+use crate::server_tests;
+
+server_tests!(4040, {
+    use serde_json::{json, Value};
+    use crate::lib::fixture::assert_field_error;
+    use crate::lib::req;
+
+    fn create_card(list_id: i64, title: &str, position: Option<i64>) -> Value {
+        let mut create = json!({"listId": list_id, "title": title});
+        if let Some(position) = position {
+            create["position"] = json!(position);
+        }
+        req(PORT, "create", "Card", json!({"create": create}))["data"].clone()
+    }
+
+    fn titles(list_id: i64) -> Vec<String> {
+        let res = req(PORT, "findMany", "Card", json!({"where": {"listId": list_id}, "orderBy": {"position": "asc"}}));
+        res["data"].as_array().unwrap().iter().map(|c| c["title"].as_str().unwrap().to_owned()).collect()
+    }
+
+    #[test]
+    fn creates_are_appended_to_their_scope() {
+        let a = create_card(1, "a", None);
+        let b = create_card(1, "b", None);
+        let other = create_card(2, "other", None);
+        assert_eq!(a["position"], 1024);
+        assert_eq!(b["position"], 2048);
+        assert_eq!(other["position"], 1024);
+    }
+
+    #[test]
+    fn moves_place_records_between_siblings() {
+        let a = create_card(3, "a", None);
+        create_card(3, "b", None);
+        let c = create_card(3, "c", None);
+        let res = req(PORT, "moveBefore", "Card", json!({"where": {"id": c["id"]}, "target": {"id": a["id"]}}));
+        assert_eq!(res["data"]["position"], 0, "unexpected response {}", res);
+        assert_eq!(titles(3), vec!["c", "a", "b"]);
+        let res = req(PORT, "moveAfter", "Card", json!({"where": {"id": c["id"]}, "target": {"id": a["id"]}}));
+        assert_eq!(res["data"]["position"], 1536, "unexpected response {}", res);
+        assert_eq!(titles(3), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn moves_renumber_siblings_without_a_gap() {
+        create_card(4, "a", Some(1));
+        let b = create_card(4, "b", Some(2));
+        let c = create_card(4, "c", Some(3));
+        let res = req(PORT, "moveBefore", "Card", json!({"where": {"id": c["id"]}, "target": {"id": b["id"]}}));
+        assert_eq!(res["data"]["position"], 1536, "unexpected response {}", res);
+        assert_eq!(titles(4), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn moves_relative_to_the_record_itself_are_rejected() {
+        let a = create_card(5, "a", None);
+        let res = req(PORT, "moveAfter", "Card", json!({"where": {"id": a["id"]}, "target": {"id": a["id"]}}));
+        assert_field_error(&res, "target", "cannot move a record relative to itself");
+    }
+
+    #[test]
+    fn nested_creates_without_a_position_are_rejected() {
+        let res = req(PORT, "create", "Deck", json!({"create": {"name": "deck", "slides": {"create": [{"title": "a", "position": 1}, {"title": "b"}]}}}));
+        assert_field_error(&res, "create.slides.create.1.position", "position of a nested create is required");
+        let res = req(PORT, "create", "Deck", json!({"create": {"name": "deck", "slides": {"create": [{"title": "a", "position": 1}, {"title": "b", "position": 2}]}}, "include": {"slides": true}}));
+        assert_eq!(res["data"]["slides"].as_array().map(Vec::len), Some(2), "unexpected response {}", res);
+    }
+});
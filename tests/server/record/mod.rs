@@ -0,0 +1,39 @@
+use crate::server_tests;
+
+server_tests!(4038, concat!("serve --record ", env!("CARGO_TARGET_TMPDIR"), "/record.jsonl"), {
+    use std::fs;
+    use serde_json::{json, Value};
+    use crate::lib::{req, run_command};
+
+    const RECORDING: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/record.jsonl");
+
+    fn replay(recording: &str) -> (bool, String) {
+        let output = run_command(file!(), &["replay", recording]);
+        (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    #[test]
+    fn recorded_requests_are_replayed() {
+        // the recording of an earlier run is dropped, the server appends to the truncated file
+        fs::File::create(RECORDING).unwrap();
+        req(PORT, "create", "Note", json!({"create": {"title": "first"}}));
+        req(PORT, "findMany", "Note", json!({"select": {"title": true}}));
+        let recordings: Vec<Value> = fs::read_to_string(RECORDING).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(recordings.len(), 2);
+        assert_eq!(recordings[0]["method"], "POST");
+        assert_eq!(recordings[0]["uri"], "/Note/create");
+        assert_eq!(recordings[0]["status"], 200);
+        let (success, output) = replay(RECORDING);
+        assert!(success, "{}", output);
+        assert!(output.contains("2 request(s) replayed, 0 changed"), "{}", output);
+        // a changed response is reported
+        let mut changed = recordings.clone();
+        changed[1]["status"] = json!(201);
+        let changed_file = concat!(env!("CARGO_TARGET_TMPDIR"), "/record-changed.jsonl");
+        fs::write(changed_file, changed.iter().map(|r| r.to_string() + "\n").collect::<String>()).unwrap();
+        let (success, output) = replay(changed_file);
+        assert!(!success);
+        assert!(output.contains("POST /Note/findMany: 201 => 200"), "{}", output);
+        assert!(output.contains("2 request(s) replayed, 1 changed"), "{}", output);
+    }
+});
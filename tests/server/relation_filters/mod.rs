@@ -0,0 +1,76 @@
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::prelude::Limits;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // `all' has only active players, `mixed' has an inactive one and one whose activity is
+    // unknown, `nulls' only the unknown one and `empty' none at all
+    async fn server() -> TestServer {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Club", "create", json!({"create": {"name": "all", "players": {"create": [{"name": "a1", "active": true, "goals": {"create": [{"minute": 10}]}}, {"name": "a2", "active": true}]}, "leagues": {"create": [{"name": "north"}]}}})).await.unwrap();
+        server.request("Club", "create", json!({"create": {"name": "mixed", "players": {"create": [{"name": "m1", "active": true, "goals": {"create": [{"minute": 3}]}}, {"name": "m2", "active": false}, {"name": "m3"}]}, "leagues": {"create": [{"name": "south"}]}}})).await.unwrap();
+        server.request("Club", "create", json!({"create": {"name": "empty"}})).await.unwrap();
+        server.request("Club", "create", json!({"create": {"name": "nulls", "players": {"create": [{"name": "n1"}]}}})).await.unwrap();
+        server
+    }
+
+    async fn names(server: &TestServer, model: &str, r#where: JsonValue) -> JsonValue {
+        let res = server.request(model, "findMany", json!({"where": r#where, "orderBy": {"id": "asc"}})).await.unwrap();
+        match res["data"].as_array() {
+            Some(records) => JsonValue::Array(records.iter().map(|r| r["name"].clone()).collect()),
+            None => res,
+        }
+    }
+
+    #[tokio::test]
+    async fn some_every_and_none() {
+        let server = server().await;
+        assert_eq!(names(&server, "Club", json!({"players": {"some": {"active": true}}})).await, json!(["all", "mixed"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"every": {"active": true}}})).await, json!(["all", "empty"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"every": {"OR": [{"active": true}, {"active": null}]}}})).await, json!(["all", "empty", "nulls"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"every": {"NOT": {"active": false}}}})).await, json!(["all", "empty"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"none": {"active": true}}})).await, json!(["empty", "nulls"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"every": {"active": true}, "some": {}}})).await, json!(["all"]));
+        assert_eq!(names(&server, "Club", json!({"OR": [{"players": {"none": {}}}, {"leagues": {"some": {"name": "north"}}}]})).await, json!(["all", "empty"]));
+        assert_eq!(names(&server, "Club", json!({"leagues": {"every": {"name": "north"}}})).await, json!(["all", "empty", "nulls"]));
+        let res = server.request("Club", "count", json!({"where": {"players": {"every": {"active": true}}}})).await.unwrap();
+        assert_eq!(res["data"], json!(2));
+    }
+
+    #[tokio::test]
+    async fn relation_filters_nest() {
+        let server = server().await;
+        let scored = json!({"goals": {"some": {"minute": {"gt": 5}}}});
+        assert_eq!(names(&server, "Club", json!({"players": {"some": scored}})).await, json!(["all"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"every": {"goals": {"some": {}}}}})).await, json!(["empty"]));
+        assert_eq!(names(&server, "Club", json!({"players": {"none": {"goals": {"some": {}}}}})).await, json!(["empty", "nulls"]));
+        let res = server.request("Goal", "findMany", json!({"where": {"player": {"is": {"club": {"is": {"leagues": {"some": {"name": "south"}}}}}}}})).await.unwrap();
+        assert_eq!(res["data"].as_array().unwrap().iter().map(|g| g["minute"].clone()).collect::<Vec<_>>(), vec![json!(3)]);
+        assert_eq!(names(&server, "Player", json!({"club": {"is": {"players": {"some": {"active": false}}}}})).await, json!(["m1", "m2", "m3"]));
+        let res = server.request("Club", "findMany", json!({
+            "where": {"players": {"some": {}}},
+            "include": {"players": {"where": {"club": {"is": {"leagues": {"none": {}}}}}}},
+            "orderBy": {"id": "asc"},
+        })).await.unwrap();
+        let players: Vec<usize> = res["data"].as_array().unwrap().iter().map(|c| c["players"].as_array().unwrap().len()).collect();
+        assert_eq!(players, vec![0, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn too_many_resolved_keys_are_rejected() {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.limits(Limits { max_relation_filter_keys: 1, ..Default::default() });
+            Ok(())
+        }).await.unwrap();
+        for name in ["a", "b"] {
+            server.request("Club", "create", json!({"create": {"name": name, "players": {"create": [{"name": name, "goals": {"create": [{"minute": 1}]}}]}}})).await.unwrap();
+        }
+        let res = names(&server, "Club", json!({"players": {"some": {"goals": {"some": {}}}}})).await;
+        assert_eq!(res["error"]["type"], json!("QueryTooComplex"), "{}", res);
+        assert_eq!(res["error"]["message"], json!("relation filter `goals.some' of Player matches more than 1 records, narrow it"));
+        let res = names(&server, "Club", json!({"players": {"some": {"goals": {"some": {"minute": {"gt": 5}}}}}})).await;
+        assert_eq!(res, json!([]));
+    }
+}
@@ -0,0 +1,79 @@
+// the schema version is set on the app and the database is changed under it, so these tests run
+// the server in process
+mod test {
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::migrate::migrate;
+    use teo::test::TestServer;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server(version: Option<&'static str>) -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            if let Some(version) = version {
+                app.schema_version(version);
+            }
+            Ok(())
+        }).await.unwrap()
+    }
+
+    async fn query(server: &TestServer, sql: &str) -> Value {
+        let model = server.app().main_namespace().model_at_path(&vec!["Post"]).unwrap();
+        let transaction = Ctx::conn_ctx().connection_for_model(model).unwrap().no_transaction().await.unwrap();
+        transaction.query_raw(&Value::String(sql.to_owned())).await.unwrap()
+    }
+
+    async fn columns(server: &TestServer) -> Vec<String> {
+        let rows = query(server, "SELECT name FROM pragma_table_info('Post') ORDER BY cid").await;
+        rows.as_array().unwrap().iter().map(|r| r.get("name").unwrap().as_str().unwrap().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn old_columns_are_written_with_the_new_ones() {
+        let server = server(None).await;
+        assert_eq!(columns(&server).await, vec!["id", "title", "name"]);
+        let res = server.request("Post", "create", json!({"create": {"title": "Hello"}})).await.unwrap();
+        assert_eq!(res["data"], json!({"id": 1, "title": "Hello"}), "{}", res);
+        server.request("Post", "update", json!({"where": {"id": 1}, "update": {"title": "Hello again"}})).await.unwrap();
+        let rows = query(&server, "SELECT name, title FROM Post").await;
+        let row = &rows.as_array().unwrap()[0];
+        assert_eq!(row.get("name").and_then(|n| n.as_str()), Some("Hello again"));
+        assert_eq!(row.get("title").and_then(|t| t.as_str()), Some("Hello again"));
+        let res = server.request("Post", "findMany", json!({"where": {"name": "Hello again"}})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+    }
+
+    #[tokio::test]
+    async fn new_columns_are_added_and_copied_in_batches() {
+        let server = server(Some("2.0")).await;
+        // the table of the previous version, with more records than a batch
+        query(&server, "ALTER TABLE Post DROP COLUMN title").await;
+        query(&server, "INSERT INTO Post (name) WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1500) SELECT 'post ' || i FROM n").await;
+        migrate(false, false, true).await.unwrap();
+        assert_eq!(columns(&server).await, vec!["id", "name", "title"]);
+        let res = server.request("Post", "findMany", json!({"where": {"id": {"in": [1, 1000, 1001, 1500]}}, "orderBy": {"id": "asc"}})).await.unwrap();
+        let titles: Vec<_> = res["data"].as_array().unwrap().iter().map(|p| p["title"].clone()).collect();
+        assert_eq!(titles, vec![json!("post 1"), json!("post 1000"), json!("post 1001"), json!("post 1500")]);
+    }
+
+    #[tokio::test]
+    async fn old_columns_are_copied_again_and_dropped_after_the_version() {
+        let server = server(Some("2.1")).await;
+        assert_eq!(columns(&server).await, vec!["id", "title"]);
+        server.request("Post", "create", json!({"create": {"title": "Hello"}})).await.unwrap();
+        // a server of the previous version changed the old column
+        query(&server, "ALTER TABLE Post ADD COLUMN name TEXT").await;
+        query(&server, "UPDATE Post SET name = 'Hello again'").await;
+        migrate(false, false, true).await.unwrap();
+        assert_eq!(columns(&server).await, vec!["id", "title"]);
+        let res = server.request("Post", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!([{"id": 1, "title": "Hello again"}]), "{}", res);
+    }
+
+    #[tokio::test]
+    async fn dropped_after_needs_renamed_from() {
+        let error = TestServer::new(SCHEMA.replace("@renamedFrom(\"name\") ", "")).await.err().unwrap();
+        assert_eq!(error.message(), "`Post.title' has `@droppedAfter' without `@renamedFrom', only old columns are dropped later");
+    }
+}
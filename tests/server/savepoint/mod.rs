@@ -0,0 +1,107 @@
+// the handler is defined on the app, so these tests run the server in process
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::prelude::{path, request, Response};
+    use teo::savepoint::transaction;
+    use teo::test::TestServer;
+    use teo_runtime::connection::transaction as transaction_ctx;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn create(ctx: &transaction_ctx::Ctx, code: &str) -> path::Result<()> {
+        let model = ctx.namespace().model_at_path(&vec!["Entry"]).unwrap();
+        let object = ctx.create_object(model, teon!({"code": code}), None).await?;
+        object.save().await?;
+        Ok(())
+    }
+
+    // the scenarios of nested transactions, chosen by the request
+    async fn nested(ctx: request::Ctx) -> path::Result<Response> {
+        let scenario = ctx.body().get("scenario").and_then(|s| s.as_str()).unwrap_or_default().to_owned();
+        transaction(&ctx.transaction_ctx(), |ctx| {
+            let scenario = scenario.clone();
+            async move {
+                create(&ctx, "outer").await?;
+                match scenario.as_str() {
+                    "retry" => {
+                        let failed = transaction(&ctx, |ctx| async move {
+                            create(&ctx, "first try").await?;
+                            Err::<(), _>(path::Error::internal_server_error(path![], "gave up"))
+                        }).await;
+                        assert!(failed.is_err());
+                        transaction(&ctx, |ctx| async move { create(&ctx, "second try").await }).await?;
+                    }
+                    "violation" => {
+                        // the failing statement is rolled back with the savepoint, the outer write stays
+                        let failed = transaction(&ctx, |ctx| async move {
+                            create(&ctx, "inner").await?;
+                            create(&ctx, "outer").await
+                        }).await;
+                        assert!(failed.is_err());
+                    }
+                    "deep" => {
+                        transaction(&ctx, |ctx| async move {
+                            create(&ctx, "middle").await?;
+                            let failed = transaction(&ctx, |ctx| async move {
+                                create(&ctx, "deepest").await?;
+                                Err::<(), _>(path::Error::internal_server_error(path![], "gave up"))
+                            }).await;
+                            assert!(failed.is_err());
+                            Ok(())
+                        }).await?;
+                    }
+                    _ => {
+                        transaction(&ctx, |ctx| async move { create(&ctx, "inner").await }).await?;
+                        Err(path::Error::internal_server_error(path![], "outer failed"))?;
+                    }
+                }
+                Ok(())
+            }
+        }).await?;
+        Ok(Response::data(teon!(true)))
+    }
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.with_main_namespace_mut(|namespace| namespace.define_handler("nested", nested))
+        }).await.unwrap()
+    }
+
+    async fn codes(server: &TestServer) -> JsonValue {
+        let res = server.request("Entry", "findMany", json!({"orderBy": {"id": "asc"}})).await.unwrap();
+        JsonValue::Array(res["data"].as_array().unwrap().iter().map(|e| e["code"].clone()).collect())
+    }
+
+    #[tokio::test]
+    async fn failed_nested_transactions_roll_back_alone_and_can_be_retried() {
+        let server = server().await;
+        let res = server.request_at_path("/nested", json!({"scenario": "retry"})).await.unwrap();
+        assert_eq!(res["data"], json!(true), "{}", res);
+        assert_eq!(codes(&server).await, json!(["outer", "second try"]));
+    }
+
+    #[tokio::test]
+    async fn failed_statements_are_rolled_back_with_their_savepoint() {
+        let server = server().await;
+        let res = server.request_at_path("/nested", json!({"scenario": "violation"})).await.unwrap();
+        assert_eq!(res["data"], json!(true), "{}", res);
+        assert_eq!(codes(&server).await, json!(["outer"]));
+    }
+
+    #[tokio::test]
+    async fn savepoints_nest() {
+        let server = server().await;
+        let res = server.request_at_path("/nested", json!({"scenario": "deep"})).await.unwrap();
+        assert_eq!(res["data"], json!(true), "{}", res);
+        assert_eq!(codes(&server).await, json!(["outer", "middle"]));
+    }
+
+    #[tokio::test]
+    async fn the_outer_transaction_rolls_back_released_savepoints() {
+        let server = server().await;
+        let res = server.request_at_path("/nested", json!({"scenario": "abort"})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        assert_eq!(codes(&server).await, json!([]));
+    }
+}
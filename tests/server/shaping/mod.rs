@@ -0,0 +1,79 @@
+use crate::server_tests;
+
+server_tests!(4023, {
+    use serde_json::json;
+    use crate::lib::req;
+    use crate::lib::fixture::assert_field_error;
+    use crate::{assert_json, matcher};
+
+    #[test]
+    fn aliases_and_computed_outputs() {
+        req(PORT, "create", "Product", json!({
+            "create": {"name": "Pen", "brand": "Ink", "price": 3, "quantity": 4},
+        }));
+        req(PORT, "create", "Product", json!({
+            "create": {"name": "Cup", "price": 5, "quantity": 2},
+        }));
+        let res = req(PORT, "findMany", "Product", json!({
+            "where": {"name": {"in": ["Pen", "Cup"]}},
+            "orderBy": {"id": "asc"},
+            "select": {
+                "label": {"alias": "name"},
+                "total": {"multiply": ["$price", "$quantity"]},
+                "title": {"concat": ["$brand", " ", "$name"]},
+            },
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                {"label": "Pen", "total": 12, "title": "Ink Pen"},
+                {"label": "Cup", "total": 10, "title": null},
+            ],
+            "meta": {"count": 2},
+        }));
+    }
+
+    #[test]
+    fn selected_fields_are_kept_and_read_fields_are_hidden() {
+        req(PORT, "create", "Product", json!({
+            "create": {"name": "Lamp", "price": 20, "quantity": 1},
+        }));
+        let res = req(PORT, "findFirst", "Product", json!({
+            "where": {"name": "Lamp"},
+            "select": {"name": true, "discounted": {"subtract": ["$price", 5]}},
+        }));
+        assert_json!(res, matcher!({
+            "data": {"name": "Lamp", "discounted": 15},
+        }));
+    }
+
+    #[test]
+    fn mutations_are_shaped() {
+        let res = req(PORT, "create", "Product", json!({
+            "create": {"name": "Mug", "price": 7, "quantity": 1},
+            "select": {"label": {"concat": ["$name", "!"]}},
+        }));
+        assert_json!(res, matcher!({
+            "data": {"label": "Mug!"},
+        }));
+    }
+
+    #[test]
+    fn excluded_fields_cannot_be_read() {
+        let res = req(PORT, "findMany", "Product", json!({
+            "select": {"name": false, "label": {"alias": "name"}},
+        }));
+        assert_field_error(&res, "select.name", "field is excluded but used in a computed output");
+    }
+
+    #[test]
+    fn unknown_fields_and_expressions_are_rejected() {
+        let res = req(PORT, "findMany", "Product", json!({
+            "select": {"label": {"alias": "missing"}},
+        }));
+        assert_field_error(&res, "select.label.alias", "field `missing' is not defined");
+        let res = req(PORT, "findMany", "Product", json!({
+            "select": {"squared": {"power": ["$price", 2]}},
+        }));
+        assert_field_error(&res, "select.squared.power", "unknown expression");
+    }
+});
@@ -0,0 +1,136 @@
+// the share secret and the middlewares are set up on the app, so these tests run the server in
+// process
+mod test {
+    use std::time::Duration;
+    use key_path::path;
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+    use teo_runtime::action::action::{ENTRY, FIND, SINGLE};
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::middleware::middleware::Middleware;
+    use teo_runtime::middleware::next::Next;
+    use teo_runtime::path;
+    use teo_runtime::request;
+    use teo_teon::teon;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // rejects the requests with a `blocked' header, and the agent named by the `agent' header
+    // makes the request
+    async fn server() -> TestServer {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.share_secret("secret");
+            app.with_main_namespace_mut(|namespace| namespace.define_middleware("blocker", |_args: Arguments| async {
+                let middleware: &'static dyn Middleware = Box::leak(Box::new(|ctx: request::Ctx, next: &'static dyn Next| async move {
+                    if ctx.request().headers().get("blocked").is_some() {
+                        return Err(path::Error::unauthorized_error_message_only("blocked"));
+                    }
+                    next.call(ctx).await
+                }));
+                Ok(middleware)
+            }))?;
+            app.with_main_namespace_mut(|namespace| namespace.define_middleware("actAs", |_args: Arguments| async {
+                let middleware: &'static dyn Middleware = Box::leak(Box::new(|ctx: request::Ctx, next: &'static dyn Next| async move {
+                    if let Some(name) = ctx.request().headers().get("agent").map(ToOwned::to_owned) {
+                        let model = ctx.namespace().model_at_path(&vec!["Agent"]).unwrap();
+                        let finder = teon!({"where": {"name": name}});
+                        if let Some(agent) = ctx.transaction_ctx().find_first_internal(model, &finder, true, FIND | SINGLE | ENTRY, None, path![]).await? {
+                            ctx.data_mut().insert("identity", agent);
+                        }
+                    }
+                    next.call(ctx).await
+                }));
+                Ok(middleware)
+            }))
+        }).await.unwrap();
+        for (title, published) in [("public", true), ("draft", false)] {
+            server.request("Post", "create", json!({"create": {"title": title, "published": published}})).await.unwrap();
+        }
+        server
+    }
+
+    async fn share(server: &TestServer, expires_in: u64) -> String {
+        let res = server.request("Post", "share", json!({"where": {"published": true}, "expiresIn": expires_in})).await.unwrap();
+        res["data"]["token"].as_str().unwrap().to_owned()
+    }
+
+    async fn read(server: &TestServer, token: &str) -> JsonValue {
+        server.request_at_path("/_share", json!({"token": token})).await.unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn token_reads_the_shared_records() {
+        let server = server().await;
+        let token = share(&server, 60).await;
+        let res = read(&server, &token).await;
+        assert_eq!(res["data"].as_array().unwrap().len(), 1);
+        assert_eq!(res["data"][0]["title"], json!("public"));
+    }
+
+    #[tokio::test]
+    async fn tampered_token_is_rejected() {
+        let server = server().await;
+        let token = share(&server, 60).await;
+        let (payload, signature) = token.split_once('.').unwrap();
+        let flipped = if signature.starts_with('0') { "1" } else { "0" };
+        let res = read(&server, &format!("{}.{}{}", payload, flipped, &signature[1..])).await;
+        assert_eq!(res["error"]["message"], json!("invalid share token"));
+    }
+
+    #[tokio::test]
+    async fn widened_scope_is_rejected() {
+        let server = server().await;
+        let token = share(&server, 60).await;
+        let (payload, signature) = token.split_once('.').unwrap();
+        let mut payload: JsonValue = serde_json::from_slice(&unhex(payload)).unwrap();
+        payload["args"] = json!({});
+        let res = read(&server, &format!("{}.{}", hex(payload.to_string().as_bytes()), signature)).await;
+        assert_eq!(res["error"]["message"], json!("invalid share token"));
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_gone() {
+        let server = server().await;
+        let token = share(&server, 1).await;
+        tokio::time::sleep(Duration::from_millis(2100)).await;
+        let res = read(&server, &token).await;
+        assert_eq!(res["error"]["message"], json!("share token has expired"));
+    }
+
+    #[tokio::test]
+    async fn token_reads_run_the_middlewares() {
+        let server = server().await;
+        let token = share(&server, 60).await;
+        let res = server.request_at_path_with_headers("/_share", json!({"token": token}), &[("blocked", "1")]).await.unwrap();
+        assert_eq!(res["error"]["message"], json!("blocked"));
+    }
+
+    #[tokio::test]
+    async fn token_reads_protected_records_as_the_creator() {
+        let server = server().await;
+        server.request("Agent", "create", json!({"create": {"name": "ann"}})).await.unwrap();
+        let res = server.request_at_path_with_headers("/Memo/create", json!({"create": {"body": "minutes"}}), &[("agent", "ann")]).await.unwrap();
+        assert_eq!(res["data"]["body"], json!("minutes"), "{}", res);
+        // an anonymous request can neither read nor share the memos
+        let res = server.request("Memo", "findMany", json!({})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        let res = server.request("Memo", "share", json!({})).await.unwrap();
+        assert!(res.get("error").is_some(), "{}", res);
+        let res = server.request_at_path_with_headers("/Memo/share", json!({}), &[("agent", "ann")]).await.unwrap();
+        let token = res["data"]["token"].as_str().unwrap().to_owned();
+        let res = read(&server, &token).await;
+        assert_eq!(res["data"][0]["body"], json!("minutes"), "{}", res);
+        // the share ends with the identity of its creator
+        server.request("Agent", "deleteMany", json!({"where": {}})).await.unwrap();
+        let res = read(&server, &token).await;
+        assert_eq!(res["error"]["message"], json!("invalid share token"));
+    }
+}
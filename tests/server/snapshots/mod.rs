@@ -0,0 +1,47 @@
+// snapshots are compared through the Rust API, so these tests run the server in process
+mod test {
+    use std::fs;
+    use serde_json::json;
+    use teo::test::{Snapshot, TestServer};
+    use uuid::Uuid;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn snapshot() -> Snapshot {
+        Snapshot::new(std::env::temp_dir().join(format!("teo-snapshots-{}", Uuid::new_v4())))
+    }
+
+    #[tokio::test]
+    async fn volatile_values_are_masked() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let snapshot = snapshot();
+        let first = server.request("Note", "create", json!({"create": {"title": "a"}})).await.unwrap();
+        snapshot.assert("note", &first).unwrap();
+        let second = server.request("Note", "create", json!({"create": {"title": "a"}})).await.unwrap();
+        assert_ne!(first, second);
+        snapshot.assert("note", &second).unwrap();
+    }
+
+    #[tokio::test]
+    async fn changes_are_reported_with_a_diff() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let snapshot = snapshot();
+        let res = server.request("Note", "create", json!({"create": {"title": "a"}})).await.unwrap();
+        snapshot.assert("note", &res).unwrap();
+        let res = server.request("Note", "create", json!({"create": {"title": "b"}})).await.unwrap();
+        let error = snapshot.assert("note", &res).unwrap_err();
+        assert!(error.message().starts_with("snapshot `note' does not match, set TEO_UPDATE_SNAPSHOTS=1 to update\n"), "{}", error.message());
+        assert!(error.message().contains("-     \"title\": \"a\",\n+     \"title\": \"b\","), "{}", error.message());
+    }
+
+    #[tokio::test]
+    async fn generated_primary_keys_are_masked() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let directory = std::env::temp_dir().join(format!("teo-snapshots-{}", Uuid::new_v4()));
+        let snapshot = Snapshot::new(&directory).generated_keys(server.app().main_namespace().model_at_path(&vec!["Tag"]).unwrap());
+        let res = server.request("Tag", "create", json!({"create": {"name": "red"}})).await.unwrap();
+        snapshot.assert("tag", &res).unwrap();
+        let written = fs::read_to_string(directory.join("tag.json")).unwrap();
+        assert_eq!(written, "{\n  \"data\": {\n    \"code\": \"[volatile]\",\n    \"name\": \"red\"\n  }\n}\n");
+    }
+}
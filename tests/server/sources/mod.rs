@@ -0,0 +1,71 @@
+// sources are registered on the app before it connects, so these tests run the server in process
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use serde_json::json;
+    use teo::app::ctx::Ctx;
+    use teo::test::TestServer;
+    use teo_runtime::connection::connection::Connection;
+    use teo_sql_connector::connector::SQLConnection;
+    use teo_sql_connector::schema::dialect::SQLDialect;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    type Stash = Arc<Mutex<Option<Arc<dyn Connection>>>>;
+
+    // the source is a SQLite file of its own, SQLite in memory is shared by the connections of a
+    // process, the connection is kept in `stash' to be inspected
+    async fn server(stash: &Stash, directory: &Path) -> TestServer {
+        let url = format!("sqlite:{}", directory.join("analytics.sqlite").display());
+        TestServer::new_with(SCHEMA, |app| {
+            let stash = stash.clone();
+            app.register_connector_provider("analytics", move |_url: String| {
+                let stash = stash.clone();
+                let url = url.clone();
+                async move {
+                    let connection: Arc<dyn Connection> = Arc::new(SQLConnection::new(SQLDialect::SQLite, &url, false).await);
+                    *stash.lock().unwrap() = Some(connection.clone());
+                    Ok(connection)
+                }
+            });
+            app.register_source("analytics", "analytics://events");
+            Ok(())
+        }).await.unwrap()
+    }
+
+    async fn tables(connection: Arc<dyn Connection>) -> Value {
+        let transaction = connection.no_transaction().await.unwrap();
+        transaction.query_raw(&Value::String("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name".to_owned())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_are_stored_in_the_source() {
+        let stash = Stash::default();
+        let directory = std::env::temp_dir().join(format!("teo-source-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        let server = server(&stash, &directory).await;
+        server.request("User", "create", json!({"create": {"name": "Ada"}})).await.unwrap();
+        let res = server.request("Event", "create", json!({"create": {"kind": "signIn"}})).await.unwrap();
+        assert_eq!(res["data"]["kind"], json!("signIn"));
+        let res = server.request("Event", "update", json!({"where": {"id": res["data"]["id"]}, "update": {"kind": "signOut"}})).await.unwrap();
+        assert_eq!(res["data"]["kind"], json!("signOut"));
+        let res = server.request("Event", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(1));
+        let source = stash.lock().unwrap().clone().unwrap();
+        let source_tables = format!("{:?}", tables(source).await);
+        assert!(source_tables.contains("Event") && !source_tables.contains("User"), "{}", source_tables);
+        let main = Ctx::conn_ctx().connection_for_model(server.app().main_namespace().model_at_path(&vec!["User"]).unwrap()).unwrap();
+        let main_tables = format!("{:?}", tables(main).await);
+        assert!(main_tables.contains("User") && !main_tables.contains("Event"), "{}", main_tables);
+        drop(server);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unregistered_source_is_an_error() {
+        let error = TestServer::new(SCHEMA).await.err().unwrap();
+        assert_eq!(error.message(), "model `Event' uses source `analytics', which is not registered");
+    }
+}
@@ -0,0 +1,108 @@
+// the transitions are checked in the save pipeline of every write path, nested writes and many
+// updates included, and a hook is set up on the app, so these tests run the server in process
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+    use teo_result::Error;
+    use teo_runtime::arguments::Arguments;
+    use teo_runtime::pipeline::Ctx;
+    use teo_teon::value::Value;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.define_pipeline_item("requireReviewer", |_args: Arguments, ctx: Ctx| async move {
+                let post = ctx.value().as_model_object().unwrap();
+                if post.get_value("reviewer")? == Value::Null {
+                    Err(Error::new("a reviewer is required"))?
+                }
+                Ok(ctx.value().clone())
+            })
+        }).await.unwrap()
+    }
+
+    fn error_message(res: &JsonValue) -> Option<&str> {
+        res.get("error").and_then(|e| e.get("fields")).and_then(|f| f.as_object()).and_then(|f| f.values().next()).and_then(|m| m.as_str())
+    }
+
+    async fn create_order(server: &TestServer, status: &str) -> i64 {
+        let res = server.request("Order", "create", json!({"create": {"status": status, "lines": {"create": [{"status": "open"}]}}})).await.unwrap();
+        res["data"]["id"].as_i64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn allowed_transition_is_saved() {
+        let server = server().await;
+        let id = create_order(&server, "pending").await;
+        let res = server.request("Order", "update", json!({"where": {"id": id}, "update": {"status": "approved"}})).await.unwrap();
+        assert_eq!(res["data"]["status"], json!("approved"));
+        let res = server.request("Order", "update", json!({"where": {"id": id}, "update": {"status": "cancelled"}})).await.unwrap();
+        assert_eq!(res["data"]["status"], json!("cancelled"));
+    }
+
+    #[tokio::test]
+    async fn disallowed_transition_is_rejected() {
+        let server = server().await;
+        let id = create_order(&server, "pending").await;
+        let res = server.request("Order", "update", json!({"where": {"id": id}, "update": {"status": "shipped"}})).await.unwrap();
+        assert_eq!(error_message(&res), Some("transition from `pending' to `shipped' is not allowed"));
+        let res = server.request("Order", "findUnique", json!({"where": {"id": id}})).await.unwrap();
+        assert_eq!(res["data"]["status"], json!("pending"));
+    }
+
+    #[tokio::test]
+    async fn update_many_is_checked() {
+        let server = server().await;
+        create_order(&server, "pending").await;
+        create_order(&server, "approved").await;
+        let res = server.request("Order", "updateMany", json!({"where": {}, "update": {"status": "shipped"}})).await.unwrap();
+        assert_eq!(error_message(&res), Some("transition from `pending' to `shipped' is not allowed"));
+        let res = server.request("Order", "findMany", json!({"where": {"status": "shipped"}})).await.unwrap();
+        assert_eq!(res["data"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn nested_update_is_checked() {
+        let server = server().await;
+        let id = create_order(&server, "pending").await;
+        let update = |status: &str| json!({"where": {"id": id}, "update": {"lines": {"updateMany": {"where": {}, "update": {"status": status}}}}, "include": {"lines": true}});
+        let res = server.request("Order", "update", update("reopened")).await.unwrap();
+        // the runtime reports the errors of nested writes with the path in the message
+        assert_eq!(res["error"]["message"], json!("update.lines.updateMany.status: transition from `open' to `reopened' is not allowed"));
+        let res = server.request("Order", "update", update("closed")).await.unwrap();
+        assert_eq!(res["data"]["lines"][0]["status"], json!("closed"));
+    }
+
+    async fn create_post(server: &TestServer) -> i64 {
+        let res = server.request("Post", "create", json!({"create": {"stage": "draft"}})).await.unwrap();
+        res["data"]["id"].as_i64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn enum_transitions_are_checked() {
+        let server = server().await;
+        let id = create_post(&server).await;
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "published"}})).await.unwrap();
+        assert_eq!(error_message(&res), Some("transition from `draft' to `published' is not allowed"));
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "review"}})).await.unwrap();
+        assert_eq!(res["data"]["stage"], json!("review"));
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "archived"}})).await.unwrap();
+        assert_eq!(res["data"]["stage"], json!("archived"));
+    }
+
+    #[tokio::test]
+    async fn transition_hook_runs_on_its_transition() {
+        let server = server().await;
+        let id = create_post(&server).await;
+        // the hook is only run from review to published
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "review"}})).await.unwrap();
+        assert_eq!(res["data"]["stage"], json!("review"));
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "published"}})).await.unwrap();
+        assert_eq!(error_message(&res), Some("a reviewer is required"));
+        let res = server.request("Post", "findUnique", json!({"where": {"id": id}})).await.unwrap();
+        assert_eq!(res["data"]["stage"], json!("review"));
+        let res = server.request("Post", "update", json!({"where": {"id": id}, "update": {"stage": "published", "reviewer": "Ada"}})).await.unwrap();
+        assert_eq!(res["data"]["stage"], json!("published"));
+    }
+}
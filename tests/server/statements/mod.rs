@@ -0,0 +1,81 @@
+// the limits are set on the app, so these tests run the server in process
+mod test {
+    use std::time::Duration;
+    use serde_json::{json, Value};
+    use teo::prelude::Limits;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // the limits apply to `findUnique', `findMany' and `create', the other actions set up and check
+    // the records
+    async fn server(limits: Limits) -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            for action in ["findUnique", "findMany", "create"] {
+                app.action_limits(action, limits);
+            }
+            Ok(())
+        }).await.unwrap()
+    }
+
+    async fn seeded(server: &TestServer) {
+        let res = server.request("User", "createMany", json!({"create": [{"email": "ada@example.com"}]})).await.unwrap();
+        assert_eq!(res["meta"]["count"], json!(1), "unexpected response {}", res);
+    }
+
+    fn assert_error_code(res: &Value, error_type: &str, code: &str) {
+        assert_eq!(res["error"]["type"], json!(error_type), "unexpected response {}", res);
+        assert_eq!(res["error"]["code"], json!(code), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn strict_budgets_fail_reads_over_them() {
+        let server = server(Limits { max_statements: Some(1), strict_max_statements: true, ..Default::default() }).await;
+        seeded(&server).await;
+        let res = server.request("User", "findUnique", json!({"where": {"id": 1}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("ada@example.com"), "unexpected response {}", res);
+        // the records and their count
+        let res = server.request("User", "findMany", json!({})).await.unwrap();
+        assert_error_code(&res, "TooManyStatements", "T5001");
+        assert_eq!(res["error"]["message"], json!("request issued more than 1 statements"));
+    }
+
+    #[tokio::test]
+    async fn strict_budgets_roll_back_writes_over_them() {
+        let server = server(Limits { max_statements: Some(2), strict_max_statements: true, ..Default::default() }).await;
+        let res = server.request("User", "create", json!({"create": {"email": "ada@example.com", "posts": {"create": [{"title": "One"}, {"title": "Two"}]}}})).await.unwrap();
+        assert_error_code(&res, "TooManyStatements", "T5001");
+        let res = server.request("User", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(0));
+        let res = server.request("Post", "count", json!({})).await.unwrap();
+        assert_eq!(res["data"], json!(0));
+    }
+
+    #[tokio::test]
+    async fn creates_issue_one_statement() {
+        // a lookup of the unique handle before the insert would be the statement over the budget
+        let server = server(Limits { max_statements: Some(1), strict_max_statements: true, ..Default::default() }).await;
+        let res = server.request("User", "create", json!({"create": {"email": "ada@example.com", "handle": "ada"}})).await.unwrap();
+        assert_eq!(res["data"]["handle"], json!("ada"), "unexpected response {}", res);
+        let res = server.request("User", "create", json!({"create": {"email": "ada@example.org", "handle": "ada"}})).await.unwrap();
+        assert_error_code(&res, "ValueError", "T4001");
+        assert_eq!(res["error"]["fields"]["create.handle"], json!("unique value duplicated: handle"));
+    }
+
+    #[tokio::test]
+    async fn budgets_only_log_when_not_strict() {
+        let server = server(Limits { max_statements: Some(1), ..Default::default() }).await;
+        seeded(&server).await;
+        let res = server.request("User", "findMany", json!({})).await.unwrap();
+        assert_eq!(res["data"][0]["email"], json!("ada@example.com"), "unexpected response {}", res);
+    }
+
+    #[tokio::test]
+    async fn strict_slow_requests_fail_reads_only() {
+        let server = server(Limits { slow_request: Some(Duration::ZERO), strict_slow_request: true, ..Default::default() }).await;
+        let res = server.request("User", "create", json!({"create": {"email": "ada@example.com"}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("ada@example.com"), "unexpected response {}", res);
+        let res = server.request("User", "findMany", json!({})).await.unwrap();
+        assert_error_code(&res, "SlowRequest", "T5002");
+    }
+}
@@ -0,0 +1,64 @@
+// the handler is defined on the app, so these tests run the server in process
+mod test {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{read_body, TestRequest};
+    use teo::prelude::{serve_static_files_with_options, StaticFilesOptions};
+    use teo::test::TestServer;
+    use teo_runtime::request;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+    static PUBLIC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/server/static_files/public");
+
+    fn options() -> StaticFilesOptions {
+        StaticFilesOptions { spa_fallback: true, cache_control: Some("public, max-age=60".to_owned()) }
+    }
+
+    async fn server() -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.with_main_namespace_mut(|namespace| namespace.define_handler("assets", |ctx: request::Ctx| async move {
+                let path = ctx.handler_match().captures.get("path").cloned().unwrap_or_default();
+                Ok(serve_static_files_with_options(PUBLIC, path, &options())?)
+            }))
+        }).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_files_with_their_cache_control() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/assets/js/main.js").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("Cache-Control").unwrap(), "public, max-age=60");
+        assert!(res.headers().get("Content-Type").unwrap().to_str().unwrap().contains("javascript"));
+        assert_eq!(read_body(res).await, "console.log(\"main\")\n");
+    }
+
+    #[tokio::test]
+    async fn answers_conditional_requests_with_not_modified() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/assets/index.html").to_request()).await;
+        let etag = res.headers().get("ETag").unwrap().to_str().unwrap().to_owned();
+        let res = server.call(TestRequest::get().uri("/assets/index.html").insert_header(("If-None-Match", etag.as_str())).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_index_for_client_routes() {
+        let server = server().await;
+        let res = server.call(TestRequest::get().uri("/assets/settings/profile").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "<html>index</html>\n");
+        // a missing file is not a client route
+        let res = server.call(TestRequest::get().uri("/assets/js/missing.js").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn rejects_paths_out_of_the_base() {
+        for path in ["../schema.teo", "js/../../schema.teo", "/../mod.rs"] {
+            assert!(serve_static_files_with_options(PUBLIC, path, &StaticFilesOptions::default()).is_err(), "{} is served", path);
+        }
+        // the fallback doesn't apply to them either
+        assert!(serve_static_files_with_options(PUBLIC, "../schema.teo", &options()).is_err());
+        assert!(serve_static_files_with_options(PUBLIC, "js/main.js", &StaticFilesOptions::default()).is_ok());
+    }
+}
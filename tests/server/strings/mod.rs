@@ -0,0 +1,85 @@
+// the items are loaded by the app rather than declared in a schema of the runtime, so these tests
+// run the server in process with the declarations in the schema
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    // every field is required, `fields' replace some of these valid values
+    async fn create(server: &TestServer, fields: JsonValue) -> JsonValue {
+        let mut create = json!({
+            "slug": "slug",
+            "lower": "lower",
+            "upper": "upper",
+            "englishUpper": "upper",
+            "start": "start",
+            "end": "end",
+            "website": "https://example.com/path?q=1",
+            "reference": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "card": "4111 1111 1111 1111",
+            "phone": "020 7946 0958",
+            "internationalPhone": "+1 (415) 555-2671",
+            "imei": "490154203237518",
+        });
+        create.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        server.request("Entry", "create", json!({"create": create})).await.unwrap()
+    }
+
+    async fn update(server: &TestServer, id: &JsonValue, update: JsonValue) -> JsonValue {
+        server.request("Entry", "update", json!({"where": {"id": id}, "update": update})).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn transforms_on_create_and_update() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = create(&server, json!({
+            "slug": "  Crème Brûlée: a Recipe!  ",
+            "lower": "ISTANBUL İzmir",
+            "upper": "istanbul ılık",
+            "englishUpper": "istanbul",
+            "start": "  padded  ",
+            "end": "  padded  ",
+        })).await;
+        assert_eq!(res["data"]["slug"], json!("creme-brulee-a-recipe"));
+        assert_eq!(res["data"]["lower"], json!("ıstanbul izmir"));
+        assert_eq!(res["data"]["upper"], json!("İSTANBUL ILIK"));
+        assert_eq!(res["data"]["englishUpper"], json!("ISTANBUL"));
+        assert_eq!(res["data"]["start"], json!("padded  "));
+        assert_eq!(res["data"]["end"], json!("  padded"));
+        let res = update(&server, &res["data"]["id"], json!({"slug": "Hello,   World", "lower": "DIŞ"})).await;
+        assert_eq!(res["data"]["slug"], json!("hello-world"));
+        assert_eq!(res["data"]["lower"], json!("dış"));
+    }
+
+    #[tokio::test]
+    async fn valid_values_are_saved() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = create(&server, json!({})).await;
+        assert_eq!(res["data"]["card"], json!("4111 1111 1111 1111"));
+        assert_eq!(res["data"]["phone"], json!("020 7946 0958"));
+        let res = update(&server, &res["data"]["id"], json!({"phone": "+44 20 7946 0958"})).await;
+        assert_eq!(res["data"]["phone"], json!("+44 20 7946 0958"));
+    }
+
+    #[tokio::test]
+    async fn invalid_values_are_value_errors() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        for (field, value, message) in [
+            ("website", "ftp://example.com", "input is not url"),
+            ("website", "example.com", "input is not url"),
+            ("reference", "67e5504410b1426f9247bb680e5fe0c8", "input is not uuid"),
+            ("card", "4111 1111 1111 1112", "input is not credit card"),
+            ("card", "4111", "input is not credit card"),
+            ("phone", "+1 415 555 2671", "input is not phone number"),
+            ("internationalPhone", "415 555 2671", "input is not phone number"),
+            ("imei", "490154203237519", "input doesn't pass luhn check"),
+        ] {
+            let res = create(&server, json!({field: value})).await;
+            assert_eq!(res["error"]["fields"][format!("create.{}", field)], json!(message), "{} {}", field, value);
+        }
+        let id = create(&server, json!({})).await["data"]["id"].clone();
+        let res = update(&server, &id, json!({"card": "1234 5678 9012 3456"})).await;
+        assert_eq!(res["error"]["fields"]["update.card"], json!("input is not credit card"));
+    }
+}
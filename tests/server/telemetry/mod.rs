@@ -0,0 +1,123 @@
+// the exporter is configured on the app, so these tests run the server in process
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use serde_json::{json, Value as JsonValue};
+    use teo::telemetry::TelemetryOptions;
+    use teo::test::TestServer;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::time::sleep;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    type Spans = Arc<Mutex<Vec<JsonValue>>>;
+
+    // a collector which keeps the spans of the posted exports
+    async fn collector() -> (String, Spans) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let spans: Spans = Arc::new(Mutex::new(vec![]));
+        let received = spans.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut reader = BufReader::new(read);
+                    loop {
+                        let mut length = 0;
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some((name, value)) = line.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    length = value.trim().parse().unwrap();
+                                }
+                            }
+                        }
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).await.unwrap();
+                        let export: JsonValue = serde_json::from_slice(&body).unwrap();
+                        let spans = export["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap().clone();
+                        received.lock().unwrap().extend(spans);
+                        write.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\ncontent-type: application/json\r\n\r\n{}").await.unwrap();
+                    }
+                });
+            }
+        });
+        (endpoint, spans)
+    }
+
+    async fn server(endpoint: String) -> TestServer {
+        TestServer::new_with(SCHEMA, |app| {
+            app.telemetry(TelemetryOptions { endpoint, service_name: "telemetry-test".to_owned(), headers: vec![] });
+            Ok(())
+        }).await.unwrap()
+    }
+
+    // the spans are exported in the background
+    async fn wait_for(spans: &Spans, name: &str) {
+        for _ in 0..100 {
+            if spans.lock().unwrap().iter().any(|s| s["name"] == json!(name)) {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    fn span(spans: &Spans, name: &str) -> JsonValue {
+        spans.lock().unwrap().iter().find(|s| s["name"] == json!(name)).cloned().unwrap_or_else(|| panic!("no span {}", name))
+    }
+
+    fn attribute(span: &JsonValue, key: &str) -> JsonValue {
+        span["attributes"].as_array().unwrap().iter().find(|a| a["key"] == json!(key)).map(|a| a["value"].clone()).unwrap_or(JsonValue::Null)
+    }
+
+    #[tokio::test]
+    async fn requests_continue_the_trace_of_their_traceparent() {
+        let (endpoint, spans) = collector().await;
+        let server = server(endpoint).await;
+        let traceparent = format!("00-{}-{}-01", TRACE_ID, PARENT_ID);
+        server.request_at_path_with_headers("/Gauge/create", json!({"create": {"label": "pressure"}}), &[("traceparent", &traceparent)]).await.unwrap();
+        wait_for(&spans, "POST Gauge.create").await;
+        let request = span(&spans, "POST Gauge.create");
+        assert_eq!(request["traceId"], json!(TRACE_ID));
+        assert_eq!(request["parentSpanId"], json!(PARENT_ID));
+        assert_eq!(request["kind"], json!(2));
+        assert_eq!(attribute(&request, "http.response.status_code"), json!({"intValue": "200"}));
+        let handler = span(&spans, "handler Gauge.create");
+        assert_eq!(handler["traceId"], json!(TRACE_ID));
+        assert_eq!(handler["parentSpanId"], request["spanId"]);
+        let create = span(&spans, "create Gauge");
+        assert_eq!(create["parentSpanId"], handler["spanId"]);
+        assert_eq!(create["kind"], json!(3));
+        assert_eq!(attribute(&create, "db.system"), json!({"stringValue": "sqlite"}));
+        assert!(attribute(&create, "db.statement")["stringValue"].as_str().unwrap().contains("INSERT"), "{}", create);
+    }
+
+    #[tokio::test]
+    async fn unsampled_traces_are_propagated_without_spans() {
+        let (endpoint, spans) = collector().await;
+        let server = server(endpoint).await;
+        let traceparent = format!("00-{}-{}-00", TRACE_ID, PARENT_ID);
+        server.request_at_path_with_headers("/Gauge/findMany", json!({}), &[("traceparent", &traceparent)]).await.unwrap();
+        // an invalid header starts a trace of its own
+        server.request_at_path_with_headers("/Gauge/count", json!({}), &[("traceparent", "00-nothex-00f067aa0ba902b7-01")]).await.unwrap();
+        wait_for(&spans, "POST Gauge.count").await;
+        let count = span(&spans, "POST Gauge.count");
+        assert_ne!(count["traceId"], json!(TRACE_ID));
+        assert!(count.get("parentSpanId").is_none());
+        assert!(spans.lock().unwrap().iter().all(|s| s["traceId"] != json!(TRACE_ID)));
+    }
+}
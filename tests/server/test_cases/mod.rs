@@ -0,0 +1,76 @@
+// the test cases and contexts of an instance are used through the Rust API, so these tests run the
+// server in process
+mod test {
+    use serde_json::{json, Value};
+    use teo::test::{TestContext, TestServer};
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    async fn titles(server: &TestServer) -> Value {
+        let res = server.request("Book", "findMany", json!({"orderBy": {"title": "asc"}, "select": {"title": true}})).await.unwrap();
+        res["data"].clone()
+    }
+
+    async fn context_titles(context: &TestContext<'_>) -> Value {
+        let res = context.request("Book", "findMany", json!({"orderBy": {"title": "asc"}, "select": {"title": true}})).await.unwrap();
+        res["data"].clone()
+    }
+
+    #[tokio::test]
+    async fn writes_are_rolled_back_after_each_case() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.isolated(&["library"], || async {
+            assert_eq!(titles(&server).await, json!([{"title": "First"}]));
+            server.request("Book", "create", json!({"create": {"title": "Second", "authorId": 1}})).await.unwrap();
+            assert_eq!(titles(&server).await, json!([{"title": "First"}, {"title": "Second"}]));
+        }).await.unwrap();
+        assert_eq!(titles(&server).await, json!([]));
+        server.isolated(&["library"], || async {
+            assert_eq!(titles(&server).await, json!([{"title": "First"}]));
+            let res = server.request("Author", "count", json!({})).await.unwrap();
+            assert_eq!(res["data"], json!(1));
+        }).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_failing_action_rolls_back_its_own_writes() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.isolated(&["library"], || async {
+            server.request("Book", "create", json!({"create": {"title": "Second", "authorId": 1}})).await.unwrap();
+            // the first book is inserted before the duplicate title fails
+            let res = server.request("Book", "createMany", json!({"create": [{"title": "Third", "authorId": 1}, {"title": "First", "authorId": 1}]})).await.unwrap();
+            assert!(res["error"].is_object());
+            assert_eq!(titles(&server).await, json!([{"title": "First"}, {"title": "Second"}]));
+        }).await.unwrap();
+        assert_eq!(titles(&server).await, json!([]));
+    }
+
+    #[tokio::test]
+    async fn a_panicking_case_is_rolled_back() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let case = std::panic::AssertUnwindSafe(server.isolated(&["library"], || async { panic!("failed") }));
+        assert!(futures_util::FutureExt::catch_unwind(case).await.is_err());
+        assert_eq!(titles(&server).await, json!([]));
+    }
+
+    #[tokio::test]
+    async fn contexts_have_databases_of_their_own() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let a = server.context().await.unwrap();
+        let b = server.context().await.unwrap();
+        a.seed(&["library"]).await.unwrap();
+        b.seed(&["library"]).await.unwrap();
+        let (created_a, created_b) = futures_util::future::join(
+            a.request("Book", "create", json!({"create": {"title": "Second", "authorId": 1}})),
+            b.request("Book", "create", json!({"create": {"title": "Other", "authorId": 1}})),
+        ).await;
+        assert!(created_a.unwrap()["data"].is_object());
+        assert!(created_b.unwrap()["data"].is_object());
+        assert_eq!(context_titles(&a).await, json!([{"title": "First"}, {"title": "Second"}]));
+        assert_eq!(context_titles(&b).await, json!([{"title": "First"}, {"title": "Other"}]));
+        assert_eq!(titles(&server).await, json!([]));
+        drop(a);
+        let c = server.context().await.unwrap();
+        assert_eq!(context_titles(&c).await, json!([]));
+    }
+}
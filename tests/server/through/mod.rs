@@ -0,0 +1,51 @@
+mod test {
+    use serde_json::json;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    #[tokio::test]
+    async fn join_records_take_through_values_and_are_included() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        server.request("Group", "create", json!({"create": {"name": "readers"}})).await.unwrap();
+        let res = server.request("User", "create", json!({
+            "create": {"name": "ann", "groups": {
+                "create": {"name": "admins", "_through": {"role": "owner"}},
+                "connect": [{"name": "readers", "_through": {"role": "member"}}],
+            }},
+            "include": {"groups": {"_through": true, "orderBy": {"id": "asc"}}},
+        })).await.unwrap();
+        let id = res["data"]["id"].clone();
+        let groups = res["data"]["groups"].as_array().unwrap();
+        assert_eq!(groups.iter().map(|g| (g["name"].clone(), g["_through"]["role"].clone())).collect::<Vec<_>>(), vec![(json!("readers"), json!("member")), (json!("admins"), json!("owner"))]);
+        server.request("User", "update", json!({"where": {"id": id}, "update": {"groups": {"connectOrCreate": {"where": {"name": "writers"}, "create": {"name": "writers"}, "_through": {"role": "editor"}}}}})).await.unwrap();
+        let res = server.request("Group", "findMany", json!({"where": {"name": {"in": ["readers", "admins", "writers"]}}, "include": {"users": {"_through": true}}, "orderBy": {"id": "asc"}})).await.unwrap();
+        let roles: Vec<serde_json::Value> = res["data"].as_array().unwrap().iter().map(|g| g["users"][0]["_through"]["role"].clone()).collect();
+        assert_eq!(roles, vec![json!("member"), json!("owner"), json!("editor")]);
+    }
+
+    #[tokio::test]
+    async fn through_values_are_validated() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("User", "create", json!({"create": {"name": "ann", "groups": {"create": {"name": "admins", "_through": {"userId": 2}}}}})).await.unwrap();
+        assert_eq!(res["error"]["fields"]["create.groups.create._through.userId"], json!("field `userId' is not an input of `Membership'"));
+        let res = server.request("User", "create", json!({"create": {"name": "ann", "groups": {"create": {"name": "admins"}}}})).await.unwrap();
+        assert_eq!(res["error"]["fields"]["create.groups.create._through"], json!("`role' of the join record is required"));
+        let res = server.request("User", "findMany", json!({"include": {"groups": {"_through": "yes"}}})).await.unwrap();
+        assert_eq!(res["error"]["fields"]["include.groups._through"], json!("expect bool"));
+    }
+
+    #[tokio::test]
+    async fn upserts_look_among_the_connected_records() {
+        let server = TestServer::new(SCHEMA).await.unwrap();
+        let res = server.request("User", "create", json!({"create": {"name": "ann", "groups": {"create": {"name": "a", "_through": {"role": "owner"}}}}})).await.unwrap();
+        let id = res["data"]["id"].clone();
+        server.request("User", "create", json!({"create": {"name": "bob", "groups": {"create": {"name": "b", "_through": {"role": "owner"}}}}})).await.unwrap();
+        let upsert = |name: &str, created: &str| json!({"where": {"name": name}, "create": {"name": created}, "update": {"name": format!("{}2", name)}, "_through": {"role": "member"}});
+        let res = server.request("User", "update", json!({"where": {"id": id}, "update": {"groups": {"upsert": [upsert("a", "x"), upsert("b", "c")]}}, "include": {"groups": {"_through": true, "orderBy": {"id": "asc"}}}})).await.unwrap();
+        let groups: Vec<(serde_json::Value, serde_json::Value)> = res["data"]["groups"].as_array().unwrap().iter().map(|g| (g["name"].clone(), g["_through"]["role"].clone())).collect();
+        assert_eq!(groups, vec![(json!("a2"), json!("owner")), (json!("c"), json!("member"))]);
+        let res = server.request("Group", "findUnique", json!({"where": {"name": "b"}})).await.unwrap();
+        assert!(!res["data"].is_null(), "{}", res);
+    }
+}
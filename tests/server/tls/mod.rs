@@ -0,0 +1,103 @@
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use std::future::Future;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use bytes::Bytes;
+    use serde_json::{json, Value};
+    use teo::server::tls::self_signed_certificate;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::client::TlsStream;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::rustls::crypto::ring::default_provider;
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use crate::lib::ExecutionHandle;
+    use once_cell::sync::Lazy;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4067;
+
+    fn dir() -> PathBuf {
+        std::env::temp_dir().join("teo-tls-test")
+    }
+
+    // the server is started with a certificate the client trusts
+    fn before_all() {
+        let (cert, key) = self_signed_certificate(&["localhost", "127.0.0.1"]).unwrap();
+        std::fs::create_dir_all(dir()).unwrap();
+        std::fs::write(dir().join("cert.pem"), cert).unwrap();
+        std::fs::write(dir().join("key.pem"), key).unwrap();
+        let args = format!("serve --tls-cert {} --tls-key {}", dir().join("cert.pem").display(), dir().join("key.pem").display());
+        HANDLE.lock().unwrap().execute(file!(), &args);
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+        let _ = std::fs::remove_dir_all(dir());
+    }
+
+    // the teardown wraps the tests in closures, so they can't be async themselves
+    fn block_on<F: Future>(f: F) -> F::Output {
+        tokio::runtime::Runtime::new().unwrap().block_on(f)
+    }
+
+    async fn connect(protocol: &[u8]) -> TlsStream<TcpStream> {
+        let pem = std::fs::read(dir().join("cert.pem")).unwrap();
+        let mut roots = RootCertStore::empty();
+        for der in rustls_pemfile::certs(&mut pem.as_slice()) {
+            roots.add(der.unwrap()).unwrap();
+        }
+        let mut config = ClientConfig::builder_with_provider(Arc::new(default_provider())).with_safe_default_protocol_versions().unwrap().with_root_certificates(roots).with_no_client_auth();
+        config.alpn_protocols = vec![protocol.to_vec()];
+        let stream = TcpStream::connect(("127.0.0.1", PORT as u16)).await.unwrap();
+        TlsConnector::from(Arc::new(config)).connect(ServerName::try_from("localhost").unwrap(), stream).await.unwrap()
+    }
+
+    #[test]
+    fn http2_is_negotiated() { block_on(async {
+        let stream = connect(b"h2").await;
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+        let (client, connection) = h2::client::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+        let mut client = client.ready().await.unwrap();
+        let request = http::Request::post(format!("https://localhost:{}/Support/create", PORT)).header("content-type", "application/json").body(()).unwrap();
+        let (response, mut send) = client.send_request(request, false).unwrap();
+        send.send_data(Bytes::from(json!({"create": {"string": "over h2"}}).to_string()), true).unwrap();
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), 200);
+        let mut body = response.into_body();
+        let mut data = vec![];
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            let _ = body.flow_control().release_capacity(chunk.len());
+            data.extend_from_slice(&chunk);
+        }
+        let res: Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(res["data"]["string"], json!("over h2"));
+    }) }
+
+    #[test]
+    fn http1_is_served_to_other_clients() { block_on(async {
+        let mut stream = connect(b"http/1.1").await;
+        let body = json!({"create": {"string": "over h1"}}).to_string();
+        let request = format!("POST /Support/create HTTP/1.1\r\nhost: localhost\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", body.len(), body);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains("over h1"), "{}", response);
+    }) }
+
+    #[test]
+    fn plain_http_is_refused() { block_on(async {
+        let res = reqwest::Client::new().post(format!("http://127.0.0.1:{}/Support/findMany", PORT)).json(&json!({})).send().await;
+        assert!(res.is_err());
+    }) }
+}
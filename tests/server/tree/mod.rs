@@ -0,0 +1,99 @@
+use crate::server_tests;
+
+server_tests!(4041, {
+    use serde_json::{json, Value};
+    use crate::lib::fixture::assert_field_error;
+    use crate::lib::req;
+
+    fn create_category(name: &str, parent: Option<&Value>) -> Value {
+        let mut create = json!({"name": name});
+        if let Some(parent) = parent {
+            create["parentId"] = parent["id"].clone();
+        }
+        req(PORT, "create", "Category", json!({"create": create}))["data"].clone()
+    }
+
+    fn names(res: &Value) -> Vec<String> {
+        res["data"].as_array().unwrap_or_else(|| panic!("unexpected response {}", res)).iter().map(|c| c["name"].as_str().unwrap().to_owned()).collect()
+    }
+
+    fn sorted_names(res: &Value) -> Vec<String> {
+        let mut names = names(res);
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn descendants_are_found_level_by_level() {
+        let root = create_category("root", None);
+        let a = create_category("a", Some(&root));
+        let b = create_category("b", Some(&a));
+        create_category("c", Some(&b));
+        create_category("d", Some(&root));
+        let res = req(PORT, "findDescendants", "Category", json!({"where": {"id": root["id"]}}));
+        assert_eq!(sorted_names(&res), vec!["a", "b", "c", "d"]);
+        let mut first_level = names(&res)[..2].to_vec();
+        first_level.sort();
+        assert_eq!(first_level, vec!["a", "d"]);
+        let res = req(PORT, "findDescendants", "Category", json!({"where": {"id": root["id"]}, "depth": 1}));
+        assert_eq!(sorted_names(&res), vec!["a", "d"]);
+    }
+
+    #[test]
+    fn ancestors_are_found_from_the_parent_to_the_root() {
+        let root = create_category("root", None);
+        let a = create_category("a", Some(&root));
+        let b = create_category("b", Some(&a));
+        let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": b["id"]}}));
+        assert_eq!(names(&res), vec!["a", "root"]);
+        let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": root["id"]}}));
+        assert_eq!(names(&res), Vec::<String>::new());
+    }
+
+    #[test]
+    fn subtrees_are_moved_with_their_descendants() {
+        let root = create_category("root", None);
+        let a = create_category("a", Some(&root));
+        let b = create_category("b", Some(&a));
+        let c = create_category("c", Some(&b));
+        let d = create_category("d", Some(&root));
+        let res = req(PORT, "moveSubtree", "Category", json!({"where": {"id": b["id"]}, "parent": {"id": d["id"]}}));
+        assert_eq!(res["data"]["parentId"], d["id"], "unexpected response {}", res);
+        let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": c["id"]}}));
+        assert_eq!(names(&res), vec!["b", "d", "root"]);
+        let res = req(PORT, "moveSubtree", "Category", json!({"where": {"id": b["id"]}, "parent": null}));
+        assert_eq!(res["data"]["parentId"], Value::Null, "unexpected response {}", res);
+        let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": c["id"]}}));
+        assert_eq!(names(&res), vec!["b"]);
+    }
+
+    #[test]
+    fn subtrees_cannot_form_cycles() {
+        let root = create_category("root", None);
+        let a = create_category("a", Some(&root));
+        let b = create_category("b", Some(&a));
+        let res = req(PORT, "moveSubtree", "Category", json!({"where": {"id": a["id"]}, "parent": {"id": a["id"]}}));
+        assert_field_error(&res, "parent", "cannot move a record under itself");
+        let res = req(PORT, "moveSubtree", "Category", json!({"where": {"id": a["id"]}, "parent": {"id": b["id"]}}));
+        assert_field_error(&res, "parent", "cannot move a record under its descendant");
+        let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": b["id"]}}));
+        assert_eq!(names(&res), vec!["a", "root"]);
+    }
+
+    #[test]
+    fn concurrent_moves_cannot_form_cycles() {
+        let root = create_category("root", None);
+        let a = create_category("a", Some(&root));
+        let b = create_category("b", Some(&root));
+        let move_under = |id: Value, parent: Value| std::thread::spawn(move || {
+            req(PORT, "moveSubtree", "Category", json!({"where": {"id": id}, "parent": {"id": parent}}))
+        });
+        let moves = [move_under(a["id"].clone(), b["id"].clone()), move_under(b["id"].clone(), a["id"].clone())];
+        let moved = moves.into_iter().map(|m| m.join().unwrap()).filter(|res| res.get("data").is_some()).count();
+        assert_eq!(moved, 1);
+        for record in [&a, &b] {
+            let res = req(PORT, "findAncestors", "Category", json!({"where": {"id": record["id"]}}));
+            assert_eq!(names(&res).last().map(String::as_str), Some("root"));
+        }
+    }
+});
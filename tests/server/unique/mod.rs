@@ -0,0 +1,47 @@
+// the runtime can't migrate a compound unique index twice yet, so the schema is loaded by one test,
+// the insert has to be the first statement of a create, duplicates are found by the database
+// instead of a lookup before it
+mod test {
+    use serde_json::{json, Value};
+    use teo::app::ctx::Ctx;
+    use teo::app::database::unique::violated_fields;
+    use teo::prelude::Limits;
+    use teo::test::TestServer;
+
+    static SCHEMA: &str = include_str!("schema.teo");
+
+    fn fields(model: &str, message: &str) -> Option<Vec<String>> {
+        violated_fields(Ctx::main_namespace().model_at_path(&vec![model]).unwrap(), message)
+    }
+
+    fn assert_duplicated(res: &Value, fields: Value) {
+        assert_eq!(res["error"]["type"], json!("ValueError"), "{}", res);
+        assert_eq!(res["error"]["fields"], fields);
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_value_errors_on_their_fields() {
+        let server = TestServer::new_with(SCHEMA, |app| {
+            app.action_limits("create", Limits { max_statements: Some(1), strict_max_statements: true, ..Default::default() });
+            Ok(())
+        }).await.unwrap();
+        let res = server.request("Member", "create", json!({"create": {"email": "a", "handle": "x"}})).await.unwrap();
+        assert_eq!(res["data"]["email"], json!("a"), "{}", res);
+        let res = server.request("Member", "create", json!({"create": {"email": "a"}})).await.unwrap();
+        assert_duplicated(&res, json!({"create.email": "unique value duplicated: email"}));
+        server.request("Member", "create", json!({"create": {"email": "b"}})).await.unwrap();
+        let res = server.request("Member", "update", json!({"where": {"email": "b"}, "update": {"handle": "x"}})).await.unwrap();
+        assert_duplicated(&res, json!({"update.handle": "unique value duplicated: handle"}));
+        let res = server.request("Member", "createMany", json!({"create": [{"email": "c"}, {"email": "c"}]})).await.unwrap();
+        assert_duplicated(&res, json!({"create.1.email": "unique value duplicated: email"}));
+        server.request("Seat", "create", json!({"create": {"row": "A", "number": 1}})).await.unwrap();
+        let res = server.request("Seat", "create", json!({"create": {"row": "A", "number": 1}})).await.unwrap();
+        assert_duplicated(&res, json!({"create.row": "unique value duplicated: row, number", "create.number": "unique value duplicated: row, number"}));
+        // the other dialects
+        assert_eq!(fields("Member", "duplicate key value violates unique constraint \"handle\""), Some(vec!["handle".to_owned()]));
+        assert_eq!(fields("Seat", "Duplicate entry 'A-1' for key 'Seat.seats_row_number'"), Some(vec!["row".to_owned(), "number".to_owned()]));
+        assert_eq!(fields("Member", "Duplicate entry '1' for key 'PRIMARY'"), Some(vec!["id".to_owned()]));
+        assert_eq!(fields("Member", "E11000 duplicate key error collection: app.Member index: email_1 dup key: { email: \"a\" }"), Some(vec!["email".to_owned()]));
+        assert_eq!(fields("Member", "NOT NULL constraint failed: Member.email"), None);
+    }
+}